@@ -0,0 +1,6 @@
+//! Shared (`libhel_ffi.so`) and static (`libhel_ffi.a`) builds of the HEL C API
+//!
+//! The functions are defined in the `hel` crate behind its `ffi` feature and declared
+//! in `include/hel.h`. Build with `cargo build --release -p hel-ffi`.
+
+pub use hel::ffi::*;
@@ -0,0 +1,168 @@
+//! Compile-time validated HEL expressions
+//!
+//! The `hel!` macro parses a HEL expression while the host crate compiles. Syntax
+//! errors (and parse limit violations) fail the build with the parser's message,
+//! so rule typos in Rust-embedded policies never reach runtime.
+//!
+//! ## Output
+//! - The macro expands to an expression of type `hel::AstNode` that builds the
+//!   parsed tree directly; no parsing happens at runtime
+//! - `AstNode` holds `Arc<str>` values, so the result is not a `const`; bind it in a
+//!   `static` `LazyLock` (or similar) to construct it once
+
+use hel::{AstNode, Comparator};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+// region:    --- hel!
+
+/// Parse a HEL expression at compile time
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_ast, EvalContext, FactsEvalContext, Value};
+/// use hel_macros::hel;
+///
+/// let rule = hel!(r#"binary.arch == "x86_64" AND binary.entropy > 7"#);
+///
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("binary.arch", Value::String("x86_64".into()));
+/// facts.add_fact("binary.entropy", Value::Number(7.5));
+/// assert!(evaluate_ast(&rule, &EvalContext::new(&facts)).unwrap());
+/// ```
+///
+/// Syntax errors fail the build:
+///
+/// ```compile_fail
+/// let rule = hel_macros::hel!("binary.arch == ");
+/// ```
+#[proc_macro]
+pub fn hel(input: TokenStream) -> TokenStream {
+	let literal = parse_macro_input!(input as LitStr);
+	match hel::parse_expression(&literal.value()) {
+		Ok(ast) => quote_node(&ast).into(),
+		Err(e) => syn::Error::new(literal.span(), format!("invalid HEL expression: {}", e))
+			.to_compile_error()
+			.into(),
+	}
+}
+
+// endregion: --- hel!
+
+// region:    --- Code Generation
+
+fn quote_node(node: &AstNode) -> TokenStream2 {
+	match node {
+		AstNode::Bool(b) => quote! { ::hel::AstNode::Bool(#b) },
+		AstNode::String(s) => {
+			let s = quote_str(s);
+			quote! { ::hel::AstNode::String(#s) }
+		}
+		AstNode::Number(n) => quote! { ::hel::AstNode::Number(#n) },
+		AstNode::Float(f) => {
+			// Round-trip through the bit pattern so the value is exact
+			let bits = f.to_bits();
+			quote! { ::hel::AstNode::Float(f64::from_bits(#bits)) }
+		}
+		AstNode::Identifier(s) => {
+			let s = quote_str(s);
+			quote! { ::hel::AstNode::Identifier(#s) }
+		}
+		AstNode::Attribute { object, field } => {
+			let (object, field) = (quote_str(object), quote_str(field));
+			quote! { ::hel::AstNode::Attribute { object: #object, field: #field } }
+		}
+		AstNode::Wildcard { object, field } => {
+			let (object, field) = (quote_str(object), quote_str(field));
+			quote! { ::hel::AstNode::Wildcard { object: #object, field: #field } }
+		}
+		AstNode::Comparison { left, op, right, span } => {
+			let (left, op, right) = (quote_node(left), quote_comparator(*op), quote_node(right));
+			let (start, end, line, column) = (span.start, span.end, span.line, span.column);
+			quote! {
+				::hel::AstNode::Comparison {
+					left: ::std::boxed::Box::new(#left),
+					op: #op,
+					right: ::std::boxed::Box::new(#right),
+					span: ::hel::SourceSpan { start: #start, end: #end, line: #line, column: #column },
+				}
+			}
+		}
+		AstNode::And(nodes) => {
+			let nodes = nodes.iter().map(quote_node);
+			quote! { ::hel::AstNode::And(::std::vec![#(#nodes),*]) }
+		}
+		AstNode::Or(nodes) => {
+			let nodes = nodes.iter().map(quote_node);
+			quote! { ::hel::AstNode::Or(::std::vec![#(#nodes),*]) }
+		}
+		AstNode::Xor(nodes) => {
+			let nodes = nodes.iter().map(quote_node);
+			quote! { ::hel::AstNode::Xor(::std::vec![#(#nodes),*]) }
+		}
+		AstNode::ListLiteral(items) => {
+			let items = items.iter().map(quote_node);
+			quote! { ::hel::AstNode::ListLiteral(::std::vec![#(#items),*]) }
+		}
+		AstNode::MapLiteral(entries) => {
+			let entries = entries.iter().map(|(k, v)| {
+				let (k, v) = (quote_str(k), quote_node(v));
+				quote! { (#k, #v) }
+			});
+			quote! { ::hel::AstNode::MapLiteral(::std::vec![#(#entries),*]) }
+		}
+		AstNode::FunctionCall { namespace, name, args, span } => {
+			let namespace = match namespace {
+				Some(ns) => {
+					let ns = quote_str(ns);
+					quote! { ::std::option::Option::Some(#ns) }
+				}
+				None => quote! { ::std::option::Option::None },
+			};
+			let name = quote_str(name);
+			let args = args.iter().map(quote_node);
+			let (start, end, line, column) = (span.start, span.end, span.line, span.column);
+			quote! {
+				::hel::AstNode::FunctionCall {
+					namespace: #namespace,
+					name: #name,
+					args: ::std::vec![#(#args),*],
+					span: ::hel::SourceSpan { start: #start, end: #end, line: #line, column: #column },
+				}
+			}
+		}
+		AstNode::Range { start, end } => {
+			let (start, end) = (quote_node(start), quote_node(end));
+			quote! {
+				::hel::AstNode::Range {
+					start: ::std::boxed::Box::new(#start),
+					end: ::std::boxed::Box::new(#end),
+				}
+			}
+		}
+	}
+}
+
+fn quote_str(s: &str) -> TokenStream2 {
+	quote! { ::std::sync::Arc::<str>::from(#s) }
+}
+
+fn quote_comparator(op: Comparator) -> TokenStream2 {
+	match op {
+		Comparator::Eq => quote! { ::hel::Comparator::Eq },
+		Comparator::Ne => quote! { ::hel::Comparator::Ne },
+		Comparator::Gt => quote! { ::hel::Comparator::Gt },
+		Comparator::Ge => quote! { ::hel::Comparator::Ge },
+		Comparator::Lt => quote! { ::hel::Comparator::Lt },
+		Comparator::Le => quote! { ::hel::Comparator::Le },
+		Comparator::Contains => quote! { ::hel::Comparator::Contains },
+		Comparator::In => quote! { ::hel::Comparator::In },
+		Comparator::EqIgnoreCase => quote! { ::hel::Comparator::EqIgnoreCase },
+		Comparator::Like => quote! { ::hel::Comparator::Like },
+	}
+}
+
+// endregion: --- Code Generation
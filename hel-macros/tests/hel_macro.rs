@@ -0,0 +1,43 @@
+//! Integration tests for the `hel!` macro
+//!
+//! The macro output must be structurally identical to the runtime parser's AST.
+
+use hel::{evaluate_ast, parse_expression, AstNode, EvalContext, FactsEvalContext, Value};
+use hel_macros::hel;
+
+#[test]
+fn test_macro_matches_runtime_parser() {
+	let cases: Vec<(AstNode, &str)> = vec![
+		(hel!(r#"binary.arch == "x86_64""#), r#"binary.arch == "x86_64""#),
+		(
+			hel!("a.b > 1.5 AND (c.d < 0x10 OR e.f != true)"),
+			"a.b > 1.5 AND (c.d < 0x10 OR e.f != true)",
+		),
+		(
+			hel!(r#"core.len([1, 2]) IN [2] OR {"k": "v"} == $x"#),
+			r#"core.len([1, 2]) IN [2] OR {"k": "v"} == $x"#,
+		),
+		(hel!("app.perms CONTAINS \"SMS\""), "app.perms CONTAINS \"SMS\""),
+	];
+
+	for (compiled, source) in cases {
+		let parsed = parse_expression(source).expect("parse failed");
+		assert_eq!(
+			format!("{:?}", compiled),
+			format!("{:?}", parsed),
+			"mismatch for {}",
+			source
+		);
+	}
+}
+
+#[test]
+fn test_macro_output_evaluates() {
+	let rule = hel!(r#"manifest.permissions CONTAINS "SMS" AND manifest.min_sdk < 23"#);
+
+	let mut facts = FactsEvalContext::new();
+	facts.add_fact("manifest.permissions", Value::List(vec!["SMS".into()]));
+	facts.add_fact("manifest.min_sdk", Value::Number(21.0));
+
+	assert!(evaluate_ast(&rule, &EvalContext::new(&facts)).unwrap());
+}
@@ -18,7 +18,7 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use hel::builtins::{BuiltinFn, BuiltinsProvider};
+use hel::builtins::{BuiltinArgs, BuiltinFn, BuiltinsProvider};
 use hel::{EvalError, Value};
 // endregion: --- Modules
 
@@ -60,6 +60,11 @@ impl BuiltinsProvider for AcmeBuiltins {
 		ACME_PROVIDER_NAMESPACE
 	}
 
+	// Recorded in the registry fingerprint for audit evidence
+	fn version(&self) -> Option<&str> {
+		Some(self.version)
+	}
+
 	fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
 		let mut builtins: BTreeMap<String, BuiltinFn> = BTreeMap::new();
 
@@ -68,42 +73,14 @@ impl BuiltinsProvider for AcmeBuiltins {
 		builtins.insert(
 			"score".to_string(),
 			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
-				if args.len() != 1 {
-					return Err(EvalError::InvalidOperation(
-						"acme.score expects 1 argument (list of numbers)".to_string(),
-					));
-				}
-
-				match &args[0] {
-					Value::List(items) => {
-						if items.is_empty() {
-							return Ok(Value::Number(0.0));
-						}
-						let mut sum = 0.0f64;
-						let mut count = 0usize;
-						for item in items {
-							match item {
-								Value::Number(n) => {
-									sum += *n;
-									count += 1;
-								}
-								_ => {
-									return Err(EvalError::TypeMismatch {
-										expected: "Number".to_string(),
-										got: format!("{:?}", item),
-										context: "acme.score".to_string(),
-									});
-								}
-							}
-						}
-						Ok(Value::Number(sum / (count as f64)))
-					}
-					_ => Err(EvalError::TypeMismatch {
-						expected: "List".to_string(),
-						got: format!("{:?}", args[0]),
-						context: "acme.score".to_string(),
-					}),
+				// BuiltinArgs gives the same arity / type error text as the hel core built-ins
+				let args = BuiltinArgs::new("acme.score", args);
+				args.expect_args(1)?;
+				let numbers = args.expect_list_of_numbers(0)?;
+				if numbers.is_empty() {
+					return Ok(Value::Number(0.0));
 				}
+				Ok(Value::Number(numbers.iter().sum::<f64>() / numbers.len() as f64))
 			}) as BuiltinFn,
 		);
 
@@ -112,31 +89,18 @@ impl BuiltinsProvider for AcmeBuiltins {
 		builtins.insert(
 			"enrich".to_string(),
 			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
-				if args.len() != 2 {
-					return Err(EvalError::InvalidOperation(
-						"acme.enrich expects 2 arguments (key:string, value:any)".to_string(),
-					));
-				}
-
+				let args = BuiltinArgs::new("acme.enrich", args);
+				args.expect_args(2)?;
 				// key must be a string
-				let key = match &args[0] {
-					Value::String(s) => s.to_string(),
-					_ => {
-						return Err(EvalError::TypeMismatch {
-							expected: "String".to_string(),
-							got: format!("{:?}", args[0]),
-							context: "acme.enrich".to_string(),
-						})
-					}
-				};
+				let key = args.expect_string(0)?;
 
 				let mut map = std::collections::BTreeMap::new();
 				// Insert the original pair under provided key
-				map.insert(key.clone(), args[1].clone());
+				map.insert(Arc::from(key), args.expect_value(1)?.clone());
 				// Add provider metadata (deterministic)
-				map.insert("provided_by".to_string(), Value::String("acme".into()));
+				map.insert(Arc::from("provided_by"), Value::String("acme".into()));
 				map.insert(
-					"provider_version".to_string(),
+					Arc::from("provider_version"),
 					Value::String(ACME_PROVIDER_VERSION.into()),
 				);
 
@@ -204,5 +168,40 @@ mod tests {
 			_ => panic!("expected map result"),
 		}
 	}
+
+	#[test]
+	fn test_acme_provider_conformance() {
+		use hel::builtins::testing::ProviderHarness;
+
+		// -- Exec: panics, non-determinism and arity drift against the provider contract
+		let report = ProviderHarness::new(&AcmeBuiltins::new())
+			.with_arity("score", 1..=1)
+			.with_arity("enrich", 2..=2)
+			.run();
+
+		// -- Check
+		assert!(report.is_conformant(), "{}", report);
+	}
+
+	#[test]
+	fn test_acme_score_errors() {
+		// -- Setup & Fixtures
+		let provider = AcmeBuiltins::new();
+		let mut registry = BuiltinsRegistry::new();
+		registry.register(&provider).expect("registration failed");
+
+		// -- Exec
+		let arity = registry.call("acme", "score", &[]).unwrap_err();
+		let element = registry
+			.call("acme", "score", &[Value::List(vec![Value::Number(1.0), Value::Null])])
+			.unwrap_err();
+
+		// -- Check
+		assert_eq!(arity.to_string(), "Invalid operation: acme.score expects 1 argument");
+		assert_eq!(
+			element.to_string(),
+			"Type mismatch in acme.score: expected List of Number, got Null"
+		);
+	}
 }
 // endregion: --- Tests
@@ -0,0 +1,66 @@
+/*
+ * HEL C API
+ *
+ * Declarations for the `ffi` feature of the hel crate (src/ffi.rs).
+ * Build: cargo build --release -p hel-ffi (libhel_ffi.so / libhel_ffi.a)
+ *
+ * - All strings are NUL-terminated UTF-8.
+ * - Failing calls return NULL / -1; hel_last_error() then returns the message
+ *   (per thread, valid until the next failing call on that thread).
+ * - Release handles with hel_expr_free() and returned strings with hel_string_free().
+ */
+
+#ifndef HEL_H
+#define HEL_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+/*
+ * Parsed expression handle (opaque to C)
+ */
+typedef struct HelExpr HelExpr;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ * Parse an expression; returns `NULL` on error
+ */
+HelExpr *hel_parse(const char *source);
+
+/*
+ * Release a handle returned by `hel_parse`
+ */
+void hel_expr_free(HelExpr *expr);
+
+/*
+ * Evaluate against facts given as a JSON object; returns 1 (true), 0 (false) or -1 (error)
+ */
+int hel_evaluate_json(const HelExpr *expr, const char *facts_json);
+
+/*
+ * Evaluate with tracing; returns the trace as a JSON string or `NULL` on error
+ */
+char *hel_trace_json(const HelExpr *expr, const char *facts_json);
+
+/*
+ * Release a string returned by the library
+ */
+void hel_string_free(char *s);
+
+/*
+ * Message of the last failing call on this thread, or `NULL` if none
+ */
+const char *hel_last_error(void);
+
+/*
+ * Library version (static string)
+ */
+const char *hel_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HEL_H */
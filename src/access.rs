@@ -0,0 +1,253 @@
+//! Facts-access recording for least-privilege analysis
+//!
+//! `RecordingResolver` wraps any `HelResolver` and logs every `(object, field)`
+//! requested during evaluation, including misses. Hosts use the resulting
+//! `AccessManifest` to verify that rules only touch facts they are authorized
+//! for, or to publish a data-access manifest per rule.
+//!
+//! ## Notes
+//! - Accesses are recorded dynamically: short-circuited branches are not visited,
+//!   so a manifest lists what one evaluation read, not everything a rule could read
+//! - Manifests use sorted sets for deterministic output
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use crate::redact::path_matches;
+use crate::rules::RuleSet;
+use crate::{EvalError, HelResolver, Value};
+
+// region:    --- FactAccess
+
+/// A single attribute lookup observed by a `RecordingResolver`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactAccess {
+	/// Object part of the path (e.g., "binary")
+	pub object: String,
+	/// Field part of the path (e.g., "format", or "*.entropy" for a wildcard)
+	pub field: String,
+	/// Whether the wrapped resolver returned a value
+	pub found: bool,
+}
+
+impl FactAccess {
+	/// Full attribute path (`object.field`)
+	pub fn path(&self) -> String {
+		format!("{}.{}", self.object, self.field)
+	}
+}
+
+// endregion: --- FactAccess
+
+// region:    --- AccessManifest
+
+/// Set of fact paths read by one or more evaluations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessManifest {
+	/// Paths that resolved to a value
+	pub read: BTreeSet<String>,
+	/// Paths that were requested but missing
+	pub missing: BTreeSet<String>,
+}
+
+impl AccessManifest {
+	/// Every requested path, found or not
+	pub fn requested(&self) -> BTreeSet<String> {
+		self.read.union(&self.missing).cloned().collect()
+	}
+
+	/// Requested paths not covered by `allowed` (`object.field` or `object.*` patterns)
+	pub fn unauthorized(&self, allowed: &[&str]) -> Vec<String> {
+		self.requested()
+			.into_iter()
+			.filter(|path| !allowed.iter().any(|pattern| path_matches(pattern, path)))
+			.collect()
+	}
+}
+
+// endregion: --- AccessManifest
+
+// region:    --- RecordingResolver
+
+/// Resolver wrapper that records every attribute lookup
+///
+/// # Examples
+///
+/// ```
+/// use hel::access::RecordingResolver;
+/// use hel::{evaluate_with_resolver, FactsEvalContext, Value};
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("binary.format", Value::String("elf".into()));
+///
+/// let recorder = RecordingResolver::new(&ctx);
+/// evaluate_with_resolver(r#"binary.format == "elf" AND user.email == "x""#, &recorder).unwrap();
+///
+/// let manifest = recorder.manifest();
+/// assert!(manifest.read.contains("binary.format"));
+/// assert!(manifest.missing.contains("user.email"));
+/// assert_eq!(manifest.unauthorized(&["binary.*"]), vec!["user.email".to_string()]);
+/// ```
+#[derive(Debug)]
+pub struct RecordingResolver<R> {
+	inner: R,
+	accesses: Mutex<Vec<FactAccess>>,
+}
+
+impl<R: HelResolver> RecordingResolver<R> {
+	/// Wrap a resolver
+	pub fn new(inner: R) -> Self {
+		Self {
+			inner,
+			accesses: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// All lookups in the order they happened (including repeats)
+	pub fn accesses(&self) -> Vec<FactAccess> {
+		self.lock().clone()
+	}
+
+	/// Deduplicated manifest of the recorded lookups
+	pub fn manifest(&self) -> AccessManifest {
+		let mut manifest = AccessManifest::default();
+		for access in self.lock().iter() {
+			if access.found {
+				manifest.read.insert(access.path());
+			} else {
+				manifest.missing.insert(access.path());
+			}
+		}
+		manifest
+	}
+
+	/// Forget recorded lookups (e.g., between rules)
+	pub fn clear(&self) {
+		self.lock().clear();
+	}
+
+	/// Unwrap the inner resolver
+	pub fn into_inner(self) -> R {
+		self.inner
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, Vec<FactAccess>> {
+		// A poisoned log is still a valid log; recording never panics mid-update
+		self.accesses.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+impl<R: HelResolver> HelResolver for RecordingResolver<R> {
+	fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+		let value = self.inner.resolve_attr(object, field);
+		self.lock().push(FactAccess {
+			object: object.to_string(),
+			field: field.to_string(),
+			found: value.is_some(),
+		});
+		value
+	}
+
+	fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+		let matches = self.inner.resolve_wildcard(object, field);
+		self.lock().push(FactAccess {
+			object: object.to_string(),
+			field: format!("*.{}", field),
+			found: !matches.is_empty(),
+		});
+		matches
+	}
+}
+
+// endregion: --- RecordingResolver
+
+// region:    --- Rule Manifests
+
+/// Evaluate every rule in a set and collect the facts each one accessed
+///
+/// Rules are evaluated as by `RuleSet::evaluate()`: a `rule("id")` reference reads the
+/// memoized result, so the referenced rule's lookups appear only in its own manifest.
+/// Rules that fail to evaluate still report the lookups made before the error.
+pub fn rule_access_manifests(
+	rules: &RuleSet,
+	resolver: &dyn HelResolver,
+) -> BTreeMap<Arc<str>, (AccessManifest, Result<bool, EvalError>)> {
+	let recorder = RecordingResolver::new(resolver);
+	let mut manifests = BTreeMap::new();
+	rules.evaluate_each_with(
+		|_| true,
+		|| rules.context(&recorder),
+		|rule, ctx| {
+			recorder.clear();
+			crate::evaluate_ast_with_context(&rule.condition, ctx)
+		},
+		|rule, result| {
+			manifests.insert(rule.id.clone(), (recorder.manifest(), result));
+			true
+		},
+	);
+	manifests
+}
+
+// endregion: --- Rule Manifests
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::FactsEvalContext;
+
+	fn facts() -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.format", Value::String("elf".into()));
+		ctx.add_fact("user.email", Value::String("alice@example.com".into()));
+		ctx
+	}
+
+	#[test]
+	fn test_records_hits_misses_and_order() {
+		let ctx = facts();
+		let recorder = RecordingResolver::new(&ctx);
+		assert!(crate::evaluate_with_resolver(
+			r#"binary.format == "elf" AND binary.arch == "x86" OR binary.format == "elf""#,
+			&recorder
+		)
+		.unwrap());
+
+		let accesses = recorder.accesses();
+		assert_eq!(accesses.len(), 3);
+		assert_eq!(accesses[1].path(), "binary.arch");
+		assert!(!accesses[1].found);
+
+		let manifest = recorder.manifest();
+		assert_eq!(manifest.read.len(), 1);
+		assert_eq!(manifest.missing.len(), 1);
+		assert!(manifest.unauthorized(&["binary.*"]).is_empty());
+	}
+
+	#[test]
+	fn test_rule_access_manifests() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("format", r#"binary.format == "elf""#)
+			.expect("add failed");
+		rules.add_rule("pii", r#"user.email CONTAINS "@""#).expect("add failed");
+		rules
+			.add_rule("elf-x86", r#"rule("format") AND binary.arch == "x86""#)
+			.expect("add failed");
+
+		let manifests = rule_access_manifests(&rules, &facts());
+		let (dependent, result) = &manifests["elf-x86"];
+		assert_eq!(result.as_ref().unwrap(), &false);
+		assert_eq!(dependent.requested(), BTreeSet::from(["binary.arch".to_string()]));
+		let (format, result) = &manifests["format"];
+		assert!(result.as_ref().unwrap());
+		assert!(format.unauthorized(&["binary.format"]).is_empty());
+
+		let (pii, _) = &manifests["pii"];
+		assert_eq!(pii.unauthorized(&["binary.*"]), vec!["user.email".to_string()]);
+	}
+}
+
+// endregion: --- Tests
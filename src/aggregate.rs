@@ -0,0 +1,303 @@
+//! Corpus-level trace aggregation
+//!
+//! `TraceAggregator` folds many `EvalTrace`s into per-rule statistics: how often
+//! each rule matched, how often each atom was evaluated and held, and which atoms
+//! held together. Noisy detections show up as rules with high match rates; atoms
+//! that held on every evaluation (or on none) never discriminate and are
+//! candidates for removal.
+//!
+//! ## Notes
+//! - Atoms are identified by their text (`left op right`), in order of first appearance
+//! - Short-circuited atoms are not evaluated, so `evaluated` can be below `evaluations`
+//! - Co-occurrence counts traces in which both atoms held
+//!
+//! ## Determinism
+//! - Rules are reported in the order they were first added
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::trace::{comparator_to_str, AtomTrace, EvalTrace};
+use crate::EvalError;
+
+// region:    --- Statistics
+
+/// Hit statistics of one atom across a corpus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtomStats {
+	/// Atom text (`left op right`)
+	pub atom: String,
+	/// Times the atom was evaluated
+	pub evaluated: usize,
+	/// Times the atom held
+	pub hits: usize,
+}
+
+impl AtomStats {
+	/// Fraction of evaluations in which the atom held (0.0 if never evaluated)
+	pub fn hit_rate(&self) -> f64 {
+		match self.evaluated {
+			0 => 0.0,
+			evaluated => self.hits as f64 / evaluated as f64,
+		}
+	}
+
+	/// Whether the atom both held and failed at least once
+	pub fn discriminates(&self) -> bool {
+		self.hits > 0 && self.hits < self.evaluated
+	}
+}
+
+/// Match and atom statistics of one rule across a corpus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleStats {
+	/// Rule id
+	pub rule_id: Arc<str>,
+	/// Traces aggregated
+	pub evaluations: usize,
+	/// Traces in which the rule matched
+	pub matches: usize,
+	/// Evaluations that failed (not counted in `evaluations`)
+	pub errors: usize,
+	/// Atoms in order of first appearance
+	pub atoms: Vec<AtomStats>,
+	/// Traces in which both atoms held, keyed by atom index pair (lower first)
+	co_hits: BTreeMap<(usize, usize), usize>,
+}
+
+impl RuleStats {
+	fn new(rule_id: Arc<str>) -> Self {
+		Self {
+			rule_id,
+			evaluations: 0,
+			matches: 0,
+			errors: 0,
+			atoms: Vec::new(),
+			co_hits: BTreeMap::new(),
+		}
+	}
+
+	/// Fraction of traces in which the rule matched (0.0 if none were added)
+	pub fn match_rate(&self) -> f64 {
+		match self.evaluations {
+			0 => 0.0,
+			evaluations => self.matches as f64 / evaluations as f64,
+		}
+	}
+
+	/// Statistics of an atom by its text
+	pub fn atom(&self, atom: &str) -> Option<&AtomStats> {
+		self.atoms.iter().find(|a| a.atom == atom)
+	}
+
+	/// Traces in which the atoms at indices `a` and `b` both held
+	pub fn co_occurrence(&self, a: usize, b: usize) -> usize {
+		let key = if a <= b { (a, b) } else { (b, a) };
+		self.co_hits.get(&key).copied().unwrap_or(0)
+	}
+
+	/// Every pair of distinct atoms that held together, with the number of traces
+	pub fn co_occurrences(&self) -> impl Iterator<Item = (&AtomStats, &AtomStats, usize)> {
+		self.co_hits
+			.iter()
+			.filter(|((a, b), _)| a != b)
+			.map(|(&(a, b), &count)| (&self.atoms[a], &self.atoms[b], count))
+	}
+
+	/// Evaluated atoms that held every time or never
+	pub fn non_discriminating(&self) -> impl Iterator<Item = &AtomStats> {
+		self.atoms.iter().filter(|a| a.evaluated > 0 && !a.discriminates())
+	}
+
+	fn add(&mut self, trace: &EvalTrace) {
+		self.evaluations += 1;
+		if trace.result {
+			self.matches += 1;
+		}
+
+		let mut held = BTreeSet::new();
+		for atom in &trace.atoms {
+			let text = atom_text(atom);
+			let index = match self.atoms.iter().position(|a| a.atom == text) {
+				Some(index) => index,
+				None => {
+					self.atoms.push(AtomStats {
+						atom: text,
+						evaluated: 0,
+						hits: 0,
+					});
+					self.atoms.len() - 1
+				}
+			};
+			let stats = &mut self.atoms[index];
+			stats.evaluated += 1;
+			if atom.atom_result {
+				stats.hits += 1;
+				held.insert(index);
+			}
+		}
+
+		let held: Vec<usize> = held.into_iter().collect();
+		for (i, &a) in held.iter().enumerate() {
+			for &b in &held[i..] {
+				*self.co_hits.entry((a, b)).or_insert(0) += 1;
+			}
+		}
+	}
+}
+
+fn atom_text(atom: &AtomTrace) -> String {
+	format!("{} {} {}", atom.left, comparator_to_str(atom.op), atom.right)
+}
+
+// endregion: --- Statistics
+
+// region:    --- Aggregator
+
+/// Folds evaluation traces into per-rule statistics
+///
+/// # Examples
+///
+/// ```
+/// use hel::aggregate::TraceAggregator;
+/// use hel::{FactsEvalContext, RuleSet, Value};
+///
+/// let mut rules = RuleSet::new();
+/// rules.add_rule("packed", "binary.entropy > 7.5 AND binary.format == \"pe\"").unwrap();
+///
+/// let mut aggregator = TraceAggregator::new();
+/// for entropy in [7.9, 8.1, 3.0] {
+///     let mut ctx = FactsEvalContext::new();
+///     ctx.add_fact("binary.entropy", Value::Number(entropy));
+///     ctx.add_fact("binary.format", Value::String("pe".into()));
+///     aggregator.add_results(&rules.evaluate_with_trace(&ctx));
+/// }
+///
+/// let packed = aggregator.rule("packed").unwrap();
+/// assert_eq!((packed.evaluations, packed.matches), (3, 2));
+/// assert_eq!(packed.atoms[0].hit_rate(), 2.0 / 3.0);
+/// // Every binary in the corpus is a PE: the format check never discriminates
+/// let constant: Vec<&str> = packed.non_discriminating().map(|a| a.atom.as_str()).collect();
+/// assert_eq!(constant, ["binary.format == \"pe\""]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TraceAggregator {
+	rules: Vec<RuleStats>,
+}
+
+impl TraceAggregator {
+	/// Create an empty aggregator
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add one trace of a rule
+	pub fn add(&mut self, rule_id: &str, trace: &EvalTrace) {
+		self.stats_mut(rule_id).add(trace);
+	}
+
+	/// Add the traces of a rule set evaluation (e.g., `RuleSet::evaluate_with_trace()`),
+	/// counting failed evaluations as errors
+	pub fn add_results(&mut self, results: &[(Arc<str>, Result<EvalTrace, EvalError>)]) {
+		for (rule_id, result) in results {
+			match result {
+				Ok(trace) => self.add(rule_id, trace),
+				Err(_) => self.stats_mut(rule_id).errors += 1,
+			}
+		}
+	}
+
+	/// Statistics of one rule
+	pub fn rule(&self, rule_id: &str) -> Option<&RuleStats> {
+		self.rules.iter().find(|r| r.rule_id.as_ref() == rule_id)
+	}
+
+	/// Statistics of every rule, in the order rules were first added
+	pub fn rules(&self) -> &[RuleStats] {
+		&self.rules
+	}
+
+	fn stats_mut(&mut self, rule_id: &str) -> &mut RuleStats {
+		let index = match self.rules.iter().position(|r| r.rule_id.as_ref() == rule_id) {
+			Some(index) => index,
+			None => {
+				self.rules.push(RuleStats::new(Arc::from(rule_id)));
+				self.rules.len() - 1
+			}
+		};
+		&mut self.rules[index]
+	}
+}
+
+// endregion: --- Aggregator
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rules::RuleSet;
+	use crate::{FactsEvalContext, Value};
+
+	fn record(entropy: f64, signed: bool, packer: &str) -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(entropy));
+		ctx.add_fact("binary.signed", Value::Bool(signed));
+		ctx.add_fact("binary.packer", Value::String(packer.into()));
+		ctx
+	}
+
+	#[test]
+	fn test_trace_aggregation() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule(
+				"packed",
+				r#"binary.entropy > 7.5 AND (binary.signed == false OR binary.packer == "upx")"#,
+			)
+			.unwrap();
+		rules.add_rule("broken", "core.nope(binary.entropy) == true").unwrap();
+
+		let corpus = [
+			record(7.9, false, "upx"),
+			record(7.9, true, "upx"),
+			record(3.0, false, "none"),
+			record(7.8, true, "none"),
+		];
+		let mut aggregator = TraceAggregator::new();
+		for ctx in &corpus {
+			aggregator.add_results(&rules.evaluate_with_trace(ctx));
+		}
+
+		let ids: Vec<&str> = aggregator.rules().iter().map(|r| r.rule_id.as_ref()).collect();
+		assert_eq!(ids, ["packed", "broken"]);
+		let broken = aggregator.rule("broken").unwrap();
+		assert_eq!((broken.evaluations, broken.errors), (0, 4));
+
+		let packed = aggregator.rule("packed").unwrap();
+		assert_eq!((packed.evaluations, packed.matches), (4, 2));
+		assert_eq!(packed.match_rate(), 0.5);
+		let atoms: Vec<(&str, usize, usize)> = packed
+			.atoms
+			.iter()
+			.map(|a| (a.atom.as_str(), a.evaluated, a.hits))
+			.collect();
+		assert_eq!(
+			atoms,
+			[
+				("binary.entropy > 7.5", 4, 3),
+				("binary.signed == false", 3, 1),
+				("binary.packer == \"upx\"", 2, 1),
+			]
+		);
+		assert_eq!(packed.atom("binary.signed == false").unwrap().hit_rate(), 1.0 / 3.0);
+		assert_eq!(packed.co_occurrence(0, 1), 1);
+		assert_eq!(packed.co_occurrence(2, 0), 1);
+		assert_eq!(packed.co_occurrence(1, 2), 0);
+		assert_eq!(packed.co_occurrences().count(), 2);
+		assert_eq!(packed.non_discriminating().count(), 0);
+	}
+}
+
+// endregion: --- Tests
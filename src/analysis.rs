@@ -0,0 +1,762 @@
+//! Editor analysis for HEL rules
+//!
+//! Language-agnostic building blocks for editor integrations (the `hel-lsp`
+//! server, the web rule editor): diagnostics, completion, hover and
+//! go-to-definition, all expressed in byte offsets into the rule text.
+//!
+//! ## Type information
+//! - Fact objects are matched to schema types via `TypeEnvironment::type_for_object`
+//!   (`binary` -> `Binary`), or explicitly with `Analyzer::bind_object`
+//! - Without a type environment only syntax and built-in checks are performed
+//! - Go-to-definition needs the `PackageRegistry` the environment was built from
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use serde_json::Value as Json;
+
+use crate::builtins::BuiltinsRegistry;
+use crate::diagnostics::{diagnostic_json, span_json, Diagnostics, Warning, WarningKind};
+use crate::lexer::{tokenize, Token, TokenKind};
+use crate::schema::{FieldDef, FieldType, PackageRegistry, TypeDef, TypeEnvironment};
+use crate::{lint_script, parse_expression, validate_script, HelError, Value};
+
+const KEYWORDS: &[&str] = &[
+	"AND", "OR", "XOR", "CONTAINS", "IN", "EQI", "LIKE", "BETWEEN", "true", "false", "let", "const",
+];
+
+// region:    --- Types
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	/// The rule does not parse or references something that does not exist
+	Error,
+	/// The rule parses but is likely wrong
+	Warning,
+}
+
+/// A problem found in rule text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+	/// Byte range the diagnostic applies to
+	pub span: Range<usize>,
+	/// Severity
+	pub severity: Severity,
+	/// Human-readable message
+	pub message: String,
+}
+
+impl Diagnostic {
+	/// Structured form for tools (see `diagnostics::DIAGNOSTIC_SCHEMA`), with code `hel::analysis`
+	pub fn to_json(&self) -> Json {
+		let severity = match self.severity {
+			Severity::Error => "error",
+			Severity::Warning => "warning",
+		};
+		diagnostic_json(
+			"hel::analysis",
+			severity,
+			&self.message,
+			span_json(Some(&self.span)),
+			None,
+		)
+	}
+}
+
+/// Kind of a completion item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+	/// Fact object (e.g., `binary`)
+	Object,
+	/// Field of a fact object
+	Field,
+	/// Built-ins namespace (e.g., `core`)
+	Namespace,
+	/// Built-in function
+	Function,
+	/// Language keyword or boolean literal
+	Keyword,
+	/// Variant of a schema enum (e.g., `Won` in `Stage.Won`)
+	Variant,
+}
+
+/// A completion candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+	/// Text to insert (the segment after the last `.`)
+	pub label: String,
+	/// Item kind
+	pub kind: CompletionKind,
+	/// Short detail (e.g., field type)
+	pub detail: Option<String>,
+	/// Longer documentation (e.g., the schema description of a field)
+	pub documentation: Option<String>,
+}
+
+/// Hover information for the token under the cursor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hover {
+	/// Byte range of the hovered token
+	pub span: Range<usize>,
+	/// Markdown contents
+	pub contents: String,
+}
+
+/// Source location of a schema definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+	/// Schema file
+	pub path: PathBuf,
+	/// Zero-based line of the definition
+	pub line: usize,
+}
+
+// endregion: --- Types
+
+// region:    --- Analyzer
+
+/// Editor analysis over optional schema and built-ins information
+///
+/// # Examples
+///
+/// ```
+/// use hel::analysis::{Analyzer, Severity};
+/// use hel::BuiltinsRegistry;
+///
+/// let builtins = BuiltinsRegistry::builder().with_core().build().unwrap();
+/// let analyzer = Analyzer::new().with_builtins(&builtins);
+///
+/// let diagnostics = analyzer.diagnostics("core.length(app.perms) > 2");
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// assert_eq!(diagnostics[0].span, 0..11);
+///
+/// let labels: Vec<String> = analyzer.completions("core.le", 7).into_iter().map(|c| c.label).collect();
+/// assert_eq!(labels, vec!["len", "levenshtein"]);
+/// ```
+#[derive(Clone, Default)]
+pub struct Analyzer<'a> {
+	types: Option<&'a TypeEnvironment>,
+	packages: Option<&'a PackageRegistry>,
+	builtins: Option<&'a BuiltinsRegistry>,
+	bindings: BTreeMap<String, String>,
+}
+
+impl<'a> Analyzer<'a> {
+	/// Create an analyzer with no schema or built-ins information
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Type-check attributes against a type environment
+	pub fn with_types(mut self, types: &'a TypeEnvironment) -> Self {
+		self.types = Some(types);
+		self
+	}
+
+	/// Package registry used to locate schema definitions
+	pub fn with_packages(mut self, packages: &'a PackageRegistry) -> Self {
+		self.packages = Some(packages);
+		self
+	}
+
+	/// Check and complete built-in function calls
+	pub fn with_builtins(mut self, builtins: &'a BuiltinsRegistry) -> Self {
+		self.builtins = Some(builtins);
+		self
+	}
+
+	/// Explicitly map a fact object to a qualified type (e.g., `pe` -> `security-binary.Binary`)
+	pub fn bind_object(mut self, object: &str, qualified_type: &str) -> Self {
+		self.bindings.insert(object.to_string(), qualified_type.to_string());
+		self
+	}
+
+	/// Syntax, attribute and built-in diagnostics, sorted by position
+	pub fn diagnostics(&self, source: &str) -> Vec<Diagnostic> {
+		let mut diagnostics = Vec::new();
+
+		let errors = parse_source(source).err().unwrap_or_default();
+		// Binding lints on a half-typed script are noise; report them once it parses
+		let warnings = if errors.is_empty() {
+			binding_warnings(source)
+		} else {
+			Vec::new()
+		};
+		for e in errors {
+			let offset = e.offset_in(source).unwrap_or(0);
+			let end = source[offset..]
+				.chars()
+				.next()
+				.map_or(offset, |c| offset + c.len_utf8());
+			diagnostics.push(Diagnostic {
+				span: offset..end,
+				severity: Severity::Error,
+				message: e.message,
+			});
+		}
+
+		for warning in warnings {
+			diagnostics.push(Diagnostic {
+				span: warning.span.unwrap_or_default(),
+				severity: Severity::Warning,
+				message: warning.message,
+			});
+		}
+
+		let tokens = tokenize(source);
+		for (i, token) in tokens.iter().enumerate() {
+			match token.kind {
+				TokenKind::Attribute => self.check_attribute(source, &tokens, i, &mut diagnostics),
+				TokenKind::Function => self.check_function(source, token, &mut diagnostics),
+				_ => {}
+			}
+		}
+
+		diagnostics.sort_by_key(|d| (d.span.start, d.severity));
+		diagnostics
+	}
+
+	/// Record the warnings of `diagnostics()` in a collector shared with evaluation
+	///
+	/// Errors are not recorded; they are hard failures reported by `diagnostics()`.
+	pub fn collect_warnings(&self, source: &str, collector: &Diagnostics) {
+		let functions: Vec<usize> = tokenize(source)
+			.into_iter()
+			.filter(|t| t.kind == TokenKind::Function)
+			.map(|t| t.span.start)
+			.collect();
+		let bindings = binding_warnings(source);
+		for diagnostic in self.diagnostics(source) {
+			if diagnostic.severity != Severity::Warning {
+				continue;
+			}
+			let binding = bindings
+				.iter()
+				.find(|w| w.span.as_ref() == Some(&diagnostic.span) && w.message == diagnostic.message);
+			let kind = if let Some(binding) = binding {
+				binding.kind
+			} else if functions.contains(&diagnostic.span.start) {
+				WarningKind::DeprecatedBuiltin
+			} else {
+				WarningKind::Schema
+			};
+			collector.push(Warning {
+				kind,
+				message: diagnostic.message,
+				span: Some(diagnostic.span),
+			});
+		}
+	}
+
+	/// Completion candidates for the word ending at `offset`
+	pub fn completions(&self, source: &str, offset: usize) -> Vec<Completion> {
+		let word_start = source[..offset]
+			.rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+			.map_or(0, |i| i + 1);
+		let word = &source[word_start..offset];
+
+		let mut items = Vec::new();
+		match word.rsplit_once('.') {
+			Some((object, prefix)) => {
+				if let Some((qualified, enum_def)) = self.types.and_then(|t| t.enum_named(object)) {
+					items.extend(enum_def.variants.iter().map(|v| Completion {
+						label: v.name.to_string(),
+						kind: CompletionKind::Variant,
+						detail: Some(qualified.to_string()),
+						documentation: None,
+					}));
+				}
+				if let Some((_, typedef)) = self.type_of(object) {
+					items.extend(typedef.fields.iter().map(field_completion));
+				}
+				if let Some(functions) = self.builtins.and_then(|b| b.functions_in_namespace(object)) {
+					items.extend(functions.into_iter().map(|name| Completion {
+						detail: Some(format!("{}.{}(...)", object, name)),
+						label: name,
+						kind: CompletionKind::Function,
+						documentation: None,
+					}));
+				}
+				items.retain(|c| c.label.starts_with(prefix));
+			}
+			None => {
+				for object in self.objects() {
+					items.push(Completion {
+						label: object,
+						kind: CompletionKind::Object,
+						detail: None,
+						documentation: None,
+					});
+				}
+				for namespace in self.builtins.map(|b| b.namespaces()).unwrap_or_default() {
+					items.push(Completion {
+						label: namespace,
+						kind: CompletionKind::Namespace,
+						detail: None,
+						documentation: None,
+					});
+				}
+				items.extend(KEYWORDS.iter().map(|k| Completion {
+					label: k.to_string(),
+					kind: CompletionKind::Keyword,
+					detail: None,
+					documentation: keyword_doc(k).map(str::to_string),
+				}));
+				items.retain(|c| c.label.starts_with(word));
+			}
+		}
+		items
+	}
+
+	/// Hover information for the token at `offset`
+	pub fn hover(&self, source: &str, offset: usize) -> Option<Hover> {
+		let token = token_at(source, offset)?;
+		let text = token.text(source);
+		let contents = match token.kind {
+			TokenKind::Attribute => {
+				let (object, field) = split_path(text)?;
+				if let Some((qualified, enum_def)) = self.types.and_then(|t| t.enum_named(object)) {
+					let variant = enum_def.variant(field)?;
+					return Some(Hover {
+						span: token.span,
+						contents: format!(
+							"`{}.{}` = `{}` (in `{}`)",
+							object,
+							field,
+							constant_literal(&variant.value),
+							qualified
+						),
+					});
+				}
+				let (qualified, typedef) = self.type_of(object)?;
+				let field = typedef.field(field)?;
+				let mut contents = format!(
+					"`{}.{}`: `{}` (in `{}`)",
+					object,
+					field.name,
+					field_signature(field.optional, &field.field_type),
+					qualified
+				);
+				if let Some(description) = &field.description {
+					contents.push_str("\n\n");
+					contents.push_str(description);
+				}
+				contents
+			}
+			TokenKind::Function => match text.split_once('.') {
+				Some((namespace, name)) if self.builtins.is_some_and(|b| b.has_function(namespace, name)) => {
+					format!("Built-in function `{}.{}`", namespace, name)
+				}
+				_ => return None,
+			},
+			TokenKind::Keyword => keyword_doc(text)?.to_string(),
+			_ => return None,
+		};
+		Some(Hover {
+			span: token.span,
+			contents,
+		})
+	}
+
+	/// Schema location of the type or field referenced at `offset`
+	pub fn definition(&self, source: &str, offset: usize) -> Option<Definition> {
+		let token = token_at(source, offset)?;
+		if token.kind != TokenKind::Attribute {
+			return None;
+		}
+		let (object, field) = split_path(token.text(source))?;
+		let (qualified, typedef) = self.type_of(object)?;
+		let package_name = qualified.strip_suffix(typedef.name.as_ref())?.strip_suffix('.')?;
+		let package = self.packages?.get_package(package_name)?;
+
+		for schema_file in &package.manifest.schemas {
+			let path = package.root_path.join(schema_file);
+			let Ok(content) = std::fs::read_to_string(&path) else {
+				continue;
+			};
+			if let Some(line) = find_definition_line(&content, &typedef.name, field) {
+				return Some(Definition { path, line });
+			}
+		}
+		None
+	}
+
+	fn type_of(&self, object: &str) -> Option<(&'a str, &'a TypeDef)> {
+		let types = self.types?;
+		match self.bindings.get(object) {
+			Some(qualified) => types
+				.types
+				.get_key_value(qualified.as_str())
+				.map(|(name, typedef)| (name.as_ref(), typedef)),
+			None => types.type_for_object(object),
+		}
+	}
+
+	/// Known fact objects: explicit bindings plus lowercased type names
+	fn objects(&self) -> Vec<String> {
+		let mut objects: Vec<String> = self.bindings.keys().cloned().collect();
+		if let Some(types) = self.types {
+			objects.extend(types.types.values().map(|t| t.name.to_ascii_lowercase()));
+		}
+		objects.sort();
+		objects.dedup();
+		objects
+	}
+
+	fn check_attribute(&self, source: &str, tokens: &[Token], index: usize, diagnostics: &mut Vec<Diagnostic>) {
+		let Some(types) = self.types else {
+			return;
+		};
+		let token = &tokens[index];
+		let Some((object, field)) = split_path(token.text(source)) else {
+			return;
+		};
+		if let Some((qualified, enum_def)) = types.enum_named(object) {
+			if enum_def.variant(field).is_none() {
+				diagnostics.push(Diagnostic {
+					span: token.span.clone(),
+					severity: Severity::Error,
+					message: format!("Enum `{}` has no variant `{}`", qualified, field),
+				});
+			}
+			return;
+		}
+		let Some((qualified, typedef)) = self.type_of(object) else {
+			if !types.types.is_empty() {
+				diagnostics.push(Diagnostic {
+					span: token.span.clone(),
+					severity: Severity::Warning,
+					message: format!("No schema type describes fact object `{}`", object),
+				});
+			}
+			return;
+		};
+		let Some(field_def) = typedef.field(field) else {
+			diagnostics.push(Diagnostic {
+				span: token.span.clone(),
+				severity: Severity::Error,
+				message: format!("Type `{}` has no field `{}`", qualified, field),
+			});
+			return;
+		};
+
+		// `attr <op> literal`: flag literals that can never match the field type
+		let (Some(op), Some(literal)) = (tokens.get(index + 1), tokens.get(index + 2)) else {
+			return;
+		};
+		let literal_type = match literal.kind {
+			TokenKind::String => "String",
+			TokenKind::Number => "Number",
+			TokenKind::Boolean => "Bool",
+			_ => return,
+		};
+		let message = match field_def.field_type.resolved() {
+			// `map CONTAINS key`: keys are strings, and enum-typed keys are the variant values
+			FieldType::Map { key, .. } if op.text(source).eq_ignore_ascii_case("CONTAINS") => {
+				match (key.resolved(), literal.kind) {
+					(_, TokenKind::Number | TokenKind::Boolean) => Some(format!(
+						"`{}.{}` has `{}` keys but is searched for a {} literal",
+						object, field, key, literal_type
+					)),
+					(FieldType::TypeRef(name), _) => {
+						let text = literal.text(source).trim_matches('"');
+						types
+							.enum_named(name)
+							.filter(|(_, e)| !e.variants.iter().any(|v| v.value == Value::String(text.into())))
+							.map(|_| format!("`{}.{}` has `{}` keys; \"{}\" is not one of them", object, field, key, text))
+					}
+					_ => None,
+				}
+			}
+			_ if op.kind != TokenKind::Operator => None,
+			primitive @ (FieldType::Bool | FieldType::String | FieldType::Number)
+				if primitive.to_string() != literal_type =>
+			{
+				Some(format!(
+					"`{}.{}` is `{}` but is compared with a {} literal",
+					object, field, field_def.field_type, literal_type
+				))
+			}
+			_ => None,
+		};
+		if let Some(message) = message {
+			diagnostics.push(Diagnostic {
+				span: token.span.start..literal.span.end,
+				severity: Severity::Warning,
+				message,
+			});
+		}
+	}
+
+	fn check_function(&self, source: &str, token: &Token, diagnostics: &mut Vec<Diagnostic>) {
+		let Some(builtins) = self.builtins else {
+			return;
+		};
+		let Some((namespace, name)) = token.text(source).split_once('.') else {
+			return;
+		};
+		if !builtins.has_function(namespace, name) {
+			diagnostics.push(Diagnostic {
+				span: token.span.clone(),
+				severity: Severity::Error,
+				message: format!("Unknown built-in function `{}.{}`", namespace, name),
+			});
+		} else if let Some(note) = builtins.deprecation(namespace, name) {
+			diagnostics.push(Diagnostic {
+				span: token.span.clone(),
+				severity: Severity::Warning,
+				message: format!("`{}.{}` is deprecated: {}", namespace, name, note),
+			});
+		}
+	}
+}
+
+// endregion: --- Analyzer
+
+// region:    --- Support
+
+/// Parse rule text as a script if it has `let` or `const` bindings, otherwise as an expression
+fn parse_source(source: &str) -> Result<(), Vec<HelError>> {
+	if is_script(source) {
+		validate_script(source)
+	} else {
+		parse_expression(source).map(|_| ()).map_err(|e| vec![e])
+	}
+}
+
+fn is_script(source: &str) -> bool {
+	source.lines().any(|l| {
+		let l = l.trim_start();
+		l.starts_with("let ") || l.starts_with("const ")
+	})
+}
+
+/// Unused and shadowed `let` bindings of a script (see `lint_script`)
+fn binding_warnings(source: &str) -> Vec<Warning> {
+	let collector = Diagnostics::new();
+	if is_script(source) {
+		lint_script(source, &collector);
+	}
+	collector.take()
+}
+
+fn token_at(source: &str, offset: usize) -> Option<Token> {
+	tokenize(source)
+		.into_iter()
+		.find(|t| t.span.start <= offset && offset <= t.span.end)
+}
+
+/// Split `object.field[.more]` into object and field
+/// `(object, field)` of an attribute path; wildcard paths (`object.*.field`) are not checked
+fn split_path(path: &str) -> Option<(&str, &str)> {
+	if path.contains(".*.") {
+		return None;
+	}
+	let mut parts = path.split('.');
+	Some((parts.next()?, parts.next()?))
+}
+
+/// Completion for a schema field, with its type and description
+pub(crate) fn field_completion(field: &FieldDef) -> Completion {
+	Completion {
+		label: field.name.to_string(),
+		kind: CompletionKind::Field,
+		detail: Some(field_signature(field.optional, &field.field_type)),
+		documentation: field.description.as_deref().map(str::to_string),
+	}
+}
+
+fn field_signature(optional: bool, field_type: &FieldType) -> String {
+	if optional {
+		format!("{}?", field_type)
+	} else {
+		field_type.to_string()
+	}
+}
+
+/// An enum variant's value as it would be written in an expression
+fn constant_literal(value: &Value) -> String {
+	match value {
+		Value::String(s) => format!("{:?}", s),
+		Value::Number(n) => n.to_string(),
+		Value::Bool(b) => b.to_string(),
+		other => format!("{:?}", other),
+	}
+}
+
+fn keyword_doc(keyword: &str) -> Option<&'static str> {
+	if keyword == "let" {
+		return Some("`let name = expr`: bind a value for later lines of a script");
+	}
+	if keyword == "const" {
+		return Some("`const NAME = value`: declare a script constant the host may override (no fact access)");
+	}
+	match keyword.to_ascii_uppercase().as_str() {
+		"AND" => Some("`AND`: true if both operands are true (short-circuits)"),
+		"OR" => Some("`OR`: true if either operand is true (short-circuits)"),
+		"XOR" => Some("`XOR`: true if exactly one of two operands is true (binds tighter than `OR`)"),
+		"CONTAINS" => Some("`CONTAINS`: list membership or substring test"),
+		"IN" => Some("`IN`: left value is an element of the right list, or within an inclusive range `lo..hi`"),
+		"BETWEEN" => Some("`BETWEEN lo AND hi`: inclusive numeric range, same as `IN lo..hi`"),
+		"EQI" => Some("`EQI` (or `==*`): case-insensitive string equality"),
+		"LIKE" => Some("`LIKE`: glob match; `*` matches any characters, `?` exactly one"),
+		_ => None,
+	}
+}
+
+/// Zero-based line of `type Name {` or of `field:` inside it
+fn find_definition_line(content: &str, type_name: &str, field: &str) -> Option<usize> {
+	let mut lines = content.lines().enumerate();
+	let (type_line, _) = lines.by_ref().find(|(_, l)| {
+		let mut words = l.split_whitespace();
+		words.next() == Some("type") && words.next() == Some(type_name)
+	})?;
+	for (i, line) in lines {
+		let line = line.trim();
+		if line == "}" {
+			break;
+		}
+		let name = line.split(':').next().unwrap_or("").trim().trim_end_matches('?');
+		if name == field {
+			return Some(i);
+		}
+	}
+	Some(type_line)
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::parse_schema;
+	use crate::CoreBuiltinsProvider;
+	use std::sync::Arc;
+
+	fn environment() -> TypeEnvironment {
+		let schema = parse_schema(
+			"type Binary {\n  format: String\n  entropy: Number\n  sections?: List<String>\n  by_format?: Map<Format, Number>\n}\n\nenum Format {\n  Elf = \"elf\"\n  Pe = \"pe\"\n}\n",
+		)
+		.expect("schema failed");
+		let types = schema
+			.types
+			.into_iter()
+			.map(|(name, def)| (Arc::from(format!("security-binary.{}", name)), def))
+			.collect();
+		let enums = schema
+			.enums
+			.into_iter()
+			.map(|(name, def)| (Arc::from(format!("security-binary.{}", name)), def))
+			.collect();
+		TypeEnvironment { types, enums }
+	}
+
+	#[test]
+	fn test_diagnostics() {
+		let env = environment();
+		let mut builtins = BuiltinsRegistry::new();
+		builtins.register(&CoreBuiltinsProvider).expect("register failed");
+		let analyzer = Analyzer::new().with_types(&env).with_builtins(&builtins);
+
+		let src = r#"binary.arch == "x86" AND binary.entropy == "high" AND core.len(binary.sections) > 0"#;
+		let diagnostics = analyzer.diagnostics(src);
+		assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+		assert_eq!(diagnostics[0].severity, Severity::Error);
+		assert_eq!(&src[diagnostics[0].span.clone()], "binary.arch");
+		assert_eq!(diagnostics[1].severity, Severity::Warning);
+		assert_eq!(&src[diagnostics[1].span.clone()], r#"binary.entropy == "high""#);
+
+		assert!(analyzer.diagnostics("binary.*.entropy > 7").is_empty());
+		assert!(analyzer.diagnostics("binary.format == Format.Pe").is_empty());
+		let variant = analyzer.diagnostics("binary.format == Format.Macho");
+		assert_eq!(variant.len(), 1, "{:?}", variant);
+		assert_eq!(
+			variant[0].message,
+			"Enum `security-binary.Format` has no variant `Macho`"
+		);
+
+		assert!(analyzer.diagnostics(r#"binary.by_format CONTAINS "elf""#).is_empty());
+		let keys = analyzer.diagnostics(r#"binary.by_format CONTAINS "macho" OR binary.by_format CONTAINS 1"#);
+		assert_eq!(keys.len(), 2, "{:?}", keys);
+		assert_eq!(keys[0].message, r#"`binary.by_format` has `Format` keys; "macho" is not one of them"#);
+		assert_eq!(keys[1].message, "`binary.by_format` has `Format` keys but is searched for a Number literal");
+
+		let parse_error = analyzer.diagnostics("binary.format == \"pe\"\n  AND ==");
+		assert_eq!(parse_error.len(), 1, "{:?}", parse_error);
+		assert_eq!(parse_error[0].severity, Severity::Error);
+		assert!(parse_error[0].span.start >= 22);
+
+		let collector = Diagnostics::new();
+		analyzer.collect_warnings(src, &collector);
+		let warnings = collector.take();
+		assert_eq!(warnings.len(), 1);
+		assert_eq!(warnings[0].kind, WarningKind::Schema);
+		assert_eq!(warnings[0].span, Some(diagnostics[1].span.clone()));
+		assert_eq!(
+			diagnostics[0].to_json()["span"],
+			serde_json::json!({ "start": 0, "end": 11 })
+		);
+		assert_eq!(diagnostics[1].to_json()["severity"], "warning");
+
+		let mut deprecated = builtins.clone();
+		deprecated.deprecate("core", "len", "compare the list directly");
+		Analyzer::new()
+			.with_builtins(&deprecated)
+			.collect_warnings("core.len(app.perms) > 1", &collector);
+		let warnings = collector.take();
+		assert_eq!(warnings.len(), 1);
+		assert_eq!(warnings[0].kind, WarningKind::DeprecatedBuiltin);
+		assert_eq!(warnings[0].span, Some(0..8));
+
+		let script = "let a = binary.format ==\nlet b = true\nb AND ==";
+		let script_errors = analyzer.diagnostics(script);
+		assert_eq!(script_errors.len(), 2, "{:?}", script_errors);
+		assert!(script_errors[1].span.start > script.rfind('\n').unwrap());
+
+		let script = "let a = binary.format == Format.Pe\nlet unused = true\na";
+		let bindings = analyzer.diagnostics(script);
+		assert_eq!(bindings.len(), 1, "{:?}", bindings);
+		assert_eq!(&script[bindings[0].span.clone()], "unused");
+		analyzer.collect_warnings(script, &collector);
+		assert_eq!(collector.take()[0].kind, WarningKind::UnusedBinding);
+	}
+
+	#[test]
+	fn test_completions_and_hover() {
+		let env = environment();
+		let analyzer = Analyzer::new()
+			.with_types(&env)
+			.bind_object("pe", "security-binary.Binary");
+
+		let fields: Vec<String> = analyzer.completions("pe.e", 4).into_iter().map(|c| c.label).collect();
+		assert_eq!(fields, vec!["entropy"]);
+
+		let objects: Vec<String> = analyzer
+			.completions("x AND b", 7)
+			.into_iter()
+			.map(|c| c.label)
+			.collect();
+		assert_eq!(objects, vec!["binary"]);
+
+		let hover = analyzer
+			.hover("binary.sections CONTAINS \".text\"", 3)
+			.expect("no hover");
+		assert_eq!(hover.span, 0..15);
+		assert!(hover.contents.contains("List<String>?"));
+
+		let variants: Vec<String> = analyzer
+			.completions("Format.", 7)
+			.into_iter()
+			.map(|c| c.label)
+			.collect();
+		assert_eq!(variants, vec!["Elf", "Pe"]);
+		let hover = analyzer.hover("Format.Pe", 0).expect("no hover");
+		assert_eq!(hover.contents, "`Format.Pe` = `\"pe\"` (in `security-binary.Format`)");
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,555 @@
+//! Audit log for HEL policy decisions
+//!
+//! Compliance requires a tamper-evident record of every policy decision. This
+//! module provides an `AuditSink` trait for persisting `AuditRecord`s, a default
+//! JSON Lines writer, and an `AuditLog` that evaluates rules and appends one
+//! record per evaluation.
+//!
+//! ## Tamper evidence
+//! - Every record carries the hash of the previous record (`prev_hash`) and its
+//!   own hash (`record_hash`) over its canonical JSON form
+//! - `verify_chain` recomputes the chain and reports the first broken record
+//! - Hashes are SHA-256, hex encoded
+//!
+//! ## Determinism
+//! - Rule and facts hashes depend only on the rule text and the evaluation trace
+//! - The clock is injectable so tests and replays produce identical records
+//! - `EvalTrace::stable_hash` fingerprints a whole trace for evidence chains kept
+//!   outside the audit log
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::builtins::BuiltinsRegistry;
+use crate::trace::{comparator_to_str, trace_ast, EvalTrace};
+use crate::{parse_expression_with_limits, EvalError, EvalOptions, HelResolver};
+
+/// Hash of the empty chain, used as `prev_hash` of the first record
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// region:    --- AuditRecord
+
+/// A single append-only audit record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+	/// Position of this record in the log (starting at 0)
+	pub sequence: u64,
+	/// Milliseconds since the Unix epoch when the decision was recorded
+	pub timestamp_ms: u64,
+	/// Host-assigned rule identifier, if any
+	pub rule_id: Option<String>,
+	/// SHA-256 of the rule source text
+	pub rule_hash: String,
+	/// SHA-256 over the facts used and their resolved values
+	pub facts_used_hash: String,
+	/// Host-assigned identity of the resolver (e.g., "desmond-facts@1.4")
+	pub resolver_id: String,
+	/// Version or fingerprint of the built-ins registry, if any
+	pub registry_version: Option<String>,
+	/// Evaluation result (absent if evaluation failed)
+	pub result: Option<bool>,
+	/// Evaluation error message (absent if evaluation succeeded)
+	pub error: Option<String>,
+	/// `record_hash` of the previous record (or `GENESIS_HASH`)
+	pub prev_hash: String,
+	/// SHA-256 over this record's canonical JSON with an empty `record_hash`
+	pub record_hash: String,
+}
+
+impl AuditRecord {
+	/// Recompute the hash of this record from its contents
+	pub fn compute_hash(&self) -> String {
+		let mut unsealed = self.clone();
+		unsealed.record_hash = String::new();
+		let canonical = serde_json::to_string(&unsealed).expect("audit record serialization cannot fail");
+		sha256_hex(canonical.as_bytes())
+	}
+}
+
+// endregion: --- AuditRecord
+
+// region:    --- AuditSink
+
+/// Destination for audit records
+///
+/// Sinks must persist records in the order they are appended and must never
+/// rewrite earlier records.
+pub trait AuditSink {
+	/// Append a record
+	fn append(&mut self, record: &AuditRecord) -> Result<(), AuditError>;
+
+	/// Flush buffered records to durable storage
+	fn flush(&mut self) -> Result<(), AuditError> {
+		Ok(())
+	}
+}
+
+/// Default sink writing one JSON object per line
+pub struct JsonlAuditSink<W: Write> {
+	writer: W,
+}
+
+impl<W: Write> JsonlAuditSink<W> {
+	/// Create a sink writing to the given writer (e.g., a file opened for append)
+	pub fn new(writer: W) -> Self {
+		Self { writer }
+	}
+
+	/// Consume the sink and return the underlying writer
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
+
+impl<W: Write> AuditSink for JsonlAuditSink<W> {
+	fn append(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+		let line = serde_json::to_string(record).map_err(|e| AuditError::Serialize(e.to_string()))?;
+		writeln!(self.writer, "{}", line).map_err(|e| AuditError::Io(e.to_string()))
+	}
+
+	fn flush(&mut self) -> Result<(), AuditError> {
+		self.writer.flush().map_err(|e| AuditError::Io(e.to_string()))
+	}
+}
+
+/// In-memory sink, mainly useful for tests
+impl AuditSink for Vec<AuditRecord> {
+	fn append(&mut self, record: &AuditRecord) -> Result<(), AuditError> {
+		self.push(record.clone());
+		Ok(())
+	}
+}
+
+// endregion: --- AuditSink
+
+// region:    --- AuditLog
+
+/// Evaluates rules and appends a hash-chained record per decision
+///
+/// # Examples
+///
+/// ```
+/// use hel::audit::{verify_chain, AuditLog};
+/// use hel::{FactsEvalContext, Value};
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("binary.format", Value::String("elf".into()));
+///
+/// let mut log = AuditLog::new(Vec::new(), "unit-test-facts");
+/// let trace = log.evaluate(Some("is-elf"), r#"binary.format == "elf""#, &ctx, None).unwrap();
+/// assert!(trace.result);
+///
+/// let records = log.into_sink();
+/// assert_eq!(records[0].result, Some(true));
+/// assert!(verify_chain(&records).is_ok());
+/// ```
+pub struct AuditLog<S: AuditSink> {
+	sink: S,
+	resolver_id: String,
+	registry_version: Option<String>,
+	sequence: u64,
+	last_hash: String,
+	clock: Box<dyn FnMut() -> u64 + Send>,
+}
+
+impl<S: AuditSink> AuditLog<S> {
+	/// Create a log writing to `sink`, attributing decisions to `resolver_id`
+	pub fn new(sink: S, resolver_id: &str) -> Self {
+		Self {
+			sink,
+			resolver_id: resolver_id.to_string(),
+			registry_version: None,
+			sequence: 0,
+			last_hash: GENESIS_HASH.to_string(),
+			clock: Box::new(system_time_ms),
+		}
+	}
+
+	/// Record the built-ins registry version in every subsequent record
+	pub fn with_registry_version(mut self, version: &str) -> Self {
+		self.registry_version = Some(version.to_string());
+		self
+	}
+
+	/// Record the fingerprint of `registry` (`BuiltinsRegistry::fingerprint`) in every subsequent record
+	pub fn with_registry(self, registry: &BuiltinsRegistry) -> Self {
+		self.with_registry_version(&registry.fingerprint())
+	}
+
+	/// Replace the wall clock (milliseconds since the Unix epoch)
+	pub fn with_clock(mut self, clock: impl FnMut() -> u64 + Send + 'static) -> Self {
+		self.clock = Box::new(clock);
+		self
+	}
+
+	/// Continue an existing chain after `last`, e.g. when reopening a log file
+	pub fn resume_after(mut self, last: &AuditRecord) -> Self {
+		self.sequence = last.sequence + 1;
+		self.last_hash = last.record_hash.clone();
+		self
+	}
+
+	/// Evaluate a rule with tracing and append an audit record for the decision
+	///
+	/// The evaluation outcome is returned unchanged; failing to write the audit
+	/// record is reported as `EvalError::InvalidOperation` so that no decision is
+	/// ever returned without being recorded.
+	pub fn evaluate(
+		&mut self,
+		rule_id: Option<&str>,
+		condition: &str,
+		resolver: &dyn HelResolver,
+		builtins: Option<&BuiltinsRegistry>,
+	) -> Result<EvalTrace, EvalError> {
+		self.evaluate_with_options(rule_id, condition, resolver, builtins, &EvalOptions::default())
+	}
+
+	/// `evaluate()` under explicit evaluation options
+	///
+	/// An interrupted evaluation (`EvalError::Timeout`, `EvalError::Cancelled`) is
+	/// recorded like any other error.
+	pub fn evaluate_with_options(
+		&mut self,
+		rule_id: Option<&str>,
+		condition: &str,
+		resolver: &dyn HelResolver,
+		builtins: Option<&BuiltinsRegistry>,
+		options: &EvalOptions,
+	) -> Result<EvalTrace, EvalError> {
+		let outcome = parse_expression_with_limits(condition, &options.limits)
+			.map_err(|e| EvalError::ParseError(e.message))
+			.and_then(|ast| trace_ast(&ast, &crate::options_context(resolver, builtins, options)));
+
+		self.record(rule_id, condition, &outcome)
+			.map_err(|e| EvalError::InvalidOperation(format!("Audit log write failed: {}", e)))?;
+		outcome
+	}
+
+	/// Append a record for an evaluation performed elsewhere
+	pub fn record(
+		&mut self,
+		rule_id: Option<&str>,
+		condition: &str,
+		outcome: &Result<EvalTrace, EvalError>,
+	) -> Result<AuditRecord, AuditError> {
+		let (result, error, facts_used_hash) = match outcome {
+			Ok(trace) => (Some(trace.result), None, facts_used_hash(trace)),
+			Err(e) => (None, Some(e.to_string()), sha256_hex(b"")),
+		};
+
+		let mut record = AuditRecord {
+			sequence: self.sequence,
+			timestamp_ms: (self.clock)(),
+			rule_id: rule_id.map(str::to_string),
+			rule_hash: rule_hash(condition),
+			facts_used_hash,
+			resolver_id: self.resolver_id.clone(),
+			registry_version: self.registry_version.clone(),
+			result,
+			error,
+			prev_hash: self.last_hash.clone(),
+			record_hash: String::new(),
+		};
+		record.record_hash = record.compute_hash();
+
+		self.sink.append(&record)?;
+		self.sequence += 1;
+		self.last_hash = record.record_hash.clone();
+		Ok(record)
+	}
+
+	/// Flush the underlying sink
+	pub fn flush(&mut self) -> Result<(), AuditError> {
+		self.sink.flush()
+	}
+
+	/// Consume the log and return the sink
+	pub fn into_sink(self) -> S {
+		self.sink
+	}
+}
+
+// endregion: --- AuditLog
+
+// region:    --- Verification
+
+/// Read records back from a JSON Lines audit log
+pub fn read_jsonl(reader: impl BufRead) -> Result<Vec<AuditRecord>, AuditError> {
+	let mut records = Vec::new();
+	for line in reader.lines() {
+		let line = line.map_err(|e| AuditError::Io(e.to_string()))?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		records.push(serde_json::from_str(&line).map_err(|e| AuditError::Serialize(e.to_string()))?);
+	}
+	Ok(records)
+}
+
+/// Verify sequence numbers and the hash chain of a complete log
+///
+/// Returns `AuditError::ChainBroken` with the sequence number of the first
+/// record that was altered, removed or reordered.
+pub fn verify_chain(records: &[AuditRecord]) -> Result<(), AuditError> {
+	let mut expected_prev = GENESIS_HASH.to_string();
+	for (i, record) in records.iter().enumerate() {
+		if record.sequence != i as u64
+			|| record.prev_hash != expected_prev
+			|| record.record_hash != record.compute_hash()
+		{
+			return Err(AuditError::ChainBroken { sequence: i as u64 });
+		}
+		expected_prev = record.record_hash.clone();
+	}
+	Ok(())
+}
+
+/// SHA-256 of a rule's source text (hex)
+pub fn rule_hash(source: &str) -> String {
+	sha256_hex(source.as_bytes())
+}
+
+fn facts_used_hash(trace: &EvalTrace) -> String {
+	let mut hasher = Sha256::new();
+	for fact in trace.facts_used() {
+		hasher.update(fact.as_bytes());
+		hasher.update(b"\n");
+	}
+	for atom in &trace.atoms {
+		let left = atom.resolved_left_value.as_deref().unwrap_or("");
+		let right = atom.resolved_right_value.as_deref().unwrap_or("");
+		hasher.update(format!("{}={}\n{}={}\n", atom.left, left, atom.right, right).as_bytes());
+	}
+	to_hex(&hasher.finalize())
+}
+
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+	to_hex(&Sha256::digest(data))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn system_time_ms() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_millis() as u64)
+		.unwrap_or(0)
+}
+
+// endregion: --- Verification
+
+// region:    --- Trace Hashing
+
+impl EvalTrace {
+	/// Canonical JSON form of this trace for the rule with source `rule_source`
+	///
+	/// Covers the rule hash, every atom (operands, operator, resolved values,
+	/// wildcard matches, result) in evaluation order and the final result. Object
+	/// keys are sorted and source spans and timings are left out, so the form
+	/// depends only on what was evaluated and what it resolved to.
+	pub fn canonical_json(&self, rule_source: &str) -> String {
+		let atoms: Vec<serde_json::Value> = self
+			.atoms
+			.iter()
+			.map(|atom| {
+				let matches: Vec<serde_json::Value> = atom
+					.matches
+					.iter()
+					.map(|m| serde_json::json!({ "path": m.path, "result": m.result, "value": m.value }))
+					.collect();
+				serde_json::json!({
+					"left": atom.left,
+					"matches": matches,
+					"op": comparator_to_str(atom.op),
+					"resolved_left": atom.resolved_left_value,
+					"resolved_right": atom.resolved_right_value,
+					"result": atom.atom_result,
+					"right": atom.right,
+				})
+			})
+			.collect();
+		let canonical = serde_json::json!({
+			"atoms": atoms,
+			"result": self.result,
+			"rule_hash": rule_hash(rule_source),
+		});
+		serde_json::to_string(&canonical).expect("trace serialization cannot fail")
+	}
+
+	/// SHA-256 (hex) of `canonical_json(rule_source)`
+	///
+	/// Two evaluations of the same rule text that resolved the same values hash
+	/// identically, so downstream systems can chain and verify evaluation evidence
+	/// by storing only the hash.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{evaluate_with_trace, FactsEvalContext, Value};
+	///
+	/// let rule = r#"binary.format == "elf""#;
+	/// let mut facts = FactsEvalContext::new();
+	/// facts.add_fact("binary.format", Value::String("elf".into()));
+	///
+	/// let first = evaluate_with_trace(rule, &facts, None).unwrap().stable_hash(rule);
+	/// let second = evaluate_with_trace(rule, &facts, None).unwrap().stable_hash(rule);
+	/// assert_eq!(first, second);
+	/// assert_eq!(first.len(), 64);
+	/// ```
+	pub fn stable_hash(&self, rule_source: &str) -> String {
+		sha256_hex(self.canonical_json(rule_source).as_bytes())
+	}
+}
+
+// endregion: --- Trace Hashing
+
+// region:    --- Error Types
+
+/// Audit-related errors
+#[derive(Debug, Clone)]
+pub enum AuditError {
+	/// Writing or reading the log failed
+	Io(String),
+	/// A record could not be serialized or deserialized
+	Serialize(String),
+	/// The hash chain does not verify starting at this record
+	ChainBroken { sequence: u64 },
+}
+
+impl std::fmt::Display for AuditError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			AuditError::Io(e) => write!(f, "Audit I/O error: {}", e),
+			AuditError::Serialize(e) => write!(f, "Audit serialization error: {}", e),
+			AuditError::ChainBroken { sequence } => {
+				write!(f, "Audit hash chain broken at record {}", sequence)
+			}
+		}
+	}
+}
+
+impl std::error::Error for AuditError {}
+
+// endregion: --- Error Types
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{FactsEvalContext, Value};
+
+	fn facts() -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.format", Value::String("elf".into()));
+		ctx
+	}
+
+	fn fixed_log<S: AuditSink>(sink: S) -> AuditLog<S> {
+		AuditLog::new(sink, "test-resolver")
+			.with_registry_version("core@1")
+			.with_clock(|| 1_700_000_000_000)
+	}
+
+	#[test]
+	fn test_jsonl_round_trip_and_chain() {
+		let mut log = fixed_log(JsonlAuditSink::new(Vec::new()));
+		log.evaluate(Some("a"), r#"binary.format == "elf""#, &facts(), None)
+			.unwrap();
+		log.evaluate(Some("b"), r#"binary.format == "pe""#, &facts(), None)
+			.unwrap();
+		assert!(log.evaluate(Some("c"), "(", &facts(), None).is_err());
+
+		let bytes = log.into_sink().into_inner();
+		let records = read_jsonl(bytes.as_slice()).expect("read failed");
+
+		assert_eq!(records.len(), 3);
+		assert_eq!(records[0].result, Some(true));
+		assert_eq!(records[1].result, Some(false));
+		assert!(records[2].error.is_some());
+		assert_eq!(records[0].prev_hash, GENESIS_HASH);
+		assert_eq!(records[1].prev_hash, records[0].record_hash);
+		assert_eq!(records[0].registry_version.as_deref(), Some("core@1"));
+		assert!(verify_chain(&records).is_ok());
+	}
+
+	#[test]
+	fn test_tampering_is_detected() {
+		let mut log = fixed_log(Vec::new());
+		for _ in 0..3 {
+			log.evaluate(None, r#"binary.format == "elf""#, &facts(), None).unwrap();
+		}
+		let mut records = log.into_sink();
+
+		records[1].result = Some(false);
+		assert!(matches!(
+			verify_chain(&records),
+			Err(AuditError::ChainBroken { sequence: 1 })
+		));
+
+		records.remove(1);
+		assert!(matches!(
+			verify_chain(&records),
+			Err(AuditError::ChainBroken { sequence: 1 })
+		));
+	}
+
+	#[test]
+	fn test_hashes_are_deterministic() {
+		let run = || {
+			let mut log = fixed_log(Vec::new());
+			log.evaluate(None, r#"binary.format == "elf""#, &facts(), None).unwrap();
+			log.into_sink().remove(0)
+		};
+		assert_eq!(run(), run());
+		assert_eq!(run().rule_hash, rule_hash(r#"binary.format == "elf""#));
+	}
+
+	#[test]
+	fn test_trace_stable_hash() {
+		let rule = r#"binary.format == "elf""#;
+		let trace = |facts: &FactsEvalContext| crate::evaluate_with_trace(rule, facts, None).unwrap();
+		let hash = trace(&facts()).stable_hash(rule);
+		assert_eq!(hash, trace(&facts()).stable_hash(rule));
+
+		// Same outcome, different resolved value
+		let mut other = FactsEvalContext::new();
+		other.add_fact("binary.format", Value::String("pe".into()));
+		assert_ne!(hash, trace(&other).stable_hash(rule));
+
+		// Same trace, different rule text
+		assert_ne!(hash, trace(&facts()).stable_hash(r#"binary.format  == "elf""#));
+
+		let canonical = trace(&facts()).canonical_json(rule);
+		assert!(canonical.starts_with(r#"{"atoms":[{"left":"binary.format","matches":[],"op":"==""#));
+		assert!(canonical.ends_with(&format!(r#""result":true,"rule_hash":"{}"}}"#, rule_hash(rule))));
+	}
+
+	#[test]
+	fn test_evaluate_with_options_records_interruptions() {
+		let mut log = fixed_log(JsonlAuditSink::new(Vec::new()));
+		let expired = EvalOptions::new().with_timeout(std::time::Duration::ZERO);
+		let err = log
+			.evaluate_with_options(Some("a"), r#"binary.format == "elf""#, &facts(), None, &expired)
+			.unwrap_err();
+		assert!(matches!(err, EvalError::Timeout));
+
+		let tolerant = EvalOptions::new().with_numeric_tolerance(0.01);
+		let mut ctx = facts();
+		ctx.add_fact("binary.entropy", crate::Value::Number(7.501));
+		let trace = log
+			.evaluate_with_options(Some("b"), "binary.entropy == 7.5", &ctx, None, &tolerant)
+			.unwrap();
+		assert!(trace.result);
+
+		let records = read_jsonl(log.into_sink().into_inner().as_slice()).expect("read failed");
+		assert_eq!(records[0].error.as_deref(), Some(EvalError::Timeout.to_string().as_str()));
+		assert_eq!(records[1].result, Some(true));
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,292 @@
+//! HEL language server
+//!
+//! Speaks LSP over stdio and serves the `hel::analysis` features: diagnostics,
+//! completion, hover and go-to-definition for schema types.
+//!
+//! ## Usage
+//! ```text
+//! hel-lsp [--packages DIR]... [--package NAME]...
+//! ```
+//! - `--packages DIR` adds a package search path
+//! - `--package NAME` loads a package (and its dependencies) for type checking
+//!
+//! Build with `cargo build --features lsp --bin hel-lsp`.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use hel::analysis::{Analyzer, CompletionKind, Severity};
+use hel::schema::{PackageRegistry, TypeEnvironment};
+use hel::BuiltinsRegistry;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+	DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+	CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse, DiagnosticSeverity,
+	DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Documentation,
+	GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+	MarkupContent, MarkupKind, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities,
+	TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Sync + Send>>;
+
+// region:    --- Main
+
+fn main() -> Result<()> {
+	let (packages, types) = load_packages(std::env::args().skip(1))?;
+	let builtins = BuiltinsRegistry::builder().with_core().build()?;
+	let analyzer = Analyzer::new()
+		.with_types(&types)
+		.with_packages(&packages)
+		.with_builtins(&builtins);
+
+	let (connection, io_threads) = Connection::stdio();
+	let capabilities = ServerCapabilities {
+		text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+		completion_provider: Some(CompletionOptions {
+			trigger_characters: Some(vec![".".to_string()]),
+			..Default::default()
+		}),
+		hover_provider: Some(HoverProviderCapability::Simple(true)),
+		definition_provider: Some(OneOf::Left(true)),
+		..Default::default()
+	};
+	connection.initialize(serde_json::to_value(capabilities)?)?;
+
+	Server {
+		connection: &connection,
+		analyzer,
+		documents: HashMap::new(),
+	}
+	.run()?;
+
+	// The writer thread exits once the connection's sender is dropped
+	drop(connection);
+	io_threads.join()?;
+	Ok(())
+}
+
+/// Parse the command line and build the type environment
+fn load_packages(args: impl Iterator<Item = String>) -> Result<(PackageRegistry, TypeEnvironment)> {
+	let mut registry = PackageRegistry::new();
+	let mut roots = Vec::new();
+	let mut args = args;
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--packages" => registry.add_search_path(PathBuf::from(args.next().ok_or("--packages needs a directory")?)),
+			"--package" => roots.push(args.next().ok_or("--package needs a name")?),
+			other => return Err(format!("unknown argument: {}", other).into()),
+		}
+	}
+
+	let mut names = Vec::new();
+	for root in &roots {
+		for name in registry.resolve_all(root)? {
+			if !names.contains(&name) {
+				names.push(name);
+			}
+		}
+	}
+	let types = registry.build_type_environment(&names)?;
+	Ok((registry, types))
+}
+
+// endregion: --- Main
+
+// region:    --- Server
+
+struct Server<'a> {
+	connection: &'a Connection,
+	analyzer: Analyzer<'a>,
+	documents: HashMap<Url, String>,
+}
+
+impl Server<'_> {
+	fn run(mut self) -> Result<()> {
+		for message in &self.connection.receiver {
+			match message {
+				Message::Request(request) => {
+					if self.connection.handle_shutdown(&request)? {
+						return Ok(());
+					}
+					let response = self.handle_request(request)?;
+					self.connection.sender.send(Message::Response(response))?;
+				}
+				Message::Notification(notification) => self.handle_notification(notification)?,
+				Message::Response(_) => {}
+			}
+		}
+		Ok(())
+	}
+
+	fn handle_request(&self, request: Request) -> Result<Response> {
+		let id = request.id.clone();
+		let result = match request.method.as_str() {
+			Completion::METHOD => {
+				let params: CompletionParams = serde_json::from_value(request.params)?;
+				serde_json::to_value(self.completion(params))?
+			}
+			HoverRequest::METHOD => {
+				let params: HoverParams = serde_json::from_value(request.params)?;
+				serde_json::to_value(self.hover(params))?
+			}
+			GotoDefinition::METHOD => {
+				let params: GotoDefinitionParams = serde_json::from_value(request.params)?;
+				serde_json::to_value(self.definition(params))?
+			}
+			_ => {
+				return Ok(Response::new_err(
+					id,
+					lsp_server::ErrorCode::MethodNotFound as i32,
+					format!("unsupported request: {}", request.method),
+				));
+			}
+		};
+		Ok(Response::new_ok(id, result))
+	}
+
+	fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+		match notification.method.as_str() {
+			DidOpenTextDocument::METHOD => {
+				let params: DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+				self.update(params.text_document.uri, params.text_document.text)
+			}
+			DidChangeTextDocument::METHOD => {
+				let params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+				// Full sync: the last change holds the whole document
+				match params.content_changes.into_iter().last() {
+					Some(change) => self.update(params.text_document.uri, change.text),
+					None => Ok(()),
+				}
+			}
+			DidCloseTextDocument::METHOD => {
+				let params: DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+				self.documents.remove(&params.text_document.uri);
+				self.publish(params.text_document.uri, Vec::new())
+			}
+			_ => Ok(()),
+		}
+	}
+
+	fn update(&mut self, uri: Url, text: String) -> Result<()> {
+		let diagnostics = self
+			.analyzer
+			.diagnostics(&text)
+			.into_iter()
+			.map(|d| lsp_types::Diagnostic {
+				range: Range::new(position_at(&text, d.span.start), position_at(&text, d.span.end)),
+				severity: Some(match d.severity {
+					Severity::Error => DiagnosticSeverity::ERROR,
+					Severity::Warning => DiagnosticSeverity::WARNING,
+				}),
+				source: Some("hel".to_string()),
+				message: d.message,
+				..Default::default()
+			})
+			.collect();
+		self.documents.insert(uri.clone(), text);
+		self.publish(uri, diagnostics)
+	}
+
+	fn publish(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<()> {
+		let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+		let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+		self.connection.sender.send(Message::Notification(notification))?;
+		Ok(())
+	}
+
+	fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+		let position = params.text_document_position;
+		let text = self.documents.get(&position.text_document.uri)?;
+		let items = self
+			.analyzer
+			.completions(text, offset_at(text, position.position))
+			.into_iter()
+			.map(|c| CompletionItem {
+				label: c.label,
+				kind: Some(match c.kind {
+					CompletionKind::Object => CompletionItemKind::VARIABLE,
+					CompletionKind::Field => CompletionItemKind::FIELD,
+					CompletionKind::Namespace => CompletionItemKind::MODULE,
+					CompletionKind::Function => CompletionItemKind::FUNCTION,
+					CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+					CompletionKind::Variant => CompletionItemKind::ENUM_MEMBER,
+				}),
+				detail: c.detail,
+				documentation: c.documentation.map(|value| {
+					Documentation::MarkupContent(MarkupContent {
+						kind: MarkupKind::Markdown,
+						value,
+					})
+				}),
+				..Default::default()
+			})
+			.collect();
+		Some(CompletionResponse::Array(items))
+	}
+
+	fn hover(&self, params: HoverParams) -> Option<Hover> {
+		let position = params.text_document_position_params;
+		let text = self.documents.get(&position.text_document.uri)?;
+		let hover = self.analyzer.hover(text, offset_at(text, position.position))?;
+		Some(Hover {
+			contents: HoverContents::Markup(MarkupContent {
+				kind: MarkupKind::Markdown,
+				value: hover.contents,
+			}),
+			range: Some(Range::new(
+				position_at(text, hover.span.start),
+				position_at(text, hover.span.end),
+			)),
+		})
+	}
+
+	fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+		let position = params.text_document_position_params;
+		let text = self.documents.get(&position.text_document.uri)?;
+		let definition = self.analyzer.definition(text, offset_at(text, position.position))?;
+		let path = definition.path.canonicalize().unwrap_or(definition.path);
+		let uri = Url::from_file_path(path).ok()?;
+		let start = Position::new(definition.line as u32, 0);
+		Some(GotoDefinitionResponse::Scalar(Location::new(
+			uri,
+			Range::new(start, start),
+		)))
+	}
+}
+
+// endregion: --- Server
+
+// region:    --- Positions
+
+/// Byte offset of an LSP position (UTF-16 code units)
+fn offset_at(text: &str, position: Position) -> usize {
+	let line_start: usize = text
+		.split_inclusive('\n')
+		.take(position.line as usize)
+		.map(str::len)
+		.sum();
+	let mut units = 0;
+	for (i, c) in text[line_start..].char_indices() {
+		if units >= position.character as usize || c == '\n' {
+			return line_start + i;
+		}
+		units += c.len_utf16();
+	}
+	text.len()
+}
+
+/// LSP position (UTF-16 code units) of a byte offset
+fn position_at(text: &str, offset: usize) -> Position {
+	let before = &text[..offset];
+	let line = before.matches('\n').count();
+	let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+	let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+	Position::new(line as u32, character as u32)
+}
+
+// endregion: --- Positions
@@ -0,0 +1,259 @@
+//! HEL command-line tool
+//!
+//! ## Usage
+//! ```text
+//! hel repl [--facts FILE] [--packages DIR]... [--package NAME]...
+//! hel fmt [--check] [--width N] FILE...
+//! hel lint PACKAGE_DIR...
+//! ```
+//! - `--facts FILE` loads facts from JSON (YAML / TOML with the `facts-yaml` / `facts-toml` features)
+//! - `--packages DIR` adds a package search path
+//! - `--package NAME` loads a package (and its dependencies) for attribute completion
+//! - `fmt` rewrites rule files in canonical form; `--check` only lists files that are not
+//!   formatted and exits with an error if there are any; parse errors are shown with a source snippet
+//! - `lint` prints the `SchemaPackage::lint` warnings of each package directory and exits with
+//!   an error if there are any
+//!
+//! Build with `cargo build --features cli --bin hel`.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use hel::analysis::Analyzer;
+use hel::format::{format_script, FormatOptions};
+use hel::repl::ReplSession;
+use hel::schema::{PackageRegistry, SchemaPackage, TypeEnvironment};
+use hel::{BuiltinsRegistry, FactsEvalContext};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Sync + Send>>;
+
+const USAGE: &str = "usage:
+  hel repl [--facts FILE] [--packages DIR]... [--package NAME]...
+  hel fmt [--check] [--width N] FILE...
+  hel lint PACKAGE_DIR...";
+
+// region:    --- Main
+
+fn main() -> Result<()> {
+	let mut args = std::env::args().skip(1);
+	match args.next().as_deref() {
+		Some("repl") => repl(Options::parse(args)?),
+		Some("fmt") => fmt(Options::parse(args)?),
+		Some("lint") => lint(Options::parse(args)?),
+		_ => Err(USAGE.into()),
+	}
+}
+
+/// Command-line options shared by subcommands
+#[derive(Default)]
+struct Options {
+	facts: Option<PathBuf>,
+	search_paths: Vec<PathBuf>,
+	packages: Vec<String>,
+	check: bool,
+	width: Option<usize>,
+	files: Vec<PathBuf>,
+}
+
+impl Options {
+	fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+		let mut options = Options::default();
+		while let Some(arg) = args.next() {
+			let mut value = || args.next().ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE));
+			match arg.as_str() {
+				"--facts" => options.facts = Some(PathBuf::from(value()?)),
+				"--packages" => options.search_paths.push(PathBuf::from(value()?)),
+				"--package" => options.packages.push(value()?),
+				"--check" => options.check = true,
+				"--width" => options.width = Some(value()?.parse()?),
+				_ if !arg.starts_with('-') => options.files.push(PathBuf::from(arg)),
+				_ => return Err(format!("unknown argument: {}\n{}", arg, USAGE).into()),
+			}
+		}
+		Ok(options)
+	}
+
+	fn load_packages(&self) -> Result<(PackageRegistry, TypeEnvironment)> {
+		let mut registry = PackageRegistry::new();
+		for path in &self.search_paths {
+			registry.add_search_path(path.clone());
+		}
+		let mut names = Vec::new();
+		for root in &self.packages {
+			for name in registry.resolve_all(root)? {
+				if !names.contains(&name) {
+					names.push(name);
+				}
+			}
+		}
+		let types = registry.build_type_environment(&names)?;
+		Ok((registry, types))
+	}
+}
+
+/// Load a facts file, picking the format by extension
+fn load_facts(path: &Path) -> Result<FactsEvalContext> {
+	let content = std::fs::read_to_string(path)?;
+	let facts = match path.extension().and_then(|e| e.to_str()) {
+		#[cfg(feature = "facts-yaml")]
+		Some("yaml" | "yml") => FactsEvalContext::from_yaml(&content)?,
+		#[cfg(feature = "facts-toml")]
+		Some("toml") => FactsEvalContext::from_toml(&content)?,
+		_ => FactsEvalContext::from_json(&content)?,
+	};
+	Ok(facts)
+}
+
+// endregion: --- Main
+
+// region:    --- repl
+
+fn repl(options: Options) -> Result<()> {
+	let facts = match &options.facts {
+		Some(path) => load_facts(path)?,
+		None => FactsEvalContext::new(),
+	};
+	let (packages, types) = options.load_packages()?;
+	let builtins = BuiltinsRegistry::builder().with_core().build()?;
+
+	let analyzer = Analyzer::new()
+		.with_types(&types)
+		.with_packages(&packages)
+		.with_builtins(&builtins);
+	let session = ReplSession::new(&facts)
+		.with_builtins(&builtins)
+		.with_analyzer(analyzer);
+
+	let mut editor = Editor::new()?;
+	editor.set_helper(Some(ReplHelper { session }));
+	println!(
+		"HEL {} REPL (:help for commands, Ctrl-D to exit)",
+		env!("CARGO_PKG_VERSION")
+	);
+
+	loop {
+		let line = match editor.readline("hel> ") {
+			Ok(line) => line,
+			Err(ReadlineError::Interrupted) => continue,
+			Err(ReadlineError::Eof) => return Ok(()),
+			Err(e) => return Err(e.into()),
+		};
+		editor.add_history_entry(line.as_str())?;
+
+		let Some(helper) = editor.helper_mut() else {
+			return Ok(());
+		};
+		match helper.session.eval_line(&line) {
+			Ok(output) if output.is_empty() => {}
+			Ok(output) => println!("{}", output),
+			Err(e) => eprintln!("error: {}", e),
+		}
+	}
+}
+
+/// Line-editor helper completing attributes, built-ins and let bindings
+struct ReplHelper<'a> {
+	session: ReplSession<'a>,
+}
+
+impl Completer for ReplHelper<'_> {
+	type Candidate = Pair;
+
+	fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+		// Candidates replace the segment after the last `.` (or the whole word)
+		let start = line[..pos]
+			.rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+			.map_or(0, |i| i + 1);
+		let candidates = self
+			.session
+			.completions(line, pos)
+			.into_iter()
+			.map(|c| Pair {
+				display: c.label.clone(),
+				replacement: c.label,
+			})
+			.collect();
+		Ok((start, candidates))
+	}
+}
+
+impl Hinter for ReplHelper<'_> {
+	type Hint = String;
+}
+
+impl Highlighter for ReplHelper<'_> {}
+
+impl Validator for ReplHelper<'_> {}
+
+impl Helper for ReplHelper<'_> {}
+
+// endregion: --- repl
+
+// region:    --- fmt
+
+fn fmt(options: Options) -> Result<()> {
+	if options.files.is_empty() {
+		return Err(USAGE.into());
+	}
+	let mut format_options = FormatOptions::default();
+	if let Some(width) = options.width {
+		format_options.max_width = width;
+	}
+
+	let mut unformatted = 0;
+	for path in &options.files {
+		let source = std::fs::read_to_string(path)?;
+		let formatted = match format_script(&source, &format_options) {
+			Ok(formatted) => formatted,
+			Err(e) => {
+				eprint!("{}", e.with_source(path.display().to_string(), source).render());
+				return Err(format!("{}: invalid HEL", path.display()).into());
+			}
+		};
+		if formatted == source {
+			continue;
+		}
+		if options.check {
+			println!("{}", path.display());
+			unformatted += 1;
+		} else {
+			std::fs::write(path, formatted)?;
+		}
+	}
+
+	if unformatted > 0 {
+		return Err(format!("{} file(s) not formatted", unformatted).into());
+	}
+	Ok(())
+}
+
+// endregion: --- fmt
+
+// region:    --- lint
+
+fn lint(options: Options) -> Result<()> {
+	if options.files.is_empty() {
+		return Err(USAGE.into());
+	}
+
+	let mut count = 0;
+	for dir in &options.files {
+		for lint in SchemaPackage::from_directory(dir)?.lint() {
+			println!("{}", lint);
+			count += 1;
+		}
+	}
+
+	if count > 0 {
+		return Err(format!("{} lint warning(s)", count).into());
+	}
+	Ok(())
+}
+
+// endregion: --- lint
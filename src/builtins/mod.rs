@@ -0,0 +1,1600 @@
+//! Built-in function registry for HEL
+//!
+//! This module provides a pluggable system for domain-specific built-in functions.
+//! Domains can register deterministic functions under their namespace without
+//! modifying the HEL core language.
+//!
+//! ## Architecture
+//! - BuiltinsProvider trait: defines how to provide built-in functions
+//! - BuiltinsRegistry: namespace-aware function dispatcher
+//! - Built-ins are pure and deterministic (no I/O, no global state)
+//!
+//! ## Namespacing
+//! - Functions are called as `namespace.function_name(args)`
+//! - Example: `security.contains(list, value)` or `sales.email_is_valid(email)`
+//!
+//! ## Determinism
+//! - All built-ins must be pure functions
+//! - Registry uses BTreeMap for stable iteration order
+//! - Function names are normalized to lowercase for consistency
+//! - `testing::ProviderHarness` checks a provider against these rules
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use super::{compare_new_values, numbers_equal, Comparator, EvalError, EvalOptions, Value};
+use crate::prelude::*;
+use crate::rules::RULE_REFERENCE;
+use crate::visit::{walk_ast, Visitor, Walk};
+use crate::AstNode;
+
+#[cfg(feature = "std")]
+pub mod testing;
+
+// region:    --- Built-in Function Type
+
+/// A built-in function signature
+///
+/// Takes a list of arguments and returns a Value or error.
+/// Must be deterministic and pure (no I/O, no global state).
+pub type BuiltinFn = Arc<dyn Fn(&[Value]) -> Result<Value, EvalError> + Send + Sync>;
+
+/// A built-in function that also reads the options of the evaluation calling it
+///
+/// For functions whose limits the host tunes per evaluation (e.g., the longest input
+/// `core.levenshtein` accepts); see `BuiltinsProvider::get_builtins_with_options`.
+/// The same purity rules apply: the options are the only extra input.
+pub type BuiltinFnWithOptions = Arc<dyn Fn(&[Value], &EvalOptions) -> Result<Value, EvalError> + Send + Sync>;
+
+/// Every function of a provider, with plain functions ignoring the options
+pub(crate) fn provider_functions(provider: &dyn BuiltinsProvider) -> BTreeMap<String, BuiltinFnWithOptions> {
+	let mut functions: BTreeMap<String, BuiltinFnWithOptions> = provider
+		.get_builtins()
+		.into_iter()
+		.map(|(name, func)| (name, Arc::new(move |args: &[Value], _: &EvalOptions| func(args)) as BuiltinFnWithOptions))
+		.collect();
+	functions.extend(provider.get_builtins_with_options());
+	functions
+}
+
+// endregion: --- Built-in Function Type
+
+// region:    --- Argument Helpers
+
+/// Argument checks for built-in implementations, with the same error text everywhere
+///
+/// Wraps the arguments of one call together with the function's qualified name, which
+/// every error names. Arity errors are `InvalidOperation("ns.f expects 2 arguments")`,
+/// type errors are `TypeMismatch` with the offending value as `got` and the function as
+/// `context`.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{BuiltinArgs, EvalError, Value};
+///
+/// fn weighted(args: &[Value]) -> Result<Value, EvalError> {
+///     let args = BuiltinArgs::new("acme.weighted", args);
+///     args.expect_args(2)?;
+///     let scores = args.expect_list_of_numbers(0)?;
+///     let weight = args.expect_number(1)?;
+///     Ok(Value::Number(scores.iter().sum::<f64>() * weight))
+/// }
+///
+/// let scores = Value::List(vec![Value::Number(1.0), Value::Number(2.0)]);
+/// assert_eq!(weighted(&[scores, Value::Number(2.0)]).unwrap(), Value::Number(6.0));
+///
+/// let err = weighted(&[Value::from("1, 2"), Value::Number(2.0)]).unwrap_err();
+/// assert_eq!(err.to_string(), r#"Type mismatch in acme.weighted: expected List of Number, got String("1, 2")"#);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BuiltinArgs<'a> {
+	name: &'a str,
+	args: &'a [Value],
+}
+
+impl<'a> BuiltinArgs<'a> {
+	/// Wrap the arguments of a call to `name` (e.g., `core.len`)
+	pub fn new(name: &'a str, args: &'a [Value]) -> Self {
+		Self { name, args }
+	}
+
+	/// Require exactly `n` arguments
+	pub fn expect_args(&self, n: usize) -> Result<&'a [Value], EvalError> {
+		if self.args.len() != n {
+			return Err(EvalError::InvalidOperation(format!("{} expects {}", self.name, plural_args(n))));
+		}
+		Ok(self.args)
+	}
+
+	/// Require at least `n` arguments
+	pub fn expect_min_args(&self, n: usize) -> Result<&'a [Value], EvalError> {
+		if self.args.len() < n {
+			return Err(EvalError::InvalidOperation(format!("{} expects at least {}", self.name, plural_args(n))));
+		}
+		Ok(self.args)
+	}
+
+	/// Argument `i`, which must be present
+	pub fn expect_value(&self, i: usize) -> Result<&'a Value, EvalError> {
+		self.args.get(i).ok_or_else(|| {
+			EvalError::InvalidOperation(format!("{} expects at least {}", self.name, plural_args(i + 1)))
+		})
+	}
+
+	/// Argument `i` as a string (see `Value::try_str`)
+	pub fn expect_string(&self, i: usize) -> Result<&'a str, EvalError> {
+		self.expect_value(i)?.try_str(self.name)
+	}
+
+	/// Argument `i` as a number (see `Value::try_f64`)
+	pub fn expect_number(&self, i: usize) -> Result<f64, EvalError> {
+		self.expect_value(i)?.try_f64(self.name)
+	}
+
+	/// Argument `i` as a boolean (see `Value::try_bool`)
+	pub fn expect_bool(&self, i: usize) -> Result<bool, EvalError> {
+		self.expect_value(i)?.try_bool(self.name)
+	}
+
+	/// Argument `i` as a list (see `Value::try_list`)
+	pub fn expect_list(&self, i: usize) -> Result<&'a [Value], EvalError> {
+		self.expect_value(i)?.try_list(self.name)
+	}
+
+	/// Argument `i` as a list whose elements are all numbers
+	pub fn expect_list_of_numbers(&self, i: usize) -> Result<Vec<f64>, EvalError> {
+		let value = self.expect_value(i)?;
+		let items = value.as_list().ok_or_else(|| self.mismatch("List of Number", value))?;
+		items
+			.iter()
+			.map(|item| item.as_f64().ok_or_else(|| self.mismatch("List of Number", item)))
+			.collect()
+	}
+
+	/// Type mismatch for argument `got` of this call, for checks the helpers above do not cover
+	pub fn mismatch(&self, expected: &str, got: &Value) -> EvalError {
+		EvalError::TypeMismatch {
+			expected: expected.to_string(),
+			got: format!("{:?}", got),
+			context: self.name.to_string(),
+		}
+	}
+}
+
+/// `1 argument`, `2 arguments`
+fn plural_args(n: usize) -> String {
+	format!("{} argument{}", n, if n == 1 { "" } else { "s" })
+}
+
+// endregion: --- Argument Helpers
+
+// region:    --- BuiltinsProvider Trait
+
+/// Trait for providing built-in functions for a domain
+///
+/// Domains implement this trait to provide their custom functions.
+/// The namespace is typically the domain package name.
+pub trait BuiltinsProvider {
+	/// Get the namespace for these built-ins (e.g., "security", "sales")
+	fn namespace(&self) -> &str;
+
+	/// Get all built-in functions provided by this domain
+	///
+	/// Returns a map of function name (lowercase) -> implementation
+	fn get_builtins(&self) -> BTreeMap<String, BuiltinFn>;
+
+	/// Built-in functions that read the options of the calling evaluation
+	///
+	/// Merged with `get_builtins`, replacing a plain function of the same name. Empty by default.
+	fn get_builtins_with_options(&self) -> BTreeMap<String, BuiltinFnWithOptions> {
+		BTreeMap::new()
+	}
+
+	/// Version of these built-ins (e.g., the providing crate's version), recorded in
+	/// `BuiltinsRegistry::fingerprint`; bump it whenever a function's behavior changes
+	fn version(&self) -> Option<&str> {
+		None
+	}
+}
+
+// endregion: --- BuiltinsProvider Trait
+
+// region:    --- BuiltinsRegistry
+
+/// Registry for namespace-aware built-in functions
+///
+/// Manages multiple providers and dispatches function calls deterministically.
+/// Cloning is cheap: the function tables are shared until one of the clones registers
+/// or unregisters a namespace (see `scoped`).
+#[derive(Clone)]
+pub struct BuiltinsRegistry {
+	/// Namespace -> (function_name -> implementation), shared between clones
+	providers: Arc<BTreeMap<String, BTreeMap<String, BuiltinFnWithOptions>>>,
+	/// `namespace.function_name` -> deprecation note
+	deprecated: BTreeMap<String, String>,
+	/// Namespace -> provider version, for providers declaring one
+	versions: BTreeMap<String, String>,
+	/// Namespaces searched, in order, for unqualified calls
+	search_order: Vec<String>,
+}
+
+impl BuiltinsRegistry {
+	/// Create a new empty registry
+	pub fn new() -> Self {
+		Self {
+			providers: Arc::new(BTreeMap::new()),
+			deprecated: BTreeMap::new(),
+			versions: BTreeMap::new(),
+			search_order: vec!["core".to_string()],
+		}
+	}
+
+	/// Start building a registry from several providers
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{BuiltinsRegistry, CoreBuiltinsProvider};
+	///
+	/// let registry = BuiltinsRegistry::builder().with_core().build().unwrap();
+	/// assert!(registry.has_function("core", "len"));
+	///
+	/// let err = BuiltinsRegistry::builder().with_core().with(&CoreBuiltinsProvider).build().unwrap_err();
+	/// assert_eq!(err.errors, ["Namespace 'core' is already registered"]);
+	/// ```
+	pub fn builder() -> BuiltinsRegistryBuilder {
+		BuiltinsRegistryBuilder::default()
+	}
+
+	/// Register a built-ins provider
+	///
+	/// Returns error if the namespace is already registered
+	pub fn register(&mut self, provider: &dyn BuiltinsProvider) -> Result<(), String> {
+		let namespace = provider.namespace().to_lowercase();
+
+		if self.providers.contains_key(&namespace) {
+			return Err(format!("Namespace '{}' is already registered", namespace));
+		}
+
+		let builtins = provider_functions(provider);
+		if let Some(version) = provider.version() {
+			self.versions.insert(namespace.clone(), version.to_string());
+		}
+		Arc::make_mut(&mut self.providers).insert(namespace, builtins);
+
+		Ok(())
+	}
+
+	/// Remove a namespace with its functions, provider version and deprecations
+	///
+	/// Returns `false` if the namespace was not registered.
+	pub fn unregister(&mut self, namespace: &str) -> bool {
+		let namespace = namespace.to_lowercase();
+		if !self.providers.contains_key(&namespace) {
+			return false;
+		}
+
+		Arc::make_mut(&mut self.providers).remove(&namespace);
+		self.versions.remove(&namespace);
+		let prefix = format!("{}.", namespace);
+		self.deprecated.retain(|key, _| !key.starts_with(&prefix));
+		true
+	}
+
+	/// Child registry starting with this registry's namespaces
+	///
+	/// The child shares the function tables until it registers or unregisters a
+	/// namespace, and changes to either registry never affect the other, so a shared
+	/// base can be layered with per-tenant or per-test providers cheaply.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{BuiltinsRegistry, CoreBuiltinsProvider};
+	///
+	/// let base = BuiltinsRegistry::builder().with_core().build().unwrap();
+	///
+	/// let mut tenant = base.scoped();
+	/// tenant.unregister("core");
+	/// assert!(!tenant.has_function("core", "len"));
+	/// assert!(base.has_function("core", "len"));
+	///
+	/// tenant.register(&CoreBuiltinsProvider).unwrap();
+	/// assert!(tenant.has_function("core", "len"));
+	/// ```
+	pub fn scoped(&self) -> Self {
+		self.clone()
+	}
+
+	/// Call a built-in function by qualified name
+	///
+	/// # Arguments
+	/// * `namespace` - The namespace (e.g., "security")
+	/// * `function_name` - The function name (e.g., "contains")
+	/// * `args` - The function arguments
+	///
+	/// # Returns
+	/// The function result, or error if function not found or execution fails
+	pub fn call(&self, namespace: &str, function_name: &str, args: &[Value]) -> Result<Value, EvalError> {
+		self.call_with_options(namespace, function_name, args, &EvalOptions::default())
+	}
+
+	/// Call a built-in function on behalf of an evaluation running with `options`
+	///
+	/// Functions registered through `BuiltinsProvider::get_builtins_with_options` read
+	/// their limits from `options`; `call` passes the defaults.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{BuiltinsRegistry, EvalError, EvalOptions, Value};
+	///
+	/// let registry = BuiltinsRegistry::builder().with_core().build().unwrap();
+	/// let args = [Value::from("paypal.com"), Value::from("paypa1.com")];
+	/// assert_eq!(registry.call("core", "levenshtein", &args).unwrap(), Value::Number(1.0));
+	///
+	/// let options = EvalOptions::new().with_max_distance_input_len(8);
+	/// let err = registry.call_with_options("core", "levenshtein", &args, &options).unwrap_err();
+	/// assert!(matches!(err, EvalError::BuiltinFailure { code, .. } if code == "input_too_long"));
+	/// ```
+	pub fn call_with_options(
+		&self,
+		namespace: &str,
+		function_name: &str,
+		args: &[Value],
+		options: &EvalOptions,
+	) -> Result<Value, EvalError> {
+		let namespace = namespace.to_lowercase();
+		let function_name = function_name.to_lowercase();
+
+		let provider = self.providers.get(&namespace).ok_or_else(|| EvalError::InvalidOperation(format!("Unknown namespace: {}", namespace)))?;
+
+		let func = provider
+			.get(&function_name)
+			.ok_or_else(|| EvalError::InvalidOperation(format!("Unknown function: {}.{}", namespace, function_name)))?;
+
+		func(args, options).map_err(|mut err| {
+			if let EvalError::BuiltinFailure { namespace: ns, name, .. } = &mut err {
+				if ns.is_empty() {
+					*ns = namespace;
+					*name = function_name;
+				}
+			}
+			err
+		})
+	}
+
+	/// Check if a function exists
+	pub fn has_function(&self, namespace: &str, function_name: &str) -> bool {
+		let namespace = namespace.to_lowercase();
+		let function_name = function_name.to_lowercase();
+
+		self.providers
+			.get(&namespace)
+			.and_then(|p| p.get(&function_name))
+			.is_some()
+	}
+
+	/// Mark a function as deprecated; calls still succeed but raise a warning
+	///
+	/// `note` should say what to use instead.
+	pub fn deprecate(&mut self, namespace: &str, function_name: &str, note: impl Into<String>) {
+		let key = format!("{}.{}", namespace.to_lowercase(), function_name.to_lowercase());
+		self.deprecated.insert(key, note.into());
+	}
+
+	/// Deprecation note of a function, if it is deprecated
+	pub fn deprecation(&self, namespace: &str, function_name: &str) -> Option<&str> {
+		let key = format!("{}.{}", namespace.to_lowercase(), function_name.to_lowercase());
+		self.deprecated.get(&key).map(String::as_str)
+	}
+
+	/// List all registered namespaces
+	pub fn namespaces(&self) -> Vec<String> {
+		self.providers.keys().cloned().collect()
+	}
+
+	/// List all functions in a namespace
+	pub fn functions_in_namespace(&self, namespace: &str) -> Option<Vec<String>> {
+		let namespace = namespace.to_lowercase();
+		self.providers.get(&namespace).map(|p| p.keys().cloned().collect())
+	}
+
+	/// Set the namespaces searched, in order, for unqualified calls (`core` by default)
+	///
+	/// An unqualified call `name(args)` runs the function from the first namespace
+	/// declaring `name`; an empty order restores the default.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::BuiltinsRegistry;
+	///
+	/// let mut registry = BuiltinsRegistry::builder().with_core().build().unwrap();
+	/// assert_eq!(registry.resolve_unqualified("len"), "core");
+	///
+	/// registry.set_search_order(&["security", "core"]);
+	/// assert_eq!(registry.search_order(), ["security", "core"]);
+	/// assert_eq!(registry.resolve_unqualified("len"), "core");
+	/// assert_eq!(registry.resolve_unqualified("is_dangerous"), "security");
+	/// ```
+	pub fn set_search_order(&mut self, namespaces: &[&str]) {
+		self.search_order = match namespaces.is_empty() {
+			true => vec!["core".to_string()],
+			false => namespaces.iter().map(|ns| ns.to_lowercase()).collect(),
+		};
+	}
+
+	/// Namespaces searched, in order, for unqualified calls
+	pub fn search_order(&self) -> &[String] {
+		&self.search_order
+	}
+
+	/// Namespace an unqualified call to `function_name` resolves to
+	///
+	/// The first namespace in the search order declaring the function, or the first
+	/// namespace in the search order if none does (so the call fails as unknown there).
+	pub fn resolve_unqualified(&self, function_name: &str) -> &str {
+		self.search_order
+			.iter()
+			.find(|ns| self.has_function(ns, function_name))
+			.unwrap_or(&self.search_order[0])
+	}
+
+	/// Check that every function an expression calls exists
+	///
+	/// Reports each unknown call once, in source order, with the closest known name as
+	/// a suggestion. Unqualified calls are looked up through the search order (see
+	/// `set_search_order`); `rule("id")` references are not built-in calls and are skipped.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{parse_expression, BuiltinsRegistry};
+	///
+	/// let registry = BuiltinsRegistry::builder().with_core().build().unwrap();
+	/// let ast = parse_expression("core.lenght(app.perms) > 2 AND core.len(app.perms) < 9").unwrap();
+	///
+	/// let errors = registry.validate_expression(&ast).unwrap_err();
+	/// assert_eq!(errors[0].to_string(), "Unknown function `core.lenght` (did you mean `core.len`?)");
+	/// ```
+	pub fn validate_expression(&self, ast: &AstNode) -> Result<(), Vec<UnknownFunction>> {
+		let mut calls = FunctionCalls(Vec::new());
+		walk_ast(ast, &mut calls);
+
+		let mut unknown: Vec<UnknownFunction> = Vec::new();
+		for (namespace, name) in calls.0 {
+			let known = match &namespace {
+				Some(namespace) => self.has_function(namespace, &name),
+				None => name == RULE_REFERENCE || self.has_function(self.resolve_unqualified(&name), &name),
+			};
+			if known || unknown.iter().any(|u| u.namespace == namespace && u.name == name) {
+				continue;
+			}
+			let suggestion = self.suggest(namespace.as_deref(), &name);
+			unknown.push(UnknownFunction {
+				namespace,
+				name,
+				suggestion,
+			});
+		}
+
+		match unknown.is_empty() {
+			true => Ok(()),
+			false => Err(unknown),
+		}
+	}
+
+	/// Closest known call for a misspelled one, written the way the caller wrote it
+	fn suggest(&self, namespace: Option<&str>, name: &str) -> Option<String> {
+		let (written, candidates): (String, Vec<String>) = match namespace {
+			Some(namespace) => (
+				format!("{}.{}", namespace.to_lowercase(), name.to_lowercase()),
+				self.providers
+					.iter()
+					.flat_map(|(ns, fns)| fns.keys().map(move |f| format!("{}.{}", ns, f)))
+					.collect(),
+			),
+			None => (
+				name.to_lowercase(),
+				self.search_order
+					.iter()
+					.filter_map(|ns| self.providers.get(ns))
+					.flat_map(|fns| fns.keys().cloned())
+					.collect(),
+			),
+		};
+		let max_distance = (written.chars().count() / 3).max(1);
+		candidates
+			.into_iter()
+			.map(|candidate| (edit_distance(&written, &candidate), candidate))
+			.filter(|(distance, _)| *distance <= max_distance)
+			.min_by(|a, b| a.0.cmp(&b.0))
+			.map(|(_, candidate)| candidate)
+	}
+
+	/// Version declared by the provider of a namespace (see `BuiltinsProvider::version`)
+	pub fn provider_version(&self, namespace: &str) -> Option<&str> {
+		self.versions.get(&namespace.to_lowercase()).map(String::as_str)
+	}
+
+	/// Canonical JSON listing of the built-in surface
+	///
+	/// Each namespace with its provider version (`null` if undeclared) and sorted
+	/// function names, and the search order for unqualified calls, e.g.
+	/// `{"namespaces":{"core":{"functions":["len",...],"version":"0.2.0"}},"search_order":["core"]}`.
+	/// Implementations are not covered; providers signal behavior changes through their version.
+	pub fn snapshot(&self) -> String {
+		let namespaces: serde_json::Map<String, serde_json::Value> = self
+			.providers
+			.iter()
+			.map(|(namespace, functions)| {
+				let functions: Vec<&String> = functions.keys().collect();
+				let version = self.versions.get(namespace);
+				(namespace.clone(), serde_json::json!({ "functions": functions, "version": version }))
+			})
+			.collect();
+		serde_json::to_string(&serde_json::json!({ "namespaces": namespaces, "search_order": self.search_order }))
+			.expect("registry snapshot serialization cannot fail")
+	}
+
+	/// SHA-256 (hex) of `snapshot()`
+	///
+	/// Record it with traces and audit logs (`AuditLog::with_registry`) to pin exactly which
+	/// built-in surface was active during an evaluation.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::BuiltinsRegistry;
+	///
+	/// let core = BuiltinsRegistry::builder().with_core().build().unwrap();
+	/// assert_eq!(core.fingerprint(), BuiltinsRegistry::builder().with_core().build().unwrap().fingerprint());
+	/// assert_ne!(core.fingerprint(), BuiltinsRegistry::new().fingerprint());
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn fingerprint(&self) -> String {
+		crate::audit::sha256_hex(self.snapshot().as_bytes())
+	}
+}
+
+impl core::fmt::Debug for BuiltinsRegistry {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let functions: BTreeMap<&String, Vec<&String>> = self
+			.providers
+			.iter()
+			.map(|(ns, fns)| (ns, fns.keys().collect()))
+			.collect();
+		f.debug_struct("BuiltinsRegistry")
+			.field("functions", &functions)
+			.field("deprecated", &self.deprecated)
+			.field("versions", &self.versions)
+			.field("search_order", &self.search_order)
+			.finish()
+	}
+}
+
+impl Default for BuiltinsRegistry {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Builder registering several providers, collecting every registration error
+#[derive(Default)]
+pub struct BuiltinsRegistryBuilder {
+	registry: BuiltinsRegistry,
+	errors: Vec<String>,
+}
+
+impl BuiltinsRegistryBuilder {
+	/// Register the `core` built-ins (`CoreBuiltinsProvider`)
+	pub fn with_core(self) -> Self {
+		self.with(&CoreBuiltinsProvider)
+	}
+
+	/// Set the search order for unqualified calls (see `BuiltinsRegistry::set_search_order`)
+	pub fn with_search_order(mut self, namespaces: &[&str]) -> Self {
+		self.registry.set_search_order(namespaces);
+		self
+	}
+
+	/// Register a provider (a namespace collision is reported by `build()`)
+	pub fn with(mut self, provider: &dyn BuiltinsProvider) -> Self {
+		if let Err(e) = self.registry.register(provider) {
+			self.errors.push(e);
+		}
+		self
+	}
+
+	/// The registry, or every registration error in registration order
+	pub fn build(self) -> Result<BuiltinsRegistry, RegistrationError> {
+		match self.errors.is_empty() {
+			true => Ok(self.registry),
+			false => Err(RegistrationError { errors: self.errors }),
+		}
+	}
+}
+
+/// Registration errors collected by `BuiltinsRegistryBuilder::build()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistrationError {
+	/// One message per failed registration
+	pub errors: Vec<String>,
+}
+
+impl core::fmt::Display for RegistrationError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "Built-ins registration failed: {}", self.errors.join("; "))
+	}
+}
+
+impl core::error::Error for RegistrationError {}
+
+/// A call to a function the registry does not have (see `BuiltinsRegistry::validate_expression`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFunction {
+	/// Namespace as written (`None` for an unqualified call)
+	pub namespace: Option<String>,
+	/// Function name as written
+	pub name: String,
+	/// Closest known function, written the same way (qualified or not)
+	pub suggestion: Option<String>,
+}
+
+impl core::fmt::Display for UnknownFunction {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match &self.namespace {
+			Some(namespace) => write!(f, "Unknown function `{}.{}`", namespace, self.name)?,
+			None => write!(f, "Unknown function `{}`", self.name)?,
+		}
+		if let Some(suggestion) = &self.suggestion {
+			write!(f, " (did you mean `{}`?)", suggestion)?;
+		}
+		Ok(())
+	}
+}
+
+/// Function calls in an expression, in source order
+struct FunctionCalls(Vec<(Option<String>, String)>);
+
+impl Visitor for FunctionCalls {
+	fn enter_node(&mut self, node: &AstNode) -> Walk {
+		if let AstNode::FunctionCall { namespace, name, .. } = node {
+			self.0.push((namespace.as_deref().map(str::to_string), name.to_string()));
+		}
+		Walk::Continue
+	}
+}
+
+// endregion: --- BuiltinsRegistry
+
+// region:    --- Core Built-ins Provider (Open Implementation)
+
+/// Core built-ins provider for common/open functions
+///
+/// These are generic, product-agnostic functions that are safe to open-source.
+pub struct CoreBuiltinsProvider;
+
+impl CoreBuiltinsProvider {
+	/// Longest string (in characters) accepted by `core.levenshtein` and `core.similarity`
+	/// unless `EvalOptions::with_max_distance_input_len` sets another limit; bounds the
+	/// quadratic cost of a single call, and longer input fails with the structured failure
+	/// code `input_too_long`
+	pub const DEFAULT_MAX_DISTANCE_INPUT_LEN: usize = 256;
+}
+
+impl BuiltinsProvider for CoreBuiltinsProvider {
+	fn namespace(&self) -> &str {
+		"core"
+	}
+
+	fn version(&self) -> Option<&str> {
+		Some(env!("CARGO_PKG_VERSION"))
+	}
+
+	fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+		let mut builtins = BTreeMap::new();
+
+		// core.len(list) - get length of list
+		builtins.insert(
+			"len".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let args = BuiltinArgs::new("core.len", args);
+				match &args.expect_args(1)?[0] {
+					Value::List(list) => Ok(Value::Number(list.len() as f64)),
+					Value::String(s) => Ok(Value::Number(s.len() as f64)),
+					other => Err(args.mismatch("List or String", other)),
+				}
+			}) as BuiltinFn,
+		);
+
+		// core.contains(list, value) - check if list contains value
+		builtins.insert(
+			"contains".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let args = BuiltinArgs::new("core.contains", args);
+				let [container, value] = args.expect_args(2)? else {
+					unreachable!("two arguments")
+				};
+				match container {
+					Value::List(list) => {
+						let result = list.iter().any(|item| values_equal(item, value));
+						Ok(Value::Bool(result))
+					}
+					Value::String(haystack) => match value {
+						Value::String(needle) => Ok(Value::Bool(haystack.contains(&**needle))),
+						_ => Ok(Value::Bool(false)),
+					},
+					other => Err(args.mismatch("List or String", other)),
+				}
+			}) as BuiltinFn,
+		);
+
+		// core.upper(string) - convert to uppercase
+		builtins.insert(
+			"upper".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let args = BuiltinArgs::new("core.upper", args);
+				args.expect_args(1)?;
+				let s = args.expect_string(0)?;
+				Ok(Value::String(s.to_uppercase().into()))
+			}) as BuiltinFn,
+		);
+
+		// core.lower(string) - convert to lowercase
+		builtins.insert(
+			"lower".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let args = BuiltinArgs::new("core.lower", args);
+				args.expect_args(1)?;
+				let s = args.expect_string(0)?;
+				Ok(Value::String(s.to_lowercase().into()))
+			}) as BuiltinFn,
+		);
+
+		// core.union(a, b) - elements of either list
+		builtins.insert(
+			"union".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let (a, b) = set_args("core.union", args)?;
+				Ok(set_of(a.iter().chain(b)))
+			}) as BuiltinFn,
+		);
+
+		// core.intersect(a, b) - elements of a that are also in b
+		builtins.insert(
+			"intersect".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let (a, b) = set_args("core.intersect", args)?;
+				Ok(set_of(a.iter().filter(|item| set_contains(b, item))))
+			}) as BuiltinFn,
+		);
+
+		// core.difference(a, b) - elements of a that are not in b
+		builtins.insert(
+			"difference".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let (a, b) = set_args("core.difference", args)?;
+				Ok(set_of(a.iter().filter(|item| !set_contains(b, item))))
+			}) as BuiltinFn,
+		);
+
+		// core.is_subset(a, b) - check if every element of a is in b
+		builtins.insert(
+			"is_subset".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let (a, b) = set_args("core.is_subset", args)?;
+				Ok(Value::Bool(a.iter().all(|item| set_contains(b, item))))
+			}) as BuiltinFn,
+		);
+
+		// core.approx_eq(a, b, eps) - check if two numbers are within eps of each other
+		builtins.insert(
+			"approx_eq".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let args = BuiltinArgs::new("core.approx_eq", args);
+				args.expect_args(3)?;
+				let (a, b, eps) = (args.expect_number(0)?, args.expect_number(1)?, args.expect_number(2)?);
+				Ok(Value::Bool(numbers_equal(a, b, Some(eps))))
+			}) as BuiltinFn,
+		);
+
+		// core.coalesce(a, b, ...) - first argument that is not null (null if all are)
+		builtins.insert(
+			"coalesce".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let args = BuiltinArgs::new("core.coalesce", args).expect_min_args(1)?;
+				Ok(args.iter().find(|arg| !matches!(arg, Value::Null)).cloned().unwrap_or(Value::Null))
+			}) as BuiltinFn,
+		);
+
+		// core.default(x, fallback) - x, or fallback if x is null
+		builtins.insert(
+			"default".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				match BuiltinArgs::new("core.default", args).expect_args(2)? {
+					[Value::Null, fallback] => Ok(fallback.clone()),
+					[value, _] => Ok(value.clone()),
+					_ => unreachable!("two arguments"),
+				}
+			}) as BuiltinFn,
+		);
+
+		// core.median(list) - middle value of a list of numbers (mean of the two middle values
+		// for an even count); null for an empty list
+		builtins.insert(
+			"median".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let values = sorted_numbers("core.median", args, 1)?;
+				Ok(quantile(&values, 0.5).map_or(Value::Null, Value::Number))
+			}) as BuiltinFn,
+		);
+
+		// core.stddev(list) - population standard deviation of a list of numbers; null for an
+		// empty list
+		builtins.insert(
+			"stddev".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let values = sorted_numbers("core.stddev", args, 1)?;
+				if values.is_empty() {
+					return Ok(Value::Null);
+				}
+				let count = values.len() as f64;
+				let mean = values.iter().sum::<f64>() / count;
+				let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / count;
+				Ok(Value::Number(sqrt(variance)))
+			}) as BuiltinFn,
+		);
+
+		// core.percentile(list, p) - p-th percentile (0 to 100) of a list of numbers, linearly
+		// interpolated between the closest ranks; null for an empty list
+		builtins.insert(
+			"percentile".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let values = sorted_numbers("core.percentile", args, 2)?;
+				let p = match args[1] {
+					Value::Number(p) if (0.0..=100.0).contains(&p) => p,
+					_ => {
+						return Err(EvalError::TypeMismatch {
+							expected: "Number between 0 and 100".to_string(),
+							got: format!("{:?}", args[1]),
+							context: "core.percentile".to_string(),
+						})
+					}
+				};
+				Ok(quantile(&values, p / 100.0).map_or(Value::Null, Value::Number))
+			}) as BuiltinFn,
+		);
+
+		builtins
+	}
+
+	fn get_builtins_with_options(&self) -> BTreeMap<String, BuiltinFnWithOptions> {
+		let mut builtins = BTreeMap::new();
+
+		// core.levenshtein(a, b) - number of single-character edits turning a into b
+		builtins.insert(
+			"levenshtein".to_string(),
+			Arc::new(|args: &[Value], options: &EvalOptions| -> Result<Value, EvalError> {
+				let (a, b) = distance_args("core.levenshtein", args, options)?;
+				Ok(Value::Number(edit_distance(a, b) as f64))
+			}) as BuiltinFnWithOptions,
+		);
+
+		// core.similarity(a, b) - Jaro-Winkler similarity, from 0 (nothing in common) to 1 (equal)
+		builtins.insert(
+			"similarity".to_string(),
+			Arc::new(|args: &[Value], options: &EvalOptions| -> Result<Value, EvalError> {
+				let (a, b) = distance_args("core.similarity", args, options)?;
+				Ok(Value::Number(jaro_winkler(a, b)))
+			}) as BuiltinFnWithOptions,
+		);
+
+		builtins
+	}
+}
+
+// region:    --- Set Helpers
+
+// Lists are treated as sets: elements compare with `==` semantics (as in CONTAINS
+// and IN), duplicates are dropped, and results keep first-appearance order.
+
+/// Check the arguments of a two-list set builtin
+fn set_args<'a>(name: &'a str, args: &'a [Value]) -> Result<(&'a [Value], &'a [Value]), EvalError> {
+	let args = BuiltinArgs::new(name, args);
+	args.expect_args(2)?;
+	Ok((args.expect_list(0)?, args.expect_list(1)?))
+}
+
+fn set_contains(set: &[Value], value: &Value) -> bool {
+	set.iter().any(|item| compare_new_values(item, value, Comparator::Eq))
+}
+
+/// Collect distinct values, in order of first appearance
+fn set_of<'a>(values: impl Iterator<Item = &'a Value>) -> Value {
+	let mut set: Vec<Value> = Vec::new();
+	for value in values {
+		if !set_contains(&set, value) {
+			set.push(value.clone());
+		}
+	}
+	Value::List(set)
+}
+
+// endregion: --- Set Helpers
+
+// region:    --- Statistics Helpers
+
+// Statistics sort their input first, so results do not depend on element order; NaN sorts
+// after every other number (`f64::total_cmp`).
+
+/// Check the arguments of a statistics builtin taking a list of numbers (and `arity - 1`
+/// further arguments), returning the numbers in ascending order
+fn sorted_numbers(name: &str, args: &[Value], arity: usize) -> Result<Vec<f64>, EvalError> {
+	let args = BuiltinArgs::new(name, args);
+	args.expect_args(arity)?;
+	let mut values = args.expect_list_of_numbers(0)?;
+	values.sort_by(f64::total_cmp);
+	Ok(values)
+}
+
+/// Quantile `q` (0 to 1) of ascending `values`: the value at rank `q * (n - 1)`, linearly
+/// interpolated between the two closest ranks
+fn quantile(values: &[f64], q: f64) -> Option<f64> {
+	let last = values.len().checked_sub(1)?;
+	let rank = q * last as f64;
+	let lower = rank as usize;
+	let upper = (lower + 1).min(last);
+	let fraction = rank - lower as f64;
+	if fraction == 0.0 {
+		return Some(values[lower]);
+	}
+	Some(values[lower] + (values[upper] - values[lower]) * fraction)
+}
+
+/// Square root
+#[cfg(feature = "std")]
+fn sqrt(x: f64) -> f64 {
+	x.sqrt()
+}
+
+/// Square root (without `std`, by Newton's method)
+#[cfg(not(feature = "std"))]
+fn sqrt(x: f64) -> f64 {
+	if x.is_nan() || x < 0.0 {
+		return f64::NAN;
+	}
+	if x == 0.0 || x.is_infinite() {
+		return x;
+	}
+	// Starting at or above the root, the iterates decrease until they converge
+	let mut guess = x.max(1.0);
+	loop {
+		let next = 0.5 * (guess + x / guess);
+		if next >= guess {
+			return guess;
+		}
+		guess = next;
+	}
+}
+
+// endregion: --- Statistics Helpers
+
+// region:    --- String Distance Helpers
+
+/// Check the arguments of a string distance builtin, including the input length guard
+fn distance_args<'a>(name: &'a str, args: &'a [Value], options: &EvalOptions) -> Result<(&'a str, &'a str), EvalError> {
+	let args = BuiltinArgs::new(name, args);
+	args.expect_args(2)?;
+	let (a, b) = (args.expect_string(0)?, args.expect_string(1)?);
+	let limit = options.max_distance_input_len.unwrap_or(CoreBuiltinsProvider::DEFAULT_MAX_DISTANCE_INPUT_LEN);
+	for s in [a, b] {
+		let length = s.chars().count();
+		if length > limit {
+			return Err(EvalError::builtin_failure(
+				"input_too_long",
+				[("length", Value::Number(length as f64)), ("limit", Value::Number(limit as f64))],
+			));
+		}
+	}
+	Ok((a, b))
+}
+
+/// Levenshtein distance between two strings (in characters)
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut row: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.chars().enumerate() {
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+		for (j, cb) in b.iter().enumerate() {
+			let substitution = diagonal + usize::from(ca != *cb);
+			diagonal = row[j + 1];
+			row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+		}
+	}
+	row[b.len()]
+}
+
+/// Jaro-Winkler similarity of two strings (in characters): the Jaro similarity, boosted by
+/// 0.1 per character of common prefix (up to 4)
+fn jaro_winkler(a: &str, b: &str) -> f64 {
+	let a: Vec<char> = a.chars().collect();
+	let b: Vec<char> = b.chars().collect();
+	if a.is_empty() && b.is_empty() {
+		return 1.0;
+	}
+
+	// Characters match if equal and at most `window` positions apart
+	let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+	let mut a_matched = vec![false; a.len()];
+	let mut b_matched = vec![false; b.len()];
+	let mut matches = 0;
+	for (i, ca) in a.iter().enumerate() {
+		let end = (i + window + 1).min(b.len());
+		for j in i.saturating_sub(window)..end {
+			if !b_matched[j] && b[j] == *ca {
+				a_matched[i] = true;
+				b_matched[j] = true;
+				matches += 1;
+				break;
+			}
+		}
+	}
+	if matches == 0 {
+		return 0.0;
+	}
+
+	// Transpositions: half the matched characters that appear in a different order
+	let a_order = a.iter().zip(&a_matched).filter(|(_, &m)| m).map(|(c, _)| c);
+	let b_order = b.iter().zip(&b_matched).filter(|(_, &m)| m).map(|(c, _)| c);
+	let transpositions = a_order.zip(b_order).filter(|(x, y)| x != y).count() / 2;
+
+	let m = matches as f64;
+	let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+	let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+	jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+// endregion: --- String Distance Helpers
+
+/// Helper function to compare values for equality
+fn values_equal(a: &Value, b: &Value) -> bool {
+	match (a, b) {
+		(Value::Null, Value::Null) => true,
+		(Value::Bool(a), Value::Bool(b)) => a == b,
+		(Value::String(a), Value::String(b)) => a == b,
+		(Value::Number(a), Value::Number(b)) => a == b,
+		(Value::List(a), Value::List(b)) => {
+			a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| values_equal(x, y))
+		}
+		_ => false,
+	}
+}
+
+// endregion: --- Core Built-ins Provider (Open Implementation)
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_core_len_builtin() {
+		let provider = CoreBuiltinsProvider;
+		let builtins = provider.get_builtins();
+
+		let len_fn = builtins.get("len").expect("len function not found");
+
+		// Test with list
+		let result = len_fn(&[Value::List(vec![Value::Number(1.0), Value::Number(2.0)])]).expect("len failed");
+		assert_eq!(result, Value::Number(2.0));
+
+		// Test with string
+		let result = len_fn(&[Value::String("hello".into())]).expect("len failed");
+		assert_eq!(result, Value::Number(5.0));
+	}
+
+	#[test]
+	fn test_core_contains_builtin() {
+		let provider = CoreBuiltinsProvider;
+		let builtins = provider.get_builtins();
+
+		let contains_fn = builtins.get("contains").expect("contains function not found");
+
+		// Test list contains
+		let list = Value::List(vec![Value::String("a".into()), Value::String("b".into())]);
+		let result = contains_fn(&[list, Value::String("a".into())]).expect("contains failed");
+		assert_eq!(result, Value::Bool(true));
+
+		// Test string contains
+		let result = contains_fn(&[Value::String("hello".into()), Value::String("ell".into())]).expect("contains failed");
+		assert_eq!(result, Value::Bool(true));
+	}
+
+	#[test]
+	fn test_core_set_builtins() {
+		let builtins = CoreBuiltinsProvider.get_builtins();
+		let list = |items: &[&str]| Value::List(items.iter().map(|s| Value::String((*s).into())).collect());
+		let call = |name: &str, a: &[&str], b: &[&str]| builtins[name](&[list(a), list(b)]).expect(name);
+
+		assert_eq!(call("union", &["a", "b", "a"], &["c", "b"]), list(&["a", "b", "c"]));
+		assert_eq!(call("intersect", &["c", "a", "b"], &["b", "c"]), list(&["c", "b"]));
+		assert_eq!(call("difference", &["a", "b", "a"], &["b"]), list(&["a"]));
+		assert_eq!(call("is_subset", &["b", "a"], &["a", "b", "c"]), Value::Bool(true));
+		assert_eq!(call("is_subset", &["a", "d"], &["a", "b"]), Value::Bool(false));
+		assert_eq!(call("is_subset", &[], &[]), Value::Bool(true));
+
+		// Numbers compare as in CONTAINS
+		let numbers = Value::List(vec![Value::Number(1.0), Value::Number(1.0)]);
+		let result = builtins["union"](&[numbers.clone(), Value::List(vec![])]).unwrap();
+		assert_eq!(result, Value::List(vec![Value::Number(1.0)]));
+
+		assert!(matches!(
+			builtins["union"](&[numbers.clone(), Value::String("a".into())]),
+			Err(EvalError::TypeMismatch { .. })
+		));
+		assert!(matches!(
+			builtins["difference"](&[numbers]),
+			Err(EvalError::InvalidOperation(_))
+		));
+	}
+
+	#[test]
+	fn test_core_approx_eq() {
+		let builtins = CoreBuiltinsProvider.get_builtins();
+		let approx_eq = |a: f64, b: f64, eps: f64| {
+			builtins["approx_eq"](&[Value::Number(a), Value::Number(b), Value::Number(eps)]).unwrap()
+		};
+
+		assert_eq!(approx_eq(0.1 + 0.2, 0.3, 1e-9), Value::Bool(true));
+		assert_eq!(approx_eq(0.31, 0.3, 1e-9), Value::Bool(false));
+		assert_eq!(approx_eq(f64::NAN, f64::NAN, 1.0), Value::Bool(false));
+		assert_eq!(approx_eq(f64::INFINITY, f64::INFINITY, 0.0), Value::Bool(true));
+		assert!(builtins["approx_eq"](&[Value::Number(1.0), Value::Number(1.0)]).is_err());
+	}
+
+	#[test]
+	fn test_core_coalesce_and_default() {
+		let builtins = CoreBuiltinsProvider.get_builtins();
+		let call = |name: &str, args: &[Value]| builtins[name](args);
+
+		assert_eq!(
+			call("coalesce", &[Value::Null, Value::Number(0.0), Value::Number(1.0)]).unwrap(),
+			Value::Number(0.0)
+		);
+		assert_eq!(call("coalesce", &[Value::Bool(false), Value::Null]).unwrap(), Value::Bool(false));
+		assert_eq!(call("coalesce", &[Value::Null, Value::Null]).unwrap(), Value::Null);
+		assert!(call("coalesce", &[]).is_err());
+
+		assert_eq!(call("default", &[Value::Null, Value::from("n/a")]).unwrap(), Value::from("n/a"));
+		assert_eq!(call("default", &[Value::from(""), Value::from("n/a")]).unwrap(), Value::from(""));
+		assert!(call("default", &[Value::Null]).is_err());
+	}
+
+	#[test]
+	fn test_core_statistics() {
+		let builtins = CoreBuiltinsProvider.get_builtins();
+		let numbers = |values: &[f64]| Value::List(values.iter().map(|&v| Value::Number(v)).collect());
+		let call = |name: &str, args: &[Value]| builtins[name](args);
+		let latencies = numbers(&[400.0, 100.0, 300.0, 200.0]);
+
+		assert_eq!(call("median", core::slice::from_ref(&latencies)).unwrap(), Value::Number(250.0));
+		assert_eq!(call("median", &[numbers(&[3.0, 1.0, 2.0])]).unwrap(), Value::Number(2.0));
+		let spread = numbers(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+		assert_eq!(call("stddev", &[spread]).unwrap(), Value::Number(2.0));
+		assert_eq!(call("stddev", &[numbers(&[5.0])]).unwrap(), Value::Number(0.0));
+
+		// Linear interpolation between the closest ranks: rank = p / 100 * (n - 1)
+		let percentile = |p: f64| call("percentile", &[latencies.clone(), Value::Number(p)]).unwrap();
+		assert_eq!(percentile(0.0), Value::Number(100.0));
+		assert_eq!(percentile(50.0), Value::Number(250.0));
+		assert_eq!(percentile(95.0), Value::Number(385.0));
+		assert_eq!(percentile(100.0), Value::Number(400.0));
+
+		for name in ["median", "stddev"] {
+			assert_eq!(call(name, &[numbers(&[])]).unwrap(), Value::Null);
+			assert!(call(name, &[Value::List(vec![Value::from("1")])]).is_err());
+		}
+		assert_eq!(call("percentile", &[numbers(&[]), Value::Number(50.0)]).unwrap(), Value::Null);
+		assert!(call("percentile", &[latencies.clone(), Value::Number(101.0)]).is_err());
+		assert!(call("percentile", &[latencies]).is_err());
+	}
+
+	#[test]
+	fn test_core_string_distance() {
+		let builtins = provider_functions(&CoreBuiltinsProvider);
+		let defaults = EvalOptions::default();
+		let call = |name: &str, a: &str, b: &str| builtins[name](&[Value::from(a), Value::from(b)], &defaults);
+		let similarity = |a: &str, b: &str| match call("similarity", a, b).unwrap() {
+			Value::Number(n) => n,
+			other => panic!("expected a number, got {:?}", other),
+		};
+
+		assert_eq!(call("levenshtein", "paypal.com", "paypa1.com").unwrap(), Value::Number(1.0));
+		assert_eq!(call("levenshtein", "kitten", "sitting").unwrap(), Value::Number(3.0));
+		assert_eq!(call("levenshtein", "", "abc").unwrap(), Value::Number(3.0));
+
+		assert!((similarity("MARTHA", "MARHTA") - 0.9611).abs() < 1e-4);
+		assert!((similarity("DIXON", "DICKSONX") - 0.8133).abs() < 1e-4);
+		assert!((similarity("DWAYNE", "DUANE") - 0.84).abs() < 1e-4);
+		assert_eq!(similarity("", ""), 1.0);
+		assert_eq!(similarity("abc", "xyz"), 0.0);
+		assert_eq!(similarity("abc", ""), 0.0);
+
+		let long = "a".repeat(CoreBuiltinsProvider::DEFAULT_MAX_DISTANCE_INPUT_LEN + 1);
+		for name in ["levenshtein", "similarity"] {
+			let err = call(name, &long, "a").unwrap_err();
+			assert!(matches!(err, EvalError::BuiltinFailure { ref code, .. } if code == "input_too_long"));
+			assert!(builtins[name](&[Value::from("a"), Value::Number(1.0)], &defaults).is_err());
+		}
+	}
+
+	#[test]
+	fn test_core_string_distance_limit_from_options() {
+		let registry = BuiltinsRegistry::builder().with_core().build().unwrap();
+		let args = |a: &str, b: &str| [Value::from(a), Value::from(b)];
+		let long = "a".repeat(CoreBuiltinsProvider::DEFAULT_MAX_DISTANCE_INPUT_LEN + 1);
+
+		// A raised limit accepts input over the default
+		let raised = EvalOptions::new().with_max_distance_input_len(1024);
+		let result = registry.call_with_options("core", "levenshtein", &args(&long, ""), &raised);
+		assert_eq!(result.unwrap(), Value::Number(long.len() as f64));
+
+		// A lowered limit rejects input under the default, naming length and limit
+		let lowered = EvalOptions::new().with_max_distance_input_len(4);
+		assert!(registry.call_with_options("core", "similarity", &args("abcd", "abce"), &lowered).is_ok());
+		for name in ["levenshtein", "similarity"] {
+			let err = registry.call_with_options("core", name, &args("abcd", "abcde"), &lowered).unwrap_err();
+			assert_eq!(err.to_string(), format!("Built-in `core.{}` failed with `input_too_long` (length: 5, limit: 4)", name));
+		}
+
+		// Evaluation passes its options to the call
+		let ctx = crate::FactsEvalContext::new();
+		let rule = r#"core.levenshtein("kitten", "sitting") == 3"#;
+		assert!(crate::evaluate_with_options(rule, &ctx, Some(&registry), &EvalOptions::new()).unwrap());
+		let err = crate::evaluate_with_options(rule, &ctx, Some(&registry), &lowered).unwrap_err();
+		assert!(matches!(err, EvalError::BuiltinFailed { ref source, .. }
+			if matches!(**source, EvalError::BuiltinFailure { ref code, .. } if code == "input_too_long")));
+	}
+
+	#[test]
+	fn test_core_upper_lower() {
+		let provider = CoreBuiltinsProvider;
+		let builtins = provider.get_builtins();
+
+		let upper_fn = builtins.get("upper").expect("upper not found");
+		let lower_fn = builtins.get("lower").expect("lower not found");
+
+		let result = upper_fn(&[Value::String("hello".into())]).expect("upper failed");
+		assert_eq!(result, Value::String("HELLO".into()));
+
+		let result = lower_fn(&[Value::String("WORLD".into())]).expect("lower failed");
+		assert_eq!(result, Value::String("world".into()));
+	}
+
+	#[test]
+	fn test_builtins_registry() {
+		let mut registry = BuiltinsRegistry::new();
+
+		// Register core provider
+		let provider = CoreBuiltinsProvider;
+		registry.register(&provider).expect("registration failed");
+
+		// Test function call
+		let result = registry
+			.call("core", "len", &[Value::List(vec![Value::Number(1.0)])])
+			.expect("call failed");
+		assert_eq!(result, Value::Number(1.0));
+
+		// Test namespace listing
+		let namespaces = registry.namespaces();
+		assert_eq!(namespaces, vec!["core"]);
+
+		// Test function listing
+		let functions = registry.functions_in_namespace("core").expect("functions not found");
+		assert!(functions.contains(&"len".to_string()));
+		assert!(functions.contains(&"contains".to_string()));
+	}
+
+	#[test]
+	fn test_custom_builtin_provider() {
+		struct TestProvider;
+
+		impl BuiltinsProvider for TestProvider {
+			fn namespace(&self) -> &str {
+				"test"
+			}
+
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				let mut builtins = BTreeMap::new();
+
+				// test.add(a, b)
+				builtins.insert(
+					"add".to_string(),
+					Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+						if args.len() != 2 {
+							return Err(EvalError::InvalidOperation("test.add expects 2 arguments".to_string()));
+						}
+
+						match (&args[0], &args[1]) {
+							(Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+							_ => Err(EvalError::TypeMismatch {
+								expected: "Number".to_string(),
+								got: "other".to_string(),
+								context: "test.add".to_string(),
+							}),
+						}
+					}) as BuiltinFn,
+				);
+
+				builtins
+			}
+		}
+
+		let mut registry = BuiltinsRegistry::new();
+		let provider = TestProvider;
+		registry.register(&provider).expect("registration failed");
+
+		let result = registry.call("test", "add", &[Value::Number(1.0), Value::Number(2.0)]).expect("call failed");
+		assert_eq!(result, Value::Number(3.0));
+	}
+
+	#[test]
+	fn test_registry_builder() {
+		struct Named(&'static str);
+
+		impl BuiltinsProvider for Named {
+			fn namespace(&self) -> &str {
+				self.0
+			}
+
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				BTreeMap::new()
+			}
+		}
+
+		let registry = BuiltinsRegistry::builder()
+			.with_core()
+			.with(&Named("acme"))
+			.build()
+			.expect("build failed");
+		assert_eq!(registry.namespaces(), ["acme", "core"]);
+
+		let err = BuiltinsRegistry::builder()
+			.with(&Named("acme"))
+			.with(&Named("ACME"))
+			.with_core()
+			.with_core()
+			.build()
+			.unwrap_err();
+		assert_eq!(
+			err.errors,
+			[
+				"Namespace 'acme' is already registered",
+				"Namespace 'core' is already registered"
+			]
+		);
+		let message = err.to_string();
+		assert!(message.starts_with("Built-ins registration failed: Namespace 'acme'"));
+	}
+
+	#[test]
+	fn test_registry_snapshot_and_fingerprint() {
+		struct Versioned(&'static str, &'static [&'static str]);
+
+		impl BuiltinsProvider for Versioned {
+			fn namespace(&self) -> &str {
+				"acme"
+			}
+
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				self.1
+					.iter()
+					.map(|name| (name.to_string(), Arc::new(|_: &[Value]| Ok(Value::Null)) as BuiltinFn))
+					.collect()
+			}
+
+			fn version(&self) -> Option<&str> {
+				Some(self.0)
+			}
+		}
+
+		let build = |provider: &Versioned| BuiltinsRegistry::builder().with(provider).build().unwrap();
+		let registry = build(&Versioned("1.0.0", &["score", "lookup"]));
+		assert_eq!(registry.provider_version("ACME"), Some("1.0.0"));
+		assert_eq!(
+			registry.snapshot(),
+			r#"{"namespaces":{"acme":{"functions":["lookup","score"],"version":"1.0.0"}},"search_order":["core"]}"#
+		);
+
+		#[cfg(feature = "std")]
+		{
+			let fingerprint = registry.fingerprint();
+			assert_eq!(fingerprint.len(), 64);
+			assert_eq!(fingerprint, build(&Versioned("1.0.0", &["lookup", "score"])).fingerprint());
+			assert_ne!(fingerprint, build(&Versioned("1.0.1", &["score", "lookup"])).fingerprint());
+			assert_ne!(fingerprint, build(&Versioned("1.0.0", &["score"])).fingerprint());
+		}
+	}
+
+	#[test]
+	fn test_unregister_and_scoped() {
+		struct Tenant;
+
+		impl BuiltinsProvider for Tenant {
+			fn namespace(&self) -> &str {
+				"tenant"
+			}
+
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				let mut builtins = BTreeMap::new();
+				builtins.insert("tier".to_string(), Arc::new(|_: &[Value]| Ok(Value::from("gold"))) as BuiltinFn);
+				builtins
+			}
+		}
+
+		let mut base = BuiltinsRegistry::builder().with_core().build().unwrap();
+		base.deprecate("core", "len", "use core.count");
+
+		let mut child = base.scoped();
+		assert!(Arc::ptr_eq(&base.providers, &child.providers));
+		child.register(&Tenant).unwrap();
+		assert!(!Arc::ptr_eq(&base.providers, &child.providers));
+		assert_eq!(child.call("tenant", "tier", &[]).unwrap(), Value::from("gold"));
+		assert!(!base.has_function("tenant", "tier"));
+
+		assert!(child.unregister("CORE"));
+		assert!(!child.unregister("core"));
+		assert_eq!(child.namespaces(), ["tenant"]);
+		assert_eq!(child.deprecation("core", "len"), None);
+		assert_eq!(child.provider_version("core"), None);
+		assert_eq!(base.namespaces(), ["core"]);
+		assert_eq!(base.deprecation("core", "len"), Some("use core.count"));
+	}
+
+	#[test]
+	fn test_validate_expression() {
+		use crate::parse_expression;
+
+		let mut registry = BuiltinsRegistry::builder().with_core().build().unwrap();
+		let validate = |registry: &BuiltinsRegistry, src: &str| {
+			registry
+				.validate_expression(&parse_expression(src).unwrap())
+				.map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())
+		};
+
+		assert_eq!(validate(&registry, r#"core.len(app.perms) > 1 AND upper(app.name) == "A""#), Ok(()));
+		assert_eq!(validate(&registry, r#"rule("other") AND core.LEN(app.perms) > 1"#), Ok(()));
+		assert_eq!(
+			validate(
+				&registry,
+				"core.lenn(app.perms) > 1 OR core.len(acme.scor(core.lenn(app.perms))) > 1 OR entropy(app.data) > 7"
+			),
+			Err(vec![
+				"Unknown function `core.lenn` (did you mean `core.len`?)".to_string(),
+				"Unknown function `acme.scor`".to_string(),
+				"Unknown function `entropy`".to_string(),
+			])
+		);
+
+		// Suggestions for unqualified calls come from the search order
+		registry.set_search_order(&["acme", "core"]);
+		assert_eq!(
+			validate(&registry, "uper(app.name) == 1"),
+			Err(vec!["Unknown function `uper` (did you mean `upper`?)".to_string()])
+		);
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+	}
+
+	#[test]
+	fn test_structured_builtin_failure() {
+		use crate::{evaluate_with_context, FactsEvalContext};
+
+		struct Strict;
+
+		impl BuiltinsProvider for Strict {
+			fn namespace(&self) -> &str {
+				"strict"
+			}
+
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				let mut builtins = BTreeMap::new();
+				builtins.insert(
+					"check".to_string(),
+					Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+						Err(EvalError::builtin_failure(
+							"limit_exceeded",
+							[("limit", Value::Number(3.0)), ("got", Value::Number(args.len() as f64))],
+						))
+					}) as BuiltinFn,
+				);
+				builtins
+			}
+		}
+
+		let registry = BuiltinsRegistry::builder().with(&Strict).build().unwrap();
+		let err = registry.call("Strict", "CHECK", &[]).unwrap_err();
+		assert!(matches!(
+			&err,
+			EvalError::BuiltinFailure { namespace, name, code, details }
+				if namespace == "strict" && name == "check" && code == "limit_exceeded"
+					&& details.get("limit") == Some(&Value::Number(3.0))
+		));
+		assert_eq!(
+			err.to_string(),
+			"Built-in `strict.check` failed with `limit_exceeded` (got: 0, limit: 3)"
+		);
+
+		// Evaluation adds the call site; `cause()` recovers the structured failure
+		let err = evaluate_with_context("strict.check(1) == true", &FactsEvalContext::new(), &registry).unwrap_err();
+		assert!(matches!(err, EvalError::BuiltinFailed { .. }));
+		assert!(matches!(err.cause(), EvalError::BuiltinFailure { code, .. } if code == "limit_exceeded"));
+	}
+
+	#[test]
+	fn test_builtin_args() {
+		let values = [Value::from("a"), Value::Number(2.0), Value::List(vec![Value::Number(1.0), Value::Null])];
+		let args = BuiltinArgs::new("acme.f", &values);
+
+		assert_eq!(args.expect_args(3).unwrap().len(), 3);
+		assert_eq!(args.expect_args(1).unwrap_err().to_string(), "Invalid operation: acme.f expects 1 argument");
+		assert_eq!(
+			args.expect_min_args(4).unwrap_err().to_string(),
+			"Invalid operation: acme.f expects at least 4 arguments"
+		);
+		assert_eq!(args.expect_string(0).unwrap(), "a");
+		assert_eq!(args.expect_number(1).unwrap(), 2.0);
+		assert_eq!(args.expect_list(2).unwrap().len(), 2);
+		assert!(args.expect_value(3).is_err());
+
+		let err = args.expect_list_of_numbers(2).unwrap_err();
+		assert!(matches!(
+			err,
+			EvalError::TypeMismatch { ref expected, ref got, ref context }
+				if expected == "List of Number" && got == "Null" && context == "acme.f"
+		));
+		// Same text as the `Value::try_*` checks
+		assert_eq!(
+			args.expect_bool(0).unwrap_err().to_string(),
+			values[0].try_bool("acme.f").unwrap_err().to_string()
+		);
+	}
+
+	#[test]
+	fn test_namespace_collision() {
+		struct Provider1;
+		impl BuiltinsProvider for Provider1 {
+			fn namespace(&self) -> &str {
+				"test"
+			}
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				BTreeMap::new()
+			}
+		}
+
+		struct Provider2;
+		impl BuiltinsProvider for Provider2 {
+			fn namespace(&self) -> &str {
+				"test"
+			}
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				BTreeMap::new()
+			}
+		}
+
+		let mut registry = BuiltinsRegistry::new();
+		let p1 = Provider1;
+		let p2 = Provider2;
+
+		registry.register(&p1).expect("first registration failed");
+		let result = registry.register(&p2);
+		assert!(result.is_err());
+		assert!(result.unwrap_err().contains("already registered"));
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,585 @@
+//! Conformance harness for built-in providers
+//!
+//! Every `BuiltinsProvider` promises pure, deterministic functions that report bad input
+//! as an `EvalError` instead of panicking. `ProviderHarness` exercises a provider against
+//! that contract, so product teams can certify closed providers in their own test suites.
+//!
+//! ## Checks
+//! - Naming: the namespace and every function name are lowercase identifiers (calls are
+//!   normalized to lowercase, so anything else is unreachable), and a version is declared
+//! - Panic freedom: every function is called with a fixed sample of edge-case values
+//!   (NaN, infinities, empty and long strings, nested lists and maps) and with
+//!   pseudo-random values, for every argument count up to `with_max_arity`
+//! - Determinism: every call is repeated, once against a second function table,
+//!   and the results (or errors) must render identically
+//! - Arity: functions declared with `with_arity` must fail for every other argument count
+//!   and succeed for at least one input at each declared count
+//!
+//! Inputs are generated from a seed, so a failing report reproduces exactly. Panics are
+//! caught, but still go through the panic hook (and so print to stderr).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+use super::{provider_functions, BuiltinFnWithOptions, BuiltinsProvider};
+use crate::{EvalError, EvalOptions, Value};
+
+/// Sample argument lists per argument count beyond which combinations are drawn at random
+const MAX_COMBINATIONS: usize = 1024;
+
+// region:    --- Violation
+
+/// A breach of the provider contract found by `ProviderHarness`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+	/// The namespace or a function name is not a lowercase identifier
+	InvalidName {
+		/// The offending name
+		name: String,
+	},
+	/// The provider declares no version, so registry fingerprints cannot tell releases apart
+	MissingVersion,
+	/// A call panicked
+	Panic {
+		/// Qualified function name (`namespace.function`)
+		function: String,
+		/// Arguments of the call
+		args: Vec<Value>,
+		/// Panic message, if it was a string
+		message: String,
+	},
+	/// Repeating a call gave a different result or error
+	NonDeterministic {
+		/// Qualified function name
+		function: String,
+		/// Arguments of the call
+		args: Vec<Value>,
+		/// Rendering of the first call's outcome
+		first: String,
+		/// Rendering of the diverging call's outcome
+		second: String,
+	},
+	/// A call with an undeclared argument count succeeded
+	ArityAccepted {
+		/// Qualified function name
+		function: String,
+		/// Arguments of the call
+		args: Vec<Value>,
+	},
+	/// No input succeeded at a declared argument count
+	ArityRejected {
+		/// Qualified function name
+		function: String,
+		/// The declared argument count
+		count: usize,
+	},
+	/// `with_arity` named a function the provider does not have
+	UnknownFunction {
+		/// Qualified function name
+		function: String,
+	},
+}
+
+impl fmt::Display for Violation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Violation::InvalidName { name } => write!(f, "`{}` is not a lowercase identifier", name),
+			Violation::MissingVersion => write!(f, "Provider declares no version"),
+			Violation::Panic { function, args, message } => {
+				write!(f, "`{}` panicked on {:?}: {}", function, args, message)
+			}
+			Violation::NonDeterministic { function, args, first, second } => write!(
+				f,
+				"`{}` is non-deterministic on {:?}: first {}, then {}",
+				function, args, first, second
+			),
+			Violation::ArityAccepted { function, args } => {
+				write!(f, "`{}` accepted an undeclared argument count: {:?}", function, args)
+			}
+			Violation::ArityRejected { function, count } => {
+				write!(f, "`{}` rejected every input with {} argument(s)", function, count)
+			}
+			Violation::UnknownFunction { function } => write!(f, "`{}` is not provided", function),
+		}
+	}
+}
+
+// endregion: --- Violation
+
+// region:    --- ConformanceReport
+
+/// Outcome of `ProviderHarness::run`
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+	/// Functions exercised
+	pub functions: usize,
+	/// Calls made, repeats included
+	pub calls: usize,
+	/// Contract breaches, at most one per function and kind
+	pub violations: Vec<Violation>,
+}
+
+impl ConformanceReport {
+	/// Whether no violation was found
+	pub fn is_conformant(&self) -> bool {
+		self.violations.is_empty()
+	}
+}
+
+impl fmt::Display for ConformanceReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} function(s), {} call(s), {} violation(s)",
+			self.functions,
+			self.calls,
+			self.violations.len()
+		)?;
+		for violation in &self.violations {
+			write!(f, "\n- {}", violation)?;
+		}
+		Ok(())
+	}
+}
+
+// endregion: --- ConformanceReport
+
+// region:    --- ProviderHarness
+
+/// Checks a provider against the built-ins contract
+///
+/// # Examples
+///
+/// ```
+/// use hel::builtins::testing::ProviderHarness;
+/// use hel::CoreBuiltinsProvider;
+///
+/// let report = ProviderHarness::new(&CoreBuiltinsProvider)
+///     .with_arity("len", 1..=1)
+///     .with_arity("coalesce", 1..=usize::MAX)
+///     .run();
+/// assert!(report.is_conformant(), "{}", report);
+/// ```
+pub struct ProviderHarness<'a> {
+	provider: &'a dyn BuiltinsProvider,
+	arities: BTreeMap<String, RangeInclusive<usize>>,
+	max_arity: usize,
+	random_cases: usize,
+	repeats: usize,
+	seed: u64,
+}
+
+impl<'a> ProviderHarness<'a> {
+	/// Harness for `provider`: up to 3 arguments, 64 random inputs per argument count,
+	/// every call made 3 times
+	pub fn new(provider: &'a dyn BuiltinsProvider) -> Self {
+		Self {
+			provider,
+			arities: BTreeMap::new(),
+			max_arity: 3,
+			random_cases: 64,
+			repeats: 3,
+			seed: 0x5eed,
+		}
+	}
+
+	/// Declare the argument counts `function` (unqualified) accepts
+	pub fn with_arity(mut self, function: &str, counts: RangeInclusive<usize>) -> Self {
+		self.arities.insert(function.to_lowercase(), counts);
+		self
+	}
+
+	/// Highest argument count tried
+	pub fn with_max_arity(mut self, max_arity: usize) -> Self {
+		self.max_arity = max_arity;
+		self
+	}
+
+	/// Pseudo-random inputs tried per argument count, on top of the fixed samples
+	pub fn with_random_cases(mut self, cases: usize) -> Self {
+		self.random_cases = cases;
+		self
+	}
+
+	/// Times each call is made (the last against a second function table); at least 2
+	pub fn with_repeats(mut self, repeats: usize) -> Self {
+		self.repeats = repeats.max(2);
+		self
+	}
+
+	/// Seed of the pseudo-random inputs
+	pub fn with_seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self
+	}
+
+	/// Run every check
+	pub fn run(&self) -> ConformanceReport {
+		let namespace = self.provider.namespace();
+		let first = provider_functions(self.provider);
+		let second = provider_functions(self.provider);
+		let mut report = ConformanceReport {
+			functions: first.len(),
+			..ConformanceReport::default()
+		};
+
+		for name in core::iter::once(namespace).chain(first.keys().map(String::as_str)) {
+			if !is_lowercase_identifier(name) {
+				report.violations.push(Violation::InvalidName { name: name.to_string() });
+			}
+		}
+		if self.provider.version().is_none() {
+			report.violations.push(Violation::MissingVersion);
+		}
+		for name in self.arities.keys().filter(|name| !first.contains_key(*name)) {
+			report.violations.push(Violation::UnknownFunction {
+				function: format!("{}.{}", namespace, name),
+			});
+		}
+
+		for (name, func) in &first {
+			let function = format!("{}.{}", namespace, name);
+			let declared = self.arities.get(name);
+			// Always reach the smallest declared count, even past `max_arity`
+			let max_count = declared.map_or(self.max_arity, |counts| self.max_arity.max(*counts.start()));
+			let mut checker = FunctionChecker {
+				function,
+				func,
+				twin: second.get(name),
+				repeats: self.repeats,
+				report: &mut report,
+				panicked: false,
+				diverged: false,
+			};
+			let mut accepted_undeclared = false;
+			for count in 0..=max_count {
+				let mut succeeded = false;
+				for args in self.inputs(count) {
+					let Some(ok) = checker.check(&args) else {
+						continue;
+					};
+					succeeded |= ok;
+					let undeclared = declared.is_some_and(|counts| !counts.contains(&count));
+					if ok && undeclared && !accepted_undeclared {
+						accepted_undeclared = true;
+						checker.report.violations.push(Violation::ArityAccepted {
+							function: checker.function.clone(),
+							args,
+						});
+					}
+				}
+				if !succeeded && declared.is_some_and(|counts| counts.contains(&count)) {
+					checker.report.violations.push(Violation::ArityRejected {
+						function: checker.function.clone(),
+						count,
+					});
+				}
+			}
+		}
+
+		report
+	}
+
+	/// Argument lists with `count` arguments: sample combinations, then random values
+	fn inputs(&self, count: usize) -> Vec<Vec<Value>> {
+		let samples = sample_values();
+		let mut rng = Rng::new(self.seed ^ count as u64);
+		let mut inputs = Vec::new();
+
+		let combinations = u32::try_from(count).ok().and_then(|count| samples.len().checked_pow(count));
+		match combinations {
+			Some(total) if total <= MAX_COMBINATIONS => {
+				for mut index in 0..total {
+					let mut args = Vec::with_capacity(count);
+					for _ in 0..count {
+						args.push(samples[index % samples.len()].clone());
+						index /= samples.len();
+					}
+					inputs.push(args);
+				}
+			}
+			_ => {
+				for _ in 0..MAX_COMBINATIONS {
+					inputs.push((0..count).map(|_| samples[rng.below(samples.len())].clone()).collect());
+				}
+			}
+		}
+
+		for _ in 0..self.random_cases {
+			inputs.push((0..count).map(|_| random_value(&mut rng, 2)).collect());
+		}
+		inputs
+	}
+}
+
+/// Calls one function, recording its first panic and first divergence
+struct FunctionChecker<'r> {
+	function: String,
+	func: &'r BuiltinFnWithOptions,
+	twin: Option<&'r BuiltinFnWithOptions>,
+	repeats: usize,
+	report: &'r mut ConformanceReport,
+	panicked: bool,
+	diverged: bool,
+}
+
+impl FunctionChecker<'_> {
+	/// Whether the call succeeded, or `None` if it panicked
+	fn check(&mut self, args: &[Value]) -> Option<bool> {
+		let first = self.call(self.func, args)?;
+		for repeat in 1..self.repeats {
+			let func = match self.twin {
+				Some(twin) if repeat == self.repeats - 1 => twin,
+				_ => self.func,
+			};
+			let again = self.call(func, args)?;
+			let (first_text, again_text) = (render(&first), render(&again));
+			if first_text != again_text && !self.diverged {
+				self.diverged = true;
+				self.report.violations.push(Violation::NonDeterministic {
+					function: self.function.clone(),
+					args: args.to_vec(),
+					first: first_text,
+					second: again_text,
+				});
+			}
+		}
+		Some(first.is_ok())
+	}
+
+	fn call(&mut self, func: &BuiltinFnWithOptions, args: &[Value]) -> Option<Result<Value, EvalError>> {
+		self.report.calls += 1;
+		match catch_unwind(AssertUnwindSafe(|| func(args, &EvalOptions::default()))) {
+			Ok(result) => Some(result),
+			Err(payload) => {
+				if !self.panicked {
+					self.panicked = true;
+					let message = payload
+						.downcast_ref::<&str>()
+						.map(|s| s.to_string())
+						.or_else(|| payload.downcast_ref::<String>().cloned())
+						.unwrap_or_else(|| "non-string panic payload".to_string());
+					self.report.violations.push(Violation::Panic {
+						function: self.function.clone(),
+						args: args.to_vec(),
+						message,
+					});
+				}
+				None
+			}
+		}
+	}
+}
+
+// endregion: --- ProviderHarness
+
+// region:    --- Inputs
+
+/// Edge-case values every function is called with
+fn sample_values() -> Vec<Value> {
+	let list = Value::List;
+	let mut map = BTreeMap::new();
+	map.insert(Arc::from("a"), Value::Number(1.0));
+	map.insert(Arc::from("b"), Value::from("x"));
+	vec![
+		Value::Null,
+		Value::Bool(true),
+		Value::Bool(false),
+		Value::Number(0.0),
+		Value::Number(-0.0),
+		Value::Number(1.0),
+		Value::Number(-1.0),
+		Value::Number(0.5),
+		Value::Number(50.0),
+		Value::Number(255.0),
+		Value::Number(1e308),
+		Value::Number(f64::NAN),
+		Value::Number(f64::INFINITY),
+		Value::Number(f64::NEG_INFINITY),
+		Value::from(""),
+		Value::from("a"),
+		Value::from("Hello, World"),
+		Value::from("é漢🙂\0"),
+		Value::from("https://user@Example.com:8080/path?q=1"),
+		Value::from("a".repeat(1024).as_str()),
+		list(vec![]),
+		list(vec![Value::Number(1.0), Value::Number(2.0), Value::Number(3.0)]),
+		list(vec![Value::Null, Value::from("a"), Value::Bool(true)]),
+		list(vec![list(vec![Value::Number(1.0)])]),
+		Value::Map(BTreeMap::new()),
+		Value::Map(map),
+	]
+}
+
+/// Random value nested at most `depth` levels
+fn random_value(rng: &mut Rng, depth: usize) -> Value {
+	let kinds = if depth == 0 { 4 } else { 6 };
+	match rng.below(kinds) {
+		0 => Value::Null,
+		1 => Value::Bool(rng.below(2) == 1),
+		2 => Value::Number(match rng.below(3) {
+			0 => rng.below(512) as f64 - 256.0,
+			1 => f64::from_bits(rng.next()),
+			_ => rng.below(1000) as f64 / 7.0,
+		}),
+		3 => Value::String((0..rng.below(24)).map(|_| random_char(rng)).collect::<String>().into()),
+		4 => Value::List((0..rng.below(5)).map(|_| random_value(rng, depth - 1)).collect()),
+		_ => Value::Map(
+			(0..rng.below(4))
+				.map(|i| (Arc::from(format!("k{}", i)), random_value(rng, depth - 1)))
+				.collect(),
+		),
+	}
+}
+
+fn random_char(rng: &mut Rng) -> char {
+	match rng.below(4) {
+		0 => char::from_u32(rng.below(0x11_0000) as u32).unwrap_or('\u{fffd}'),
+		_ => char::from(b' ' + rng.below(95) as u8),
+	}
+}
+
+/// Outcome rendering compared by the determinism check (`Debug` shows NaN and error variants)
+fn render(outcome: &Result<Value, EvalError>) -> String {
+	format!("{:?}", outcome)
+}
+
+fn is_lowercase_identifier(name: &str) -> bool {
+	let mut chars = name.chars();
+	chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+		&& chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// xorshift64*, enough to spread inputs without pulling in a dependency
+struct Rng(u64);
+
+impl Rng {
+	fn new(seed: u64) -> Self {
+		Self(seed | 1)
+	}
+
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+	}
+
+	/// Uniform-enough value in `0..n`
+	fn below(&mut self, n: usize) -> usize {
+		(self.next() % n as u64) as usize
+	}
+}
+
+// endregion: --- Inputs
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::builtins::BuiltinFn;
+	use crate::CoreBuiltinsProvider;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[test]
+	fn test_core_provider_conforms() {
+		let report = ProviderHarness::new(&CoreBuiltinsProvider)
+			.with_arity("len", 1..=1)
+			.with_arity("contains", 2..=2)
+			.with_arity("default", 2..=2)
+			.with_arity("coalesce", 1..=usize::MAX)
+			.with_arity("percentile", 2..=2)
+			.with_arity("levenshtein", 2..=2)
+			.with_arity("similarity", 2..=2)
+			.run();
+		assert!(report.is_conformant(), "{}", report);
+		assert!(report.calls > report.functions * 1000);
+
+		let report = ProviderHarness::new(&crate::UrlBuiltinsProvider::new()).with_arity("host", 1..=1).run();
+		assert!(report.is_conformant(), "{}", report);
+		#[cfg(feature = "byte-stats")]
+		{
+			let report = ProviderHarness::new(&crate::ByteStatsProvider).with_arity("shannon_entropy", 1..=1).run();
+			assert!(report.is_conformant(), "{}", report);
+		}
+	}
+
+	#[test]
+	fn test_violations_reported() {
+		struct Broken(AtomicUsize);
+
+		impl BuiltinsProvider for Broken {
+			fn namespace(&self) -> &str {
+				"broken"
+			}
+
+			fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+				let calls = Arc::new(AtomicUsize::new(self.0.fetch_add(1, Ordering::SeqCst)));
+				let mut builtins = BTreeMap::new();
+				builtins.insert(
+					"first".to_string(),
+					Arc::new(|args: &[Value]| -> Result<Value, EvalError> { Ok(args[0].clone()) }) as BuiltinFn,
+				);
+				builtins.insert(
+					"counter".to_string(),
+					Arc::new(move |_: &[Value]| -> Result<Value, EvalError> {
+						Ok(Value::Number(calls.fetch_add(1, Ordering::SeqCst) as f64))
+					}) as BuiltinFn,
+				);
+				builtins.insert(
+					"Loose".to_string(),
+					Arc::new(|_: &[Value]| -> Result<Value, EvalError> { Ok(Value::Null) }) as BuiltinFn,
+				);
+				builtins.insert(
+					"lenient".to_string(),
+					Arc::new(|_: &[Value]| -> Result<Value, EvalError> { Ok(Value::Null) }) as BuiltinFn,
+				);
+				builtins
+			}
+		}
+
+		let report = ProviderHarness::new(&Broken(AtomicUsize::new(0)))
+			.with_arity("lenient", 1..=1)
+			.with_arity("missing", 0..=0)
+			.with_max_arity(1)
+			.with_random_cases(4)
+			.run();
+
+		assert!(report.violations.contains(&Violation::InvalidName { name: "Loose".to_string() }));
+		assert!(report.violations.contains(&Violation::MissingVersion));
+		assert!(report.violations.contains(&Violation::UnknownFunction {
+			function: "broken.missing".to_string()
+		}));
+		assert!(report.violations.contains(&Violation::ArityAccepted {
+			function: "broken.lenient".to_string(),
+			args: vec![]
+		}));
+		assert!(matches!(
+			report.violations.iter().find(|v| matches!(v, Violation::Panic { .. })),
+			Some(Violation::Panic { function, args, message })
+				if function == "broken.first" && args.is_empty() && message.contains("out of bounds")
+		));
+		let divergences = report
+			.violations
+			.iter()
+			.filter(|v| matches!(v, Violation::NonDeterministic { function, .. } if function == "broken.counter"))
+			.count();
+		assert_eq!(divergences, 1);
+		assert!(!report.is_conformant());
+	}
+
+	#[test]
+	fn test_inputs_are_reproducible() {
+		let harness = ProviderHarness::new(&CoreBuiltinsProvider).with_seed(7);
+		let render = |inputs: Vec<Vec<Value>>| format!("{:?}", inputs);
+		assert_eq!(harness.inputs(2).len(), 26 * 26 + 64);
+		assert_eq!(render(harness.inputs(3)), render(harness.inputs(3)));
+		assert_ne!(render(harness.inputs(3)), render(harness.with_seed(8).inputs(3)));
+	}
+}
+
+// endregion: --- Tests
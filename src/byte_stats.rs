@@ -0,0 +1,173 @@
+//! Byte-statistics built-ins (`bin` namespace)
+//!
+//! Obfuscation heuristics (packed scripts, base64 blobs, encrypted payloads) look at
+//! how bytes are distributed. `ByteStatsProvider` lets rules compute that on small
+//! payloads passed as facts instead of requiring the host to precompute every metric.
+//!
+//! ## Functions
+//! - `bin.shannon_entropy(data)`: Shannon entropy in bits per byte, from 0 (one repeated
+//!   byte, or empty) to 8 (every byte value equally often)
+//! - `bin.printable_ratio(data)`: fraction of bytes that are printable ASCII (space to `~`,
+//!   tab, line feed, carriage return), or null for empty input
+//!
+//! `data` is a string (its UTF-8 bytes) or a list of numbers from 0 to 255. Inputs longer
+//! than `ByteStatsProvider::MAX_INPUT_LEN` bytes fail with the structured code `input_too_long`.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::builtins::{BuiltinArgs, BuiltinFn, BuiltinsProvider};
+use crate::prelude::*;
+use crate::{EvalError, Value};
+
+// region:    --- ByteStatsProvider
+
+/// Provider of the `bin` namespace
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_with_context, BuiltinsRegistry, ByteStatsProvider, FactsEvalContext, Value};
+///
+/// let builtins = BuiltinsRegistry::builder().with(&ByteStatsProvider).build().unwrap();
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("script.body", Value::String("aaaabbbb".into()));
+///
+/// let rule = "bin.shannon_entropy(script.body) == 1 AND bin.printable_ratio(script.body) == 1";
+/// assert!(evaluate_with_context(rule, &facts, &builtins).unwrap());
+/// ```
+pub struct ByteStatsProvider;
+
+impl ByteStatsProvider {
+	/// Longest input (in bytes) accepted by the `bin` functions
+	pub const MAX_INPUT_LEN: usize = 64 * 1024;
+}
+
+impl BuiltinsProvider for ByteStatsProvider {
+	fn namespace(&self) -> &str {
+		"bin"
+	}
+
+	fn version(&self) -> Option<&str> {
+		Some(env!("CARGO_PKG_VERSION"))
+	}
+
+	fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+		let mut builtins = BTreeMap::new();
+
+		// bin.shannon_entropy(data) - bits per byte, 0 to 8
+		builtins.insert(
+			"shannon_entropy".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let bytes = bytes_arg("bin.shannon_entropy", args)?;
+				Ok(Value::Number(shannon_entropy(&bytes)))
+			}) as BuiltinFn,
+		);
+
+		// bin.printable_ratio(data) - fraction of printable ASCII bytes, null if empty
+		builtins.insert(
+			"printable_ratio".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let bytes = bytes_arg("bin.printable_ratio", args)?;
+				if bytes.is_empty() {
+					return Ok(Value::Null);
+				}
+				let printable = bytes.iter().filter(|&&b| matches!(b, b' '..=b'~' | b'\t' | b'\n' | b'\r')).count();
+				Ok(Value::Number(printable as f64 / bytes.len() as f64))
+			}) as BuiltinFn,
+		);
+
+		builtins
+	}
+}
+
+// endregion: --- ByteStatsProvider
+
+// region:    --- Byte Helpers
+
+/// Check the single data argument of a `bin` builtin: a string or a list of byte values
+fn bytes_arg(name: &str, args: &[Value]) -> Result<Vec<u8>, EvalError> {
+	let args = BuiltinArgs::new(name, args);
+	let bytes = match &args.expect_args(1)?[0] {
+		Value::String(s) => s.as_bytes().to_vec(),
+		Value::List(items) => items
+			.iter()
+			.map(|item| match item {
+				Value::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => Ok(*n as u8),
+				other => Err(args.mismatch("byte (integer from 0 to 255)", other)),
+			})
+			.collect::<Result<Vec<u8>, EvalError>>()?,
+		other => return Err(args.mismatch("String or List of bytes", other)),
+	};
+	let limit = ByteStatsProvider::MAX_INPUT_LEN;
+	if bytes.len() > limit {
+		return Err(EvalError::builtin_failure(
+			"input_too_long",
+			[("length", Value::Number(bytes.len() as f64)), ("limit", Value::Number(limit as f64))],
+		));
+	}
+	Ok(bytes)
+}
+
+/// Shannon entropy of `bytes` in bits per byte, summed in byte-value order
+fn shannon_entropy(bytes: &[u8]) -> f64 {
+	let mut counts = [0usize; 256];
+	for &b in bytes {
+		counts[b as usize] += 1;
+	}
+	let total = bytes.len() as f64;
+	let entropy: f64 = counts
+		.iter()
+		.filter(|&&count| count > 0)
+		.map(|&count| {
+			let p = count as f64 / total;
+			-p * p.log2()
+		})
+		.sum();
+	// A single repeated byte sums to -0.0
+	entropy.abs()
+}
+
+// endregion: --- Byte Helpers
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_shannon_entropy() {
+		let builtins = ByteStatsProvider.get_builtins();
+		let entropy = |data: Value| builtins["shannon_entropy"](&[data]).unwrap();
+		let bytes = |values: &[u8]| Value::List(values.iter().map(|&b| Value::Number(b.into())).collect());
+
+		assert_eq!(entropy(Value::from("")), Value::Number(0.0));
+		assert_eq!(entropy(Value::from("aaaa")), Value::Number(0.0));
+		assert_eq!(entropy(Value::from("abab")), Value::Number(1.0));
+		assert_eq!(entropy(bytes(&(0..=255).collect::<Vec<u8>>())), Value::Number(8.0));
+		assert_eq!(entropy(bytes(&[0, 1, 2, 3])), Value::Number(2.0));
+
+		assert!(builtins["shannon_entropy"](&[bytes(&[]), Value::Null]).is_err());
+		assert!(builtins["shannon_entropy"](&[Value::List(vec![Value::Number(256.0)])]).is_err());
+		assert!(builtins["shannon_entropy"](&[Value::List(vec![Value::Number(1.5)])]).is_err());
+		let long = Value::from("a".repeat(ByteStatsProvider::MAX_INPUT_LEN + 1).as_str());
+		let err = builtins["shannon_entropy"](&[long]).unwrap_err();
+		assert!(matches!(err, EvalError::BuiltinFailure { ref code, .. } if code == "input_too_long"));
+	}
+
+	#[test]
+	fn test_printable_ratio() {
+		let builtins = ByteStatsProvider.get_builtins();
+		let ratio = |data: Value| builtins["printable_ratio"](&[data]).unwrap();
+
+		assert_eq!(ratio(Value::from("hello\tworld\r\n")), Value::Number(1.0));
+		assert_eq!(ratio(Value::from("")), Value::Null);
+		let bytes = Value::List([0x4d, 0x5a, 0x90, 0x00].map(|b: u8| Value::Number(b.into())).to_vec());
+		assert_eq!(ratio(bytes), Value::Number(0.5));
+		// Non-ASCII characters are counted by their UTF-8 bytes
+		assert_eq!(ratio(Value::from("aé")), Value::Number(1.0 / 3.0));
+	}
+}
+
+// endregion: --- Tests
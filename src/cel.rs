@@ -0,0 +1,991 @@
+//! Conversion between HEL and Google CEL expressions
+//!
+//! Partner systems that speak CEL can exchange policies with HEL for the
+//! overlapping subset of the two languages. Constructs outside the subset are
+//! collected into a `CelError::Unsupported` report instead of stopping at the first.
+//!
+//! ## Mapping
+//! | HEL                        | CEL                                  |
+//! |----------------------------|--------------------------------------|
+//! | `AND` / `OR`               | `&&` / `\|\|`                        |
+//! | `== != > >= < <=`          | same                                 |
+//! | `x IN list`                | `x in list`                          |
+//! | `list CONTAINS x`          | `x in list` (import: also `s.contains(x)`) |
+//! | `core.len(x)`              | `size(x)` / `x.size()`               |
+//! | `core.upper(s)` / `lower`  | `s.upperAscii()` / `s.lowerAscii()`  |
+//! | `object.field`             | `object.field`                       |
+//! | `object.*.field OP x`      | `object.exists(k, object[k].field OP x)` |
+//!
+//! ## Notes
+//! - Export warnings flag approximations (e.g., `CONTAINS` on strings, ASCII-only case mapping)
+//! - Not importable: `!`, arithmetic, ternaries, indexing, `null`, negative numbers,
+//!   deeper selections (`a.b.c`) and strings containing `"` (HEL strings have no escapes)
+//! - Not exportable: `$variables` and `%CONSTANTS`
+//! - Imports are bounded by the default `ParseLimits`; see `from_cel_with_limits()`
+
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::{AstNode, Comparator, ParseLimits, SourceSpan};
+
+// region:    --- Types
+
+/// A HEL expression exported to CEL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CelExport {
+	/// CEL source text
+	pub expression: String,
+	/// Approximations made during export (semantics may differ)
+	pub warnings: Vec<String>,
+}
+
+/// Conversion error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CelError {
+	/// The CEL source does not parse
+	Syntax {
+		/// Description of the problem
+		message: String,
+		/// Byte offset in the CEL source
+		offset: usize,
+	},
+	/// Constructs outside the common subset (all of them, in source order)
+	Unsupported(Vec<String>),
+}
+
+impl core::fmt::Display for CelError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			CelError::Syntax { message, offset } => write!(f, "CEL syntax error at offset {}: {}", offset, message),
+			CelError::Unsupported(constructs) => {
+				write!(f, "Unsupported constructs: {}", constructs.join("; "))
+			}
+		}
+	}
+}
+
+impl core::error::Error for CelError {}
+
+// endregion: --- Types
+
+// region:    --- Export
+
+/// Convert a HEL expression to CEL
+///
+/// # Examples
+///
+/// ```
+/// use hel::cel::to_cel;
+/// use hel::parse_expression;
+///
+/// let ast = parse_expression(r#"app.perms CONTAINS "SMS" AND core.len(app.perms) > 2"#).unwrap();
+/// let export = to_cel(&ast).unwrap();
+/// assert_eq!(export.expression, r#""SMS" in app.perms && size(app.perms) > 2"#);
+/// ```
+pub fn to_cel(ast: &AstNode) -> Result<CelExport, CelError> {
+	let mut exporter = Exporter::default();
+	let expression = exporter.condition(ast);
+	if !exporter.unsupported.is_empty() {
+		return Err(CelError::Unsupported(exporter.unsupported));
+	}
+	Ok(CelExport {
+		expression,
+		warnings: exporter.warnings,
+	})
+}
+
+#[derive(Default)]
+struct Exporter {
+	unsupported: Vec<String>,
+	warnings: Vec<String>,
+}
+
+impl Exporter {
+	fn warn(&mut self, warning: String) {
+		if !self.warnings.contains(&warning) {
+			self.warnings.push(warning);
+		}
+	}
+
+	/// Render in condition position (top level or chain operand)
+	fn condition(&mut self, node: &AstNode) -> String {
+		match node {
+			AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) if nodes.len() == 1 => {
+				self.condition(&nodes[0])
+			}
+			AstNode::And(nodes) => self.chain(nodes, "&&"),
+			AstNode::Or(nodes) => self.chain(nodes, "||"),
+			AstNode::Xor(nodes) => self.xor(nodes),
+			_ => self.expr(node),
+		}
+	}
+
+	/// CEL has no XOR: compare the operands' truth values pairwise, `(a != b) != c`
+	fn xor(&mut self, nodes: &[AstNode]) -> String {
+		let operands = nodes.iter().map(|n| self.operand(n)).collect::<Vec<_>>();
+		let Some((first, rest)) = operands.split_first() else {
+			return "false".to_string();
+		};
+		let mut result = first.clone();
+		for (i, operand) in rest.iter().enumerate() {
+			if i > 0 {
+				result = format!("({})", result);
+			}
+			result = format!("{} != {}", result, operand);
+		}
+		result
+	}
+
+	fn chain(&mut self, nodes: &[AstNode], op: &str) -> String {
+		if nodes.is_empty() {
+			return (op == "&&").to_string();
+		}
+		nodes
+			.iter()
+			.map(|n| match collapse(n) {
+				AstNode::And(inner) | AstNode::Or(inner) | AstNode::Xor(inner) if inner.len() > 1 => {
+					format!("({})", self.condition(n))
+				}
+				_ => self.condition(n),
+			})
+			.collect::<Vec<_>>()
+			.join(&format!(" {} ", op))
+	}
+
+	/// Render in operand position (comparison side, argument, element)
+	fn operand(&mut self, node: &AstNode) -> String {
+		match collapse(node) {
+			node @ (AstNode::And(_) | AstNode::Or(_) | AstNode::Xor(_) | AstNode::Comparison { .. }) => {
+				format!("({})", self.condition(node))
+			}
+			node => self.expr(node),
+		}
+	}
+
+	fn expr(&mut self, node: &AstNode) -> String {
+		match node {
+			AstNode::Bool(b) => b.to_string(),
+			AstNode::String(s) => cel_string(s),
+			AstNode::Number(n) if *n > i64::MAX as u64 => format!("{}u", n),
+			AstNode::Number(n) => n.to_string(),
+			AstNode::Float(f) => format!("{:?}", f),
+			AstNode::Identifier(name) if name.starts_with('$') || name.starts_with('%') => {
+				self.unsupported
+					.push(format!("identifier `{}` (bind it before export)", name));
+				name.to_string()
+			}
+			AstNode::Identifier(name) => name.to_string(),
+			AstNode::Attribute { object, field } => format!("{}.{}", object, field),
+			AstNode::Comparison { left, op, right, .. } => {
+				if let Some(comparison) = self.wildcard(left, *op, right) {
+					return comparison;
+				}
+				if let (Comparator::In, AstNode::Range { start, end }) = (op, &**right) {
+					let (l, start, end) = (self.operand(left), self.operand(start), self.operand(end));
+					return format!("({} <= {} && {} <= {})", start, l, l, end);
+				}
+				let (l, r) = (self.operand(left), self.operand(right));
+				match op {
+					Comparator::In => format!("{} in {}", l, r),
+					Comparator::Like => match collapse(right) {
+						AstNode::String(pattern) => format!("{}.matches({})", l, cel_string(&glob_to_regex(pattern))),
+						_ => {
+							self.unsupported.push("LIKE with a non-literal pattern".into());
+							format!("{}.matches({})", l, r)
+						}
+					},
+					Comparator::EqIgnoreCase => {
+						self.warn("EQI exported with lowerAscii(); non-ASCII letters are compared exactly".into());
+						format!("{}.lowerAscii() == {}.lowerAscii()", l, r)
+					}
+					Comparator::Contains => {
+						self.warn(
+							"CONTAINS exported as list membership (`in`); use `.contains()` for substrings".into(),
+						);
+						format!("{} in {}", r, l)
+					}
+					Comparator::Eq
+					| Comparator::Ne
+					| Comparator::Gt
+					| Comparator::Ge
+					| Comparator::Lt
+					| Comparator::Le => format!("{} {} {}", l, crate::trace::comparator_to_str(*op), r),
+				}
+			}
+			AstNode::And(_) | AstNode::Or(_) | AstNode::Xor(_) => self.condition(node),
+			AstNode::ListLiteral(items) => {
+				let items = items.iter().map(|i| self.operand(i)).collect::<Vec<_>>();
+				format!("[{}]", items.join(", "))
+			}
+			AstNode::MapLiteral(entries) => {
+				let entries = entries
+					.iter()
+					.map(|(k, v)| format!("{}: {}", cel_string(k), self.operand(v)))
+					.collect::<Vec<_>>();
+				format!("{{{}}}", entries.join(", "))
+			}
+			AstNode::FunctionCall { namespace, name, args, .. } => self.call(namespace.as_deref(), name, args),
+			AstNode::Range { start, end } => {
+				self.unsupported.push("range outside of IN".into());
+				format!("{}..{}", self.operand(start), self.operand(end))
+			}
+			AstNode::Wildcard { object, field } => {
+				self.unsupported
+					.push(format!("wildcard `{}.*.{}` outside a comparison", object, field));
+				format!("{}.{}", object, field)
+			}
+		}
+	}
+
+	/// A comparison against a wildcard as `object.exists(k, object[k].field OP x)`
+	///
+	/// Mirrors evaluation: the left side is expanded if it is a wildcard, otherwise the
+	/// right side unless the comparison is `IN`.
+	fn wildcard(&mut self, left: &AstNode, op: Comparator, right: &AstNode) -> Option<String> {
+		let member = |object: &str, field: &str| AstNode::Identifier(format!("{}[k].{}", object, field).into());
+		let (object, left, right) = match (left, right) {
+			(AstNode::Wildcard { object, field }, _) => (object, member(object, field), right.clone()),
+			(_, AstNode::Wildcard { object, field }) if op != Comparator::In => {
+				(object, left.clone(), member(object, field))
+			}
+			_ => return None,
+		};
+		let comparison = AstNode::Comparison {
+			left: Box::new(left),
+			op,
+			right: Box::new(right),
+			span: SourceSpan::default(),
+		};
+		Some(format!("{}.exists(k, {})", object, self.expr(&comparison)))
+	}
+
+	fn call(&mut self, namespace: Option<&str>, name: &str, args: &[AstNode]) -> String {
+		let rendered = args.iter().map(|a| self.operand(a)).collect::<Vec<_>>();
+		match (namespace, name, rendered.as_slice()) {
+			(Some("core"), "len", [x]) => format!("size({})", x),
+			(Some("core"), "upper" | "lower", [x]) => {
+				self.warn("core.upper / core.lower exported as ASCII-only upperAscii() / lowerAscii()".into());
+				format!("{}.{}Ascii()", x, name)
+			}
+			(Some("core"), "contains", [haystack, needle]) => {
+				self.warn("core.contains exported as list membership (`in`); use `.contains()` for substrings".into());
+				format!("{} in {}", needle, haystack)
+			}
+			(Some(ns), _, _) => {
+				self.warn(format!(
+					"function {}.{}() must be declared in the CEL environment",
+					ns, name
+				));
+				format!("{}.{}({})", ns, name, rendered.join(", "))
+			}
+			(None, _, _) => format!("{}({})", name, rendered.join(", ")),
+		}
+	}
+}
+
+fn collapse(mut node: &AstNode) -> &AstNode {
+	while let AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) = node {
+		match nodes.as_slice() {
+			[single] => node = single,
+			_ => break,
+		}
+	}
+	node
+}
+
+/// RE2 pattern equivalent to a HEL glob
+fn glob_to_regex(glob: &str) -> String {
+	let mut out = String::from("(?s)^");
+	for c in glob.chars() {
+		match c {
+			'*' => out.push_str(".*"),
+			'?' => out.push('.'),
+			c if "\\.+()|[]{}^$".contains(c) => {
+				out.push('\\');
+				out.push(c);
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('$');
+	out
+}
+
+fn cel_string(s: &str) -> String {
+	let mut out = String::from("\"");
+	for c in s.chars() {
+		match c {
+			'\\' => out.push_str("\\\\"),
+			'"' => out.push_str("\\\""),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+	out
+}
+
+// endregion: --- Export
+
+// region:    --- Import
+
+/// Parse a CEL expression and convert it to a HEL expression
+///
+/// # Examples
+///
+/// ```
+/// use hel::cel::from_cel;
+/// use hel::{evaluate_ast, EvalContext, FactsEvalContext, Value};
+///
+/// let ast = from_cel(r#"app.name.contains("bank") && app.rating >= 4"#).unwrap();
+///
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("app.name", Value::String("mybank".into()));
+/// facts.add_fact("app.rating", Value::Number(4.5));
+/// assert!(evaluate_ast(&ast, &EvalContext::new(&facts)).unwrap());
+///
+/// assert!(from_cel("!app.verified || app.age * 2 > 10").is_err());
+/// ```
+pub fn from_cel(source: &str) -> Result<AstNode, CelError> {
+	from_cel_with_limits(source, &ParseLimits::default())
+}
+
+/// Like `from_cel()`, with explicit input length and nesting depth limits
+///
+/// Exceeding a limit is reported as a `CelError::Syntax` error.
+pub fn from_cel_with_limits(source: &str, limits: &ParseLimits) -> Result<AstNode, CelError> {
+	if source.len() > limits.max_input_len {
+		return Err(CelError::Syntax {
+			message: format!("expression length exceeds maximum of {} bytes", limits.max_input_len),
+			offset: limits.max_input_len,
+		});
+	}
+	let tokens = lex(source)?;
+	let mut parser = Parser {
+		tokens: &tokens,
+		pos: 0,
+		len: source.len(),
+		depth: 0,
+		max_depth: limits.max_nesting_depth,
+	};
+	let cel = parser.ternary()?;
+	if let Some(token) = parser.peek() {
+		return Err(CelError::Syntax {
+			message: format!("unexpected `{}`", token.text),
+			offset: token.offset,
+		});
+	}
+
+	let mut unsupported = Vec::new();
+	let ast = import(&cel, &mut unsupported);
+	if !unsupported.is_empty() {
+		return Err(CelError::Unsupported(unsupported));
+	}
+	Ok(ast)
+}
+
+/// Parsed CEL expression (the full syntax, so unsupported parts can be reported)
+#[derive(Debug, Clone, PartialEq)]
+enum Cel {
+	Null,
+	Bool(bool),
+	Int(u64),
+	Uint(u64),
+	Double(f64),
+	Str(String),
+	Ident(String),
+	Select(Box<Cel>, String),
+	Call {
+		target: Option<Box<Cel>>,
+		name: String,
+		args: Vec<Cel>,
+	},
+	List(Vec<Cel>),
+	Map(Vec<(Cel, Cel)>),
+	Unary(&'static str, Box<Cel>),
+	Binary(&'static str, Box<Cel>, Box<Cel>),
+	Ternary(Box<Cel>, Box<Cel>, Box<Cel>),
+	Index(Box<Cel>, Box<Cel>),
+}
+
+fn import(cel: &Cel, unsupported: &mut Vec<String>) -> AstNode {
+	let mut unsupported_node = |what: String| {
+		unsupported.push(what);
+		AstNode::Bool(false)
+	};
+
+	match cel {
+		Cel::Bool(b) => AstNode::Bool(*b),
+		Cel::Int(n) | Cel::Uint(n) => AstNode::Number(*n),
+		Cel::Double(f) => AstNode::Float(*f),
+		Cel::Str(s) if s.contains('"') => unsupported_node(format!("string {:?} (contains `\"`)", s)),
+		Cel::Str(s) => AstNode::String(s.as_str().into()),
+		Cel::Ident(name) => AstNode::Identifier(name.as_str().into()),
+		Cel::Select(target, field) => match target.as_ref() {
+			Cel::Ident(object) => AstNode::Attribute {
+				object: object.as_str().into(),
+				field: field.as_str().into(),
+			},
+			_ => unsupported_node(format!("nested selection `.{}`", field)),
+		},
+		Cel::List(items) => AstNode::ListLiteral(items.iter().map(|i| import(i, unsupported)).collect()),
+		Cel::Map(entries) => AstNode::MapLiteral(
+			entries
+				.iter()
+				.map(|(k, v)| {
+					let key: Arc<str> = match k {
+						Cel::Str(s) => s.as_str().into(),
+						_ => {
+							unsupported.push("non-string map key".to_string());
+							"".into()
+						}
+					};
+					(key, import(v, unsupported))
+				})
+				.collect(),
+		),
+		Cel::Binary(op @ ("&&" | "||"), _, _) => {
+			let mut operands = Vec::new();
+			flatten(cel, op, &mut operands);
+			let nodes = operands.into_iter().map(|n| import(n, unsupported)).collect();
+			if *op == "&&" {
+				AstNode::And(nodes)
+			} else {
+				AstNode::Or(nodes)
+			}
+		}
+		Cel::Binary(op, left, right) => {
+			let op = match *op {
+				"==" => Comparator::Eq,
+				"!=" => Comparator::Ne,
+				">" => Comparator::Gt,
+				">=" => Comparator::Ge,
+				"<" => Comparator::Lt,
+				"<=" => Comparator::Le,
+				"in" => Comparator::In,
+				_ => return unsupported_node(format!("arithmetic operator `{}`", op)),
+			};
+			comparison(import(left, unsupported), op, import(right, unsupported))
+		}
+		Cel::Call { target, name, args } => import_call(target.as_deref(), name, args, unsupported),
+		Cel::Null => unsupported_node("`null`".to_string()),
+		Cel::Unary("!", _) => unsupported_node("negation `!`".to_string()),
+		Cel::Unary(_, _) => unsupported_node("negative number".to_string()),
+		Cel::Ternary(..) => unsupported_node("conditional `? :`".to_string()),
+		Cel::Index(..) => unsupported_node("indexing `[...]`".to_string()),
+	}
+}
+
+fn import_call(target: Option<&Cel>, name: &str, args: &[Cel], unsupported: &mut Vec<String>) -> AstNode {
+	let core = |name: &str, args: Vec<AstNode>| AstNode::FunctionCall {
+		namespace: Some("core".into()),
+		name: name.into(),
+		args,
+		span: SourceSpan::default(),
+	};
+
+	match (target, name, args) {
+		(Some(t), "contains", [x]) => comparison(import(t, unsupported), Comparator::Contains, import(x, unsupported)),
+		(Some(t), "size", []) | (None, "size", [t]) => core("len", vec![import(t, unsupported)]),
+		(Some(t), "upperAscii", []) => core("upper", vec![import(t, unsupported)]),
+		(Some(t), "lowerAscii", []) => core("lower", vec![import(t, unsupported)]),
+		(Some(Cel::Ident(namespace)), _, _) if !is_cel_method(name) => AstNode::FunctionCall {
+			namespace: Some(namespace.as_str().into()),
+			name: name.into(),
+			args: args.iter().map(|a| import(a, unsupported)).collect(),
+			span: SourceSpan::default(),
+		},
+		(Some(_), _, _) => {
+			unsupported.push(format!("method call `.{}()`", name));
+			AstNode::Bool(false)
+		}
+		(None, _, _) => AstNode::FunctionCall {
+			namespace: None,
+			name: name.into(),
+			args: args.iter().map(|a| import(a, unsupported)).collect(),
+			span: SourceSpan::default(),
+		},
+	}
+}
+
+/// Standard CEL receiver-style methods (never a HEL namespace call)
+fn is_cel_method(name: &str) -> bool {
+	matches!(
+		name,
+		"contains"
+			| "startsWith"
+			| "endsWith"
+			| "matches"
+			| "size" | "exists"
+			| "all" | "exists_one"
+			| "map" | "filter"
+	)
+}
+
+fn comparison(left: AstNode, op: Comparator, right: AstNode) -> AstNode {
+	AstNode::Comparison {
+		left: Box::new(left),
+		op,
+		right: Box::new(right),
+		span: SourceSpan::default(),
+	}
+}
+
+/// Collect the operands of a left-nested chain of `op`
+fn flatten<'c>(cel: &'c Cel, op: &str, out: &mut Vec<&'c Cel>) {
+	match cel {
+		Cel::Binary(o, left, right) if *o == op => {
+			flatten(left, op, out);
+			flatten(right, op, out);
+		}
+		_ => out.push(cel),
+	}
+}
+
+// endregion: --- Import
+
+// region:    --- CEL Parser
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenValue {
+	Int(u64),
+	Uint(u64),
+	Double(f64),
+	Str(String),
+	Ident(String),
+	Punct(&'static str),
+}
+
+#[derive(Debug, Clone)]
+struct CelToken {
+	value: TokenValue,
+	text: String,
+	offset: usize,
+}
+
+const PUNCTUATION: &[&str] = &[
+	"||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "?", ":", ".", ",", "(", ")", "[", "]",
+	"{", "}",
+];
+
+fn lex(source: &str) -> Result<Vec<CelToken>, CelError> {
+	let mut tokens = Vec::new();
+	let bytes = source.as_bytes();
+	let mut pos = 0;
+
+	while pos < bytes.len() {
+		let c = bytes[pos];
+		let start = pos;
+		let value = if c.is_ascii_whitespace() {
+			pos += 1;
+			continue;
+		} else if c.is_ascii_digit() {
+			let (value, end) = lex_number(source, pos)?;
+			pos = end;
+			value
+		} else if c == b'"' || c == b'\'' {
+			let (value, end) = lex_string(source, pos)?;
+			pos = end;
+			TokenValue::Str(value)
+		} else if c.is_ascii_alphabetic() || c == b'_' {
+			let end = bytes[pos..]
+				.iter()
+				.position(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+				.map_or(bytes.len(), |i| pos + i);
+			pos = end;
+			TokenValue::Ident(source[start..end].to_string())
+		} else if let Some(p) = PUNCTUATION.iter().find(|p| source[pos..].starts_with(**p)) {
+			pos += p.len();
+			TokenValue::Punct(p)
+		} else {
+			return Err(CelError::Syntax {
+				message: format!("unexpected character `{}`", source[pos..].chars().next().unwrap_or('?')),
+				offset: pos,
+			});
+		};
+		tokens.push(CelToken {
+			value,
+			text: source[start..pos].to_string(),
+			offset: start,
+		});
+	}
+	Ok(tokens)
+}
+
+fn lex_number(source: &str, start: usize) -> Result<(TokenValue, usize), CelError> {
+	let bytes = source.as_bytes();
+	let syntax = |message: &str| CelError::Syntax {
+		message: message.to_string(),
+		offset: start,
+	};
+	let scan = |from: usize, pred: fn(&u8) -> bool| {
+		bytes[from..]
+			.iter()
+			.position(|b| !pred(b))
+			.map_or(bytes.len(), |i| from + i)
+	};
+
+	let (mut end, value) = if source[start..].starts_with("0x") {
+		let end = scan(start + 2, u8::is_ascii_hexdigit);
+		let value = u64::from_str_radix(&source[start + 2..end], 16).map_err(|_| syntax("invalid hex literal"))?;
+		(end, value)
+	} else {
+		let end = scan(start, u8::is_ascii_digit);
+		if bytes.get(end) == Some(&b'.') && bytes.get(end + 1).is_some_and(u8::is_ascii_digit) {
+			let mut end = scan(end + 1, u8::is_ascii_digit);
+			if matches!(bytes.get(end), Some(b'e' | b'E')) {
+				let exp_start = if matches!(bytes.get(end + 1), Some(b'+' | b'-')) {
+					end + 2
+				} else {
+					end + 1
+				};
+				end = scan(exp_start, u8::is_ascii_digit);
+			}
+			let value = source[start..end]
+				.parse()
+				.map_err(|_| syntax("invalid double literal"))?;
+			return Ok((TokenValue::Double(value), end));
+		}
+		let value = source[start..end]
+			.parse()
+			.map_err(|_| syntax("integer literal out of range"))?;
+		(end, value)
+	};
+
+	if matches!(bytes.get(end), Some(b'u' | b'U')) {
+		end += 1;
+		return Ok((TokenValue::Uint(value), end));
+	}
+	Ok((TokenValue::Int(value), end))
+}
+
+fn lex_string(source: &str, start: usize) -> Result<(String, usize), CelError> {
+	let quote = source.as_bytes()[start] as char;
+	let mut out = String::new();
+	let mut chars = source[start + 1..].char_indices();
+	while let Some((i, c)) = chars.next() {
+		match c {
+			c if c == quote => return Ok((out, start + 1 + i + 1)),
+			'\\' => match chars.next().map(|(_, c)| c) {
+				Some('n') => out.push('\n'),
+				Some('r') => out.push('\r'),
+				Some('t') => out.push('\t'),
+				Some(c @ ('\\' | '"' | '\'')) => out.push(c),
+				_ => {
+					return Err(CelError::Syntax {
+						message: "unsupported escape sequence".to_string(),
+						offset: start + 1 + i,
+					})
+				}
+			},
+			c => out.push(c),
+		}
+	}
+	Err(CelError::Syntax {
+		message: "unterminated string".to_string(),
+		offset: start,
+	})
+}
+
+struct Parser<'t> {
+	tokens: &'t [CelToken],
+	pos: usize,
+	len: usize,
+	depth: usize,
+	max_depth: usize,
+}
+
+impl Parser<'_> {
+	fn peek(&self) -> Option<&CelToken> {
+		self.tokens.get(self.pos)
+	}
+
+	fn eat(&mut self, punct: &str) -> bool {
+		match self.peek() {
+			Some(CelToken {
+				value: TokenValue::Punct(p),
+				..
+			}) if *p == punct => {
+				self.pos += 1;
+				true
+			}
+			_ => false,
+		}
+	}
+
+	fn expect(&mut self, punct: &str) -> Result<(), CelError> {
+		if self.eat(punct) {
+			return Ok(());
+		}
+		Err(self.error(&format!("expected `{}`", punct)))
+	}
+
+	fn error(&self, message: &str) -> CelError {
+		CelError::Syntax {
+			message: message.to_string(),
+			offset: self.peek().map_or(self.len, |t| t.offset),
+		}
+	}
+
+	/// Run a recursive parse step one nesting level deeper, bounded by `max_depth`
+	fn nested(&mut self, parse: fn(&mut Self) -> Result<Cel, CelError>) -> Result<Cel, CelError> {
+		if self.depth > self.max_depth {
+			// Point at the bracket or operator that opened the level
+			let opener = self.pos.checked_sub(1).and_then(|pos| self.tokens.get(pos));
+			return Err(CelError::Syntax {
+				message: format!("nesting depth exceeds maximum of {}", self.max_depth),
+				offset: opener.map_or(0, |t| t.offset),
+			});
+		}
+		self.depth += 1;
+		let result = parse(self);
+		self.depth -= 1;
+		result
+	}
+
+	fn ternary(&mut self) -> Result<Cel, CelError> {
+		self.nested(Self::conditional)
+	}
+
+	fn conditional(&mut self) -> Result<Cel, CelError> {
+		let condition = self.binary(0)?;
+		if !self.eat("?") {
+			return Ok(condition);
+		}
+		let then = self.ternary()?;
+		self.expect(":")?;
+		let otherwise = self.ternary()?;
+		Ok(Cel::Ternary(Box::new(condition), Box::new(then), Box::new(otherwise)))
+	}
+
+	/// Precedence climbing over the binary operator levels
+	fn binary(&mut self, level: usize) -> Result<Cel, CelError> {
+		const LEVELS: &[&[&str]] = &[
+			&["||"],
+			&["&&"],
+			&["==", "!=", "<", "<=", ">", ">=", "in"],
+			&["+", "-"],
+			&["*", "/", "%"],
+		];
+		if level == LEVELS.len() {
+			return self.unary();
+		}
+
+		let mut left = self.binary(level + 1)?;
+		loop {
+			let op = match self.peek().map(|t| &t.value) {
+				Some(TokenValue::Punct(p)) => LEVELS[level].iter().find(|op| *op == p),
+				Some(TokenValue::Ident(id)) if id == "in" => LEVELS[level].iter().find(|op| **op == "in"),
+				_ => None,
+			};
+			let Some(op) = op else {
+				return Ok(left);
+			};
+			self.pos += 1;
+			let right = self.binary(level + 1)?;
+			left = Cel::Binary(op, Box::new(left), Box::new(right));
+		}
+	}
+
+	fn unary(&mut self) -> Result<Cel, CelError> {
+		for op in ["!", "-"] {
+			if self.eat(op) {
+				return Ok(Cel::Unary(op, Box::new(self.nested(Self::unary)?)));
+			}
+		}
+		self.member()
+	}
+
+	fn member(&mut self) -> Result<Cel, CelError> {
+		let mut expr = self.primary()?;
+		loop {
+			if self.eat(".") {
+				let name = self.ident()?;
+				if self.eat("(") {
+					let args = self.list(")")?;
+					expr = Cel::Call {
+						target: Some(Box::new(expr)),
+						name,
+						args,
+					};
+				} else {
+					expr = Cel::Select(Box::new(expr), name);
+				}
+			} else if self.eat("[") {
+				let index = self.ternary()?;
+				self.expect("]")?;
+				expr = Cel::Index(Box::new(expr), Box::new(index));
+			} else {
+				return Ok(expr);
+			}
+		}
+	}
+
+	fn primary(&mut self) -> Result<Cel, CelError> {
+		let token = self
+			.peek()
+			.cloned()
+			.ok_or_else(|| self.error("unexpected end of input"))?;
+		self.pos += 1;
+		match token.value {
+			TokenValue::Int(n) => Ok(Cel::Int(n)),
+			TokenValue::Uint(n) => Ok(Cel::Uint(n)),
+			TokenValue::Double(f) => Ok(Cel::Double(f)),
+			TokenValue::Str(s) => Ok(Cel::Str(s)),
+			TokenValue::Ident(id) => match id.as_str() {
+				"true" => Ok(Cel::Bool(true)),
+				"false" => Ok(Cel::Bool(false)),
+				"null" => Ok(Cel::Null),
+				_ if self.eat("(") => Ok(Cel::Call {
+					target: None,
+					name: id,
+					args: self.list(")")?,
+				}),
+				_ => Ok(Cel::Ident(id)),
+			},
+			TokenValue::Punct("(") => {
+				let expr = self.ternary()?;
+				self.expect(")")?;
+				Ok(expr)
+			}
+			TokenValue::Punct("[") => Ok(Cel::List(self.list("]")?)),
+			TokenValue::Punct("{") => {
+				let mut entries = Vec::new();
+				while !self.eat("}") {
+					let key = self.ternary()?;
+					self.expect(":")?;
+					entries.push((key, self.ternary()?));
+					if !self.eat(",") {
+						self.expect("}")?;
+						break;
+					}
+				}
+				Ok(Cel::Map(entries))
+			}
+			TokenValue::Punct(_) => {
+				self.pos -= 1;
+				Err(self.error(&format!("unexpected `{}`", token.text)))
+			}
+		}
+	}
+
+	/// Comma-separated expressions up to `close` (trailing comma allowed)
+	fn list(&mut self, close: &str) -> Result<Vec<Cel>, CelError> {
+		let mut items = Vec::new();
+		while !self.eat(close) {
+			items.push(self.ternary()?);
+			if !self.eat(",") {
+				self.expect(close)?;
+				break;
+			}
+		}
+		Ok(items)
+	}
+
+	fn ident(&mut self) -> Result<String, CelError> {
+		match self.peek().map(|t| t.value.clone()) {
+			Some(TokenValue::Ident(id)) => {
+				self.pos += 1;
+				Ok(id)
+			}
+			_ => Err(self.error("expected identifier")),
+		}
+	}
+}
+
+// endregion: --- CEL Parser
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::format::{format_ast, FormatOptions};
+	use crate::parse_expression;
+
+	#[test]
+	fn test_round_trip() {
+		let hel = r#"(a.b == 1 OR c.d IN ["x", "y"]) AND core.upper(e.f) != "Q" AND {"k": 2.5} == g.h AND big.n > 18446744073709551615"#;
+		let ast = parse_expression(hel).unwrap();
+		let export = to_cel(&ast).unwrap();
+		assert_eq!(
+			export.expression,
+			r#"(a.b == 1 || c.d in ["x", "y"]) && e.f.upperAscii() != "Q" && {"k": 2.5} == g.h && big.n > 18446744073709551615u"#
+		);
+		assert_eq!(export.warnings.len(), 1);
+
+		let imported = from_cel(&export.expression).unwrap();
+		let options = FormatOptions::default();
+		assert_eq!(format_ast(&imported, &options), format_ast(&ast, &options));
+	}
+
+	#[test]
+	fn test_unsupported_report() {
+		let ast = parse_expression("a.b == $limit OR c.d == %MAX").unwrap();
+		assert_eq!(
+			to_cel(&ast).unwrap_err(),
+			CelError::Unsupported(vec![
+				"identifier `$limit` (bind it before export)".to_string(),
+				"identifier `%MAX` (bind it before export)".to_string(),
+			])
+		);
+
+		let CelError::Unsupported(report) =
+			from_cel(r#"!a.b && a.b.c == 1 && x[0] > -1 && (y ? 1 : 2) == 1 && s.startsWith('a')"#).unwrap_err()
+		else {
+			panic!("expected unsupported report");
+		};
+		assert_eq!(report.len(), 6, "{:?}", report);
+
+		let like = to_cel(&parse_expression(r#"p.path LIKE "C:\*.exe" AND p.name LIKE p.glob"#).unwrap());
+		assert_eq!(
+			like.unwrap_err(),
+			CelError::Unsupported(vec!["LIKE with a non-literal pattern".to_string()])
+		);
+		let like = to_cel(&parse_expression(r#"p.path LIKE "C:\*.ex?""#).unwrap()).unwrap();
+		assert_eq!(like.expression, r#"p.path.matches("(?s)^C:\\\\.*\\.ex.$")"#);
+		let xor = to_cel(&parse_expression("a.b XOR c.d == 1 XOR e.f OR g.h").unwrap()).unwrap();
+		assert_eq!(xor.expression, "((a.b != (c.d == 1)) != e.f) || g.h");
+		let range = to_cel(&parse_expression("n.port IN 1024..n.max").unwrap()).unwrap();
+		assert_eq!(range.expression, "(1024 <= n.port && n.port <= n.max)");
+		let any = to_cel(&parse_expression("s.*.entropy > 7.5 AND 1 IN s.*.ids").unwrap());
+		assert_eq!(
+			any.unwrap_err(),
+			CelError::Unsupported(vec!["wildcard `s.*.ids` outside a comparison".to_string()])
+		);
+		let any = to_cel(&parse_expression("s.*.entropy > 7.5 OR 1 <= s.*.size").unwrap()).unwrap();
+		assert_eq!(
+			any.expression,
+			"s.exists(k, s[k].entropy > 7.5) || s.exists(k, 1 <= s[k].size)"
+		);
+
+		assert!(matches!(from_cel("a.b == "), Err(CelError::Syntax { offset: 7, .. })));
+		assert!(matches!(from_cel("a.b == 'x"), Err(CelError::Syntax { offset: 7, .. })));
+	}
+
+	#[test]
+	fn test_import_limits() {
+		let nested = |depth: usize| format!("{}a.b == 1{}", "(".repeat(depth), ")".repeat(depth));
+		assert!(from_cel(&nested(crate::DEFAULT_MAX_NESTING_DEPTH)).is_ok());
+
+		// Deep input is rejected instead of overflowing the stack
+		let err = from_cel(&nested(100_000)).unwrap_err();
+		assert!(matches!(err, CelError::Syntax { offset: 32, .. }), "{}", err);
+		let err = from_cel(&format!("{}a.b", "!".repeat(100_000))).unwrap_err();
+		assert!(matches!(err, CelError::Syntax { .. }), "{}", err);
+		assert!(from_cel(&format!("{}1]", "[".repeat(100_000))).is_err());
+
+		let limits = ParseLimits {
+			max_nesting_depth: 2,
+			max_input_len: 16,
+		};
+		assert!(from_cel_with_limits("((a.b == 1))", &limits).is_ok());
+		assert!(from_cel_with_limits("(((a.b == 1)))", &limits).is_err());
+		assert!(from_cel_with_limits("[[[1]]] == a.b", &limits).is_err());
+		assert!(from_cel_with_limits("a.b == 1 && a.c == 2", &limits).is_err());
+	}
+}
+
+// endregion: --- Tests
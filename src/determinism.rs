@@ -0,0 +1,338 @@
+//! Determinism verification for HEL evaluations
+//!
+//! HEL promises that the same rule evaluated against the same facts always
+//! produces the same result and trace. Closed built-in providers are expected to
+//! honor that contract, but nothing enforces it. This module provides a debug
+//! mode that evaluates a rule twice and reports any divergence.
+//!
+//! ## Verification passes
+//! - The second pass can use a separately constructed `BuiltinsRegistry`, which
+//!   catches providers that keep state across calls or registry instances
+//! - Results, traces and errors are compared on their canonical text rendering
+
+use std::sync::Arc;
+
+use crate::builtins::BuiltinsRegistry;
+use crate::rules::RuleSet;
+use crate::trace::{trace_ast, EvalTrace};
+use crate::{parse_expression_with_limits, AstNode, EvalError, EvalOptions, HelResolver};
+
+// region:    --- DeterminismError
+
+/// Failure reported by a determinism check
+#[derive(Debug, Clone)]
+pub enum DeterminismError {
+	/// The rule could not be parsed or evaluated (identically on both passes)
+	Eval(EvalError),
+	/// The two passes disagreed
+	Mismatch {
+		/// What diverged: "result", "trace" or "error"
+		aspect: &'static str,
+		/// Rendering from the first pass
+		first: String,
+		/// Rendering from the second pass
+		second: String,
+	},
+}
+
+impl std::fmt::Display for DeterminismError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			DeterminismError::Eval(e) => write!(f, "{}", e),
+			DeterminismError::Mismatch { aspect, first, second } => {
+				write!(
+					f,
+					"Non-deterministic {}: first pass {:?}, second pass {:?}",
+					aspect, first, second
+				)
+			}
+		}
+	}
+}
+
+impl std::error::Error for DeterminismError {}
+
+// endregion: --- DeterminismError
+
+// region:    --- DeterminismVerifier
+
+/// Evaluates rules twice and reports divergent results or traces
+///
+/// # Examples
+///
+/// ```
+/// use hel::determinism::DeterminismVerifier;
+/// use hel::{BuiltinsRegistry, FactsEvalContext};
+///
+/// let first = BuiltinsRegistry::builder().with_core().build().unwrap();
+/// let second = BuiltinsRegistry::builder().with_core().build().unwrap();
+///
+/// let verifier = DeterminismVerifier::new()
+///     .with_builtins(&first)
+///     .with_second_registry(&second);
+///
+/// let trace = verifier
+///     .verify(r#"core.len([{"a": 1, "b": 2}]) == 1"#, &FactsEvalContext::new())
+///     .expect("core builtins are deterministic");
+/// assert!(trace.result);
+/// ```
+#[derive(Clone, Copy, Default)]
+pub struct DeterminismVerifier<'a> {
+	builtins: Option<&'a BuiltinsRegistry>,
+	second_builtins: Option<&'a BuiltinsRegistry>,
+	options: Option<&'a EvalOptions>,
+}
+
+impl<'a> DeterminismVerifier<'a> {
+	/// Create a verifier with no built-ins
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Built-ins registry used by the first pass (and the second, unless overridden)
+	pub fn with_builtins(mut self, builtins: &'a BuiltinsRegistry) -> Self {
+		self.builtins = Some(builtins);
+		self
+	}
+
+	/// Use a separately constructed registry for the second pass
+	pub fn with_second_registry(mut self, builtins: &'a BuiltinsRegistry) -> Self {
+		self.second_builtins = Some(builtins);
+		self
+	}
+
+	/// Evaluate both passes with `options` (conditions are parsed with `options.limits`)
+	pub fn with_options(mut self, options: &'a EvalOptions) -> Self {
+		self.options = Some(options);
+		self
+	}
+
+	/// Verify a single condition, returning the first-pass trace on success
+	pub fn verify(&self, condition: &str, resolver: &dyn HelResolver) -> Result<EvalTrace, DeterminismError> {
+		let limits = self.options.map(|options| options.limits).unwrap_or_default();
+		let ast = parse_expression_with_limits(condition, &limits)
+			.map_err(|e| DeterminismError::Eval(EvalError::ParseError(e.message)))?;
+		self.verify_ast(&ast, resolver)
+	}
+
+	/// Verify every rule in a rule set, returning the failures keyed by rule id
+	///
+	/// Each pass evaluates the whole set as `RuleSet::evaluate()` does, so `rule("id")`
+	/// references read the result memoized in the same pass. Failures are in evaluation order.
+	pub fn verify_rules(&self, rules: &RuleSet, resolver: &dyn HelResolver) -> Vec<(Arc<str>, DeterminismError)> {
+		let options = self.options.cloned().unwrap_or_default();
+		let first = rule_traces(rules, resolver, self.builtins, &options);
+		let second = rule_traces(rules, resolver, self.second_builtins.or(self.builtins), &options);
+		first
+			.into_iter()
+			.zip(second)
+			.filter_map(|((id, first), (_, second))| compare(first, second).err().map(|e| (id, e)))
+			.collect()
+	}
+
+	/// Verify an already-parsed expression
+	pub fn verify_ast(&self, ast: &AstNode, resolver: &dyn HelResolver) -> Result<EvalTrace, DeterminismError> {
+		let options = self.options.cloned().unwrap_or_default();
+		let first = run(ast, resolver, self.builtins, &options);
+		let second = run(ast, resolver, self.second_builtins.or(self.builtins), &options);
+		compare(first, second)
+	}
+}
+
+// endregion: --- DeterminismVerifier
+
+// region:    --- Support
+
+/// Compare the outcomes of two passes, returning the first on agreement
+fn compare(
+	first: Result<EvalTrace, EvalError>,
+	second: Result<EvalTrace, EvalError>,
+) -> Result<EvalTrace, DeterminismError> {
+	match (first, second) {
+		(Ok(a), Ok(b)) => {
+			if a.result != b.result {
+				return Err(mismatch("result", &a.result, &b.result));
+			}
+			let (a_text, b_text) = (a.pretty_print(), b.pretty_print());
+			if a_text != b_text {
+				return Err(DeterminismError::Mismatch {
+					aspect: "trace",
+					first: a_text,
+					second: b_text,
+				});
+			}
+			Ok(a)
+		}
+		(Err(a), Err(b)) => {
+			if a.to_string() != b.to_string() {
+				return Err(mismatch("error", &a, &b));
+			}
+			Err(DeterminismError::Eval(a))
+		}
+		(Ok(a), Err(b)) => Err(DeterminismError::Mismatch {
+			aspect: "error",
+			first: format!("Ok({})", a.result),
+			second: format!("Err({})", b),
+		}),
+		(Err(a), Ok(b)) => Err(DeterminismError::Mismatch {
+			aspect: "error",
+			first: format!("Err({})", a),
+			second: format!("Ok({})", b.result),
+		}),
+	}
+}
+
+/// Trace every rule of a set in evaluation order, memoizing `rule("id")` references
+fn rule_traces(
+	rules: &RuleSet,
+	resolver: &dyn HelResolver,
+	builtins: Option<&BuiltinsRegistry>,
+	options: &EvalOptions,
+) -> Vec<(Arc<str>, Result<EvalTrace, EvalError>)> {
+	let mut traces = Vec::new();
+	rules.evaluate_each_with(
+		|_| true,
+		|| crate::options_context(resolver, builtins, options),
+		|rule, ctx| trace_ast(&rule.condition, ctx),
+		|rule, trace| {
+			traces.push((rule.id.clone(), trace));
+			true
+		},
+	);
+	traces
+}
+
+fn run(
+	ast: &AstNode,
+	resolver: &dyn HelResolver,
+	builtins: Option<&BuiltinsRegistry>,
+	options: &EvalOptions,
+) -> Result<EvalTrace, EvalError> {
+	trace_ast(ast, &crate::options_context(resolver, builtins, options))
+}
+
+fn mismatch(aspect: &'static str, first: &impl std::fmt::Display, second: &impl std::fmt::Display) -> DeterminismError {
+	DeterminismError::Mismatch {
+		aspect,
+		first: first.to_string(),
+		second: second.to_string(),
+	}
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::builtins::{BuiltinFn, BuiltinsProvider};
+	use crate::{CoreBuiltinsProvider, FactsEvalContext, Value};
+	use std::collections::BTreeMap;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	/// Provider whose `tick()` returns a different value on every call
+	struct CounterProvider {
+		counter: Arc<AtomicUsize>,
+	}
+
+	impl BuiltinsProvider for CounterProvider {
+		fn namespace(&self) -> &str {
+			"counter"
+		}
+
+		fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+			let counter = self.counter.clone();
+			let mut builtins = BTreeMap::new();
+			builtins.insert(
+				"tick".to_string(),
+				Arc::new(move |_args: &[Value]| -> Result<Value, EvalError> {
+					Ok(Value::Number(counter.fetch_add(1, Ordering::SeqCst) as f64))
+				}) as BuiltinFn,
+			);
+			builtins
+		}
+	}
+
+	#[test]
+	fn test_deterministic_rule_passes() {
+		let mut registry = BuiltinsRegistry::new();
+		registry.register(&CoreBuiltinsProvider).expect("register failed");
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("app.perms", Value::List(vec!["SMS".into()]));
+
+		let verifier = DeterminismVerifier::new().with_builtins(&registry);
+		let trace = verifier
+			.verify(
+				r#"core.len(app.perms) == 1 AND core.len([{"x": 1, "y": 2}]) == 1"#,
+				&ctx,
+			)
+			.expect("should be deterministic");
+		assert!(trace.result);
+	}
+
+	#[test]
+	fn test_stateful_builtin_is_detected() {
+		let mut registry = BuiltinsRegistry::new();
+		registry
+			.register(&CounterProvider {
+				counter: Arc::new(AtomicUsize::new(0)),
+			})
+			.expect("register failed");
+
+		let verifier = DeterminismVerifier::new().with_builtins(&registry);
+		let err = verifier
+			.verify("counter.tick() >= 0", &FactsEvalContext::new())
+			.unwrap_err();
+		assert!(matches!(err, DeterminismError::Mismatch { aspect: "trace", .. }));
+	}
+
+	#[test]
+	fn test_verify_rules_reports_failures_by_id() {
+		let mut registry = BuiltinsRegistry::new();
+		registry
+			.register(&CounterProvider {
+				counter: Arc::new(AtomicUsize::new(0)),
+			})
+			.expect("register failed");
+
+		let mut rules = RuleSet::new();
+		rules.add_rule("stable", "true").expect("add failed");
+		rules.add_rule("flaky", "counter.tick() == 0").expect("add failed");
+		rules.add_rule("uses-stable", r#"rule("stable")"#).expect("add failed");
+		rules.add_rule("uses-flaky", r#"rule("flaky") OR false"#).expect("add failed");
+
+		// References read the result memoized in the same pass
+		let failures = DeterminismVerifier::new()
+			.with_builtins(&registry)
+			.verify_rules(&rules, &FactsEvalContext::new());
+		let ids: Vec<&str> = failures.iter().map(|(id, _)| id.as_ref()).collect();
+		assert_eq!(ids, vec!["flaky", "uses-flaky"]);
+		assert!(failures
+			.iter()
+			.all(|(_, e)| matches!(e, DeterminismError::Mismatch { aspect: "result", .. })));
+	}
+
+	#[test]
+	fn test_verify_with_options() {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(7.501));
+
+		let tolerant = EvalOptions::new().with_numeric_tolerance(0.01);
+		let trace = DeterminismVerifier::new()
+			.with_options(&tolerant)
+			.verify("binary.entropy == 7.5", &ctx)
+			.expect("deterministic");
+		assert!(trace.result);
+
+		let expired = EvalOptions::new().with_timeout(std::time::Duration::ZERO);
+		let err = DeterminismVerifier::new()
+			.with_options(&expired)
+			.verify("binary.entropy == 7.5", &ctx)
+			.unwrap_err();
+		assert!(matches!(err, DeterminismError::Eval(EvalError::Timeout)));
+	}
+}
+
+// endregion: --- Tests
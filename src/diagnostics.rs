@@ -0,0 +1,328 @@
+//! Warnings collected alongside evaluation
+//!
+//! Hard errors abort evaluation; warnings do not. A `Diagnostics` collector is attached
+//! with `EvalContext::with_diagnostics` (and filled by `Analyzer::collect_warnings` and
+//! `lint_script` during static checks); the host reads the warnings afterwards.
+//!
+//! ## Warnings
+//! - `MissingFact`: an attribute had no fact and was treated as null
+//! - `LenientCoercion`: a comparison of incompatible types was treated as false, or an
+//!   unbound identifier was treated as a string
+//! - `DeprecatedBuiltin`: a built-in marked with `BuiltinsRegistry::deprecate` was called
+//! - `Schema`: static analysis found a likely mistake (see `analysis`)
+//! - `UnusedBinding` / `ShadowedBinding`: a script `let` binding is never referenced, or
+//!   rebinds an earlier name (see `lint_script`)
+//!
+//! Identical warnings are recorded once, in the order they were first raised.
+//!
+//! ## JSON
+//! `HelError::to_json`, `Warning::to_json` and `analysis::Diagnostic::to_json` share one
+//! shape, described by the JSON Schema in `DIAGNOSTIC_SCHEMA`:
+//!
+//! ```json
+//! {
+//!   "code": "hel::parse",
+//!   "severity": "error",
+//!   "message": "parsing error: expected primary",
+//!   "span": { "line": 1, "column": 16 },
+//!   "suggestions": ["expected a value: ..."]
+//! }
+//! ```
+//!
+//! `span` is `null` when the location is unknown; it carries 1-based `line` / `column`
+//! and/or byte offsets `start` / `end`, whichever the producer knows.
+
+use core::cell::RefCell;
+use core::ops::Range;
+
+use serde_json::{json, Value as Json};
+
+use crate::prelude::*;
+use crate::{ErrorKind, HelError};
+
+/// JSON Schema (draft 2020-12) of a serialized diagnostic
+pub const DIAGNOSTIC_SCHEMA: &str = include_str!("../docs/diagnostic.schema.json");
+
+// region:    --- Types
+
+/// Warning category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WarningKind {
+	/// A fact was missing and treated as null
+	MissingFact,
+	/// A value was coerced leniently instead of failing
+	LenientCoercion,
+	/// A deprecated built-in was called
+	DeprecatedBuiltin,
+	/// Static analysis warning (schema mismatch, unknown fact object)
+	Schema,
+	/// A script `let` binding is never referenced
+	UnusedBinding,
+	/// A script `let` binding rebinds an earlier name
+	ShadowedBinding,
+}
+
+impl WarningKind {
+	/// Stable machine-readable code, e.g. `hel::missing_fact`
+	pub fn code(&self) -> &'static str {
+		match self {
+			WarningKind::MissingFact => "hel::missing_fact",
+			WarningKind::LenientCoercion => "hel::lenient_coercion",
+			WarningKind::DeprecatedBuiltin => "hel::deprecated_builtin",
+			WarningKind::Schema => "hel::schema",
+			WarningKind::UnusedBinding => "hel::unused_binding",
+			WarningKind::ShadowedBinding => "hel::shadowed_binding",
+		}
+	}
+}
+
+/// A non-fatal problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+	/// Category
+	pub kind: WarningKind,
+	/// Human-readable message
+	pub message: String,
+	/// Byte range in the rule text, when known
+	pub span: Option<Range<usize>>,
+}
+
+/// Warning collector shared by reference during checks and evaluation
+///
+/// # Examples
+///
+/// ```
+/// use hel::diagnostics::{Diagnostics, WarningKind};
+/// use hel::{evaluate_ast, parse_expression, EvalContext, FactsEvalContext};
+///
+/// let ast = parse_expression("binary.entropy > 7").unwrap();
+/// let facts = FactsEvalContext::new();
+/// let diagnostics = Diagnostics::new();
+///
+/// let ctx = EvalContext::new(&facts).with_diagnostics(&diagnostics);
+/// assert!(!evaluate_ast(&ast, &ctx).unwrap());
+///
+/// let warnings = diagnostics.take();
+/// assert_eq!(warnings[0].kind, WarningKind::MissingFact);
+/// ```
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+	warnings: RefCell<Vec<Warning>>,
+}
+
+// endregion: --- Types
+
+// region:    --- Diagnostics
+
+impl Diagnostics {
+	/// Create an empty collector
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record a warning without a span
+	pub fn warn(&self, kind: WarningKind, message: impl Into<String>) {
+		self.push(Warning {
+			kind,
+			message: message.into(),
+			span: None,
+		});
+	}
+
+	/// Record a warning (ignored if an identical one was already recorded)
+	pub fn push(&self, warning: Warning) {
+		let mut warnings = self.warnings.borrow_mut();
+		if !warnings.contains(&warning) {
+			warnings.push(warning);
+		}
+	}
+
+	/// Warnings recorded so far
+	pub fn warnings(&self) -> Vec<Warning> {
+		self.warnings.borrow().clone()
+	}
+
+	/// Remove and return all warnings, leaving the collector empty for reuse
+	pub fn take(&self) -> Vec<Warning> {
+		core::mem::take(&mut *self.warnings.borrow_mut())
+	}
+
+	/// Whether no warnings were recorded
+	pub fn is_empty(&self) -> bool {
+		self.warnings.borrow().is_empty()
+	}
+}
+
+// endregion: --- Diagnostics
+
+// region:    --- JSON
+
+impl HelError {
+	/// Suggested fix for common mistakes
+	pub fn help(&self) -> Option<&'static str> {
+		let message = self.message.as_str();
+		let help = match self.kind {
+			ErrorKind::ParseError if message.contains("final boolean expression") => {
+				"end the script with an expression combining the `let` bindings, e.g. `a AND b`"
+			}
+			ErrorKind::ParseError if message.contains("primary") => {
+				"expected a value: a literal, an attribute like `object.field`, a list or a function call"
+			}
+			ErrorKind::ParseError if message.contains("comparator") => {
+				"compare with one of `==`, `!=`, `>`, `>=`, `<`, `<=`, `CONTAINS` or `IN`"
+			}
+			ErrorKind::ParseError if message.contains("EOI") => {
+				"join conditions with `AND` / `OR` and check that parentheses are balanced"
+			}
+			ErrorKind::UnknownAttribute => "check the attribute name against the schema for its object",
+			ErrorKind::TypeError => "both sides of a comparison must have compatible types",
+			ErrorKind::LimitExceeded => "split the rule or raise the limit in `EvalOptions`",
+			_ => return None,
+		};
+		Some(help)
+	}
+
+	/// Structured form for tools (see `DIAGNOSTIC_SCHEMA`)
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::validate_expression;
+	/// use serde_json::json;
+	///
+	/// let json = validate_expression("binary.arch == )").unwrap_err().to_json();
+	/// assert_eq!(json["code"], json!("hel::parse"));
+	/// assert_eq!(json["severity"], json!("error"));
+	/// assert_eq!(json["span"], json!({ "line": 1, "column": 16 }));
+	/// ```
+	pub fn to_json(&self) -> Json {
+		let span = match (self.line, self.column) {
+			(Some(line), Some(column)) => json!({ "line": line, "column": column }),
+			(Some(line), None) => json!({ "line": line }),
+			_ => Json::Null,
+		};
+		diagnostic_json(self.kind.code(), "error", &self.message, span, self.help())
+	}
+}
+
+impl Warning {
+	/// Structured form for tools (see `DIAGNOSTIC_SCHEMA`)
+	pub fn to_json(&self) -> Json {
+		diagnostic_json(
+			self.kind.code(),
+			"warning",
+			&self.message,
+			span_json(self.span.as_ref()),
+			None,
+		)
+	}
+}
+
+/// Byte range as a diagnostic span
+pub(crate) fn span_json(span: Option<&Range<usize>>) -> Json {
+	span.map_or(Json::Null, |span| json!({ "start": span.start, "end": span.end }))
+}
+
+/// A diagnostic object in the shared JSON shape
+pub(crate) fn diagnostic_json(code: &str, severity: &str, message: &str, span: Json, help: Option<&str>) -> Json {
+	json!({
+		"code": code,
+		"severity": severity,
+		"message": message,
+		"span": span,
+		"suggestions": help.into_iter().collect::<Vec<_>>(),
+	})
+}
+
+// endregion: --- JSON
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::builtins::{BuiltinsRegistry, CoreBuiltinsProvider};
+	use crate::{evaluate_ast, parse_expression, EvalContext, FactsEvalContext, Value};
+
+	fn run(expr: &str, facts: &FactsEvalContext, builtins: &BuiltinsRegistry) -> (bool, Vec<Warning>) {
+		let diagnostics = Diagnostics::new();
+		let ctx = EvalContext::with_builtins(facts, builtins).with_diagnostics(&diagnostics);
+		let result = evaluate_ast(&parse_expression(expr).unwrap(), &ctx).unwrap();
+		(result, diagnostics.take())
+	}
+
+	#[test]
+	fn test_evaluation_warnings() {
+		let mut facts = FactsEvalContext::new();
+		facts.add_fact("app.name", Value::String("x".into()));
+		facts.add_fact("app.perms", Value::List(vec![Value::String("SMS".into())]));
+		let mut builtins = BuiltinsRegistry::new();
+		builtins.register(&CoreBuiltinsProvider).unwrap();
+		builtins.deprecate("core", "upper", "use core.lower instead");
+
+		let (result, warnings) = run(r#"app.perms CONTAINS "SMS" AND app.name == "x""#, &facts, &builtins);
+		assert!(result);
+		assert!(warnings.is_empty(), "{:?}", warnings);
+
+		let (result, warnings) = run(r#"app.name > 3 OR app.size > 1 OR app.size > 2"#, &facts, &builtins);
+		assert!(!result);
+		let kinds: Vec<_> = warnings.iter().map(|w| w.kind).collect();
+		assert_eq!(kinds, vec![WarningKind::LenientCoercion, WarningKind::MissingFact]);
+		assert!(warnings[1].message.contains("app.size"));
+
+		let (result, warnings) = run(r#"core.upper(app.name) == X"#, &facts, &builtins);
+		assert!(result);
+		let kinds: Vec<_> = warnings.iter().map(|w| w.kind).collect();
+		assert_eq!(
+			kinds,
+			vec![WarningKind::DeprecatedBuiltin, WarningKind::LenientCoercion]
+		);
+		assert!(warnings[0].message.contains("use core.lower instead"));
+	}
+
+	#[test]
+	fn test_collector_without_context_is_silent() {
+		let facts = FactsEvalContext::new();
+		let ast = parse_expression("a.b == 1").unwrap();
+		assert!(!evaluate_ast(&ast, &EvalContext::new(&facts)).unwrap());
+
+		let diagnostics = Diagnostics::new();
+		diagnostics.warn(WarningKind::Schema, "one");
+		diagnostics.warn(WarningKind::Schema, "one");
+		assert_eq!(diagnostics.warnings().len(), 1);
+		assert_eq!(diagnostics.take().len(), 1);
+		assert!(diagnostics.is_empty());
+	}
+
+	#[test]
+	fn test_json_matches_schema() {
+		let schema: Json = serde_json::from_str(DIAGNOSTIC_SCHEMA).unwrap();
+		let required = schema["required"].as_array().unwrap();
+		let span_keys = schema["$defs"]["span"]["properties"].as_object().unwrap();
+
+		let error = crate::parse_script("let a = x.y ==\nlet b = true\nb").unwrap_err();
+		let warning = Warning {
+			kind: WarningKind::MissingFact,
+			message: "fact `a.b` is missing; treated as null".into(),
+			span: Some(0..3),
+		};
+		let location_free = HelError::eval_error("boom".into());
+		for json in [error.to_json(), warning.to_json(), location_free.to_json()] {
+			let object = json.as_object().unwrap();
+			assert_eq!(object.len(), required.len(), "{}", json);
+			assert!(required.iter().all(|key| object.contains_key(key.as_str().unwrap())));
+			if let Some(span) = json["span"].as_object() {
+				assert!(span.keys().all(|key| span_keys.contains_key(key)), "{}", json);
+			}
+		}
+
+		let json = error.to_json();
+		assert_eq!(json["span"], json!({ "line": 1, "column": 15 }));
+		assert_eq!(json["suggestions"].as_array().unwrap().len(), 1);
+		assert_eq!(warning.to_json()["span"], json!({ "start": 0, "end": 3 }));
+		assert_eq!(warning.to_json()["code"], json!("hel::missing_fact"));
+		assert_eq!(location_free.to_json()["span"], Json::Null);
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,530 @@
+//! Shareable evaluation engine
+//!
+//! `HelEngine` is the single integration point for hosts: it owns the built-ins
+//! registry, the schema type environment, the evaluation options, the compiled
+//! rules and a cache of ad-hoc expressions. Configure it once with the `with_*`
+//! builders, then share it (e.g., in an `Arc`) across threads; compiling and
+//! evaluating only need `&self`.
+//!
+//! ## Notes
+//! - Compiling a rule parses it with `EvalOptions::limits` and checks it against the
+//!   registered built-ins and the type environment; only errors reject the rule
+//! - Compiled rules are optimized by folding constants: literal comparisons (`1 < 2`)
+//!   become booleans, and `true`/`false` operands are dropped from `AND`/`OR`/`XOR` where
+//!   that cannot change the result or skip an error
+//! - Compiling an existing rule id replaces the rule, so rules can be reloaded in place
+//! - Rules are evaluated independently: a `rule("id")` reference fails here, use a
+//!   `RuleSet` for rules that depend on each other
+//! - The expression cache holds at most `HelEngine::DEFAULT_CACHE_CAPACITY` entries and
+//!   is cleared when full
+//! - `precompile_all` compiles every rule shipped with the loaded packages and keeps going
+//!   past failures, so a deploy step can report all broken rules at once; package rule
+//!   files hold a single condition (see `PackageManifest::rules`)
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::analysis::{Analyzer, Severity};
+use crate::builtins::BuiltinsRegistry;
+use crate::rewrite::{rewrite_ast, Rewriter};
+use crate::trace::{trace_ast, EvalTrace};
+use crate::{
+	evaluate_ast_with_context, parse_expression_with_limits, AstNode, EvalContext, EvalError, EvalOptions,
+	FactsEvalContext, HelError, HelResolver, PackageRegistry, TypeEnvironment,
+};
+
+// region:    --- HelEngine
+
+/// Thread-safe facade owning everything needed to compile and evaluate rules
+///
+/// # Examples
+///
+/// ```
+/// use hel::{FactsEvalContext, HelEngine, Value};
+/// use std::sync::Arc;
+///
+/// let engine = Arc::new(HelEngine::new());
+/// engine.compile("many-perms", "core.len(manifest.permissions) > 1").unwrap();
+///
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("manifest.permissions", Value::List(vec!["READ_SMS".into(), "SEND_SMS".into()]));
+///
+/// let shared = Arc::clone(&engine);
+/// let matched = std::thread::spawn(move || shared.evaluate("many-perms", &facts).unwrap());
+/// assert!(matched.join().unwrap());
+/// ```
+pub struct HelEngine {
+	builtins: BuiltinsRegistry,
+	types: Option<TypeEnvironment>,
+	options: EvalOptions,
+	/// Compiled rules by id
+	rules: RwLock<BTreeMap<Arc<str>, Arc<AstNode>>>,
+	/// Parsed ad-hoc expressions by source text
+	cache: RwLock<BTreeMap<String, Arc<AstNode>>>,
+}
+
+impl HelEngine {
+	/// Maximum number of cached ad-hoc expressions
+	pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+	/// Create an engine with the `core` built-ins, no types and default options
+	pub fn new() -> Self {
+		let builtins = BuiltinsRegistry::builder()
+			.with_core()
+			.build()
+			.expect("core built-ins register into an empty registry");
+		Self {
+			builtins,
+			types: None,
+			options: EvalOptions::default(),
+			rules: RwLock::new(BTreeMap::new()),
+			cache: RwLock::new(BTreeMap::new()),
+		}
+	}
+
+	/// Replace the built-ins registry (register `CoreBuiltinsProvider` to keep `core`)
+	pub fn with_builtins(mut self, builtins: BuiltinsRegistry) -> Self {
+		self.builtins = builtins;
+		self
+	}
+
+	/// Check rules against a type environment and resolve its `Enum.Variant` constants
+	pub fn with_types(mut self, types: TypeEnvironment) -> Self {
+		self.types = Some(types);
+		self
+	}
+
+	/// Set the options rules are parsed and evaluated with
+	pub fn with_options(mut self, options: EvalOptions) -> Self {
+		self.options = options;
+		self
+	}
+
+	/// The built-ins registry
+	pub fn builtins(&self) -> &BuiltinsRegistry {
+		&self.builtins
+	}
+
+	/// The evaluation options
+	pub fn options(&self) -> &EvalOptions {
+		&self.options
+	}
+
+	/// Parse, check and optimize a rule, then store it under `rule_id` (replacing any previous rule)
+	pub fn compile(&self, rule_id: &str, condition: &str) -> Result<(), HelError> {
+		let ast = self.parse(condition)?;
+		write(&self.rules).insert(Arc::from(rule_id), Arc::new(ast));
+		Ok(())
+	}
+
+	/// Compile every rule shipped with the packages in `registry`
+	///
+	/// Each rule is parsed, checked and optimized as by `compile`. Rules are compiled under their package rule id (`package/file-stem`), in package
+	/// name order and then manifest order. A failing rule does not stop the others; it is
+	/// recorded in the report and any previously compiled rule with that id is kept.
+	pub fn precompile_all(&self, registry: &PackageRegistry) -> PrecompileReport {
+		let mut report = PrecompileReport::default();
+		for package in registry.packages() {
+			for rule in &package.rules {
+				match self.compile(&rule.id, rule.source.trim()) {
+					Ok(()) => report.compiled.push(Arc::from(rule.id.as_str())),
+					Err(error) => report.failures.push(PrecompileFailure {
+						rule_id: Arc::from(rule.id.as_str()),
+						package: package.manifest.name.clone(),
+						file: package.root_path.join(&rule.file),
+						error,
+					}),
+				}
+			}
+		}
+		report
+	}
+
+	/// Remove a compiled rule, returning whether it existed
+	pub fn remove(&self, rule_id: &str) -> bool {
+		write(&self.rules).remove(rule_id).is_some()
+	}
+
+	/// Ids of the compiled rules, sorted
+	pub fn rule_ids(&self) -> Vec<Arc<str>> {
+		read(&self.rules).keys().cloned().collect()
+	}
+
+	/// Evaluate a compiled rule
+	pub fn evaluate(&self, rule_id: &str, resolver: &dyn HelResolver) -> Result<bool, EvalError> {
+		let ast = self.rule(rule_id)?;
+		evaluate_ast_with_context(&ast, &self.context(resolver))
+	}
+
+	/// Evaluate a compiled rule with tracing (see `EvalOptions::trace`)
+	pub fn trace(&self, rule_id: &str, resolver: &dyn HelResolver) -> Result<EvalTrace, EvalError> {
+		let ast = self.rule(rule_id)?;
+		trace_ast(&ast, &self.context(resolver))
+	}
+
+	/// Evaluate every compiled rule, in id order
+	pub fn evaluate_all(&self, resolver: &dyn HelResolver) -> Vec<(Arc<str>, Result<bool, EvalError>)> {
+		let rules: Vec<(Arc<str>, Arc<AstNode>)> = read(&self.rules)
+			.iter()
+			.map(|(id, ast)| (id.clone(), ast.clone()))
+			.collect();
+		let ctx = self.context(resolver);
+		rules
+			.into_iter()
+			.map(|(id, ast)| (id, evaluate_ast_with_context(&ast, &ctx)))
+			.collect()
+	}
+
+	/// Evaluate an ad-hoc expression, parsing and checking it only the first time it is seen
+	pub fn evaluate_expression(&self, condition: &str, resolver: &dyn HelResolver) -> Result<bool, HelError> {
+		let cached = read(&self.cache).get(condition).cloned();
+		let ast = match cached {
+			Some(ast) => ast,
+			None => {
+				let ast = Arc::new(self.parse(condition)?);
+				let mut cache = write(&self.cache);
+				if cache.len() >= Self::DEFAULT_CACHE_CAPACITY {
+					cache.clear();
+				}
+				cache.insert(condition.to_string(), ast.clone());
+				ast
+			}
+		};
+		evaluate_ast_with_context(&ast, &self.context(resolver)).map_err(HelError::from)
+	}
+
+	/// Number of cached ad-hoc expressions
+	pub fn cached_expressions(&self) -> usize {
+		read(&self.cache).len()
+	}
+
+	fn parse(&self, condition: &str) -> Result<AstNode, HelError> {
+		let ast = parse_expression_with_limits(condition, &self.options.limits)?;
+		let mut analyzer = Analyzer::new().with_builtins(&self.builtins);
+		if let Some(types) = &self.types {
+			analyzer = analyzer.with_types(types);
+		}
+		if let Some(error) = analyzer
+			.diagnostics(condition)
+			.into_iter()
+			.find(|d| d.severity == Severity::Error)
+		{
+			return Err(HelError::type_error(error.message));
+		}
+		if let Err(unknown) = self.builtins.validate_expression(&ast) {
+			return Err(HelError::type_error(unknown[0].to_string()));
+		}
+		Ok(rewrite_ast(ast, &mut ConstantFolder { options: &self.options }))
+	}
+
+	fn rule(&self, rule_id: &str) -> Result<Arc<AstNode>, EvalError> {
+		read(&self.rules)
+			.get(rule_id)
+			.cloned()
+			.ok_or_else(|| EvalError::InvalidOperation(format!("rule `{}` is not compiled", rule_id)))
+	}
+
+	fn context<'a>(&'a self, resolver: &'a dyn HelResolver) -> EvalContext<'a> {
+		let ctx = EvalContext::with_builtins(resolver, &self.builtins).with_options(self.options.clone());
+		match &self.types {
+			Some(types) => ctx.with_types(types),
+			None => ctx,
+		}
+	}
+}
+
+impl Default for HelEngine {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// A poisoned lock still holds a consistent map; updates never panic mid-way
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+	lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+	lock.write().unwrap_or_else(|e| e.into_inner())
+}
+
+// endregion: --- HelEngine
+
+// region:    --- ConstantFolder
+
+/// Folds literal comparisons and boolean literals in logical operators, and unwraps
+/// single-operand logical operators
+///
+/// `AND`/`OR` stop at their first deciding operand, so only the operands up to a
+/// literal one are kept; `XOR` evaluates every operand, so its literals fold into one.
+struct ConstantFolder<'a> {
+	options: &'a EvalOptions,
+}
+
+impl Rewriter for ConstantFolder<'_> {
+	fn rewrite(&mut self, node: &AstNode) -> Option<AstNode> {
+		match node {
+			AstNode::Comparison { left, right, .. } if is_literal(left) && is_literal(right) => {
+				let no_facts = FactsEvalContext::new();
+				let ctx = EvalContext::new(&no_facts).with_options(self.options.clone());
+				// An erroring comparison is kept, so the error surfaces at evaluation
+				evaluate_ast_with_context(node, &ctx).ok().map(AstNode::Bool)
+			}
+			AstNode::And(nodes) => fold_short_circuit(nodes, false, AstNode::And),
+			AstNode::Or(nodes) => fold_short_circuit(nodes, true, AstNode::Or),
+			AstNode::Xor(nodes) => {
+				let mut parity = false;
+				let mut rest = Vec::new();
+				for node in nodes {
+					match node {
+						AstNode::Bool(value) => parity ^= value,
+						other => rest.push(other.clone()),
+					}
+				}
+				if rest.len() == nodes.len() && nodes.len() > 1 {
+					return None;
+				}
+				if parity {
+					rest.push(AstNode::Bool(true));
+				}
+				Some(collapse(rest, AstNode::Bool(parity), AstNode::Xor))
+			}
+			_ => None,
+		}
+	}
+}
+
+/// Fold `AND` (`decides` false) or `OR` (`decides` true): drop operands that cannot
+/// decide, and everything after one that always does
+fn fold_short_circuit(nodes: &[AstNode], decides: bool, rebuild: fn(Vec<AstNode>) -> AstNode) -> Option<AstNode> {
+	let mut kept = Vec::new();
+	let mut changed = false;
+	for (i, node) in nodes.iter().enumerate() {
+		match node {
+			AstNode::Bool(value) if *value == decides => {
+				changed |= i + 1 < nodes.len();
+				if kept.is_empty() {
+					return Some(AstNode::Bool(decides));
+				}
+				kept.push(node.clone());
+				break;
+			}
+			AstNode::Bool(_) => changed = true,
+			other => kept.push(other.clone()),
+		}
+	}
+	(changed || nodes.len() == 1).then(|| collapse(kept, AstNode::Bool(!decides), rebuild))
+}
+
+/// No operands become `empty`; a single boolean operand stands alone (others keep the
+/// operator, which turns their value into a boolean)
+fn collapse(mut nodes: Vec<AstNode>, empty: AstNode, rebuild: fn(Vec<AstNode>) -> AstNode) -> AstNode {
+	match nodes.as_slice() {
+		[] => empty,
+		[AstNode::Bool(_) | AstNode::Comparison { .. } | AstNode::And(_) | AstNode::Or(_) | AstNode::Xor(_)] => {
+			nodes.remove(0)
+		}
+		_ => rebuild(nodes),
+	}
+}
+
+fn is_literal(node: &AstNode) -> bool {
+	match node {
+		AstNode::Bool(_) | AstNode::String(_) | AstNode::Number(_) | AstNode::Float(_) => true,
+		AstNode::ListLiteral(items) => items.iter().all(is_literal),
+		_ => false,
+	}
+}
+
+// endregion: --- ConstantFolder
+
+// region:    --- PrecompileReport
+
+/// Outcome of `HelEngine::precompile_all`
+#[derive(Debug, Clone, Default)]
+pub struct PrecompileReport {
+	/// Ids of the rules that compiled, in compilation order
+	pub compiled: Vec<Arc<str>>,
+	/// Rules that failed to parse or check
+	pub failures: Vec<PrecompileFailure>,
+}
+
+impl PrecompileReport {
+	/// Whether every packaged rule compiled
+	pub fn is_ok(&self) -> bool {
+		self.failures.is_empty()
+	}
+}
+
+/// A packaged rule that failed to compile
+#[derive(Debug, Clone)]
+pub struct PrecompileFailure {
+	/// Rule id (`package/file-stem`)
+	pub rule_id: Arc<str>,
+	/// Package the rule ships with
+	pub package: String,
+	/// Path of the rule file
+	pub file: PathBuf,
+	/// Parse or check error
+	pub error: HelError,
+}
+
+impl std::fmt::Display for PrecompileFailure {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} ({}): {}", self.rule_id, self.file.display(), self.error.message)
+	}
+}
+
+// endregion: --- PrecompileReport
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{parse_schema, FactsEvalContext, Value};
+
+	fn facts(entropy: f64) -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(entropy));
+		ctx.add_fact("binary.format", Value::String("pe".into()));
+		ctx
+	}
+
+	#[test]
+	fn test_engine_compile_and_evaluate() {
+		fn assert_send_sync<T: Send + Sync>() {}
+		assert_send_sync::<HelEngine>();
+
+		let engine = HelEngine::new().with_options(EvalOptions::new().with_numeric_tolerance(1e-9));
+		engine.compile("packed", "binary.entropy > 7.5").unwrap();
+		engine.compile("pe", r#"core.lower(binary.format) == "pe""#).unwrap();
+		assert!(engine.compile("broken", "binary.entropy >").is_err());
+		assert!(engine.compile("unknown", "core.nope(binary.format) == 1").is_err());
+		let err = engine.compile("unqualified", "lowr(binary.format) == \"pe\"").unwrap_err();
+		assert_eq!(err.message, "Unknown function `lowr` (did you mean `lower`?)");
+		assert_eq!(engine.rule_ids(), [Arc::from("packed"), Arc::from("pe")]);
+
+		assert!(engine.evaluate("packed", &facts(7.9)).unwrap());
+		assert!(!engine.evaluate("packed", &facts(3.0)).unwrap());
+		assert!(engine.evaluate("missing", &facts(7.9)).is_err());
+		let results = engine.evaluate_all(&facts(3.0));
+		assert_eq!(results.len(), 2);
+		assert!(matches!(results[0].1, Ok(false)));
+		assert!(matches!(results[1].1, Ok(true)));
+		assert!(engine.trace("packed", &facts(7.9)).unwrap().result);
+
+		// Recompiling replaces the rule
+		engine.compile("packed", "binary.entropy > 2").unwrap();
+		assert!(engine.evaluate("packed", &facts(3.0)).unwrap());
+		assert!(engine.remove("packed"));
+		assert!(!engine.remove("packed"));
+
+		// Ad-hoc expressions are parsed once and evaluated with the engine's options
+		let expr = "binary.entropy == 0.3";
+		assert!(engine.evaluate_expression(expr, &facts(0.1 + 0.2)).unwrap());
+		assert!(engine.evaluate_expression(expr, &facts(0.1 + 0.2)).unwrap());
+		assert_eq!(engine.cached_expressions(), 1);
+	}
+
+	#[test]
+	fn test_engine_shared_across_threads() {
+		let engine = Arc::new(HelEngine::new());
+		engine.compile("packed", "binary.entropy > 7.5").unwrap();
+
+		let handles: Vec<_> = (0..4)
+			.map(|i| {
+				let engine = Arc::clone(&engine);
+				std::thread::spawn(move || {
+					engine.compile(&format!("rule-{}", i), "binary.entropy > 1").unwrap();
+					engine.evaluate("packed", &facts(7.0 + i as f64 / 2.0)).unwrap()
+				})
+			})
+			.collect();
+		let results: Vec<bool> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+		assert_eq!(results, [false, false, true, true]);
+		assert_eq!(engine.rule_ids().len(), 5);
+	}
+
+	#[test]
+	fn test_engine_types() {
+		let schema =
+			parse_schema("type Binary {\n  format: String\n  entropy: Number\n}\n\nenum Format {\n  Pe = \"pe\"\n}\n")
+				.expect("schema failed");
+		let qualify = |name: Arc<str>| Arc::from(format!("security-binary.{}", name));
+		let types = TypeEnvironment {
+			types: schema.types.into_iter().map(|(n, d)| (qualify(n), d)).collect(),
+			enums: schema.enums.into_iter().map(|(n, d)| (qualify(n), d)).collect(),
+		};
+		let engine = HelEngine::new().with_types(types);
+
+		let err = engine.compile("arch", r#"binary.arch == "x86""#).unwrap_err();
+		assert!(err.message.contains("arch"), "{}", err.message);
+		engine.compile("pe", "binary.format == Format.Pe").unwrap();
+		assert!(engine.evaluate("pe", &facts(1.0)).unwrap());
+	}
+
+	#[test]
+	fn test_engine_precompile_all() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = tempfile::TempDir::new()?;
+		let dir = temp.path().join("security-binary");
+		std::fs::create_dir_all(dir.join("rules"))?;
+		std::fs::write(
+			dir.join("hel-package.toml"),
+			"name = \"security-binary\"\nversion = \"0.1.0\"\nschemas = []\nrules = [\"rules/packed.hel\", \"rules/broken.hel\"]\n",
+		)?;
+		std::fs::write(dir.join("rules/packed.hel"), "binary.entropy > 7.5\n")?;
+		std::fs::write(dir.join("rules/broken.hel"), "core.nope(binary.format) == 1\n")?;
+
+		let mut registry = PackageRegistry::new();
+		registry.add_search_path(temp.path().to_path_buf());
+		registry.load_package("security-binary")?;
+
+		let engine = HelEngine::new();
+		let report = engine.precompile_all(&registry);
+		assert!(!report.is_ok());
+		assert_eq!(report.compiled, [Arc::from("security-binary/packed")]);
+		assert_eq!(report.failures.len(), 1);
+		let failure = &report.failures[0];
+		assert_eq!(&*failure.rule_id, "security-binary/broken");
+		assert!(failure.file.ends_with("rules/broken.hel"));
+		assert!(failure.to_string().contains("core.nope"), "{}", failure);
+
+		assert_eq!(engine.rule_ids(), [Arc::from("security-binary/packed")]);
+		assert!(engine.evaluate("security-binary/packed", &facts(7.9))?);
+		Ok(())
+	}
+
+	#[test]
+	fn test_engine_folds_constants() {
+		let engine = HelEngine::new();
+		let compiled = |condition: &str| {
+			engine.compile("rule", condition).unwrap();
+			(*read(&engine.rules)["rule"]).clone()
+		};
+		let packed = compiled("binary.entropy > 7.5");
+		assert!(matches!(packed, AstNode::Comparison { .. }));
+
+		assert_eq!(compiled("1 < 2 AND binary.entropy > 7.5 AND true"), packed);
+		assert_eq!(compiled("false OR binary.entropy > 7.5"), packed);
+		assert_eq!(compiled("true XOR binary.entropy > 7.5 XOR true"), packed);
+		assert_eq!(compiled(r#""a" IN ["a", "b"] OR binary.entropy > 7.5"#), AstNode::Bool(true));
+		assert_eq!(
+			compiled("binary.entropy > 7.5 AND false AND binary.entropy < 9"),
+			AstNode::And(vec![packed.clone(), AstNode::Bool(false)])
+		);
+		// A lone non-boolean operand keeps its operator, which makes it a boolean
+		assert_eq!(
+			compiled("binary.format AND true"),
+			AstNode::And(vec![AstNode::Attribute {
+				object: "binary".into(),
+				field: "format".into(),
+			}])
+		);
+		compiled("1 < 2 AND binary.entropy > 0.5");
+		assert!(engine.evaluate("rule", &facts(1.0)).unwrap());
+	}
+}
+
+// endregion: --- Tests
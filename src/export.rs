@@ -0,0 +1,696 @@
+//! Policy export for enforcement points that cannot link Rust
+//!
+//! `RuleSet::to_json_logic()` translates every rule into a JsonLogic expression and
+//! `RuleSet::to_rego()` generates an OPA Rego module stub. Both translations are
+//! approximations: every place where the target's semantics may differ from HEL is
+//! recorded as a `FidelityNote` rather than silently dropped.
+//!
+//! ## JsonLogic Mapping
+//! - `AND` / `OR` → `and` / `or`; `==` / `!=` → strict `===` / `!==`
+//! - `a.b` → `{"var": "a.b"}`; `x IN list` → `{"in": [x, list]}`; `l CONTAINS x` → `{"in": [x, l]}`
+//! - Function calls become custom operations (`{"core.len": [..]}`) the host must register
+//!
+//! ## Rego Mapping
+//! - Each rule becomes `rule_name if { .. }`; `OR` becomes one body per alternative,
+//!   nested `OR` inside `AND` becomes a helper rule
+//! - Rule and helper names are unique: a colliding name gets a numeric suffix
+//! - `a.b` → `input.a.b`; `core.len` / `core.upper` / `core.lower` → `count` / `upper` / `lower`
+
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+
+use serde_json::{json, Value as Json};
+
+use crate::prelude::*;
+use crate::rules::RuleSet;
+use crate::trace::comparator_to_str;
+use crate::{AstNode, Comparator};
+
+// region:    --- Types
+
+/// A lossy translation detail for one rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FidelityNote {
+	/// Rule id
+	pub rule: Arc<str>,
+	/// What may behave differently in the target
+	pub message: String,
+}
+
+/// Rule set exported to JsonLogic
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonLogicExport {
+	/// JsonLogic expression per rule, in rule order
+	pub rules: Vec<(Arc<str>, Json)>,
+	/// Lossy translations
+	pub fidelity: Vec<FidelityNote>,
+}
+
+impl JsonLogicExport {
+	/// Rules as a single JSON object keyed by rule id
+	pub fn to_json(&self) -> Json {
+		Json::Object(
+			self.rules
+				.iter()
+				.map(|(id, logic)| (id.to_string(), logic.clone()))
+				.collect(),
+		)
+	}
+}
+
+/// Rule set exported as an OPA Rego module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegoExport {
+	/// Rego module source
+	pub module: String,
+	/// Lossy translations
+	pub fidelity: Vec<FidelityNote>,
+}
+
+// endregion: --- Types
+
+// region:    --- RuleSet Export
+
+impl RuleSet {
+	/// Translate every rule to JsonLogic
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::RuleSet;
+	/// use serde_json::json;
+	///
+	/// let mut rules = RuleSet::new();
+	/// rules.add_rule("sms", r#"app.perms CONTAINS "READ_SMS" AND app.rating < 3"#).unwrap();
+	///
+	/// let export = rules.to_json_logic();
+	/// assert_eq!(
+	///     export.to_json(),
+	///     json!({"sms": {"and": [{"in": ["READ_SMS", {"var": "app.perms"}]}, {"<": [{"var": "app.rating"}, 3]}]}})
+	/// );
+	/// assert_eq!(export.fidelity.len(), 1); // CONTAINS on maps checks keys in HEL
+	/// ```
+	pub fn to_json_logic(&self) -> JsonLogicExport {
+		let mut fidelity = Vec::new();
+		let rules = self
+			.rules()
+			.iter()
+			.map(|rule| {
+				let mut notes = Notes::new(&rule.id, &mut fidelity);
+				(rule.id.clone(), json_logic(&rule.condition, &mut notes))
+			})
+			.collect();
+		JsonLogicExport { rules, fidelity }
+	}
+
+	/// Generate an OPA Rego module with one rule per HEL rule
+	///
+	/// Rule ids are turned into Rego identifiers (`is-elf` → `is_elf`). Ids that
+	/// map to an already used name get a numeric suffix (`is_elf_2`), noted in
+	/// `fidelity`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::RuleSet;
+	///
+	/// let mut rules = RuleSet::new();
+	/// rules.add_rule("is-elf", r#"binary.format == "elf" OR binary.magic == 127"#).unwrap();
+	///
+	/// let export = rules.to_rego("hel.rules");
+	/// assert!(export.module.contains("is_elf if {\n\tinput.binary.format == \"elf\"\n}"));
+	/// assert!(export.module.contains("is_elf if {\n\tinput.binary.magic == 127\n}"));
+	/// ```
+	pub fn to_rego(&self, package: &str) -> RegoExport {
+		let mut fidelity = Vec::new();
+		let mut module = format!("package {}\n\nimport rego.v1\n", package);
+		// Name every rule before generating helpers, so helpers never take a rule's name
+		let mut taken = BTreeSet::new();
+		let names: Vec<String> = self
+			.rules()
+			.iter()
+			.map(|rule| unique_name(rego_name(&rule.id), &mut taken))
+			.collect();
+		for (rule, name) in self.rules().iter().zip(names) {
+			let mut notes = Notes::new(&rule.id, &mut fidelity);
+			if name != rego_name(&rule.id) {
+				notes.push(format!("rule name collides with another rule; exported as `{}`", name));
+			}
+			let mut exporter = RegoRule {
+				name,
+				notes,
+				helpers: Vec::new(),
+				next_helper: 1,
+				taken: &mut taken,
+			};
+			let bodies = exporter.bodies(&rule.condition);
+			let name = exporter.name.clone();
+			module.push_str(&format!("\n# {}\n", rule.source.lines().collect::<Vec<_>>().join(" ")));
+			push_rego_rule(&mut module, &name, &bodies);
+			for (helper, bodies) in exporter.helpers {
+				push_rego_rule(&mut module, &helper, &bodies);
+			}
+		}
+		RegoExport { module, fidelity }
+	}
+}
+
+// endregion: --- RuleSet Export
+
+// region:    --- JsonLogic
+
+fn json_logic(node: &AstNode, notes: &mut Notes) -> Json {
+	match node {
+		AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) if nodes.len() == 1 => {
+			json_logic(&nodes[0], notes)
+		}
+		AstNode::And(nodes) => json!({ "and": nodes.iter().map(|n| json_logic(n, notes)).collect::<Vec<_>>() }),
+		AstNode::Or(nodes) => json!({ "or": nodes.iter().map(|n| json_logic(n, notes)).collect::<Vec<_>>() }),
+		// XOR as pairwise inequality of truth values
+		AstNode::Xor(nodes) => nodes
+			.iter()
+			.map(|n| json_logic(n, notes))
+			.reduce(|acc, operand| json!({ "!==": [acc, operand] }))
+			.unwrap_or(json!(false)),
+		AstNode::Bool(b) => json!(b),
+		AstNode::String(s) => json!(s.as_ref()),
+		AstNode::Number(n) => json!(n),
+		AstNode::Float(f) => serde_json::Number::from_f64(*f).map(Json::Number).unwrap_or_else(|| {
+			notes.push(format!("non-finite number {} exported as null", f));
+			Json::Null
+		}),
+		AstNode::Identifier(name) if name.starts_with('$') || name.starts_with('%') => {
+			notes.push(format!("`{}` is read from the data object (bind it there)", name));
+			json!({ "var": name.as_ref() })
+		}
+		// Unbound bare identifiers evaluate to their own name
+		AstNode::Identifier(name) => json!(name.as_ref()),
+		AstNode::Attribute { object, field } => json!({ "var": format!("{}.{}", object, field) }),
+		AstNode::Comparison { left, op, right, .. } => {
+			// JsonLogic's three-argument `<=` is an inclusive between
+			if let (Comparator::In, AstNode::Range { start, end }) = (op, &**right) {
+				let (l, start, end) = (
+					json_logic(left, notes),
+					json_logic(start, notes),
+					json_logic(end, notes),
+				);
+				return json!({ "<=": [start, l, end] });
+			}
+			let (l, r) = (json_logic(left, notes), json_logic(right, notes));
+			match op {
+				Comparator::Eq => json!({ "===": [l, r] }),
+				Comparator::Ne => json!({ "!==": [l, r] }),
+				Comparator::In => json!({ "in": [l, r] }),
+				Comparator::Like => {
+					notes.push("`like` (glob match) must be registered as a custom JsonLogic operation".to_string());
+					json!({ "like": [l, r] })
+				}
+				Comparator::EqIgnoreCase => {
+					notes.push("`core.lower` must be registered as a custom JsonLogic operation".to_string());
+					json!({ "===": [{ "core.lower": [l] }, { "core.lower": [r] }] })
+				}
+				Comparator::Contains => {
+					notes.push("CONTAINS on a map checks keys in HEL; JsonLogic `in` does not".to_string());
+					json!({ "in": [r, l] })
+				}
+				Comparator::Gt | Comparator::Ge | Comparator::Lt | Comparator::Le => {
+					json!({ comparator_to_str(*op): [l, r] })
+				}
+			}
+		}
+		AstNode::ListLiteral(items) => Json::Array(items.iter().map(|i| json_logic(i, notes)).collect()),
+		AstNode::MapLiteral(_) => {
+			notes.push("map literal has no JsonLogic equivalent; exported as null".to_string());
+			Json::Null
+		}
+		AstNode::FunctionCall { namespace, name, args, .. } => {
+			let op = match namespace {
+				Some(ns) => format!("{}.{}", ns, name),
+				None => name.to_string(),
+			};
+			notes.push(format!("`{}` must be registered as a custom JsonLogic operation", op));
+			json!({ op: args.iter().map(|a| json_logic(a, notes)).collect::<Vec<_>>() })
+		}
+		AstNode::Range { .. } => {
+			notes.push("range outside of IN has no JsonLogic equivalent; exported as null".to_string());
+			Json::Null
+		}
+		AstNode::Wildcard { object, field } => {
+			notes.push(format!(
+				"wildcard `{}.*.{}` has no JsonLogic equivalent; exported as null",
+				object, field
+			));
+			Json::Null
+		}
+	}
+}
+
+// endregion: --- JsonLogic
+
+// region:    --- Rego
+
+struct RegoRule<'n> {
+	name: String,
+	notes: Notes<'n>,
+	helpers: Vec<(String, Vec<Vec<String>>)>,
+	next_helper: usize,
+	/// Rule and helper names used so far in the module
+	taken: &'n mut BTreeSet<String>,
+}
+
+impl RegoRule<'_> {
+	/// Disjunction of rule bodies, each a conjunction of Rego expressions
+	fn bodies(&mut self, node: &AstNode) -> Vec<Vec<String>> {
+		match collapse(node) {
+			AstNode::Or(nodes) if !nodes.is_empty() => nodes.iter().flat_map(|n| self.bodies(n)).collect(),
+			AstNode::Xor(nodes) if !nodes.is_empty() => self.xor_bodies(nodes),
+			AstNode::Or(_) | AstNode::Xor(_) => vec![vec!["false".to_string()]],
+			node => vec![self.conjunction(node)],
+		}
+	}
+
+	/// One body per assignment where an odd number of operands hold, the others negated
+	fn xor_bodies(&mut self, nodes: &[AstNode]) -> Vec<Vec<String>> {
+		let terms: Vec<String> = nodes
+			.iter()
+			.map(|n| match self.conjunction(n).as_slice() {
+				[single] => single.clone(),
+				exprs => self.helper(vec![exprs.to_vec()]),
+			})
+			.collect();
+		(0u64..1 << terms.len())
+			.filter(|mask| mask.count_ones() % 2 == 1)
+			.map(|mask| {
+				terms
+					.iter()
+					.enumerate()
+					.map(|(i, term)| match mask & (1 << i) {
+						0 => format!("not {}", term),
+						_ => term.clone(),
+					})
+					.collect()
+			})
+			.collect()
+	}
+
+	/// Name a new helper rule with the given bodies
+	fn helper(&mut self, bodies: Vec<Vec<String>>) -> String {
+		let helper = self.helper_name();
+		self.helpers.push((helper.clone(), bodies));
+		helper
+	}
+
+	/// Next `{name}_{n}` not used by a rule or another helper
+	fn helper_name(&mut self) -> String {
+		loop {
+			let helper = format!("{}_{}", self.name, self.next_helper);
+			self.next_helper += 1;
+			if self.taken.insert(helper.clone()) {
+				return helper;
+			}
+		}
+	}
+
+	fn conjunction(&mut self, node: &AstNode) -> Vec<String> {
+		match collapse(node) {
+			AstNode::And(nodes) => nodes.iter().flat_map(|n| self.conjunction(n)).collect(),
+			AstNode::Or(nodes) | AstNode::Xor(nodes) if nodes.len() > 1 => {
+				// Reserve the helper slot first so nested helpers are numbered after it
+				let index = self.helpers.len();
+				let helper = self.helper_name();
+				self.helpers.push((helper.clone(), Vec::new()));
+				self.helpers[index].1 = self.bodies(node);
+				vec![helper]
+			}
+			node => self.range(node).unwrap_or_else(|| vec![self.condition(node)]),
+		}
+	}
+
+	/// `x IN lo..hi` as two bound checks
+	fn range(&mut self, node: &AstNode) -> Option<Vec<String>> {
+		let AstNode::Comparison {
+			left,
+			op: Comparator::In,
+			right,
+			..
+		} = node
+		else {
+			return None;
+		};
+		let AstNode::Range { start, end } = collapse(right) else {
+			return None;
+		};
+		let (start, end) = (self.operand(start), self.operand(end));
+		// `[_]` would pick a member per bound; a helper binds one member for both
+		if let AstNode::Wildcard { object, field } = &**left {
+			let member = format!("input.{}[k].{}", object, field);
+			let body = vec![format!("{} <= {}", start, member), format!("{} <= {}", member, end)];
+			return Some(vec![self.helper(vec![body])]);
+		}
+		let value = self.operand(left);
+		Some(vec![format!("{} <= {}", start, value), format!("{} <= {}", value, end)])
+	}
+
+	fn condition(&mut self, node: &AstNode) -> String {
+		let AstNode::Comparison { left, op, right, .. } = node else {
+			return self.operand(node);
+		};
+		let (l, r) = (self.operand(left), self.operand(right));
+		match op {
+			Comparator::In => {
+				if !matches!(collapse(right), AstNode::ListLiteral(_)) {
+					self.notes
+						.push("IN exported as membership; HEL substring IN on strings differs".to_string());
+				}
+				format!("{} in {}", l, r)
+			}
+			Comparator::Contains => {
+				self.notes.push(
+					"CONTAINS exported as membership; use contains() for strings, object keys differ".to_string(),
+				);
+				format!("{} in {}", r, l)
+			}
+			Comparator::Ne => {
+				self.notes
+					.push("`!=` on a missing attribute is undefined in Rego (true in HEL)".to_string());
+				format!("{} != {}", l, r)
+			}
+			Comparator::Like => match collapse(right) {
+				AstNode::String(pattern) => format!("glob.match({}, [], {})", json!(rego_glob(pattern)), l),
+				_ => {
+					self.notes
+						.push("LIKE with a non-literal pattern: Rego glob syntax differs from HEL".to_string());
+					format!("glob.match({}, [], {})", r, l)
+				}
+			},
+			Comparator::EqIgnoreCase => {
+				self.notes
+					.push("EQI exported with lower(), which is undefined for non-string values".to_string());
+				format!("lower({}) == lower({})", l, r)
+			}
+			Comparator::Eq | Comparator::Gt | Comparator::Ge | Comparator::Lt | Comparator::Le => {
+				format!("{} {} {}", l, comparator_to_str(*op), r)
+			}
+		}
+	}
+
+	fn operand(&mut self, node: &AstNode) -> String {
+		match collapse(node) {
+			AstNode::Bool(b) => b.to_string(),
+			AstNode::String(s) => json!(s.as_ref()).to_string(),
+			AstNode::Number(n) => n.to_string(),
+			AstNode::Float(f) => format!("{:?}", f),
+			AstNode::Identifier(name) if name.starts_with('$') || name.starts_with('%') => {
+				self.notes.push(format!("`{}` is read from data.hel.variables", name));
+				format!("data.hel.variables[{}]", json!(name.as_ref()))
+			}
+			AstNode::Identifier(name) => json!(name.as_ref()).to_string(),
+			AstNode::Attribute { object, field } => format!("input.{}.{}", object, field),
+			// `_` iterates the object's members, so a comparison holds if any member matches
+			AstNode::Wildcard { object, field } => format!("input.{}[_].{}", object, field),
+			AstNode::ListLiteral(items) => {
+				format!(
+					"[{}]",
+					items.iter().map(|i| self.operand(i)).collect::<Vec<_>>().join(", ")
+				)
+			}
+			AstNode::MapLiteral(entries) => format!(
+				"{{{}}}",
+				entries
+					.iter()
+					.map(|(k, v)| format!("{}: {}", json!(k.as_ref()), self.operand(v)))
+					.collect::<Vec<_>>()
+					.join(", ")
+			),
+			AstNode::FunctionCall { namespace, name, args, .. } => {
+				let args = args.iter().map(|a| self.operand(a)).collect::<Vec<_>>();
+				match (namespace.as_deref(), name.as_ref()) {
+					(Some("core"), "len") => format!("count({})", args.join(", ")),
+					(Some("core"), "upper" | "lower") => format!("{}({})", name, args.join(", ")),
+					(namespace, _) => {
+						let function = match namespace {
+							Some(ns) => format!("{}.{}", ns, name),
+							None => name.to_string(),
+						};
+						self.notes
+							.push(format!("`{}` must be provided as a Rego function", function));
+						format!("{}({})", function, args.join(", "))
+					}
+				}
+			}
+			AstNode::Range { start, end } => {
+				self.notes
+					.push("range outside of IN has no Rego equivalent; exported as numbers.range".to_string());
+				format!("numbers.range({}, {})", self.operand(start), self.operand(end))
+			}
+			AstNode::Comparison { .. } | AstNode::And(_) | AstNode::Or(_) | AstNode::Xor(_) => {
+				self.notes.push(
+					"boolean sub-expression used as a value has no Rego equivalent; exported as false".to_string(),
+				);
+				"false".to_string()
+			}
+		}
+	}
+}
+
+fn push_rego_rule(module: &mut String, name: &str, bodies: &[Vec<String>]) {
+	for body in bodies {
+		module.push_str(&format!("{} if {{\n", name));
+		for expr in body {
+			module.push_str(&format!("\t{}\n", expr));
+		}
+		module.push_str("}\n");
+	}
+}
+
+/// `name`, or `name_{n}` for the smallest `n >= 2` not yet taken
+fn unique_name(name: String, taken: &mut BTreeSet<String>) -> String {
+	if taken.insert(name.clone()) {
+		return name;
+	}
+	(2..)
+		.map(|n| format!("{}_{}", name, n))
+		.find(|candidate| taken.insert(candidate.clone()))
+		.expect("unbounded suffixes")
+}
+
+/// Rego identifier for a rule id
+fn rego_name(id: &str) -> String {
+	let name: String = id
+		.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() {
+				c.to_ascii_lowercase()
+			} else {
+				'_'
+			}
+		})
+		.collect();
+	if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+		name
+	} else {
+		format!("rule_{}", name)
+	}
+}
+
+// endregion: --- Rego
+
+// region:    --- Support
+
+/// Deduplicating fidelity note collector for one rule
+struct Notes<'n> {
+	rule: Arc<str>,
+	out: &'n mut Vec<FidelityNote>,
+}
+
+impl<'n> Notes<'n> {
+	fn new(rule: &Arc<str>, out: &'n mut Vec<FidelityNote>) -> Self {
+		Self {
+			rule: rule.clone(),
+			out,
+		}
+	}
+
+	fn push(&mut self, message: String) {
+		if !self.out.iter().any(|n| n.rule == self.rule && n.message == message) {
+			self.out.push(FidelityNote {
+				rule: self.rule.clone(),
+				message,
+			});
+		}
+	}
+}
+
+/// Rego glob pattern matching like a HEL glob (only `*` and `?` are special)
+fn rego_glob(glob: &str) -> String {
+	let mut out = String::new();
+	for c in glob.chars() {
+		if "\\[]{}".contains(c) {
+			out.push('\\');
+		}
+		out.push(c);
+	}
+	out
+}
+
+fn collapse(mut node: &AstNode) -> &AstNode {
+	while let AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) = node {
+		match nodes.as_slice() {
+			[single] => node = single,
+			_ => break,
+		}
+	}
+	node
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_json_logic_export() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("a", r#"x.kind IN ["apk", "dex"] OR (x.size >= 10 AND x.name != "y")"#)
+			.unwrap();
+		rules.add_rule("b", "core.len(x.perms) > $limit").unwrap();
+
+		let export = rules.to_json_logic();
+		assert_eq!(export.rules[0].0.as_ref(), "a");
+		assert_eq!(
+			export.rules[0].1,
+			json!({"or": [
+				{"in": [{"var": "x.kind"}, ["apk", "dex"]]},
+				{"and": [{">=": [{"var": "x.size"}, 10]}, {"!==": [{"var": "x.name"}, "y"]}]}
+			]})
+		);
+		assert_eq!(
+			export.rules[1].1,
+			json!({">": [{"core.len": [{"var": "x.perms"}]}, {"var": "$limit"}]})
+		);
+		let notes: Vec<&str> = export.fidelity.iter().map(|n| n.rule.as_ref()).collect();
+		assert_eq!(notes, vec!["b", "b"]);
+
+		let mut rules = RuleSet::new();
+		rules.add_rule("x", "a.b XOR c.d == 1").unwrap();
+		assert_eq!(
+			rules.to_json_logic().rules[0].1,
+			json!({"!==": [{"var": "a.b"}, {"===": [{"var": "c.d"}, 1]}]})
+		);
+
+		let mut rules = RuleSet::new();
+		rules.add_rule("ports", "net.port BETWEEN 1024 AND 49151").unwrap();
+		let export = rules.to_json_logic();
+		assert_eq!(export.rules[0].1, json!({"<=": [1024, {"var": "net.port"}, 49151]}));
+		assert!(export.fidelity.is_empty());
+	}
+
+	#[test]
+	fn test_rego_export() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("2fa-bypass", r#"a.b == 1 AND (c.d CONTAINS "x" OR core.len(e.f) < 2)"#)
+			.unwrap();
+
+		let export = rules.to_rego("hel.policies");
+		assert_eq!(
+			export.module,
+			"package hel.policies\n\nimport rego.v1\n\n# a.b == 1 AND (c.d CONTAINS \"x\" OR core.len(e.f) < 2)\n\
+			 rule_2fa_bypass if {\n\tinput.a.b == 1\n\trule_2fa_bypass_1\n}\n\
+			 rule_2fa_bypass_1 if {\n\t\"x\" in input.c.d\n}\n\
+			 rule_2fa_bypass_1 if {\n\tcount(input.e.f) < 2\n}\n"
+		);
+		assert_eq!(export.fidelity.len(), 1);
+		assert_eq!(export.fidelity[0].rule.as_ref(), "2fa-bypass");
+
+		let mut rules = RuleSet::new();
+		rules.add_rule("lolbin", r#"p.path LIKE "C:\Win*\[x]?.exe""#).unwrap();
+		let export = rules.to_rego("hel.policies");
+		assert!(
+			export
+				.module
+				.contains(r#"glob.match("C:\\\\Win*\\\\\\[x\\]?.exe", [], input.p.path)"#),
+			"{}",
+			export.module
+		);
+		assert!(export.fidelity.is_empty());
+
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("signed", "a.signed == true XOR (a.packed == true AND a.size > 10)")
+			.unwrap();
+		let export = rules.to_rego("hel.policies");
+		assert!(
+			export.module.ends_with(
+				"signed if {\n\tinput.a.signed == true\n\tnot signed_1\n}\n\
+				 signed if {\n\tnot input.a.signed == true\n\tsigned_1\n}\n\
+				 signed_1 if {\n\tinput.a.packed == true\n\tinput.a.size > 10\n}\n"
+			),
+			"{}",
+			export.module
+		);
+
+		let mut rules = RuleSet::new();
+		rules.add_rule("ports", "net.port IN 1024..49151").unwrap();
+		let export = rules.to_rego("hel.policies");
+		assert!(
+			export
+				.module
+				.contains("ports if {\n\t1024 <= input.net.port\n\tinput.net.port <= 49151\n}"),
+			"{}",
+			export.module
+		);
+
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("packed", "sections.*.entropy > 7.5 AND sections.*.size IN 0..10")
+			.unwrap();
+		let export = rules.to_rego("hel.policies");
+		assert!(
+			export.module.contains("input.sections[_].entropy > 7.5")
+				&& export
+					.module
+					.contains("0 <= input.sections[k].size\n\tinput.sections[k].size <= 10"),
+			"{}",
+			export.module
+		);
+		let logic = rules.to_json_logic();
+		assert!(
+			logic.fidelity.iter().any(|n| n.message.contains("sections.*.entropy")),
+			"{:?}",
+			logic.fidelity
+		);
+	}
+
+	#[test]
+	fn test_rego_names_are_unique() {
+		let mut rules = RuleSet::new();
+		rules.add_rule("is-elf", "a.x == 1 AND (a.y == 1 OR a.z == 1)").unwrap();
+		rules.add_rule("is_elf", "a.w == 1").unwrap();
+		rules.add_rule("is_elf_1", "a.v == 1").unwrap();
+
+		let export = rules.to_rego("hel.policies");
+		let heads: BTreeSet<&str> = export
+			.module
+			.lines()
+			.filter_map(|line| line.strip_suffix(" if {"))
+			.collect();
+		assert_eq!(
+			heads,
+			BTreeSet::from(["is_elf", "is_elf_1", "is_elf_2", "is_elf_3"]),
+			"{}",
+			export.module
+		);
+		assert!(export.module.contains("is_elf if {\n\tinput.a.x == 1\n\tis_elf_3\n}"));
+		assert!(export.module.contains("is_elf_2 if {\n\tinput.a.w == 1\n}"));
+		assert!(export.module.contains("is_elf_1 if {\n\tinput.a.v == 1\n}"));
+		assert_eq!(export.fidelity.len(), 1);
+		assert_eq!(export.fidelity[0].rule.as_ref(), "is_elf");
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,427 @@
+//! Shared fact contexts for concurrent evaluation
+//!
+//! Hosts often evaluate many requests against a large, mostly static fact
+//! baseline (threat intel, asset inventory) plus a handful of request-specific
+//! facts. `SharedFactsContext` keeps the baseline behind an `Arc` so that each
+//! evaluation only pays for its own overlay instead of cloning every fact.
+//!
+//! ## Determinism
+//! - Overlay facts shadow baseline facts with the same key
+//! - All maps are BTreeMaps for stable iteration order
+//!
+//! ## Flattening
+//! Structured host data is flattened into dotted facts the same way for every
+//! loader (`from_serializable`, `from_json`, `from_yaml`, `from_toml`):
+//! - Top-level keys containing a dot are used as fact names directly
+//! - Top-level objects become `object.field` facts, one per entry
+//! - Field values keep their shape: lists stay `Value::List`, maps stay `Value::Map`
+//!
+//! ## Plain Maps
+//! `BTreeMap<String, Value>` and (with `std`) `HashMap<String, Value>` keyed by
+//! `object.field` are resolvers themselves, so hosts need no wrapper type for a
+//! fixed set of facts.
+//!
+//! ## Wildcards
+//! `object.*.field` matches the `field` entry of every map-valued `object.<name>` fact, so
+//! `{"sections": {"text": {"entropy": 6.1}}}` yields the match `sections.text.entropy`.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::{FactsEvalContext, HelError, HelResolver, Value};
+
+// region:    --- SharedFactsContext
+
+/// Copy-on-write facts context: an immutable shared baseline plus a local overlay
+///
+/// Cloning and overlaying are O(overlay size); the baseline is never copied.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_with_resolver, FactsEvalContext, SharedFactsContext, Value};
+///
+/// let mut baseline = FactsEvalContext::new();
+/// baseline.add_fact("policy.max_entropy", Value::Number(7.5));
+/// let shared = SharedFactsContext::from(baseline);
+///
+/// // Per-request overlay; `shared` itself is unchanged
+/// let request = shared.overlay().with_fact("binary.entropy", Value::Number(8.1));
+///
+/// assert!(evaluate_with_resolver("binary.entropy > policy.max_entropy", &request).unwrap());
+/// assert!(shared.get("binary.entropy").is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedFactsContext {
+	base: Arc<BTreeMap<String, Value>>,
+	overlay: BTreeMap<String, Value>,
+}
+
+impl SharedFactsContext {
+	/// Create a shared context from a baseline fact map
+	pub fn new(facts: BTreeMap<String, Value>) -> Self {
+		Self {
+			base: Arc::new(facts),
+			overlay: BTreeMap::new(),
+		}
+	}
+
+	/// Create a cheap child context sharing this context's facts
+	pub fn overlay(&self) -> Self {
+		self.clone()
+	}
+
+	/// Add a fact to the overlay (builder style)
+	pub fn with_fact(mut self, key: &str, value: Value) -> Self {
+		self.add_fact(key, value);
+		self
+	}
+
+	/// Add a fact to the overlay, shadowing any baseline fact with the same key
+	pub fn add_fact(&mut self, key: &str, value: Value) {
+		self.overlay.insert(key.to_string(), value);
+	}
+
+	/// Look up a fact by its full key (e.g., "binary.arch")
+	pub fn get(&self, key: &str) -> Option<&Value> {
+		self.overlay.get(key).or_else(|| self.base.get(key))
+	}
+
+	/// Merge the overlay into a new shared baseline
+	///
+	/// Use this to promote accumulated overlay facts so that further overlays
+	/// built from the result share them without copying.
+	pub fn freeze(mut self) -> Self {
+		if !self.overlay.is_empty() {
+			let overlay = core::mem::take(&mut self.overlay);
+			Arc::make_mut(&mut self.base).extend(overlay);
+		}
+		self
+	}
+
+	/// Number of distinct fact keys visible through this context
+	pub fn len(&self) -> usize {
+		self.base.len() + self.overlay.keys().filter(|k| !self.base.contains_key(*k)).count()
+	}
+
+	/// Check if no facts are visible through this context
+	pub fn is_empty(&self) -> bool {
+		self.base.is_empty() && self.overlay.is_empty()
+	}
+}
+
+impl From<FactsEvalContext> for SharedFactsContext {
+	fn from(ctx: FactsEvalContext) -> Self {
+		Self::new(ctx.facts)
+	}
+}
+
+impl HelResolver for SharedFactsContext {
+	fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+		let key = format!("{}.{}", object, field);
+		self.get(&key).cloned()
+	}
+
+	fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+		let mut facts: BTreeMap<&String, &Value> = object_facts(&self.base, object).collect();
+		facts.extend(object_facts(&self.overlay, object));
+		wildcard_matches(facts, field)
+	}
+}
+
+// endregion: --- SharedFactsContext
+
+// region:    --- Map Resolvers
+
+/// Facts keyed by `object.field`
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_with_resolver, Value};
+/// use std::collections::BTreeMap;
+///
+/// let facts = BTreeMap::from([("binary.format".to_string(), Value::from("elf"))]);
+/// assert!(evaluate_with_resolver(r#"binary.format == "elf""#, &facts).unwrap());
+/// ```
+impl HelResolver for BTreeMap<String, Value> {
+	fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+		self.get(&format!("{}.{}", object, field)).cloned()
+	}
+
+	fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+		wildcard_matches(object_facts(self, object), field)
+	}
+}
+
+/// Facts keyed by `object.field`; wildcard matches are returned in key order
+#[cfg(feature = "std")]
+impl<S: std::hash::BuildHasher> HelResolver for std::collections::HashMap<String, Value, S> {
+	fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+		self.get(&format!("{}.{}", object, field)).cloned()
+	}
+
+	fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+		let prefix = format!("{}.", object);
+		let facts: BTreeMap<&String, &Value> = self.iter().filter(|(key, _)| key.starts_with(&prefix)).collect();
+		wildcard_matches(facts, field)
+	}
+}
+
+// endregion: --- Map Resolvers
+
+// region:    --- Wildcards
+
+/// Facts named `object.<name>`, in key order
+pub(crate) fn object_facts<'a>(
+	facts: &'a BTreeMap<String, Value>,
+	object: &str,
+) -> impl Iterator<Item = (&'a String, &'a Value)> {
+	let prefix = format!("{}.", object);
+	facts
+		.range(prefix.clone()..)
+		.take_while(move |(key, _)| key.starts_with(&prefix))
+}
+
+/// The `field` entry of each map-valued fact, named `<fact>.<field>`
+pub(crate) fn wildcard_matches<'a>(
+	facts: impl IntoIterator<Item = (&'a String, &'a Value)>,
+	field: &str,
+) -> Vec<(String, Value)> {
+	facts
+		.into_iter()
+		.filter_map(|(key, value)| match value {
+			Value::Map(map) => map.get(field).map(|v| (format!("{}.{}", key, field), v.clone())),
+			_ => None,
+		})
+		.collect()
+}
+
+// endregion: --- Wildcards
+
+// region:    --- Flattening
+
+/// Flatten a top-level JSON object into dotted facts
+pub(crate) fn flatten_json(root: serde_json::Value) -> Result<BTreeMap<String, Value>, HelError> {
+	let serde_json::Value::Object(entries) = root else {
+		return Err(HelError::type_error(format!(
+			"Facts must be an object at the top level, found {}",
+			json_type_name(&root)
+		)));
+	};
+
+	let mut facts = BTreeMap::new();
+	for (key, value) in entries {
+		match value {
+			serde_json::Value::Object(fields) if !key.contains('.') => {
+				for (field, value) in fields {
+					facts.insert(format!("{}.{}", key, field), json_to_value(value));
+				}
+			}
+			value => {
+				facts.insert(key, json_to_value(value));
+			}
+		}
+	}
+	Ok(facts)
+}
+
+/// Convert a JSON value into a HEL value, preserving lists and maps
+pub(crate) fn json_to_value(value: serde_json::Value) -> Value {
+	match value {
+		serde_json::Value::Null => Value::Null,
+		serde_json::Value::Bool(b) => Value::Bool(b),
+		serde_json::Value::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+		serde_json::Value::String(s) => Value::String(s.into()),
+		serde_json::Value::Array(items) => Value::List(items.into_iter().map(json_to_value).collect()),
+		serde_json::Value::Object(map) => {
+			Value::Map(map.into_iter().map(|(k, v)| (Arc::from(k), json_to_value(v))).collect())
+		}
+	}
+}
+
+fn json_type_name(value: &serde_json::Value) -> &'static str {
+	match value {
+		serde_json::Value::Null => "null",
+		serde_json::Value::Bool(_) => "bool",
+		serde_json::Value::Number(_) => "number",
+		serde_json::Value::String(_) => "string",
+		serde_json::Value::Array(_) => "list",
+		serde_json::Value::Object(_) => "object",
+	}
+}
+
+// endregion: --- Flattening
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn baseline() -> SharedFactsContext {
+		let mut facts = BTreeMap::new();
+		facts.insert("binary.arch".to_string(), Value::String("x86_64".into()));
+		facts.insert("binary.entropy".to_string(), Value::Number(6.0));
+		SharedFactsContext::new(facts)
+	}
+
+	#[test]
+	fn test_overlay_shadows_without_touching_base() {
+		let shared = baseline();
+		let request = shared.overlay().with_fact("binary.entropy", Value::Number(8.0));
+
+		assert_eq!(request.resolve_attr("binary", "entropy"), Some(Value::Number(8.0)));
+		assert_eq!(shared.resolve_attr("binary", "entropy"), Some(Value::Number(6.0)));
+		assert_eq!(
+			request.resolve_attr("binary", "arch"),
+			Some(Value::String("x86_64".into()))
+		);
+		assert_eq!(request.len(), 2);
+		assert!(Arc::ptr_eq(&shared.base, &request.base));
+	}
+
+	#[test]
+	fn test_overlay_shadows_wildcard_matches() {
+		let section = |entropy: f64| Value::Map([(Arc::from("entropy"), Value::Number(entropy))].into());
+		let shared = baseline()
+			.with_fact("sections.data", section(4.0))
+			.with_fact("sections.text", section(6.0))
+			.freeze();
+		let request = shared
+			.overlay()
+			.with_fact("sections.text", section(7.9))
+			.with_fact("sections.rsrc", Value::Null);
+
+		assert_eq!(
+			request.resolve_wildcard("sections", "entropy"),
+			vec![
+				("sections.data.entropy".to_string(), Value::Number(4.0)),
+				("sections.text.entropy".to_string(), Value::Number(7.9)),
+			]
+		);
+		assert!(request.resolve_wildcard("binary", "entropy").is_empty());
+	}
+
+	#[test]
+	fn test_freeze_promotes_overlay() {
+		let shared = baseline().with_fact("security.nx", Value::Bool(true)).freeze();
+		assert!(shared.overlay.is_empty());
+		assert_eq!(shared.len(), 3);
+		assert_eq!(shared.get("security.nx"), Some(&Value::Bool(true)));
+	}
+
+	#[test]
+	fn test_shared_context_across_threads() {
+		let shared = baseline();
+		let handles: Vec<_> = (0..4)
+			.map(|i| {
+				let ctx = shared.overlay().with_fact("req.id", Value::Number(i as f64));
+				std::thread::spawn(move || crate::evaluate_with_resolver("binary.entropy < 7.0 AND req.id >= 0", &ctx))
+			})
+			.collect();
+
+		for handle in handles {
+			assert!(handle.join().unwrap().unwrap());
+		}
+	}
+
+	#[test]
+	fn test_plain_maps_resolve_facts() {
+		let section = |entropy: f64| Value::Map([(Arc::from("entropy"), Value::Number(entropy))].into());
+		let facts = BTreeMap::from([
+			("binary.entropy".to_string(), Value::Number(7.9)),
+			("sections.text".to_string(), section(6.0)),
+			("sections.data".to_string(), section(4.0)),
+		]);
+		let hashed: std::collections::HashMap<String, Value> = facts.clone().into_iter().collect();
+
+		for resolver in [&facts as &dyn HelResolver, &hashed] {
+			assert_eq!(resolver.resolve_attr("binary", "entropy"), Some(Value::Number(7.9)));
+			assert_eq!(resolver.resolve_attr("binary", "arch"), None);
+			assert_eq!(
+				resolver.resolve_wildcard("sections", "entropy"),
+				vec![
+					("sections.data.entropy".to_string(), Value::Number(4.0)),
+					("sections.text.entropy".to_string(), Value::Number(6.0)),
+				]
+			);
+			assert!(crate::evaluate_with_resolver("binary.entropy > 7.5", resolver).unwrap());
+		}
+	}
+
+	#[test]
+	fn test_flatten_json_semantics() {
+		let root = serde_json::json!({
+			"binary.arch": "x86_64",
+			"binary": {"format": "elf", "sections": [".text", ".data"], "headers": {"nx": true}},
+			"score": 3
+		});
+		let facts = flatten_json(root).expect("flatten failed");
+
+		assert_eq!(facts["binary.arch"], Value::String("x86_64".into()));
+		assert_eq!(facts["binary.format"], Value::String("elf".into()));
+		assert!(matches!(&facts["binary.sections"], Value::List(items) if items.len() == 2));
+		assert!(matches!(&facts["binary.headers"], Value::Map(m) if m["nx"] == Value::Bool(true)));
+		assert_eq!(facts["score"], Value::Number(3.0));
+
+		assert!(flatten_json(serde_json::json!([1, 2])).is_err());
+	}
+
+	#[test]
+	fn test_from_serializable_and_from_json_agree() {
+		#[derive(serde::Serialize)]
+		struct Manifest {
+			permissions: Vec<&'static str>,
+			min_sdk: u32,
+		}
+		#[derive(serde::Serialize)]
+		struct Facts {
+			manifest: Manifest,
+		}
+
+		let from_struct = FactsEvalContext::from_serializable(&Facts {
+			manifest: Manifest {
+				permissions: vec!["SMS"],
+				min_sdk: 21,
+			},
+		})
+		.expect("serialize failed");
+		let from_json = FactsEvalContext::from_json(r#"{"manifest": {"permissions": ["SMS"], "min_sdk": 21}}"#)
+			.expect("parse failed");
+
+		assert_eq!(from_struct.facts, from_json.facts);
+		assert!(crate::evaluate("manifest.min_sdk < 23", &from_struct).unwrap());
+		assert!(FactsEvalContext::from_json("{").is_err());
+	}
+
+	#[cfg(feature = "facts-yaml")]
+	#[test]
+	fn test_from_yaml_matches_json() {
+		let yaml = "binary:\n  format: elf\n  sections: [.text, .data]\nbinary.arch: x86_64\n";
+		let from_yaml = FactsEvalContext::from_yaml(yaml).expect("parse failed");
+		let from_json = FactsEvalContext::from_json(
+			r#"{"binary": {"format": "elf", "sections": [".text", ".data"]}, "binary.arch": "x86_64"}"#,
+		)
+		.expect("parse failed");
+
+		assert_eq!(from_yaml.facts, from_json.facts);
+		assert!(FactsEvalContext::from_yaml("- just\n- a list\n").is_err());
+	}
+
+	#[cfg(feature = "facts-toml")]
+	#[test]
+	fn test_from_toml_matches_json() {
+		let toml = "[binary]\nformat = \"elf\"\nentropy = 7.9\n\n[manifest]\npermissions = [\"SMS\"]\n";
+		let from_toml = FactsEvalContext::from_toml(toml).expect("parse failed");
+		let from_json = FactsEvalContext::from_json(
+			r#"{"binary": {"format": "elf", "entropy": 7.9}, "manifest": {"permissions": ["SMS"]}}"#,
+		)
+		.expect("parse failed");
+
+		assert_eq!(from_toml.facts, from_json.facts);
+	}
+}
+
+// endregion: --- Tests
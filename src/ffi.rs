@@ -0,0 +1,304 @@
+//! C ABI for embedding HEL in non-Rust hosts (feature `ffi`)
+//!
+//! The declarations live in `include/hel.h`, generated by cbindgen (`cbindgen.toml`).
+//! Build a shared or static library with `cargo build --release -p hel-ffi`.
+//!
+//! ## Conventions
+//! - All strings are NUL-terminated UTF-8
+//! - Failing calls return `NULL` / `-1` and store a message retrievable with `hel_last_error()`
+//!   (per thread, valid until the next failing call on that thread)
+//! - Handles and strings returned by the library are released with `hel_expr_free` /
+//!   `hel_string_free`; passing `NULL` to either is a no-op
+//! - Panics never unwind across the boundary; they are reported as errors
+//! - Expressions are evaluated with the `core` built-ins registered
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use serde_json::json;
+
+use crate::builtins::BuiltinsRegistry;
+use crate::trace::trace_ast;
+use crate::{evaluate_ast_with_context, parse_expression, AstNode, EvalContext, FactsEvalContext};
+
+thread_local! {
+	static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Version string (`"0.2.0"`), NUL-terminated
+static VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+// region:    --- Handles
+
+/// Parsed expression handle (opaque to C)
+pub struct HelExpr {
+	ast: AstNode,
+	builtins: BuiltinsRegistry,
+}
+
+impl HelExpr {
+	/// Evaluate against facts given as a JSON object string
+	///
+	/// # Safety
+	/// `facts_json` must be `NULL` or a valid NUL-terminated string.
+	unsafe fn evaluate(&self, facts_json: *const c_char) -> Result<bool, String> {
+		// SAFETY: forwarded from this function's contract
+		let facts = unsafe { load_facts(facts_json)? };
+		let ctx = EvalContext::with_builtins(&facts, &self.builtins);
+		evaluate_ast_with_context(&self.ast, &ctx).map_err(|e| e.to_string())
+	}
+
+	/// Evaluate with tracing, returning the trace as JSON
+	///
+	/// # Safety
+	/// `facts_json` must be `NULL` or a valid NUL-terminated string.
+	unsafe fn trace(&self, facts_json: *const c_char) -> Result<String, String> {
+		// SAFETY: forwarded from this function's contract
+		let facts = unsafe { load_facts(facts_json)? };
+		let ctx = EvalContext::with_builtins(&facts, &self.builtins);
+		let trace = trace_ast(&self.ast, &ctx).map_err(|e| e.to_string())?;
+		let atoms = trace
+			.atoms
+			.iter()
+			.map(|atom| {
+				json!({
+					"left": atom.left,
+					"op": crate::trace::comparator_to_str(atom.op),
+					"right": atom.right,
+					"resolved_left_value": atom.resolved_left_value,
+					"resolved_right_value": atom.resolved_right_value,
+					"atom_result": atom.atom_result,
+					"matches": atom
+						.matches
+						.iter()
+						.map(|m| json!({ "path": m.path, "value": m.value, "result": m.result }))
+						.collect::<Vec<_>>(),
+					"span": atom.span.map(|s| json!({
+						"start": s.start,
+						"end": s.end,
+						"line": s.line,
+						"column": s.column,
+					})),
+				})
+			})
+			.collect::<Vec<_>>();
+		Ok(json!({
+			"result": trace.result,
+			"atoms": atoms,
+			"facts_used": trace.facts_used(),
+			"missing_facts": trace.missing_facts(),
+		})
+		.to_string())
+	}
+}
+
+// endregion: --- Handles
+
+// region:    --- Exported Functions
+
+/// Parse an expression; returns `NULL` on error
+///
+/// # Safety
+/// `source` must be `NULL` or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hel_parse(source: *const c_char) -> *mut HelExpr {
+	guard(std::ptr::null_mut(), || {
+		let source = to_str(source, "source")?;
+		let ast = parse_expression(source).map_err(|e| e.to_string())?;
+		let builtins = BuiltinsRegistry::builder()
+			.with_core()
+			.build()
+			.map_err(|e| e.to_string())?;
+		Ok(Box::into_raw(Box::new(HelExpr { ast, builtins })))
+	})
+}
+
+/// Release a handle returned by `hel_parse`
+///
+/// # Safety
+/// `expr` must be `NULL` or a handle from `hel_parse` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn hel_expr_free(expr: *mut HelExpr) {
+	if !expr.is_null() {
+		drop(Box::from_raw(expr));
+	}
+}
+
+/// Evaluate against facts given as a JSON object; returns 1 (true), 0 (false) or -1 (error)
+///
+/// # Safety
+/// `expr` must be a live handle from `hel_parse`; `facts_json` must be `NULL` or a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn hel_evaluate_json(expr: *const HelExpr, facts_json: *const c_char) -> c_int {
+	guard(-1, || {
+		let expr = expr.as_ref().ok_or("expr is NULL")?;
+		expr.evaluate(facts_json).map(c_int::from)
+	})
+}
+
+/// Evaluate with tracing; returns the trace as a JSON string or `NULL` on error
+///
+/// The string has the shape `{"result": bool, "atoms": [..], "facts_used": [..], "missing_facts": [..]}` and must
+/// be released with `hel_string_free`.
+///
+/// # Safety
+/// Same requirements as `hel_evaluate_json`.
+#[no_mangle]
+pub unsafe extern "C" fn hel_trace_json(expr: *const HelExpr, facts_json: *const c_char) -> *mut c_char {
+	guard(std::ptr::null_mut(), || {
+		let expr = expr.as_ref().ok_or("expr is NULL")?;
+		let trace = expr.trace(facts_json)?;
+		Ok(CString::new(trace).map_err(|e| e.to_string())?.into_raw())
+	})
+}
+
+/// Release a string returned by the library
+///
+/// # Safety
+/// `s` must be `NULL` or a string returned by `hel_trace_json` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn hel_string_free(s: *mut c_char) {
+	if !s.is_null() {
+		drop(CString::from_raw(s));
+	}
+}
+
+/// Message of the last failing call on this thread, or `NULL` if none
+#[no_mangle]
+pub extern "C" fn hel_last_error() -> *const c_char {
+	LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |msg| msg.as_ptr()))
+}
+
+/// Library version (static string)
+#[no_mangle]
+pub extern "C" fn hel_version() -> *const c_char {
+	VERSION.as_ptr().cast()
+}
+
+// endregion: --- Exported Functions
+
+// region:    --- Support
+
+/// Run `f`, recording its error (or panic) as the last error and returning `fallback`
+fn guard<T>(fallback: T, f: impl FnOnce() -> Result<T, String>) -> T {
+	let message = match catch_unwind(AssertUnwindSafe(f)) {
+		Ok(Ok(value)) => return value,
+		Ok(Err(message)) => message,
+		Err(_) => "internal error (panic)".to_string(),
+	};
+	let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+	LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+	fallback
+}
+
+/// Borrow a C string as UTF-8
+///
+/// # Safety
+/// `ptr` must be `NULL` or a valid NUL-terminated string that outlives the result.
+unsafe fn to_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
+	if ptr.is_null() {
+		return Err(format!("{} is NULL", name));
+	}
+	CStr::from_ptr(ptr)
+		.to_str()
+		.map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+/// Facts from a JSON object string (`NULL` means no facts)
+///
+/// # Safety
+/// `facts_json` must be `NULL` or a valid NUL-terminated string.
+unsafe fn load_facts(facts_json: *const c_char) -> Result<FactsEvalContext, String> {
+	if facts_json.is_null() {
+		return Ok(FactsEvalContext::new());
+	}
+	let json = to_str(facts_json, "facts_json")?;
+	FactsEvalContext::from_json(json).map_err(|e| e.to_string())
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn last_error() -> String {
+		let ptr = hel_last_error();
+		assert!(!ptr.is_null());
+		unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+	}
+
+	#[test]
+	fn test_parse_evaluate_trace() {
+		let source = CString::new(r#"app.perms CONTAINS "SMS" AND core.len(app.perms) > 1"#).unwrap();
+		let facts = CString::new(r#"{"app": {"perms": ["SMS", "NET"]}}"#).unwrap();
+		unsafe {
+			let expr = hel_parse(source.as_ptr());
+			assert!(!expr.is_null());
+			assert_eq!(hel_evaluate_json(expr, facts.as_ptr()), 1);
+			assert_eq!(hel_evaluate_json(expr, std::ptr::null()), 0);
+
+			let trace = hel_trace_json(expr, facts.as_ptr());
+			assert!(!trace.is_null());
+			let parsed: serde_json::Value = serde_json::from_str(CStr::from_ptr(trace).to_str().unwrap()).unwrap();
+			assert_eq!(parsed["result"], json!(true));
+			assert_eq!(parsed["atoms"][0]["op"], json!("CONTAINS"));
+			assert_eq!(
+				parsed["atoms"][1]["span"],
+				json!({ "start": 29, "end": 52, "line": 1, "column": 30 })
+			);
+			assert_eq!(parsed["missing_facts"], json!([]));
+			hel_string_free(trace);
+			hel_expr_free(expr);
+		}
+		assert_eq!(
+			unsafe { CStr::from_ptr(hel_version()) }.to_str().unwrap(),
+			env!("CARGO_PKG_VERSION")
+		);
+	}
+
+	#[test]
+	fn test_errors_are_reported() {
+		let bad = CString::new("a.b ==").unwrap();
+		unsafe {
+			assert!(hel_parse(bad.as_ptr()).is_null());
+			assert!(!last_error().is_empty());
+			assert!(hel_parse(std::ptr::null()).is_null());
+			assert_eq!(last_error(), "source is NULL");
+
+			let source = CString::new("a.b == 1").unwrap();
+			let expr = hel_parse(source.as_ptr());
+			let facts = CString::new("[1, 2]").unwrap();
+			assert_eq!(hel_evaluate_json(expr, facts.as_ptr()), -1);
+			assert_eq!(hel_evaluate_json(std::ptr::null(), facts.as_ptr()), -1);
+			assert_eq!(last_error(), "expr is NULL");
+			hel_expr_free(expr);
+			hel_expr_free(std::ptr::null_mut());
+		}
+	}
+
+	#[test]
+	fn test_header_declares_all_exports() {
+		let header = include_str!("../include/hel.h");
+		let source = include_str!("ffi.rs");
+		let exports: Vec<&str> = source
+			.lines()
+			.filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+			.filter_map(|rest| rest.split('(').next())
+			.collect();
+		assert_eq!(exports.len(), 7);
+		for name in exports {
+			assert!(
+				header.contains(&format!("{}(", name)),
+				"{} missing from include/hel.h",
+				name
+			);
+		}
+	}
+}
+
+// endregion: --- Tests
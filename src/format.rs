@@ -0,0 +1,395 @@
+//! Canonical formatting for HEL expressions and scripts
+//!
+//! Formats from the parsed AST, so the output depends only on what the rule
+//! means, not on how it was typed. Formatting is idempotent: formatting the
+//! output again yields the same text.
+//!
+//! ## Canonical Form
+//! - Single spaces around operators; `AND` / `OR` / `XOR` in upper case
+//! - Nested chains of the same operator are flattened and redundant parentheses dropped;
+//!   a chain inside a chain of another operator (e.g. `AND` inside `OR`) is always parenthesized
+//! - Chains that do not fit `max_width` are broken one operand per line with a trailing
+//!   operator (the style `parse_script` continues across lines)
+//! - Lists, maps and calls stay on one line: `[a, b]`, `{"k": v}`, `ns.f(a, b)`
+//! - Scripts keep `#` / `//` comments in order between statements, collapse blank runs to one
+//!   line and end with a newline; a comment trailing code moves onto its own line above the
+//!   statement (`format_expression` drops comments)
+
+use crate::prelude::*;
+use crate::trace::comparator_to_str;
+use crate::{parse_expression, split_comment, split_script, AstNode, HelError};
+
+// region:    --- FormatOptions
+
+/// Layout options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+	/// Preferred maximum line width (in characters)
+	pub max_width: usize,
+	/// Indentation of continuation lines (in spaces)
+	pub indent: usize,
+}
+
+impl Default for FormatOptions {
+	fn default() -> Self {
+		Self {
+			max_width: 100,
+			indent: 2,
+		}
+	}
+}
+
+// endregion: --- FormatOptions
+
+// region:    --- Public API
+
+/// Format a single expression (no trailing newline)
+///
+/// # Examples
+///
+/// ```
+/// use hel::format::{format_expression, FormatOptions};
+///
+/// let formatted = format_expression(r#"a.b==1 and (c.d CONTAINS "x"||(e.f>2))"#, &FormatOptions::default()).unwrap();
+/// assert_eq!(formatted, r#"a.b == 1 AND (c.d CONTAINS "x" OR e.f > 2)"#);
+/// ```
+pub fn format_expression(source: &str, options: &FormatOptions) -> Result<String, HelError> {
+	Ok(format_ast(&parse_expression(source)?, options))
+}
+
+/// Format a parsed expression (no trailing newline)
+pub fn format_ast(ast: &AstNode, options: &FormatOptions) -> String {
+	layout(ast, 0, 0, options)
+}
+
+/// Format a script: constants, let bindings, final expression and comments
+///
+/// A plain expression is a valid script; the result always ends with a newline.
+pub fn format_script(source: &str, options: &FormatOptions) -> Result<String, HelError> {
+	let lines: Vec<&str> = source.lines().collect();
+	let mut out = String::new();
+	let mut next_line = 0;
+
+	for statement in split_script(source) {
+		push_trivia(&mut out, &lines[next_line..statement.line]);
+		next_line = statement.line + 1;
+
+		// Comments trailing the statement's lines move onto their own lines above it
+		let mut statement_lines: Vec<usize> = statement.segments.iter().map(|&(_, line, _)| line).collect();
+		statement_lines.insert(0, statement.line);
+		statement_lines.dedup();
+		for line in statement_lines {
+			if let (_, Some(comment)) = split_comment(lines[line]) {
+				out.push_str(comment.trim_end());
+				out.push('\n');
+			}
+		}
+
+		let ast = parse_expression(&statement.source).map_err(|e| statement.locate(e))?;
+		match statement.name {
+			Some(name) => {
+				let keyword = if statement.constant { "const" } else { "let" };
+				let head = format!("{} {} =", keyword, name);
+				let flat = flat(&ast);
+				if width(&head) + 1 + width(&flat) <= options.max_width {
+					out.push_str(&format!("{} {}\n", head, flat));
+				} else {
+					let body = layout(&ast, options.indent, 0, options);
+					out.push_str(&format!("{}\n{}{}\n", head, pad(options.indent), body));
+				}
+			}
+			None => {
+				out.push_str(&layout(&ast, 0, 0, options));
+				out.push('\n');
+			}
+		}
+	}
+	push_trivia(&mut out, &lines[next_line.min(lines.len())..]);
+
+	Ok(out)
+}
+
+/// Check mode: whether `source` is already in canonical script form
+pub fn is_formatted(source: &str, options: &FormatOptions) -> Result<bool, HelError> {
+	Ok(format_script(source, options)? == source)
+}
+
+// endregion: --- Public API
+
+// region:    --- Layout
+
+/// Lay out `node` starting at column `indent`, leaving `trailing` columns after it
+///
+/// Continuation lines carry their own indentation.
+fn layout(node: &AstNode, indent: usize, trailing: usize, options: &FormatOptions) -> String {
+	let node = collapse(node);
+	let flat_text = flat(node);
+	if indent + width(&flat_text) + trailing <= options.max_width {
+		return flat_text;
+	}
+	let Some((op, operands)) = chain(node) else {
+		return flat_text;
+	};
+
+	let separator = format!("\n{}", pad(indent));
+	let last = operands.len() - 1;
+	operands
+		.iter()
+		.enumerate()
+		.map(|(i, operand)| {
+			let suffix = if i == last { trailing } else { op.len() + 1 };
+			let text = if chain(operand).is_some() {
+				// Operand is itself a chain of another operator (e.g. AND inside OR): keep it parenthesized
+				let inline = format!("({})", flat(operand));
+				if indent + width(&inline) + suffix <= options.max_width {
+					inline
+				} else {
+					let inner = layout(operand, indent + options.indent, 0, options);
+					format!("(\n{}{}\n{})", pad(indent + options.indent), inner, pad(indent))
+				}
+			} else {
+				layout(operand, indent, suffix, options)
+			};
+			if i == last {
+				text
+			} else {
+				format!("{} {}", text, op)
+			}
+		})
+		.collect::<Vec<_>>()
+		.join(&separator)
+}
+
+/// Single-line rendering
+fn flat(node: &AstNode) -> String {
+	let node = collapse(node);
+	if let Some((op, operands)) = chain(node) {
+		return operands
+			.iter()
+			.map(|operand| match chain(operand) {
+				Some(_) => format!("({})", flat(operand)),
+				None => flat(operand),
+			})
+			.collect::<Vec<_>>()
+			.join(&format!(" {} ", op));
+	}
+
+	match node {
+		AstNode::Bool(b) => b.to_string(),
+		AstNode::String(s) => format!("\"{}\"", s),
+		AstNode::Number(n) => n.to_string(),
+		AstNode::Float(f) => float_literal(*f),
+		AstNode::Identifier(name) => name.to_string(),
+		AstNode::Attribute { object, field } => format!("{}.{}", object, field),
+		AstNode::Wildcard { object, field } => format!("{}.*.{}", object, field),
+		AstNode::Comparison { left, op, right, .. } => {
+			format!("{} {} {}", operand(left), comparator_to_str(*op), operand(right))
+		}
+		// Empty chains (only produced by rewriters) are their identity values
+		AstNode::And(_) => "true".to_string(),
+		AstNode::Or(_) | AstNode::Xor(_) => "false".to_string(),
+		AstNode::ListLiteral(items) => format!("[{}]", items.iter().map(operand).collect::<Vec<_>>().join(", ")),
+		AstNode::MapLiteral(entries) => format!(
+			"{{{}}}",
+			entries
+				.iter()
+				.map(|(key, value)| format!("\"{}\": {}", key, operand(value)))
+				.collect::<Vec<_>>()
+				.join(", ")
+		),
+		AstNode::FunctionCall { namespace, name, args, .. } => {
+			let args = args.iter().map(operand).collect::<Vec<_>>().join(", ");
+			match namespace {
+				Some(ns) => format!("{}.{}({})", ns, name, args),
+				None => format!("{}({})", name, args),
+			}
+		}
+		AstNode::Range { start, end } => format!("{}..{}", flat(start), flat(end)),
+	}
+}
+
+/// Render a node in primary position (comparison operand, list item, argument)
+fn operand(node: &AstNode) -> String {
+	let node = collapse(node);
+	match node {
+		AstNode::Comparison { .. } => format!("({})", flat(node)),
+		_ if chain(node).is_some() => format!("({})", flat(node)),
+		_ => flat(node),
+	}
+}
+
+/// Strip single-operand AND / OR / XOR wrappers (the parser wraps every term)
+fn collapse(mut node: &AstNode) -> &AstNode {
+	while let AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) = node {
+		match nodes.as_slice() {
+			[single] => node = single,
+			_ => break,
+		}
+	}
+	node
+}
+
+/// Operator and flattened operands of a multi-operand AND / OR / XOR chain
+fn chain(node: &AstNode) -> Option<(&'static str, Vec<&AstNode>)> {
+	let (op, nodes) = match collapse(node) {
+		AstNode::And(nodes) if nodes.len() > 1 => ("AND", nodes),
+		AstNode::Or(nodes) if nodes.len() > 1 => ("OR", nodes),
+		AstNode::Xor(nodes) if nodes.len() > 1 => ("XOR", nodes),
+		_ => return None,
+	};
+
+	let mut operands = Vec::new();
+	for node in nodes {
+		match chain(node) {
+			// `a AND (b AND c)` is `a AND b AND c`
+			Some((inner_op, inner)) if inner_op == op => operands.extend(inner),
+			_ => operands.push(collapse(node)),
+		}
+	}
+	Some((op, operands))
+}
+
+/// Float literal that re-parses as a float (`7.0`, not `7`)
+fn float_literal(value: f64) -> String {
+	let text = value.to_string();
+	if text.contains('.') || !value.is_finite() {
+		text
+	} else {
+		format!("{}.0", text)
+	}
+}
+
+// endregion: --- Layout
+
+// region:    --- Support
+
+/// Append comment-only lines, collapsing blank runs to a single blank line
+fn push_trivia(out: &mut String, lines: &[&str]) {
+	let mut blank = false;
+	for line in lines {
+		let line = line.trim();
+		if line.is_empty() {
+			blank = true;
+		} else if line.starts_with('#') || line.starts_with("//") {
+			if blank && !out.is_empty() {
+				out.push('\n');
+			}
+			blank = false;
+			out.push_str(line);
+			out.push('\n');
+		}
+	}
+	if blank && !out.is_empty() {
+		out.push('\n');
+	}
+}
+
+fn pad(width: usize) -> String {
+	" ".repeat(width)
+}
+
+fn width(text: &str) -> usize {
+	text.chars().count()
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{evaluate_script, parse_script, FactsEvalContext, Value};
+
+	#[test]
+	fn test_format_expression_canonical() {
+		let options = FormatOptions::default();
+		let cases = [
+			("a.b==1&&c.d!=2", "a.b == 1 AND c.d != 2"),
+			("(a.b == 1) or ((c.d > 2 || e.f < 3))", "a.b == 1 OR c.d > 2 OR e.f < 3"),
+			(
+				"a.b == 1 AND (c.d == 2 OR e.f == 3)",
+				"a.b == 1 AND (c.d == 2 OR e.f == 3)",
+			),
+			("(a.b == 1) == true", "(a.b == 1) == true"),
+			(r#"core.len( [ 1,2.0 ,"x"] )>=0x10"#, r#"core.len([1, 2.0, "x"]) >= 16"#),
+			(r#"{ "k" :$v , "m":%M } IN x.y"#, r#"{"k": $v, "m": %M} IN x.y"#),
+			("a.b xor c.d and e.f or g.h", "(a.b XOR (c.d AND e.f)) OR g.h"),
+			("a.b XOR (c.d XOR e.f)", "a.b XOR c.d XOR e.f"),
+		];
+		for (input, expected) in cases {
+			assert_eq!(
+				format_expression(input, &options).unwrap(),
+				expected,
+				"input: {}",
+				input
+			);
+		}
+	}
+
+	#[test]
+	fn test_format_wraps_long_chains() {
+		let options = FormatOptions {
+			max_width: 40,
+			indent: 2,
+		};
+		let source = r#"binary.entropy > 7.5 AND (strings.count < 10 OR manifest.permissions CONTAINS "READ_SMS") AND binary.packed == true"#;
+		let formatted = format_expression(source, &options).unwrap();
+		assert_eq!(
+			formatted,
+			"binary.entropy > 7.5 AND\n(\n  strings.count < 10 OR\n  manifest.permissions CONTAINS \"READ_SMS\"\n) AND\nbinary.packed == true"
+		);
+		assert_eq!(format_expression(&formatted, &options).unwrap(), formatted);
+	}
+
+	#[test]
+	fn test_format_script_keeps_comments_and_semantics() {
+		let options = FormatOptions {
+			max_width: 50,
+			indent: 2,
+		};
+		let source = "\n\n# SMS permissions\nlet has_sms =   manifest.permissions CONTAINS \"READ_SMS\"  and manifest.permissions CONTAINS \"SEND_SMS\"\n\n\n\n# packed\nlet packed = binary.entropy>7.5\nhas_sms AND packed\n# trailing\n";
+		let formatted = format_script(source, &options).unwrap();
+		assert_eq!(
+			formatted,
+			"# SMS permissions\nlet has_sms =\n  manifest.permissions CONTAINS \"READ_SMS\" AND\n  manifest.permissions CONTAINS \"SEND_SMS\"\n\n# packed\nlet packed = binary.entropy > 7.5\nhas_sms AND packed\n# trailing\n"
+		);
+		assert!(is_formatted(&formatted, &options).unwrap());
+		assert!(!is_formatted(source, &options).unwrap());
+
+		let original = parse_script(source).unwrap();
+		let reparsed = parse_script(&formatted).unwrap();
+		assert_eq!(original.bindings.len(), reparsed.bindings.len());
+
+		let mut facts = FactsEvalContext::new();
+		facts.add_fact(
+			"manifest.permissions",
+			Value::List(vec!["READ_SMS".into(), "SEND_SMS".into()]),
+		);
+		facts.add_fact("binary.entropy", Value::Number(7.9));
+		assert!(evaluate_script(&formatted, &facts).unwrap());
+	}
+
+	#[test]
+	fn test_format_script_moves_trailing_comments() {
+		let options = FormatOptions::default();
+		let source =
+			"let packed = # entropy heuristic\n  binary.entropy > 7.5 AND // high\n  binary.size > 10\npacked # done\n";
+		let formatted = format_script(source, &options).unwrap();
+		assert_eq!(
+			formatted,
+			"# entropy heuristic\n// high\nlet packed = binary.entropy > 7.5 AND binary.size > 10\n# done\npacked\n"
+		);
+		assert!(is_formatted(&formatted, &options).unwrap());
+		assert_eq!(
+			format_script("const  MAX=7.5\nbinary.entropy>MAX", &options).unwrap(),
+			"const MAX = 7.5\nbinary.entropy > MAX\n"
+		);
+		assert_eq!(
+			format_expression("a.b == \"#1\" // note", &options).unwrap(),
+			"a.b == \"#1\""
+		);
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,347 @@
+//! Random inputs for fuzzing and property tests (feature `arbitrary`)
+//!
+//! `AstNode` and `Value` implement `arbitrary::Arbitrary`, and `arbitrary_source`
+//! turns the same bytes into valid HEL source text.
+//!
+//! ## Guarantees
+//! - Generated ASTs are shaped exactly like parser output (`Or` of `And` of terms, with
+//!   parentheses only where the formatter prints them), so
+//!   `parse_expression(&format_ast(&ast, ..)) == Ok(ast)` holds for every generated `ast`
+//! - Nesting depth and collection sizes are bounded, so generation always terminates
+//! - Identifiers never start with a digit or a keyword; strings never contain `"`;
+//!   floats are finite and non-negative; function calls are always namespaced
+//!
+//! # Examples
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use hel::format::{format_ast, FormatOptions};
+//! use hel::{parse_expression, AstNode};
+//!
+//! let bytes = [7u8; 256];
+//! let ast = AstNode::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+//! let source = format_ast(&ast, &FormatOptions::default());
+//! assert_eq!(parse_expression(&source).unwrap(), ast);
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::format::{format_ast, FormatOptions};
+use crate::{AstNode, Comparator, SourceSpan, Value};
+
+/// Maximum nesting of parentheses, lists, maps and calls
+const MAX_DEPTH: usize = 3;
+
+/// Identifiers must not start with these, in any case (the grammar would read a keyword)
+const KEYWORDS: &[&str] = &[
+	"true", "false", "and", "or", "xor", "in", "contains", "eqi", "like", "between",
+];
+
+const COMPARATORS: &[Comparator] = &[
+	Comparator::Eq,
+	Comparator::Ne,
+	Comparator::Gt,
+	Comparator::Ge,
+	Comparator::Lt,
+	Comparator::Le,
+	Comparator::Contains,
+	Comparator::In,
+	Comparator::EqIgnoreCase,
+	Comparator::Like,
+];
+
+// region:    --- Arbitrary Impls
+
+impl<'a> Arbitrary<'a> for AstNode {
+	/// A parser-shaped condition
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		let op = *u.choose(&[None, Some(Chain::And), Some(Chain::Or), Some(Chain::Xor)])?;
+		condition(u, 0, op)
+	}
+}
+
+impl<'a> Arbitrary<'a> for Value {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		value(u, 0)
+	}
+}
+
+/// Valid HEL source for a random condition
+pub fn arbitrary_source(u: &mut Unstructured<'_>) -> Result<String> {
+	Ok(format_ast(&AstNode::arbitrary(u)?, &FormatOptions::default()))
+}
+
+// endregion: --- Arbitrary Impls
+
+// region:    --- AST Generation
+
+/// Operator a term is printed inside of (terms of that chain must not be chains of the same operator)
+#[derive(Clone, Copy, PartialEq)]
+enum Chain {
+	And,
+	Or,
+	Xor,
+}
+
+/// A chain of 2-3 terms joined by `op`, or a single term if `op` is `None`
+///
+/// The formatter parenthesizes a chain inside a chain of another operator, so the
+/// operands of a multi-operand `Or` / `Xor` are `And` nodes with a single term
+/// (possibly a parenthesized chain).
+fn condition(u: &mut Unstructured<'_>, depth: usize, op: Option<Chain>) -> Result<AstNode> {
+	let count = if op.is_some() { u.int_in_range(2..=3)? } else { 1 };
+	match op {
+		Some(Chain::And) | None => {
+			let terms = (0..count).map(|_| term(u, depth, op)).collect::<Result<Vec<_>>>()?;
+			Ok(AstNode::Or(vec![AstNode::And(terms)]))
+		}
+		Some(chain) => {
+			let operands = (0..count)
+				.map(|_| Ok(AstNode::And(vec![term(u, depth, op)?])))
+				.collect::<Result<Vec<_>>>()?;
+			Ok(match chain {
+				Chain::Xor => AstNode::Or(vec![AstNode::Xor(operands)]),
+				_ => AstNode::Or(operands),
+			})
+		}
+	}
+}
+
+/// Parenthesized chain of `op` (as the parser builds `( .. )`)
+fn chain(u: &mut Unstructured<'_>, depth: usize, op: Chain) -> Result<AstNode> {
+	condition(u, depth + 1, Some(op))
+}
+
+fn term(u: &mut Unstructured<'_>, depth: usize, enclosing: Option<Chain>) -> Result<AstNode> {
+	match (u.int_in_range(0..=3)?, enclosing) {
+		(0, Some(op)) if depth < MAX_DEPTH => {
+			let others: Vec<Chain> = [Chain::And, Chain::Or, Chain::Xor]
+				.into_iter()
+				.filter(|other| *other != op)
+				.collect();
+			let other = *u.choose(&others)?;
+			chain(u, depth, other)
+		}
+		(0 | 1, _) => primary(u, depth),
+		_ => comparison(u, depth),
+	}
+}
+
+fn comparison(u: &mut Unstructured<'_>, depth: usize) -> Result<AstNode> {
+	let left = Box::new(operand(u, depth)?);
+	let op = *u.choose(COMPARATORS)?;
+	let right = if op == Comparator::In && u.ratio(1, 4)? {
+		AstNode::Range {
+			start: Box::new(range_bound(u)?),
+			end: Box::new(range_bound(u)?),
+		}
+	} else {
+		operand(u, depth)?
+	};
+	Ok(AstNode::Comparison {
+		left,
+		op,
+		right: Box::new(right),
+		span: SourceSpan::default(),
+	})
+}
+
+/// Bound of `lo..hi`: a number, attribute or variable
+fn range_bound(u: &mut Unstructured<'_>) -> Result<AstNode> {
+	let node = match u.int_in_range(0..=3)? {
+		0 => AstNode::Number(u.arbitrary()?),
+		1 => AstNode::Float(float(u)?),
+		2 => {
+			let prefix = *u.choose(&["$", "%"])?;
+			AstNode::Identifier(format!("{}{}", prefix, identifier(u)?).into())
+		}
+		_ => AstNode::Attribute {
+			object: identifier(u)?.into(),
+			field: identifier(u)?.into(),
+		},
+	};
+	Ok(node)
+}
+
+/// Comparison operand, list item, map value or argument
+fn operand(u: &mut Unstructured<'_>, depth: usize) -> Result<AstNode> {
+	if depth >= MAX_DEPTH || u.ratio(3, 4)? {
+		return primary(u, depth);
+	}
+	// Parenthesized sub-conditions are printed only if they are a comparison or a chain
+	match u.int_in_range(0..=3)? {
+		0 => Ok(AstNode::Or(vec![AstNode::And(vec![comparison(u, depth + 1)?])])),
+		1 => chain(u, depth, Chain::And),
+		2 => chain(u, depth, Chain::Xor),
+		_ => chain(u, depth, Chain::Or),
+	}
+}
+
+fn primary(u: &mut Unstructured<'_>, depth: usize) -> Result<AstNode> {
+	let nested = depth < MAX_DEPTH;
+	let node = match u.int_in_range(0..=9)? {
+		0 => AstNode::Bool(u.arbitrary()?),
+		1 => AstNode::String(string(u)?.into()),
+		2 => AstNode::Number(u.arbitrary()?),
+		3 => AstNode::Float(float(u)?),
+		4 => {
+			let prefix = *u.choose(&["", "$", "%"])?;
+			AstNode::Identifier(format!("{}{}", prefix, identifier(u)?).into())
+		}
+		5 if nested => AstNode::ListLiteral(items(u, depth)?),
+		6 if nested => {
+			let count = u.int_in_range(0..=3)?;
+			let entries = (0..count)
+				.map(|_| Ok((Arc::from(string(u)?), operand(u, depth + 1)?)))
+				.collect::<Result<Vec<_>>>()?;
+			AstNode::MapLiteral(entries)
+		}
+		7 if nested => AstNode::FunctionCall {
+			namespace: Some(identifier(u)?.into()),
+			name: identifier(u)?.into(),
+			args: items(u, depth)?,
+			span: SourceSpan::default(),
+		},
+		8 => AstNode::Wildcard {
+			object: identifier(u)?.into(),
+			field: identifier(u)?.into(),
+		},
+		_ => AstNode::Attribute {
+			object: identifier(u)?.into(),
+			field: identifier(u)?.into(),
+		},
+	};
+	Ok(node)
+}
+
+fn items(u: &mut Unstructured<'_>, depth: usize) -> Result<Vec<AstNode>> {
+	let count = u.int_in_range(0..=3)?;
+	(0..count).map(|_| operand(u, depth + 1)).collect()
+}
+
+fn identifier(u: &mut Unstructured<'_>) -> Result<String> {
+	const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_";
+	const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_0123456789";
+
+	let mut name = String::from(*u.choose(FIRST)? as char);
+	for _ in 0..u.int_in_range(0..=7)? {
+		name.push(*u.choose(REST)? as char);
+	}
+	if KEYWORDS.iter().any(|k| name.to_ascii_lowercase().starts_with(k)) {
+		name.insert(0, '_');
+	}
+	Ok(name)
+}
+
+fn string(u: &mut Unstructured<'_>) -> Result<String> {
+	let s: String = u.arbitrary()?;
+	Ok(s.replace('"', ""))
+}
+
+fn float(u: &mut Unstructured<'_>) -> Result<f64> {
+	let f: f64 = u.arbitrary()?;
+	Ok(if f.is_finite() { f.abs() } else { 0.5 })
+}
+
+// endregion: --- AST Generation
+
+// region:    --- Value Generation
+
+fn value(u: &mut Unstructured<'_>, depth: usize) -> Result<Value> {
+	let nested = depth < MAX_DEPTH;
+	let value = match u.int_in_range(0..=5)? {
+		0 => Value::Null,
+		1 => Value::Bool(u.arbitrary()?),
+		2 => Value::String(Arc::from(u.arbitrary::<&str>()?)),
+		3 => Value::Number(u.arbitrary()?),
+		4 if nested => {
+			let count = u.int_in_range(0..=4)?;
+			Value::List((0..count).map(|_| value(u, depth + 1)).collect::<Result<_>>()?)
+		}
+		5 if nested => {
+			let mut map = BTreeMap::new();
+			for _ in 0..u.int_in_range(0..=4)? {
+				map.insert(Arc::from(u.arbitrary::<&str>()?), value(u, depth + 1)?);
+			}
+			Value::Map(map)
+		}
+		_ => Value::Number(u.arbitrary()?),
+	};
+	Ok(value)
+}
+
+// endregion: --- Value Generation
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::visit::{walk_ast, Visitor};
+	use crate::{evaluate_ast, parse_expression, EvalContext, FactsEvalContext};
+
+	/// Deterministic pseudo-random byte buffers (xorshift)
+	fn buffers(count: usize) -> impl Iterator<Item = Vec<u8>> {
+		let mut state = 0x2545_f491_4f6c_dd1d_u64;
+		(0..count).map(move |i| {
+			(0..64 + i % 512)
+				.map(|_| {
+					state ^= state << 13;
+					state ^= state >> 7;
+					state ^= state << 17;
+					state as u8
+				})
+				.collect()
+		})
+	}
+
+	#[test]
+	fn test_parse_print_round_trip() {
+		for bytes in buffers(2000) {
+			let ast = AstNode::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+			let source = format_ast(&ast, &FormatOptions::default());
+			let parsed = parse_expression(&source).unwrap_or_else(|e| panic!("{}: {}", source, e));
+			assert_eq!(parsed, ast, "source: {}", source);
+			assert_eq!(arbitrary_source(&mut Unstructured::new(&bytes)).unwrap(), source);
+		}
+	}
+
+	/// Collects attribute paths and wildcard `(object, field)` pairs
+	struct Attributes(Vec<String>, Vec<(String, String)>);
+
+	impl Visitor for Attributes {
+		fn visit_attribute(&mut self, object: &str, field: &str) {
+			self.0.push(format!("{}.{}", object, field));
+		}
+
+		fn visit_wildcard(&mut self, object: &str, field: &str) {
+			self.1.push((object.to_string(), field.to_string()));
+		}
+	}
+
+	#[test]
+	fn test_evaluator_never_panics() {
+		for bytes in buffers(500) {
+			let mut u = Unstructured::new(&bytes);
+			let ast = AstNode::arbitrary(&mut u).unwrap();
+			let mut attributes = Attributes(Vec::new(), Vec::new());
+			walk_ast(&ast, &mut attributes);
+
+			// Remaining bytes become fact values (Null once they run out)
+			let mut facts = FactsEvalContext::new();
+			for path in attributes.0 {
+				facts.add_fact(&path, Value::arbitrary(&mut u).unwrap());
+			}
+			for (object, field) in attributes.1 {
+				let member = BTreeMap::from([(Arc::from(field.as_str()), Value::arbitrary(&mut u).unwrap())]);
+				facts.add_fact(&format!("{}.w", object), Value::Map(member));
+			}
+			let _ = evaluate_ast(&ast, &EvalContext::new(&facts));
+		}
+	}
+}
+
+// endregion: --- Tests
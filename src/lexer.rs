@@ -0,0 +1,370 @@
+//! Tokenizer for HEL source text
+//!
+//! Editors and terminal highlighters need categorized tokens with byte spans,
+//! including for incomplete or invalid input while the user is typing. This
+//! lexer follows the grammar's token rules but never fails: anything it cannot
+//! classify becomes a `TokenKind::Unknown` token.
+//!
+//! ## Notes
+//! - Spans are byte ranges into the input, always on `char` boundaries
+//! - Whitespace is not emitted; `#` comments (script syntax) are
+//! - Dotted paths are one token: `Attribute` (also wildcard paths like `sections.*.entropy`),
+//!   or `Function` when followed by `(`
+
+use core::ops::Range;
+
+use crate::prelude::*;
+
+// region:    --- Token
+
+/// Token category, suitable for picking a highlight color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+	/// `AND`, `OR`, `CONTAINS`, `IN`, `let`, `const` (and lowercase `and` / `or`)
+	Keyword,
+	/// Comparison and logical operators (`==`, `>=`, `&&`, `=`, ...)
+	Operator,
+	/// Brackets, braces, parentheses, commas and colons
+	Punctuation,
+	/// String literal including quotes (may be unterminated)
+	String,
+	/// Integer, hex or float literal
+	Number,
+	/// `true` or `false`
+	Boolean,
+	/// Attribute path (`object.field`)
+	Attribute,
+	/// Function name, optionally namespaced (`core.len`)
+	Function,
+	/// Bare identifier
+	Identifier,
+	/// Variable reference (`$name`)
+	Variable,
+	/// Symbolic constant (`%NAME`)
+	Symbol,
+	/// Comment (`# ...` or `// ...` to end of line)
+	Comment,
+	/// Anything the lexer cannot classify
+	Unknown,
+}
+
+/// A categorized slice of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+	/// Token category
+	pub kind: TokenKind,
+	/// Byte range in the input
+	pub span: Range<usize>,
+}
+
+impl Token {
+	/// The token's text within `source`
+	pub fn text<'s>(&self, source: &'s str) -> &'s str {
+		&source[self.span.clone()]
+	}
+}
+
+// endregion: --- Token
+
+// region:    --- tokenize
+
+/// Keyword operators, matched case-insensitively like the grammar does
+const KEYWORDS: &[&str] = &["AND", "OR", "XOR", "CONTAINS", "IN", "EQI", "LIKE", "BETWEEN"];
+const OPERATORS: &[&str] = &["==*", "==", "!=", ">=", "<=", "&&", "||", ">", "<", "=", ".."];
+
+/// Split HEL source into categorized tokens
+///
+/// # Examples
+///
+/// ```
+/// use hel::lexer::{tokenize, TokenKind};
+///
+/// let src = r#"core.len(app.perms) > 2 AND name == "x"#;
+/// let kinds: Vec<TokenKind> = tokenize(src).iter().map(|t| t.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         TokenKind::Function,
+///         TokenKind::Punctuation,
+///         TokenKind::Attribute,
+///         TokenKind::Punctuation,
+///         TokenKind::Operator,
+///         TokenKind::Number,
+///         TokenKind::Keyword,
+///         TokenKind::Identifier,
+///         TokenKind::Operator,
+///         TokenKind::String, // unterminated strings still highlight
+///     ]
+/// );
+/// ```
+pub fn tokenize(input: &str) -> Vec<Token> {
+	let bytes = input.as_bytes();
+	let mut tokens = Vec::new();
+	let mut pos = 0;
+
+	while pos < bytes.len() {
+		let start = pos;
+		let c = bytes[pos];
+
+		let kind = match c {
+			b' ' | b'\t' | b'\r' | b'\n' => {
+				pos += 1;
+				continue;
+			}
+			_ if c == b'#' || input[pos..].starts_with("//") => {
+				pos = find_from(bytes, pos, |b| b == b'\n');
+				TokenKind::Comment
+			}
+			b'"' => {
+				pos = find_from(bytes, pos + 1, |b| b == b'"');
+				pos = (pos + 1).min(bytes.len());
+				TokenKind::String
+			}
+			b'(' | b')' | b'[' | b']' | b'{' | b'}' | b',' | b':' => {
+				pos += 1;
+				TokenKind::Punctuation
+			}
+			b'0'..=b'9' => {
+				pos = lex_number(bytes, pos);
+				TokenKind::Number
+			}
+			b'$' | b'%' if bytes.get(pos + 1).copied().is_some_and(is_ident_byte) => {
+				pos = find_from(bytes, pos + 1, |b| !is_ident_byte(b));
+				if c == b'$' {
+					TokenKind::Variable
+				} else {
+					TokenKind::Symbol
+				}
+			}
+			c if is_ident_byte(c) => {
+				let (end, kind) = lex_word(input, pos);
+				pos = end;
+				kind
+			}
+			_ => match OPERATORS.iter().find(|op| input[pos..].starts_with(*op)) {
+				Some(op) => {
+					pos += op.len();
+					TokenKind::Operator
+				}
+				None => {
+					pos += input[pos..].chars().next().map_or(1, char::len_utf8);
+					TokenKind::Unknown
+				}
+			},
+		};
+
+		tokens.push(Token { kind, span: start..pos });
+	}
+
+	tokens
+}
+
+fn is_ident_byte(b: u8) -> bool {
+	b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Index of the first byte at or after `from` matching `pred` (or the end)
+fn find_from(bytes: &[u8], from: usize, pred: impl Fn(u8) -> bool) -> usize {
+	bytes[from..]
+		.iter()
+		.position(|&b| pred(b))
+		.map_or(bytes.len(), |i| from + i)
+}
+
+fn lex_number(bytes: &[u8], start: usize) -> usize {
+	let decimal = |b: u8| b.is_ascii_digit();
+	if bytes[start..].starts_with(b"0x") && bytes.get(start + 2).is_some_and(|b| b.is_ascii_hexdigit()) {
+		return digits_end(bytes, start + 2, |b| b.is_ascii_hexdigit());
+	}
+	let mut end = digits_end(bytes, start, decimal);
+	if bytes.get(end) == Some(&b'.') && bytes.get(end + 1).is_some_and(|b| b.is_ascii_digit()) {
+		end = digits_end(bytes, end + 1, decimal);
+	}
+	if matches!(bytes.get(end), Some(b'e' | b'E')) {
+		let digits = end + 1 + usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+		if bytes.get(digits).is_some_and(|b| b.is_ascii_digit()) {
+			end = digits_end(bytes, digits, decimal);
+		}
+	}
+	end
+}
+
+/// End of a run of digits starting at `from`, allowing single `_` separators between digits
+fn digits_end(bytes: &[u8], from: usize, is_digit: impl Fn(u8) -> bool) -> usize {
+	let mut end = from;
+	while let Some(&b) = bytes.get(end) {
+		let separator = b == b'_' && end > from && bytes.get(end + 1).is_some_and(|&next| is_digit(next));
+		if !(is_digit(b) || separator) {
+			break;
+		}
+		end += 1;
+	}
+	end
+}
+
+/// Lex an identifier, dotted path or keyword starting at `start`
+fn lex_word(input: &str, start: usize) -> (usize, TokenKind) {
+	let bytes = input.as_bytes();
+	let mut end = find_from(bytes, start, |b| !is_ident_byte(b));
+	let mut dotted = false;
+	while bytes.get(end) == Some(&b'.') {
+		// Skip the `*.` of a wildcard segment (`sections.*.entropy`)
+		let dot = match bytes.get(end + 1..end + 3) {
+			Some(b"*.") => end + 2,
+			_ => end,
+		};
+		if !bytes.get(dot + 1).copied().is_some_and(is_ident_byte) {
+			break;
+		}
+		end = find_from(bytes, dot + 1, |b| !is_ident_byte(b));
+		dotted = true;
+	}
+
+	let word = &input[start..end];
+	let next = find_from(bytes, end, |b| !b.is_ascii_whitespace());
+	let kind = if bytes.get(next) == Some(&b'(') {
+		TokenKind::Function
+	} else if dotted {
+		TokenKind::Attribute
+	} else if word == "true" || word == "false" {
+		TokenKind::Boolean
+	} else if word == "let" || word == "const" || KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word)) {
+		TokenKind::Keyword
+	} else {
+		TokenKind::Identifier
+	};
+	(end, kind)
+}
+
+// endregion: --- tokenize
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn lex(src: &str) -> Vec<(TokenKind, &str)> {
+		tokenize(src).into_iter().map(|t| (t.kind, t.text(src))).collect()
+	}
+
+	#[test]
+	fn test_tokenize_expression() {
+		use TokenKind::*;
+		assert_eq!(
+			lex(r#"binary.arch == "x86_64" && [0x1F, 2.5] CONTAINS $v OR %LIMIT != true"#),
+			vec![
+				(Attribute, "binary.arch"),
+				(Operator, "=="),
+				(String, "\"x86_64\""),
+				(Operator, "&&"),
+				(Punctuation, "["),
+				(Number, "0x1F"),
+				(Punctuation, ","),
+				(Number, "2.5"),
+				(Punctuation, "]"),
+				(Keyword, "CONTAINS"),
+				(Variable, "$v"),
+				(Keyword, "OR"),
+				(Symbol, "%LIMIT"),
+				(Operator, "!="),
+				(Boolean, "true"),
+			]
+		);
+		assert_eq!(
+			lex("a XOR b xor c Xorx"),
+			vec![
+				(Identifier, "a"),
+				(Keyword, "XOR"),
+				(Identifier, "b"),
+				(Keyword, "xor"),
+				(Identifier, "c"),
+				(Identifier, "Xorx"),
+			]
+		);
+	}
+
+	#[test]
+	fn test_tokenize_script_and_invalid_input() {
+		use TokenKind::*;
+		assert_eq!(
+			lex("# note\nlet x = a.b @ é"),
+			vec![
+				(Comment, "# note"),
+				(Keyword, "let"),
+				(Identifier, "x"),
+				(Operator, "="),
+				(Attribute, "a.b"),
+				(Unknown, "@"),
+				(Unknown, "é"),
+			]
+		);
+		assert_eq!(lex("const MAX = 7")[0], (Keyword, "const"));
+		assert_eq!(
+			lex("a.b == \"//x\" // why\n/ c"),
+			vec![
+				(Attribute, "a.b"),
+				(Operator, "=="),
+				(String, "\"//x\""),
+				(Comment, "// why"),
+				(Unknown, "/"),
+				(Identifier, "c"),
+			]
+		);
+		assert_eq!(
+			lex("a. \"open"),
+			vec![(Identifier, "a"), (Unknown, "."), (String, "\"open")]
+		);
+		assert_eq!(
+			lex("s.*.e > 1 AND s.*"),
+			vec![
+				(Attribute, "s.*.e"),
+				(Operator, ">"),
+				(Number, "1"),
+				(Keyword, "AND"),
+				(Identifier, "s"),
+				(Unknown, "."),
+				(Unknown, "*"),
+			]
+		);
+	}
+
+	#[test]
+	fn test_tokenize_numbers() {
+		use TokenKind::*;
+		assert_eq!(
+			lex("1_000 1.5e6 2E-3 0xFF_FF 1e x"),
+			vec![
+				(Number, "1_000"),
+				(Number, "1.5e6"),
+				(Number, "2E-3"),
+				(Number, "0xFF_FF"),
+				(Number, "1"),
+				(Identifier, "e"),
+				(Identifier, "x"),
+			]
+		);
+		assert_eq!(
+			lex("a ==* b EQI c"),
+			vec![
+				(TokenKind::Identifier, "a"),
+				(TokenKind::Operator, "==*"),
+				(TokenKind::Identifier, "b"),
+				(TokenKind::Keyword, "EQI"),
+				(TokenKind::Identifier, "c"),
+			]
+		);
+		assert_eq!(
+			lex("net.port IN 1..a.max"),
+			vec![
+				(Attribute, "net.port"),
+				(Keyword, "IN"),
+				(Number, "1"),
+				(Operator, ".."),
+				(Attribute, "a.max"),
+			]
+		);
+	}
+}
+
+// endregion: --- Tests
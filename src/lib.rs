@@ -128,24 +128,147 @@
 //! assert!(trace.result);
 //! assert_eq!(trace.atoms.len(), 1);
 //! ```
+//!
+//! # `no_std`
+//!
+//! With default features disabled the parser, evaluator, built-ins, tracing, rule sets
+//! and schema parsing build with `no_std + alloc`. The `std` feature (on by default)
+//! adds package loading from disk, audit logs, deadlines, analysis and the REPL.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
-use pest::iterators::Pair;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
 use pest_derive::Parser;
-use std::collections::BTreeMap;
-use std::sync::Arc;
+
+/// Items of the std prelude that come from `alloc`, for modules that build without `std`
+mod prelude {
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
 
 pub mod schema;
+#[cfg(feature = "std")]
+pub use schema::package::{
+    DependencyPatch, DependencyRequirement, PackageError, PackageLint, PackageLintKind,
+    PackageManifest, PackageRegistry, PackageRule, SchemaPackage, TypeEnvironment,
+    WorkspaceManifest,
+};
 pub use schema::{
-    package::{PackageError, PackageManifest, PackageRegistry, SchemaPackage, TypeEnvironment},
-    parse_schema, FieldDef, FieldType, Schema, TypeDef,
+    parse_schema, AliasDef, EnumDef, EnumVariant, FieldDef, FieldMigration, FieldType, Schema,
+    TypeDef,
 };
 
 pub mod builtins;
-pub use builtins::{BuiltinFn, BuiltinsProvider, BuiltinsRegistry, CoreBuiltinsProvider};
+pub use builtins::{
+    BuiltinArgs, BuiltinFn, BuiltinFnWithOptions, BuiltinsProvider, BuiltinsRegistry,
+    BuiltinsRegistryBuilder, CoreBuiltinsProvider, RegistrationError, UnknownFunction,
+};
 
 pub mod trace;
-pub use trace::{evaluate_with_trace, AtomTrace as TraceAtom, EvalTrace};
+pub use trace::{
+    evaluate_with_trace, evaluate_with_trace_options, evaluate_with_trace_redacted,
+    evaluate_with_trace_redacted_options, AtomTiming, AtomTrace as TraceAtom, EvalTrace,
+    TraceOptions, TraceVerbosity,
+};
+
+pub mod rules;
+pub use rules::{
+    EvaluationStrategy, OutcomeReport, RuleOutcome, RuleScore, RuleSet, ScoreContribution,
+    StrategyMatch, TagFilter,
+};
+
+pub mod stream;
+pub use stream::{evaluate_stream, EvalStream, MatchResult};
+
+pub mod simulate;
+pub use simulate::{RecordDelta, RuleChange, RuleDelta, SimulationReport};
+
+pub mod aggregate;
+pub use aggregate::{AtomStats, RuleStats, TraceAggregator};
+
+pub mod facts;
+pub use facts::SharedFactsContext;
+
+pub mod options;
+pub use options::{CancellationToken, EvalOptions, NonFinitePolicy};
+
+pub mod diagnostics;
+pub use diagnostics::{Diagnostics, Warning, WarningKind};
+
+#[cfg(feature = "std")]
+pub mod determinism;
+#[cfg(feature = "std")]
+pub use determinism::{DeterminismError, DeterminismVerifier};
+
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub use audit::{AuditLog, AuditRecord, AuditSink, JsonlAuditSink};
+
+#[cfg(feature = "std")]
+pub mod engine;
+#[cfg(feature = "std")]
+pub use engine::{HelEngine, PrecompileFailure, PrecompileReport};
+
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub use replay::{replay, EvalSnapshot, ReplayError};
+
+pub mod redact;
+pub use redact::{RedactionPolicy, Redactor};
+
+#[cfg(feature = "std")]
+pub mod access;
+#[cfg(feature = "std")]
+pub use access::{AccessManifest, RecordingResolver};
+
+pub mod visit;
+pub use visit::{walk_ast, Visitor, Walk};
+
+pub mod rewrite;
+pub use rewrite::{rewrite_ast, Rewriter};
+
+pub mod mutate;
+pub use mutate::{mutation_test, MutantOutcome, Mutation, MutationKind, MutationReport};
+
+pub mod lexer;
+pub use lexer::{tokenize, Token, TokenKind};
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "byte-stats")]
+pub mod byte_stats;
+#[cfg(feature = "byte-stats")]
+pub use byte_stats::ByteStatsProvider;
+pub mod cel;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod repl;
+#[cfg(feature = "miette")]
+pub mod report;
+#[cfg(feature = "miette")]
+pub use report::HelReport;
+pub mod sql;
+#[cfg(feature = "std")]
+pub mod testing;
+pub mod url;
+pub use url::UrlBuiltinsProvider;
 
 /// HEL parser generated by Pest
 ///
@@ -198,6 +321,16 @@ pub enum AstNode {
         /// Field name
         field: Arc<str>,
     },
+    /// Wildcard attribute (`object.*.field`): `field` of every fact under `object`
+    ///
+    /// A comparison against it holds if it holds for any match; as a value it is the
+    /// list of matches (see `HelResolver::resolve_wildcard`).
+    Wildcard {
+        /// Object name
+        object: Arc<str>,
+        /// Field read from each match
+        field: Arc<str>,
+    },
     /// Comparison expression (left op right)
     Comparison {
         /// Left operand
@@ -206,11 +339,15 @@ pub enum AstNode {
         op: Comparator,
         /// Right operand
         right: Box<AstNode>,
+        /// Location of the comparison in the parsed text (ignored by equality)
+        span: SourceSpan,
     },
     /// Logical AND expression
     And(Vec<AstNode>),
     /// Logical OR expression
     Or(Vec<AstNode>),
+    /// Logical XOR expression: true if an odd number of operands are true
+    Xor(Vec<AstNode>),
     /// List literal: [1, 2, 3] or ["a", "b"]
     ListLiteral(Vec<AstNode>),
     /// Map literal: {"key": value, ...}
@@ -223,9 +360,90 @@ pub enum AstNode {
         name: Arc<str>,
         /// Arguments
         args: Vec<AstNode>,
+        /// Location of the call in the parsed text (ignored by equality)
+        span: SourceSpan,
+    },
+    /// Inclusive numeric range: `lo..hi` (right side of `IN`; `x BETWEEN lo AND hi` parses to
+    /// `x IN lo..hi`)
+    Range {
+        /// Lower bound
+        start: Box<AstNode>,
+        /// Upper bound
+        end: Box<AstNode>,
     },
 }
 
+// Spans are location metadata, so nodes compare equal however they were formatted
+impl PartialEq for AstNode {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::String(a), Self::String(b)) | (Self::Identifier(a), Self::Identifier(b)) => {
+                a == b
+            }
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            (
+                Self::Attribute {
+                    object: o1,
+                    field: f1,
+                },
+                Self::Attribute {
+                    object: o2,
+                    field: f2,
+                },
+            )
+            | (
+                Self::Wildcard {
+                    object: o1,
+                    field: f1,
+                },
+                Self::Wildcard {
+                    object: o2,
+                    field: f2,
+                },
+            ) => o1 == o2 && f1 == f2,
+            (
+                Self::Comparison {
+                    left: l1,
+                    op: op1,
+                    right: r1,
+                    ..
+                },
+                Self::Comparison {
+                    left: l2,
+                    op: op2,
+                    right: r2,
+                    ..
+                },
+            ) => op1 == op2 && l1 == l2 && r1 == r2,
+            (Self::And(a), Self::And(b))
+            | (Self::Or(a), Self::Or(b))
+            | (Self::Xor(a), Self::Xor(b))
+            | (Self::ListLiteral(a), Self::ListLiteral(b)) => a == b,
+            (Self::MapLiteral(a), Self::MapLiteral(b)) => a == b,
+            (
+                Self::FunctionCall {
+                    namespace: n1,
+                    name: m1,
+                    args: a1,
+                    ..
+                },
+                Self::FunctionCall {
+                    namespace: n2,
+                    name: m2,
+                    args: a2,
+                    ..
+                },
+            ) => n1 == n2 && m1 == m2 && a1 == a2,
+            (Self::Range { start: s1, end: e1 }, Self::Range { start: s2, end: e2 }) => {
+                s1 == s2 && e1 == e2
+            }
+            _ => false,
+        }
+    }
+}
+
 /// Comparison operators supported by HEL
 ///
 /// These operators are used in comparison expressions to compare two values.
@@ -252,6 +470,14 @@ pub enum AstNode {
 /// // Contains (for lists and strings)
 /// ctx.add_fact("vars.list", Value::List(vec![1.0.into(), 2.0.into()]));
 /// assert!(evaluate(r#"vars.list CONTAINS 1"#, &ctx).unwrap());
+///
+/// // Case-insensitive equality: EQI (or ==*)
+/// ctx.add_fact("vars.name", Value::String("PowerShell.EXE".into()));
+/// assert!(evaluate(r#"vars.name EQI "powershell.exe""#, &ctx).unwrap());
+///
+/// // Glob match: LIKE (backslashes are literal characters)
+/// ctx.add_fact("vars.path", Value::String(r"C:\Windows\System32\cmd.exe".into()));
+/// assert!(evaluate(r#"vars.path LIKE "C:\Windows\*\*.exe""#, &ctx).unwrap());
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Comparator {
@@ -271,6 +497,49 @@ pub enum Comparator {
     Contains,
     /// IN operator for membership tests (e.g., "a" IN ["a", "b"])
     In,
+    /// Case-insensitive string equality (EQI, alias ==*); other values compare as ==
+    EqIgnoreCase,
+    /// Glob match of a string against a pattern (LIKE): `*` matches any run of
+    /// characters, `?` exactly one; every other character matches itself
+    Like,
+}
+
+/// Location of a node in the text it was parsed from
+///
+/// `start..end` is a byte range; `line` and `column` (1-based, counting characters)
+/// locate `start`. Spans are metadata: they never affect equality of `AstNode`s, so an
+/// expression compares equal however it was formatted. Nodes built in code carry the
+/// default, unknown span.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{parse_expression, AstNode};
+///
+/// let source = "binary.signed == false AND\n  binary.entropy > 7.5";
+/// let AstNode::Or(alternatives) = parse_expression(source).unwrap() else { panic!() };
+/// let AstNode::And(atoms) = &alternatives[0] else { panic!() };
+/// let AstNode::Comparison { span, .. } = &atoms[1] else { panic!() };
+/// assert_eq!(&source[span.start..span.end], "binary.entropy > 7.5");
+/// assert_eq!((span.line, span.column), (2, 3));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// Byte offset of the first character
+    pub start: usize,
+    /// Byte offset just past the last character
+    pub end: usize,
+    /// Line of `start` (1-based; 0 if unknown)
+    pub line: u32,
+    /// Column of `start` in characters (1-based; 0 if unknown)
+    pub column: u32,
+}
+
+impl SourceSpan {
+    /// Whether the span was recorded by the parser
+    pub fn is_known(&self) -> bool {
+        self.line > 0
+    }
 }
 
 /// Runtime value type for HEL evaluation
@@ -330,6 +599,25 @@ pub trait HelResolver {
     /// Returns `Some(Value)` if the attribute exists, `None` if missing.
     /// Missing attributes are treated as `Null` by the evaluator.
     fn resolve_attr(&self, object: &str, field: &str) -> Option<Value>;
+
+    /// Resolve a wildcard path (`object.*.field`) to every matching value
+    ///
+    /// Returns `(path, value)` pairs in a stable order, where `path` names the
+    /// concrete fact (e.g. `sections.text.entropy`). The default finds no matches.
+    fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+        let _ = (object, field);
+        Vec::new()
+    }
+}
+
+impl<T: HelResolver + ?Sized> HelResolver for &T {
+    fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+        (**self).resolve_attr(object, field)
+    }
+
+    fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+        (**self).resolve_wildcard(object, field)
+    }
 }
 
 /// Evaluation context that includes resolver and optional built-ins registry
@@ -360,6 +648,20 @@ pub struct EvalContext<'a> {
     builtins: Option<&'a builtins::BuiltinsRegistry>,
     /// Variable bindings for let expressions (name -> value)
     variables: BTreeMap<Arc<str>, Value>,
+    /// Deadline, cancellation, trace and other evaluation options
+    options: EvalOptions,
+    /// Redactor applied to resolved values captured in traces
+    redactor: Option<&'a dyn Redactor>,
+    /// Accumulates resolver and built-in time while tracing with timings
+    timer: Option<&'a trace::Timer>,
+    /// Collector for non-fatal warnings
+    diagnostics: Option<&'a Diagnostics>,
+    /// Schema enums referenced as `Enum.Variant` constants
+    enums: Option<&'a BTreeMap<Arc<str>, schema::EnumDef>>,
+    /// Memoized results of rules referenced with `rule("id")`
+    rule_results: Option<&'a BTreeMap<Arc<str>, bool>>,
+    /// Built-in calls made so far per namespace, checked against `EvalOptions::call_quotas`
+    call_counts: core::cell::RefCell<BTreeMap<String, u32>>,
 }
 
 impl<'a> EvalContext<'a> {
@@ -369,6 +671,13 @@ impl<'a> EvalContext<'a> {
             resolver,
             builtins: None,
             variables: BTreeMap::new(),
+            options: EvalOptions::default(),
+            redactor: None,
+            timer: None,
+            diagnostics: None,
+            enums: None,
+            rule_results: None,
+            call_counts: Default::default(),
         }
     }
 
@@ -381,6 +690,137 @@ impl<'a> EvalContext<'a> {
             resolver,
             builtins: Some(builtins),
             variables: BTreeMap::new(),
+            options: EvalOptions::default(),
+            redactor: None,
+            timer: None,
+            diagnostics: None,
+            enums: None,
+            rule_results: None,
+            call_counts: Default::default(),
+        }
+    }
+
+    /// Apply evaluation options (deadline, cancellation, numeric policies, trace options)
+    ///
+    /// Parse limits are not used here: the expression is already parsed.
+    pub fn with_options(mut self, options: EvalOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Dispatch function calls to `builtins`, replacing any registry set before
+    pub(crate) fn with_registry(mut self, builtins: &'a builtins::BuiltinsRegistry) -> Self {
+        self.builtins = Some(builtins);
+        self
+    }
+
+    /// Redact sensitive resolved values before they are stored in traces
+    pub fn with_redactor(mut self, redactor: &'a dyn Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Limit how much of each resolved value is stored in traces
+    pub fn with_trace_options(mut self, options: trace::TraceOptions) -> Self {
+        self.options.trace = options;
+        self
+    }
+
+    /// Record warnings (missing facts, lenient coercions, deprecated built-ins) in `diagnostics`
+    pub fn with_diagnostics(mut self, diagnostics: &'a Diagnostics) -> Self {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Resolve `Enum.Variant` references against the enums of a type environment
+    ///
+    /// An enum name shadows a fact object of the same name.
+    #[cfg(feature = "std")]
+    pub fn with_types(mut self, types: &'a TypeEnvironment) -> Self {
+        self.enums = Some(&types.enums);
+        self
+    }
+
+    /// Results of already evaluated rules, read by `rule("id")` references
+    pub(crate) fn with_rule_results(mut self, results: &'a BTreeMap<Arc<str>, bool>) -> Self {
+        self.rule_results = Some(results);
+        self
+    }
+
+    /// Value of `object.field` if `object` names a schema enum
+    fn enum_constant(&self, object: &str, field: &str) -> Option<Result<Value, EvalError>> {
+        let enum_def = self.enums?.values().find(|e| e.name.as_ref() == object)?;
+        Some(match enum_def.variant(field) {
+            Some(variant) => Ok(variant.value.clone()),
+            None => Err(EvalError::UnknownAttribute {
+                object: object.to_string(),
+                field: field.to_string(),
+            }),
+        })
+    }
+
+    /// Count a call to a function of `namespace`, failing once its quota is used up
+    fn count_call(&self, namespace: &str) -> Result<(), EvalError> {
+        let Some(&limit) = self.options.call_quotas.get(namespace) else {
+            return Ok(());
+        };
+        let mut counts = self.call_counts.borrow_mut();
+        let count = counts.entry(namespace.to_string()).or_insert(0);
+        if *count >= limit {
+            return Err(EvalError::QuotaExceeded {
+                namespace: namespace.to_string(),
+                limit,
+            });
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    /// Short form of a built-in argument for error messages, redacted like trace values
+    fn summarize_arg(&self, arg: &AstNode, value: &Value) -> String {
+        const MAX_ARG_LEN: usize = 32;
+        let text = match value {
+            Value::String(s) => format!("{:?}", s),
+            other => other.to_string(),
+        };
+        if let Some(redactor) = self.redactor {
+            let path = match arg {
+                AstNode::Attribute { object, field } => format!("{}.{}", object, field),
+                _ => String::new(),
+            };
+            if let Some(replacement) = redactor.redact(&path, &value.to_string()) {
+                return replacement;
+            }
+        }
+        match text.char_indices().nth(MAX_ARG_LEN) {
+            Some((cut, _)) => format!("{}{}", &text[..cut], trace::TRUNCATION_MARKER),
+            None => text,
+        }
+    }
+
+    /// Record a warning if a collector is attached (the message is only built then)
+    fn warn(&self, kind: WarningKind, message: impl FnOnce() -> String) {
+        if let Some(diagnostics) = self.diagnostics {
+            diagnostics.warn(kind, message());
+        }
+    }
+
+    /// The same context resolving facts through `resolver`
+    fn with_resolver<'b>(&self, resolver: &'b dyn HelResolver) -> EvalContext<'b>
+    where
+        'a: 'b,
+    {
+        EvalContext {
+            resolver,
+            builtins: self.builtins,
+            variables: self.variables.clone(),
+            options: self.options.clone(),
+            redactor: self.redactor,
+            timer: self.timer,
+            diagnostics: self.diagnostics,
+            enums: self.enums,
+            rule_results: self.rule_results,
+            call_counts: self.call_counts.clone(),
         }
     }
 
@@ -429,10 +869,81 @@ pub enum EvalError {
     InvalidOperation(String),
     /// Parse error occurred
     ParseError(String),
+    /// Evaluation deadline was exceeded
+    Timeout,
+    /// Evaluation was cancelled through a `CancellationToken`
+    Cancelled,
+    /// More calls to a namespace's functions than `EvalOptions::with_call_quota` allows
+    QuotaExceeded {
+        /// Namespace whose quota was exhausted
+        namespace: String,
+        /// Calls allowed per evaluation
+        limit: u32,
+    },
+    /// A built-in function returned an error; wraps it with the call site
+    BuiltinFailed {
+        /// Called function, `namespace.function`
+        function: String,
+        /// Summaries of the argument values, in call order (long values are truncated)
+        args: Vec<String>,
+        /// Location of the call in the rule text (unknown for ASTs built in code)
+        span: SourceSpan,
+        /// Error returned by the built-in
+        source: Box<EvalError>,
+    },
+    /// Structured failure reported by a built-in (see `EvalError::builtin_failure`)
+    BuiltinFailure {
+        /// Namespace of the failing built-in (filled in by the registry)
+        namespace: String,
+        /// Function name of the failing built-in (filled in by the registry)
+        name: String,
+        /// Stable, machine-readable failure code (e.g., `invalid_pattern`)
+        code: String,
+        /// Values describing the failure
+        details: BTreeMap<String, Value>,
+    },
+}
+
+impl EvalError {
+    /// Structured failure for a built-in to return, so hosts can branch on `code`
+    ///
+    /// `BuiltinsRegistry::call` fills in the namespace and name of the built-in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{EvalError, Value};
+    ///
+    /// let err = EvalError::builtin_failure("invalid_pattern", [("pattern", Value::from("(a"))]);
+    /// assert!(matches!(&err, EvalError::BuiltinFailure { code, .. } if code == "invalid_pattern"));
+    /// ```
+    pub fn builtin_failure<'k>(
+        code: &str,
+        details: impl IntoIterator<Item = (&'k str, Value)>,
+    ) -> Self {
+        EvalError::BuiltinFailure {
+            namespace: String::new(),
+            name: String::new(),
+            code: code.to_string(),
+            details: details
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        }
+    }
+
+    /// The error a built-in returned, looking through the call-site context of
+    /// `BuiltinFailed`; any other error is returned as is
+    pub fn cause(&self) -> &EvalError {
+        match self {
+            EvalError::BuiltinFailed { source, .. } => source.cause(),
+            other => other,
+        }
+    }
 }
 
-impl std::fmt::Display for EvalError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             EvalError::UnknownAttribute { object, field } => {
                 write!(f, "Unknown attribute: {}.{}", object, field)
@@ -450,11 +961,57 @@ impl std::fmt::Display for EvalError {
             }
             EvalError::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
             EvalError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            EvalError::Timeout => write!(f, "Evaluation timed out"),
+            EvalError::Cancelled => write!(f, "Evaluation cancelled"),
+            EvalError::QuotaExceeded { namespace, limit } => write!(
+                f,
+                "Call quota exceeded: more than {} calls to `{}` functions",
+                limit, namespace
+            ),
+            EvalError::BuiltinFailed {
+                function,
+                args,
+                span,
+                source,
+            } => {
+                write!(f, "`{}({})` failed", function, args.join(", "))?;
+                if span.is_known() {
+                    write!(f, " at line {}, column {}", span.line, span.column)?;
+                }
+                write!(f, ": {}", source)
+            }
+            EvalError::BuiltinFailure {
+                namespace,
+                name,
+                code,
+                details,
+            } => {
+                write!(
+                    f,
+                    "Built-in `{}.{}` failed with `{}`",
+                    namespace, name, code
+                )?;
+                for (i, (key, value)) in details.iter().enumerate() {
+                    let separator = if i == 0 { " (" } else { ", " };
+                    write!(f, "{}{}: {}", separator, key, value)?;
+                }
+                if !details.is_empty() {
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-impl std::error::Error for EvalError {}
+impl core::error::Error for EvalError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            EvalError::BuiltinFailed { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 /// Enhanced error type for HEL with line/column information
 ///
@@ -505,6 +1062,21 @@ pub enum ErrorKind {
     TypeError,
     /// Unknown attribute (attempted to access non-existent field)
     UnknownAttribute,
+    /// A configured resource limit was exceeded (e.g., expression size or nesting depth)
+    LimitExceeded,
+}
+
+impl ErrorKind {
+    /// Stable machine-readable code, e.g. `hel::parse`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::ParseError => "hel::parse",
+            ErrorKind::EvaluationError => "hel::eval",
+            ErrorKind::TypeError => "hel::type",
+            ErrorKind::UnknownAttribute => "hel::unknown_attribute",
+            ErrorKind::LimitExceeded => "hel::limit",
+        }
+    }
 }
 
 impl HelError {
@@ -557,10 +1129,41 @@ impl HelError {
             kind: ErrorKind::UnknownAttribute,
         }
     }
+
+    /// Create a resource limit error
+    pub fn limit_exceeded(message: String) -> Self {
+        Self {
+            message,
+            line: None,
+            column: None,
+            kind: ErrorKind::LimitExceeded,
+        }
+    }
+
+    /// Byte offset of the error location in `source`, if the error has one
+    ///
+    /// Lines and columns are 1-based; columns count characters. Locations past the end
+    /// of a line or of the source are clamped.
+    pub fn offset_in(&self, source: &str) -> Option<usize> {
+        let (line, column) = (self.line?, self.column?);
+        let line_start: usize = source
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let line_text = source[line_start..].split('\n').next().unwrap_or("");
+        Some(
+            line_start
+                + line_text
+                    .char_indices()
+                    .nth(column.saturating_sub(1))
+                    .map_or(line_text.len(), |(i, _)| i),
+        )
+    }
 }
 
-impl std::fmt::Display for HelError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for HelError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if let (Some(line), Some(column)) = (self.line, self.column) {
             write!(
                 f,
@@ -573,7 +1176,7 @@ impl std::fmt::Display for HelError {
     }
 }
 
-impl std::error::Error for HelError {}
+impl core::error::Error for HelError {}
 
 impl From<EvalError> for HelError {
     fn from(err: EvalError) -> Self {
@@ -591,6 +1194,26 @@ impl From<EvalError> for HelError {
                 HelError::unknown_attribute(format!("Unknown attribute: {}.{}", object, field))
             }
             EvalError::InvalidOperation(msg) => HelError::eval_error(msg),
+            EvalError::BuiltinFailure { .. } => HelError::eval_error(err.to_string()),
+            EvalError::Timeout | EvalError::Cancelled | EvalError::QuotaExceeded { .. } => {
+                HelError::limit_exceeded(err.to_string())
+            }
+            EvalError::BuiltinFailed { span, .. } => {
+                // Keep the kind of the built-in's own error, locating it at the call
+                let message = err.to_string();
+                let EvalError::BuiltinFailed { source, .. } = err else {
+                    unreachable!()
+                };
+                let location = span
+                    .is_known()
+                    .then_some((span.line as usize, span.column as usize));
+                HelError {
+                    message,
+                    line: location.map(|(line, _)| line),
+                    column: location.map(|(_, column)| column),
+                    ..HelError::from(*source)
+                }
+            }
         }
     }
 }
@@ -617,45 +1240,285 @@ impl From<EvalError> for HelError {
 /// let ast = parse_rule(r#"binary.format == "elf""#);
 /// ```
 pub fn parse_rule(input: &str) -> AstNode {
-    let mut pairs = HelParser::parse(Rule::condition, input).expect("parse error");
-    build_ast(pairs.next().unwrap())
+    parse_expression(input).unwrap_or_else(|e| panic!("parse error: {}", e))
 }
 
-fn build_ast(pair: Pair<Rule>) -> AstNode {
-    match pair.as_rule() {
-        Rule::condition => {
-            let mut inner = pair.into_inner();
-            let next = inner.next().expect("Empty condition");
-            build_ast(next)
+/// Default maximum expression length in bytes (1 MiB)
+pub const DEFAULT_MAX_INPUT_LEN: usize = 1 << 20;
+
+/// Default maximum nesting depth of parentheses, lists, maps and function calls
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 32;
+
+/// Resource limits applied while parsing an expression
+///
+/// Pathological inputs such as `((((...))))` can exhaust the stack in the
+/// recursive-descent parser. These limits are checked before parsing and while
+/// building the AST, and violations are reported as `ErrorKind::LimitExceeded`.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{parse_expression_with_limits, ErrorKind, ParseLimits};
+///
+/// let limits = ParseLimits {
+///     max_nesting_depth: 2,
+///     ..ParseLimits::default()
+/// };
+///
+/// assert!(parse_expression_with_limits("((a.b == 1))", &limits).is_ok());
+///
+/// let err = parse_expression_with_limits("(((a.b == 1)))", &limits).unwrap_err();
+/// assert!(matches!(err.kind, ErrorKind::LimitExceeded));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum expression length in bytes
+    pub max_input_len: usize,
+    /// Maximum nesting depth of parentheses, lists, maps and function calls
+    pub max_nesting_depth: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_input_len: DEFAULT_MAX_INPUT_LEN,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
         }
+    }
+}
 
-        Rule::logical_and | Rule::logical_or => {
-            let is_and = pair.as_rule() == Rule::logical_and;
-            let nodes: Vec<AstNode> = pair
-                .into_inner()
-                .filter_map(|inner| match inner.as_rule() {
-                    Rule::and_op | Rule::or_op => None,
-                    _ => Some(build_ast(inner)),
-                })
-                .collect();
+/// Reject inputs that exceed the parse limits before handing them to pest
+///
+/// Nesting is measured by bracket depth outside of string literals and comments, which is an
+/// upper bound on the recursion depth of the grammar.
+fn check_parse_limits(input: &str, limits: &ParseLimits) -> Result<(), HelError> {
+    if input.len() > limits.max_input_len {
+        return Err(HelError::limit_exceeded(format!(
+            "Expression length {} exceeds maximum of {} bytes",
+            input.len(),
+            limits.max_input_len
+        )));
+    }
 
-            if is_and {
-                AstNode::And(nodes)
-            } else {
-                AstNode::Or(nodes)
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut chars = input.chars().peekable();
+    let (mut line, mut column) = (1, 0);
+    while let Some(ch) = chars.next() {
+        if ch == '\n' {
+            line += 1;
+            column = 0;
+            in_comment = false;
+        } else {
+            column += 1;
+        }
+
+        match ch {
+            _ if in_comment => {}
+            '#' if !in_string => in_comment = true,
+            '/' if !in_string && chars.peek() == Some(&'/') => in_comment = true,
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => {
+                depth += 1;
+                if depth > limits.max_nesting_depth {
+                    let mut err = nesting_error(limits);
+                    err.line = Some(line);
+                    err.column = Some(column);
+                    return Err(err);
+                }
             }
+            ')' | ']' | '}' if !in_string => depth = depth.saturating_sub(1),
+            _ => {}
         }
+    }
+    Ok(())
+}
 
-        Rule::comparison => {
-            let mut inner = pair.into_inner();
-            let left = build_ast(inner.next().expect("Missing left operand"));
-            let op = parse_comparator(inner.next().expect("Missing comparator"));
-            let right = build_ast(inner.next().expect("Missing right operand"));
+/// Span from the start of `from` to the end of `to`
+fn span_between(from: &pest::Span, to: &pest::Span) -> SourceSpan {
+    let (line, column) = from.start_pos().line_col();
+    SourceSpan {
+        start: from.start(),
+        end: to.end(),
+        line: line as u32,
+        column: column as u32,
+    }
+}
+
+fn nesting_error(limits: &ParseLimits) -> HelError {
+    HelError::limit_exceeded(format!(
+        "Nesting depth exceeds maximum of {}",
+        limits.max_nesting_depth
+    ))
+}
+
+/// Comparison `left op right`, or for a chain `a < b <= c` the `And` of its links
+/// (`a < b AND b <= c`); the shared operands are repeated in each link
+///
+/// Only the ordering comparators `<`, `<=`, `>` and `>=` can be chained.
+fn build_comparisons(
+    left: AstNode,
+    left_span: pest::Span,
+    comparator: Pair<Rule>,
+    mut rest: Pairs<Rule>,
+    depth: usize,
+    limits: &ParseLimits,
+) -> Result<AstNode, HelError> {
+    let mut links = Vec::new();
+    let mut left = left;
+    let mut left_span = left_span;
+    let mut next = Some(comparator);
+    while let Some(comparator) = next {
+        let (line, column) = comparator.line_col();
+        let token = comparator.as_str();
+        let op = parse_comparator(comparator);
+        let right_pair = rest.next().expect("Missing right operand");
+        let right_span = right_pair.as_span();
+        let right = build_ast(right_pair, depth, limits)?;
+        next = rest.next();
+        let chained = next.is_some() || !links.is_empty();
+        if chained
+            && !matches!(
+                op,
+                Comparator::Lt | Comparator::Le | Comparator::Gt | Comparator::Ge
+            )
+        {
+            return Err(HelError::parse_error_at(
+                format!(
+                    "`{}` cannot be chained; only <, <=, > and >= comparisons can",
+                    token
+                ),
+                line,
+                column,
+            ));
+        }
+        links.push(AstNode::Comparison {
+            left: Box::new(left),
+            op,
+            right: Box::new(right.clone()),
+            span: span_between(&left_span, &right_span),
+        });
+        left = right;
+        left_span = right_span;
+    }
+
+    Ok(match links.len() {
+        1 => links.pop().expect("one link"),
+        _ => AstNode::And(links),
+    })
+}
+
+/// `logical_or` with operands joined by XOR grouped into `Xor` nodes (XOR binds tighter
+/// than OR); XOR-free input keeps the `Or` of `And` shape
+///
+/// Kept out of `build_ast` so its locals do not grow that function's stack frame, which
+/// recurses once per nesting level.
+fn build_or(pair: Pair<Rule>, depth: usize, limits: &ParseLimits) -> Result<AstNode, HelError> {
+    let mut groups: Vec<Vec<AstNode>> = Vec::new();
+    let mut xor = false;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::xor_op => xor = true,
+            Rule::or_op => {}
+            _ => {
+                let node = build_ast(inner, depth, limits)?;
+                match groups.last_mut() {
+                    Some(group) if xor => group.push(node),
+                    _ => groups.push(vec![node]),
+                }
+                xor = false;
+            }
+        }
+    }
+
+    let nodes = groups
+        .into_iter()
+        .map(|mut group| match group.len() {
+            1 => group.pop().expect("Empty XOR group"),
+            _ => AstNode::Xor(group),
+        })
+        .collect();
+    Ok(AstNode::Or(nodes))
+}
+
+/// Operand, optionally compared or tested against a range (`x IN lo..hi`, `x BETWEEN lo AND hi`)
+fn build_term(pair: Pair<Rule>, depth: usize, limits: &ParseLimits) -> Result<AstNode, HelError> {
+    let mut inner = pair.into_inner();
+    let left_pair = inner.next().expect("Missing left operand");
+    let left_span = left_pair.as_span();
+    let left = build_ast(left_pair, depth, limits)?;
+    Ok(match inner.next() {
+        Some(range) if matches!(range.as_rule(), Rule::range_in | Rule::between) => {
+            let span = span_between(&left_span, &range.as_span());
+            let mut bounds = range.into_inner();
+            if bounds.peek().map(|pair| pair.as_rule()) == Some(Rule::range) {
+                bounds = bounds.next().expect("Missing range").into_inner();
+            }
+            let start = build_ast(bounds.next().expect("Missing range start"), depth, limits)?;
+            let end = build_ast(bounds.next().expect("Missing range end"), depth, limits)?;
 
             AstNode::Comparison {
                 left: Box::new(left),
-                op,
-                right: Box::new(right),
+                op: Comparator::In,
+                right: Box::new(AstNode::Range {
+                    start: Box::new(start),
+                    end: Box::new(end),
+                }),
+                span,
+            }
+        }
+        Some(comparator) => build_comparisons(left, left_span, comparator, inner, depth, limits)?,
+        None => left,
+    })
+}
+
+fn build_ast(pair: Pair<Rule>, depth: usize, limits: &ParseLimits) -> Result<AstNode, HelError> {
+    // Nested constructs increase depth; wrappers pass it through unchanged
+    let depth = match pair.as_rule() {
+        Rule::parenthesized | Rule::list_literal | Rule::map_literal | Rule::function_call => {
+            if depth >= limits.max_nesting_depth {
+                return Err(nesting_error(limits));
+            }
+            depth + 1
+        }
+        _ => depth,
+    };
+
+    let node = match pair.as_rule() {
+        Rule::condition => {
+            let mut inner = pair.into_inner();
+            let next = inner.next().expect("Empty condition");
+            build_ast(next, depth, limits)?
+        }
+
+        Rule::logical_and => {
+            let mut nodes = Vec::new();
+            for inner in pair.into_inner() {
+                if inner.as_rule() == Rule::and_op {
+                    continue;
+                }
+                match build_ast(inner, depth, limits)? {
+                    // A chained comparison joins the enclosing AND
+                    AstNode::And(links) => nodes.extend(links),
+                    node => nodes.push(node),
+                }
+            }
+
+            AstNode::And(nodes)
+        }
+
+        Rule::logical_or => build_or(pair, depth, limits)?,
+
+        Rule::term => build_term(pair, depth, limits)?,
+
+        Rule::wildcard_access => {
+            let mut inner = pair.into_inner();
+            let object = inner.next().expect("Missing object").as_str();
+            let field = inner.next().expect("Missing field").as_str();
+            AstNode::Wildcard {
+                object: object.into(),
+                field: field.into(),
             }
         }
 
@@ -671,13 +1534,17 @@ fn build_ast(pair: Pair<Rule>) -> AstNode {
 
         Rule::literal => {
             let inner_pair = pair.into_inner().next().expect("Empty literal");
-            build_ast(inner_pair)
+            build_ast(inner_pair, depth, limits)?
         }
 
         Rule::string_literal => AstNode::String(pair.as_str().trim_matches('"').into()),
 
         Rule::float_literal => {
-            let val = pair.as_str().parse::<f64>().expect("invalid float");
+            let val = pair
+                .as_str()
+                .replace('_', "")
+                .parse::<f64>()
+                .expect("invalid float");
             AstNode::Float(val)
         }
 
@@ -685,14 +1552,24 @@ fn build_ast(pair: Pair<Rule>) -> AstNode {
             let num_str = pair.as_str();
             match parse_number(num_str) {
                 Some(n) => AstNode::Number(n),
-                None => panic!("Failed to parse number literal: '{}'", num_str),
+                None => {
+                    let (line, column) = pair.line_col();
+                    return Err(HelError::parse_error_at(
+                        format!("Integer literal '{}' exceeds {}", num_str, u64::MAX),
+                        line,
+                        column,
+                    ));
+                }
             }
         }
 
         Rule::boolean_literal => AstNode::Bool(pair.as_str() == "true"),
 
         Rule::list_literal => {
-            let elements: Vec<AstNode> = pair.into_inner().map(|p| build_ast(p)).collect();
+            let elements = pair
+                .into_inner()
+                .map(|p| build_ast(p, depth, limits))
+                .collect::<Result<Vec<AstNode>, HelError>>()?;
             AstNode::ListLiteral(elements)
         }
 
@@ -703,54 +1580,70 @@ fn build_ast(pair: Pair<Rule>) -> AstNode {
                     let mut entry_inner = entry_pair.into_inner();
                     let key_pair = entry_inner.next().expect("Missing map key");
                     let key = key_pair.as_str().trim_matches('"').into();
-                    let value = build_ast(entry_inner.next().expect("Missing map value"));
+                    let value = build_ast(
+                        entry_inner.next().expect("Missing map value"),
+                        depth,
+                        limits,
+                    )?;
                     entries.push((key, value));
                 }
             }
             AstNode::MapLiteral(entries)
         }
 
-        Rule::function_call => {
-            let mut inner = pair.into_inner();
-            let first = inner.next().expect("Missing function name");
-
-            // Check if second element exists (namespace.function case)
-            let second = inner.next();
-            let (namespace, name, remaining_args) = if second.is_some() {
-                (
-                    Some(Arc::from(first.as_str())),
-                    Arc::from(second.unwrap().as_str()),
-                    inner,
-                )
-            } else {
-                (None, Arc::from(first.as_str()), inner)
-            };
-
-            // Parse arguments from remaining items
-            let args: Vec<AstNode> = remaining_args.map(|arg| build_ast(arg)).collect();
-
-            AstNode::FunctionCall {
-                namespace,
-                name,
-                args,
-            }
-        }
+        Rule::function_call => build_function_call(pair, depth, limits)?,
 
         Rule::identifier | Rule::variable | Rule::symbolic => {
             AstNode::Identifier(pair.as_str().into())
         }
 
-        Rule::primary | Rule::comparison_term | Rule::term | Rule::parenthesized => {
-            build_ast(pair.into_inner().next().expect("Empty wrapper"))
-        }
+        Rule::primary | Rule::parenthesized => build_ast(
+            pair.into_inner().next().expect("Empty wrapper"),
+            depth,
+            limits,
+        )?,
 
         _ => unreachable!("Unhandled rule: {:?}", pair.as_rule()),
-    }
+    };
+    Ok(node)
+}
+
+/// `namespace.function(args)` or `function(args)`
+fn build_function_call(
+    pair: Pair<Rule>,
+    depth: usize,
+    limits: &ParseLimits,
+) -> Result<AstNode, HelError> {
+    let call_span = pair.as_span();
+    let mut inner = pair.into_inner();
+    let first = inner.next().expect("Missing function name");
+
+    // A second identifier is the function name (namespace.function case);
+    // arguments are `primary` pairs
+    let (namespace, name) = match inner.peek() {
+        Some(second) if second.as_rule() == Rule::identifier => (
+            Some(Arc::from(first.as_str())),
+            Arc::from(inner.next().expect("Missing function name").as_str()),
+        ),
+        _ => (None, Arc::from(first.as_str())),
+    };
+
+    // Parse arguments from remaining items
+    let args = inner
+        .map(|arg| build_ast(arg, depth, limits))
+        .collect::<Result<Vec<AstNode>, HelError>>()?;
+
+    Ok(AstNode::FunctionCall {
+        namespace,
+        name,
+        args,
+        span: span_between(&call_span, &call_span),
+    })
 }
 
 fn parse_comparator(pair: Pair<Rule>) -> Comparator {
-    let token = pair.as_str().trim();
-    match token {
+    let token = pair.as_str().trim().to_ascii_uppercase();
+    match token.as_str() {
         "==" => Comparator::Eq,
         "!=" => Comparator::Ne,
         ">" => Comparator::Gt,
@@ -759,8 +1652,10 @@ fn parse_comparator(pair: Pair<Rule>) -> Comparator {
         "<=" => Comparator::Le,
         "CONTAINS" => Comparator::Contains,
         "IN" => Comparator::In,
+        "EQI" | "==*" => Comparator::EqIgnoreCase,
+        "LIKE" => Comparator::Like,
         _ => panic!(
-            "Unhandled comparator: {}. Supported comparators: ==, !=, >, >=, <, <=, CONTAINS, IN",
+            "Unhandled comparator: {}. Supported comparators: ==, !=, >, >=, <, <=, CONTAINS, IN, EQI, LIKE",
             token
         ),
     }
@@ -814,7 +1709,7 @@ pub fn evaluate_with_resolver(
     condition: &str,
     resolver: &dyn HelResolver,
 ) -> Result<bool, EvalError> {
-    let ast = parse_rule(condition);
+    let ast = parse_expression(condition).map_err(|e| EvalError::ParseError(e.message))?;
     let ctx = EvalContext::new(resolver);
     evaluate_ast_with_context(&ast, &ctx)
 }
@@ -838,15 +1733,14 @@ pub fn evaluate_with_resolver(
 /// # Examples
 ///
 /// ```
-/// use hel::{evaluate_with_context, HelResolver, Value, BuiltinsRegistry, CoreBuiltinsProvider};
+/// use hel::{evaluate_with_context, HelResolver, Value, BuiltinsRegistry};
 ///
 /// struct MyResolver;
 /// impl HelResolver for MyResolver {
 ///     fn resolve_attr(&self, _: &str, _: &str) -> Option<Value> { None }
 /// }
 ///
-/// let mut registry = BuiltinsRegistry::new();
-/// registry.register(&CoreBuiltinsProvider).expect("register failed");
+/// let registry = BuiltinsRegistry::builder().with_core().build().expect("register failed");
 ///
 /// let result = evaluate_with_context(
 ///     r#"core.len(["a", "b"]) == 2"#,
@@ -860,19 +1754,70 @@ pub fn evaluate_with_context(
     resolver: &dyn HelResolver,
     builtins: &builtins::BuiltinsRegistry,
 ) -> Result<bool, EvalError> {
-    let ast = parse_rule(condition);
+    let ast = parse_expression(condition).map_err(|e| EvalError::ParseError(e.message))?;
     let ctx = EvalContext::with_builtins(resolver, builtins);
     evaluate_ast_with_context(&ast, &ctx)
 }
 
-fn evaluate_ast_with_context(ast: &AstNode, ctx: &EvalContext) -> Result<bool, EvalError> {
-    match ast {
-        AstNode::Bool(b) => Ok(*b),
-        AstNode::And(nodes) => {
-            for node in nodes {
-                if !evaluate_ast_with_context(node, ctx)? {
-                    return Ok(false);
-                }
+/// Evaluate a HEL expression with explicit evaluation options (low-level API)
+///
+/// Like `evaluate_with_context()`, but the built-ins registry is optional, the
+/// condition is parsed with `options.limits` and the evaluation observes every
+/// other setting in `options`. An interrupted evaluation returns
+/// `EvalError::Timeout` or `EvalError::Cancelled`.
+pub fn evaluate_with_options(
+    condition: &str,
+    resolver: &dyn HelResolver,
+    builtins: Option<&builtins::BuiltinsRegistry>,
+    options: &EvalOptions,
+) -> Result<bool, EvalError> {
+    let ast = parse_expression_with_limits(condition, &options.limits)
+        .map_err(|e| EvalError::ParseError(e.message))?;
+    evaluate_ast_with_context(&ast, &options_context(resolver, builtins, options))
+}
+
+/// Context for the `*_with_options` entry points
+pub(crate) fn options_context<'a>(
+    resolver: &'a dyn HelResolver,
+    builtins: Option<&'a builtins::BuiltinsRegistry>,
+    options: &EvalOptions,
+) -> EvalContext<'a> {
+    match builtins {
+        Some(b) => EvalContext::with_builtins(resolver, b),
+        None => EvalContext::new(resolver),
+    }
+    .with_options(options.clone())
+}
+
+/// Evaluate an already-parsed expression (low-level API)
+///
+/// Use this with ASTs from `parse_expression()` or the `hel_macros::hel!` macro
+/// to avoid re-parsing rule text on every evaluation.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_ast, parse_expression, EvalContext, FactsEvalContext, Value};
+///
+/// let ast = parse_expression(r#"binary.format == "elf""#).expect("parse failed");
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("binary.format", Value::String("elf".into()));
+///
+/// assert!(evaluate_ast(&ast, &EvalContext::new(&facts)).unwrap());
+/// ```
+pub fn evaluate_ast(ast: &AstNode, ctx: &EvalContext) -> Result<bool, EvalError> {
+    evaluate_ast_with_context(ast, ctx)
+}
+
+fn evaluate_ast_with_context(ast: &AstNode, ctx: &EvalContext) -> Result<bool, EvalError> {
+    ctx.options.check_interrupt()?;
+    match ast {
+        AstNode::Bool(b) => Ok(*b),
+        AstNode::And(nodes) => {
+            for node in nodes {
+                if !evaluate_ast_with_context(node, ctx)? {
+                    return Ok(false);
+                }
             }
             Ok(true)
         }
@@ -884,9 +1829,18 @@ fn evaluate_ast_with_context(ast: &AstNode, ctx: &EvalContext) -> Result<bool, E
             }
             Ok(false)
         }
-        AstNode::Comparison { left, op, right } => {
-            evaluate_comparison_with_context(left, *op, right, ctx)
+        // No short-circuit: every operand decides the parity
+        AstNode::Xor(nodes) => {
+            let mut result = false;
+            for node in nodes {
+                result ^= evaluate_ast_with_context(node, ctx)?;
+            }
+            Ok(result)
         }
+        AstNode::Comparison {
+            left, op, right, ..
+        } => evaluate_comparison_with_context(left, *op, right, ctx),
+        AstNode::Wildcard { object, field } => any_wildcard_true(object, field, ctx),
         // Handle identifiers and other nodes that might evaluate to boolean
         other => {
             let value = eval_node_to_value_with_context(other, ctx)?;
@@ -908,15 +1862,192 @@ fn evaluate_comparison_with_context(
     right: &AstNode,
     ctx: &EvalContext,
 ) -> Result<bool, EvalError> {
+    if let Some(wildcard) = evaluate_wildcard(left, op, right, ctx)? {
+        return Ok(wildcard.result);
+    }
+    if let (Comparator::In, AstNode::Range { start, end }) = (op, right) {
+        return evaluate_range(left, start, end, ctx).map(|(.., result)| result);
+    }
     let left_val = eval_node_to_value_with_context(left, ctx)?;
     let right_val = eval_node_to_value_with_context(right, ctx)?;
-    Ok(compare_new_values(&left_val, &right_val, op))
+    Ok(compare_checked(&left_val, &right_val, op, ctx))
+}
+
+/// `compare_values_with_tolerance` under the context's options, warning when the operand
+/// types are incompatible
+fn compare_checked(left: &Value, right: &Value, op: Comparator, ctx: &EvalContext) -> bool {
+    if !comparable(left, right, op) {
+        ctx.warn(WarningKind::LenientCoercion, || {
+            format!(
+                "{} {} {} compares incompatible types; treated as false",
+                left.type_name(),
+                trace::comparator_to_str(op),
+                right.type_name()
+            )
+        });
+    }
+    compare_values_with_tolerance(left, right, op, ctx.options.numeric_tolerance)
+}
+
+/// A comparison with a wildcard operand, expanded over the matched facts
+pub(crate) struct WildcardComparison {
+    /// Whether the wildcard is the left operand
+    pub(crate) wildcard_left: bool,
+    /// `(path, value, result)` for each match, in resolver order
+    pub(crate) matches: Vec<(String, Value, bool)>,
+    /// The other operand: its value, or the bounds of a range
+    pub(crate) other: Vec<Value>,
+    /// Whether any match held
+    pub(crate) result: bool,
+}
+
+/// Evaluate a comparison with a wildcard operand (`object.*.field`) against each match
+///
+/// The left operand is expanded if it is a wildcard, otherwise the right one, except on
+/// the right of `IN` where the wildcard is the list of matches. Returns `None` if neither
+/// side is expanded.
+pub(crate) fn evaluate_wildcard(
+    left: &AstNode,
+    op: Comparator,
+    right: &AstNode,
+    ctx: &EvalContext,
+) -> Result<Option<WildcardComparison>, EvalError> {
+    let (wildcard_left, object, field, other) = match (left, right) {
+        (AstNode::Wildcard { object, field }, _) => (true, object, field, right),
+        (_, AstNode::Wildcard { object, field }) if op != Comparator::In => {
+            (false, object, field, left)
+        }
+        _ => return Ok(None),
+    };
+    let other = match other {
+        AstNode::Range { start, end } if op == Comparator::In => vec![
+            eval_node_to_value_with_context(start, ctx)?,
+            eval_node_to_value_with_context(end, ctx)?,
+        ],
+        other => vec![eval_node_to_value_with_context(other, ctx)?],
+    };
+
+    let mut matches = Vec::new();
+    for (path, value) in wildcard_matches(object, field, ctx)? {
+        ctx.options.check_interrupt()?;
+        let result = match other.as_slice() {
+            [low, high] => range_contains(&value, low, high, ctx),
+            [other] if wildcard_left => compare_checked(&value, other, op, ctx),
+            [other] => compare_checked(other, &value, op, ctx),
+            _ => false,
+        };
+        matches.push((path, value, result));
+    }
+
+    Ok(Some(WildcardComparison {
+        wildcard_left,
+        result: matches.iter().any(|(.., result)| *result),
+        matches,
+        other,
+    }))
+}
+
+/// Facts matched by `object.*.field`, warning if there are none
+fn wildcard_matches(
+    object: &str,
+    field: &str,
+    ctx: &EvalContext,
+) -> Result<Vec<(String, Value)>, EvalError> {
+    let matches = ctx.resolver.resolve_wildcard(object, field);
+    if matches.is_empty() {
+        ctx.warn(WarningKind::MissingFact, || {
+            format!("no facts match `{}.*.{}`", object, field)
+        });
+    }
+    for (path, value) in &matches {
+        ctx.options.check_finite(value, || path.clone())?;
+    }
+    Ok(matches)
+}
+
+/// A bare wildcard in boolean position: whether any match is `true`
+fn any_wildcard_true(object: &str, field: &str, ctx: &EvalContext) -> Result<bool, EvalError> {
+    let mut result = false;
+    for (_, value) in wildcard_matches(object, field, ctx)? {
+        match value {
+            Value::Bool(b) => result |= b,
+            other => {
+                return Err(EvalError::TypeMismatch {
+                    expected: "boolean".to_string(),
+                    got: format!("{:?}", other),
+                    context: "boolean expression context".to_string(),
+                })
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Evaluate `left IN start..end`, returning the resolved operands and the result
+///
+/// Bounds are inclusive; anything but three numbers is false.
+pub(crate) fn evaluate_range(
+    left: &AstNode,
+    start: &AstNode,
+    end: &AstNode,
+    ctx: &EvalContext,
+) -> Result<(Value, Value, Value, bool), EvalError> {
+    let value = eval_node_to_value_with_context(left, ctx)?;
+    let low = eval_node_to_value_with_context(start, ctx)?;
+    let high = eval_node_to_value_with_context(end, ctx)?;
+    let result = range_contains(&value, &low, &high, ctx);
+    Ok((value, low, high, result))
+}
+
+/// Whether `low <= value <= high`, warning unless all three are numbers or null
+fn range_contains(value: &Value, low: &Value, high: &Value, ctx: &EvalContext) -> bool {
+    match (value, low, high) {
+        (Value::Number(v), Value::Number(lo), Value::Number(hi)) => lo <= v && v <= hi,
+        _ => {
+            let operands = [value, low, high];
+            if !operands
+                .iter()
+                .all(|v| matches!(v, Value::Number(_) | Value::Null))
+            {
+                ctx.warn(WarningKind::LenientCoercion, || {
+                    format!(
+                        "{} IN {}..{} needs numbers; treated as false",
+                        value.type_name(),
+                        low.type_name(),
+                        high.type_name()
+                    )
+                });
+            }
+            false
+        }
+    }
+}
+
+/// Whether `op` is defined for these operand types (null operands always are)
+fn comparable(left: &Value, right: &Value, op: Comparator) -> bool {
+    match (left, right, op) {
+        (Value::Null, _, _) | (_, Value::Null, _) => true,
+        (_, _, Comparator::Eq | Comparator::Ne | Comparator::EqIgnoreCase) => {
+            core::mem::discriminant(left) == core::mem::discriminant(right)
+        }
+        (
+            Value::Number(_),
+            Value::Number(_),
+            Comparator::Gt | Comparator::Ge | Comparator::Lt | Comparator::Le,
+        ) => true,
+        (_, _, Comparator::Gt | Comparator::Ge | Comparator::Lt | Comparator::Le) => false,
+        (Value::String(_), Value::String(_), _) => true,
+        (Value::List(_), _, Comparator::Contains) | (_, Value::List(_), Comparator::In) => true,
+        (Value::Map(_), Value::String(_), Comparator::Contains) => true,
+        _ => false,
+    }
 }
 
 pub(crate) fn eval_node_to_value_with_context(
     node: &AstNode,
     ctx: &EvalContext,
 ) -> Result<Value, EvalError> {
+    ctx.options.check_interrupt()?;
     match node {
         AstNode::Bool(b) => Ok(Value::Bool(*b)),
         AstNode::String(s) => Ok(Value::String(s.clone())),
@@ -928,13 +2059,32 @@ pub(crate) fn eval_node_to_value_with_context(
                 Ok(value.clone())
             } else {
                 // Otherwise treat it as a string literal
+                ctx.warn(WarningKind::LenientCoercion, || {
+                    format!("`{}` is not a binding; treated as the string \"{}\"", s, s)
+                });
                 Ok(Value::String(s.clone()))
             }
         }
-        AstNode::Attribute { object, field } => Ok(ctx
-            .resolver
-            .resolve_attr(object, field)
-            .unwrap_or(Value::Null)),
+        AstNode::Attribute { object, field } => {
+            if let Some(constant) = ctx.enum_constant(object, field) {
+                return constant;
+            }
+            let value = ctx.resolver.resolve_attr(object, field).unwrap_or_else(|| {
+                ctx.warn(WarningKind::MissingFact, || {
+                    format!("fact `{}.{}` is missing; treated as null", object, field)
+                });
+                Value::Null
+            });
+            ctx.options
+                .check_finite(&value, || format!("{}.{}", object, field))?;
+            Ok(value)
+        }
+        AstNode::Wildcard { object, field } => Ok(Value::List(
+            wildcard_matches(object, field, ctx)?
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect(),
+        )),
         AstNode::ListLiteral(elements) => {
             let values: Result<Vec<Value>, EvalError> = elements
                 .iter()
@@ -951,15 +2101,22 @@ pub(crate) fn eval_node_to_value_with_context(
             Ok(Value::Map(map))
         }
         // Handle boolean expressions (Comparison, And, Or)
-        AstNode::Comparison { .. } | AstNode::And(_) | AstNode::Or(_) => {
+        AstNode::Comparison { .. } | AstNode::And(_) | AstNode::Or(_) | AstNode::Xor(_) => {
             // Evaluate as boolean and wrap in Value::Bool
             let bool_result = evaluate_ast_with_context(node, ctx)?;
             Ok(Value::Bool(bool_result))
         }
+        AstNode::FunctionCall {
+            namespace: None,
+            name,
+            args,
+            ..
+        } if name.as_ref() == rules::RULE_REFERENCE => rule_reference(args, ctx),
         AstNode::FunctionCall {
             namespace,
             name,
             args,
+            span,
         } => {
             // Evaluate arguments
             let arg_values: Result<Vec<Value>, EvalError> = args
@@ -970,8 +2127,40 @@ pub(crate) fn eval_node_to_value_with_context(
 
             // Call built-in function if registry is available
             if let Some(builtins) = ctx.builtins {
-                let ns = namespace.as_ref().map(|s| s.as_ref()).unwrap_or("core");
-                builtins.call(ns, name, &arg_values)
+                let ns = match namespace {
+                    Some(ns) => ns.to_lowercase(),
+                    None => builtins.resolve_unqualified(name).to_string(),
+                };
+                let ns = ns.as_str();
+                ctx.count_call(ns)?;
+                if let Some(note) = ctx.diagnostics.and_then(|_| builtins.deprecation(ns, name)) {
+                    ctx.warn(WarningKind::DeprecatedBuiltin, || {
+                        format!("`{}.{}` is deprecated: {}", ns, name, note)
+                    });
+                }
+                ctx.options.check_interrupt()?;
+                let result = trace::Timer::builtin(ctx.timer, || {
+                    builtins.call_with_options(ns, name, &arg_values, &ctx.options)
+                })
+                .map_err(|err| {
+                    if !builtins.has_function(ns, name) {
+                        return err;
+                    }
+                    EvalError::BuiltinFailed {
+                        function: format!("{}.{}", ns, name.to_lowercase()),
+                        args: args
+                            .iter()
+                            .zip(&arg_values)
+                            .map(|(arg, value)| ctx.summarize_arg(arg, value))
+                            .collect(),
+                        span: *span,
+                        source: Box::new(err),
+                    }
+                })?;
+                ctx.options.check_interrupt()?;
+                ctx.options
+                    .check_finite(&result, || format!("{}.{}()", ns, name))?;
+                Ok(result)
             } else {
                 Err(EvalError::InvalidOperation(format!(
                     "Function calls not supported without built-ins registry: {}.{}",
@@ -980,60 +2169,142 @@ pub(crate) fn eval_node_to_value_with_context(
                 )))
             }
         }
+        AstNode::Range { .. } => Err(EvalError::InvalidOperation(
+            "a range `lo..hi` is only valid on the right of IN".to_string(),
+        )),
+    }
+}
+
+/// Result of the rule referenced by `rule("id")`, memoized by the rule set being evaluated
+fn rule_reference(args: &[AstNode], ctx: &EvalContext) -> Result<Value, EvalError> {
+    let Some(id) = rules::referenced_rule(args) else {
+        return Err(EvalError::InvalidOperation(
+            "rule() takes a single rule id string".to_string(),
+        ));
+    };
+    match ctx.rule_results.map(|results| results.get(id)) {
+        Some(Some(&result)) => Ok(Value::Bool(result)),
+        Some(None) => Err(EvalError::InvalidOperation(format!(
+            "rule `{}` is unknown or failed to evaluate",
+            id
+        ))),
+        None => Err(EvalError::InvalidOperation(format!(
+            "rule(\"{}\") can only be evaluated as part of a RuleSet",
+            id
+        ))),
     }
 }
 
 pub(crate) fn compare_new_values(left: &Value, right: &Value, op: Comparator) -> bool {
+    compare_values_with_tolerance(left, right, op, None)
+}
+
+/// `compare_new_values` with an optional absolute tolerance for numeric equality
+/// (`EvalOptions::numeric_tolerance`)
+pub(crate) fn compare_values_with_tolerance(
+    left: &Value,
+    right: &Value,
+    op: Comparator,
+    tolerance: Option<f64>,
+) -> bool {
     match op {
         Comparator::Eq => match (left, right) {
             (Value::Null, Value::Null) => true,
             (Value::Null, _) | (_, Value::Null) => false,
             (Value::Bool(l), Value::Bool(r)) => l == r,
             (Value::String(l), Value::String(r)) => l == r,
-            (Value::Number(l), Value::Number(r)) => {
-                if l.is_nan() || r.is_nan() {
-                    return false;
-                }
-                l == r
-            }
+            (Value::Number(l), Value::Number(r)) => numbers_equal(*l, *r, tolerance),
+            _ => false,
+        },
+        Comparator::Ne => !compare_values_with_tolerance(left, right, Comparator::Eq, tolerance),
+        Comparator::EqIgnoreCase => match (left, right) {
+            (Value::String(l), Value::String(r)) => l
+                .chars()
+                .flat_map(char::to_lowercase)
+                .eq(r.chars().flat_map(char::to_lowercase)),
+            _ => compare_values_with_tolerance(left, right, Comparator::Eq, tolerance),
+        },
+        Comparator::Like => match (left, right) {
+            (Value::String(text), Value::String(pattern)) => glob_match(pattern, text),
             _ => false,
         },
-        Comparator::Ne => !compare_new_values(left, right, Comparator::Eq),
         Comparator::Contains => match (left, right) {
             (Value::String(l), Value::String(r)) => l.contains(&**r),
             (Value::List(list), val) => list
                 .iter()
-                .any(|item| compare_new_values(item, val, Comparator::Eq)),
+                .any(|item| compare_values_with_tolerance(item, val, Comparator::Eq, tolerance)),
             (Value::Map(map), Value::String(key)) => map.contains_key(key),
             _ => false,
         },
         Comparator::In => match (left, right) {
             (val, Value::List(list)) => list
                 .iter()
-                .any(|item| compare_new_values(val, item, Comparator::Eq)),
+                .any(|item| compare_values_with_tolerance(val, item, Comparator::Eq, tolerance)),
             (Value::String(s), Value::String(haystack)) => haystack.contains(&**s),
             _ => false,
         },
-        Comparator::Gt | Comparator::Ge | Comparator::Lt | Comparator::Le => match (left, right) {
-            (Value::Number(l), Value::Number(r)) => {
-                if l.is_nan() || r.is_nan() {
-                    return false;
-                }
-                match op {
-                    Comparator::Gt => l > r,
-                    Comparator::Ge => l >= r,
-                    Comparator::Lt => l < r,
-                    Comparator::Le => l <= r,
+        Comparator::Gt | Comparator::Ge | Comparator::Lt | Comparator::Le => {
+            match left.partial_cmp_semantics(right) {
+                Some(ordering) => match op {
+                    Comparator::Gt => ordering.is_gt(),
+                    Comparator::Ge => ordering.is_ge(),
+                    Comparator::Lt => ordering.is_lt(),
+                    Comparator::Le => ordering.is_le(),
                     _ => false,
-                }
+                },
+                None => false,
             }
-            _ => false,
-        },
+        }
+    }
+}
+
+/// Numeric equality: NaN is never equal, and with a tolerance `eps` two numbers are
+/// equal if `l == r` or `|l - r| <= eps` (a negative or NaN tolerance is exact)
+pub(crate) fn numbers_equal(l: f64, r: f64, tolerance: Option<f64>) -> bool {
+    if l.is_nan() || r.is_nan() {
+        return false;
+    }
+    l == r || tolerance.is_some_and(|eps| (l - r).abs() <= eps)
+}
+
+/// Match `text` against a glob: `*` matches any run of characters (including
+/// separators such as `\` and `/`), `?` exactly one character
+///
+/// Backtracks only to the most recent `*`, so matching is O(pattern × text) at worst.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Pattern position after the last `*` and the text position it resumes from
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` absorb one more character
+                Some((after_star, resume)) => {
+                    p = after_star;
+                    t = resume + 1;
+                    star = Some((after_star, resume + 1));
+                }
+                None => return false,
+            },
+        }
     }
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
 fn parse_number(val: &str) -> Option<u64> {
-    let val = val.trim();
+    let val = val.trim().replace('_', "");
+    let val = val.as_str();
     if let Some(stripped) = val.strip_prefix("0x").or_else(|| val.strip_prefix("0X")) {
         u64::from_str_radix(stripped, 16).ok()
     } else {
@@ -1066,21 +2337,15 @@ pub type Expression = AstNode;
 /// assert!(validate_expression(bad_expr).is_err());
 /// ```
 pub fn validate_expression(expr: &str) -> Result<(), HelError> {
-    match HelParser::parse(Rule::condition, expr) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            let (line, column) = match &e.line_col {
-                pest::error::LineColLocation::Pos((l, c)) => (*l, *c),
-                pest::error::LineColLocation::Span((l, c), _) => (*l, *c),
-            };
+    validate_expression_with_limits(expr, &ParseLimits::default())
+}
 
-            Err(HelError::parse_error_at(
-                format!("{}", e.variant),
-                line,
-                column,
-            ))
-        }
-    }
+/// Validates HEL expression syntax using explicit parse limits
+///
+/// Like `validate_expression()`, but with caller-provided `ParseLimits` for hosts
+/// accepting rules from untrusted sources.
+pub fn validate_expression_with_limits(expr: &str, limits: &ParseLimits) -> Result<(), HelError> {
+    parse_expression_with_limits(expr, limits).map(|_| ())
 }
 
 /// Parse a HEL expression into an AST (for advanced use cases)
@@ -1096,8 +2361,30 @@ pub fn validate_expression(expr: &str) -> Result<(), HelError> {
 /// let ast = parse_expression(expr).expect("parse failed");
 /// ```
 pub fn parse_expression(expr: &str) -> Result<Expression, HelError> {
-    validate_expression(expr)?;
-    Ok(parse_rule(expr))
+    parse_expression_with_limits(expr, &ParseLimits::default())
+}
+
+/// Parse a HEL expression into an AST using explicit parse limits
+///
+/// Inputs longer than `limits.max_input_len` or nested deeper than
+/// `limits.max_nesting_depth` are rejected with `ErrorKind::LimitExceeded`
+/// before they can exhaust the stack.
+pub fn parse_expression_with_limits(
+    expr: &str,
+    limits: &ParseLimits,
+) -> Result<Expression, HelError> {
+    check_parse_limits(expr, limits)?;
+
+    let mut pairs = HelParser::parse(Rule::expression, expr).map_err(|e| {
+        let (line, column) = match &e.line_col {
+            pest::error::LineColLocation::Pos((l, c)) => (*l, *c),
+            pest::error::LineColLocation::Span((l, c), _) => (*l, *c),
+        };
+
+        HelError::parse_error_at(format!("{}", e.variant), line, column)
+    })?;
+
+    build_ast(pairs.next().expect("Empty parse"), 0, limits)
 }
 
 /// Evaluation context with facts/data for expression evaluation
@@ -1116,6 +2403,10 @@ pub fn parse_expression(expr: &str) -> Result<Expression, HelError> {
 /// ```
 pub struct FactsEvalContext {
     facts: BTreeMap<String, Value>,
+    /// Schema facts are validated against (see `with_schema`)
+    schema: Option<Arc<Schema>>,
+    /// Facts rejected by the schema, with the reason
+    rejected: Vec<(String, HelError)>,
 }
 
 impl FactsEvalContext {
@@ -1123,29 +2414,152 @@ impl FactsEvalContext {
     pub fn new() -> Self {
         Self {
             facts: BTreeMap::new(),
+            schema: None,
+            rejected: Vec::new(),
         }
     }
 
+    /// Validate facts against a schema as they are added
+    ///
+    /// A fact `object.field` is accepted only if a type named like the object
+    /// (case-insensitively) declares the field and the value matches its type
+    /// (see `Schema::validate_fact`). Facts already in the context are validated
+    /// too; rejected facts are dropped and listed in `rejected_facts()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{parse_schema, FactsEvalContext, Value};
+    ///
+    /// let schema = parse_schema("type Binary {\n  entropy: Number\n}\n").unwrap();
+    /// let mut ctx = FactsEvalContext::new().with_schema(schema);
+    ///
+    /// assert!(ctx.try_add_fact("binary.entropy", Value::Number(7.9)).is_ok());
+    /// assert!(ctx.try_add_fact("binary.entropy", Value::String("high".into())).is_err());
+    /// assert!(ctx.try_add_fact("binary.arch", Value::String("x86".into())).is_err());
+    /// ```
+    pub fn with_schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(Arc::new(schema));
+        for (key, value) in core::mem::take(&mut self.facts) {
+            self.add_fact(&key, value);
+        }
+        self
+    }
+
     /// Add a fact to the context
+    ///
+    /// On a schema-validated context a fact that fails validation is not stored;
+    /// it is recorded in `rejected_facts()`. Use `try_add_fact` to handle the error.
     pub fn add_fact(&mut self, key: &str, value: Value) {
+        if let Err(error) = self.try_add_fact(key, value) {
+            self.rejected.push((key.to_string(), error));
+        }
+    }
+
+    /// Add a fact, returning a type error if it fails schema validation
+    pub fn try_add_fact(&mut self, key: &str, value: Value) -> Result<(), HelError> {
+        if let Some(schema) = &self.schema {
+            schema
+                .validate_fact(key, &value)
+                .map_err(HelError::type_error)?;
+        }
         self.facts.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Facts rejected by the schema through `add_fact` or `with_schema`, in order
+    pub fn rejected_facts(&self) -> &[(String, HelError)] {
+        &self.rejected
     }
 
     /// Create a context from JSON data
     ///
-    /// **Note**: This method is currently not implemented and will return an empty context.
-    /// A full implementation would require the `serde_json` dependency.
+    /// The JSON must be an object. Keys containing a dot (e.g., "binary.arch")
+    /// are used as fact names directly; nested objects are flattened one level
+    /// into `object.field` facts. Lists and deeper maps are preserved as values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{evaluate, FactsEvalContext};
     ///
-    /// The JSON should be an object where keys are fact names (e.g., "binary.arch")
-    /// and values are the fact values.
+    /// let ctx = FactsEvalContext::from_json(
+    ///     r#"{"binary.arch": "x86_64", "manifest": {"permissions": ["SMS", "CAMERA"]}}"#,
+    /// )
+    /// .unwrap();
+    /// assert!(evaluate(r#"manifest.permissions CONTAINS "SMS""#, &ctx).unwrap());
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, HelError> {
+        let root: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| HelError::parse_error(format!("Invalid JSON facts: {}", e)))?;
+        Ok(Self {
+            facts: facts::flatten_json(root)?,
+            ..Self::new()
+        })
+    }
+
+    /// Create a context from YAML data
     ///
-    /// # TODO
+    /// Uses the same flattening rules as `from_json`. Requires the `facts-yaml` feature.
+    #[cfg(feature = "facts-yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, HelError> {
+        let root: serde_json::Value = serde_yaml::from_str(yaml)
+            .map_err(|e| HelError::parse_error(format!("Invalid YAML facts: {}", e)))?;
+        Ok(Self {
+            facts: facts::flatten_json(root)?,
+            ..Self::new()
+        })
+    }
+
+    /// Create a context from TOML data
     ///
-    /// Implement proper JSON parsing once serde_json is added as a dependency.
-    pub fn from_json(_json: &str) -> Result<Self, HelError> {
-        // Placeholder implementation
-        // TODO: Implement proper JSON parsing with serde_json
-        Ok(Self::new())
+    /// Uses the same flattening rules as `from_json`, so `[binary]` tables yield
+    /// `binary.field` facts. Requires the `facts-toml` feature.
+    #[cfg(feature = "facts-toml")]
+    pub fn from_toml(content: &str) -> Result<Self, HelError> {
+        let root: serde_json::Value = toml::from_str(content)
+            .map_err(|e| HelError::parse_error(format!("Invalid TOML facts: {}", e)))?;
+        Ok(Self {
+            facts: facts::flatten_json(root)?,
+            ..Self::new()
+        })
+    }
+
+    /// Create a context by flattening any serde-serializable host struct
+    ///
+    /// Uses the same flattening rules as `from_json`, so a struct with a
+    /// `binary: BinaryInfo` field yields facts like `binary.format`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{evaluate, FactsEvalContext};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Binary {
+    ///     format: String,
+    ///     sections: Vec<String>,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// struct Facts {
+    ///     binary: Binary,
+    /// }
+    ///
+    /// let facts = Facts {
+    ///     binary: Binary { format: "elf".into(), sections: vec![".text".into()] },
+    /// };
+    /// let ctx = FactsEvalContext::from_serializable(&facts).unwrap();
+    /// assert!(evaluate(r#"binary.format == "elf" AND binary.sections CONTAINS ".text""#, &ctx).unwrap());
+    /// ```
+    pub fn from_serializable<T: serde::Serialize + ?Sized>(value: &T) -> Result<Self, HelError> {
+        let root = serde_json::to_value(value)
+            .map_err(|e| HelError::type_error(format!("Facts are not serializable: {}", e)))?;
+        Ok(Self {
+            facts: facts::flatten_json(root)?,
+            ..Self::new()
+        })
     }
 }
 
@@ -1160,6 +2574,10 @@ impl HelResolver for FactsEvalContext {
         let key = format!("{}.{}", object, field);
         self.facts.get(&key).cloned()
     }
+
+    fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+        facts::wildcard_matches(facts::object_facts(&self.facts, object), field)
+    }
 }
 
 /// Evaluate expression against context
@@ -1189,18 +2607,59 @@ pub fn evaluate(expr: &str, context: &FactsEvalContext) -> Result<bool, HelError
 // Script Support (Let Bindings and Multi-Expression Scripts)
 // ============================================================================
 
-/// Represents a parsed HEL script with let bindings
+/// Represents a parsed HEL script with constants and let bindings
 #[derive(Debug, Clone)]
 pub struct Script {
+    /// Constants declared with `const` (name -> value), in declaration order
+    pub constants: Vec<(Arc<str>, Value)>,
     /// Let bindings in the script (name -> expression)
     pub bindings: Vec<(Arc<str>, AstNode)>,
     /// Final expression that must evaluate to a boolean
     pub final_expr: AstNode,
 }
 
+impl Script {
+    /// Override a declared constant (e.g., a threshold tuned by the host at load time)
+    ///
+    /// Fails if the script declares no constant `name`. Constants declared from `name`
+    /// keep the value computed at parse time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{evaluate_parsed_script, parse_script, FactsEvalContext, Value};
+    ///
+    /// let mut script = parse_script("const MAX_ENTROPY = 7.5\nbinary.entropy > MAX_ENTROPY").unwrap();
+    /// let mut ctx = FactsEvalContext::new();
+    /// ctx.add_fact("binary.entropy", Value::Number(7.0));
+    /// assert!(!evaluate_parsed_script(&script, &ctx).unwrap());
+    ///
+    /// script.set_constant("MAX_ENTROPY", Value::Number(6.5)).unwrap();
+    /// assert!(evaluate_parsed_script(&script, &ctx).unwrap());
+    /// ```
+    pub fn set_constant(&mut self, name: &str, value: Value) -> Result<(), HelError> {
+        match self.constants.iter_mut().find(|(n, _)| n.as_ref() == name) {
+            Some((_, slot)) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(HelError::eval_error(format!(
+                "Script declares no constant `{}`",
+                name
+            ))),
+        }
+    }
+}
+
 /// Parse and validate a .hel script file (may contain multiple expressions, let bindings)
 ///
-/// Scripts support let bindings for reusable sub-expressions and a final boolean expression.
+/// Scripts support `const` declarations for tunable values, let bindings for reusable
+/// sub-expressions and a final boolean expression. Constants come first and are evaluated
+/// at parse time, so they may not reference facts; later constants may use earlier ones.
+///
+/// Bindings are scoped lexically: each is visible to the statements after it, and a later
+/// `let` of the same name shadows it. Referencing a binding before its declaration is an
+/// error rather than a bare string.
 ///
 /// **Implementation Note**: The current parser uses heuristics to determine expression boundaries,
 /// which works for most cases but may have edge cases with complex multi-line expressions.
@@ -1219,164 +2678,1003 @@ pub struct Script {
 /// let parsed = parse_script(script).expect("parse failed");
 /// ```
 pub fn parse_script(script: &str) -> Result<Script, HelError> {
-    let lines: Vec<&str> = script.lines().collect();
-    let mut bindings = Vec::new();
+    parse_script_with_recovery(script).map_err(|mut errors| errors.remove(0))
+}
+
+/// Parse a script, reporting every syntax error instead of only the first
+///
+/// Parsing recovers at statement boundaries: each `let` binding and the final
+/// expression is parsed independently, so one broken statement does not hide
+/// errors in the others. Error lines and columns refer to the script text.
+///
+/// # Examples
+///
+/// ```
+/// use hel::parse_script_with_recovery;
+///
+/// let script = "let a = x.y ==\nlet b = x.z > 1\nlet c = x.w >\nlet d = true\nd AND ==";
+/// let errors = parse_script_with_recovery(script).unwrap_err();
+/// let lines: Vec<_> = errors.iter().map(|e| e.line).collect();
+/// assert_eq!(lines, vec![Some(1), Some(3), Some(5)]);
+/// ```
+pub fn parse_script_with_recovery(script: &str) -> Result<Script, Vec<HelError>> {
+    parse_script_statements(script, &ParseLimits::default())
+}
+
+/// Parse a script using explicit parse limits, applied to each statement
+pub fn parse_script_with_limits(script: &str, limits: &ParseLimits) -> Result<Script, HelError> {
+    parse_script_statements(script, limits).map_err(|mut errors| errors.remove(0))
+}
+
+fn parse_script_statements(script: &str, limits: &ParseLimits) -> Result<Script, Vec<HelError>> {
+    let mut constants: Vec<(Arc<str>, Value)> = Vec::new();
+    let mut bindings: Vec<(Arc<str>, AstNode)> = Vec::new();
     let mut final_expr = None;
+    let mut has_final = false;
+    let mut errors = Vec::new();
+
+    let statements = split_script(script);
+    for (index, statement) in statements.iter().enumerate() {
+        has_final |= statement.name.is_none();
+        let expr = match parse_expression_with_limits(&statement.source, limits) {
+            Ok(expr) => expr,
+            Err(e) => {
+                errors.push(statement.locate(e));
+                continue;
+            }
+        };
+        if let Err(e) = check_scope(&expr, &statements[..index], &statements[index..]) {
+            errors.push(statement.locate(e));
+            continue;
+        }
+        if let Some(Err(e)) = statement.name.as_deref().map(check_binding_name) {
+            errors.push(statement.locate(e));
+            continue;
+        }
+        match statement.name.clone() {
+            Some(name) if statement.constant => {
+                let value = if bindings.is_empty() {
+                    constant_value(&name, &expr, &constants)
+                } else {
+                    Err(HelError::parse_error(format!(
+                        "`const {}` must be declared before any `let` binding",
+                        name
+                    )))
+                };
+                match value {
+                    Ok(value) => constants.push((name, value)),
+                    Err(e) => errors.push(statement.locate(e)),
+                }
+            }
+            Some(name) if constants.iter().any(|(n, _)| *n == name) => {
+                errors.push(statement.locate(HelError::parse_error(format!(
+                    "`{}` is a constant and cannot be rebound with `let`",
+                    name
+                ))));
+            }
+            Some(name) => bindings.push((name, expr)),
+            None => final_expr = Some(expr),
+        }
+    }
+
+    if !has_final {
+        errors.push(HelError::parse_error(
+            "Script must have a final boolean expression".to_string(),
+        ));
+    }
+
+    match final_expr {
+        Some(final_expr) if errors.is_empty() => Ok(Script {
+            constants,
+            bindings,
+            final_expr,
+        }),
+        _ => Err(errors),
+    }
+}
+
+/// Check that `expr` only references bindings declared in `earlier` statements
+///
+/// Script scope is sequential: a `const` or `let` is visible to the statements after it,
+/// and a later `let` of the same name shadows it from then on. An identifier that names
+/// no binding is a bare string, but one naming a binding declared later (including the
+/// statement's own) is a mistake, not a string.
+fn check_scope(
+    expr: &AstNode,
+    earlier: &[ScriptStatement],
+    later: &[ScriptStatement],
+) -> Result<(), HelError> {
+    let mut references = IdentifierReferences(Vec::new());
+    walk_ast(expr, &mut references);
+    let declared = |statements: &[ScriptStatement], name: &str| {
+        statements
+            .iter()
+            .find(|s| s.name.as_deref() == Some(name))
+            .map(|s| s.line)
+    };
+    for name in references.0 {
+        if declared(earlier, &name).is_some() {
+            continue;
+        }
+        if let Some(line) = declared(later, &name) {
+            return Err(HelError::parse_error(format!(
+                "`{}` is used before its binding on line {}",
+                name,
+                line + 1
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a binding name is a plain identifier a later statement can reference
+pub(crate) fn check_binding_name(name: &str) -> Result<(), HelError> {
+    const RESERVED: &[&str] = &[
+        "AND", "OR", "XOR", "CONTAINS", "IN", "EQI", "LIKE", "BETWEEN", "TRUE", "FALSE", "LET",
+        "CONST",
+    ];
+    let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !plain {
+        return Err(HelError::parse_error(format!(
+            "`{}` is not a valid binding name: use letters, digits and `_` (a dotted name would read as a fact)",
+            name
+        )));
+    }
+    if RESERVED.iter().any(|r| r.eq_ignore_ascii_case(name)) {
+        return Err(HelError::parse_error(format!(
+            "`{}` is a reserved word and cannot be a binding name",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Evaluate the value of `const name = expr`, given the constants declared before it
+fn constant_value(
+    name: &str,
+    expr: &AstNode,
+    constants: &[(Arc<str>, Value)],
+) -> Result<Value, HelError> {
+    if constants.iter().any(|(n, _)| n.as_ref() == name) {
+        return Err(HelError::parse_error(format!(
+            "Constant `{}` is already declared",
+            name
+        )));
+    }
+    let mut facts = FactReferences(None);
+    walk_ast(expr, &mut facts);
+    if let Some(path) = facts.0 {
+        return Err(HelError::parse_error(format!(
+            "Constant `{}` may not reference facts (`{}`)",
+            name, path
+        )));
+    }
+
+    let no_facts = FactsEvalContext::new();
+    let ctx = constants
+        .iter()
+        .fold(EvalContext::new(&no_facts), |ctx, (name, value)| {
+            ctx.with_variable(name.clone(), value.clone())
+        });
+    eval_node_to_value_with_context(single_operand(expr), &ctx).map_err(HelError::from)
+}
+
+/// Finds the first fact path referenced by an expression
+struct FactReferences(Option<String>);
+
+impl Visitor for FactReferences {
+    fn visit_attribute(&mut self, object: &str, field: &str) {
+        self.0
+            .get_or_insert_with(|| format!("{}.{}", object, field));
+    }
+
+    fn visit_wildcard(&mut self, object: &str, field: &str) {
+        self.0
+            .get_or_insert_with(|| format!("{}.*.{}", object, field));
+    }
+}
+
+/// The operand of single-element `OR` / `AND` chains, so bare values stay values
+pub(crate) fn single_operand(mut node: &AstNode) -> &AstNode {
+    while let AstNode::Or(nodes) | AstNode::And(nodes) | AstNode::Xor(nodes) = node {
+        match nodes.as_slice() {
+            [single] => node = single,
+            _ => break,
+        }
+    }
+    node
+}
+
+/// Validate script syntax, returning all errors in one pass
+///
+/// Like `validate_expression()` for scripts; see `parse_script_with_recovery()`.
+pub fn validate_script(script: &str) -> Result<(), Vec<HelError>> {
+    parse_script_with_recovery(script).map(|_| ())
+}
+
+/// Report unused and shadowed `let` bindings of a script as warnings
+///
+/// A binding is used when a later statement references it by name before it is
+/// rebound; rebinding a name shadows the earlier binding. Statements that fail to
+/// parse are skipped (see `validate_script()` for errors). Warning spans cover the
+/// binding name in `script`.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{lint_script, Diagnostics, WarningKind};
+///
+/// let script = "let packed = binary.entropy > 7\nlet packed = binary.entropy > 7.5\nlet unused = true\npacked";
+/// let diagnostics = Diagnostics::new();
+/// lint_script(script, &diagnostics);
+///
+/// let kinds: Vec<WarningKind> = diagnostics.take().iter().map(|w| w.kind).collect();
+/// assert_eq!(
+///     kinds,
+///     vec![WarningKind::UnusedBinding, WarningKind::ShadowedBinding, WarningKind::UnusedBinding]
+/// );
+/// ```
+pub fn lint_script(script: &str, diagnostics: &Diagnostics) {
+    let mut line_starts = vec![0];
+    line_starts.extend(script.match_indices('\n').map(|(i, _)| i + 1));
+
+    // Live bindings: name -> (name span, referenced since bound)
+    let mut live: BTreeMap<Arc<str>, (core::ops::Range<usize>, bool)> = BTreeMap::new();
+    let mut warnings = Vec::new();
+    for statement in split_script(script) {
+        let mut references = IdentifierReferences(Vec::new());
+        match parse_expression(&statement.source) {
+            Ok(expr) => {
+                walk_ast(&expr, &mut references);
+            }
+            // Count identifiers in a broken statement as uses rather than report false positives
+            Err(_) => references.0.extend(
+                tokenize(&statement.source)
+                    .into_iter()
+                    .filter(|t| t.kind == TokenKind::Identifier)
+                    .map(|t| statement.source[t.span].to_string()),
+            ),
+        }
+        for name in references.0 {
+            if let Some((_, used)) = live.get_mut(name.as_str()) {
+                *used = true;
+            }
+        }
+
+        let Some(name) = statement.name.filter(|_| !statement.constant) else {
+            continue;
+        };
+        let line = script[line_starts[statement.line]..]
+            .lines()
+            .next()
+            .unwrap_or_default();
+        let span = declaration(line.trim_start())
+            .and_then(|(_, rest)| {
+                rest.find(name.as_ref())
+                    .map(|i| i + line.len() - rest.len())
+            })
+            .map_or(0..0, |i| {
+                let start = line_starts[statement.line] + i;
+                start..start + name.len()
+            });
+        if let Some((previous, used)) = live.insert(name.clone(), (span.clone(), false)) {
+            if !used {
+                warnings.push(unused_binding(&name, previous));
+            }
+            warnings.push(Warning {
+                kind: WarningKind::ShadowedBinding,
+                message: format!("`let {}` shadows an earlier binding of the same name", name),
+                span: Some(span),
+            });
+        }
+    }
+    warnings.extend(
+        live.into_iter()
+            .filter(|(_, (_, used))| !used)
+            .map(|(name, (span, _))| unused_binding(&name, span)),
+    );
+
+    warnings.sort_by_key(|w| w.span.as_ref().map(|s| s.start));
+    for warning in warnings {
+        diagnostics.push(warning);
+    }
+}
+
+fn unused_binding(name: &str, span: core::ops::Range<usize>) -> Warning {
+    Warning {
+        kind: WarningKind::UnusedBinding,
+        message: format!("`let {}` is never used", name),
+        span: Some(span),
+    }
+}
+
+/// Collects every bare identifier referenced by an expression
+struct IdentifierReferences(Vec<String>);
+
+impl Visitor for IdentifierReferences {
+    fn visit_identifier(&mut self, name: &str) {
+        self.0.push(name.to_string());
+    }
+}
+
+/// A script statement as written: a constant, a let binding or the final expression
+pub(crate) struct ScriptStatement {
+    /// Zero-based line the statement starts on
+    pub(crate) line: usize,
+    /// Binding name (`None` for the final expression)
+    pub(crate) name: Option<Arc<str>>,
+    /// Whether the binding is a `const` declaration rather than `let`
+    pub(crate) constant: bool,
+    /// Expression source, continuation lines joined with spaces
+    pub(crate) source: String,
+    /// Origin of each joined line: (byte offset in `source`, script line, column), zero-based
+    pub(crate) segments: Vec<(usize, usize, usize)>,
+}
+
+impl ScriptStatement {
+    fn push_segment(&mut self, line: usize, line_text: &str, part: &str) {
+        if !self.source.is_empty() {
+            self.source.push(' ');
+        }
+        // `part` is a trimmed slice of `line_text`
+        let byte_column = part.as_ptr() as usize - line_text.as_ptr() as usize;
+        let column = line_text[..byte_column].chars().count();
+        self.segments.push((self.source.len(), line, column));
+        self.source.push_str(part);
+    }
+
+    /// Translate an error location in `source` to the script text
+    pub(crate) fn locate(&self, mut error: HelError) -> HelError {
+        let Some(&(_, first_line, first_column)) = self.segments.first() else {
+            error.line = Some(self.line + 1);
+            return error;
+        };
+        let (Some(1), Some(column)) = (error.line, error.column) else {
+            error.line = Some(first_line + 1);
+            error.column = Some(first_column + 1);
+            return error;
+        };
+
+        // Parse columns count characters (1-based)
+        let offset = self
+            .source
+            .char_indices()
+            .nth(column - 1)
+            .map_or(self.source.len(), |(i, _)| i);
+        let &(start, line, line_column) = self
+            .segments
+            .iter()
+            .rev()
+            .find(|(start, _, _)| *start <= offset)
+            .unwrap_or(&self.segments[0]);
+        error.line = Some(line + 1);
+        error.column = Some(line_column + self.source[start..offset].chars().count() + 1);
+        error
+    }
+}
+
+/// Split a line at its comment (`#` or `//` outside a string literal)
+///
+/// Returns the code before the comment and the comment itself, if any.
+pub(crate) fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return (&line[..i], Some(&line[i..])),
+            '/' if !in_string && line[i + 1..].starts_with('/') => {
+                return (&line[..i], Some(&line[i..]))
+            }
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// Whether a line continues the previous one with a logical operator (`AND`, `or`, `&&`, ...)
+///
+/// A keyword operator must be followed by whitespace, `(` or the end of the line, so
+/// `order.total` or `xor_flags.count` start a new statement.
+fn starts_with_logical_op(line: &str) -> bool {
+    if line.starts_with("&&") || line.starts_with("||") {
+        return true;
+    }
+    ["AND", "OR", "XOR"].iter().any(|op| {
+        line.get(..op.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(op))
+            && line[op.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || c == '(')
+    })
+}
+
+/// Split a `let` or `const` declaration into its keyword and the rest of the line
+fn declaration(line: &str) -> Option<(&'static str, &str)> {
+    ["let", "const"].into_iter().find_map(|keyword| {
+        line.strip_prefix(keyword)
+            .and_then(|rest| rest.strip_prefix(' '))
+            .map(|rest| (keyword, rest))
+    })
+}
+
+/// Split a script into statements (without parsing the expressions)
+///
+/// Comments are stripped from each line first, so statements never contain them.
+pub(crate) fn split_script(script: &str) -> Vec<ScriptStatement> {
+    let lines: Vec<&str> = script.lines().map(|line| split_comment(line).0).collect();
+    let mut statements = Vec::new();
 
     let mut i = 0;
     while i < lines.len() {
         let line = lines[i].trim();
 
-        // Skip empty lines and comments
-        if line.is_empty() || line.starts_with('#') {
+        // Skip empty and comment-only lines
+        if line.is_empty() {
             i += 1;
             continue;
         }
 
-        // Check for let binding
-        if line.starts_with("let ") {
-            // Parse: let name = expression
-            let rest = line.strip_prefix("let ").unwrap().trim();
+        // Check for let binding or constant
+        if let Some((keyword, rest)) = declaration(line) {
+            // Parse: let name = expression (or const name = expression)
+            let rest = rest.trim();
 
             if let Some(eq_pos) = rest.find('=') {
-                let name = rest[..eq_pos].trim();
+                let mut statement = ScriptStatement {
+                    line: i,
+                    name: Some(Arc::from(rest[..eq_pos].trim())),
+                    constant: keyword == "const",
+                    source: String::new(),
+                    segments: Vec::new(),
+                };
                 let expr_after_eq = rest[eq_pos + 1..].trim();
-                let mut expr_str = String::new();
 
                 // Start expression string if there's content after '='
                 if !expr_after_eq.is_empty() {
-                    expr_str = expr_after_eq.to_string();
+                    statement.push_segment(i, lines[i], expr_after_eq);
                 }
 
                 // Handle multi-line let expressions
-                // Continue collecting lines until we hit another "let" or a potential final expression
+                // Continue collecting lines until we hit another declaration or a potential final expression
                 i += 1;
                 while i < lines.len() {
                     let next_line = lines[i].trim();
 
-                    // Skip empty lines and comments
-                    if next_line.is_empty() || next_line.starts_with('#') {
+                    // Skip empty and comment-only lines
+                    if next_line.is_empty() {
                         i += 1;
                         continue;
                     }
 
-                    // If we hit another "let", stop collecting
-                    if next_line.starts_with("let ") {
+                    // If we hit another "let" or "const", stop collecting
+                    if declaration(next_line).is_some() {
                         break;
                     }
 
                     // If this line looks like it could be a standalone final expression
                     // (doesn't start with an operator), check if we have collected enough
-                    if !expr_str.is_empty()
-                        && !next_line.starts_with("AND")
-                        && !next_line.starts_with("OR")
-                        && !next_line.starts_with("and")
-                        && !next_line.starts_with("or")
-                        && !next_line.starts_with("&&")
-                        && !next_line.starts_with("||")
-                    {
+                    if !statement.source.is_empty() && !starts_with_logical_op(next_line) {
                         // Try to parse what we have so far
-                        if parse_expression(&expr_str).is_ok() {
+                        if parse_expression(&statement.source).is_ok() {
                             // We have a complete expression, stop here
                             break;
                         }
                     }
 
                     // Add this line to the expression
-                    if !expr_str.is_empty() {
-                        expr_str.push(' ');
-                    }
-                    expr_str.push_str(next_line);
+                    statement.push_segment(i, lines[i], next_line);
                     i += 1;
                 }
 
-                let expr = parse_expression(&expr_str)?;
-                bindings.push((Arc::from(name), expr));
+                statements.push(statement);
                 continue;
             }
         }
 
         // This is the final expression
-        if final_expr.is_none() {
-            let mut expr_str = line.to_string();
-
-            // Collect remaining lines as part of final expression
+        let mut statement = ScriptStatement {
+            line: i,
+            name: None,
+            constant: false,
+            source: String::new(),
+            segments: Vec::new(),
+        };
+        statement.push_segment(i, lines[i], line);
+
+        // Collect remaining lines as part of final expression
+        i += 1;
+        while i < lines.len() {
+            let next_line = lines[i].trim();
+            if !next_line.is_empty() {
+                statement.push_segment(i, lines[i], next_line);
+            }
             i += 1;
-            while i < lines.len() {
-                let next_line = lines[i].trim();
-                if !next_line.is_empty() && !next_line.starts_with('#') {
-                    if !expr_str.is_empty() {
-                        expr_str.push(' ');
+        }
+
+        statements.push(statement);
+        break;
+    }
+
+    statements
+}
+
+/// Evaluate a script and return the final boolean result
+///
+/// Binds the script's constants, evaluates all let bindings in order, then evaluates
+/// the final expression. To override constants, parse the script and use
+/// `Script::set_constant()` with `evaluate_parsed_script()`. Hosts with their own
+/// `HelResolver` use `evaluate_script_with_resolver()`.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_script, FactsEvalContext, Value};
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("manifest.permissions", Value::List(vec![
+///     Value::String("READ_SMS".into()),
+///     Value::String("SEND_SMS".into()),
+/// ]));
+/// ctx.add_fact("binary.entropy", Value::Number(8.0));
+///
+/// let script = r#"
+/// let has_sms_perms = manifest.permissions CONTAINS "READ_SMS"
+/// has_sms_perms AND binary.entropy > 7.5
+/// "#;
+///
+/// let result = evaluate_script(script, &ctx).expect("evaluation failed");
+/// assert!(result);
+/// ```
+pub fn evaluate_script(script: &str, context: &FactsEvalContext) -> Result<bool, HelError> {
+    evaluate_script_with_resolver(script, context)
+}
+
+/// Evaluate a script against a custom resolver
+///
+/// Like `evaluate_script()` for hosts that resolve facts with their own `HelResolver`;
+/// use `evaluate_script_with_context()` to make built-in functions available as well.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_script_with_resolver, HelResolver, Value};
+///
+/// struct MyResolver;
+/// impl HelResolver for MyResolver {
+///     fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+///         match (object, field) {
+///             ("binary", "entropy") => Some(Value::Number(7.9)),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let script = "let packed = binary.entropy > 7.5\npacked";
+/// assert!(evaluate_script_with_resolver(script, &MyResolver).unwrap());
+/// ```
+pub fn evaluate_script_with_resolver(
+    script: &str,
+    resolver: &dyn HelResolver,
+) -> Result<bool, HelError> {
+    evaluate_parsed_script(&parse_script(script)?, resolver)
+}
+
+/// Evaluate a script with a custom resolver and built-in functions
+///
+/// Like `evaluate_script()`, but let bindings and the final expression may call the
+/// functions in `builtins`. Constants are evaluated when the script is parsed and
+/// cannot call built-ins.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_script_with_context, BuiltinsRegistry, FactsEvalContext, Value};
+///
+/// let registry = BuiltinsRegistry::builder().with_core().build().expect("register failed");
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("manifest.permissions", Value::List(vec!["READ_SMS".into(), "SEND_SMS".into()]));
+///
+/// let script = r#"
+/// let permission_count = core.len(manifest.permissions)
+/// permission_count > 1
+/// "#;
+/// assert!(evaluate_script_with_context(script, &ctx, &registry).unwrap());
+/// ```
+pub fn evaluate_script_with_context(
+    script: &str,
+    resolver: &dyn HelResolver,
+    builtins: &builtins::BuiltinsRegistry,
+) -> Result<bool, HelError> {
+    let parsed = parse_script(script)?;
+    evaluate_script_in(&parsed, EvalContext::with_builtins(resolver, builtins))
+}
+
+/// Evaluate a script with optional built-ins under explicit evaluation options
+///
+/// The script's expressions are parsed with `options.limits`; bindings and the
+/// final expression are evaluated with every other setting in `options`.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_script_with_options, EvalOptions, FactsEvalContext, Value};
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("file.score", Value::Number(0.1 + 0.2));
+///
+/// let options = EvalOptions::new().with_numeric_tolerance(1e-9);
+/// let script = "let expected = file.score == 0.3\nexpected";
+/// assert!(evaluate_script_with_options(script, &ctx, None, &options).unwrap());
+/// ```
+pub fn evaluate_script_with_options(
+    script: &str,
+    resolver: &dyn HelResolver,
+    builtins: Option<&builtins::BuiltinsRegistry>,
+    options: &EvalOptions,
+) -> Result<bool, HelError> {
+    let parsed = parse_script_with_limits(script, &options.limits)?;
+    evaluate_script_in(&parsed, options_context(resolver, builtins, options))
+}
+
+/// Evaluate an already parsed script against any resolver (see `evaluate_script()`)
+pub fn evaluate_parsed_script(
+    parsed: &Script,
+    resolver: &dyn HelResolver,
+) -> Result<bool, HelError> {
+    evaluate_script_in(parsed, EvalContext::new(resolver))
+}
+
+/// Bind the script's constants and let bindings in `ctx`, then evaluate the final expression
+fn evaluate_script_in(parsed: &Script, ctx: EvalContext) -> Result<bool, HelError> {
+    // Start with base context and the script's constants
+    let mut eval_ctx = parsed.constants.iter().fold(ctx, |ctx, (name, value)| {
+        ctx.with_variable(name.clone(), value.clone())
+    });
+
+    // Evaluate and store let bindings
+    for (name, expr) in &parsed.bindings {
+        let value = eval_node_to_value_with_context(single_operand(expr), &eval_ctx)
+            .map_err(HelError::from)?;
+
+        // Add variable to context
+        eval_ctx = eval_ctx.with_variable(name.clone(), value);
+    }
+
+    // Evaluate final expression
+    evaluate_ast_with_context(&parsed.final_expr, &eval_ctx).map_err(|e| e.into())
+}
+
+// ============================================================================
+// Helper implementations
+// ============================================================================
+
+impl Value {
+    /// Order two values the way the `<`, `<=`, `>` and `>=` comparators do
+    ///
+    /// Only numbers are ordered: any other pair, or a NaN operand, is unordered
+    /// (`None`), and every ordering comparator is then false.
+    pub fn partial_cmp_semantics(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+            _ => None,
+        }
+    }
+
+    /// A total order over every value, for sorting and deterministic output
+    ///
+    /// Built-ins that sort or pick a minimum or maximum should use this order
+    /// rather than their own:
+    /// - Values of different variants order `Null < Bool < Number < String < List < Map`
+    /// - `false < true`
+    /// - Numbers follow `f64::total_cmp`: `-0.0 < 0.0`, and NaN sorts after every
+    ///   other number (before for negative NaN)
+    /// - Strings compare by Unicode code point (byte order of their UTF-8)
+    /// - Lists compare element by element, a prefix before the longer list
+    /// - Maps compare their entries in key order, each by key and then value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::Value;
+    ///
+    /// let mut values = vec![
+    ///     Value::String("b".into()),
+    ///     Value::Number(f64::NAN),
+    ///     Value::Null,
+    ///     Value::Number(2.0),
+    ///     Value::Bool(true),
+    ///     Value::String("a".into()),
+    ///     Value::Number(-1.0),
+    /// ];
+    /// values.sort_by(Value::total_cmp);
+    /// let rendered: Vec<String> = values.iter().map(|v| format!("{:?}", v)).collect();
+    /// assert_eq!(
+    ///     rendered,
+    ///     ["Null", "Bool(true)", "Number(-1.0)", "Number(2.0)", "Number(NaN)", "String(\"a\")", "String(\"b\")"]
+    /// );
+    /// ```
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Null => 0,
+                Value::Bool(_) => 1,
+                Value::Number(_) => 2,
+                Value::String(_) => 3,
+                Value::List(_) => 4,
+                Value::Map(_) => 5,
+            }
+        }
+
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => l.cmp(r),
+            (Value::Number(l), Value::Number(r)) => l.total_cmp(r),
+            (Value::String(l), Value::String(r)) => l.cmp(r),
+            (Value::List(l), Value::List(r)) => l
+                .iter()
+                .zip(r)
+                .map(|(l, r)| l.total_cmp(r))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len())),
+            (Value::Map(l), Value::Map(r)) => l
+                .iter()
+                .zip(r)
+                .map(|((lk, lv), (rk, rv))| lk.cmp(rk).then_with(|| lv.total_cmp(rv)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| l.len().cmp(&r.len())),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+
+    /// Multi-line rendering with lists and maps indented by two spaces per level
+    ///
+    /// Scalars and empty collections render as in `Display`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::Value;
+    ///
+    /// let value = Value::Map([("imports".into(), Value::List(vec!["VirtualAlloc".into()]))].into());
+    /// assert_eq!(value.to_pretty_string(), "{\n  imports: [\n    VirtualAlloc\n  ]\n}");
+    /// ```
+    pub fn to_pretty_string(&self) -> String {
+        fn write_pretty(value: &Value, indent: usize, out: &mut String) {
+            let pad = |out: &mut String, level: usize| out.push_str(&"  ".repeat(level));
+            match value {
+                Value::List(items) if !items.is_empty() => {
+                    out.push_str("[\n");
+                    for (i, item) in items.iter().enumerate() {
+                        pad(out, indent + 1);
+                        write_pretty(item, indent + 1, out);
+                        out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
                     }
-                    expr_str.push_str(next_line);
+                    pad(out, indent);
+                    out.push(']');
                 }
-                i += 1;
+                Value::Map(entries) if !entries.is_empty() => {
+                    out.push_str("{\n");
+                    for (i, (key, item)) in entries.iter().enumerate() {
+                        pad(out, indent + 1);
+                        out.push_str(key);
+                        out.push_str(": ");
+                        write_pretty(item, indent + 1, out);
+                        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                    }
+                    pad(out, indent);
+                    out.push('}');
+                }
+                scalar => out.push_str(&scalar.to_string()),
             }
+        }
 
-            final_expr = Some(parse_expression(&expr_str)?);
-            break;
+        let mut out = String::new();
+        write_pretty(self, 0, &mut out);
+        out
+    }
+
+    /// Traverse nested maps and lists by path (e.g., `"sections[0].name"`)
+    ///
+    /// A path is a dot-separated list of map keys, each optionally followed by
+    /// `[index]` list subscripts; a leading subscript (`"[0].name"`) indexes the
+    /// value itself and an empty path returns it. Returns `None` when a key or
+    /// index is missing, a step hits a value of the wrong kind, or the path is
+    /// malformed. Keys containing `.` or `[` cannot be addressed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::Value;
+    ///
+    /// let section = Value::Map([("name".into(), ".text".into())].into());
+    /// let binary = Value::Map([("sections".into(), Value::List(vec![section]))].into());
+    /// assert_eq!(binary.get_path("sections[0].name"), Some(&".text".into()));
+    /// assert_eq!(binary.get_path("sections[1].name"), None);
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        if path.is_empty() {
+            return Some(self);
         }
+        let mut current = self;
+        for (i, segment) in path.split('.').enumerate() {
+            let (key, mut subscripts) = match segment.find('[') {
+                Some(pos) => segment.split_at(pos),
+                None => (segment, ""),
+            };
+            if !key.is_empty() {
+                current = match current {
+                    Value::Map(map) => map.get(key)?,
+                    _ => return None,
+                };
+            } else if i > 0 || subscripts.is_empty() {
+                return None;
+            }
+            while !subscripts.is_empty() {
+                let (index, rest) = subscripts.strip_prefix('[')?.split_once(']')?;
+                let index: usize = index.parse().ok()?;
+                current = match current {
+                    Value::List(items) => items.get(index)?,
+                    _ => return None,
+                };
+                subscripts = rest;
+            }
+        }
+        Some(current)
+    }
 
-        i += 1;
+    /// Type name of the value (`"Null"`, `"Bool"`, `"String"`, `"Number"`, `"List"` or `"Map"`)
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "Null",
+            Value::Bool(_) => "Bool",
+            Value::String(_) => "String",
+            Value::Number(_) => "Number",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+        }
     }
 
-    let final_expr = final_expr.ok_or_else(|| {
-        HelError::parse_error("Script must have a final boolean expression".to_string())
-    })?;
+    /// The string, if this is a `String`
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The number, if this is a `Number`
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The boolean, if this is a `Bool`
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The items, if this is a `List`
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// The entries, if this is a `Map`
+    pub fn as_map(&self) -> Option<&BTreeMap<Arc<str>, Value>> {
+        match self {
+            Value::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Whether this is `Null`
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// The string, or a `TypeMismatch` naming `context` (e.g., the built-in)
+    ///
+    /// The `try_*` accessors produce the same error built-ins report for a wrong
+    /// argument, so a built-in can extract its arguments with `?`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{EvalError, Value};
+    ///
+    /// assert_eq!(Value::from("elf").try_str("core.upper").unwrap(), "elf");
+    /// let err = Value::Number(1.0).try_str("core.upper").unwrap_err();
+    /// assert!(matches!(err, EvalError::TypeMismatch { expected, .. } if expected == "String"));
+    /// ```
+    pub fn try_str(&self, context: &str) -> Result<&str, EvalError> {
+        self.as_str()
+            .ok_or_else(|| self.mismatch("String", context))
+    }
+
+    /// The number, or a `TypeMismatch` naming `context`
+    pub fn try_f64(&self, context: &str) -> Result<f64, EvalError> {
+        self.as_f64()
+            .ok_or_else(|| self.mismatch("Number", context))
+    }
+
+    /// The boolean, or a `TypeMismatch` naming `context`
+    pub fn try_bool(&self, context: &str) -> Result<bool, EvalError> {
+        self.as_bool().ok_or_else(|| self.mismatch("Bool", context))
+    }
+
+    /// The items, or a `TypeMismatch` naming `context`
+    pub fn try_list(&self, context: &str) -> Result<&[Value], EvalError> {
+        self.as_list().ok_or_else(|| self.mismatch("List", context))
+    }
+
+    /// The entries, or a `TypeMismatch` naming `context`
+    pub fn try_map(&self, context: &str) -> Result<&BTreeMap<Arc<str>, Value>, EvalError> {
+        self.as_map().ok_or_else(|| self.mismatch("Map", context))
+    }
 
-    Ok(Script {
-        bindings,
-        final_expr,
-    })
+    fn mismatch(&self, expected: &str, context: &str) -> EvalError {
+        EvalError::TypeMismatch {
+            expected: expected.to_string(),
+            got: format!("{:?}", self),
+            context: context.to_string(),
+        }
+    }
 }
 
-/// Evaluate a script and return the final boolean result
+/// Stable, single-line rendering used in traces and diagnostics
 ///
-/// Evaluates all let bindings in order, then evaluates the final expression.
+/// Strings are written without quotes, numbers with Rust's shortest `f64`
+/// formatting (`7`, `7.5`, `NaN`), lists as `[a, b]` and maps as `{key: value}` in
+/// key order. Use `Value::to_pretty_string()` for large values.
 ///
 /// # Examples
 ///
 /// ```
-/// use hel::{evaluate_script, FactsEvalContext, Value};
-///
-/// let mut ctx = FactsEvalContext::new();
-/// ctx.add_fact("manifest.permissions", Value::List(vec![
-///     Value::String("READ_SMS".into()),
-///     Value::String("SEND_SMS".into()),
-/// ]));
-/// ctx.add_fact("binary.entropy", Value::Number(8.0));
-///
-/// let script = r#"
-/// let has_sms_perms = manifest.permissions CONTAINS "READ_SMS"
-/// has_sms_perms AND binary.entropy > 7.5
-/// "#;
+/// use hel::Value;
 ///
-/// let result = evaluate_script(script, &ctx).expect("evaluation failed");
-/// assert!(result);
+/// let value = Value::List(vec![Value::Number(7.0), "elf".into(), Value::Null]);
+/// assert_eq!(value.to_string(), "[7, elf, null]");
 /// ```
-pub fn evaluate_script(script: &str, context: &FactsEvalContext) -> Result<bool, HelError> {
-    let parsed = parse_script(script)?;
-
-    // Start with base context
-    let mut eval_ctx = EvalContext::new(context);
-
-    // Evaluate and store let bindings
-    for (name, expr) in &parsed.bindings {
-        let value =
-            eval_node_to_value_with_context(expr, &eval_ctx).map_err(|e| HelError::from(e))?;
-
-        // Add variable to context
-        eval_ctx = eval_ctx.with_variable(name.clone(), value);
+impl core::fmt::Display for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+        }
     }
-
-    // Evaluate final expression
-    evaluate_ast_with_context(&parsed.final_expr, &eval_ctx).map_err(|e| e.into())
 }
 
-// ============================================================================
-// Helper implementations
-// ============================================================================
-
 impl From<&str> for Value {
     fn from(s: &str) -> Self {
         Value::String(Arc::from(s))
@@ -1413,6 +3711,38 @@ impl From<u64> for Value {
     }
 }
 
+/// Lists from any vector of convertible items (`vec!["READ_SMS", "SEND_SMS"].into()`)
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<&[&str]> for Value {
+    fn from(items: &[&str]) -> Self {
+        Value::List(items.iter().map(|&s| Value::from(s)).collect())
+    }
+}
+
+/// Maps from any `BTreeMap` with string keys and convertible values
+impl<K: Into<Arc<str>>, V: Into<Value>> From<BTreeMap<K, V>> for Value {
+    fn from(entries: BTreeMap<K, V>) -> Self {
+        Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// `None` becomes `Null`
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1446,6 +3776,259 @@ mod tests {
         assert!(trace.atoms[0].atom_result);
     }
 
+    #[test]
+    fn test_trace_atom_spans() {
+        let resolver = TestResolver;
+        let condition = "binary.format == \"elf\"\n  AND security.nx_enabled == true";
+
+        let trace = evaluate_with_trace(condition, &resolver, None).expect("evaluation failed");
+        let spans: Vec<SourceSpan> = trace.atoms.iter().map(|a| a.span.expect("span")).collect();
+        assert_eq!(
+            &condition[spans[0].start..spans[0].end],
+            "binary.format == \"elf\""
+        );
+        assert_eq!((spans[0].line, spans[0].column), (1, 1));
+        assert_eq!(
+            &condition[spans[1].start..spans[1].end],
+            "security.nx_enabled == true"
+        );
+        assert_eq!((spans[1].line, spans[1].column), (2, 7));
+
+        let built = AstNode::Comparison {
+            left: Box::new(AstNode::Attribute {
+                object: "binary".into(),
+                field: "format".into(),
+            }),
+            op: Comparator::Eq,
+            right: Box::new(AstNode::String("elf".into())),
+            span: SourceSpan::default(),
+        };
+        let trace =
+            trace::trace_ast(&built, &EvalContext::new(&resolver)).expect("evaluation failed");
+        assert_eq!(trace.atoms[0].span, None);
+    }
+
+    #[test]
+    fn test_builtin_error_call_site() {
+        let builtins = builtins::BuiltinsRegistry::builder()
+            .with_core()
+            .build()
+            .unwrap();
+        let mut facts = FactsEvalContext::new();
+        facts.add_fact("binary.size", Value::Number(4096.0));
+        facts.add_fact("binary.name", Value::String("a".repeat(40).into()));
+        let condition =
+            "core.len(binary.name) > 1 AND\n  core.upper(binary.name) == core.len(binary.size)";
+
+        let err = evaluate_with_context(condition, &facts, &builtins).unwrap_err();
+        let EvalError::BuiltinFailed {
+            function,
+            args,
+            span,
+            source,
+        } = &err
+        else {
+            panic!("expected BuiltinFailed, got {:?}", err);
+        };
+        assert_eq!(function, "core.len");
+        assert_eq!(args, &["4096"]);
+        assert_eq!(&condition[span.start..span.end], "core.len(binary.size)");
+        assert!(matches!(**source, EvalError::TypeMismatch { .. }));
+        assert!(err.to_string().starts_with(
+            "`core.len(4096)` failed at line 2, column 30: Type mismatch in core.len"
+        ));
+        assert!(core::error::Error::source(&err).is_some());
+
+        let hel_err: HelError = err.into();
+        assert!(matches!(hel_err.kind, ErrorKind::TypeError));
+        assert_eq!((hel_err.line, hel_err.column), (Some(2), Some(30)));
+
+        // Long arguments are truncated, and redacted like trace values
+        let err =
+            evaluate_with_context("core.len(binary.name, 1) == 1", &facts, &builtins).unwrap_err();
+        let long = format!("`core.len(\"{}…, 1)` failed", "a".repeat(31));
+        assert!(err.to_string().starts_with(&long));
+        let policy = RedactionPolicy::new().redact_path("binary.name");
+        let ctx = EvalContext::with_builtins(&facts, &builtins).with_redactor(&policy);
+        let ast = parse_expression("core.len(binary.name, 1) == 1").unwrap();
+        let err = evaluate_ast_with_context(&ast, &ctx).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("`core.len([REDACTED], 1)` failed"));
+
+        // Unknown functions are not wrapped
+        let err =
+            evaluate_with_context("core.lenght(binary.name) == 1", &facts, &builtins).unwrap_err();
+        assert!(matches!(err, EvalError::InvalidOperation(_)));
+    }
+
+    #[test]
+    fn test_value_get_path() {
+        let section = |name: &str| {
+            Value::Map(BTreeMap::from([(
+                Arc::from("name"),
+                Value::String(name.into()),
+            )]))
+        };
+        let value = Value::Map(BTreeMap::from([
+            (
+                Arc::from("sections"),
+                Value::List(vec![section(".text"), section(".rsrc")]),
+            ),
+            (
+                Arc::from("matrix"),
+                Value::List(vec![Value::List(vec![
+                    Value::Number(1.0),
+                    Value::Number(2.0),
+                ])]),
+            ),
+        ]));
+
+        assert_eq!(value.get_path(""), Some(&value));
+        assert_eq!(
+            value.get_path("sections[1].name"),
+            Some(&Value::String(".rsrc".into()))
+        );
+        assert_eq!(value.get_path("matrix[0][1]"), Some(&Value::Number(2.0)));
+        assert_eq!(
+            value
+                .get_path("sections")
+                .and_then(|s| s.get_path("[0].name")),
+            Some(&Value::String(".text".into()))
+        );
+        for missing in [
+            "sections[2].name",
+            "sections.name",
+            "sections[0].size",
+            "matrix[0][1][0]",
+            "sections[x]",
+            "sections[0",
+            "sections..name",
+            "sections.[0]",
+            "sections[0]x",
+        ] {
+            assert_eq!(value.get_path(missing), None, "{}", missing);
+        }
+    }
+
+    #[test]
+    fn test_value_accessors() {
+        let list = Value::List(vec![Value::String("READ_SMS".into())]);
+        assert_eq!(list.as_list().map(<[Value]>::len), Some(1));
+        assert_eq!(list.as_list().unwrap()[0].as_str(), Some("READ_SMS"));
+        assert_eq!(Value::Number(7.5).as_f64(), Some(7.5));
+        assert_eq!(Value::Bool(true).as_bool(), Some(true));
+        assert!(Value::Map(BTreeMap::new()).as_map().unwrap().is_empty());
+        assert!(Value::Null.is_null());
+
+        assert_eq!(Value::String("7".into()).as_f64(), None);
+        assert_eq!(Value::Null.as_bool(), None);
+        assert_eq!(list.type_name(), "List");
+
+        assert_eq!(Value::Number(2.0).try_f64("ctx").unwrap(), 2.0);
+        match list.try_map("security.imports") {
+            Err(EvalError::TypeMismatch {
+                expected,
+                got,
+                context,
+            }) => {
+                assert_eq!(expected, "Map");
+                assert!(got.starts_with("List"), "{}", got);
+                assert_eq!(context, "security.imports");
+            }
+            other => panic!("expected TypeMismatch, got {:?}", other),
+        }
+        assert!(Value::Null.try_bool("ctx").is_err());
+        assert!(Value::Null.try_list("ctx").is_err());
+    }
+
+    #[test]
+    fn test_value_display() {
+        let value = Value::Map(BTreeMap::from([
+            (Arc::from("name"), Value::String(".text".into())),
+            (
+                Arc::from("flags"),
+                Value::List(vec![
+                    Value::Bool(true),
+                    Value::Number(0.5),
+                    Value::List(vec![]),
+                ]),
+            ),
+            (Arc::from("owner"), Value::Null),
+        ]));
+        assert_eq!(
+            value.to_string(),
+            "{flags: [true, 0.5, []], name: .text, owner: null}"
+        );
+        assert_eq!(
+            value.to_pretty_string(),
+            "{\n  flags: [\n    true,\n    0.5,\n    []\n  ],\n  name: .text,\n  owner: null\n}"
+        );
+        assert_eq!(Value::Number(7.0).to_pretty_string(), "7");
+        assert_eq!(Value::Map(BTreeMap::new()).to_pretty_string(), "{}");
+    }
+
+    #[test]
+    fn test_value_total_order() {
+        let list = |items: &[f64]| Value::List(items.iter().map(|&n| Value::Number(n)).collect());
+        let map = |entries: &[(&str, f64)]| {
+            Value::Map(
+                entries
+                    .iter()
+                    .map(|&(k, v)| (Arc::from(k), Value::Number(v)))
+                    .collect(),
+            )
+        };
+
+        assert_eq!(
+            list(&[1.0, 2.0]).total_cmp(&list(&[1.0, 3.0])),
+            Ordering::Less
+        );
+        assert_eq!(list(&[1.0]).total_cmp(&list(&[1.0, 0.0])), Ordering::Less);
+        assert_eq!(list(&[]).total_cmp(&list(&[])), Ordering::Equal);
+        assert_eq!(
+            map(&[("a", 2.0)]).total_cmp(&map(&[("a", 1.0), ("b", 0.0)])),
+            Ordering::Greater
+        );
+        assert_eq!(
+            map(&[("a", 1.0)]).total_cmp(&map(&[("b", 0.0)])),
+            Ordering::Less
+        );
+        assert_eq!(list(&[9.0]).total_cmp(&map(&[])), Ordering::Less);
+        assert_eq!(
+            Value::Number(-0.0).total_cmp(&Value::Number(0.0)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::Bool(false).total_cmp(&Value::Bool(true)),
+            Ordering::Less
+        );
+
+        // Ordering comparators only order numbers
+        assert_eq!(
+            Value::Number(1.0).partial_cmp_semantics(&Value::Number(2.0)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            Value::Number(f64::NAN).partial_cmp_semantics(&Value::Number(2.0)),
+            None
+        );
+        assert_eq!(
+            Value::from("a").partial_cmp_semantics(&Value::from("b")),
+            None
+        );
+        assert!(compare_new_values(
+            &Value::Number(2.0),
+            &Value::Number(2.0),
+            Comparator::Ge
+        ));
+        assert!(!compare_new_values(
+            &Value::from("b"),
+            &Value::from("a"),
+            Comparator::Gt
+        ));
+    }
+
     #[test]
     fn test_resolver_number_and_list_behavior() {
         struct CustomResolver;
@@ -1516,6 +4099,428 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_limits_nesting_depth() {
+        let at_limit = format!(
+            "{}a.b == 1{}",
+            "(".repeat(DEFAULT_MAX_NESTING_DEPTH),
+            ")".repeat(DEFAULT_MAX_NESTING_DEPTH)
+        );
+        assert!(parse_expression(&at_limit).is_ok());
+
+        // Far beyond the limit: must fail cleanly instead of overflowing the stack
+        let deep = format!("{}a.b == 1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let err = parse_expression(&deep).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::LimitExceeded));
+        assert_eq!(err.column, Some(DEFAULT_MAX_NESTING_DEPTH + 1));
+
+        // Brackets inside string literals do not count towards nesting
+        let limits = ParseLimits {
+            max_nesting_depth: 1,
+            ..ParseLimits::default()
+        };
+        assert!(parse_expression_with_limits(r#"x.y IN ["((([[["]"#, &limits).is_ok());
+        assert!(parse_expression_with_limits("x.y IN [[1]]", &limits).is_err());
+    }
+
+    #[test]
+    fn test_nested_parentheses_parse_linearly() {
+        // Each level used to be tried as a comparison and then re-parsed as a
+        // bare primary, doubling the work per level
+        let open = "(".repeat(DEFAULT_MAX_NESTING_DEPTH - 1);
+        let close = ")".repeat(DEFAULT_MAX_NESTING_DEPTH - 1);
+        for inner in ["a.b", "a.b == 1", "core.len(a.b) > 1"] {
+            let nested = format!("{}{}{}", open, inner, close);
+            assert!(parse_expression(&nested).is_ok(), "{}", inner);
+        }
+    }
+
+    #[test]
+    fn test_default_parse_limits_apply_to_all_entry_points() {
+        let deep = format!("{}a.b == 1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let is_limit = |e: &HelError| matches!(e.kind, ErrorKind::LimitExceeded);
+
+        assert!(is_limit(&validate_expression(&deep).unwrap_err()));
+        assert!(is_limit(&parse_script(&deep).unwrap_err()));
+        assert!(is_limit(&validate_script(&deep).unwrap_err()[0]));
+        let script = format!("let x = {}\nx", deep);
+        assert!(is_limit(&parse_script(&script).unwrap_err()));
+    }
+
+    #[test]
+    fn test_parse_limits_input_length() {
+        let limits = ParseLimits {
+            max_input_len: 8,
+            ..ParseLimits::default()
+        };
+        let err = validate_expression_with_limits("binary.format == 1", &limits).unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::LimitExceeded));
+        assert!(validate_expression_with_limits("a.b == 1", &limits).is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_trailing_input() {
+        assert!(validate_expression("binary.arch == ").is_err());
+        assert!(validate_expression("a.b == 1 c.d").is_err());
+        assert!(validate_expression("  a.b == 1  ").is_ok());
+    }
+
+    #[test]
+    fn test_trailing_input_rejected_by_all_entry_points() {
+        let mut facts = FactsEvalContext::new();
+        facts.add_fact("a.b", Value::Number(1.0));
+
+        for expr in [
+            "a.b == 1 c.d",
+            "a.b == 1)",
+            "a.b == 1 AND",
+            "a.b == 1 \"x\"",
+        ] {
+            let err = parse_expression(expr).unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::ParseError), "{}", expr);
+            assert!(evaluate(expr, &facts).is_err(), "{}", expr);
+        }
+        // A valid prefix was previously evaluated and the rest ignored
+        assert!(evaluate("a.b == 1 OR", &facts).is_err());
+        assert!(parse_script("let x = a.b == 1 junk\nx").is_err());
+        assert!(parse_script("let x = a.b == 1\nx junk").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_entry_points_report_parse_errors() {
+        let facts = FactsEvalContext::new();
+        let builtins = builtins::BuiltinsRegistry::new();
+        let deep = format!(
+            "{}a.b == 1{}",
+            "(".repeat(DEFAULT_MAX_NESTING_DEPTH + 1),
+            ")".repeat(DEFAULT_MAX_NESTING_DEPTH + 1)
+        );
+
+        let err = evaluate_with_resolver(&deep, &facts).unwrap_err();
+        assert!(matches!(err, EvalError::ParseError(_)));
+        let err = evaluate_with_context(&deep, &facts, &builtins).unwrap_err();
+        assert!(matches!(err, EvalError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_eq_ignore_case() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("proc.name", Value::String("PowerShell.EXE".into()));
+        ctx.add_fact("proc.pid", Value::Number(4.0));
+        ctx.add_fact("proc.user", Value::String("ÄNNE".into()));
+
+        assert!(evaluate(r#"proc.name EQI "powershell.exe""#, &ctx).unwrap());
+        assert!(evaluate(r#"proc.name ==* "POWERSHELL.exe""#, &ctx).unwrap());
+        assert!(!evaluate(r#"proc.name EQI "powershell""#, &ctx).unwrap());
+        assert!(evaluate(r#"proc.user EQI "änne""#, &ctx).unwrap());
+        // Non-strings compare as ==
+        assert!(evaluate("proc.pid EQI 4", &ctx).unwrap());
+        assert!(!evaluate(r#"proc.pid EQI "4""#, &ctx).unwrap());
+
+        let ast = parse_expression(r#"proc.name ==* "x""#).unwrap();
+        assert_eq!(
+            format::format_ast(&ast, &format::FormatOptions::default()),
+            r#"proc.name EQI "x""#
+        );
+    }
+
+    #[test]
+    fn test_like_glob() {
+        assert!(glob_match("*.exe", "cmd.exe"));
+        assert!(glob_match("c?d.*", "cmd.exe"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(glob_match(r"C:\*\*.exe", r"C:\Windows\System32\cmd.exe"));
+        assert!(!glob_match("*.exe", "cmd.exe.bak"));
+        assert!(!glob_match("?", ""));
+        assert!(!glob_match("a*b", "aXc"));
+        assert!(glob_match("ä?ö", "äüö"));
+
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("file.path", Value::String(r"C:\Windows\Temp\x.EXE".into()));
+        ctx.add_fact("file.size", Value::Number(1.0));
+        assert!(evaluate(r#"file.path LIKE "C:\Windows\*""#, &ctx).unwrap());
+        // Case-sensitive, unlike EQI
+        assert!(!evaluate(r#"file.path LIKE "*.exe""#, &ctx).unwrap());
+        assert!(!evaluate(r#"file.size LIKE "*""#, &ctx).unwrap());
+    }
+
+    #[test]
+    fn test_keywords_are_case_insensitive() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("app.perms", Value::List(vec!["SMS".into()]));
+        ctx.add_fact("app.name", Value::String("Acme".into()));
+        ctx.add_fact("app.score", Value::Number(5.0));
+        for expr in [
+            r#"app.perms contains "SMS" and app.name in ["Acme"]"#,
+            r#"app.name eqi "ACME" Or false"#,
+            r#"app.name like "A*" && app.score between 1 and 9"#,
+            "app.score In 1..9 xor false",
+        ] {
+            assert!(evaluate(expr, &ctx).unwrap(), "{}", expr);
+        }
+        assert_eq!(
+            format::format_expression(
+                "a.b in [1] and c.d Contains 2 or e.f like \"x\"",
+                &Default::default()
+            )
+            .unwrap(),
+            r#"(a.b IN [1] AND c.d CONTAINS 2) OR e.f LIKE "x""#
+        );
+
+        // A lowercase operator at the start of a line continues a `let`
+        let script = "let a = app.score > 9\n  or app.score < 9\na";
+        assert!(evaluate_script(script, &ctx).unwrap());
+        let script = "let a = app.score > 9\n  OR(app.score < 9)\na";
+        assert!(evaluate_script(script, &ctx).unwrap());
+
+        // ...but only as a whole word: these lines start the final expression
+        ctx.add_fact("x.y", Value::Number(1.0));
+        ctx.add_fact("order.total", Value::Number(6.0));
+        ctx.add_fact("android.sdk", Value::Number(6.0));
+        ctx.add_fact("xor_flags.count", Value::Number(6.0));
+        for script in [
+            "let a = x.y == 1\norder.total > 5",
+            "let a = x.y == 1\nandroid.sdk > 5",
+            "let a = x.y == 1\nxor_flags.count > 5",
+        ] {
+            assert!(evaluate_script(script, &ctx).unwrap(), "{}", script);
+        }
+    }
+
+    #[test]
+    fn test_inline_comments() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("bin.signed", Value::Bool(true));
+        ctx.add_fact("bin.url", Value::String("http://x#y".into()));
+        let expr = "bin.signed == true # vendor builds are signed\n\
+                    AND bin.url == \"http://x#y\" // strings keep # and //";
+        assert!(evaluate(expr, &ctx).unwrap());
+        assert!(parse_expression("# only a comment").is_err());
+        assert_eq!(
+            split_comment(r##"a.b == "#" // c # d"##),
+            (r##"a.b == "#" "##, Some("// c # d"))
+        );
+
+        // Brackets in comments do not count towards nesting
+        let limits = ParseLimits {
+            max_nesting_depth: 1,
+            ..ParseLimits::default()
+        };
+        assert!(parse_expression_with_limits("a.b == 1 # ((((", &limits).is_ok());
+
+        let script = "let signed = bin.signed == true # from the manifest\n\
+                      let ok = signed AND // continued\n\
+                      bin.url == \"http://x#y\"\n\
+                      ok // final";
+        assert!(evaluate_script(script, &ctx).unwrap());
+        assert_eq!(parse_script(script).unwrap().bindings.len(), 2);
+    }
+
+    #[test]
+    fn test_xor() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("bin.signed", Value::Bool(true));
+        ctx.add_fact("bin.packed", Value::Bool(false));
+        assert!(evaluate("bin.signed == true XOR bin.packed == true", &ctx).unwrap());
+        assert!(!evaluate("bin.signed == true XOR bin.packed == false", &ctx).unwrap());
+        assert!(!evaluate("true XOR true", &ctx).unwrap());
+        // Odd parity across a chain
+        assert!(evaluate("true xor true xor true", &ctx).unwrap());
+
+        // AND binds tighter than XOR, XOR tighter than OR
+        assert!(evaluate("false AND true XOR true", &ctx).unwrap());
+        assert!(evaluate("true XOR true OR true", &ctx).unwrap());
+        let canonical = |src| format::format_expression(src, &Default::default()).unwrap();
+        assert_eq!(
+            canonical("a.b == 1 XOR c.d == 2 AND e.f == 3"),
+            "a.b == 1 XOR (c.d == 2 AND e.f == 3)"
+        );
+        // Without XOR the parser output keeps its `Or` of `And` shape
+        assert!(matches!(
+            parse_expression("a.b == 1").unwrap(),
+            AstNode::Or(ref or) if matches!(or[..], [AstNode::And(_)])
+        ));
+
+        // Both operands are evaluated and traced
+        let trace =
+            trace::evaluate_with_trace("bin.signed == true XOR bin.packed == true", &ctx, None)
+                .unwrap();
+        assert!(trace.result);
+        assert_eq!(trace.atoms.len(), 2);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("net.port", Value::Number(8080.0));
+        ctx.add_fact("ml.score", Value::Number(0.8));
+        ctx.add_fact("ml.low", Value::Number(0.5));
+        ctx.add_fact("net.proto", Value::String("tcp".into()));
+        assert!(evaluate("net.port IN 1024..49151", &ctx).unwrap());
+        assert!(evaluate("net.port IN 8080..8080", &ctx).unwrap());
+        assert!(!evaluate("net.port IN 1..1023", &ctx).unwrap());
+        assert!(evaluate("ml.score BETWEEN 0.4 AND 0.8", &ctx).unwrap());
+        assert!(evaluate("ml.score BETWEEN ml.low && 1", &ctx).unwrap());
+        assert!(!evaluate("ml.score BETWEEN 0.9 AND 1.0 OR net.proto IN 1..2", &ctx).unwrap());
+        assert!(!evaluate("net.missing IN 0..10", &ctx).unwrap());
+
+        // BETWEEN is sugar for IN with a range
+        assert_eq!(
+            parse_expression("ml.score BETWEEN 0.4 AND 0.8").unwrap(),
+            parse_expression("ml.score IN 0.4..0.8").unwrap()
+        );
+        assert!(parse_expression("net.port IN 1..").is_err());
+        assert!(parse_expression("net.port BETWEEN 1 OR 2").is_err());
+        assert!(parse_expression(r#"net.port IN "a".."b""#).is_err());
+
+        let range = AstNode::Range {
+            start: Box::new(AstNode::Number(1)),
+            end: Box::new(AstNode::Number(2)),
+        };
+        let err = evaluate_ast(&range, &EvalContext::new(&ctx)).unwrap_err();
+        assert!(matches!(err, EvalError::InvalidOperation(_)));
+
+        let trace = trace::evaluate_with_trace("net.port IN 1024..ml.low", &ctx, None).unwrap();
+        assert_eq!(trace.atoms[0].right, "1024..ml.low");
+        assert_eq!(
+            trace.atoms[0].resolved_right_value.as_deref(),
+            Some("1024..0.5")
+        );
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let section = |entropy: f64, exec: bool| {
+            Value::Map(BTreeMap::from([
+                (Arc::from("entropy"), Value::Number(entropy)),
+                (Arc::from("exec"), Value::Bool(exec)),
+            ]))
+        };
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("sections.data", section(4.2, false));
+        ctx.add_fact("sections.text", section(7.9, true));
+        ctx.add_fact("sections.note", Value::String("not a section".into()));
+        ctx.add_fact("policy.max", Value::Number(7.5));
+
+        assert!(evaluate("sections.*.entropy > 7.5", &ctx).unwrap());
+        assert!(!evaluate("sections.*.entropy > 8", &ctx).unwrap());
+        assert!(evaluate("policy.max < sections.*.entropy", &ctx).unwrap());
+        assert!(evaluate("sections.*.entropy IN 4..5", &ctx).unwrap());
+        assert!(evaluate("4.2 IN sections.*.entropy", &ctx).unwrap());
+        assert!(evaluate("sections.*.exec", &ctx).unwrap());
+        assert!(!evaluate("sections.*.missing == 1 OR other.*.exec", &ctx).unwrap());
+        assert_eq!(
+            format::format_expression("sections.*.entropy>7", &Default::default()).unwrap(),
+            "sections.*.entropy > 7"
+        );
+
+        let diagnostics = Diagnostics::new();
+        let ast = parse_expression("other.*.exec").unwrap();
+        let eval_ctx = EvalContext::new(&ctx).with_diagnostics(&diagnostics);
+        assert!(!evaluate_ast(&ast, &eval_ctx).unwrap());
+        assert!(diagnostics.take()[0].message.contains("`other.*.exec`"));
+
+        let trace =
+            trace::evaluate_with_trace("sections.*.entropy > policy.max", &ctx, None).unwrap();
+        let atom = &trace.atoms[0];
+        assert!(atom.atom_result);
+        assert_eq!(atom.left, "sections.*.entropy");
+        assert_eq!(atom.resolved_left_value.as_deref(), Some("[4.2, 7.9]"));
+        assert_eq!(atom.resolved_right_value.as_deref(), Some("7.5"));
+        let matches: Vec<_> = atom
+            .matches
+            .iter()
+            .map(|m| (m.path.as_str(), m.value.as_str(), m.result))
+            .collect();
+        assert_eq!(
+            matches,
+            vec![
+                ("sections.data.entropy", "4.2", false),
+                ("sections.text.entropy", "7.9", true)
+            ]
+        );
+        assert!(trace
+            .facts_used()
+            .contains(&"sections.text.entropy".to_string()));
+        assert!(trace
+            .to_string()
+            .contains("sections.text.entropy = 7.9 => true"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_enum_constants() {
+        let schema = parse_schema(
+            "enum Stage {\n  New\n  Won = \"closed_won\"\n  Lost = \"closed_lost\"\n}\n\ntype Lead {\n  stage: Stage\n}\n",
+        )
+        .unwrap();
+        let types = TypeEnvironment {
+            types: BTreeMap::new(),
+            enums: schema
+                .enums
+                .into_iter()
+                .map(|(name, def)| (Arc::from(format!("crm.{}", name)), def))
+                .collect(),
+        };
+        let mut facts = FactsEvalContext::new();
+        facts.add_fact("lead.stage", Value::String("closed_won".into()));
+        let ctx = EvalContext::new(&facts).with_types(&types);
+
+        let eval = |expr: &str| evaluate_ast(&parse_expression(expr).unwrap(), &ctx);
+        assert!(eval("lead.stage == Stage.Won").unwrap());
+        assert!(!eval("lead.stage == Stage.New").unwrap());
+        assert!(eval("lead.stage IN [Stage.Won, Stage.Lost]").unwrap());
+        assert!(matches!(
+            eval("lead.stage == Stage.Closed"),
+            Err(EvalError::UnknownAttribute { .. })
+        ));
+
+        // Without a type environment `Stage.Won` is an (absent) fact
+        assert!(!evaluate("lead.stage == Stage.Won", &facts).unwrap());
+    }
+
+    #[test]
+    fn test_numeric_literal_forms() {
+        let literal = |src: &str| match parse_expression(&format!("a.b == {}", src)) {
+            Ok(AstNode::Or(or)) => match &or[..] {
+                [AstNode::And(and)] => match &and[..] {
+                    [AstNode::Comparison { right, .. }] => (**right).clone(),
+                    other => panic!("unexpected {:?}", other),
+                },
+                other => panic!("unexpected {:?}", other),
+            },
+            other => panic!("{}: {:?}", src, other),
+        };
+        assert!(matches!(literal("1_000_000"), AstNode::Number(1_000_000)));
+        assert!(matches!(literal("0xFF_FF"), AstNode::Number(0xffff)));
+        assert!(matches!(literal("1.5e6"), AstNode::Float(f) if f == 1.5e6));
+        assert!(matches!(literal("2E-3"), AstNode::Float(f) if f == 2e-3));
+        assert!(matches!(literal("1e+3"), AstNode::Float(f) if f == 1e3));
+        assert!(matches!(literal("3_000.000_5"), AstNode::Float(f) if f == 3000.0005));
+
+        assert_eq!(literal("18446744073709551615"), AstNode::Number(u64::MAX));
+        assert_eq!(literal("0xFFFF_FFFF_FFFF_FFFF"), AstNode::Number(u64::MAX));
+
+        // Integers beyond u64 are parse errors at the literal, not panics
+        for overflow in ["99999999999999999999", "0x1_0000_0000_0000_0000"] {
+            let err = parse_expression(&format!("a.b == {}", overflow)).unwrap_err();
+            assert!(matches!(err.kind, ErrorKind::ParseError), "{}", overflow);
+            assert_eq!((err.line, err.column), (Some(1), Some(8)), "{}", overflow);
+            assert!(err.message.contains(overflow), "{}", err.message);
+        }
+        let err = parse_expression("x.y IN 1..99999999999999999999").unwrap_err();
+        assert_eq!(err.column, Some(11));
+
+        for invalid in ["1__0", "1_", "1e", "1.5e", "0x_1"] {
+            assert!(
+                validate_expression(&format!("a.b == {}", invalid)).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
     #[test]
     fn test_parse_expression_success() {
         let expr = r#"binary.format == "elf""#;
@@ -1528,6 +4533,7 @@ mod tests {
                 left: _,
                 op,
                 right: _,
+                span: _,
             } => {
                 assert_eq!(*op, Comparator::Eq);
             }
@@ -1537,6 +4543,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_unqualified_function_call() {
+        fn call(node: AstNode) -> (Option<Arc<str>>, Arc<str>, Vec<AstNode>) {
+            match node {
+                AstNode::Or(mut nodes) | AstNode::And(mut nodes) if nodes.len() == 1 => {
+                    call(nodes.remove(0))
+                }
+                AstNode::Comparison { left, .. } => call(*left),
+                AstNode::FunctionCall {
+                    namespace,
+                    name,
+                    args,
+                    ..
+                } => (namespace, name, args),
+                other => panic!("expected a call, got {:?}", other),
+            }
+        }
+
+        // The first argument used to be taken as the function name
+        let (namespace, name, args) = call(parse_expression("len(x.y) == 1").unwrap());
+        assert_eq!(namespace, None);
+        assert_eq!(name.as_ref(), "len");
+        assert_eq!(
+            args,
+            vec![AstNode::Attribute {
+                object: "x".into(),
+                field: "y".into()
+            }]
+        );
+
+        let (namespace, name, args) = call(parse_expression(r#"core.len("a", 2) == 1"#).unwrap());
+        assert_eq!(namespace.as_deref(), Some("core"));
+        assert_eq!(name.as_ref(), "len");
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn test_ast_equality_ignores_spans() {
+        let compact = parse_expression("a.b == 1 AND core.len(x.y) > 2").unwrap();
+        let spread = parse_expression("a.b  ==  1\n  AND core.len( x.y ) > 2").unwrap();
+        assert_eq!(compact, spread);
+        let changed = parse_expression("a.b == 1 AND core.len(x.y) > 3").unwrap();
+        assert_ne!(compact, changed);
+
+        // Spans themselves compare structurally
+        let span = SourceSpan {
+            start: 0,
+            end: 8,
+            line: 1,
+            column: 1,
+        };
+        assert_eq!(span, span);
+        assert_ne!(span, SourceSpan::default());
+    }
+
     #[test]
     fn test_facts_eval_context() {
         let mut ctx = FactsEvalContext::new();
@@ -1551,6 +4612,32 @@ mod tests {
         assert_eq!(ctx.resolve_attr("security", "nx"), Some(Value::Bool(false)));
     }
 
+    #[test]
+    fn test_facts_eval_context_with_schema() {
+        let schema = parse_schema("type Binary {\n  arch: String\n  entropy: Number\n}\n")
+            .expect("parse failed");
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("binary.arch", Value::String("x86_64".into()));
+        ctx.add_fact("binary.packed", Value::Bool(true));
+        let mut ctx = ctx.with_schema(schema);
+
+        assert_eq!(ctx.rejected_facts().len(), 1);
+        assert_eq!(ctx.rejected_facts()[0].0, "binary.packed");
+        assert!(ctx
+            .try_add_fact("binary.entropy", Value::Number(7.9))
+            .is_ok());
+        let err = ctx
+            .try_add_fact("binary.entropy", Value::String("7.9".into()))
+            .unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::TypeError), "{:?}", err.kind);
+        ctx.add_fact("binary.arch", Value::Number(64.0));
+        assert_eq!(ctx.rejected_facts().len(), 2);
+
+        // Rejected facts are never stored
+        assert!(evaluate(r#"binary.arch == "x86_64" AND binary.entropy > 7.5"#, &ctx).unwrap());
+        assert_eq!(ctx.resolve_attr("binary", "packed"), None);
+    }
+
     #[test]
     fn test_evaluate_with_facts_context() {
         let mut ctx = FactsEvalContext::new();
@@ -1599,6 +4686,71 @@ mod tests {
         assert_eq!(parsed.bindings.len(), 1);
     }
 
+    #[test]
+    fn test_trailing_commas() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("app.perm", Value::String("SEND_SMS".into()));
+        ctx.add_fact("app.size", Value::Number(3.0));
+
+        assert!(evaluate(r#"app.perm IN ["READ_SMS", "SEND_SMS",]"#, &ctx).unwrap());
+        assert_eq!(
+            parse_expression(r#"x == {"a": 1, "b": [2, 3,],}"#).unwrap(),
+            parse_expression(r#"x == {"a": 1, "b": [2, 3]}"#).unwrap()
+        );
+        assert!(parse_expression("[,] == x").is_err());
+        assert!(parse_expression("{,} == x").is_err());
+        assert!(parse_expression("[1,,] == x").is_err());
+
+        let script = r#"
+            let risky = app.perm IN [
+                "READ_SMS",
+                "SEND_SMS",
+            ]
+            risky AND app.size > 2
+        "#;
+        assert_eq!(parse_script(script).unwrap().bindings.len(), 1);
+        assert!(evaluate_script(script, &ctx).unwrap());
+    }
+
+    #[test]
+    fn test_identifier_map_keys() {
+        assert_eq!(
+            parse_expression(r#"x == {severity: "high", score: 8, "quoted key": true}"#).unwrap(),
+            parse_expression(r#"x == {"severity": "high", "score": 8, "quoted key": true}"#)
+                .unwrap()
+        );
+        assert_eq!(
+            format::format_expression("x == {severity: \"high\"}", &Default::default()).unwrap(),
+            r#"x == {"severity": "high"}"#
+        );
+        assert!(parse_expression("x == {a.b: 1}").is_err());
+    }
+
+    #[test]
+    fn test_chained_comparisons() {
+        let mut ctx = FactsEvalContext::new();
+        ctx.add_fact("score.risk", Value::Number(0.6));
+
+        assert_eq!(
+            parse_expression("0.4 <= score.risk <= 0.8 AND a.b").unwrap(),
+            parse_expression("0.4 <= score.risk AND score.risk <= 0.8 AND a.b").unwrap()
+        );
+        assert!(evaluate("0.4 <= score.risk <= 0.8", &ctx).unwrap());
+        assert!(evaluate("0.4 <= score.risk < 0.6 OR 1 > score.risk > 0.5", &ctx).unwrap());
+        assert!(!evaluate("0.4 <= score.risk < 0.6", &ctx).unwrap());
+        assert!(evaluate("0 < 0.1 < score.risk <= 0.6 <= 1", &ctx).unwrap());
+
+        let err = parse_expression("a.b == a.c < 3").unwrap_err();
+        assert!(matches!(err.kind, ErrorKind::ParseError));
+        assert!(
+            err.message.contains("`==` cannot be chained"),
+            "{}",
+            err.message
+        );
+        assert_eq!((err.line, err.column), (Some(1), Some(5)));
+        assert!(parse_expression("1 < a.b IN [1]").is_err());
+    }
+
     #[test]
     fn test_parse_script_multiple_bindings() {
         let script = r#"
@@ -1670,6 +4822,34 @@ mod tests {
 
         let v4: Value = 42i32.into();
         assert_eq!(v4, Value::Number(42.0));
+
+        let perms: Value = vec!["READ_SMS", "SEND_SMS"].into();
+        assert_eq!(
+            perms,
+            Value::List(vec![
+                Value::String("READ_SMS".into()),
+                Value::String("SEND_SMS".into())
+            ])
+        );
+        let slice: &[&str] = &["READ_SMS", "SEND_SMS"];
+        assert_eq!(Value::from(slice), perms);
+        assert_eq!(
+            Value::from(vec![vec![1.0], vec![]]),
+            Value::List(vec![
+                Value::List(vec![Value::Number(1.0)]),
+                Value::List(vec![])
+            ])
+        );
+
+        let map: Value = BTreeMap::from([("format".to_string(), Value::from("pe"))]).into();
+        assert_eq!(map.get_path("format"), Some(&Value::String("pe".into())));
+        assert_eq!(
+            Value::from(BTreeMap::from([("entropy", 7.5)])),
+            Value::Map(BTreeMap::from([(Arc::from("entropy"), Value::Number(7.5))]))
+        );
+
+        assert_eq!(Value::from(None::<&str>), Value::Null);
+        assert_eq!(Value::from(Some(true)), Value::Bool(true));
     }
 
     #[test]
@@ -0,0 +1,349 @@
+//! Mutation testing for rules
+//!
+//! A rule is only as trustworthy as the corpus it was tested against. Mutation
+//! testing makes small, plausible mistakes in a rule's atoms and re-evaluates
+//! each mutant against labeled facts: a mutant the corpus cannot tell apart from
+//! the original (a survivor) points at an untested boundary or a conjunct that
+//! could be dropped without anyone noticing.
+//!
+//! ## Mutations
+//! - Flip comparators: `==` / `!=`, `>` / `>=` / `<` / `<=` (boundary and direction)
+//! - Perturb thresholds: integer literals by ±1, float literals by ±10% (±0.1 at zero)
+//! - Drop conjuncts: remove one operand of an `AND` chain
+//!
+//! ## Determinism
+//! - Mutants are generated in source order (pre-order over the condition)
+//! - A mutant is killed by the first record, in corpus order, that exposes it
+
+use alloc::sync::Arc;
+
+use crate::format::{format_ast, FormatOptions};
+use crate::prelude::*;
+use crate::rules::Rule;
+use crate::{AstNode, Comparator, EvalContext, HelResolver};
+
+// region:    --- Mutation
+
+/// Kind of change applied to a rule atom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationKind {
+	/// A comparison operator replaced by a related one
+	FlipComparator,
+	/// A numeric literal in a comparison moved slightly
+	PerturbThreshold,
+	/// One operand of an `AND` chain removed
+	DropConjunct,
+}
+
+/// A single mutant of a rule condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+	/// Kind of change
+	pub kind: MutationKind,
+	/// The mutated atom as it appears in the original condition (formatted)
+	pub original: String,
+	/// The atom after mutation, or `None` for a dropped conjunct
+	pub replacement: Option<String>,
+	/// The whole mutated condition
+	pub condition: AstNode,
+}
+
+/// All single-point mutants of a condition, in source order
+///
+/// # Examples
+///
+/// ```
+/// use hel::mutate::{mutations, MutationKind};
+/// use hel::parse_expression;
+///
+/// let ast = parse_expression("binary.entropy > 7 AND binary.signed == false").unwrap();
+/// let kinds: Vec<MutationKind> = mutations(&ast).iter().map(|m| m.kind).collect();
+/// assert_eq!(kinds.iter().filter(|k| **k == MutationKind::DropConjunct).count(), 2);
+/// assert_eq!(mutations(&ast)[1].replacement.as_deref(), Some("binary.entropy >= 7"));
+/// ```
+pub fn mutations(condition: &AstNode) -> Vec<Mutation> {
+	let mut out = Vec::new();
+	mutate_node(condition, &mut out);
+	out
+}
+
+fn mutate_node(node: &AstNode, out: &mut Vec<Mutation>) {
+	match node {
+		AstNode::Comparison { left, op, right, span } => {
+			for &flipped in flipped_comparators(*op) {
+				let mutant = AstNode::Comparison {
+					left: left.clone(),
+					op: flipped,
+					right: right.clone(),
+					span: *span,
+				};
+				out.push(atom_mutation(MutationKind::FlipComparator, node, mutant));
+			}
+			for perturbed in perturbed_literals(right) {
+				let mutant = AstNode::Comparison {
+					left: left.clone(),
+					op: *op,
+					right: Box::new(perturbed),
+					span: *span,
+				};
+				out.push(atom_mutation(MutationKind::PerturbThreshold, node, mutant));
+			}
+			for perturbed in perturbed_literals(left) {
+				let mutant = AstNode::Comparison {
+					left: Box::new(perturbed),
+					op: *op,
+					right: right.clone(),
+					span: *span,
+				};
+				out.push(atom_mutation(MutationKind::PerturbThreshold, node, mutant));
+			}
+		}
+		AstNode::And(items) => mutate_chain(items, AstNode::And, true, out),
+		AstNode::Or(items) => mutate_chain(items, AstNode::Or, false, out),
+		AstNode::Xor(items) => mutate_chain(items, AstNode::Xor, false, out),
+		_ => {}
+	}
+}
+
+/// Mutants of a boolean chain: dropped operands (for `AND`), then mutants of each operand
+fn mutate_chain(items: &[AstNode], rebuild: fn(Vec<AstNode>) -> AstNode, drop: bool, out: &mut Vec<Mutation>) {
+	for (i, item) in items.iter().enumerate() {
+		if drop && items.len() > 1 {
+			let mut remaining = items.to_vec();
+			remaining.remove(i);
+			out.push(Mutation {
+				kind: MutationKind::DropConjunct,
+				original: format_ast(item, &FormatOptions::default()),
+				replacement: None,
+				condition: rebuild(remaining),
+			});
+		}
+
+		let start = out.len();
+		mutate_node(item, out);
+		for mutation in &mut out[start..] {
+			let mut rebuilt = items.to_vec();
+			rebuilt[i] = core::mem::replace(&mut mutation.condition, AstNode::Bool(false));
+			mutation.condition = rebuild(rebuilt);
+		}
+	}
+}
+
+fn atom_mutation(kind: MutationKind, original: &AstNode, mutant: AstNode) -> Mutation {
+	let options = FormatOptions::default();
+	Mutation {
+		kind,
+		original: format_ast(original, &options),
+		replacement: Some(format_ast(&mutant, &options)),
+		condition: mutant,
+	}
+}
+
+/// Comparators a typo or an off-by-one could have produced instead of `op`
+fn flipped_comparators(op: Comparator) -> &'static [Comparator] {
+	match op {
+		Comparator::Eq => &[Comparator::Ne],
+		Comparator::Ne => &[Comparator::Eq],
+		Comparator::Gt => &[Comparator::Ge, Comparator::Lt],
+		Comparator::Ge => &[Comparator::Gt, Comparator::Le],
+		Comparator::Lt => &[Comparator::Le, Comparator::Gt],
+		Comparator::Le => &[Comparator::Lt, Comparator::Ge],
+		_ => &[],
+	}
+}
+
+/// A numeric literal moved down and up, or nothing for other operands
+fn perturbed_literals(node: &AstNode) -> Vec<AstNode> {
+	match *node {
+		AstNode::Number(n) => n
+			.checked_sub(1)
+			.into_iter()
+			.chain(n.checked_add(1))
+			.map(AstNode::Number)
+			.collect(),
+		AstNode::Float(f) => {
+			let delta = if f == 0.0 { 0.1 } else { f.abs() * 0.1 };
+			vec![AstNode::Float(f - delta), AstNode::Float(f + delta)]
+		}
+		_ => Vec::new(),
+	}
+}
+
+// endregion: --- Mutation
+
+// region:    --- Mutation Testing
+
+/// Result of evaluating one mutant against the corpus
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutantOutcome {
+	/// The mutant
+	pub mutation: Mutation,
+	/// First record that the original rule classifies correctly and the mutant does not
+	pub killed_by: Option<usize>,
+}
+
+/// Mutation testing results for one rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutationReport {
+	/// Rule id
+	pub rule_id: Arc<str>,
+	/// Number of labeled records
+	pub records: usize,
+	/// Records the unmutated rule misclassifies or fails to evaluate
+	pub baseline_failures: Vec<usize>,
+	/// Every mutant, in source order
+	pub mutants: Vec<MutantOutcome>,
+}
+
+impl MutationReport {
+	/// Mutants no record could distinguish from the original rule
+	pub fn survivors(&self) -> impl Iterator<Item = &Mutation> {
+		self.mutants
+			.iter()
+			.filter(|m| m.killed_by.is_none())
+			.map(|m| &m.mutation)
+	}
+
+	/// Fraction of mutants killed (1.0 when the rule has no mutants)
+	pub fn score(&self) -> f64 {
+		if self.mutants.is_empty() {
+			return 1.0;
+		}
+		let killed = self.mutants.iter().filter(|m| m.killed_by.is_some()).count();
+		killed as f64 / self.mutants.len() as f64
+	}
+}
+
+/// Mutate `rule` and evaluate every mutant against a labeled corpus
+///
+/// Each record is paired with whether the rule is expected to match it. A mutant
+/// is killed by a record the original rule classifies correctly when the mutant
+/// does not (a mutant that fails to evaluate counts as misclassifying). Like
+/// `Rule::evaluate()`, rules referencing other rules with `rule("id")` fail here.
+///
+/// # Examples
+///
+/// ```
+/// use hel::mutate::mutation_test;
+/// use hel::rules::Rule;
+/// use hel::{FactsEvalContext, Value};
+///
+/// let rule = Rule::new("packed", "binary.entropy > 7").unwrap();
+/// let corpus: Vec<(FactsEvalContext, bool)> = [(3, false), (9, true)]
+///     .into_iter()
+///     .map(|(entropy, packed)| {
+///         let mut ctx = FactsEvalContext::new();
+///         ctx.add_fact("binary.entropy", Value::Number(entropy as f64));
+///         (ctx, packed)
+///     })
+///     .collect();
+///
+/// let report = mutation_test(&rule, &corpus);
+/// // Nothing in the corpus sits near the threshold, so `>=` and `> 6` / `> 8` survive
+/// let survivors: Vec<_> = report.survivors().filter_map(|m| m.replacement.clone()).collect();
+/// assert_eq!(survivors, ["binary.entropy >= 7", "binary.entropy > 6", "binary.entropy > 8"]);
+/// ```
+pub fn mutation_test<R: HelResolver>(rule: &Rule, corpus: &[(R, bool)]) -> MutationReport {
+	let verdict = |condition: &AstNode, resolver: &R| {
+		crate::evaluate_ast_with_context(condition, &EvalContext::new(resolver)).ok()
+	};
+
+	let mut baseline_failures = Vec::new();
+	let mut covered = Vec::new();
+	for (index, (resolver, expected)) in corpus.iter().enumerate() {
+		if verdict(&rule.condition, resolver) == Some(*expected) {
+			covered.push(index);
+		} else {
+			baseline_failures.push(index);
+		}
+	}
+
+	let mutants = mutations(&rule.condition)
+		.into_iter()
+		.map(|mutation| {
+			let killed_by = covered.iter().copied().find(|&index| {
+				let (resolver, expected) = &corpus[index];
+				verdict(&mutation.condition, resolver) != Some(*expected)
+			});
+			MutantOutcome { mutation, killed_by }
+		})
+		.collect();
+
+	MutationReport {
+		rule_id: rule.id.clone(),
+		records: corpus.len(),
+		baseline_failures,
+		mutants,
+	}
+}
+
+// endregion: --- Mutation Testing
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{FactsEvalContext, Value};
+
+	fn record(entropy: f64, signed: bool) -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(entropy));
+		ctx.add_fact("binary.signed", Value::Bool(signed));
+		ctx
+	}
+
+	#[test]
+	fn test_mutation_report() {
+		let rule = Rule::new("packed", "binary.entropy > 7.5 AND binary.signed == false").unwrap();
+		let mutants = mutations(&rule.condition);
+		let kinds: Vec<MutationKind> = mutants.iter().map(|m| m.kind).collect();
+		assert_eq!(
+			kinds,
+			vec![
+				MutationKind::DropConjunct,
+				MutationKind::FlipComparator,
+				MutationKind::FlipComparator,
+				MutationKind::PerturbThreshold,
+				MutationKind::PerturbThreshold,
+				MutationKind::DropConjunct,
+				MutationKind::FlipComparator,
+			]
+		);
+		assert_eq!(mutants[0].original, "binary.entropy > 7.5");
+		assert_eq!(mutants[4].replacement.as_deref(), Some("binary.entropy > 8.25"));
+
+		// Never exercises the signature check, and no record sits between 6.75 and 8.25
+		let weak = vec![(record(9.0, false), true), (record(2.0, false), false)];
+		let report = mutation_test(&rule, &weak);
+		assert!(report.baseline_failures.is_empty());
+		let survivors: Vec<Option<&str>> = report.survivors().map(|m| m.replacement.as_deref()).collect();
+		assert_eq!(
+			survivors,
+			vec![
+				Some("binary.entropy >= 7.5"),
+				Some("binary.entropy > 6.75"),
+				Some("binary.entropy > 8.25"),
+				None,
+			]
+		);
+		assert_eq!(report.mutants[0].killed_by, Some(1));
+
+		let strong = vec![
+			(record(9.0, false), true),
+			(record(2.0, false), false),
+			(record(7.5, false), false),
+			(record(7.0, false), false),
+			(record(8.0, false), true),
+			(record(9.0, true), false),
+		];
+		let report = mutation_test(&rule, &strong);
+		assert_eq!(report.survivors().count(), 0);
+		assert_eq!(report.score(), 1.0);
+
+		let mislabeled = vec![(record(9.0, true), true)];
+		assert_eq!(mutation_test(&rule, &mislabeled).baseline_failures, vec![0]);
+	}
+}
+
+// endregion: --- Tests
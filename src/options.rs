@@ -0,0 +1,438 @@
+//! Evaluation options for HEL
+//!
+//! Options that control how an expression is evaluated, independent of the
+//! expression itself and of the facts it is evaluated against. One `EvalOptions`
+//! value is accepted by every `*_with_options` entry point (`evaluate_with_options()`,
+//! `evaluate_script_with_options()`, `evaluate_with_trace_options()`) and by
+//! `RuleSet::with_options()`, so new settings are added here rather than as parameters.
+//!
+//! ## Parsing and Tracing
+//! - `ParseLimits` bound the size and nesting of expressions parsed from text
+//! - `TraceOptions` control how much of each value traces keep, and timings
+//!
+//! ## Cancellation
+//! - A deadline bounds the wall-clock time of a single evaluation (requires `std`)
+//! - A `CancellationToken` lets another thread abort an evaluation in flight
+//! - Both are checked cooperatively at AST node boundaries and around built-in
+//!   calls, so a single long-running built-in is only interrupted after it returns
+//!
+//! ## Numeric Tolerance
+//! - Off by default: numbers compare exactly, so `0.1 + 0.2`-style results may not equal `0.3`
+//! - With `with_numeric_tolerance(eps)`, two numbers are equal if `l == r` or `|l - r| <= eps`
+//! - Applies to `==`, `!=`, `EQI` and element equality in `CONTAINS` / `IN`; ordering
+//!   comparators, ranges and `LIKE` are unaffected
+//! - NaN is never equal to anything; infinities only equal themselves
+//! - The tolerance is absolute: choose it in the unit of the facts being compared
+//!
+//! ## Non-finite Numbers
+//! - By default NaN compares unequal and unordered, so bad data silently makes atoms false
+//! - `NonFinitePolicy::Reject` fails evaluation instead, naming the fact, wildcard match or
+//!   built-in call that produced NaN or ±infinity (including inside lists and maps)
+//!
+//! ## Call Quotas
+//! - `with_call_quota(ns, n)` allows at most `n` built-in calls into namespace `ns` per evaluation
+//! - Unqualified calls count against the namespace they resolve to; namespaces are case-insensitive
+//! - Exceeding a quota fails with `EvalError::QuotaExceeded`; each rule of a `RuleSet` is counted separately
+//!
+//! ## Built-in Input Limits
+//! - `with_max_distance_input_len(n)` bounds the strings `core.levenshtein` and `core.similarity`
+//!   accept (`CoreBuiltinsProvider::DEFAULT_MAX_DISTANCE_INPUT_LEN` characters by default)
+//! - Built-ins read these through `BuiltinsProvider::get_builtins_with_options`
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::prelude::*;
+use crate::trace::TraceOptions;
+use crate::{EvalError, ParseLimits, Value};
+
+// region:    --- CancellationToken
+
+/// Shared flag used to cancel evaluations from another thread
+///
+/// Clones share the same flag; cancelling any clone cancels them all.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+	cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+	/// Create a new, non-cancelled token
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Request cancellation of every evaluation observing this token
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	/// Check if cancellation has been requested
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+}
+
+// endregion: --- CancellationToken
+
+// region:    --- NonFinitePolicy
+
+/// How evaluation treats NaN and ±infinity produced by facts and built-ins
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+	/// Evaluate normally: comparisons involving NaN are false
+	#[default]
+	Allow,
+	/// Fail with `EvalError::TypeMismatch`, naming where the value came from
+	Reject,
+}
+
+// endregion: --- NonFinitePolicy
+
+// region:    --- EvalOptions
+
+/// Options applied to a single evaluation
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_with_options, EvalError, EvalOptions, FactsEvalContext};
+/// use std::time::Duration;
+///
+/// let ctx = FactsEvalContext::new();
+///
+/// let options = EvalOptions::new().with_timeout(Duration::from_secs(1));
+/// assert!(evaluate_with_options("true", &ctx, None, &options).unwrap());
+///
+/// let expired = EvalOptions::new().with_timeout(Duration::ZERO);
+/// let err = evaluate_with_options("true", &ctx, None, &expired).unwrap_err();
+/// assert!(matches!(err, EvalError::Timeout));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EvalOptions {
+	/// Instant after which evaluation fails with `EvalError::Timeout`
+	#[cfg(feature = "std")]
+	pub deadline: Option<Instant>,
+	/// Token that fails evaluation with `EvalError::Cancelled` once cancelled
+	pub cancellation: Option<CancellationToken>,
+	/// Absolute tolerance for numeric equality (exact if `None`)
+	pub numeric_tolerance: Option<f64>,
+	/// Whether NaN and ±infinity are evaluated or rejected
+	pub non_finite: NonFinitePolicy,
+	/// Limits for expressions and scripts parsed from text
+	pub limits: ParseLimits,
+	/// How much of each value traces keep (trace entry points only)
+	pub trace: TraceOptions,
+	/// Maximum built-in calls per evaluation, by lower-case namespace (unlimited if absent)
+	pub call_quotas: BTreeMap<String, u32>,
+	/// Longest input of `core.levenshtein` and `core.similarity`, in characters
+	/// (`CoreBuiltinsProvider::DEFAULT_MAX_DISTANCE_INPUT_LEN` if `None`)
+	pub max_distance_input_len: Option<usize>,
+}
+
+impl EvalOptions {
+	/// Create options with no deadline, no cancellation token, exact numeric equality,
+	/// non-finite numbers allowed, default parse limits and full traces
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set a deadline relative to now
+	#[cfg(feature = "std")]
+	pub fn with_timeout(self, timeout: Duration) -> Self {
+		self.with_deadline(Instant::now() + timeout)
+	}
+
+	/// Set an absolute deadline
+	#[cfg(feature = "std")]
+	pub fn with_deadline(mut self, deadline: Instant) -> Self {
+		self.deadline = Some(deadline);
+		self
+	}
+
+	/// Attach a cancellation token
+	pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+		self.cancellation = Some(token);
+		self
+	}
+
+	/// Treat numbers within `epsilon` of each other as equal (see the module docs)
+	pub fn with_numeric_tolerance(mut self, epsilon: f64) -> Self {
+		self.numeric_tolerance = Some(epsilon);
+		self
+	}
+
+	/// Set how NaN and ±infinity are handled
+	pub fn with_non_finite(mut self, policy: NonFinitePolicy) -> Self {
+		self.non_finite = policy;
+		self
+	}
+
+	/// Set the parse limits
+	pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Allow at most `max_calls` calls to functions of `namespace` per evaluation
+	///
+	/// Further calls fail with `EvalError::QuotaExceeded`, bounding the worst-case cost
+	/// of expensive namespaces (e.g., `regex`) in multi-tenant deployments. A rule set
+	/// counts each rule's evaluation separately.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{evaluate_with_options, BuiltinsRegistry, EvalError, EvalOptions, FactsEvalContext};
+	///
+	/// let ctx = FactsEvalContext::new();
+	/// let builtins = BuiltinsRegistry::builder().with_core().build().unwrap();
+	/// let options = EvalOptions::new().with_call_quota("core", 2);
+	///
+	/// let rule = r#"core.len([1]) == 1 AND core.upper("a") == "A""#;
+	/// assert!(evaluate_with_options(rule, &ctx, Some(&builtins), &options).unwrap());
+	///
+	/// let rule = r#"core.len([1]) == 1 AND core.upper("a") == "A" AND core.lower("A") == "a""#;
+	/// let err = evaluate_with_options(rule, &ctx, Some(&builtins), &options).unwrap_err();
+	/// assert!(matches!(err, EvalError::QuotaExceeded { limit: 2, .. }));
+	/// ```
+	pub fn with_call_quota(mut self, namespace: &str, max_calls: u32) -> Self {
+		self.call_quotas.insert(namespace.to_lowercase(), max_calls);
+		self
+	}
+
+	/// Accept strings of up to `max_chars` characters in `core.levenshtein` and `core.similarity`
+	///
+	/// Each call costs time proportional to the product of its input lengths; longer input
+	/// fails with the structured failure code `input_too_long`.
+	pub fn with_max_distance_input_len(mut self, max_chars: usize) -> Self {
+		self.max_distance_input_len = Some(max_chars);
+		self
+	}
+
+	/// Set the trace options
+	pub fn with_trace_options(mut self, trace: TraceOptions) -> Self {
+		self.trace = trace;
+		self
+	}
+
+	/// Check the deadline and cancellation token
+	///
+	/// Called by the evaluator at node boundaries; hosts rarely need to call it directly.
+	pub fn check_interrupt(&self) -> Result<(), EvalError> {
+		if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+			return Err(EvalError::Cancelled);
+		}
+		#[cfg(feature = "std")]
+		if self.deadline.is_some_and(|d| Instant::now() >= d) {
+			return Err(EvalError::Timeout);
+		}
+		Ok(())
+	}
+
+	/// Check a value produced by `source` (a fact path or built-in call) against the
+	/// non-finite policy
+	pub(crate) fn check_finite(&self, value: &Value, source: impl FnOnce() -> String) -> Result<(), EvalError> {
+		if self.non_finite == NonFinitePolicy::Allow {
+			return Ok(());
+		}
+		match first_non_finite(value) {
+			Some(n) => Err(EvalError::TypeMismatch {
+				expected: "finite number".to_string(),
+				got: n.to_string(),
+				context: format!("`{}`", source()),
+			}),
+			None => Ok(()),
+		}
+	}
+}
+
+fn first_non_finite(value: &Value) -> Option<f64> {
+	match value {
+		Value::Number(n) if !n.is_finite() => Some(*n),
+		Value::List(items) => items.iter().find_map(first_non_finite),
+		Value::Map(entries) => entries.values().find_map(first_non_finite),
+		_ => None,
+	}
+}
+
+// endregion: --- EvalOptions
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::builtins::{BuiltinFn, BuiltinsProvider, BuiltinsRegistry};
+	use crate::rules::RuleSet;
+	use crate::trace::TraceVerbosity;
+	use crate::{
+		evaluate_script_with_options, evaluate_with_options, evaluate_with_trace_options, ErrorKind, FactsEvalContext,
+		Value,
+	};
+	use std::collections::BTreeMap;
+
+	struct SlowProvider;
+
+	impl BuiltinsProvider for SlowProvider {
+		fn namespace(&self) -> &str {
+			"slow"
+		}
+
+		fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+			let mut builtins = BTreeMap::new();
+			builtins.insert(
+				"sleep".to_string(),
+				Arc::new(|_args: &[Value]| -> Result<Value, EvalError> {
+					std::thread::sleep(Duration::from_millis(20));
+					Ok(Value::Bool(true))
+				}) as BuiltinFn,
+			);
+			builtins
+		}
+	}
+
+	#[test]
+	fn test_no_options_never_interrupts() {
+		assert!(EvalOptions::new().check_interrupt().is_ok());
+	}
+
+	#[test]
+	fn test_cancellation_token() {
+		let token = CancellationToken::new();
+		let options = EvalOptions::new().with_cancellation(token.clone());
+		let ctx = FactsEvalContext::new();
+
+		assert!(evaluate_with_options("true AND true", &ctx, None, &options).unwrap());
+
+		token.cancel();
+		let err = evaluate_with_options("true AND true", &ctx, None, &options).unwrap_err();
+		assert!(matches!(err, EvalError::Cancelled));
+	}
+
+	#[test]
+	fn test_numeric_tolerance() {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("file.score", Value::Number(0.1 + 0.2));
+		let exact = EvalOptions::new();
+		let tolerant = EvalOptions::new().with_numeric_tolerance(1e-9);
+		let eval = |expr: &str, options: &EvalOptions| evaluate_with_options(expr, &ctx, None, options).unwrap();
+
+		assert!(!eval("file.score == 0.3", &exact));
+		assert!(eval("file.score == 0.3", &tolerant));
+		assert!(!eval("file.score != 0.3", &tolerant));
+		assert!(eval("[0.1, 0.3] CONTAINS file.score", &tolerant));
+		assert!(eval("file.score IN [0.3]", &tolerant));
+		assert!(!eval("file.score == 0.31", &tolerant));
+		// Ordering stays exact
+		assert!(!eval("file.score <= 0.3", &tolerant));
+		// A negative tolerance is exact
+		assert!(!eval(
+			"file.score == 0.3",
+			&EvalOptions::new().with_numeric_tolerance(-1.0)
+		));
+	}
+
+	#[test]
+	fn test_non_finite_policy() {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(f64::NAN));
+		ctx.add_fact("binary.size", Value::Number(f64::INFINITY));
+		ctx.add_fact(
+			"binary.ratios",
+			Value::List(vec![Value::Number(0.5), Value::Number(f64::NAN)]),
+		);
+		ctx.add_fact("binary.format", Value::String("pe".into()));
+		ctx.add_fact(
+			"sections.text",
+			Value::Map([("entropy".into(), Value::Number(f64::NAN))].into()),
+		);
+		let reject = EvalOptions::new().with_non_finite(NonFinitePolicy::Reject);
+
+		assert!(!evaluate_with_options("binary.entropy > 7.5", &ctx, None, &EvalOptions::new()).unwrap());
+
+		let message = |expr: &str| {
+			evaluate_with_options(expr, &ctx, None, &reject)
+				.unwrap_err()
+				.to_string()
+		};
+		assert_eq!(
+			message("binary.entropy > 7.5"),
+			"Type mismatch in `binary.entropy`: expected finite number, got NaN"
+		);
+		assert!(message("binary.format == \"pe\" AND 1 < binary.size").contains("`binary.size`"));
+		assert!(message("binary.ratios CONTAINS 0.5").contains("`binary.ratios`"));
+		assert!(message("sections.*.entropy > 7.5").contains("`sections.text.entropy`"));
+		assert!(evaluate_with_options("binary.format == \"pe\"", &ctx, None, &reject).unwrap());
+	}
+
+	#[test]
+	fn test_options_accepted_by_entry_points() {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("file.score", Value::Number(0.1 + 0.2));
+		ctx.add_fact("file.ratio", Value::Number(f64::NAN));
+		let options = EvalOptions::new()
+			.with_numeric_tolerance(1e-9)
+			.with_non_finite(NonFinitePolicy::Reject)
+			.with_limits(ParseLimits {
+				max_nesting_depth: 1,
+				..ParseLimits::default()
+			})
+			.with_trace_options(TraceOptions::new().with_verbosity(TraceVerbosity::ResultsOnly));
+
+		assert!(evaluate_with_options("file.score == 0.3", &ctx, None, &options).unwrap());
+		let err = evaluate_with_options("((file.score == 0.3))", &ctx, None, &options).unwrap_err();
+		assert!(matches!(err, EvalError::ParseError(_)));
+
+		let script = "let close = file.score == 0.3\nclose";
+		assert!(evaluate_script_with_options(script, &ctx, None, &options).unwrap());
+		let err = evaluate_script_with_options("let r = file.ratio\nr > 1", &ctx, None, &options).unwrap_err();
+		assert!(matches!(err.kind, ErrorKind::TypeError));
+
+		let trace = evaluate_with_trace_options("file.score == 0.3", &ctx, None, &options).unwrap();
+		assert!(trace.result);
+		assert_eq!(trace.atoms[0].resolved_left_value, None);
+
+		let mut rules = RuleSet::new();
+		rules.add_rule("close", "file.score == 0.3").unwrap();
+		rules.add_rule("ratio", "file.ratio > 1").unwrap();
+		let results = rules.with_options(options).evaluate(&ctx);
+		assert!(matches!(results[0].1, Ok(true)));
+		assert!(matches!(results[1].1, Err(EvalError::TypeMismatch { .. })));
+	}
+
+	#[test]
+	fn test_deadline_checked_after_builtin_call() {
+		let mut registry = BuiltinsRegistry::new();
+		registry.register(&SlowProvider).expect("register failed");
+		let ctx = FactsEvalContext::new();
+
+		let options = EvalOptions::new().with_timeout(Duration::from_millis(5));
+		let err = evaluate_with_options("slow.sleep() == true", &ctx, Some(&registry), &options).unwrap_err();
+		assert!(matches!(err, EvalError::Timeout));
+	}
+
+	#[test]
+	fn test_call_quotas() {
+		let builtins = BuiltinsRegistry::builder().with_core().build().unwrap();
+		let ctx = FactsEvalContext::new();
+		let options = EvalOptions::new().with_call_quota("CORE", 2);
+		let eval = |expr: &str| evaluate_with_options(expr, &ctx, Some(&builtins), &options);
+
+		// Unqualified calls count against the namespace they resolve to
+		assert!(eval("core.len([1]) == 1 AND len([1, 2]) == 2").unwrap());
+		let err = eval("core.len([1]) == 1 AND len([1, 2]) == 2 AND Core.len([]) == 0").unwrap_err();
+		assert!(matches!(err, EvalError::QuotaExceeded { ref namespace, limit: 2 } if namespace == "core"));
+		assert_eq!(err.to_string(), "Call quota exceeded: more than 2 calls to `core` functions");
+		// Counts start afresh for every evaluation
+		assert!(eval("core.len([1]) == 1 AND len([1, 2]) == 2").unwrap());
+		// Namespaces without a quota are unlimited
+		let unlimited = EvalOptions::new().with_call_quota("regex", 0);
+		assert!(evaluate_with_options("len([]) == 0 AND len([]) == 0", &ctx, Some(&builtins), &unlimited).unwrap());
+	}
+}
+
+// endregion: --- Tests
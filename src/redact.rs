@@ -0,0 +1,291 @@
+//! Redaction of sensitive values in evaluation traces
+//!
+//! Resolved values captured in an `AtomTrace` may contain PII such as email
+//! addresses or access tokens. A `Redactor` is consulted for every resolved value
+//! before it is stored in a trace, so traces can be shared with auditors safely.
+//!
+//! ## Matching
+//! - By attribute path: `user.email` matches exactly, `user.*` matches every field of `user`
+//! - By value pattern: any predicate over the rendered value (e.g., "contains `@`")
+//! - A redacted literal operand is also replaced in the atom's expression text
+
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::trace::{AtomTrace, EvalTrace};
+
+/// Placeholder used by `RedactionPolicy` unless overridden
+pub const DEFAULT_PLACEHOLDER: &str = "[REDACTED]";
+
+// region:    --- Redactor
+
+/// Decides whether a resolved trace value must be hidden
+///
+/// `path` is the textual operand as shown in the trace (e.g., `user.email` or
+/// `"alice@example.com"`), `value` is the rendered resolved value.
+pub trait Redactor: Send + Sync {
+	/// Return the replacement text, or `None` to keep the value
+	fn redact(&self, path: &str, value: &str) -> Option<String>;
+}
+
+impl<F> Redactor for F
+where
+	F: Fn(&str, &str) -> Option<String> + Send + Sync,
+{
+	fn redact(&self, path: &str, value: &str) -> Option<String> {
+		self(path, value)
+	}
+}
+
+// endregion: --- Redactor
+
+// region:    --- RedactionPolicy
+
+type ValuePredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Configurable redactor matching attribute paths and value patterns
+///
+/// # Examples
+///
+/// ```
+/// use hel::redact::RedactionPolicy;
+/// use hel::trace::evaluate_with_trace_redacted;
+/// use hel::{FactsEvalContext, Value};
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("user.email", Value::String("alice@example.com".into()));
+///
+/// let policy = RedactionPolicy::new().redact_values_matching(|v| v.contains('@'));
+/// let trace = evaluate_with_trace_redacted(r#"user.email == "alice@example.com""#, &ctx, None, &policy).unwrap();
+///
+/// assert!(trace.result);
+/// assert!(!trace.pretty_print().contains("alice"));
+/// ```
+#[derive(Clone)]
+pub struct RedactionPolicy {
+	placeholder: String,
+	paths: Vec<String>,
+	value_patterns: Vec<ValuePredicate>,
+}
+
+impl RedactionPolicy {
+	/// Create an empty policy (redacts nothing)
+	pub fn new() -> Self {
+		Self {
+			placeholder: DEFAULT_PLACEHOLDER.to_string(),
+			paths: Vec::new(),
+			value_patterns: Vec::new(),
+		}
+	}
+
+	/// Replace the placeholder text
+	pub fn with_placeholder(mut self, placeholder: &str) -> Self {
+		self.placeholder = placeholder.to_string();
+		self
+	}
+
+	/// Redact values of an attribute path (`object.field` or `object.*`)
+	pub fn redact_path(mut self, path: &str) -> Self {
+		self.paths.push(path.to_string());
+		self
+	}
+
+	/// Redact any value for which `predicate` returns true
+	pub fn redact_values_matching(mut self, predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+		self.value_patterns.push(Arc::new(predicate));
+		self
+	}
+
+	fn matches_path(&self, path: &str) -> bool {
+		self.paths.iter().any(|p| path_matches(p, path))
+	}
+}
+
+impl Default for RedactionPolicy {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Redactor for RedactionPolicy {
+	fn redact(&self, path: &str, value: &str) -> Option<String> {
+		if self.matches_path(path) || self.value_patterns.iter().any(|p| p(value)) {
+			Some(self.placeholder.clone())
+		} else {
+			None
+		}
+	}
+}
+
+// endregion: --- RedactionPolicy
+
+// region:    --- Applying
+
+impl AtomTrace {
+	/// Apply a redactor to both operands of this atom and to each wildcard match
+	///
+	/// If any match is redacted, the wildcard side's resolved list is rebuilt from the
+	/// redacted match values.
+	pub fn redact(&mut self, redactor: &dyn Redactor) {
+		let mut redacted_match = false;
+		for matched in &mut self.matches {
+			if let Some(replacement) = redactor.redact(&matched.path, &matched.value) {
+				matched.value = replacement;
+				redacted_match = true;
+			}
+		}
+		if redacted_match {
+			let values = self.matches.iter().map(|m| m.value.as_str()).collect::<Vec<_>>();
+			let list = Some(format!("[{}]", values.join(", ")));
+			match self.left.contains(".*.") {
+				true => self.resolved_left_value = list,
+				false => self.resolved_right_value = list,
+			}
+		}
+		redact_side(&mut self.left, &mut self.resolved_left_value, redactor);
+		redact_side(&mut self.right, &mut self.resolved_right_value, redactor);
+	}
+}
+
+impl EvalTrace {
+	/// Apply a redactor to every atom of an already captured trace
+	pub fn redact(&mut self, redactor: &dyn Redactor) {
+		for atom in &mut self.atoms {
+			atom.redact(redactor);
+		}
+	}
+}
+
+/// Match an attribute path against `object.field` or `object.*`
+pub(crate) fn path_matches(pattern: &str, path: &str) -> bool {
+	match pattern.strip_suffix(".*") {
+		Some(object) => path.strip_prefix(object).is_some_and(|rest| rest.starts_with('.')),
+		None => pattern == path,
+	}
+}
+
+fn redact_side(expr: &mut String, value: &mut Option<String>, redactor: &dyn Redactor) {
+	let Some(resolved) = value.as_deref() else {
+		return;
+	};
+	let Some(replacement) = redactor.redact(expr, resolved) else {
+		return;
+	};
+
+	// Literal operands render their value in the expression text as well
+	let is_literal = *expr == *resolved || *expr == format!("\"{}\"", resolved);
+	if is_literal {
+		*expr = replacement.clone();
+	}
+	*value = Some(replacement);
+}
+
+// endregion: --- Applying
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::trace::evaluate_with_trace_redacted;
+	use crate::{FactsEvalContext, Value};
+
+	fn facts() -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("user.email", Value::String("alice@example.com".into()));
+		ctx.add_fact("user.token", Value::String("s3cr3t".into()));
+		ctx.add_fact("binary.format", Value::String("elf".into()));
+		ctx
+	}
+
+	#[test]
+	fn test_path_wildcard_redaction() {
+		let policy = RedactionPolicy::new().redact_path("user.*");
+		let trace = evaluate_with_trace_redacted(
+			r#"user.token == "s3cr3t" AND binary.format == "elf""#,
+			&facts(),
+			None,
+			&policy,
+		)
+		.expect("evaluation failed");
+
+		assert!(trace.result, "redaction must not change the result");
+		assert_eq!(trace.atoms[0].left, "user.token");
+		assert_eq!(trace.atoms[0].resolved_left_value.as_deref(), Some(DEFAULT_PLACEHOLDER));
+		assert_eq!(trace.atoms[1].resolved_left_value.as_deref(), Some("elf"));
+		assert!(!policy.matches_path("username.token"));
+	}
+
+	#[test]
+	fn test_malformed_rule_is_parse_error() {
+		let policy = RedactionPolicy::new().redact_path("user.*");
+		let err = evaluate_with_trace_redacted("user.token ==", &facts(), None, &policy).unwrap_err();
+		assert!(matches!(err, crate::EvalError::ParseError(_)));
+	}
+
+	#[test]
+	fn test_wildcard_matches_are_redacted() {
+		let mut ctx = facts();
+		let key = |value: &str| Value::Map([(Arc::from("key"), Value::String(value.into()))].into());
+		ctx.add_fact("creds.aws", key("AKIA1"));
+		ctx.add_fact("creds.gcp", key("ya29"));
+		let policy = RedactionPolicy::new().redact_path("creds.aws.key");
+		let trace =
+			evaluate_with_trace_redacted(r#"creds.*.key == "ya29""#, &ctx, None, &policy).expect("evaluation failed");
+
+		let atom = &trace.atoms[0];
+		assert!(atom.atom_result);
+		assert_eq!(atom.matches[0].value, DEFAULT_PLACEHOLDER);
+		assert_eq!(atom.matches[1].value, "ya29");
+		assert!(!trace.to_string().contains("AKIA1"), "{}", trace);
+	}
+
+	#[test]
+	fn test_value_pattern_redacts_literals() {
+		let policy = RedactionPolicy::new()
+			.with_placeholder("***")
+			.redact_values_matching(|v| v.contains('@'));
+		let trace = evaluate_with_trace_redacted(r#"user.email == "alice@example.com""#, &facts(), None, &policy)
+			.expect("evaluation failed");
+
+		let atom = &trace.atoms[0];
+		assert_eq!(atom.right, "***");
+		assert_eq!(atom.resolved_left_value.as_deref(), Some("***"));
+		assert_eq!(atom.resolved_right_value.as_deref(), Some("***"));
+		assert!(!trace.to_string().contains("alice"));
+	}
+
+	#[test]
+	fn test_closure_redactor_on_captured_trace() {
+		let mut trace =
+			crate::evaluate_with_trace(r#"user.token == "s3cr3t""#, &facts(), None).expect("evaluation failed");
+		trace.redact(&|path: &str, _value: &str| (path == "user.token").then(|| "<token>".to_string()));
+
+		assert_eq!(trace.atoms[0].resolved_left_value.as_deref(), Some("<token>"));
+		assert_eq!(trace.atoms[0].resolved_right_value.as_deref(), Some("s3cr3t"));
+	}
+
+	#[test]
+	fn test_redacted_trace_with_options() {
+		let policy = RedactionPolicy::new().redact_path("user.*");
+		let options = crate::EvalOptions::new().with_trace_options(
+			crate::TraceOptions::new()
+				.with_verbosity(crate::TraceVerbosity::Truncated)
+				.with_max_value_len(2),
+		);
+		let trace = crate::trace::evaluate_with_trace_redacted_options(
+			r#"user.token == "s3cr3t" AND binary.format == "elf""#,
+			&facts(),
+			None,
+			&policy,
+			&options,
+		)
+		.expect("evaluation failed");
+
+		assert!(trace.result);
+		assert_eq!(trace.atoms[0].resolved_left_value.as_deref(), Some("[R…"));
+		assert_eq!(trace.atoms[1].resolved_left_value.as_deref(), Some("el…"));
+	}
+}
+
+// endregion: --- Tests
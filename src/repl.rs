@@ -0,0 +1,244 @@
+//! Interactive rule prototyping
+//!
+//! `ReplSession` is the line-at-a-time evaluator behind `hel repl`: it keeps
+//! `let` bindings between lines, evaluates expressions against a resolver and
+//! renders traces on demand. Line editing and history live in the binary.
+//!
+//! ## Input
+//! - `let name = expr` binds the value of `expr` for later lines
+//! - `expr` prints the value of a bare operand (`binary.entropy`) or the rule result
+//! - `:trace expr` evaluates `expr` and prints its atom trace
+//! - `:bindings`, `:clear`, `:help`
+
+use std::sync::Arc;
+
+use crate::analysis::{Analyzer, Completion, CompletionKind};
+use crate::builtins::BuiltinsRegistry;
+use crate::trace::trace_ast;
+use crate::{
+	check_binding_name, eval_node_to_value_with_context, parse_expression, single_operand, split_comment, EvalContext,
+	HelError, HelResolver, Value,
+};
+
+/// Help text printed by `:help`
+pub const HELP: &str = "\
+let name = expr   bind a value for later lines
+expr              evaluate an expression
+:trace expr       evaluate and print the atom trace
+:bindings         list let bindings
+:clear            drop all let bindings
+:help             show this help";
+
+// region:    --- ReplSession
+
+/// Stateful evaluator for REPL input lines
+pub struct ReplSession<'a> {
+	resolver: &'a dyn HelResolver,
+	builtins: Option<&'a BuiltinsRegistry>,
+	analyzer: Analyzer<'a>,
+	bindings: Vec<(Arc<str>, Value)>,
+}
+
+impl<'a> ReplSession<'a> {
+	/// Create a session evaluating against `resolver`
+	pub fn new(resolver: &'a dyn HelResolver) -> Self {
+		Self {
+			resolver,
+			builtins: None,
+			analyzer: Analyzer::new(),
+			bindings: Vec::new(),
+		}
+	}
+
+	/// Make built-in functions callable (and completable)
+	pub fn with_builtins(mut self, builtins: &'a BuiltinsRegistry) -> Self {
+		self.builtins = Some(builtins);
+		self.analyzer = self.analyzer.with_builtins(builtins);
+		self
+	}
+
+	/// Analyzer used for completion (e.g., with a type environment for attribute completion)
+	///
+	/// Call after `with_builtins` if the analyzer should also complete built-ins.
+	pub fn with_analyzer(mut self, analyzer: Analyzer<'a>) -> Self {
+		self.analyzer = analyzer;
+		self
+	}
+
+	/// Current let bindings, in definition order
+	pub fn bindings(&self) -> &[(Arc<str>, Value)] {
+		&self.bindings
+	}
+
+	/// Evaluate one input line and return the text to print
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::repl::ReplSession;
+	/// use hel::{FactsEvalContext, Value};
+	///
+	/// let mut facts = FactsEvalContext::new();
+	/// facts.add_fact("binary.entropy", Value::Number(7.9));
+	/// let mut session = ReplSession::new(&facts);
+	///
+	/// assert_eq!(session.eval_line("let packed = binary.entropy > 7.5").unwrap(), "packed = true");
+	/// assert_eq!(session.eval_line("packed AND binary.entropy < 8").unwrap(), "true");
+	/// assert_eq!(session.eval_line("binary.entropy").unwrap(), "7.9");
+	/// ```
+	pub fn eval_line(&mut self, line: &str) -> Result<String, HelError> {
+		let line = split_comment(line).0.trim();
+		if line.is_empty() {
+			return Ok(String::new());
+		}
+
+		if let Some(command) = line.strip_prefix(':') {
+			return self.command(command);
+		}
+
+		if let Some(rest) = line.strip_prefix("let ") {
+			let (name, expr) = rest
+				.split_once('=')
+				.ok_or_else(|| HelError::parse_error("Expected `let name = expression`".to_string()))?;
+			let name = name.trim();
+			check_binding_name(name)?;
+			let value = self.evaluate(expr.trim())?;
+			let rendered = format!("{} = {}", name, render_value(&value));
+			self.bindings.retain(|(n, _)| n.as_ref() != name);
+			self.bindings.push((Arc::from(name), value));
+			return Ok(rendered);
+		}
+
+		self.evaluate(line).map(|value| render_value(&value))
+	}
+
+	/// Completion candidates for the word ending at `offset`, including let bindings
+	pub fn completions(&self, line: &str, offset: usize) -> Vec<Completion> {
+		let mut items = self.analyzer.completions(line, offset);
+		let word_start = line[..offset]
+			.rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+			.map_or(0, |i| i + 1);
+		let word = &line[word_start..offset];
+		if !word.contains('.') {
+			items.extend(
+				self.bindings
+					.iter()
+					.filter(|(name, _)| name.starts_with(word))
+					.map(|(name, value)| Completion {
+						label: name.to_string(),
+						kind: CompletionKind::Object,
+						detail: Some(render_value(value)),
+						documentation: None,
+					}),
+			);
+		}
+		items
+	}
+
+	fn command(&mut self, command: &str) -> Result<String, HelError> {
+		let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+		match name {
+			"trace" => {
+				let ast = parse_expression(arg.trim())?;
+				let trace = trace_ast(&ast, &self.context()).map_err(HelError::from)?;
+				Ok(trace.pretty_print().trim_end().to_string())
+			}
+			"bindings" => Ok(self
+				.bindings
+				.iter()
+				.map(|(name, value)| format!("{} = {}", name, render_value(value)))
+				.collect::<Vec<_>>()
+				.join("\n")),
+			"clear" => {
+				self.bindings.clear();
+				Ok(String::new())
+			}
+			"help" => Ok(HELP.to_string()),
+			_ => Err(HelError::parse_error(format!(
+				"Unknown command `:{}` (try :help)",
+				name
+			))),
+		}
+	}
+
+	/// Evaluate an expression to a value; bare operands yield their value, not a boolean
+	fn evaluate(&self, expr: &str) -> Result<Value, HelError> {
+		let ast = parse_expression(expr)?;
+		eval_node_to_value_with_context(single_operand(&ast), &self.context()).map_err(HelError::from)
+	}
+
+	fn context(&self) -> EvalContext<'a> {
+		let ctx = match self.builtins {
+			Some(builtins) => EvalContext::with_builtins(self.resolver, builtins),
+			None => EvalContext::new(self.resolver),
+		};
+		self.bindings
+			.iter()
+			.fold(ctx, |ctx, (name, value)| ctx.with_variable(name.clone(), value.clone()))
+	}
+}
+
+// endregion: --- ReplSession
+
+// region:    --- Rendering
+
+/// Render a value in HEL literal syntax
+fn render_value(value: &Value) -> String {
+	match value {
+		Value::Null => "null".to_string(),
+		Value::Bool(b) => b.to_string(),
+		Value::String(s) => format!("{:?}", s.as_ref()),
+		Value::Number(n) => n.to_string(),
+		Value::List(items) => format!("[{}]", items.iter().map(render_value).collect::<Vec<_>>().join(", ")),
+		Value::Map(entries) => format!(
+			"{{{}}}",
+			entries
+				.iter()
+				.map(|(k, v)| format!("{:?}: {}", k.as_ref(), render_value(v)))
+				.collect::<Vec<_>>()
+				.join(", ")
+		),
+	}
+}
+
+// endregion: --- Rendering
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{CoreBuiltinsProvider, FactsEvalContext};
+
+	#[test]
+	fn test_session_bindings_trace_and_completion() {
+		let mut facts = FactsEvalContext::new();
+		facts.add_fact("app.perms", Value::List(vec!["SMS".into(), "NET".into()]));
+		let mut builtins = BuiltinsRegistry::new();
+		builtins.register(&CoreBuiltinsProvider).expect("register failed");
+		let mut session = ReplSession::new(&facts).with_builtins(&builtins);
+
+		assert_eq!(
+			session.eval_line("let perm_count = core.len(app.perms)").unwrap(),
+			"perm_count = 2"
+		);
+		assert_eq!(session.eval_line("let perm_count = 3").unwrap(), "perm_count = 3");
+		assert_eq!(session.bindings().len(), 1);
+		assert_eq!(session.eval_line("app.perms").unwrap(), r#"["SMS", "NET"]"#);
+
+		let trace = session.eval_line(r#":trace app.perms CONTAINS "SMS""#).unwrap();
+		assert!(trace.contains("app.perms"), "{}", trace);
+
+		let labels: Vec<String> = session.completions("perm", 4).into_iter().map(|c| c.label).collect();
+		assert_eq!(labels, vec!["perm_count"]);
+		let labels: Vec<String> = session.completions("core.len", 8).into_iter().map(|c| c.label).collect();
+		assert_eq!(labels, vec!["len"]);
+
+		assert!(session.eval_line(":nope").is_err());
+		assert!(session.eval_line("let = 1").is_err());
+		session.eval_line(":clear").unwrap();
+		assert!(session.bindings().is_empty());
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,457 @@
+//! Replay of recorded evaluations
+//!
+//! An `EvalSnapshot` captures everything one evaluation read: the rule source and
+//! its hash, every resolved fact value (and every miss), and the stable hash of the
+//! resulting trace. `replay` re-runs the rule purely from the snapshot, so incident
+//! reviewers can reproduce a past decision without access to the original resolver.
+//!
+//! ## Determinism
+//! - Replay fails if the rule text no longer matches its recorded hash
+//! - A lookup the snapshot did not record fails the replay instead of resolving to null
+//! - The replayed trace must hash to the recorded `EvalTrace::stable_hash`
+//! - Snapshots serialize to JSON with sorted keys (`to_json` / `from_json`)
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Value as Json};
+
+use crate::audit::rule_hash;
+use crate::builtins::BuiltinsRegistry;
+use crate::facts::json_to_value;
+use crate::trace::{trace_ast, EvalTrace};
+use crate::{parse_expression_with_limits, EvalError, EvalOptions, HelError, HelResolver, Value};
+
+// region:    --- EvalSnapshot
+
+/// Rule source and every fact value one evaluation resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalSnapshot {
+	/// Rule source text
+	pub rule_source: String,
+	/// SHA-256 of `rule_source` (see `audit::rule_hash`)
+	pub rule_hash: String,
+	/// Resolved attribute values by path (`object.field`)
+	pub facts: BTreeMap<String, Value>,
+	/// Attribute paths the resolver had no value for
+	pub missing: BTreeSet<String>,
+	/// Wildcard matches by pattern (`object.*.field`), possibly empty
+	pub wildcards: BTreeMap<String, Vec<(String, Value)>>,
+	/// `EvalTrace::stable_hash` of the recorded evaluation
+	pub trace_hash: String,
+}
+
+impl EvalSnapshot {
+	/// Evaluate a rule with tracing and record a snapshot of the facts it read
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::replay::{replay, EvalSnapshot};
+	/// use hel::{FactsEvalContext, Value};
+	///
+	/// let mut facts = FactsEvalContext::new();
+	/// facts.add_fact("binary.format", Value::String("elf".into()));
+	/// facts.add_fact("binary.entropy", Value::Number(7.9));
+	///
+	/// let (trace, snapshot) = EvalSnapshot::capture(r#"binary.format == "elf""#, &facts, None).unwrap();
+	/// assert!(trace.result);
+	/// assert_eq!(snapshot.facts.len(), 1);
+	///
+	/// // Later, without the original facts
+	/// let stored = snapshot.to_json().to_string();
+	/// let replayed = replay(&EvalSnapshot::from_json(&stored).unwrap(), None).unwrap();
+	/// assert_eq!(replayed.to_string(), trace.to_string());
+	/// ```
+	pub fn capture(
+		rule_source: &str,
+		resolver: &dyn HelResolver,
+		builtins: Option<&BuiltinsRegistry>,
+	) -> Result<(EvalTrace, EvalSnapshot), EvalError> {
+		Self::capture_with_options(rule_source, resolver, builtins, &EvalOptions::default())
+	}
+
+	/// `capture()` under explicit evaluation options
+	///
+	/// Options are not part of the snapshot: replay it with `replay_with_options()` and
+	/// the same options, or the replayed trace may not match.
+	pub fn capture_with_options(
+		rule_source: &str,
+		resolver: &dyn HelResolver,
+		builtins: Option<&BuiltinsRegistry>,
+		options: &EvalOptions,
+	) -> Result<(EvalTrace, EvalSnapshot), EvalError> {
+		let recorder = SnapshotRecorder {
+			inner: resolver,
+			recorded: RefCell::new(Recorded::default()),
+		};
+		let trace = run(rule_source, &recorder, builtins, options)?;
+		let recorded = recorder.recorded.into_inner();
+
+		let snapshot = EvalSnapshot {
+			rule_source: rule_source.to_string(),
+			rule_hash: rule_hash(rule_source),
+			facts: recorded.facts,
+			missing: recorded.missing,
+			wildcards: recorded.wildcards,
+			trace_hash: trace.stable_hash(rule_source),
+		};
+		Ok((trace, snapshot))
+	}
+
+	/// JSON form of the snapshot, for storage next to the decision it reproduces
+	pub fn to_json(&self) -> Json {
+		let facts: serde_json::Map<String, Json> = self
+			.facts
+			.iter()
+			.map(|(path, value)| (path.clone(), value_to_json(value)))
+			.collect();
+		let wildcards: serde_json::Map<String, Json> = self
+			.wildcards
+			.iter()
+			.map(|(pattern, matches)| {
+				let matches: Vec<Json> = matches
+					.iter()
+					.map(|(path, value)| json!([path, value_to_json(value)]))
+					.collect();
+				(pattern.clone(), Json::Array(matches))
+			})
+			.collect();
+		json!({
+			"rule_source": self.rule_source,
+			"rule_hash": self.rule_hash,
+			"facts": facts,
+			"missing": self.missing,
+			"wildcards": wildcards,
+			"trace_hash": self.trace_hash,
+		})
+	}
+
+	/// Read a snapshot written by `to_json`
+	pub fn from_json(json: &str) -> Result<Self, HelError> {
+		let root: Json =
+			serde_json::from_str(json).map_err(|e| HelError::parse_error(format!("Invalid snapshot JSON: {}", e)))?;
+		let text = |key: &str| {
+			root[key]
+				.as_str()
+				.map(str::to_string)
+				.ok_or_else(|| malformed(&format!("`{}` must be a string", key)))
+		};
+
+		let facts = match &root["facts"] {
+			Json::Object(entries) => entries
+				.iter()
+				.map(|(path, value)| (path.clone(), json_to_value(value.clone())))
+				.collect(),
+			_ => return Err(malformed("`facts` must be an object")),
+		};
+		let missing = match &root["missing"] {
+			Json::Array(paths) => paths
+				.iter()
+				.map(|path| path.as_str().map(str::to_string))
+				.collect::<Option<BTreeSet<String>>>()
+				.ok_or_else(|| malformed("`missing` must list strings"))?,
+			_ => return Err(malformed("`missing` must be an array")),
+		};
+		let mut wildcards = BTreeMap::new();
+		let Json::Object(entries) = &root["wildcards"] else {
+			return Err(malformed("`wildcards` must be an object"));
+		};
+		for (pattern, matches) in entries {
+			let matches = matches
+				.as_array()
+				.into_iter()
+				.flatten()
+				.map(|entry| match entry.as_array().map(Vec::as_slice) {
+					Some([Json::String(path), value]) => Ok((path.clone(), json_to_value(value.clone()))),
+					_ => Err(malformed("wildcard matches must be `[path, value]` pairs")),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+			wildcards.insert(pattern.clone(), matches);
+		}
+
+		Ok(EvalSnapshot {
+			rule_source: text("rule_source")?,
+			rule_hash: text("rule_hash")?,
+			facts,
+			missing,
+			wildcards,
+			trace_hash: text("trace_hash")?,
+		})
+	}
+}
+
+/// Resolver wrapper recording every value it hands out
+struct SnapshotRecorder<'a> {
+	inner: &'a dyn HelResolver,
+	recorded: RefCell<Recorded>,
+}
+
+#[derive(Default)]
+struct Recorded {
+	facts: BTreeMap<String, Value>,
+	missing: BTreeSet<String>,
+	wildcards: BTreeMap<String, Vec<(String, Value)>>,
+}
+
+impl HelResolver for SnapshotRecorder<'_> {
+	fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+		let value = self.inner.resolve_attr(object, field);
+		let path = format!("{}.{}", object, field);
+		let mut recorded = self.recorded.borrow_mut();
+		if let Some(value) = &value {
+			recorded.facts.insert(path, value.clone());
+		} else {
+			recorded.missing.insert(path);
+		}
+		value
+	}
+
+	fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+		let matches = self.inner.resolve_wildcard(object, field);
+		self.recorded
+			.borrow_mut()
+			.wildcards
+			.insert(format!("{}.*.{}", object, field), matches.clone());
+		matches
+	}
+}
+
+// endregion: --- EvalSnapshot
+
+// region:    --- Replay
+
+/// Re-run a recorded evaluation purely from its snapshot
+///
+/// Built-ins are not part of a snapshot: pass the registry the rule was evaluated
+/// with (built-ins are required to be deterministic). The replayed trace is
+/// returned only if it hashes to the recorded `trace_hash`.
+pub fn replay(snapshot: &EvalSnapshot, builtins: Option<&BuiltinsRegistry>) -> Result<EvalTrace, ReplayError> {
+	replay_with_options(snapshot, builtins, &EvalOptions::default())
+}
+
+/// `replay()` under the evaluation options the snapshot was captured with
+pub fn replay_with_options(
+	snapshot: &EvalSnapshot,
+	builtins: Option<&BuiltinsRegistry>,
+	options: &EvalOptions,
+) -> Result<EvalTrace, ReplayError> {
+	let actual = rule_hash(&snapshot.rule_source);
+	if actual != snapshot.rule_hash {
+		return Err(ReplayError::RuleHashMismatch {
+			expected: snapshot.rule_hash.clone(),
+			actual,
+		});
+	}
+
+	let resolver = SnapshotResolver {
+		snapshot,
+		unrecorded: RefCell::new(None),
+	};
+	let outcome = run(&snapshot.rule_source, &resolver, builtins, options);
+	if let Some(path) = resolver.unrecorded.into_inner() {
+		return Err(ReplayError::UnrecordedFact(path));
+	}
+	let trace = outcome.map_err(ReplayError::Eval)?;
+
+	let actual = trace.stable_hash(&snapshot.rule_source);
+	if actual != snapshot.trace_hash {
+		return Err(ReplayError::Diverged {
+			expected: snapshot.trace_hash.clone(),
+			actual,
+		});
+	}
+	Ok(trace)
+}
+
+/// Resolver serving only the values recorded in a snapshot
+struct SnapshotResolver<'a> {
+	snapshot: &'a EvalSnapshot,
+	/// First lookup the snapshot has no record of
+	unrecorded: RefCell<Option<String>>,
+}
+
+impl SnapshotResolver<'_> {
+	fn note_unrecorded(&self, path: String) {
+		self.unrecorded.borrow_mut().get_or_insert(path);
+	}
+}
+
+impl HelResolver for SnapshotResolver<'_> {
+	fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+		let path = format!("{}.{}", object, field);
+		let value = self.snapshot.facts.get(&path).cloned();
+		if value.is_none() && !self.snapshot.missing.contains(&path) {
+			self.note_unrecorded(path);
+		}
+		value
+	}
+
+	fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+		let pattern = format!("{}.*.{}", object, field);
+		match self.snapshot.wildcards.get(&pattern) {
+			Some(matches) => matches.clone(),
+			None => {
+				self.note_unrecorded(pattern);
+				Vec::new()
+			}
+		}
+	}
+}
+
+// endregion: --- Replay
+
+// region:    --- Support
+
+fn run(
+	rule_source: &str,
+	resolver: &dyn HelResolver,
+	builtins: Option<&BuiltinsRegistry>,
+	options: &EvalOptions,
+) -> Result<EvalTrace, EvalError> {
+	let ast = parse_expression_with_limits(rule_source, &options.limits).map_err(|e| EvalError::ParseError(e.message))?;
+	trace_ast(&ast, &crate::options_context(resolver, builtins, options))
+}
+
+fn value_to_json(value: &Value) -> Json {
+	match value {
+		Value::Null => Json::Null,
+		Value::Bool(b) => Json::Bool(*b),
+		Value::Number(n) => serde_json::Number::from_f64(*n).map_or(Json::Null, Json::Number),
+		Value::String(s) => Json::String(s.to_string()),
+		Value::List(items) => Json::Array(items.iter().map(value_to_json).collect()),
+		Value::Map(entries) => Json::Object(
+			entries
+				.iter()
+				.map(|(key, value)| (key.to_string(), value_to_json(value)))
+				.collect(),
+		),
+	}
+}
+
+fn malformed(detail: &str) -> HelError {
+	HelError::parse_error(format!("Malformed snapshot: {}", detail))
+}
+
+// endregion: --- Support
+
+// region:    --- Error Types
+
+/// Reasons a snapshot could not be replayed
+#[derive(Debug, Clone)]
+pub enum ReplayError {
+	/// The rule source does not match its recorded hash
+	RuleHashMismatch { expected: String, actual: String },
+	/// Evaluation requested a fact the snapshot has no record of
+	UnrecordedFact(String),
+	/// The rule failed to parse or evaluate
+	Eval(EvalError),
+	/// The replayed trace differs from the recorded one
+	Diverged { expected: String, actual: String },
+}
+
+impl std::fmt::Display for ReplayError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ReplayError::RuleHashMismatch { expected, actual } => {
+				write!(f, "Rule source hash {} does not match recorded {}", actual, expected)
+			}
+			ReplayError::UnrecordedFact(path) => write!(f, "Fact `{}` is not recorded in the snapshot", path),
+			ReplayError::Eval(e) => write!(f, "{}", e),
+			ReplayError::Diverged { expected, actual } => {
+				write!(f, "Replayed trace hash {} differs from recorded {}", actual, expected)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ReplayError {}
+
+// endregion: --- Error Types
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::FactsEvalContext;
+	use std::sync::Arc;
+
+	fn facts() -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.format", Value::String("elf".into()));
+		ctx.add_fact(
+			"binary.imports",
+			Value::List(vec![Value::String("VirtualAlloc".into())]),
+		);
+		let section = |entropy: f64| Value::Map([(Arc::from("entropy"), Value::Number(entropy))].into());
+		ctx.add_fact("sections.text", section(7.9));
+		ctx.add_fact("sections.data", section(4.2));
+		ctx
+	}
+
+	#[test]
+	fn test_replay_from_snapshot() {
+		let rule = r#"binary.format == "elf" AND binary.imports CONTAINS "VirtualAlloc"
+			AND (intel.verdict == "malicious" OR sections.*.entropy > 7.5)"#;
+		let (trace, snapshot) = EvalSnapshot::capture(rule, &facts(), None).unwrap();
+		assert!(trace.result);
+		assert_eq!(
+			snapshot.facts.keys().collect::<Vec<_>>(),
+			vec!["binary.format", "binary.imports"]
+		);
+		assert!(snapshot.missing.contains("intel.verdict"));
+		assert_eq!(snapshot.wildcards["sections.*.entropy"].len(), 2);
+
+		let restored = EvalSnapshot::from_json(&snapshot.to_json().to_string()).unwrap();
+		assert_eq!(restored, snapshot);
+		let replayed = replay(&restored, None).unwrap();
+		assert_eq!(replayed.to_string(), trace.to_string());
+		assert_eq!(replayed.missing_facts(), vec!["intel.verdict"]);
+
+		let mut edited = snapshot.clone();
+		edited.rule_source.push_str(" AND true");
+		assert!(matches!(
+			replay(&edited, None),
+			Err(ReplayError::RuleHashMismatch { .. })
+		));
+
+		let mut tampered = snapshot.clone();
+		tampered
+			.facts
+			.insert("binary.format".into(), Value::String("pe".into()));
+		assert!(matches!(replay(&tampered, None), Err(ReplayError::Diverged { .. })));
+
+		let mut incomplete = snapshot;
+		incomplete.missing.clear();
+		assert!(matches!(
+			replay(&incomplete, None),
+			Err(ReplayError::UnrecordedFact(path)) if path == "intel.verdict"
+		));
+	}
+
+	#[test]
+	fn test_malformed_snapshot() {
+		assert!(EvalSnapshot::from_json("[]").is_err());
+		let err =
+			EvalSnapshot::from_json(r#"{"facts": {}, "missing": [], "wildcards": {"a.*.b": [[1, 2]]}}"#).unwrap_err();
+		assert!(err.message.contains("[path, value]"), "{}", err.message);
+	}
+
+	#[test]
+	fn test_replay_with_options() {
+		let mut ctx = facts();
+		ctx.add_fact("binary.entropy", Value::Number(7.501));
+		let options = EvalOptions::new().with_numeric_tolerance(0.01);
+
+		let (trace, snapshot) = EvalSnapshot::capture_with_options("binary.entropy == 7.5", &ctx, None, &options).unwrap();
+		assert!(trace.result);
+		let replayed = replay_with_options(&snapshot, None, &options).unwrap();
+		assert_eq!(replayed.to_string(), trace.to_string());
+
+		// Without the tolerance the replayed decision differs from the recorded one
+		assert!(matches!(replay(&snapshot, None), Err(ReplayError::Diverged { .. })));
+	}
+}
+
+// endregion: --- Tests
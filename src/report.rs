@@ -0,0 +1,149 @@
+//! Rich error reports via [miette](https://docs.rs/miette) (feature `miette`)
+//!
+//! `HelError::with_source` attaches the rule text to an error, producing a `HelReport`
+//! that implements `miette::Diagnostic`: the error location becomes a labeled span and
+//! common mistakes get help text. Return it from a `miette::Result` to use the host's
+//! configured handler, or call `HelReport::render` for plain text.
+//!
+//! # Examples
+//!
+//! ```
+//! use hel::validate_expression;
+//!
+//! let source = "binary.arch == )";
+//! let report = validate_expression(source).unwrap_err().with_source("rule.hel", source);
+//! let text = report.render();
+//! assert!(text.contains("rule.hel:1:16"));
+//! assert!(text.contains("help:"));
+//! ```
+
+use std::fmt;
+
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme, LabeledSpan, NamedSource, SourceCode};
+
+use crate::{ErrorKind, HelError};
+
+// region:    --- Types
+
+/// A `HelError` together with the source it refers to
+#[derive(Debug)]
+pub struct HelReport {
+	error: HelError,
+	source: NamedSource<String>,
+	offset: Option<usize>,
+}
+
+impl HelError {
+	/// Attach the rule text this error was produced from
+	///
+	/// `name` is shown in the report header (typically a file name).
+	pub fn with_source(self, name: impl AsRef<str>, source: impl Into<String>) -> HelReport {
+		let source = source.into();
+		let offset = self.offset_in(&source);
+		HelReport {
+			error: self,
+			source: NamedSource::new(name, source).with_language("hel"),
+			offset,
+		}
+	}
+}
+
+impl HelReport {
+	/// The underlying error
+	pub fn error(&self) -> &HelError {
+		&self.error
+	}
+
+	/// Render as plain text (Unicode, no colors) with a source snippet
+	pub fn render(&self) -> String {
+		let mut out = String::new();
+		let handler = GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor());
+		// Writing to a String cannot fail
+		let _ = handler.render_report(&mut out, self);
+		out
+	}
+
+	/// Label under the error location
+	fn label(&self) -> String {
+		match self.error.kind {
+			ErrorKind::ParseError => self
+				.error
+				.message
+				.strip_prefix("parsing error: ")
+				.unwrap_or(&self.error.message)
+				.to_string(),
+			_ => "here".to_string(),
+		}
+	}
+}
+
+// endregion: --- Types
+
+// region:    --- Diagnostic
+
+impl fmt::Display for HelReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.error.message)
+	}
+}
+
+impl std::error::Error for HelReport {}
+
+impl Diagnostic for HelReport {
+	fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+		Some(Box::new(self.error.kind.code()))
+	}
+
+	fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+		self.error.help().map(|help| Box::new(help) as Box<dyn fmt::Display>)
+	}
+
+	fn source_code(&self) -> Option<&dyn SourceCode> {
+		Some(&self.source)
+	}
+
+	fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+		let offset = self.offset?;
+		let len = self.source.inner()[offset..].chars().next().map_or(0, char::len_utf8);
+		let label = LabeledSpan::new_primary_with_span(Some(self.label()), (offset, len));
+		Some(Box::new(std::iter::once(label)))
+	}
+}
+
+// endregion: --- Diagnostic
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{parse_script, validate_expression};
+
+	#[test]
+	fn test_parse_error_report() {
+		let source = "binary.arch == \"x86\"\n  AND == 1";
+		let report = validate_expression(source).unwrap_err().with_source("rule.hel", source);
+		assert_eq!(report.code().unwrap().to_string(), "hel::parse");
+		let label = report.labels().unwrap().next().unwrap();
+		assert_eq!(&source[label.offset()..label.offset() + label.len()], "=");
+
+		let text = report.render();
+		assert!(text.contains("[rule.hel:2:7]"), "{}", text);
+		assert!(text.contains("AND == 1"), "{}", text);
+		assert!(text.contains("help: expected a value"), "{}", text);
+	}
+
+	#[test]
+	fn test_report_without_location() {
+		let source = "let a = true";
+		let report = parse_script(source).unwrap_err().with_source("script.hel", source);
+		assert!(report.labels().is_none());
+		assert!(report.help().unwrap().to_string().contains("let"));
+
+		let report = HelError::eval_error("boom".to_string()).with_source("rule.hel", "a.b");
+		assert!(report.help().is_none());
+		assert!(report.render().contains("boom"));
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,214 @@
+//! AST rewriting for HEL expressions
+//!
+//! Hosts sometimes need to change an expression before evaluating it: rename
+//! deprecated attribute paths, or inject tenant-specific constants in place of
+//! placeholders. A `Rewriter` is offered every node bottom-up and may return a
+//! replacement.
+//!
+//! ## Semantics
+//! - Children are rewritten before their parent, so the parent sees rewritten children
+//! - A replacement is not rewritten again (no fixpoint iteration)
+//! - Returning `None` keeps the node unchanged
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::AstNode;
+
+// region:    --- Rewriter
+
+/// Bottom-up node rewriter
+pub trait Rewriter {
+	/// Return a replacement for `node`, or `None` to keep it
+	fn rewrite(&mut self, node: &AstNode) -> Option<AstNode>;
+}
+
+impl<F> Rewriter for F
+where
+	F: FnMut(&AstNode) -> Option<AstNode>,
+{
+	fn rewrite(&mut self, node: &AstNode) -> Option<AstNode> {
+		self(node)
+	}
+}
+
+/// Rewrite an expression bottom-up
+///
+/// # Examples
+///
+/// ```
+/// use hel::rewrite::{rewrite_ast, RenameAttributes};
+/// use hel::{evaluate_ast, parse_expression, EvalContext, FactsEvalContext, Value};
+///
+/// let ast = parse_expression(r#"pe.arch == "x86_64""#).unwrap();
+/// let ast = rewrite_ast(ast, &mut RenameAttributes::new().rename("pe.arch", "binary.arch"));
+///
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("binary.arch", Value::String("x86_64".into()));
+/// assert!(evaluate_ast(&ast, &EvalContext::new(&facts)).unwrap());
+/// ```
+pub fn rewrite_ast<R: Rewriter + ?Sized>(node: AstNode, rewriter: &mut R) -> AstNode {
+	let node = match node {
+		AstNode::Bool(_)
+		| AstNode::String(_)
+		| AstNode::Number(_)
+		| AstNode::Float(_)
+		| AstNode::Identifier(_)
+		| AstNode::Attribute { .. }
+		| AstNode::Wildcard { .. } => node,
+		AstNode::Comparison { left, op, right, span } => AstNode::Comparison {
+			left: Box::new(rewrite_ast(*left, rewriter)),
+			op,
+			right: Box::new(rewrite_ast(*right, rewriter)),
+			span,
+		},
+		AstNode::And(nodes) => AstNode::And(rewrite_all(nodes, rewriter)),
+		AstNode::Or(nodes) => AstNode::Or(rewrite_all(nodes, rewriter)),
+		AstNode::Xor(nodes) => AstNode::Xor(rewrite_all(nodes, rewriter)),
+		AstNode::ListLiteral(items) => AstNode::ListLiteral(rewrite_all(items, rewriter)),
+		AstNode::MapLiteral(entries) => AstNode::MapLiteral(
+			entries
+				.into_iter()
+				.map(|(k, v)| (k, rewrite_ast(v, rewriter)))
+				.collect(),
+		),
+		AstNode::FunctionCall { namespace, name, args, span } => AstNode::FunctionCall {
+			namespace,
+			name,
+			args: rewrite_all(args, rewriter),
+			span,
+		},
+		AstNode::Range { start, end } => AstNode::Range {
+			start: Box::new(rewrite_ast(*start, rewriter)),
+			end: Box::new(rewrite_ast(*end, rewriter)),
+		},
+	};
+
+	rewriter.rewrite(&node).unwrap_or(node)
+}
+
+fn rewrite_all<R: Rewriter + ?Sized>(nodes: Vec<AstNode>, rewriter: &mut R) -> Vec<AstNode> {
+	nodes.into_iter().map(|n| rewrite_ast(n, rewriter)).collect()
+}
+
+// endregion: --- Rewriter
+
+// region:    --- Built-in Rewriters
+
+/// Renames attribute paths (e.g., deprecated `pe.arch` to `binary.arch`)
+#[derive(Debug, Clone, Default)]
+pub struct RenameAttributes {
+	renames: BTreeMap<String, (Arc<str>, Arc<str>)>,
+}
+
+impl RenameAttributes {
+	/// Create an empty rename table
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Rename `from` (`object.field`) to `to` (`object.field`)
+	///
+	/// Panics if `to` is not of the form `object.field`.
+	pub fn rename(mut self, from: &str, to: &str) -> Self {
+		let (object, field) = to.split_once('.').expect("attribute path must be object.field");
+		self.renames.insert(from.to_string(), (object.into(), field.into()));
+		self
+	}
+}
+
+impl Rewriter for RenameAttributes {
+	fn rewrite(&mut self, node: &AstNode) -> Option<AstNode> {
+		let AstNode::Attribute { object, field } = node else {
+			return None;
+		};
+		let (object, field) = self.renames.get(&format!("{}.{}", object, field))?;
+		Some(AstNode::Attribute {
+			object: object.clone(),
+			field: field.clone(),
+		})
+	}
+}
+
+/// Replaces identifiers (e.g., `%TENANT_LIMIT`, `$threshold`) or attribute paths with constants
+#[derive(Debug, Clone, Default)]
+pub struct InjectConstants {
+	constants: BTreeMap<String, AstNode>,
+}
+
+impl InjectConstants {
+	/// Create an empty constant table
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Replace the identifier or attribute path `name` with `value`
+	pub fn constant(mut self, name: &str, value: AstNode) -> Self {
+		self.constants.insert(name.to_string(), value);
+		self
+	}
+}
+
+impl Rewriter for InjectConstants {
+	fn rewrite(&mut self, node: &AstNode) -> Option<AstNode> {
+		match node {
+			AstNode::Identifier(name) => self.constants.get(name.as_ref()).cloned(),
+			AstNode::Attribute { object, field } => self.constants.get(&format!("{}.{}", object, field)).cloned(),
+			_ => None,
+		}
+	}
+}
+
+// endregion: --- Built-in Rewriters
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{evaluate_ast, parse_expression, EvalContext, FactsEvalContext, Value};
+
+	#[test]
+	fn test_inject_constants() {
+		let ast =
+			parse_expression("binary.entropy > %TENANT_MAX_ENTROPY AND tenant.tier == $tier").expect("parse failed");
+		let mut injector = InjectConstants::new()
+			.constant("%TENANT_MAX_ENTROPY", AstNode::Float(7.2))
+			.constant("$tier", AstNode::String("gold".into()));
+		let ast = rewrite_ast(ast, &mut injector);
+
+		let mut facts = FactsEvalContext::new();
+		facts.add_fact("binary.entropy", Value::Number(7.5));
+		facts.add_fact("tenant.tier", Value::String("gold".into()));
+		assert!(evaluate_ast(&ast, &EvalContext::new(&facts)).unwrap());
+	}
+
+	#[test]
+	fn test_rewrite_is_bottom_up() {
+		// Fold `a AND true` into `a`, then the now single-operand OR into its operand
+		let mut order = Vec::new();
+		let mut fold = |node: &AstNode| -> Option<AstNode> {
+			order.push(format!("{:?}", core::mem::discriminant(node)));
+			match node {
+				AstNode::And(nodes) => {
+					let kept: Vec<AstNode> = nodes
+						.iter()
+						.filter(|n| !matches!(n, AstNode::Bool(true)))
+						.cloned()
+						.collect();
+					(kept.len() == 1).then(|| kept[0].clone())
+				}
+				AstNode::Or(nodes) if nodes.len() == 1 => Some(nodes[0].clone()),
+				_ => None,
+			}
+		};
+
+		let ast = parse_expression("a.b == 1 AND true").expect("parse failed");
+		let ast = rewrite_ast(ast, &mut fold);
+		assert!(matches!(ast, AstNode::Comparison { .. }), "{:?}", ast);
+		assert_eq!(order.len(), 6, "every node is offered exactly once");
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,1193 @@
+//! Rule sets for HEL
+//!
+//! A `RuleSet` holds named, pre-parsed rule conditions so hosts can evaluate the
+//! same policies against many fact sources without re-parsing rule text.
+//!
+//! A rule can build on another rule's result with `rule("id")`, so composite
+//! detections reuse primitives instead of duplicating their conditions.
+//!
+//! ## Determinism
+//! - Rules are kept in insertion order and evaluated in that order, except that a
+//!   rule referenced with `rule("id")` is evaluated before the rules referencing it
+//! - Each rule is evaluated once per resolver; references read the memoized result
+//! - Rule ids are unique within a set and dependency cycles are rejected
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use crate::builtins::BuiltinsRegistry;
+use crate::lexer::{tokenize, TokenKind};
+use crate::prelude::*;
+use crate::trace::{trace_ast, EvalTrace};
+use crate::{
+	parse_expression, single_operand, walk_ast, AstNode, EvalContext, EvalError, EvalOptions, HelError, HelResolver,
+	Value, Visitor, Walk,
+};
+
+/// Function referencing another rule's result: `rule("id")`
+pub(crate) const RULE_REFERENCE: &str = "rule";
+
+/// The id referenced by the arguments of `rule(...)`, if they are a single string
+pub(crate) fn referenced_rule(args: &[AstNode]) -> Option<&str> {
+	match args {
+		[AstNode::String(id)] => Some(id),
+		_ => None,
+	}
+}
+
+// region:    --- Rule
+
+/// A named, parsed rule condition
+#[derive(Debug, Clone)]
+pub struct Rule {
+	/// Unique rule identifier (e.g., "android-sms-stealer")
+	pub id: Arc<str>,
+	/// Original condition text
+	pub source: Arc<str>,
+	/// Parsed condition
+	pub condition: AstNode,
+	/// Outcome payload (a map literal) evaluated when the condition matches
+	pub outcome: Option<AstNode>,
+	/// Ids of rules referenced with `rule("id")`, in order of first reference
+	pub dependencies: Vec<Arc<str>>,
+	/// Tags for selecting subsets of a rule set (e.g., `android`, `T1437`)
+	pub tags: Vec<Arc<str>>,
+	/// Contribution of a match to `RuleSet::score()` (1.0 by default)
+	pub weight: f64,
+	/// Precedence under `EvaluationStrategy::HighestPriorityMatch` (0 by default; higher wins)
+	pub priority: i32,
+}
+
+impl Rule {
+	/// Parse a rule condition, optionally followed by an outcome payload
+	///
+	/// `condition then { key: value, ... }` declares a map evaluated when the condition
+	/// matches (see `with_outcome()`).
+	pub fn new(id: &str, condition: &str) -> Result<Self, HelError> {
+		let (condition_source, outcome) = split_outcome(condition);
+		let condition_ast = parse_expression(condition_source)?;
+		let mut rule = Self {
+			id: id.into(),
+			source: condition.into(),
+			dependencies: references(id, &condition_ast)?,
+			condition: condition_ast,
+			outcome: None,
+			tags: Vec::new(),
+			weight: 1.0,
+			priority: 0,
+		};
+		if let Some(start) = outcome {
+			rule = rule
+				.with_outcome(&condition[start..])
+				.map_err(|e| relocate(e, &condition[..start]))?;
+		}
+		Ok(rule)
+	}
+
+	/// Set the outcome payload: a map literal evaluated when the condition matches
+	///
+	/// Values are HEL expressions over the same facts as the condition
+	/// (e.g., `{ label: "malware.sms_stealer", confidence: 0.9, sample: app.name }`).
+	pub fn with_outcome(mut self, outcome: &str) -> Result<Self, HelError> {
+		let ast = parse_expression(outcome)?;
+		let map = single_operand(&ast);
+		if !matches!(map, AstNode::MapLiteral(_)) {
+			return Err(HelError::parse_error(format!(
+				"Rule {}: the outcome must be a map literal, e.g. {{ label: \"x\" }}",
+				self.id
+			)));
+		}
+		for id in references(&self.id, map)? {
+			if !self.dependencies.contains(&id) {
+				self.dependencies.push(id);
+			}
+		}
+		self.outcome = Some(map.clone());
+		Ok(self)
+	}
+
+	/// Set the weight (severity) this rule contributes to a score when it matches
+	pub fn with_weight(mut self, weight: f64) -> Self {
+		self.weight = weight;
+		self
+	}
+
+	/// Set the priority used to resolve conflicts when several rules match
+	pub fn with_priority(mut self, priority: i32) -> Self {
+		self.priority = priority;
+		self
+	}
+
+	/// Add tags to this rule (duplicates are ignored)
+	pub fn with_tags<I, S>(mut self, tags: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>,
+	{
+		for tag in tags {
+			if !self.has_tag(tag.as_ref()) {
+				self.tags.push(tag.as_ref().into());
+			}
+		}
+		self
+	}
+
+	/// Check if this rule carries `tag`
+	pub fn has_tag(&self, tag: &str) -> bool {
+		self.tags.iter().any(|t| t.as_ref() == tag)
+	}
+
+	/// Evaluate this rule against a resolver
+	///
+	/// A rule with dependencies fails here; evaluate it with `RuleSet::evaluate()`.
+	pub fn evaluate(&self, resolver: &dyn HelResolver) -> Result<bool, EvalError> {
+		self.evaluate_with_options(resolver, &EvalOptions::default())
+	}
+
+	/// Evaluate this rule against a resolver under explicit evaluation options
+	pub fn evaluate_with_options(&self, resolver: &dyn HelResolver, options: &EvalOptions) -> Result<bool, EvalError> {
+		let ctx = EvalContext::new(resolver).with_options(options.clone());
+		crate::evaluate_ast_with_context(&self.condition, &ctx)
+	}
+}
+
+/// Ids of the rules referenced with `rule("id")` in `ast`
+fn references(rule_id: &str, ast: &AstNode) -> Result<Vec<Arc<str>>, HelError> {
+	let mut references = References::default();
+	walk_ast(ast, &mut references);
+	if references.malformed {
+		return Err(HelError::parse_error(format!(
+			"Rule {}: rule() takes a single rule id string, e.g. rule(\"base-check\")",
+			rule_id
+		)));
+	}
+	Ok(references.ids)
+}
+
+/// Split `condition then { ... }` at the outcome: the condition and the outcome's offset
+pub(crate) fn split_outcome(source: &str) -> (&str, Option<usize>) {
+	let tokens = tokenize(source);
+	tokens
+		.windows(2)
+		.find(|pair| {
+			pair[0].kind == TokenKind::Identifier && pair[0].text(source) == "then" && pair[1].text(source) == "{"
+		})
+		.map_or((source, None), |pair| {
+			(&source[..pair[0].span.start], Some(pair[1].span.start))
+		})
+}
+
+/// Shift an error located in the outcome text to its position in the rule text
+fn relocate(mut error: HelError, before: &str) -> HelError {
+	let lines = before.matches('\n').count();
+	let column = before.rsplit('\n').next().unwrap_or_default().chars().count();
+	if let Some(line) = error.line {
+		if line == 1 {
+			error.column = error.column.map(|c| c + column);
+		}
+		error.line = Some(line + lines);
+	}
+	error
+}
+
+/// Collects the ids of `rule("id")` references
+#[derive(Default)]
+struct References {
+	ids: Vec<Arc<str>>,
+	malformed: bool,
+}
+
+impl Visitor for References {
+	fn enter_function_call(&mut self, namespace: Option<&str>, name: &str, args: &[AstNode]) -> Walk {
+		if namespace.is_none() && name == RULE_REFERENCE {
+			match referenced_rule(args) {
+				Some(id) if !self.ids.iter().any(|known| known.as_ref() == id) => self.ids.push(id.into()),
+				Some(_) => {}
+				None => self.malformed = true,
+			}
+		}
+		Walk::Continue
+	}
+}
+
+// endregion: --- Rule
+
+// region:    --- RuleSet
+
+/// Ordered collection of uniquely named rules
+///
+/// # Examples
+///
+/// ```
+/// use hel::{FactsEvalContext, RuleSet, Value};
+///
+/// let mut rules = RuleSet::new();
+/// rules.add_rule("is-elf", r#"binary.format == "elf""#).expect("invalid rule");
+///
+/// let mut ctx = FactsEvalContext::new();
+/// ctx.add_fact("binary.format", Value::String("elf".into()));
+///
+/// assert!(rules.get("is-elf").unwrap().evaluate(&ctx).unwrap());
+///
+/// // Composite rules reference other rules; dependencies may be added later
+/// rules.add_rule("packed-elf", r#"rule("is-packed") AND rule("is-elf")"#).expect("invalid rule");
+/// rules.add_rule("is-packed", "binary.entropy > 7.5").expect("invalid rule");
+/// ctx.add_fact("binary.entropy", Value::Number(7.9));
+///
+/// let results = rules.evaluate(&ctx);
+/// let order: Vec<&str> = results.iter().map(|(id, _)| id.as_ref()).collect();
+/// assert_eq!(order, vec!["is-elf", "is-packed", "packed-elf"]);
+/// assert!(results[2].1.as_ref().unwrap());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+	rules: Vec<Rule>,
+	/// Index into `rules` by rule id
+	index: BTreeMap<Arc<str>, usize>,
+	/// Options every rule is evaluated with
+	options: EvalOptions,
+	/// Registry for function calls in conditions and outcomes
+	builtins: Option<BuiltinsRegistry>,
+}
+
+impl RuleSet {
+	/// Create an empty rule set
+	pub fn new() -> Self {
+		Self {
+			rules: Vec::new(),
+			index: BTreeMap::new(),
+			options: EvalOptions::default(),
+			builtins: None,
+		}
+	}
+
+	/// Evaluate every rule with `options` (rules are already parsed, so parse limits do not apply)
+	pub fn with_options(mut self, options: EvalOptions) -> Self {
+		self.options = options;
+		self
+	}
+
+	/// Dispatch function calls in conditions and outcomes to `builtins`
+	///
+	/// Without a registry, every rule calling a function fails to evaluate.
+	pub fn with_builtins(mut self, builtins: BuiltinsRegistry) -> Self {
+		self.builtins = Some(builtins);
+		self
+	}
+
+	/// Evaluation context for one rule set evaluation
+	pub(crate) fn context<'a>(&'a self, resolver: &'a dyn HelResolver) -> EvalContext<'a> {
+		crate::options_context(resolver, self.builtins.as_ref(), &self.options)
+	}
+
+	/// Parse and add a rule
+	///
+	/// Returns an error if the condition does not parse, the id is already used or
+	/// the rule's `rule("id")` references would create a dependency cycle. References
+	/// to rules not yet added are allowed.
+	pub fn add_rule(&mut self, id: &str, condition: &str) -> Result<(), HelError> {
+		self.add(Rule::new(id, condition)?)
+	}
+
+	/// Add a parsed rule (e.g., one built with `Rule::with_tags()`)
+	///
+	/// Returns an error if the id is already used or the rule would create a dependency cycle.
+	pub fn add(&mut self, rule: Rule) -> Result<(), HelError> {
+		if self.index.contains_key(&rule.id) {
+			return Err(HelError::parse_error(format!("Duplicate rule id: {}", rule.id)));
+		}
+		if let Some(cycle) = self.cycle_through(&rule) {
+			return Err(HelError::parse_error(format!("Rule dependency cycle: {}", cycle)));
+		}
+		self.index.insert(rule.id.clone(), self.rules.len());
+		self.rules.push(rule);
+		Ok(())
+	}
+
+	/// The cycle `rule` would close (`a -> b -> a`), starting at its earliest added rule
+	///
+	/// The set is acyclic, so a cycle must pass through the new rule; only rules
+	/// reachable from its dependencies are visited.
+	fn cycle_through(&self, rule: &Rule) -> Option<String> {
+		if rule.dependencies.contains(&rule.id) {
+			return Some(format!("{} -> {}", rule.id, rule.id));
+		}
+		let mut visited = vec![false; self.rules.len()];
+		let mut path = Vec::new();
+		let found = rule
+			.dependencies
+			.iter()
+			.filter_map(|dependency| self.index.get(dependency))
+			.any(|&dep| self.path_to(dep, &rule.id, &mut visited, &mut path));
+		if !found {
+			return None;
+		}
+
+		let start = path.iter().enumerate().min_by_key(|(_, &i)| i).map_or(0, |(at, _)| at);
+		let mut cycle: Vec<&str> = path[start..].iter().map(|&i| self.rules[i].id.as_ref()).collect();
+		cycle.push(&rule.id);
+		cycle.extend(path[..=start].iter().map(|&i| self.rules[i].id.as_ref()));
+		Some(cycle.join(" -> "))
+	}
+
+	/// Depth-first search for a rule referencing `target`; `path` holds the rules leading to it
+	fn path_to(&self, index: usize, target: &str, visited: &mut [bool], path: &mut Vec<usize>) -> bool {
+		if visited[index] {
+			return false;
+		}
+		visited[index] = true;
+		path.push(index);
+		let rule = &self.rules[index];
+		if rule.dependencies.iter().any(|d| d.as_ref() == target)
+			|| rule
+				.dependencies
+				.iter()
+				.filter_map(|dependency| self.index.get(dependency))
+				.any(|&dep| self.path_to(dep, target, visited, path))
+		{
+			return true;
+		}
+		path.pop();
+		false
+	}
+
+	/// Evaluate every rule against a resolver, referenced rules first
+	///
+	/// Returns `(id, result)` pairs in evaluation order. Each rule is evaluated once
+	/// and `rule("id")` references read the memoized result; a reference to an unknown
+	/// or failed rule is an evaluation error of the referencing rule.
+	pub fn evaluate(&self, resolver: &dyn HelResolver) -> Vec<(Arc<str>, Result<bool, EvalError>)> {
+		let mut results = Vec::with_capacity(self.rules.len());
+		self.evaluate_each(
+			|_| true,
+			|| self.context(resolver),
+			|rule, result| {
+				results.push((rule.id.clone(), result));
+				true
+			},
+		);
+		results
+	}
+
+	/// Evaluate every rule with tracing, referenced rules first
+	///
+	/// Like `evaluate()`, returning each rule's trace instead of its result.
+	pub fn evaluate_with_trace(&self, resolver: &dyn HelResolver) -> Vec<(Arc<str>, Result<EvalTrace, EvalError>)> {
+		let mut results = Vec::with_capacity(self.rules.len());
+		self.evaluate_each_with(
+			|_| true,
+			|| self.context(resolver),
+			|rule, ctx| trace_ast(&rule.condition, ctx),
+			|rule, result| {
+				results.push((rule.id.clone(), result));
+				true
+			},
+		);
+		results
+	}
+
+	/// Evaluate the rules whose tags match `filter`, referenced rules first
+	///
+	/// Like `evaluate()`, but only rules selected by the filter are reported. Rules they
+	/// reference are still evaluated (once) so `rule("id")` sees their results.
+	pub fn evaluate_tagged(
+		&self,
+		resolver: &dyn HelResolver,
+		filter: &TagFilter,
+	) -> Vec<(Arc<str>, Result<bool, EvalError>)> {
+		let mut results = Vec::new();
+		self.evaluate_each(
+			|rule| filter.matches(rule),
+			|| self.context(resolver),
+			|rule, result| {
+				results.push((rule.id.clone(), result));
+				true
+			},
+		);
+		results
+	}
+
+	/// Evaluate the rule set and resolve which matching rules win under `strategy`
+	///
+	/// - `FirstMatch`: the first matching rule in evaluation order; later rules are not evaluated
+	/// - `AllMatches`: every matching rule, in evaluation order
+	/// - `HighestPriorityMatch`: the matching rule with the highest `priority`; ties go to
+	///   the rule earliest in evaluation order
+	///
+	/// Rules that fail to evaluate are listed in `errors` and never match.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::rules::Rule;
+	/// use hel::{EvaluationStrategy, FactsEvalContext, RuleSet, Value};
+	///
+	/// let mut rules = RuleSet::new();
+	/// rules.add(Rule::new("allow-signed", "binary.signed == true").unwrap()).unwrap();
+	/// rules.add(Rule::new("deny-packed", "binary.entropy > 7.5").unwrap().with_priority(10)).unwrap();
+	///
+	/// let mut ctx = FactsEvalContext::new();
+	/// ctx.add_fact("binary.signed", Value::Bool(true));
+	/// ctx.add_fact("binary.entropy", Value::Number(7.9));
+	///
+	/// assert_eq!(rules.evaluate_with_strategy(&ctx, EvaluationStrategy::FirstMatch).winner(), Some("allow-signed"));
+	/// assert_eq!(
+	///     rules.evaluate_with_strategy(&ctx, EvaluationStrategy::HighestPriorityMatch).winner(),
+	///     Some("deny-packed")
+	/// );
+	/// ```
+	pub fn evaluate_with_strategy(&self, resolver: &dyn HelResolver, strategy: EvaluationStrategy) -> StrategyMatch {
+		let mut result = StrategyMatch::default();
+		let mut best_priority = None;
+		self.evaluate_each(
+			|_| true,
+			|| self.context(resolver),
+			|rule, matched| {
+				match matched {
+					Ok(true) => match strategy {
+						EvaluationStrategy::FirstMatch => {
+							result.matched.push(rule.id.clone());
+							return false;
+						}
+						EvaluationStrategy::AllMatches => result.matched.push(rule.id.clone()),
+						EvaluationStrategy::HighestPriorityMatch => {
+							if best_priority.is_none_or(|best| rule.priority > best) {
+								best_priority = Some(rule.priority);
+								result.matched = vec![rule.id.clone()];
+							}
+						}
+					},
+					Ok(false) => {}
+					Err(e) => result.errors.push((rule.id.clone(), e)),
+				}
+				true
+			},
+		);
+		result
+	}
+
+	/// Score a resolver: the sum of the weights of all matched rules
+	///
+	/// Each matched rule's contribution carries its weight and evaluation trace; rules
+	/// that fail to evaluate are listed in `errors` and contribute nothing.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::rules::Rule;
+	/// use hel::{FactsEvalContext, RuleSet, Value};
+	///
+	/// let mut rules = RuleSet::new();
+	/// rules.add(Rule::new("packed", "binary.entropy > 7.5").unwrap().with_weight(40.0)).unwrap();
+	/// rules.add(Rule::new("unsigned", "binary.signed == false").unwrap().with_weight(25.0)).unwrap();
+	///
+	/// let mut ctx = FactsEvalContext::new();
+	/// ctx.add_fact("binary.entropy", Value::Number(7.9));
+	/// ctx.add_fact("binary.signed", Value::Bool(true));
+	///
+	/// let score = rules.score(&ctx);
+	/// assert_eq!(score.total, 40.0);
+	/// assert_eq!(score.contributions[0].rule_id.as_ref(), "packed");
+	/// ```
+	pub fn score(&self, resolver: &dyn HelResolver) -> RuleScore {
+		let mut score = RuleScore {
+			total: 0.0,
+			contributions: Vec::new(),
+			errors: Vec::new(),
+		};
+		self.evaluate_each_with(
+			|_| true,
+			|| self.context(resolver),
+			|rule, ctx| trace_ast(&rule.condition, ctx),
+			|rule, result| {
+				match result {
+					Ok(trace) if trace.result => {
+						score.total += rule.weight;
+						score.contributions.push(ScoreContribution {
+							rule_id: rule.id.clone(),
+							weight: rule.weight,
+							trace,
+						});
+					}
+					Ok(_) => {}
+					Err(e) => score.errors.push((rule.id.clone(), e)),
+				}
+				true
+			},
+		);
+		score
+	}
+
+	/// Evaluate every rule and the outcome payloads of the rules that match
+	///
+	/// Matched rules are reported in evaluation order with their trace and evaluated
+	/// outcome (`None` for rules without one). A rule whose condition or outcome fails
+	/// to evaluate is listed in `errors`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{FactsEvalContext, RuleSet, Value};
+	///
+	/// let mut rules = RuleSet::new();
+	/// rules
+	///     .add_rule("sms", r#"app.perms CONTAINS "SEND_SMS" then { label: "malware.sms_stealer", confidence: 0.9 }"#)
+	///     .unwrap();
+	///
+	/// let mut ctx = FactsEvalContext::new();
+	/// ctx.add_fact("app.perms", Value::List(vec!["SEND_SMS".into()]));
+	///
+	/// let report = rules.outcomes(&ctx);
+	/// let Some(Value::Map(outcome)) = &report.matched[0].outcome else { panic!() };
+	/// assert_eq!(outcome["confidence"], Value::Number(0.9));
+	/// ```
+	pub fn outcomes(&self, resolver: &dyn HelResolver) -> OutcomeReport {
+		let mut report = OutcomeReport {
+			matched: Vec::new(),
+			errors: Vec::new(),
+		};
+		self.evaluate_each_with(
+			|_| true,
+			|| self.context(resolver),
+			|rule, ctx| {
+				let trace = trace_ast(&rule.condition, ctx)?;
+				let outcome = match &rule.outcome {
+					Some(outcome) if trace.result => Some(crate::eval_node_to_value_with_context(outcome, ctx)?),
+					_ => None,
+				};
+				Ok((trace, outcome))
+			},
+			|rule, result| {
+				match result {
+					Ok((trace, outcome)) if trace.result => report.matched.push(RuleOutcome {
+						rule_id: rule.id.clone(),
+						outcome,
+						trace,
+					}),
+					Ok(_) => {}
+					Err(e) => report.errors.push((rule.id.clone(), e)),
+				}
+				true
+			},
+		);
+		report
+	}
+
+	/// Evaluate the rules selected by `include` in evaluation order, passing each result
+	/// to `visit` until it returns false
+	///
+	/// Rules referenced by selected rules are evaluated for their memoized result but not
+	/// visited. Returns true if `visit` stopped the evaluation before the last selected rule.
+	pub(crate) fn evaluate_each<'a>(
+		&self,
+		include: impl Fn(&Rule) -> bool,
+		context: impl Fn() -> EvalContext<'a>,
+		visit: impl FnMut(&Rule, Result<bool, EvalError>) -> bool,
+	) -> bool {
+		self.evaluate_each_with(
+			include,
+			context,
+			|rule, ctx| crate::evaluate_ast_with_context(&rule.condition, ctx),
+			visit,
+		)
+	}
+
+	/// `evaluate_each()` with a custom per-rule evaluation (e.g., tracing)
+	pub(crate) fn evaluate_each_with<'a, T: Matched>(
+		&self,
+		include: impl Fn(&Rule) -> bool,
+		context: impl Fn() -> EvalContext<'a>,
+		evaluate: impl Fn(&Rule, &EvalContext) -> Result<T, EvalError>,
+		mut visit: impl FnMut(&Rule, Result<T, EvalError>) -> bool,
+	) -> bool {
+		let plan = self.evaluation_plan(include);
+		let memoize = self.rules.iter().any(|r| !r.dependencies.is_empty());
+		let mut memo = BTreeMap::new();
+		for (position, &(rule, selected)) in plan.iter().enumerate() {
+			let result = {
+				let ctx = context().with_rule_results(&memo);
+				evaluate(rule, &ctx)
+			};
+			if memoize {
+				if let Ok(outcome) = &result {
+					memo.insert(rule.id.clone(), outcome.matched());
+				}
+			}
+			if selected && !visit(rule, result) {
+				return plan[position + 1..].iter().any(|&(_, selected)| selected);
+			}
+		}
+		false
+	}
+
+	/// Rules to evaluate, in evaluation order, with whether each was selected by `include`
+	/// (the others are dependencies of selected rules)
+	fn evaluation_plan(&self, include: impl Fn(&Rule) -> bool) -> Vec<(&Rule, bool)> {
+		let mut needed: Vec<&str> = Vec::new();
+		let mut plan: Vec<(&Rule, bool)> = Vec::with_capacity(self.rules.len());
+		// Dependents come after their dependencies, so walk backwards
+		for rule in self.evaluation_order().collect::<Vec<_>>().into_iter().rev() {
+			let selected = include(rule);
+			if selected || needed.contains(&rule.id.as_ref()) {
+				needed.extend(rule.dependencies.iter().map(|d| d.as_ref()));
+				plan.push((rule, selected));
+			}
+		}
+		plan.reverse();
+		plan
+	}
+
+	/// Rules in evaluation order: insertion order, with referenced rules moved before
+	/// the rules referencing them
+	///
+	/// The order is computed on each call, so adding rules stays cheap.
+	pub fn evaluation_order(&self) -> impl Iterator<Item = &Rule> {
+		self.topological_order().into_iter().map(|i| &self.rules[i])
+	}
+
+	/// Depth-first topological sort (the set is kept acyclic by `add()`)
+	fn topological_order(&self) -> Vec<usize> {
+		let mut visited = vec![false; self.rules.len()];
+		let mut order = Vec::with_capacity(self.rules.len());
+		for index in 0..self.rules.len() {
+			self.visit_order(index, &mut visited, &mut order);
+		}
+		order
+	}
+
+	fn visit_order(&self, index: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+		if visited[index] {
+			return;
+		}
+		visited[index] = true;
+		for dependency in &self.rules[index].dependencies {
+			// Unknown references fail at evaluation time
+			if let Some(&dep) = self.index.get(dependency) {
+				self.visit_order(dep, visited, order);
+			}
+		}
+		order.push(index);
+	}
+
+	/// Get a rule by id
+	pub fn get(&self, id: &str) -> Option<&Rule> {
+		self.index.get(id).map(|&i| &self.rules[i])
+	}
+
+	/// All rules in insertion order
+	pub fn rules(&self) -> &[Rule] {
+		&self.rules
+	}
+
+	/// Number of rules in the set
+	pub fn len(&self) -> usize {
+		self.rules.len()
+	}
+
+	/// Check if the set has no rules
+	pub fn is_empty(&self) -> bool {
+		self.rules.is_empty()
+	}
+}
+
+// endregion: --- RuleSet
+
+// region:    --- Strategies
+
+/// How `RuleSet::evaluate_with_strategy()` resolves several matching rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvaluationStrategy {
+	/// Stop at the first matching rule in evaluation order
+	FirstMatch,
+	/// Report every matching rule
+	#[default]
+	AllMatches,
+	/// Report the matching rule with the highest priority (earliest on ties)
+	HighestPriorityMatch,
+}
+
+/// Rules selected by an `EvaluationStrategy` for one resolver
+#[derive(Debug, Clone, Default)]
+pub struct StrategyMatch {
+	/// Winning rules in evaluation order (at most one except under `AllMatches`)
+	pub matched: Vec<Arc<str>>,
+	/// Rules that failed to evaluate
+	pub errors: Vec<(Arc<str>, EvalError)>,
+}
+
+impl StrategyMatch {
+	/// Id of the first winning rule, if any rule matched
+	pub fn winner(&self) -> Option<&str> {
+		self.matched.first().map(|id| id.as_ref())
+	}
+}
+
+// endregion: --- Strategies
+
+// region:    --- Scoring
+
+/// Aggregate score of a rule set for one resolver (see `RuleSet::score()`)
+#[derive(Debug, Clone)]
+pub struct RuleScore {
+	/// Sum of the weights of matched rules
+	pub total: f64,
+	/// Matched rules in evaluation order
+	pub contributions: Vec<ScoreContribution>,
+	/// Rules that failed to evaluate
+	pub errors: Vec<(Arc<str>, EvalError)>,
+}
+
+/// A matched rule's share of a `RuleScore`
+#[derive(Debug, Clone)]
+pub struct ScoreContribution {
+	/// Id of the matched rule
+	pub rule_id: Arc<str>,
+	/// Weight added to the total
+	pub weight: f64,
+	/// How the rule matched
+	pub trace: EvalTrace,
+}
+
+// endregion: --- Scoring
+
+// region:    --- Outcomes
+
+/// Matched rules and their outcomes for one resolver (see `RuleSet::outcomes()`)
+#[derive(Debug, Clone)]
+pub struct OutcomeReport {
+	/// Matched rules in evaluation order
+	pub matched: Vec<RuleOutcome>,
+	/// Rules whose condition or outcome failed to evaluate
+	pub errors: Vec<(Arc<str>, EvalError)>,
+}
+
+/// A matched rule with its evaluated outcome payload
+#[derive(Debug, Clone)]
+pub struct RuleOutcome {
+	/// Id of the matched rule
+	pub rule_id: Arc<str>,
+	/// The evaluated outcome map (`None` if the rule declares no outcome)
+	pub outcome: Option<Value>,
+	/// How the rule matched
+	pub trace: EvalTrace,
+}
+
+// endregion: --- Outcomes
+
+// region:    --- Tag Filter
+
+/// Selects rules by their tags
+///
+/// A rule matches if it has at least one of the `any` tags (when given), all of the
+/// `all` tags and none of the excluded tags. The default filter matches every rule.
+///
+/// # Examples
+///
+/// ```
+/// use hel::rules::Rule;
+/// use hel::TagFilter;
+///
+/// let rule = Rule::new("sms-stealer", "app.sms == true").unwrap().with_tags(["android", "sms"]);
+/// assert!(TagFilter::any(["android", "ios"]).matches(&rule));
+/// assert!(!TagFilter::all(["android", "T1437"]).matches(&rule));
+/// assert!(!TagFilter::default().excluding(["sms"]).matches(&rule));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+	any: Vec<Arc<str>>,
+	all: Vec<Arc<str>>,
+	excluded: Vec<Arc<str>>,
+}
+
+impl TagFilter {
+	/// Match rules with at least one of `tags`
+	pub fn any<I, S>(tags: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>,
+	{
+		Self {
+			any: collect_tags(tags),
+			..Self::default()
+		}
+	}
+
+	/// Match rules with every one of `tags`
+	pub fn all<I, S>(tags: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>,
+	{
+		Self {
+			all: collect_tags(tags),
+			..Self::default()
+		}
+	}
+
+	/// Additionally reject rules with any of `tags`
+	pub fn excluding<I, S>(mut self, tags: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>,
+	{
+		self.excluded.extend(collect_tags(tags));
+		self
+	}
+
+	/// Check if `rule` is selected by this filter
+	pub fn matches(&self, rule: &Rule) -> bool {
+		(self.any.is_empty() || self.any.iter().any(|t| rule.has_tag(t)))
+			&& self.all.iter().all(|t| rule.has_tag(t))
+			&& !self.excluded.iter().any(|t| rule.has_tag(t))
+	}
+}
+
+impl From<&str> for TagFilter {
+	/// Match rules carrying this single tag
+	fn from(tag: &str) -> Self {
+		Self::any([tag])
+	}
+}
+
+fn collect_tags<I, S>(tags: I) -> Vec<Arc<str>>
+where
+	I: IntoIterator<Item = S>,
+	S: AsRef<str>,
+{
+	tags.into_iter().map(|t| Arc::from(t.as_ref())).collect()
+}
+
+// endregion: --- Tag Filter
+
+// region:    --- Support
+
+/// A per-rule evaluation result whose match is memoized for `rule("id")`
+pub(crate) trait Matched {
+	fn matched(&self) -> bool;
+}
+
+impl Matched for bool {
+	fn matched(&self) -> bool {
+		*self
+	}
+}
+
+impl Matched for EvalTrace {
+	fn matched(&self) -> bool {
+		self.result
+	}
+}
+
+impl Matched for (EvalTrace, Option<Value>) {
+	fn matched(&self) -> bool {
+		self.0.result
+	}
+}
+
+// endregion: --- Support
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{FactsEvalContext, Value};
+
+	fn core_registry() -> BuiltinsRegistry {
+		BuiltinsRegistry::builder().with_core().build().unwrap()
+	}
+
+	fn sections(count: usize) -> Value {
+		Value::List((0..count).map(|i| Value::Number(i as f64)).collect())
+	}
+
+	#[test]
+	fn test_rule_set_add_and_evaluate() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("is-elf", r#"binary.format == "elf""#)
+			.expect("add failed");
+		rules
+			.add_rule("high-entropy", "binary.entropy > 7.5")
+			.expect("add failed");
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.format", Value::String("elf".into()));
+		ctx.add_fact("binary.entropy", Value::Number(6.0));
+
+		assert_eq!(rules.len(), 2);
+		assert!(rules.get("is-elf").unwrap().evaluate(&ctx).unwrap());
+		assert!(!rules.get("high-entropy").unwrap().evaluate(&ctx).unwrap());
+	}
+
+	#[test]
+	fn test_rule_set_rejects_duplicates_and_bad_syntax() {
+		let mut rules = RuleSet::new();
+		rules.add_rule("a", "x.y == 1").expect("add failed");
+
+		assert!(rules.add_rule("a", "x.y == 2").is_err());
+		assert!(rules.add_rule("b", "(").is_err());
+		assert_eq!(rules.len(), 1);
+	}
+
+	#[test]
+	fn test_rule_dependencies() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("dropper", r#"rule("packed") AND rule("network") AND rule("packed")"#)
+			.expect("add failed");
+		rules.add_rule("packed", "binary.entropy > 7.5").expect("add failed");
+		rules
+			.add_rule("network", "rule(\"packed\") OR app.net")
+			.expect("add failed");
+		rules.add_rule("orphan", r#"rule("missing")"#).expect("add failed");
+		assert_eq!(rules.get("dropper").unwrap().dependencies.len(), 2);
+
+		let order: Vec<&str> = rules.evaluation_order().map(|r| r.id.as_ref()).collect();
+		assert_eq!(order, vec!["packed", "network", "dropper", "orphan"]);
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(7.9));
+		let results = rules.evaluate(&ctx);
+		assert!(results[..3].iter().all(|(_, r)| *r.as_ref().unwrap()));
+		assert!(results[3].1.as_ref().unwrap_err().to_string().contains("`missing`"));
+		assert!(rules.get("dropper").unwrap().evaluate(&ctx).is_err());
+
+		// Cycles and malformed references are rejected without changing the set
+		let error = rules.add_rule("missing", r#"rule("orphan")"#).unwrap_err();
+		assert!(
+			error.message.contains("orphan -> missing -> orphan"),
+			"{}",
+			error.message
+		);
+		assert!(rules.add_rule("self", r#"rule("self")"#).is_err());
+		assert!(rules.add_rule("bad", "rule(binary.entropy)").is_err());
+		assert_eq!(rules.len(), 4);
+	}
+
+	#[test]
+	fn test_long_dependency_chains() {
+		// Each rule references the next one, which is added after it
+		let mut rules = RuleSet::new();
+		for i in 0..2_000 {
+			rules
+				.add_rule(&format!("r{}", i), &format!("rule(\"r{}\") OR a.b", i + 1))
+				.expect("add failed");
+		}
+		rules.add_rule("r2000", "a.b").expect("add failed");
+		let order: Vec<&str> = rules.evaluation_order().map(|r| r.id.as_ref()).collect();
+		assert_eq!(order.len(), 2_001);
+		assert_eq!((order[0], order[2_000]), ("r2000", "r0"));
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("a.b", Value::Bool(true));
+		assert!(rules.evaluate(&ctx).iter().all(|(_, r)| *r.as_ref().unwrap()));
+
+		// A cycle is reported from its earliest added rule
+		let mut rules = RuleSet::new();
+		rules.add_rule("a", r#"rule("b")"#).expect("add failed");
+		rules.add_rule("b", r#"rule("c")"#).expect("add failed");
+		let error = rules.add_rule("c", r#"rule("a")"#).unwrap_err();
+		assert!(error.message.ends_with("a -> b -> c -> a"), "{}", error.message);
+	}
+
+	#[test]
+	fn test_tag_filtered_evaluation() {
+		let mut rules = RuleSet::new().with_builtins(core_registry());
+		let rule = |id: &str, condition: &str, tags: &[&str]| Rule::new(id, condition).unwrap().with_tags(tags);
+		rules
+			.add(rule("sms", r#"core.lower(app.perm) == "send_sms""#, &["android", "sms", "sms"]))
+			.unwrap();
+		rules
+			.add(rule(
+				"sms-stealer",
+				r#"rule("sms") AND app.net == true"#,
+				&["android", "T1437"],
+			))
+			.unwrap();
+		rules
+			.add(rule("macho", r#"binary.format == "macho""#, &["ios"]))
+			.unwrap();
+		assert_eq!(rules.get("sms").unwrap().tags.len(), 2);
+		assert!(rules.add(rule("macho", "true", &[])).is_err());
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("app.perm", Value::String("SEND_SMS".into()));
+		ctx.add_fact("app.net", Value::Bool(true));
+
+		let ids = |results: Vec<(Arc<str>, Result<bool, EvalError>)>| -> Vec<String> {
+			results.into_iter().map(|(id, _)| id.to_string()).collect()
+		};
+		let android = rules.evaluate_tagged(&ctx, &"android".into());
+		assert!(android.iter().all(|(_, result)| *result.as_ref().unwrap()));
+		assert_eq!(ids(android), vec!["sms", "sms-stealer"]);
+		assert_eq!(ids(rules.evaluate_tagged(&ctx, &TagFilter::default())).len(), 3);
+
+		// The referenced `sms` rule is evaluated but not reported
+		let results = rules.evaluate_tagged(&ctx, &"T1437".into());
+		assert_eq!(results.len(), 1);
+		assert!(results[0].1.as_ref().unwrap());
+		assert_eq!(
+			ids(rules.evaluate_tagged(&ctx, &TagFilter::any(["android", "ios"]).excluding(["sms"]))),
+			vec!["sms-stealer", "macho"]
+		);
+		assert!(rules.evaluate_tagged(&ctx, &"windows".into()).is_empty());
+	}
+
+	#[test]
+	fn test_evaluation_strategies() {
+		let mut rules = RuleSet::new();
+		let rule = |id: &str, condition: &str, priority: i32| Rule::new(id, condition).unwrap().with_priority(priority);
+		rules.add(rule("broken", "core.lenght(binary.sections) > 3", 100)).unwrap();
+		rules.add(rule("allow-signed", "binary.signed == true", 0)).unwrap();
+		rules.add(rule("deny-packed", "binary.entropy > 7.5", 10)).unwrap();
+		rules
+			.add(rule(
+				"deny-packed-signed",
+				r#"rule("deny-packed") AND rule("allow-signed")"#,
+				10,
+			))
+			.unwrap();
+		rules.add(rule("never", "binary.size < 0", 50)).unwrap();
+		rules.add(rule("many-sections", "core.len(binary.sections) > 3", 5)).unwrap();
+		assert_eq!(Rule::new("plain", "true").unwrap().priority, 0);
+		assert_eq!(EvaluationStrategy::default(), EvaluationStrategy::AllMatches);
+		let rules = rules.with_builtins(core_registry());
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(7.9));
+		ctx.add_fact("binary.signed", Value::Bool(true));
+		ctx.add_fact("binary.size", Value::Number(4096.0));
+		ctx.add_fact("binary.sections", sections(4));
+
+		let first = rules.evaluate_with_strategy(&ctx, EvaluationStrategy::FirstMatch);
+		assert_eq!(first.matched, vec![Arc::from("allow-signed")]);
+		assert_eq!(first.errors.len(), 1);
+
+		// Built-in calls succeed; only the unknown function fails
+		let all = rules.evaluate_with_strategy(&ctx, EvaluationStrategy::AllMatches);
+		let ids: Vec<&str> = all.matched.iter().map(|id| id.as_ref()).collect();
+		assert_eq!(ids, vec!["allow-signed", "deny-packed", "deny-packed-signed", "many-sections"]);
+		assert_eq!(all.errors.len(), 1);
+		assert!(all.errors[0].1.to_string().contains("core.lenght"), "{}", all.errors[0].1);
+
+		// Equal priorities resolve to the rule earliest in evaluation order
+		let highest = rules.evaluate_with_strategy(&ctx, EvaluationStrategy::HighestPriorityMatch);
+		assert_eq!(highest.winner(), Some("deny-packed"));
+		assert_eq!(highest.matched.len(), 1);
+		assert_eq!(highest.errors[0].0.as_ref(), "broken");
+
+		let empty = FactsEvalContext::new();
+		let none = rules.evaluate_with_strategy(&empty, EvaluationStrategy::HighestPriorityMatch);
+		assert_eq!(none.winner(), None);
+	}
+
+	#[test]
+	fn test_weighted_score() {
+		let mut rules = RuleSet::new();
+		let rule = |id: &str, condition: &str, weight: f64| Rule::new(id, condition).unwrap().with_weight(weight);
+		rules.add(rule("packed", "binary.entropy > 7.5", 40.0)).unwrap();
+		rules
+			.add(rule(
+				"packed-unsigned",
+				r#"rule("packed") AND binary.signed == false"#,
+				25.5,
+			))
+			.unwrap();
+		rules.add(rule("tiny", "binary.size < 100", 10.0)).unwrap();
+		rules
+			.add(rule("broken", "core.lenght(binary.sections) > 3", 99.0))
+			.unwrap();
+		rules
+			.add(rule("many-sections", "core.len(binary.sections) > 3", 4.0))
+			.unwrap();
+		assert_eq!(Rule::new("plain", "true").unwrap().weight, 1.0);
+		let rules = rules.with_builtins(core_registry());
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(7.9));
+		ctx.add_fact("binary.signed", Value::Bool(false));
+		ctx.add_fact("binary.size", Value::Number(4096.0));
+		ctx.add_fact("binary.sections", sections(4));
+
+		let score = rules.score(&ctx);
+		assert_eq!(score.total, 69.5);
+		let contributions: Vec<(&str, f64)> = score
+			.contributions
+			.iter()
+			.map(|c| (c.rule_id.as_ref(), c.weight))
+			.collect();
+		assert_eq!(
+			contributions,
+			vec![("packed", 40.0), ("packed-unsigned", 25.5), ("many-sections", 4.0)]
+		);
+		assert_eq!(score.contributions[0].trace.facts_used(), vec!["binary.entropy"]);
+		assert!(score.contributions[1].trace.result);
+		assert_eq!(score.errors.len(), 1);
+		assert_eq!(score.errors[0].0.as_ref(), "broken");
+	}
+
+	#[test]
+	fn test_rule_outcomes() {
+		let mut rules = RuleSet::new().with_builtins(core_registry());
+		rules
+			.add_rule(
+				"sms-stealer",
+				"app.perms CONTAINS \"SEND_SMS\"\n  then { label: \"malware.sms_stealer\", confidence: 0.9, app: app.name }",
+			)
+			.unwrap();
+		rules.add_rule("named", r#"app.name == "then {""#).unwrap();
+		rules
+			.add_rule(
+				"escalate",
+				r#"rule("sms-stealer") then { queue: "triage", severity: core.upper("high") }"#,
+			)
+			.unwrap();
+		assert!(rules.get("named").unwrap().outcome.is_none());
+		assert_eq!(rules.get("escalate").unwrap().dependencies.len(), 1);
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("app.perms", Value::List(vec!["SEND_SMS".into()]));
+		ctx.add_fact("app.name", Value::String("then {".into()));
+
+		let report = rules.outcomes(&ctx);
+		assert_eq!(report.matched.len(), 3);
+		let Some(Value::Map(outcome)) = &report.matched[0].outcome else {
+			panic!("expected a map outcome");
+		};
+		assert_eq!(outcome["label"], Value::String("malware.sms_stealer".into()));
+		assert_eq!(outcome["app"], Value::String("then {".into()));
+		assert!(report.matched[0].trace.facts_used().contains(&"app.perms".to_string()));
+		assert_eq!(report.matched[1].rule_id.as_ref(), "named");
+		assert!(report.matched[1].outcome.is_none());
+		// Outcomes call built-ins through the rule set's registry
+		assert!(report.errors.is_empty());
+		assert_eq!(report.matched[2].rule_id.as_ref(), "escalate");
+		let Some(Value::Map(outcome)) = &report.matched[2].outcome else {
+			panic!("expected a map outcome");
+		};
+		assert_eq!(outcome["severity"], Value::String("HIGH".into()));
+
+		assert!(Rule::new("bad", "a.b == 1 then { label: }").is_err());
+		let error = Rule::new("list", "a.b == 1\nthen { x: 1 } AND").unwrap_err();
+		assert_eq!(error.line, Some(2));
+		let error = Rule::new("list", "a.b == 1 then [1]").unwrap_err();
+		assert!(error.message.contains("expected"), "{}", error.message);
+		assert!(Rule::new("x", "a.b == 1").unwrap().with_outcome("[1]").is_err());
+	}
+
+	#[test]
+	fn test_rule_evaluate_with_options() {
+		let rule = Rule::new("exact", "binary.entropy == 7.5").unwrap();
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(7.501));
+
+		assert!(!rule.evaluate(&ctx).unwrap());
+		let tolerant = EvalOptions::new().with_numeric_tolerance(0.01);
+		assert!(rule.evaluate_with_options(&ctx, &tolerant).unwrap());
+		let expired = EvalOptions::new().with_timeout(std::time::Duration::ZERO);
+		assert!(matches!(rule.evaluate_with_options(&ctx, &expired), Err(EvalError::Timeout)));
+	}
+}
+
+// endregion: --- Tests
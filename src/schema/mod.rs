@@ -4,10 +4,18 @@
 //! allowing products to define their data models in .hel schema files
 //! instead of implementing resolvers in Rust code.
 
-use std::collections::BTreeMap;
-use std::sync::Arc;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
 
+use core::cmp::Ordering;
+
+use crate::prelude::*;
+use crate::rewrite::rewrite_ast;
+use crate::{AstNode, Value};
+
+#[cfg(feature = "std")]
 pub mod package;
+#[cfg(feature = "std")]
 pub use package::{PackageError, PackageManifest, PackageRegistry, SchemaPackage, TypeEnvironment};
 
 /// Field type definition
@@ -17,9 +25,41 @@ pub enum FieldType {
 	String,
 	Number,
 	List(Box<FieldType>),
-	Map(Box<FieldType>),
+	/// Map with string keys (`Map<String, T>`, or an enum or alias naming strings) and `T` values
+	Map {
+		key: Box<FieldType>,
+		value: Box<FieldType>,
+	},
 	/// Reference to another type
 	TypeRef(Arc<str>),
+	/// Named alias (`alias Sha256 = String`), resolved to its target
+	Alias {
+		name: Arc<str>,
+		target: Box<FieldType>,
+	},
+}
+
+impl FieldType {
+	/// The type with aliases resolved (`Sha256` -> `String`); lists and maps keep their aliased items
+	pub fn resolved(&self) -> &FieldType {
+		match self {
+			FieldType::Alias { target, .. } => target.resolved(),
+			other => other,
+		}
+	}
+}
+
+impl core::fmt::Display for FieldType {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			FieldType::Bool => write!(f, "Bool"),
+			FieldType::String => write!(f, "String"),
+			FieldType::Number => write!(f, "Number"),
+			FieldType::List(inner) => write!(f, "List<{}>", inner),
+			FieldType::Map { key, value } => write!(f, "Map<{}, {}>", key, value),
+			FieldType::TypeRef(name) | FieldType::Alias { name, .. } => write!(f, "{}", name),
+		}
+	}
 }
 
 /// Field definition in a schema
@@ -32,23 +72,93 @@ pub struct FieldDef {
 }
 
 /// Type definition in a schema
+///
+/// For a type declared with `extends`, `fields` holds the merged fields: the base
+/// type's fields first (overrides in place), then the type's own.
 #[derive(Debug, Clone)]
 pub struct TypeDef {
 	pub name: Arc<str>,
 	pub fields: Vec<FieldDef>,
 	pub description: Option<Arc<str>>,
+	/// Base type (`type ElfBinary extends Binary { ... }`)
+	pub extends: Option<Arc<str>>,
+}
+
+impl TypeDef {
+	/// Get a field definition by name
+	pub fn field(&self, name: &str) -> Option<&FieldDef> {
+		self.fields.iter().find(|f| f.name.as_ref() == name)
+	}
+}
+
+/// Enum variant: a symbolic name and the constant it stands for
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+	pub name: Arc<str>,
+	pub value: Value,
+}
+
+/// Enum definition in a schema
+///
+/// Expressions reference variants as `Enum.Variant` (e.g., `lead.stage == Stage.Won`).
+#[derive(Debug, Clone)]
+pub struct EnumDef {
+	pub name: Arc<str>,
+	pub variants: Vec<EnumVariant>,
+	pub description: Option<Arc<str>>,
+}
+
+impl EnumDef {
+	/// Get a variant by name
+	pub fn variant(&self, name: &str) -> Option<&EnumVariant> {
+		self.variants.iter().find(|v| v.name.as_ref() == name)
+	}
+}
+
+/// Type alias definition in a schema (`alias Sha256 = String`)
+///
+/// Fields declared with an alias keep its name (`FieldType::Alias`), so it shows up
+/// in messages and docs while values are checked against the target.
+#[derive(Debug, Clone)]
+pub struct AliasDef {
+	pub name: Arc<str>,
+	pub target: FieldType,
+	pub description: Option<Arc<str>>,
 }
 
-/// Schema definition containing all types
+/// Field rename or move declared in a schema (`migrate Binary.arch -> Binary.architecture @ 0.2.0`)
+///
+/// Rules written against a package version older than `version` still reference
+/// `from_type.from_field`; `Schema::migrate_ast` rewrites them to `to_type.to_field`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMigration {
+	pub from_type: Arc<str>,
+	pub from_field: Arc<str>,
+	pub to_type: Arc<str>,
+	pub to_field: Arc<str>,
+	/// Package version that introduced the new name
+	pub version: Arc<str>,
+}
+
+/// Schema definition containing all types, enums, aliases and field migrations
 #[derive(Debug, Clone)]
 pub struct Schema {
 	pub types: BTreeMap<Arc<str>, TypeDef>,
+	pub enums: BTreeMap<Arc<str>, EnumDef>,
+	pub aliases: BTreeMap<Arc<str>, AliasDef>,
+	/// Field migrations in declaration order
+	pub migrations: Vec<FieldMigration>,
 }
 
 impl Schema {
 	/// Create an empty schema
 	pub fn new() -> Self {
-		Self { types: BTreeMap::new() }
+		Self {
+			types: BTreeMap::new(),
+			enums: BTreeMap::new(),
+			aliases: BTreeMap::new(),
+			migrations: Vec::new(),
+		}
 	}
 
 	/// Add a type definition to the schema
@@ -61,30 +171,308 @@ impl Schema {
 		self.types.get(name)
 	}
 
+	/// Add an enum definition to the schema
+	pub fn add_enum(&mut self, enum_def: EnumDef) {
+		self.enums.insert(enum_def.name.clone(), enum_def);
+	}
+
+	/// Get an enum definition by name
+	pub fn get_enum(&self, name: &str) -> Option<&EnumDef> {
+		self.enums.get(name)
+	}
+
+	/// Get an alias definition by name
+	pub fn get_alias(&self, name: &str) -> Option<&AliasDef> {
+		self.aliases.get(name)
+	}
+
+	/// Markdown catalog of the facts rule authors can reference
+	///
+	/// Lists each type as a fact object (`binary` for `Binary`) with a table of its
+	/// attributes, types and descriptions, followed by each enum with its constants and
+	/// a table of aliases. Types, enums and aliases appear in name order, fields in
+	/// declaration order; fields declared with an alias show the alias name.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::parse_schema;
+	///
+	/// let schema = parse_schema("/// A scanned executable\ntype Binary {\n  /// Shannon entropy\n  entropy: Number\n}\n").unwrap();
+	/// let catalog = schema.to_markdown_catalog();
+	/// assert!(catalog.contains("A scanned executable"));
+	/// assert!(catalog.contains("| `binary.entropy` | `Number` | Shannon entropy |"));
+	/// ```
+	pub fn to_markdown_catalog(&self) -> String {
+		let mut out = String::from("# Fact Catalog\n");
+		for type_def in self.types.values() {
+			let object = type_def.name.to_ascii_lowercase();
+			out.push_str(&format!("\n## `{}` ({})\n\n", object, type_def.name));
+			if let Some(base) = &type_def.extends {
+				out.push_str(&format!("Extends `{}`.\n\n", base));
+			}
+			if let Some(description) = &type_def.description {
+				out.push_str(&format!("{}\n\n", description));
+			}
+			out.push_str("| Attribute | Type | Description |\n| --- | --- | --- |\n");
+			for field in &type_def.fields {
+				let optional = if field.optional { "?" } else { "" };
+				out.push_str(&format!(
+					"| `{}.{}` | `{}{}` | {} |\n",
+					object,
+					field.name,
+					field.field_type,
+					optional,
+					table_cell(field.description.as_deref())
+				));
+			}
+		}
+		for enum_def in self.enums.values() {
+			out.push_str(&format!("\n## Enum `{}`\n\n", enum_def.name));
+			if let Some(description) = &enum_def.description {
+				out.push_str(&format!("{}\n\n", description));
+			}
+			out.push_str("| Constant | Value |\n| --- | --- |\n");
+			for variant in &enum_def.variants {
+				let value = match &variant.value {
+					Value::String(s) => format!("{:?}", s),
+					other => other.to_string(),
+				};
+				out.push_str(&format!("| `{}.{}` | `{}` |\n", enum_def.name, variant.name, value));
+			}
+		}
+		if !self.aliases.is_empty() {
+			out.push_str("\n## Aliases\n\n| Alias | Type | Description |\n| --- | --- | --- |\n");
+			for alias in self.aliases.values() {
+				out.push_str(&format!(
+					"| `{}` | `{}` | {} |\n",
+					alias.name,
+					alias.target,
+					table_cell(alias.description.as_deref())
+				));
+			}
+		}
+		out
+	}
+
 	/// Validate that all type references are defined
 	pub fn validate(&self) -> Result<(), String> {
 		for type_def in self.types.values() {
 			for field in &type_def.fields {
 				self.validate_field_type(&field.field_type)?;
 			}
+			if let Some(base) = &type_def.extends {
+				self.validate_extends(type_def, base)?;
+			}
+		}
+		for alias in self.aliases.values() {
+			self.validate_field_type(&alias.target)?;
+		}
+		for migration in &self.migrations {
+			let declared = self
+				.types
+				.get(&migration.to_type)
+				.is_some_and(|t| t.field(&migration.to_field).is_some());
+			let migrated_again = self
+				.migrations
+				.iter()
+				.any(|m| m.from_type == migration.to_type && m.from_field == migration.to_field);
+			if !declared && !migrated_again {
+				return Err(format!(
+					"Migration target {}.{} is not declared",
+					migration.to_type, migration.to_field
+				));
+			}
+		}
+		Ok(())
+	}
+
+	/// Rewrite a rule written against package version `from_version` to this schema
+	///
+	/// Applies, oldest version first, every migration introduced after `from_version`,
+	/// so chained renames (`a -> b @ 0.2.0`, `b -> c @ 0.3.0`) land on the latest name.
+	/// Attributes match a migration when the object names its source type
+	/// (case-insensitively, as in `validate_fact`); moved fields take the lower-cased
+	/// target type as object. Versions compare numerically component by component.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{parse_expression, parse_schema};
+	///
+	/// let schema = parse_schema("type Binary {\n  architecture: String\n}\nmigrate Binary.arch -> Binary.architecture @ 0.2.0\n").unwrap();
+	/// let ast = schema.migrate_ast(parse_expression(r#"binary.arch == "x86_64""#).unwrap(), "0.1.0");
+	/// assert_eq!(ast, parse_expression(r#"binary.architecture == "x86_64""#).unwrap());
+	/// ```
+	pub fn migrate_ast(&self, ast: AstNode, from_version: &str) -> AstNode {
+		let mut pending: Vec<&FieldMigration> = self
+			.migrations
+			.iter()
+			.filter(|m| compare_versions(&m.version, from_version) == Ordering::Greater)
+			.collect();
+		pending.sort_by(|a, b| compare_versions(&a.version, &b.version));
+
+		pending.into_iter().fold(ast, |ast, migration| {
+			let mut rename = |node: &AstNode| -> Option<AstNode> {
+				let AstNode::Attribute { object, field } = node else {
+					return None;
+				};
+				if !object.eq_ignore_ascii_case(&migration.from_type) || *field != migration.from_field {
+					return None;
+				}
+				let object = if migration.to_type == migration.from_type {
+					object.clone()
+				} else {
+					migration.to_type.to_ascii_lowercase().into()
+				};
+				Some(AstNode::Attribute {
+					object,
+					field: migration.to_field.clone(),
+				})
+			};
+			rewrite_ast(ast, &mut rename)
+		})
+	}
+
+	/// Check a fact (`object.field`) against the declared types
+	///
+	/// The object must name a type (case-insensitively, as in `TypeEnvironment::type_for_object`)
+	/// declaring the field, and the value must match the field type: list items and map
+	/// values recursively, enum fields one of the variant values, type fields a map with
+	/// the type's fields. `Null` is accepted only for optional fields.
+	pub fn validate_fact(&self, key: &str, value: &Value) -> Result<(), String> {
+		let (object, field) = key
+			.split_once('.')
+			.ok_or_else(|| format!("Fact `{}` is not of the form object.field", key))?;
+		let type_def = self
+			.types
+			.values()
+			.find(|t| t.name.eq_ignore_ascii_case(object))
+			.ok_or_else(|| format!("Fact `{}`: no type declared for object `{}`", key, object))?;
+		let field_def = type_def
+			.field(field)
+			.ok_or_else(|| format!("Fact `{}`: type {} has no field `{}`", key, type_def.name, field))?;
+		self.validate_field_value(key, field_def, value)
+	}
+
+	fn validate_field_value(&self, path: &str, field: &FieldDef, value: &Value) -> Result<(), String> {
+		match value {
+			Value::Null if field.optional => Ok(()),
+			_ => self.validate_value(path, &field.field_type, value),
+		}
+	}
+
+	fn validate_value(&self, path: &str, field_type: &FieldType, value: &Value) -> Result<(), String> {
+		let mismatch = || format!("Fact `{}`: expected {}, got {}", path, field_type, value.type_name());
+		match (field_type.resolved(), value) {
+			(FieldType::Bool, Value::Bool(_))
+			| (FieldType::String, Value::String(_))
+			| (FieldType::Number, Value::Number(_)) => Ok(()),
+			(FieldType::List(inner), Value::List(items)) => items
+				.iter()
+				.enumerate()
+				.try_for_each(|(i, item)| self.validate_value(&format!("{}[{}]", path, i), inner, item)),
+			(FieldType::Map { key: key_type, value: value_type }, Value::Map(entries)) => {
+				entries.iter().try_for_each(|(key, item)| {
+					let path = format!("{}.{}", path, key);
+					self.validate_value(&path, key_type, &Value::String(key.clone()))?;
+					self.validate_value(&path, value_type, item)
+				})
+			}
+			(FieldType::TypeRef(name), _) => {
+				if let Some(enum_def) = self.enums.get(name) {
+					return match enum_def.variants.iter().any(|v| &v.value == value) {
+						true => Ok(()),
+						false => Err(format!("Fact `{}`: {} is not a variant of {}", path, value, name)),
+					};
+				}
+				let type_def = self.types.get(name).ok_or_else(|| format!("Undefined type reference: {}", name))?;
+				let Value::Map(entries) = value else {
+					return Err(mismatch());
+				};
+				if let Some(key) = entries.keys().find(|k| type_def.field(k).is_none()) {
+					return Err(format!("Fact `{}`: type {} has no field `{}`", path, name, key));
+				}
+				type_def.fields.iter().try_for_each(|field| {
+					let path = format!("{}.{}", path, field.name);
+					match entries.get(&field.name) {
+						Some(item) => self.validate_field_value(&path, field, item),
+						None if field.optional => Ok(()),
+						None => Err(format!("Fact `{}`: missing required field", path)),
+					}
+				})
+			}
+			_ => Err(mismatch()),
+		}
+	}
+
+	/// A derived type must extend a declared type, without cycles, and keep every
+	/// base field with a compatible type (see `check_override`)
+	fn validate_extends(&self, type_def: &TypeDef, base: &str) -> Result<(), String> {
+		let mut seen = vec![type_def.name.clone()];
+		let mut next = Some(base);
+		while let Some(name) = next {
+			let base_def = self
+				.types
+				.get(name)
+				.ok_or_else(|| format!("Type {} extends undefined type {}", type_def.name, name))?;
+			if seen.contains(&base_def.name) {
+				return Err(format!("Circular inheritance: {}", type_def.name));
+			}
+			seen.push(base_def.name.clone());
+			next = base_def.extends.as_deref();
+		}
+
+		for base_field in &self.types[base].fields {
+			let field = type_def.field(&base_field.name).ok_or_else(|| {
+				format!("Type {} is missing field `{}` inherited from {}", type_def.name, base_field.name, base)
+			})?;
+			check_override(&type_def.name, base_field, field)?;
 		}
 		Ok(())
 	}
 
+	/// Map keys are strings: the key type must be `String` or an enum with string values
+	fn validate_map_key(&self, key: &FieldType) -> Result<(), String> {
+		let string_keys = match key.resolved() {
+			FieldType::String => true,
+			FieldType::TypeRef(name) => self
+				.enums
+				.get(name)
+				.is_some_and(|e| e.variants.iter().all(|v| matches!(v.value, Value::String(_)))),
+			_ => false,
+		};
+		match string_keys {
+			true => Ok(()),
+			false => Err(format!("Map key type must be String or a string enum, got {}", key)),
+		}
+	}
+
 	fn validate_field_type(&self, field_type: &FieldType) -> Result<(), String> {
 		match field_type {
 			FieldType::TypeRef(name) => {
-				if !self.types.contains_key(name) {
+				if !self.types.contains_key(name) && !self.enums.contains_key(name) {
 					return Err(format!("Undefined type reference: {}", name));
 				}
 				Ok(())
 			}
-			FieldType::List(inner) | FieldType::Map(inner) => self.validate_field_type(inner),
+			FieldType::List(inner) => self.validate_field_type(inner),
+			FieldType::Map { key, value } => {
+				self.validate_field_type(key)?;
+				self.validate_map_key(key)?;
+				self.validate_field_type(value)
+			}
+			FieldType::Alias { target, .. } => self.validate_field_type(target),
 			_ => Ok(()),
 		}
 	}
 }
 
+/// A description as a single Markdown table cell
+fn table_cell(description: Option<&str>) -> String {
+	description.map_or_else(String::new, |d| d.replace('\n', " ").replace('|', "\\|"))
+}
+
 impl Default for Schema {
 	fn default() -> Self {
 		Self::new()
@@ -110,22 +498,81 @@ impl Default for Schema {
 /// type Enrichment {
 ///     confidence: Number
 ///     source: String
-///     data: Map<String>
+///     data: Map<String, String>
+/// }
+///
+/// enum Stage {
+///     New
+///     Won = "closed_won"
+///     Lost = "closed_lost"
 /// }
 /// ```
+///
+/// An enum variant without a value stands for its own name as a string; values may be
+/// string, number or boolean literals. Fields may use an enum as their type.
+///
+/// `alias Sha256 = String` names a field type (`List<...>`, `Map<...>`, a type, an enum
+/// or another alias); fields declared with it become `FieldType::Alias`. Aliases may be
+/// used before they are declared but must not be circular.
+///
+/// `migrate Binary.arch -> Binary.architecture @ 0.2.0` records that a field was renamed
+/// or moved in version `0.2.0`; the target field must be declared or migrated again by a later
+/// declaration (see `Schema::migrate_ast`).
+///
+/// `///` doc comments directly above a type, enum, alias or field become its `description`
+/// (consecutive lines joined with newlines); doc comments anywhere else are ignored.
 pub fn parse_schema(input: &str) -> Result<Schema, String> {
 	let mut schema = Schema::new();
 	let mut current_type: Option<TypeDef> = None;
+	let mut current_enum: Option<EnumDef> = None;
 	let mut in_type_block = false;
+	let mut doc: Vec<&str> = Vec::new();
 
 	for line in input.lines() {
 		let line = line.trim();
 
+		// Collect doc comments for the next definition
+		if let Some(text) = line.strip_prefix("///") {
+			doc.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+			continue;
+		}
+
 		// Skip empty lines and comments
 		if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
 			continue;
 		}
 
+		// Alias definition
+		if !in_type_block && current_enum.is_none() {
+			if let Some(rest) = line.strip_prefix("alias ") {
+				let (name, target) = rest
+					.split_once('=')
+					.ok_or_else(|| format!("Invalid alias definition: {}", line))?;
+				let name = name.trim();
+				if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+					return Err(format!("Invalid alias definition: {}", line));
+				}
+				if schema.aliases.contains_key(name) {
+					return Err(format!("Duplicate alias {}", name));
+				}
+				schema.aliases.insert(
+					name.into(),
+					AliasDef {
+						name: name.into(),
+						target: parse_field_type(target)?,
+						description: take_doc(&mut doc),
+					},
+				);
+				continue;
+			}
+
+			if let Some(rest) = line.strip_prefix("migrate ") {
+				doc.clear();
+				schema.migrations.push(parse_migration(rest).ok_or_else(|| format!("Invalid migration: {}", line))?);
+				continue;
+			}
+		}
+
 		// Type definition start
 		if line.starts_with("type ") {
 			// Save previous type if any
@@ -134,25 +581,63 @@ pub fn parse_schema(input: &str) -> Result<Schema, String> {
 			}
 
 			let parts: Vec<&str> = line.split_whitespace().collect();
-			if parts.len() < 3 || parts[2] != "{" {
-				return Err(format!("Invalid type definition: {}", line));
-			}
+			let extends = match parts.as_slice() {
+				[_, _, "{", ..] => None,
+				[_, _, "extends", base, "{", ..] => Some(Arc::from(*base)),
+				_ => return Err(format!("Invalid type definition: {}", line)),
+			};
 
 			current_type = Some(TypeDef {
 				name: parts[1].into(),
 				fields: Vec::new(),
-				description: None,
+				description: take_doc(&mut doc),
+				extends,
 			});
 			in_type_block = true;
 			continue;
 		}
 
-		// Type block end
+		// Enum definition start
+		if line.starts_with("enum ") {
+			if let Some(type_def) = current_type.take() {
+				schema.add_type(type_def);
+			}
+
+			let parts: Vec<&str> = line.split_whitespace().collect();
+			if parts.len() < 3 || parts[2] != "{" {
+				return Err(format!("Invalid enum definition: {}", line));
+			}
+
+			current_enum = Some(EnumDef {
+				name: parts[1].into(),
+				variants: Vec::new(),
+				description: take_doc(&mut doc),
+			});
+			in_type_block = false;
+			continue;
+		}
+
+		// Type or enum block end
 		if line == "}" {
 			if let Some(type_def) = current_type.take() {
 				schema.add_type(type_def);
 			}
+			if let Some(enum_def) = current_enum.take() {
+				schema.add_enum(enum_def);
+			}
 			in_type_block = false;
+			doc.clear();
+			continue;
+		}
+
+		// Enum variant
+		if let Some(enum_def) = current_enum.as_mut() {
+			doc.clear();
+			let variant = parse_enum_variant(line.trim_end_matches(','))?;
+			if enum_def.variant(&variant.name).is_some() {
+				return Err(format!("Duplicate variant {} in enum {}", variant.name, enum_def.name));
+			}
+			enum_def.variants.push(variant);
 			continue;
 		}
 
@@ -180,21 +665,245 @@ pub fn parse_schema(input: &str) -> Result<Schema, String> {
 					name: name.trim().into(),
 					field_type,
 					optional,
-					description: None,
+					description: take_doc(&mut doc),
 				});
 			}
 		}
 	}
 
-	// Save last type if any
+	// Save last type or enum if any
 	if let Some(type_def) = current_type {
 		schema.add_type(type_def);
 	}
+	if let Some(enum_def) = current_enum {
+		schema.add_enum(enum_def);
+	}
 
+	resolve_aliases(&mut schema)?;
+	resolve_extends(&mut schema)?;
 	schema.validate()?;
 	Ok(schema)
 }
 
+/// Parse `Type.field -> Type.field @ version`
+fn parse_migration(input: &str) -> Option<FieldMigration> {
+	let (paths, version) = input.split_once('@')?;
+	let (from, to) = paths.split_once("->")?;
+	let path = |s: &str| -> Option<(Arc<str>, Arc<str>)> {
+		let (type_name, field) = s.trim().split_once('.')?;
+		let valid = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+		(valid(type_name) && valid(field)).then(|| (type_name.into(), field.into()))
+	};
+	let (from_type, from_field) = path(from)?;
+	let (to_type, to_field) = path(to)?;
+	let version = version.trim();
+	if version.is_empty() || !version.split('.').all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit())) {
+		return None;
+	}
+	Some(FieldMigration {
+		from_type,
+		from_field,
+		to_type,
+		to_field,
+		version: version.into(),
+	})
+}
+
+/// Compare dotted numeric versions (`0.10.0` > `0.9.1`; missing components count as 0)
+fn compare_versions(a: &str, b: &str) -> Ordering {
+	let parts = |v: &str| -> Vec<u64> { v.split('.').map(|p| p.trim().parse().unwrap_or(0)).collect() };
+	let (a, b) = (parts(a), parts(b));
+	(0..a.len().max(b.len()))
+		.map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+		.find(|o| o.is_ne())
+		.unwrap_or(Ordering::Equal)
+}
+
+/// Merge base type fields into derived types
+///
+/// A derived type may redeclare a base field to document it differently or make it
+/// required; see `check_override`. A redeclared field without a doc comment keeps
+/// the base description.
+fn resolve_extends(schema: &mut Schema) -> Result<(), String> {
+	let mut merged: BTreeMap<Arc<str>, Vec<FieldDef>> = BTreeMap::new();
+	let names: Vec<Arc<str>> = schema.types.keys().cloned().collect();
+	for name in names {
+		merged_fields(schema, &name, &mut merged, &mut Vec::new())?;
+	}
+	for (name, fields) in merged {
+		if let Some(type_def) = schema.types.get_mut(&name) {
+			type_def.fields = fields;
+		}
+	}
+	Ok(())
+}
+
+fn merged_fields(
+	schema: &Schema,
+	name: &Arc<str>,
+	merged: &mut BTreeMap<Arc<str>, Vec<FieldDef>>,
+	stack: &mut Vec<Arc<str>>,
+) -> Result<Vec<FieldDef>, String> {
+	if let Some(fields) = merged.get(name) {
+		return Ok(fields.clone());
+	}
+	let type_def = &schema.types[name];
+	let Some(base) = &type_def.extends else {
+		return Ok(type_def.fields.clone());
+	};
+	if stack.contains(name) {
+		return Err(format!("Circular inheritance: {}", name));
+	}
+	if !schema.types.contains_key(base) {
+		return Err(format!("Type {} extends undefined type {}", name, base));
+	}
+
+	stack.push(name.clone());
+	let mut fields = merged_fields(schema, base, merged, stack)?;
+	stack.pop();
+	for field in &type_def.fields {
+		match fields.iter_mut().find(|f| f.name == field.name) {
+			Some(base_field) => {
+				check_override(name, base_field, field)?;
+				let description = field.description.clone().or_else(|| base_field.description.clone());
+				*base_field = FieldDef {
+					description,
+					..field.clone()
+				};
+			}
+			None => fields.push(field.clone()),
+		}
+	}
+	merged.insert(name.clone(), fields.clone());
+	Ok(fields)
+}
+
+/// A redeclared base field must keep its type (aliases resolved) and may only go from optional to required
+fn check_override(type_name: &str, base_field: &FieldDef, field: &FieldDef) -> Result<(), String> {
+	if field.field_type.resolved() != base_field.field_type.resolved() {
+		return Err(format!(
+			"Type {} overrides field `{}` as {}, but the base type declares {}",
+			type_name, field.name, field.field_type, base_field.field_type
+		));
+	}
+	if field.optional && !base_field.optional {
+		return Err(format!("Type {} makes required field `{}` optional", type_name, field.name));
+	}
+	Ok(())
+}
+
+/// Replace references to aliases with `FieldType::Alias` carrying the resolved target
+fn resolve_aliases(schema: &mut Schema) -> Result<(), String> {
+	if schema.aliases.is_empty() {
+		return Ok(());
+	}
+	if let Some(name) = schema
+		.aliases
+		.keys()
+		.find(|name| schema.types.contains_key(*name) || schema.enums.contains_key(*name))
+	{
+		return Err(format!("Alias {} collides with a type or enum of the same name", name));
+	}
+
+	let declared: BTreeMap<Arc<str>, FieldType> = schema
+		.aliases
+		.iter()
+		.map(|(name, alias)| (name.clone(), alias.target.clone()))
+		.collect();
+	for alias in schema.aliases.values_mut() {
+		let mut seen = vec![alias.name.clone()];
+		alias.target = resolve_alias_refs(&alias.target, &declared, &mut seen)?;
+	}
+	for type_def in schema.types.values_mut() {
+		for field in &mut type_def.fields {
+			field.field_type = resolve_alias_refs(&field.field_type, &declared, &mut Vec::new())?;
+		}
+	}
+	Ok(())
+}
+
+fn resolve_alias_refs(
+	field_type: &FieldType,
+	aliases: &BTreeMap<Arc<str>, FieldType>,
+	seen: &mut Vec<Arc<str>>,
+) -> Result<FieldType, String> {
+	match field_type {
+		FieldType::TypeRef(name) => match aliases.get(name) {
+			Some(target) => {
+				if seen.contains(name) {
+					return Err(format!("Circular alias: {}", name));
+				}
+				seen.push(name.clone());
+				let target = resolve_alias_refs(target, aliases, seen)?;
+				seen.pop();
+				Ok(FieldType::Alias {
+					name: name.clone(),
+					target: Box::new(target),
+				})
+			}
+			None => Ok(field_type.clone()),
+		},
+		FieldType::List(inner) => Ok(FieldType::List(Box::new(resolve_alias_refs(inner, aliases, seen)?))),
+		FieldType::Map { key, value } => Ok(FieldType::Map {
+			key: Box::new(resolve_alias_refs(key, aliases, seen)?),
+			value: Box::new(resolve_alias_refs(value, aliases, seen)?),
+		}),
+		other => Ok(other.clone()),
+	}
+}
+
+/// Description from the collected doc comment lines, if any
+fn take_doc(doc: &mut Vec<&str>) -> Option<Arc<str>> {
+	if doc.is_empty() {
+		return None;
+	}
+	let description = doc.join("\n");
+	doc.clear();
+	Some(description.into())
+}
+
+fn parse_enum_variant(line: &str) -> Result<EnumVariant, String> {
+	let (name, value) = match line.split_once('=') {
+		Some((name, value)) => (name.trim(), Some(value.trim())),
+		None => (line.trim(), None),
+	};
+	if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+		return Err(format!("Invalid enum variant: {}", line));
+	}
+
+	let value = match value {
+		None => Value::String(name.into()),
+		Some("true") => Value::Bool(true),
+		Some("false") => Value::Bool(false),
+		Some(value) if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') => {
+			Value::String(value[1..value.len() - 1].into())
+		}
+		Some(value) => Value::Number(
+			value
+				.parse()
+				.map_err(|_| format!("Invalid enum variant value: {}", line))?,
+		),
+	};
+	Ok(EnumVariant {
+		name: name.into(),
+		value,
+	})
+}
+
+/// Split `K, V` at the comma outside any nested `<...>`
+fn split_type_args(args: &str) -> Option<(&str, &str)> {
+	let mut depth = 0usize;
+	for (i, c) in args.char_indices() {
+		match c {
+			'<' => depth += 1,
+			'>' => depth = depth.saturating_sub(1),
+			',' if depth == 0 => return Some((&args[..i], &args[i + 1..])),
+			_ => {}
+		}
+	}
+	None
+}
+
 fn parse_field_type(type_str: &str) -> Result<FieldType, String> {
 	let type_str = type_str.trim();
 
@@ -205,11 +914,17 @@ fn parse_field_type(type_str: &str) -> Result<FieldType, String> {
 		return Ok(FieldType::List(Box::new(inner_type)));
 	}
 
-	// Map<T>
+	// Map<K, V>, or Map<V> with String keys
 	if type_str.starts_with("Map<") && type_str.ends_with('>') {
 		let inner = &type_str[4..type_str.len() - 1];
-		let inner_type = parse_field_type(inner)?;
-		return Ok(FieldType::Map(Box::new(inner_type)));
+		let (key, value) = match split_type_args(inner) {
+			Some((key, value)) => (parse_field_type(key)?, parse_field_type(value)?),
+			None => (FieldType::String, parse_field_type(inner)?),
+		};
+		return Ok(FieldType::Map {
+			key: Box::new(key),
+			value: Box::new(value),
+		});
 	}
 
 	// Primitive types
@@ -299,6 +1014,287 @@ type Lead {
 		assert!(result.is_err());
 		assert!(result.unwrap_err().contains("Undefined type reference"));
 	}
+
+	#[test]
+	fn test_parse_schema_with_enums() {
+		let schema_text = r#"
+enum Stage {
+    New
+    Won = "closed_won",
+    Priority = 3
+}
+
+type Lead {
+    stage: Stage
+}
+		"#;
+
+		let schema = parse_schema(schema_text).expect("parse failed");
+		let stage = schema.get_enum("Stage").expect("Stage enum not found");
+		assert_eq!(stage.variants.len(), 3);
+		assert_eq!(stage.variant("New").unwrap().value, Value::String("New".into()));
+		assert_eq!(stage.variant("Won").unwrap().value, Value::String("closed_won".into()));
+		assert_eq!(stage.variant("Priority").unwrap().value, Value::Number(3.0));
+		assert_eq!(
+			schema.get_type("Lead").unwrap().fields[0].field_type,
+			FieldType::TypeRef("Stage".into())
+		);
+
+		assert!(parse_schema("enum Stage {\n  New\n  New\n}\n")
+			.unwrap_err()
+			.contains("Duplicate variant"));
+		assert!(parse_schema("enum Stage {\n  Won = closed\n}\n").is_err());
+	}
+
+	#[test]
+	fn test_doc_comments_and_catalog() {
+		let schema_text = r#"
+/// A scanned executable
+/// (one per sample)
+type Binary {
+    /// Shannon entropy, 0 to 8
+    entropy: Number
+    // not a doc comment
+    exports?: List<String>
+    /// Linked | packed
+    format: Format
+    /// dangling
+}
+
+/// Executable container format
+enum Format {
+    /// variant docs are ignored
+    Elf = "elf"
+    Pe = "pe"
+}
+"#;
+
+		let schema = parse_schema(schema_text).expect("parse failed");
+		let binary = schema.get_type("Binary").unwrap();
+		assert_eq!(binary.description.as_deref(), Some("A scanned executable\n(one per sample)"));
+		assert_eq!(binary.fields[0].description.as_deref(), Some("Shannon entropy, 0 to 8"));
+		assert_eq!(binary.fields[1].description, None);
+		assert_eq!(
+			schema.get_enum("Format").unwrap().description.as_deref(),
+			Some("Executable container format")
+		);
+
+		let catalog = schema.to_markdown_catalog();
+		assert_eq!(
+			catalog,
+			"# Fact Catalog\n\n## `binary` (Binary)\n\nA scanned executable\n(one per sample)\n\n\
+			 | Attribute | Type | Description |\n| --- | --- | --- |\n\
+			 | `binary.entropy` | `Number` | Shannon entropy, 0 to 8 |\n\
+			 | `binary.exports` | `List<String>?` |  |\n\
+			 | `binary.format` | `Format` | Linked \\| packed |\n\
+			 \n## Enum `Format`\n\nExecutable container format\n\n\
+			 | Constant | Value |\n| --- | --- |\n\
+			 | `Format.Elf` | `\"elf\"` |\n\
+			 | `Format.Pe` | `\"pe\"` |\n"
+		);
+	}
+
+	#[test]
+	fn test_type_aliases() {
+		let schema_text = r#"
+type Sample {
+    digest: Sha256
+    digests?: List<Sha256>
+    labels: Labels
+}
+
+/// Hex-encoded SHA-256 digest
+alias Sha256 = String
+alias Labels = Map<Label>
+alias Label = String
+"#;
+
+		let schema = parse_schema(schema_text).expect("parse failed");
+		let sample = schema.get_type("Sample").unwrap();
+		assert_eq!(sample.fields[0].field_type.to_string(), "Sha256");
+		assert_eq!(sample.fields[0].field_type.resolved(), &FieldType::String);
+		assert_eq!(sample.fields[1].field_type.to_string(), "List<Sha256>");
+		assert_eq!(
+			schema.get_alias("Sha256").unwrap().description.as_deref(),
+			Some("Hex-encoded SHA-256 digest")
+		);
+
+		// Values are checked against the target; messages name the alias
+		assert!(schema.validate_fact("sample.digest", &"ab12".into()).is_ok());
+		let labels = Value::Map([(Arc::from("family"), "emotet".into())].into());
+		assert!(schema.validate_fact("sample.labels", &labels).is_ok());
+		let err = schema.validate_fact("sample.digest", &Value::Number(1.0)).unwrap_err();
+		assert!(err.contains("expected Sha256, got Number"), "{}", err);
+
+		let catalog = schema.to_markdown_catalog();
+		assert!(catalog.contains("| `sample.digest` | `Sha256` |"), "{}", catalog);
+		assert!(catalog.contains("| `Sha256` | `String` | Hex-encoded SHA-256 digest |"), "{}", catalog);
+		assert!(catalog.contains("| `Labels` | `Map<String, Label>` |"), "{}", catalog);
+
+		assert!(parse_schema("alias A = B\nalias B = List<A>\n").unwrap_err().contains("Circular alias"));
+		assert!(parse_schema("alias A = Missing\n").unwrap_err().contains("Undefined type reference"));
+		assert!(parse_schema("alias A = String\nalias A = Number\n").unwrap_err().contains("Duplicate alias"));
+		assert!(parse_schema("alias Lead = String\ntype Lead {\n  id: Lead\n}\n")
+			.unwrap_err()
+			.contains("collides"));
+	}
+
+	#[test]
+	fn test_type_extends() {
+		let schema_text = r#"
+type ElfBinary extends Binary {
+    /// Program interpreter
+    interp: String
+    exports: List<String>
+}
+
+type Binary {
+    /// File format
+    format: String
+    exports?: List<String>
+}
+"#;
+
+		let schema = parse_schema(schema_text).expect("parse failed");
+		let elf = schema.get_type("ElfBinary").unwrap();
+		assert_eq!(elf.extends.as_deref(), Some("Binary"));
+		let names: Vec<&str> = elf.fields.iter().map(|f| f.name.as_ref()).collect();
+		assert_eq!(names, ["format", "exports", "interp"]);
+		assert_eq!(elf.fields[0].description.as_deref(), Some("File format"));
+		assert!(!elf.fields[1].optional);
+		assert!(schema.get_type("Binary").unwrap().field("interp").is_none());
+		assert!(schema.to_markdown_catalog().contains("## `elfbinary` (ElfBinary)\n\nExtends `Binary`.\n"));
+
+		let base = "type Binary {\n  format: String\n  exports?: List<String>\n}\n";
+		let err = |derived: &str| parse_schema(&format!("{}{}", base, derived)).unwrap_err();
+		assert!(err("type Pe extends Binary {\n  format: Number\n}\n").contains("overrides field `format`"));
+		assert!(err("type Pe extends Binary {\n  format?: String\n}\n").contains("optional"));
+		assert!(err("type Pe extends Missing {\n}\n").contains("undefined type Missing"));
+		assert!(err("type A extends B {\n}\ntype B extends A {\n}\n").contains("Circular inheritance"));
+		assert!(err("type Pe extends {\n}\n").contains("Invalid type definition"));
+
+		// Hand-built schemas are checked by `validate`
+		let mut schema = parse_schema(base).expect("parse failed");
+		schema.add_type(TypeDef {
+			name: "Pe".into(),
+			fields: Vec::new(),
+			description: None,
+			extends: Some("Binary".into()),
+		});
+		assert!(schema.validate().unwrap_err().contains("missing field `format`"));
+	}
+
+	#[test]
+	fn test_map_key_types() {
+		let schema_text = r#"
+enum Packer {
+    Upx = "upx"
+    Aspack = "aspack"
+}
+
+type Binary {
+    detected: Map<String, String>
+    legacy: Map<Number>
+    by_packer: Map<Packer, List<Number>>
+    nested: Map<String, Map<Packer, Bool>>
+}
+"#;
+
+		let schema = parse_schema(schema_text).expect("parse failed");
+		let binary = schema.get_type("Binary").unwrap();
+		let types: Vec<String> = binary.fields.iter().map(|f| f.field_type.to_string()).collect();
+		assert_eq!(
+			types,
+			[
+				"Map<String, String>",
+				"Map<String, Number>",
+				"Map<Packer, List<Number>>",
+				"Map<String, Map<Packer, Bool>>"
+			]
+		);
+
+		let map = |entries: &[(&str, Value)]| Value::Map(entries.iter().map(|(k, v)| (Arc::from(*k), v.clone())).collect());
+		let scores = Value::List(vec![1.0.into()]);
+		assert!(schema.validate_fact("binary.by_packer", &map(&[("upx", scores.clone())])).is_ok());
+		let err = schema.validate_fact("binary.by_packer", &map(&[("mpress", scores)])).unwrap_err();
+		assert!(err.contains("binary.by_packer.mpress"), "{}", err);
+		assert!(err.contains("not a variant of Packer"), "{}", err);
+
+		assert!(parse_schema("type A {\n  m: Map<Number, String>\n}\n").unwrap_err().contains("Map key type"));
+		assert!(parse_schema("enum N {\n  One = 1\n}\ntype A {\n  m: Map<N, String>\n}\n")
+			.unwrap_err()
+			.contains("Map key type"));
+	}
+
+	#[test]
+	fn test_validate_fact() {
+		let schema = parse_schema(
+			"enum Stage {\n  New\n  Won = \"closed_won\"\n}\n\ntype Contact {\n  email: String\n  name?: String\n}\n\ntype Lead {\n  stage: Stage\n  scores: List<Number>\n  owner?: Contact\n  tags: Map<String>\n}\n",
+		)
+		.expect("parse failed");
+		let contact = |entries: &[(&str, Value)]| {
+			Value::Map(entries.iter().map(|(k, v)| (Arc::from(*k), v.clone())).collect())
+		};
+
+		assert!(schema.validate_fact("lead.stage", &"closed_won".into()).is_ok());
+		assert!(schema.validate_fact("Lead.scores", &Value::List(vec![1.0.into(), 2.0.into()])).is_ok());
+		assert!(schema.validate_fact("lead.owner", &Value::Null).is_ok());
+		assert!(schema.validate_fact("lead.owner", &contact(&[("email", "a@b.c".into())])).is_ok());
+
+		let err = |key: &str, value: Value| schema.validate_fact(key, &value).unwrap_err();
+		assert!(err("lead.stage", "Won".into()).contains("not a variant of Stage"));
+		assert!(err("lead.scores", Value::List(vec![1.0.into(), "x".into()])).contains("lead.scores[1]"));
+		assert!(err("lead.tags", Value::Null).contains("expected Map<String, String>, got Null"));
+		assert!(err("lead.owner", contact(&[("name", "A".into())])).contains("lead.owner.email"));
+		assert!(err("lead.owner", contact(&[("email", "a".into()), ("phone", "1".into())])).contains("phone"));
+		assert!(err("lead.region", "EU".into()).contains("no field `region`"));
+		assert!(err("account.id", "1".into()).contains("no type declared"));
+		assert!(err("stage", "New".into()).contains("object.field"));
+	}
+
+	#[test]
+	fn test_field_migrations() {
+		use crate::parse_expression;
+
+		let schema = parse_schema(
+			r#"
+type Binary {
+    cpu_arch: String
+}
+
+type Signature {
+    signer: String
+}
+
+migrate Binary.architecture -> Binary.cpu_arch @ 0.10.0
+migrate Binary.arch -> Binary.architecture @ 0.2.0
+migrate Binary.signer -> Signature.signer @ 0.3
+"#,
+		)
+		.expect("Failed to parse schema");
+		assert_eq!(schema.migrations.len(), 3);
+		assert_eq!(schema.migrations[1].version.as_ref(), "0.2.0");
+
+		let rule = r#"binary.arch == "x86_64" AND Binary.signer == "ACME" AND binary.format == "pe""#;
+		let migrate = |from: &str| schema.migrate_ast(parse_expression(rule).unwrap(), from);
+		assert_eq!(
+			migrate("0.1.0"),
+			parse_expression(r#"binary.cpu_arch == "x86_64" AND signature.signer == "ACME" AND binary.format == "pe""#)
+				.unwrap()
+		);
+		// 0.9.0 is older than 0.10.0 but newer than 0.2.0 and 0.3
+		assert_eq!(
+			migrate("0.9.0"),
+			parse_expression(r#"binary.arch == "x86_64" AND Binary.signer == "ACME" AND binary.format == "pe""#).unwrap()
+		);
+		assert_eq!(migrate("0.10.0"), parse_expression(rule).unwrap());
+
+		let err = parse_schema("type Binary {\n  arch: String\n}\nmigrate Binary.arch -> Binary.architecture @ 0.2.0\n")
+			.unwrap_err();
+		assert!(err.contains("Binary.architecture is not declared"), "{}", err);
+		let err = parse_schema("migrate Binary.arch -> Binary.cpu @ latest\n").unwrap_err();
+		assert!(err.contains("Invalid migration"), "{}", err);
+	}
 }
 
 // Additional integration tests
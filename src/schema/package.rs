@@ -15,11 +15,19 @@
 //! - Error messages include package/file/line context
 
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use super::{parse_schema, Schema, TypeDef};
+use super::{parse_schema, EnumDef, FieldType, Schema, TypeDef};
+use crate::Value;
+use crate::analysis::{field_completion, Completion, CompletionKind};
+use crate::audit::to_hex;
+use crate::diagnostics::diagnostic_json;
+use crate::format::{format_ast, FormatOptions};
+use crate::{parse_expression, HelError};
 
 // region:    --- Package Manifest
 
@@ -38,6 +46,20 @@ pub struct PackageManifest {
 	/// Optional built-ins namespace (defaults to package name)
 	#[serde(default)]
 	pub builtins_namespace: Option<String>,
+	/// Rule files shipped with the package, one condition per file (no `let`/`const`
+	/// bindings or `then { ... }` outcome); ids are `package/file-stem` and must be unique
+	#[serde(default)]
+	pub rules: Vec<String>,
+	/// Local overrides for dependencies: package_name -> patch (see `PackageRegistry::resolve_all`)
+	#[serde(default)]
+	pub patch: BTreeMap<String, DependencyPatch>,
+}
+
+/// Redirects a dependency to a local directory (`core-types = { path = "../core-types" }`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyPatch {
+	/// Package directory, relative to the patching package's root
+	pub path: String,
 }
 
 impl PackageManifest {
@@ -55,6 +77,50 @@ impl PackageManifest {
 	}
 }
 
+/// Workspace manifest (hel-workspace.toml) listing member package directories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceManifest {
+	/// Member package directories, relative to the workspace root; a trailing `/*`
+	/// (e.g., `packages/*`) includes every subdirectory with a hel-package.toml
+	pub members: Vec<String>,
+}
+
+impl WorkspaceManifest {
+	/// Parse manifest from TOML string
+	pub fn from_toml(content: &str) -> Result<Self, PackageError> {
+		toml::from_str(content).map_err(|e| PackageError::ManifestParse(e.to_string()))
+	}
+
+	/// Load manifest from file
+	pub fn from_file(path: &Path) -> Result<Self, PackageError> {
+		let content = std::fs::read_to_string(path).map_err(|e| {
+			PackageError::Io(format!("Failed to read workspace manifest at {}: {}", path.display(), e))
+		})?;
+		Self::from_toml(&content)
+	}
+
+	/// Member package directories under `root`, in listed order (globs sorted by name)
+	pub fn member_dirs(&self, root: &Path) -> Result<Vec<PathBuf>, PackageError> {
+		let mut dirs = Vec::new();
+		for member in &self.members {
+			let Some(parent) = member.strip_suffix("/*") else {
+				dirs.push(root.join(member));
+				continue;
+			};
+			let parent = root.join(parent);
+			let entries = std::fs::read_dir(&parent)
+				.map_err(|e| PackageError::Io(format!("Failed to read {}: {}", parent.display(), e)))?;
+			let mut matched: Vec<PathBuf> = entries
+				.filter_map(|entry| entry.ok().map(|e| e.path()))
+				.filter(|path| path.join("hel-package.toml").is_file())
+				.collect();
+			matched.sort();
+			dirs.extend(matched);
+		}
+		Ok(dirs)
+	}
+}
+
 // endregion: --- Package Manifest
 
 // region:    --- Loaded Package
@@ -70,13 +136,33 @@ pub struct SchemaPackage {
 	pub imports: Vec<String>,
 	/// Package root directory
 	pub root_path: PathBuf,
+	/// Rules read from the manifest's rule files (not yet parsed)
+	pub rules: Vec<PackageRule>,
+	/// SHA-256 (hex) over the manifest, schema and rule files, in manifest order
+	pub content_hash: String,
+}
+
+/// A rule shipped with a package
+#[derive(Debug, Clone)]
+pub struct PackageRule {
+	/// Rule id (`package/file-stem`)
+	pub id: String,
+	/// Rule file, relative to the package root
+	pub file: String,
+	/// Rule condition source
+	pub source: String,
 }
 
 impl SchemaPackage {
 	/// Load a package from a directory containing hel-package.toml
 	pub fn from_directory(dir: &Path) -> Result<Self, PackageError> {
 		let manifest_path = dir.join("hel-package.toml");
-		let manifest = PackageManifest::from_file(&manifest_path)?;
+		let manifest_text = std::fs::read_to_string(&manifest_path).map_err(|e| {
+			PackageError::Io(format!("Failed to read manifest at {}: {}", manifest_path.display(), e))
+		})?;
+		let manifest = PackageManifest::from_toml(&manifest_text)?;
+		let mut hasher = Sha256::new();
+		hash_file(&mut hasher, "hel-package.toml", &manifest_text);
 
 		let mut combined_schema = Schema::new();
 		let mut all_imports = Vec::new();
@@ -87,6 +173,7 @@ impl SchemaPackage {
 			let content = std::fs::read_to_string(&schema_path).map_err(|e| {
 				PackageError::Io(format!("Failed to read schema {}: {}", schema_path.display(), e))
 			})?;
+			hash_file(&mut hasher, schema_file, &content);
 
 			// Parse imports from schema content (simple line-based for now)
 			let imports = extract_imports(&content);
@@ -111,6 +198,58 @@ impl SchemaPackage {
 				}
 				combined_schema.types.insert(name, typedef);
 			}
+			for (name, enum_def) in parsed.enums {
+				if combined_schema.types.contains_key(&name) || combined_schema.enums.contains_key(&name) {
+					return Err(PackageError::DuplicateType {
+						package: manifest.name.clone(),
+						type_name: name.to_string(),
+					});
+				}
+				combined_schema.enums.insert(name, enum_def);
+			}
+			for (name, alias) in parsed.aliases {
+				if combined_schema.aliases.contains_key(&name) {
+					return Err(PackageError::DuplicateType {
+						package: manifest.name.clone(),
+						type_name: name.to_string(),
+					});
+				}
+				combined_schema.aliases.insert(name, alias);
+			}
+			combined_schema.migrations.extend(parsed.migrations);
+		}
+
+		// Read rule files; they are parsed when compiled (see `HelEngine::precompile_all`)
+		let mut rules = Vec::new();
+		for rule_file in &manifest.rules {
+			let rule_path = dir.join(rule_file);
+			let source = std::fs::read_to_string(&rule_path)
+				.map_err(|e| PackageError::Io(format!("Failed to read rule {}: {}", rule_path.display(), e)))?;
+			hash_file(&mut hasher, rule_file, &source);
+			if let Some(reason) = unsupported_rule(&source) {
+				return Err(PackageError::UnsupportedRule {
+					package: manifest.name.clone(),
+					file: rule_file.clone(),
+					reason: reason.to_string(),
+				});
+			}
+			let stem = Path::new(rule_file)
+				.file_stem()
+				.map(|s| s.to_string_lossy().into_owned())
+				.unwrap_or_else(|| rule_file.clone());
+			let id = format!("{}/{}", manifest.name, stem);
+			if let Some(first) = rules.iter().find(|r: &&PackageRule| r.id == id) {
+				return Err(PackageError::DuplicateRule {
+					package: manifest.name.clone(),
+					rule_id: id,
+					files: (first.file.clone(), rule_file.clone()),
+				});
+			}
+			rules.push(PackageRule {
+				id,
+				file: rule_file.clone(),
+				source,
+			});
 		}
 
 		Ok(Self {
@@ -118,6 +257,8 @@ impl SchemaPackage {
 			schema: combined_schema,
 			imports: all_imports,
 			root_path: dir.to_path_buf(),
+			rules,
+			content_hash: to_hex(&hasher.finalize()),
 		})
 	}
 
@@ -126,6 +267,16 @@ impl SchemaPackage {
 		&self.manifest.name
 	}
 
+	/// Rewrite a rule condition written against package version `from_version`
+	///
+	/// Applies the package's field migrations (see `Schema::migrate_ast`) and returns
+	/// the condition in canonical format.
+	pub fn migrate_rule(&self, source: &str, from_version: &str) -> Result<String, HelError> {
+		let ast = parse_expression(source)?;
+		let ast = self.schema.migrate_ast(ast, from_version);
+		Ok(format_ast(&ast, &FormatOptions::default()))
+	}
+
 	/// Get built-ins namespace (manifest.builtins_namespace or package name)
 	pub fn builtins_namespace(&self) -> String {
 		self.manifest
@@ -135,8 +286,310 @@ impl SchemaPackage {
 	}
 }
 
+/// Why a rule file cannot be compiled as a single condition, if it cannot
+fn unsupported_rule(source: &str) -> Option<&'static str> {
+	if crate::split_script(source).iter().any(|s| s.name.is_some()) {
+		return Some("`let`/`const` scripts are not supported, a rule file holds one condition");
+	}
+	if crate::rules::split_outcome(source).1.is_some() {
+		return Some("`then { ... }` outcomes are not supported, a rule file holds one condition");
+	}
+	None
+}
+
+/// Feed a file into a package content hash (length-prefixed, so boundaries are unambiguous)
+fn hash_file(hasher: &mut Sha256, path: &str, content: &str) {
+	for part in [path, content] {
+		hasher.update((part.len() as u64).to_le_bytes());
+		hasher.update(part.as_bytes());
+	}
+}
+
 // endregion: --- Loaded Package
 
+// region:    --- Package Lint
+
+/// Package lint category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PackageLintKind {
+	/// A dependency no schema file imports, or an import repeated
+	UnusedImport,
+	/// A type, enum or alias nothing references
+	DeadType,
+	/// A fact object or attribute that reads like a built-in call (`core.len`)
+	ShadowedBuiltin,
+	/// A type, enum, alias or field without a `///` doc comment
+	MissingDescription,
+	/// An invalid version or requirement, an import without a version requirement,
+	/// or a migration newer than the package
+	VersionMismatch,
+}
+
+impl PackageLintKind {
+	/// Stable machine-readable code, e.g. `hel::dead_type`
+	pub fn code(&self) -> &'static str {
+		match self {
+			PackageLintKind::UnusedImport => "hel::unused_import",
+			PackageLintKind::DeadType => "hel::dead_type",
+			PackageLintKind::ShadowedBuiltin => "hel::shadowed_builtin",
+			PackageLintKind::MissingDescription => "hel::missing_description",
+			PackageLintKind::VersionMismatch => "hel::version_mismatch",
+		}
+	}
+}
+
+/// A problem found by `SchemaPackage::lint`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageLint {
+	/// Category
+	pub kind: PackageLintKind,
+	/// Human-readable message, naming the package
+	pub message: String,
+}
+
+impl PackageLint {
+	/// Structured form for tools (see `diagnostics::DIAGNOSTIC_SCHEMA`), always a warning
+	pub fn to_json(&self) -> serde_json::Value {
+		diagnostic_json(self.kind.code(), "warning", &self.message, serde_json::Value::Null, None)
+	}
+}
+
+impl std::fmt::Display for PackageLint {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "warning[{}]: {}", self.kind.code(), self.message)
+	}
+}
+
+impl SchemaPackage {
+	/// Check the package for likely mistakes
+	///
+	/// - `UnusedImport`: a `[dependencies]` entry no schema file imports, or a repeated import
+	/// - `DeadType`: an enum or alias no field or alias uses; a type no other type uses, when
+	///   the package ships rules and none of them references its fact object (without rules,
+	///   every type may be a fact root)
+	/// - `ShadowedBuiltin`: a type whose fact object is a built-in namespace (`core`, or the
+	///   package's own), and each of its fields named like a function in that namespace
+	/// - `MissingDescription`: a type, enum, alias or field (inherited fields excepted)
+	///   without a doc comment
+	/// - `VersionMismatch`: a manifest version or dependency requirement that does not
+	///   parse, an import with no `[dependencies]` entry, or a migration newer than the package
+	///
+	/// Lints are grouped in the order above.
+	pub fn lint(&self) -> Vec<PackageLint> {
+		let mut lints = Vec::new();
+		let name = &self.manifest.name;
+		let mut lint = |kind, message: String| lints.push(PackageLint { kind, message });
+		let schema = &self.schema;
+
+		// Unused imports
+		for dep in self.manifest.dependencies.keys() {
+			if !self.imports.contains(dep) {
+				lint(
+					PackageLintKind::UnusedImport,
+					format!("Package '{}': dependency '{}' is never imported", name, dep),
+				);
+			}
+		}
+		let mut seen = Vec::new();
+		for import in &self.imports {
+			if seen.contains(&import) {
+				lint(
+					PackageLintKind::UnusedImport,
+					format!("Package '{}': '{}' is imported more than once", name, import),
+				);
+			}
+			seen.push(import);
+		}
+
+		// Dead types
+		let mut referenced = std::collections::BTreeSet::new();
+		for type_def in schema.types.values() {
+			for field in &type_def.fields {
+				referenced_types(&field.field_type, &mut referenced);
+			}
+			if let Some(base) = &type_def.extends {
+				referenced.insert(base.clone());
+			}
+		}
+		for alias in schema.aliases.values() {
+			referenced_types(&alias.target, &mut referenced);
+		}
+		let mut objects = RuleObjects::default();
+		for rule in &self.rules {
+			if let Ok(ast) = parse_expression(&rule.source) {
+				crate::visit::walk_ast(&ast, &mut objects);
+			}
+		}
+		let used_by_rules = |type_name: &str| {
+			self.rules.is_empty() || objects.0.iter().any(|o| o.eq_ignore_ascii_case(type_name))
+		};
+		for type_name in schema.types.keys() {
+			if !referenced.contains(type_name) && !used_by_rules(type_name) {
+				lint(
+					PackageLintKind::DeadType,
+					format!("Package '{}': type {} is never referenced", name, type_name),
+				);
+			}
+		}
+		for dead in schema.enums.keys().filter(|n| !referenced.contains(*n)) {
+			lint(
+				PackageLintKind::DeadType,
+				format!("Package '{}': enum {} is never referenced", name, dead),
+			);
+		}
+		for dead in schema.aliases.keys().filter(|n| !referenced.contains(*n)) {
+			lint(
+				PackageLintKind::DeadType,
+				format!("Package '{}': alias {} is never referenced", name, dead),
+			);
+		}
+
+		// Shadowed built-ins
+		let core = crate::BuiltinsRegistry::builder().with_core().build().ok();
+		let namespace = self.builtins_namespace();
+		for type_def in schema.types.values() {
+			let object = type_def.name.to_ascii_lowercase();
+			let functions = core.as_ref().and_then(|c| c.functions_in_namespace(&object));
+			if functions.is_none() && object != namespace {
+				continue;
+			}
+			lint(
+				PackageLintKind::ShadowedBuiltin,
+				format!(
+					"Package '{}': fact object `{}` (type {}) shadows built-in namespace `{}`",
+					name, object, type_def.name, object
+				),
+			);
+			for field in &type_def.fields {
+				if functions.as_ref().is_some_and(|f| f.iter().any(|f| *f == *field.name)) {
+					lint(
+						PackageLintKind::ShadowedBuiltin,
+						format!(
+							"Package '{}': attribute `{}.{}` shadows built-in `{}.{}()`",
+							name, object, field.name, object, field.name
+						),
+					);
+				}
+			}
+		}
+
+		// Missing descriptions
+		for type_def in schema.types.values() {
+			if type_def.description.is_none() {
+				lint(
+					PackageLintKind::MissingDescription,
+					format!("Package '{}': type {} has no description", name, type_def.name),
+				);
+			}
+			let base = type_def.extends.as_ref().and_then(|b| schema.get_type(b));
+			for field in &type_def.fields {
+				let inherited = base.is_some_and(|b| b.field(&field.name).is_some());
+				if field.description.is_none() && !inherited {
+					lint(
+						PackageLintKind::MissingDescription,
+						format!("Package '{}': field {}.{} has no description", name, type_def.name, field.name),
+					);
+				}
+			}
+		}
+		for enum_def in schema.enums.values().filter(|e| e.description.is_none()) {
+			lint(
+				PackageLintKind::MissingDescription,
+				format!("Package '{}': enum {} has no description", name, enum_def.name),
+			);
+		}
+		for alias in schema.aliases.values().filter(|a| a.description.is_none()) {
+			lint(
+				PackageLintKind::MissingDescription,
+				format!("Package '{}': alias {} has no description", name, alias.name),
+			);
+		}
+
+		// Version mismatches
+		let version = parse_version(&self.manifest.version);
+		if version.is_none() {
+			lint(
+				PackageLintKind::VersionMismatch,
+				format!("Package '{}': version '{}' is not a valid version", name, self.manifest.version),
+			);
+		}
+		for (dep, requirement) in &self.manifest.dependencies {
+			if version_matches("0.0.0", requirement).is_err() {
+				lint(
+					PackageLintKind::VersionMismatch,
+					format!("Package '{}': invalid version requirement '{}' for '{}'", name, requirement, dep),
+				);
+			}
+		}
+		for import in &self.imports {
+			if !self.manifest.dependencies.contains_key(import) {
+				lint(
+					PackageLintKind::VersionMismatch,
+					format!("Package '{}': import '{}' has no version requirement in [dependencies]", name, import),
+				);
+			}
+		}
+		for migration in &schema.migrations {
+			let newer = match (&version, parse_version(&migration.version)) {
+				(Some(version), Some(since)) => cmp_versions(&since, version).is_gt(),
+				_ => false,
+			};
+			if newer {
+				lint(
+					PackageLintKind::VersionMismatch,
+					format!(
+						"Package '{}': migration {}.{} -> {}.{} is declared @ {}, after version {}",
+						name,
+						migration.from_type,
+						migration.from_field,
+						migration.to_type,
+						migration.to_field,
+						migration.version,
+						self.manifest.version
+					),
+				);
+			}
+		}
+
+		lints
+	}
+}
+
+/// Collect type, enum and alias names a field type refers to
+fn referenced_types(field_type: &FieldType, names: &mut std::collections::BTreeSet<Arc<str>>) {
+	match field_type {
+		FieldType::TypeRef(name) => {
+			names.insert(name.clone());
+		}
+		FieldType::List(inner) => referenced_types(inner, names),
+		FieldType::Map { key, value } => {
+			referenced_types(key, names);
+			referenced_types(value, names);
+		}
+		FieldType::Alias { name, target } => {
+			names.insert(name.clone());
+			referenced_types(target, names);
+		}
+		_ => {}
+	}
+}
+
+/// Fact objects referenced by rules
+#[derive(Default)]
+struct RuleObjects(std::collections::BTreeSet<String>);
+
+impl crate::visit::Visitor for RuleObjects {
+	fn visit_attribute(&mut self, object: &str, _field: &str) {
+		self.0.insert(object.to_string());
+	}
+
+	fn visit_wildcard(&mut self, object: &str, _field: &str) {
+		self.0.insert(object.to_string());
+	}
+}
+
+// endregion: --- Package Lint
+
 // region:    --- Package Registry
 
 /// Registry that manages loading and resolving packages
@@ -146,6 +599,25 @@ pub struct PackageRegistry {
 	search_paths: Vec<PathBuf>,
 	/// Loaded packages: name -> package
 	packages: BTreeMap<String, SchemaPackage>,
+	/// Resolution graph: package name -> the requirements placed on it
+	requirements: BTreeMap<String, Vec<DependencyRequirement>>,
+	/// Package directories used instead of the search paths: name -> directory
+	overrides: BTreeMap<String, PathBuf>,
+}
+
+/// A version requirement on a package, with the dependency path that placed it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyRequirement {
+	/// Version requirement (e.g., `^1.2`, `>=0.3, <0.5`)
+	pub requirement: String,
+	/// Packages from the resolved root down to the one declaring the dependency
+	pub path: Vec<String>,
+}
+
+impl std::fmt::Display for DependencyRequirement {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} required by {}", self.requirement, self.path.join(" -> "))
+	}
 }
 
 impl PackageRegistry {
@@ -154,6 +626,8 @@ impl PackageRegistry {
 		Self {
 			search_paths: Vec::new(),
 			packages: BTreeMap::new(),
+			requirements: BTreeMap::new(),
+			overrides: BTreeMap::new(),
 		}
 	}
 
@@ -162,23 +636,34 @@ impl PackageRegistry {
 		self.search_paths.push(path);
 	}
 
+	/// Load package `name` from `dir` instead of the search paths
+	///
+	/// Takes precedence over `[patch]` entries in manifests. Only affects packages
+	/// not loaded yet.
+	pub fn add_override(&mut self, name: &str, dir: PathBuf) {
+		self.overrides.insert(name.to_string(), dir);
+	}
+
 	/// Load a package by name
 	///
-	/// Searches in all registered search paths for a directory matching the package name.
-	/// Version requirements are not yet enforced (milestone 1).
+	/// Loads from the override directory if one is set (see `add_override`), otherwise
+	/// searches in all registered search paths for a directory matching the package name.
+	/// Version requirements are checked by `resolve_all`.
 	pub fn load_package(&mut self, name: &str) -> Result<&SchemaPackage, PackageError> {
 		// Check if already loaded
 		if self.packages.contains_key(name) {
 			return Ok(&self.packages[name]);
 		}
 
-		// Search for package directory
-		let mut package_dir = None;
-		for search_path in &self.search_paths {
-			let candidate = search_path.join(name);
-			if candidate.is_dir() && candidate.join("hel-package.toml").exists() {
-				package_dir = Some(candidate);
-				break;
+		// Use the override, or search for the package directory
+		let mut package_dir = self.overrides.get(name).cloned();
+		if package_dir.is_none() {
+			for search_path in &self.search_paths {
+				let candidate = search_path.join(name);
+				if candidate.is_dir() && candidate.join("hel-package.toml").exists() {
+					package_dir = Some(candidate);
+					break;
+				}
 			}
 		}
 
@@ -202,14 +687,66 @@ impl PackageRegistry {
 		Ok(&self.packages[name])
 	}
 
+	/// Load and resolve every member of a workspace
+	///
+	/// `root` is the directory containing hel-workspace.toml. Members are loaded from
+	/// their directories, so they can depend on each other without search paths;
+	/// other dependencies are looked up in the registered search paths. Returns all
+	/// resolved packages in deterministic topological order (dependencies first).
+	pub fn load_workspace(&mut self, root: &Path) -> Result<Vec<String>, PackageError> {
+		let manifest = WorkspaceManifest::from_file(&root.join("hel-workspace.toml"))?;
+
+		let mut members = Vec::new();
+		for dir in manifest.member_dirs(root)? {
+			let package = SchemaPackage::from_directory(&dir)?;
+			let name = package.manifest.name.clone();
+			if members.contains(&name) {
+				return Err(PackageError::DuplicatePackage { name });
+			}
+			self.packages.insert(name.clone(), package);
+			members.push(name);
+		}
+
+		let mut order = Vec::new();
+		for member in &members {
+			for name in self.resolve_all(member)? {
+				if !order.contains(&name) {
+					order.push(name);
+				}
+			}
+		}
+		Ok(order)
+	}
+
 	/// Resolve all dependencies for a root package recursively
 	///
-	/// Returns packages in deterministic topological order (dependencies first)
+	/// Returns packages in deterministic topological order (dependencies first).
+	/// Every dependency edge is recorded in the resolution graph (see `required_by`);
+	/// a package whose version does not satisfy all requirements placed on it, by this
+	/// root or by earlier resolutions, fails with `PackageError::VersionConflict`.
+	///
+	/// The root's `[patch]` entries become overrides (see `add_override`) unless the
+	/// registry already overrides that package; patches in dependencies are ignored.
 	pub fn resolve_all(&mut self, root_package: &str) -> Result<Vec<String>, PackageError> {
+		let root = self.load_package(root_package)?;
+		let patches: Vec<(String, PathBuf)> = root
+			.manifest
+			.patch
+			.iter()
+			.map(|(name, patch)| (name.clone(), root.root_path.join(&patch.path)))
+			.collect();
+		for (name, dir) in patches {
+			self.overrides.entry(name).or_insert(dir);
+		}
+
 		let mut resolved = Vec::new();
-		let mut visiting = std::collections::HashSet::new();
+		let mut path = Vec::new();
 
-		self.resolve_recursive(root_package, &mut resolved, &mut visiting)?;
+		self.resolve_recursive(root_package, &mut resolved, &mut path)?;
+
+		for name in &resolved {
+			self.check_requirements(name)?;
+		}
 
 		Ok(resolved)
 	}
@@ -218,10 +755,10 @@ impl PackageRegistry {
 		&mut self,
 		package_name: &str,
 		resolved: &mut Vec<String>,
-		visiting: &mut std::collections::HashSet<String>,
+		path: &mut Vec<String>,
 	) -> Result<(), PackageError> {
 		// Cycle detection
-		if visiting.contains(package_name) {
+		if path.iter().any(|p| p == package_name) {
 			return Err(PackageError::CircularDependency {
 				package: package_name.to_string(),
 			});
@@ -232,33 +769,88 @@ impl PackageRegistry {
 			return Ok(());
 		}
 
-		visiting.insert(package_name.to_string());
+		path.push(package_name.to_string());
 
 		// Load package
 		let package = self.load_package(package_name)?.clone();
 
-		// Resolve dependencies first
-		let deps: Vec<_> = package.manifest.dependencies.keys().cloned().collect();
-		for dep in deps {
-			self.resolve_recursive(&dep, resolved, visiting)?;
+		// Record requirements, then resolve dependencies first
+		for (dep, requirement) in &package.manifest.dependencies {
+			let edge = DependencyRequirement {
+				requirement: requirement.clone(),
+				path: path.clone(),
+			};
+			let edges = self.requirements.entry(dep.clone()).or_default();
+			if !edges.contains(&edge) {
+				edges.push(edge);
+			}
+			self.resolve_recursive(dep, resolved, path)?;
 		}
 
-		visiting.remove(package_name);
+		path.pop();
 		resolved.push(package_name.to_string());
 
 		Ok(())
 	}
 
+	fn check_requirements(&self, name: &str) -> Result<(), PackageError> {
+		let (Some(package), Some(requirements)) = (self.packages.get(name), self.requirements.get(name)) else {
+			return Ok(());
+		};
+		let version = &package.manifest.version;
+		for edge in requirements {
+			let matches = version_matches(version, &edge.requirement).map_err(|e| {
+				PackageError::ManifestParse(format!(
+					"Invalid version requirement for '{}' in package '{}': {}",
+					name,
+					edge.path.last().map_or("", |p| p.as_str()),
+					e
+				))
+			})?;
+			if !matches {
+				return Err(PackageError::VersionConflict {
+					package: name.to_string(),
+					version: version.clone(),
+					requirements: requirements.clone(),
+				});
+			}
+		}
+		Ok(())
+	}
+
+	/// Requirements placed on a package by the packages resolved so far
+	pub fn required_by(&self, name: &str) -> &[DependencyRequirement] {
+		self.requirements.get(name).map_or(&[], |r| r.as_slice())
+	}
+
 	/// Get a loaded package by name
 	pub fn get_package(&self, name: &str) -> Option<&SchemaPackage> {
 		self.packages.get(name)
 	}
 
+	/// Iterate over loaded packages, sorted by name
+	pub fn packages(&self) -> impl Iterator<Item = &SchemaPackage> {
+		self.packages.values()
+	}
+
+	/// Canonical JSON snapshot of every loaded package, for compliance evidence
+	///
+	/// Lists packages by name with their version, content hash, dependencies and
+	/// schema: types (base type and fields with types as written), enums (variant
+	/// values), aliases and migrations. Object keys are sorted and nothing depends on
+	/// load order or paths, so registries loaded from the same package files snapshot
+	/// identically; attach it next to `BuiltinsRegistry::snapshot` in evidence bundles.
+	pub fn snapshot(&self) -> String {
+		let packages: Vec<Json> = self.packages.values().map(package_snapshot).collect();
+		serde_json::to_string(&json!({ "packages": packages })).expect("package snapshot serialization cannot fail")
+	}
+
 	/// Build a merged type environment from resolved packages
 	///
-	/// Returns a map of qualified type names (package.Type) to TypeDef
+	/// Returns maps of qualified type and enum names (package.Type) to their definitions
 	pub fn build_type_environment(&self, package_names: &[String]) -> Result<TypeEnvironment, PackageError> {
 		let mut types = BTreeMap::new();
+		let mut enums = BTreeMap::new();
 
 		for pkg_name in package_names {
 			let package = self.packages.get(pkg_name).ok_or_else(|| PackageError::PackageNotFound {
@@ -278,9 +870,21 @@ impl PackageRegistry {
 
 				types.insert(qualified_name, typedef.clone());
 			}
+
+			for (enum_name, enum_def) in &package.schema.enums {
+				let qualified_name: Arc<str> = format!("{}.{}", package.namespace(), enum_name).into();
+
+				if types.contains_key(&qualified_name) || enums.contains_key(&qualified_name) {
+					return Err(PackageError::TypeCollision {
+						type_name: qualified_name.to_string(),
+					});
+				}
+
+				enums.insert(qualified_name, enum_def.clone());
+			}
 		}
 
-		Ok(TypeEnvironment { types })
+		Ok(TypeEnvironment { types, enums })
 	}
 }
 
@@ -290,6 +894,68 @@ impl Default for PackageRegistry {
 	}
 }
 
+fn package_snapshot(package: &SchemaPackage) -> Json {
+	let schema = &package.schema;
+	let types: serde_json::Map<String, Json> = schema
+		.types
+		.values()
+		.map(|t| {
+			let fields: Vec<Json> = t
+				.fields
+				.iter()
+				.map(|f| json!({ "name": &*f.name, "optional": f.optional, "type": f.field_type.to_string() }))
+				.collect();
+			(t.name.to_string(), json!({ "extends": t.extends.as_deref(), "fields": fields }))
+		})
+		.collect();
+	let enums: serde_json::Map<String, Json> = schema
+		.enums
+		.values()
+		.map(|e| {
+			let variants: Vec<Json> = e
+				.variants
+				.iter()
+				.map(|v| {
+					let value = match &v.value {
+						Value::String(s) => json!(&**s),
+						Value::Number(n) => json!(n),
+						Value::Bool(b) => json!(b),
+						other => json!(other.to_string()),
+					};
+					json!({ "name": &*v.name, "value": value })
+				})
+				.collect();
+			(e.name.to_string(), json!(variants))
+		})
+		.collect();
+	let aliases: serde_json::Map<String, Json> = schema
+		.aliases
+		.values()
+		.map(|a| (a.name.to_string(), json!(a.target.to_string())))
+		.collect();
+	let migrations: Vec<Json> = schema
+		.migrations
+		.iter()
+		.map(|m| {
+			json!({
+				"from": format!("{}.{}", m.from_type, m.from_field),
+				"to": format!("{}.{}", m.to_type, m.to_field),
+				"version": &*m.version,
+			})
+		})
+		.collect();
+	json!({
+		"aliases": aliases,
+		"content_hash": package.content_hash,
+		"dependencies": package.manifest.dependencies,
+		"enums": enums,
+		"migrations": migrations,
+		"name": package.manifest.name,
+		"types": types,
+		"version": package.manifest.version,
+	})
+}
+
 // endregion: --- Package Registry
 
 // region:    --- Type Environment
@@ -299,6 +965,8 @@ impl Default for PackageRegistry {
 pub struct TypeEnvironment {
 	/// Qualified type name (package.Type) -> TypeDef
 	pub types: BTreeMap<Arc<str>, TypeDef>,
+	/// Qualified enum name (package.Enum) -> EnumDef
+	pub enums: BTreeMap<Arc<str>, EnumDef>,
 }
 
 impl TypeEnvironment {
@@ -307,6 +975,80 @@ impl TypeEnvironment {
 		self.types.get(qualified_name)
 	}
 
+	/// Find the type describing a fact object (e.g., `binary` -> `security-binary.Binary`)
+	///
+	/// Matches the unqualified type name case-insensitively; the first match in
+	/// qualified-name order wins.
+	pub fn type_for_object(&self, object: &str) -> Option<(&str, &TypeDef)> {
+		self.types
+			.iter()
+			.find(|(_, typedef)| typedef.name.eq_ignore_ascii_case(object))
+			.map(|(name, typedef)| (name.as_ref(), typedef))
+	}
+
+	/// Complete a partial attribute path (e.g., `binary.ent`)
+	///
+	/// Without a `.` the prefix completes fact objects (lowercased type names, with the
+	/// qualified type as detail); otherwise the fields of the object's type (see
+	/// `type_for_object`) starting with the text after the last `.`, with their types
+	/// and descriptions. Results are sorted by label.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{parse_schema, TypeEnvironment};
+	///
+	/// let schema = parse_schema("type Binary {\n  entropy: Number\n  format: String\n}\n").unwrap();
+	/// let types = TypeEnvironment { types: schema.types, enums: schema.enums };
+	///
+	/// let fields = types.complete_attribute("binary.en");
+	/// assert_eq!(fields[0].label, "entropy");
+	/// assert_eq!(fields[0].detail.as_deref(), Some("Number"));
+	/// ```
+	pub fn complete_attribute(&self, prefix: &str) -> Vec<Completion> {
+		let mut items: Vec<Completion> = match prefix.rsplit_once('.') {
+			Some((object, field)) => self
+				.type_for_object(object)
+				.map(|(_, typedef)| {
+					typedef
+						.fields
+						.iter()
+						.filter(|f| f.name.starts_with(field))
+						.map(field_completion)
+						.collect()
+				})
+				.unwrap_or_default(),
+			None => self
+				.types
+				.iter()
+				.map(|(qualified, typedef)| Completion {
+					label: typedef.name.to_ascii_lowercase(),
+					kind: CompletionKind::Object,
+					detail: Some(qualified.to_string()),
+					documentation: typedef.description.as_deref().map(str::to_string),
+				})
+				.filter(|c| c.label.starts_with(prefix))
+				.collect(),
+		};
+		items.sort_by(|a, b| a.label.cmp(&b.label));
+		items
+	}
+
+	/// Lookup an enum by qualified name
+	pub fn get_enum(&self, qualified_name: &str) -> Option<&EnumDef> {
+		self.enums.get(qualified_name)
+	}
+
+	/// Find an enum by its unqualified name, as referenced in expressions (e.g., `Stage`)
+	///
+	/// The first match in qualified-name order wins.
+	pub fn enum_named(&self, name: &str) -> Option<(&str, &EnumDef)> {
+		self.enums
+			.iter()
+			.find(|(_, enum_def)| enum_def.name.as_ref() == name)
+			.map(|(qualified, enum_def)| (qualified.as_ref(), enum_def))
+	}
+
 	/// Validate all type references in the environment
 	pub fn validate(&self) -> Result<(), PackageError> {
 		for (qualified_name, typedef) in &self.types {
@@ -321,7 +1063,7 @@ impl TypeEnvironment {
 		match field_type {
 			super::FieldType::TypeRef(name) => {
 				// Type references should be qualified (package.Type)
-				if !self.types.contains_key(name) {
+				if !self.types.contains_key(name) && !self.enums.contains_key(name) {
 					return Err(PackageError::UndefinedTypeReference {
 						type_name: name.to_string(),
 						context: context.to_string(),
@@ -329,7 +1071,12 @@ impl TypeEnvironment {
 				}
 				Ok(())
 			}
-			super::FieldType::List(inner) | super::FieldType::Map(inner) => self.validate_field_type(inner, context),
+			super::FieldType::List(inner) => self.validate_field_type(inner, context),
+			super::FieldType::Map { key, value } => {
+				self.validate_field_type(key, context)?;
+				self.validate_field_type(value, context)
+			}
+			super::FieldType::Alias { target, .. } => self.validate_field_type(target, context),
 			_ => Ok(()),
 		}
 	}
@@ -367,6 +1114,26 @@ pub enum PackageError {
 	UndefinedTypeReference { type_name: String, context: String },
 	/// Circular dependency
 	CircularDependency { package: String },
+	/// Two workspace members with the same package name
+	DuplicatePackage { name: String },
+	/// Two rule files in the same package map to the same rule id (`package/file-stem`)
+	DuplicateRule {
+		package: String,
+		rule_id: String,
+		files: (String, String),
+	},
+	/// A rule file that is not a single condition (a script or a rule with an outcome)
+	UnsupportedRule {
+		package: String,
+		file: String,
+		reason: String,
+	},
+	/// The loaded version of a package does not satisfy every requirement on it
+	VersionConflict {
+		package: String,
+		version: String,
+		requirements: Vec<DependencyRequirement>,
+	},
 }
 
 impl std::fmt::Display for PackageError {
@@ -395,6 +1162,32 @@ impl std::fmt::Display for PackageError {
 			PackageError::CircularDependency { package } => {
 				write!(f, "Circular dependency detected involving package '{}'", package)
 			}
+			PackageError::DuplicatePackage { name } => {
+				write!(f, "Package '{}' is declared by more than one workspace member", name)
+			}
+			PackageError::DuplicateRule { package, rule_id, files } => write!(
+				f,
+				"Duplicate rule id '{}' in package '{}' ('{}' and '{}')",
+				rule_id, package, files.0, files.1
+			),
+			PackageError::UnsupportedRule { package, file, reason } => {
+				write!(f, "Unsupported rule file '{}' in package '{}': {}", file, package, reason)
+			}
+			PackageError::VersionConflict {
+				package,
+				version,
+				requirements,
+			} => {
+				write!(f, "Version conflict for package '{}' (found {}):", package, version)?;
+				for requirement in requirements {
+					let marker = match version_matches(version, &requirement.requirement) {
+						Ok(true) => "",
+						_ => " (not satisfied)",
+					};
+					write!(f, "\n  {}{}", requirement, marker)?;
+				}
+				Ok(())
+			}
 		}
 	}
 }
@@ -403,6 +1196,65 @@ impl std::error::Error for PackageError {}
 
 // endregion: --- Error Types
 
+// region:    --- Version Requirements
+
+/// Check a version against a requirement
+///
+/// Requirements follow Cargo: comma-separated comparators (`=`, `>`, `>=`, `<`, `<=`,
+/// `~`, `^`), where a bare version means `^` and `*` matches anything.
+fn version_matches(version: &str, requirement: &str) -> Result<bool, String> {
+	let version = parse_version(version).ok_or_else(|| format!("invalid version '{}'", version))?;
+	for comparator in requirement.split(',').map(str::trim) {
+		if comparator == "*" {
+			continue;
+		}
+		let (op, rest) = [">=", "<=", "=", ">", "<", "~", "^"]
+			.iter()
+			.find_map(|op| comparator.strip_prefix(op).map(|rest| (*op, rest)))
+			.unwrap_or(("^", comparator));
+		let bound = parse_version(rest.trim()).ok_or_else(|| format!("invalid requirement '{}'", requirement))?;
+		let ord = cmp_versions(&version, &bound);
+		let ok = match op {
+			">=" => ord.is_ge(),
+			"<=" => ord.is_le(),
+			"=" => ord.is_eq(),
+			">" => ord.is_gt(),
+			"<" => ord.is_lt(),
+			"~" => ord.is_ge() && cmp_versions(&version, &bump(&bound, bound.len().min(2) - 1)).is_lt(),
+			_ => {
+				let first_nonzero = bound.iter().position(|&n| n != 0).unwrap_or(bound.len() - 1);
+				ord.is_ge() && cmp_versions(&version, &bump(&bound, first_nonzero)).is_lt()
+			}
+		};
+		if !ok {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
+
+/// Parse `1`, `1.2` or `1.2.3`
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+	let parts: Vec<u64> = version.split('.').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+	(parts.len() <= 3).then_some(parts)
+}
+
+fn cmp_versions(a: &[u64], b: &[u64]) -> std::cmp::Ordering {
+	(0..3)
+		.map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+		.find(|o| o.is_ne())
+		.unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Smallest version above every version matching `version` up to component `index`
+fn bump(version: &[u64], index: usize) -> Vec<u64> {
+	let mut next = version[..=index].to_vec();
+	next[index] += 1;
+	next
+}
+
+// endregion: --- Version Requirements
+
 // region:    --- Import Parsing
 
 /// Extract import declarations from schema content
@@ -525,6 +1377,27 @@ type MyType {
 		Ok(())
 	}
 
+	#[test]
+	fn test_migrate_rule() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		let pkg_dir = temp.path().join("test-pkg");
+		create_test_package(&pkg_dir, "test-pkg", &[])?;
+		fs::write(
+			pkg_dir.join("schema/00_domain.hel"),
+			"type Binary {\n  architecture: String\n}\nmigrate Binary.arch -> Binary.architecture @ 0.2.0\n",
+		)?;
+
+		let package = SchemaPackage::from_directory(&pkg_dir)?;
+		assert_eq!(package.schema.migrations.len(), 1);
+		assert_eq!(
+			package.migrate_rule(r#"binary.arch  ==  "x86_64""#, "0.1.0")?,
+			r#"binary.architecture == "x86_64""#
+		);
+		assert!(package.migrate_rule("binary.arch ==", "0.1.0").is_err());
+
+		Ok(())
+	}
+
 	#[test]
 	fn test_package_registry_loading() -> Result<(), Box<dyn std::error::Error>> {
 		let temp = TempDir::new()?;
@@ -581,6 +1454,36 @@ type MyType {
 		Ok(())
 	}
 
+	#[test]
+	fn test_complete_attribute() {
+		let mut schema = parse_schema(
+			"type Binary {\n  entropy: Number\n  exports?: List<String>\n  format: String\n}\n\ntype Manifest {\n  permissions: List<String>\n}\n",
+		)
+		.expect("parse failed");
+		schema.types.get_mut("Binary").unwrap().fields[0].description = Some("Shannon entropy of the file".into());
+		let qualify = |name: Arc<str>| Arc::from(format!("security-binary.{}", name));
+		let types = TypeEnvironment {
+			types: schema.types.into_iter().map(|(n, d)| (qualify(n), d)).collect(),
+			enums: BTreeMap::new(),
+		};
+
+		let fields = types.complete_attribute("binary.e");
+		let labels: Vec<&str> = fields.iter().map(|c| c.label.as_str()).collect();
+		assert_eq!(labels, ["entropy", "exports"]);
+		assert_eq!(fields[0].documentation.as_deref(), Some("Shannon entropy of the file"));
+		assert_eq!(fields[1].detail.as_deref(), Some("List<String>?"));
+		assert_eq!(types.complete_attribute("binary.").len(), 3);
+
+		let objects = types.complete_attribute("m");
+		assert_eq!(objects.len(), 1);
+		assert_eq!(objects[0].label, "manifest");
+		assert_eq!(objects[0].kind, CompletionKind::Object);
+		assert_eq!(objects[0].detail.as_deref(), Some("security-binary.Manifest"));
+		assert_eq!(types.complete_attribute("").len(), 2);
+
+		assert!(types.complete_attribute("process.p").is_empty());
+	}
+
 	#[test]
 	fn test_circular_dependency_detection() -> Result<(), Box<dyn std::error::Error>> {
 		let temp = TempDir::new()?;
@@ -602,6 +1505,276 @@ type MyType {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_version_conflict_paths() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		create_test_package(&temp.path().join("app"), "app", &[("bin", "0.1"), ("enrich", "0.1")])?;
+		create_test_package(&temp.path().join("bin"), "bin", &[("core", "^1.2")])?;
+		create_test_package(&temp.path().join("enrich"), "enrich", &[("core", ">=2.0, <3")])?;
+		create_test_package(&temp.path().join("core"), "core", &[])?;
+		let manifest = temp.path().join("core/hel-package.toml");
+		fs::write(&manifest, fs::read_to_string(&manifest)?.replace("0.1.0", "1.4.0"))?;
+
+		let mut registry = PackageRegistry::new();
+		registry.add_search_path(temp.path().to_path_buf());
+
+		let err = registry.resolve_all("app").unwrap_err();
+		assert!(matches!(err, PackageError::VersionConflict { ref package, .. } if package == "core"));
+		assert_eq!(
+			err.to_string(),
+			"Version conflict for package 'core' (found 1.4.0):\n  \
+			 ^1.2 required by app -> bin\n  \
+			 >=2.0, <3 required by app -> enrich (not satisfied)"
+		);
+		assert_eq!(registry.required_by("bin")[0].path, vec!["app".to_string()]);
+		assert!(registry.required_by("app").is_empty());
+
+		// The requirements from `bin` alone are satisfied
+		let mut registry = PackageRegistry::new();
+		registry.add_search_path(temp.path().to_path_buf());
+		assert_eq!(registry.resolve_all("bin")?, vec!["core", "bin"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_load_workspace() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		let root = temp.path();
+		create_test_package(&root.join("packages/binary"), "binary", &[("core", "0.1")])?;
+		create_test_package(&root.join("packages/enrich"), "enrich", &[("binary", "0.1"), ("ext", "0.1")])?;
+		create_test_package(&root.join("core"), "core", &[])?;
+		fs::create_dir_all(root.join("packages/notes"))?;
+		fs::write(root.join("hel-workspace.toml"), "members = [\"packages/*\", \"core\"]\n")?;
+
+		// `ext` is outside the workspace and found through a search path
+		let external = TempDir::new()?;
+		create_test_package(&external.path().join("ext"), "ext", &[])?;
+
+		let mut registry = PackageRegistry::new();
+		registry.add_search_path(external.path().to_path_buf());
+		assert_eq!(registry.load_workspace(root)?, vec!["core", "binary", "ext", "enrich"]);
+		assert!(registry.get_package("enrich").is_some());
+
+		create_test_package(&root.join("dup"), "core", &[])?;
+		fs::write(root.join("hel-workspace.toml"), "members = [\"core\", \"dup\"]\n")?;
+		let err = PackageRegistry::new().load_workspace(root).unwrap_err();
+		assert!(matches!(err, PackageError::DuplicatePackage { ref name } if name == "core"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_dependency_patch() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		let published = temp.path().join("registry");
+		create_test_package(&published.join("app"), "app", &[("core", "0.1")])?;
+		create_test_package(&published.join("core"), "core", &[])?;
+		create_test_package(&temp.path().join("dev/core"), "core", &[])?;
+		fs::write(temp.path().join("dev/core/schema/00_domain.hel"), "type Patched {\n  id: String\n}\n")?;
+		create_test_package(&temp.path().join("other/core"), "core", &[])?;
+		fs::write(temp.path().join("other/core/schema/00_domain.hel"), "type Overridden {\n  id: String\n}\n")?;
+
+		let manifest = published.join("app/hel-package.toml");
+		let patch = "\n[patch]\ncore = { path = \"../../dev/core\" }\n";
+		fs::write(&manifest, fs::read_to_string(&manifest)? + patch)?;
+
+		let mut registry = PackageRegistry::new();
+		registry.add_search_path(published.clone());
+		assert_eq!(registry.resolve_all("app")?, vec!["core", "app"]);
+		assert!(registry.get_package("core").unwrap().schema.get_type("Patched").is_some());
+
+		// Registry overrides win over manifest patches
+		let mut registry = PackageRegistry::new();
+		registry.add_search_path(published);
+		registry.add_override("core", temp.path().join("other/core"));
+		registry.resolve_all("app")?;
+		assert!(registry.get_package("core").unwrap().schema.get_type("Overridden").is_some());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_package_lint() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		let dir = temp.path().join("lint-pkg");
+		create_test_package(&dir, "lint-pkg", &[("unused", "^1"), ("shared", "latest")])?;
+		fs::write(
+			dir.join("schema/00_domain.hel"),
+			r#"
+import "shared";
+import "shared";
+import "extra";
+
+/// A scanned executable
+type Binary {
+    /// Target architecture
+    arch: Arch
+    size: Number
+}
+
+/// Built-in lookalikes
+type Core {
+    /// Collides with core.len()
+    len: Number
+}
+
+/// Only used as a base
+type Base {
+    /// Identifier
+    id: String
+}
+
+/// Derived
+type Sample extends Base {
+}
+
+/// Architectures
+enum Arch {
+    X86
+}
+
+enum Unused {
+    A
+}
+
+/// Never used
+alias Sha256 = String
+
+migrate Binary.cpu -> Binary.arch @ 0.2.0
+"#,
+		)?;
+		fs::create_dir_all(dir.join("rules"))?;
+		fs::write(dir.join("rules/big.hel"), "binary.size > 10 OR sample.id == \"x\"")?;
+		let manifest = dir.join("hel-package.toml");
+		fs::write(
+			&manifest,
+			fs::read_to_string(&manifest)?.replace("schemas", "rules = [\"rules/big.hel\"]\nschemas"),
+		)?;
+
+		let package = SchemaPackage::from_directory(&dir)?;
+		let lints: Vec<String> = package.lint().iter().map(|l| l.to_string()).collect();
+		assert_eq!(
+			lints,
+			vec![
+				"warning[hel::unused_import]: Package 'lint-pkg': dependency 'unused' is never imported",
+				"warning[hel::unused_import]: Package 'lint-pkg': 'shared' is imported more than once",
+				"warning[hel::dead_type]: Package 'lint-pkg': type Core is never referenced",
+				"warning[hel::dead_type]: Package 'lint-pkg': enum Unused is never referenced",
+				"warning[hel::dead_type]: Package 'lint-pkg': alias Sha256 is never referenced",
+				"warning[hel::shadowed_builtin]: Package 'lint-pkg': fact object `core` (type Core) shadows built-in \
+				 namespace `core`",
+				"warning[hel::shadowed_builtin]: Package 'lint-pkg': attribute `core.len` shadows built-in `core.len()`",
+				"warning[hel::missing_description]: Package 'lint-pkg': field Binary.size has no description",
+				"warning[hel::missing_description]: Package 'lint-pkg': enum Unused has no description",
+				"warning[hel::version_mismatch]: Package 'lint-pkg': invalid version requirement 'latest' for 'shared'",
+				"warning[hel::version_mismatch]: Package 'lint-pkg': import 'extra' has no version requirement in \
+				 [dependencies]",
+				"warning[hel::version_mismatch]: Package 'lint-pkg': migration Binary.cpu -> Binary.arch is declared \
+				 @ 0.2.0, after version 0.1.0",
+			]
+		);
+		assert_eq!(package.lint()[0].to_json()["code"], "hel::unused_import");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_registry_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		create_test_package(&temp.path().join("app"), "app", &[("core", "0.1")])?;
+		create_test_package(&temp.path().join("core"), "core", &[])?;
+		fs::write(
+			temp.path().join("core/schema/00_domain.hel"),
+			"type Binary {\n  tags?: List<String>\n  kind: Kind\n}\nenum Kind {\n  Exe = \"exe\"\n}\n",
+		)?;
+
+		let snapshot = |roots: &[&str]| -> Result<String, PackageError> {
+			let mut registry = PackageRegistry::new();
+			registry.add_search_path(temp.path().to_path_buf());
+			for root in roots {
+				registry.resolve_all(root)?;
+			}
+			Ok(registry.snapshot())
+		};
+		let first = snapshot(&["app"])?;
+		assert_eq!(first, snapshot(&["core", "app"])?);
+
+		let doc: serde_json::Value = serde_json::from_str(&first)?;
+		let core = &doc["packages"][1];
+		assert_eq!(doc["packages"][0]["dependencies"]["core"], "0.1");
+		assert_eq!(core["name"], "core");
+		assert_eq!(core["content_hash"].as_str().map(str::len), Some(64));
+		assert_eq!(
+			core["types"]["Binary"]["fields"][0],
+			json!({ "name": "tags", "optional": true, "type": "List<String>" })
+		);
+		assert_eq!(core["enums"]["Kind"], json!([{ "name": "Exe", "value": "exe" }]));
+
+		// Any change to a package file changes its content hash
+		fs::write(temp.path().join("core/schema/00_domain.hel"), "type Binary {\n  kind: String\n}\n")?;
+		let changed: serde_json::Value = serde_json::from_str(&snapshot(&["app"])?)?;
+		assert_ne!(changed["packages"][1]["content_hash"], core["content_hash"]);
+		assert_eq!(changed["packages"][0]["content_hash"], doc["packages"][0]["content_hash"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_version_matches() {
+		let check = |version: &str, requirement: &str| version_matches(version, requirement).unwrap();
+		assert!(check("1.4.0", "1.2"));
+		assert!(!check("2.0.0", "^1.2"));
+		assert!(check("0.2.5", "^0.2.3"));
+		assert!(!check("0.3.0", "^0.2.3"));
+		assert!(!check("0.0.4", "^0.0.3"));
+		assert!(check("1.2.9", "~1.2.3"));
+		assert!(!check("1.3.0", "~1.2.3"));
+		assert!(check("1.9.0", "~1"));
+		assert!(check("0.4.0", ">=0.3, <0.5"));
+		assert!(!check("0.5.0", ">=0.3, <0.5"));
+		assert!(check("1.0.0", "=1.0.0"));
+		assert!(check("7.1.0", "*"));
+		assert!(version_matches("1.0.0", "latest").is_err());
+	}
+
+	#[test]
+	fn test_package_rule_files() -> Result<(), Box<dyn std::error::Error>> {
+		let temp = TempDir::new()?;
+		let dir = temp.path().join("rules-pkg");
+		create_test_package(&dir, "rules-pkg", &[])?;
+		fs::create_dir_all(dir.join("rules/a"))?;
+		fs::create_dir_all(dir.join("rules/b"))?;
+		fs::write(dir.join("rules/a/x.hel"), "rules_pkg.value == \"a\"\n")?;
+		fs::write(dir.join("rules/b/x.hel"), "rules_pkg.value == \"b\"\n")?;
+		fs::write(dir.join("rules/script.hel"), "let a = rules_pkg.value == \"a\"\na\n")?;
+		fs::write(dir.join("rules/outcome.hel"), "rules_pkg.value == \"a\" then { label: \"a\" }\n")?;
+		let manifest = dir.join("hel-package.toml");
+		let base = fs::read_to_string(&manifest)?;
+		let load = |rules: &str| {
+			fs::write(&manifest, base.replace("schemas", &format!("rules = [{}]\nschemas", rules)))
+				.expect("write manifest");
+			SchemaPackage::from_directory(&dir)
+		};
+
+		let package = load(r#""rules/a/x.hel""#)?;
+		assert_eq!(package.rules[0].id, "rules-pkg/x");
+
+		// Ids come from file stems, so rules in different directories can collide
+		let err = load(r#""rules/a/x.hel", "rules/b/x.hel""#).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"Duplicate rule id 'rules-pkg/x' in package 'rules-pkg' ('rules/a/x.hel' and 'rules/b/x.hel')"
+		);
+		let err = load(r#""rules/script.hel""#).unwrap_err();
+		assert!(matches!(&err, PackageError::UnsupportedRule { file, .. } if file == "rules/script.hel"));
+		assert!(err.to_string().contains("`let`/`const` scripts are not supported"), "{}", err);
+		let err = load(r#""rules/outcome.hel""#).unwrap_err();
+		assert!(err.to_string().contains("`then { ... }` outcomes are not supported"), "{}", err);
+
+		Ok(())
+	}
 }
 
 // endregion: --- Tests
@@ -0,0 +1,296 @@
+//! A/B simulation of rule-set changes
+//!
+//! `RuleSet::simulate` evaluates two versions of a rule set against the same
+//! records and reports where they disagree, so a rule change can be canaried
+//! against a corpus before rollout.
+//!
+//! ## Determinism
+//! - Records are reported in iterator order
+//! - Rule deltas follow the new set's evaluation order, then rules removed from the old set
+
+use alloc::sync::Arc;
+
+use crate::prelude::*;
+use crate::rules::RuleSet;
+use crate::HelResolver;
+
+// region:    --- Report
+
+/// Differences between two rule-set versions over a corpus of records
+#[derive(Debug, Clone, Default)]
+pub struct SimulationReport {
+	/// Number of records evaluated
+	pub records: usize,
+	/// Records whose set of matched rules differs between the versions
+	pub changed_records: Vec<RecordDelta>,
+	/// Per-rule match counts and changes, for every rule in either version
+	pub rule_deltas: Vec<RuleDelta>,
+}
+
+impl SimulationReport {
+	/// Records whose verdict (whether any rule matched) changed
+	pub fn verdict_changes(&self) -> impl Iterator<Item = &RecordDelta> {
+		self.changed_records.iter().filter(|r| r.verdict_changed())
+	}
+
+	/// Rules that changed behavior (added, removed or matching different records)
+	pub fn changed_rules(&self) -> impl Iterator<Item = &RuleDelta> {
+		self.rule_deltas.iter().filter(|r| r.is_changed())
+	}
+}
+
+/// A record matched by different rules in the two versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordDelta {
+	/// Zero-based position of the record in the input
+	pub index: usize,
+	/// Rules matched by the old version (in evaluation order)
+	pub old_matched: Vec<Arc<str>>,
+	/// Rules matched by the new version (in evaluation order)
+	pub new_matched: Vec<Arc<str>>,
+}
+
+impl RecordDelta {
+	/// Whether the record went from matching no rule to some rule, or back
+	pub fn verdict_changed(&self) -> bool {
+		self.old_matched.is_empty() != self.new_matched.is_empty()
+	}
+}
+
+/// How a rule differs between the two versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleChange {
+	/// Only in the new version
+	Added,
+	/// Only in the old version
+	Removed,
+	/// In both versions with a different condition
+	Modified,
+	/// In both versions with the same condition
+	Unchanged,
+}
+
+/// Match statistics of one rule in both versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleDelta {
+	/// Rule id
+	pub rule_id: Arc<str>,
+	/// How the rule's definition changed
+	pub change: RuleChange,
+	/// Records matched by the old version
+	pub old_matches: usize,
+	/// Records matched by the new version
+	pub new_matches: usize,
+	/// Records that failed to evaluate in the old version
+	pub old_errors: usize,
+	/// Records that failed to evaluate in the new version
+	pub new_errors: usize,
+	/// Records matched only by the new version
+	pub gained: Vec<usize>,
+	/// Records matched only by the old version
+	pub lost: Vec<usize>,
+}
+
+impl RuleDelta {
+	fn new(rule_id: Arc<str>, change: RuleChange) -> Self {
+		Self {
+			rule_id,
+			change,
+			old_matches: 0,
+			new_matches: 0,
+			old_errors: 0,
+			new_errors: 0,
+			gained: Vec::new(),
+			lost: Vec::new(),
+		}
+	}
+
+	/// Whether the rule was added, removed or matches a different set of records
+	pub fn is_changed(&self) -> bool {
+		self.change != RuleChange::Unchanged || !self.gained.is_empty() || !self.lost.is_empty()
+	}
+}
+
+// endregion: --- Report
+
+// region:    --- Simulation
+
+impl RuleSet {
+	/// Evaluate two versions of a rule set against the same records and report differences
+	///
+	/// Rules are matched by id. A rule that fails to evaluate for a record counts as
+	/// not matching it (and is tallied in `old_errors` / `new_errors`).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::{FactsEvalContext, RuleSet, Value};
+	///
+	/// let mut old = RuleSet::new();
+	/// old.add_rule("packed", "binary.entropy > 7.5").unwrap();
+	/// let mut new = RuleSet::new();
+	/// new.add_rule("packed", "binary.entropy > 7.0").unwrap();
+	///
+	/// let records = [6.5, 7.2, 7.9].map(|entropy| {
+	///     let mut ctx = FactsEvalContext::new();
+	///     ctx.add_fact("binary.entropy", Value::Number(entropy));
+	///     ctx
+	/// });
+	///
+	/// let report = RuleSet::simulate(&old, &new, &records);
+	/// assert_eq!(report.records, 3);
+	/// assert_eq!(report.verdict_changes().map(|r| r.index).collect::<Vec<_>>(), vec![1]);
+	/// assert_eq!(report.rule_deltas[0].gained, vec![1]);
+	/// ```
+	pub fn simulate<I, R>(old_set: &RuleSet, new_set: &RuleSet, contexts: I) -> SimulationReport
+	where
+		I: IntoIterator<Item = R>,
+		R: HelResolver,
+	{
+		let mut deltas: Vec<RuleDelta> = new_set
+			.evaluation_order()
+			.map(|rule| {
+				let change = match old_set.get(&rule.id) {
+					None => RuleChange::Added,
+					Some(old) if old.source == rule.source => RuleChange::Unchanged,
+					Some(_) => RuleChange::Modified,
+				};
+				RuleDelta::new(rule.id.clone(), change)
+			})
+			.collect();
+		deltas.extend(
+			old_set
+				.evaluation_order()
+				.filter(|rule| new_set.get(&rule.id).is_none())
+				.map(|rule| RuleDelta::new(rule.id.clone(), RuleChange::Removed)),
+		);
+
+		let mut report = SimulationReport::default();
+		for (index, record) in contexts.into_iter().enumerate() {
+			report.records += 1;
+			let old_matched = tally(old_set, &record, &mut deltas, false);
+			let new_matched = tally(new_set, &record, &mut deltas, true);
+
+			for delta in &mut deltas {
+				match (
+					old_matched.contains(&delta.rule_id),
+					new_matched.contains(&delta.rule_id),
+				) {
+					(false, true) => delta.gained.push(index),
+					(true, false) => delta.lost.push(index),
+					_ => {}
+				}
+			}
+			if old_matched != new_matched {
+				report.changed_records.push(RecordDelta {
+					index,
+					old_matched,
+					new_matched,
+				});
+			}
+		}
+
+		report.rule_deltas = deltas;
+		report
+	}
+}
+
+/// Evaluate `rules` for one record, counting matches and errors; returns the matched ids
+fn tally(rules: &RuleSet, record: &dyn HelResolver, deltas: &mut [RuleDelta], new: bool) -> Vec<Arc<str>> {
+	let mut matched = Vec::new();
+	for (id, result) in rules.evaluate(record) {
+		let delta = deltas
+			.iter_mut()
+			.find(|d| d.rule_id == id)
+			.expect("every rule has a delta");
+		match (result, new) {
+			(Ok(true), false) => delta.old_matches += 1,
+			(Ok(true), true) => delta.new_matches += 1,
+			(Ok(false), _) => continue,
+			(Err(_), false) => {
+				delta.old_errors += 1;
+				continue;
+			}
+			(Err(_), true) => {
+				delta.new_errors += 1;
+				continue;
+			}
+		}
+		matched.push(id);
+	}
+	matched
+}
+
+// endregion: --- Simulation
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{FactsEvalContext, Value};
+
+	fn record(entropy: f64, signed: bool) -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(entropy));
+		ctx.add_fact("binary.signed", Value::Bool(signed));
+		ctx
+	}
+
+	#[test]
+	fn test_simulation_report() {
+		let mut old = RuleSet::new();
+		old.add_rule("packed", "binary.entropy > 7.5").unwrap();
+		old.add_rule("unsigned", "binary.signed == false").unwrap();
+		old.add_rule("legacy", "binary.entropy > 9").unwrap();
+
+		let mut new = RuleSet::new();
+		new.add_rule("packed", "binary.entropy > 7.0").unwrap();
+		new.add_rule("unsigned", "binary.signed == false").unwrap();
+		new.add_rule("strict", "core.nope(binary.entropy) == true").unwrap();
+
+		let records = vec![
+			record(6.0, true),
+			record(7.2, true),
+			record(7.2, false),
+			record(8.0, true),
+		];
+		let report = RuleSet::simulate(&old, &new, &records);
+
+		assert_eq!(report.records, 4);
+		let changed: Vec<usize> = report.changed_records.iter().map(|r| r.index).collect();
+		assert_eq!(changed, vec![1, 2]);
+		let verdicts: Vec<usize> = report.verdict_changes().map(|r| r.index).collect();
+		assert_eq!(verdicts, vec![1]);
+		assert_eq!(report.changed_records[1].old_matched, vec![Arc::from("unsigned")]);
+		assert_eq!(
+			report.changed_records[1].new_matched,
+			vec![Arc::from("packed"), Arc::from("unsigned")]
+		);
+
+		let ids: Vec<(&str, RuleChange)> = report
+			.rule_deltas
+			.iter()
+			.map(|d| (d.rule_id.as_ref(), d.change))
+			.collect();
+		assert_eq!(
+			ids,
+			vec![
+				("packed", RuleChange::Modified),
+				("unsigned", RuleChange::Unchanged),
+				("strict", RuleChange::Added),
+				("legacy", RuleChange::Removed)
+			]
+		);
+		let packed = &report.rule_deltas[0];
+		assert_eq!((packed.old_matches, packed.new_matches), (1, 3));
+		assert_eq!(packed.gained, vec![1, 2]);
+		assert!(packed.lost.is_empty());
+		assert_eq!(report.rule_deltas[2].new_errors, 4);
+
+		let changed: Vec<&str> = report.changed_rules().map(|d| d.rule_id.as_ref()).collect();
+		assert_eq!(changed, vec!["packed", "strict", "legacy"]);
+	}
+}
+
+// endregion: --- Tests
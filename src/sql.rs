@@ -0,0 +1,527 @@
+//! Transpile HEL expressions to parameterized SQL `WHERE` clauses
+//!
+//! Lets hosts push rule pre-filters down into a database or warehouse. Literals
+//! become bind parameters; attributes are mapped to columns by a `ColumnMapper`.
+//! Anything without a faithful SQL translation is an error rather than a guess.
+//!
+//! ## Translation
+//! - `==` `>` `>=` `<` `<=` -> `=` `>` `>=` `<` `<=`
+//! - `x != v` -> `(x <> v OR x IS NULL)`, since a missing attribute is unequal to
+//!   everything in HEL (HEL has no `NOT`; `!=` is the only negation)
+//! - `AND` / `OR` -> parenthesized `AND` / `OR`
+//! - `x IN [a, b]` / `[a, b] CONTAINS x` -> `x IN ($1, $2)`
+//! - `text_column CONTAINS "s"` -> `LIKE` with an escaped `%s%` parameter (`\` is the
+//!   escape character, written `ESCAPE '\\'` for MySQL's string literal syntax)
+//! - `array_column CONTAINS v` / `v IN array_column` -> `v = ANY(column)` (PostgreSQL only)
+//! - A bare boolean attribute -> `column = TRUE` (as a parameter)
+//!
+//! ## Notes
+//! - SQL `NULL` semantics differ from HEL's missing attributes (a comparison with a
+//!   `NULL` column is never true), so translated clauses are pre-filters: re-evaluate
+//!   matches with HEL when exact semantics matter
+//! - Function calls, map literals and identifiers (`$var`, `%CONST`) are rejected
+
+use crate::prelude::*;
+use crate::{AstNode, Comparator, Value};
+
+// region:    --- Types
+
+/// Target SQL dialect (placeholders, identifier quoting, array support)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+	/// `$1` placeholders, `"ident"` quoting, array columns supported
+	Postgres,
+	/// `?` placeholders, `` `ident` `` quoting
+	MySql,
+	/// `?` placeholders, `"ident"` quoting
+	Sqlite,
+}
+
+/// A column an attribute maps to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqlColumn {
+	/// Column name, optionally qualified (`table.column`); quoted per dialect
+	pub name: String,
+	/// Whether the column holds an array (`CONTAINS` / `IN` use `= ANY(...)`)
+	pub array: bool,
+}
+
+impl SqlColumn {
+	/// A scalar column
+	pub fn scalar(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			array: false,
+		}
+	}
+
+	/// An array column (PostgreSQL)
+	pub fn array(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			array: true,
+		}
+	}
+}
+
+/// Maps HEL attributes (`object.field`) to SQL columns
+pub trait ColumnMapper {
+	/// Column for an attribute, or `None` if it has no column (an error)
+	fn map_column(&self, object: &str, field: &str) -> Option<SqlColumn>;
+}
+
+impl<F> ColumnMapper for F
+where
+	F: Fn(&str, &str) -> Option<SqlColumn>,
+{
+	fn map_column(&self, object: &str, field: &str) -> Option<SqlColumn> {
+		self(object, field)
+	}
+}
+
+/// A translated `WHERE` clause and its bind parameters, in placeholder order
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlQuery {
+	/// Clause text (without the `WHERE` keyword)
+	pub clause: String,
+	/// Parameter values (`String`, `Number` or `Bool`)
+	pub params: Vec<Value>,
+}
+
+/// Translation error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlError {
+	/// The column mapper has no column for an attribute
+	UnmappedAttribute(String),
+	/// A construct with no faithful SQL translation in the dialect
+	Unsupported(String),
+}
+
+impl core::fmt::Display for SqlError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			SqlError::UnmappedAttribute(path) => write!(f, "No SQL column mapped for attribute '{}'", path),
+			SqlError::Unsupported(construct) => write!(f, "Cannot translate to SQL: {}", construct),
+		}
+	}
+}
+
+impl core::error::Error for SqlError {}
+
+// endregion: --- Types
+
+// region:    --- to_sql
+
+impl AstNode {
+	/// Translate this expression to a parameterized SQL `WHERE` clause
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use hel::parse_expression;
+	/// use hel::sql::{SqlColumn, SqlDialect};
+	///
+	/// let expr = parse_expression(r#"binary.arch IN ["x86", "arm"] AND binary.entropy > 7.5"#).unwrap();
+	/// let query = expr
+	///     .to_sql(SqlDialect::Postgres, &|object: &str, field: &str| {
+	///         Some(SqlColumn::scalar(format!("{}s.{}", object, field)))
+	///     })
+	///     .unwrap();
+	/// assert_eq!(query.clause, r#"("binarys"."arch" IN ($1, $2) AND "binarys"."entropy" > $3)"#);
+	/// assert_eq!(query.params.len(), 3);
+	/// ```
+	pub fn to_sql(&self, dialect: SqlDialect, mapper: &dyn ColumnMapper) -> Result<SqlQuery, SqlError> {
+		let mut translator = Translator {
+			dialect,
+			mapper,
+			params: Vec::new(),
+		};
+		let clause = translator.condition(self)?;
+		Ok(SqlQuery {
+			clause,
+			params: translator.params,
+		})
+	}
+}
+
+/// A comparison operand after mapping
+enum Operand<'n> {
+	Column(SqlColumn),
+	Literal(Value),
+	List(&'n [AstNode]),
+	Range(&'n AstNode, &'n AstNode),
+}
+
+struct Translator<'m> {
+	dialect: SqlDialect,
+	mapper: &'m dyn ColumnMapper,
+	params: Vec<Value>,
+}
+
+impl Translator<'_> {
+	fn condition(&mut self, node: &AstNode) -> Result<String, SqlError> {
+		match node {
+			AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) if nodes.len() == 1 => {
+				self.condition(&nodes[0])
+			}
+			AstNode::And(nodes) => self.chain(nodes, "AND", "1 = 1"),
+			AstNode::Or(nodes) => self.chain(nodes, "OR", "1 = 0"),
+			AstNode::Xor(nodes) => self.xor(nodes),
+			AstNode::Comparison { left, op, right, .. } => self.comparison(left, *op, right),
+			AstNode::Bool(true) => Ok("1 = 1".to_string()),
+			AstNode::Bool(false) => Ok("1 = 0".to_string()),
+			AstNode::Attribute { .. } => match self.operand(node)? {
+				Operand::Column(column) if !column.array => {
+					let column = self.quote(&column.name);
+					let param = self.param(Value::Bool(true));
+					Ok(format!("{} = {}", column, param))
+				}
+				_ => Err(SqlError::Unsupported("array attribute in boolean position".to_string())),
+			},
+			AstNode::String(_)
+			| AstNode::Number(_)
+			| AstNode::Float(_)
+			| AstNode::Identifier(_)
+			| AstNode::ListLiteral(_)
+			| AstNode::MapLiteral(_)
+			| AstNode::FunctionCall { .. }
+			| AstNode::Range { .. }
+			| AstNode::Wildcard { .. } => Err(unsupported(node)),
+		}
+	}
+
+	fn chain(&mut self, nodes: &[AstNode], op: &str, empty: &str) -> Result<String, SqlError> {
+		if nodes.is_empty() {
+			return Ok(empty.to_string());
+		}
+		let parts = nodes.iter().map(|n| self.condition(n)).collect::<Result<Vec<_>, _>>()?;
+		Ok(format!("({})", parts.join(&format!(" {} ", op))))
+	}
+
+	/// MySQL has `XOR`; elsewhere XOR is inequality of the operands' truth values
+	fn xor(&mut self, nodes: &[AstNode]) -> Result<String, SqlError> {
+		if self.dialect == SqlDialect::MySql {
+			return self.chain(nodes, "XOR", "1 = 0");
+		}
+		let mut parts = nodes.iter().map(|n| self.condition(n));
+		let Some(first) = parts.next() else {
+			return Ok("1 = 0".to_string());
+		};
+		parts.try_fold(first?, |acc, part| Ok(format!("(({}) <> ({}))", acc, part?)))
+	}
+
+	fn comparison(&mut self, left: &AstNode, op: Comparator, right: &AstNode) -> Result<String, SqlError> {
+		let (left, right) = (self.operand(left)?, self.operand(right)?);
+		let sql_op = match op {
+			Comparator::Eq => "=",
+			Comparator::Ne => return self.not_equal(left, right),
+			Comparator::Gt => ">",
+			Comparator::Ge => ">=",
+			Comparator::Lt => "<",
+			Comparator::Le => "<=",
+			Comparator::Contains => return self.membership(right, left, true),
+			Comparator::In => return self.membership(left, right, false),
+			Comparator::Like => return self.like(left, right),
+			Comparator::EqIgnoreCase => {
+				let (left, right) = (self.scalar(left)?, self.scalar(right)?);
+				return Ok(format!("LOWER({}) = LOWER({})", left, right));
+			}
+		};
+
+		let left = self.scalar(left)?;
+		let right = self.scalar(right)?;
+		Ok(format!("{} {} {}", left, sql_op, right))
+	}
+
+	/// `left <> right`, also matching rows where a column operand is `NULL`
+	fn not_equal(&mut self, left: Operand, right: Operand) -> Result<String, SqlError> {
+		let mut nulls = String::new();
+		for operand in [&left, &right] {
+			if let Operand::Column(column) = operand {
+				nulls.push_str(&format!(" OR {} IS NULL", self.quote(&column.name)));
+			}
+		}
+		let (left, right) = (self.scalar(left)?, self.scalar(right)?);
+		if nulls.is_empty() {
+			return Ok(format!("{} <> {}", left, right));
+		}
+		Ok(format!("({} <> {}{})", left, right, nulls))
+	}
+
+	/// `needle IN haystack` (or `haystack CONTAINS needle`)
+	fn membership(&mut self, needle: Operand, haystack: Operand, contains: bool) -> Result<String, SqlError> {
+		match haystack {
+			Operand::List(items) => {
+				if items.is_empty() {
+					return Ok("1 = 0".to_string());
+				}
+				let needle = self.scalar(needle)?;
+				let items = items
+					.iter()
+					.map(|item| match self.operand(item)? {
+						Operand::Literal(value) => Ok(self.param(value)),
+						_ => Err(SqlError::Unsupported("non-literal list element".to_string())),
+					})
+					.collect::<Result<Vec<_>, _>>()?;
+				Ok(format!("{} IN ({})", needle, items.join(", ")))
+			}
+			Operand::Range(start, end) if !contains => {
+				let needle = self.scalar(needle)?;
+				let (start, end) = (self.operand(start)?, self.operand(end)?);
+				let (start, end) = (self.scalar(start)?, self.scalar(end)?);
+				Ok(format!("{} BETWEEN {} AND {}", needle, start, end))
+			}
+			Operand::Range(..) => Err(SqlError::Unsupported("CONTAINS on a range".to_string())),
+			Operand::Column(column) if column.array => {
+				if self.dialect != SqlDialect::Postgres {
+					return Err(SqlError::Unsupported(format!(
+						"array column '{}' in {:?}",
+						column.name, self.dialect
+					)));
+				}
+				let needle = self.scalar(needle)?;
+				Ok(format!("{} = ANY({})", needle, self.quote(&column.name)))
+			}
+			Operand::Column(column) if contains => match needle {
+				Operand::Literal(Value::String(s)) => {
+					let column = self.quote(&column.name);
+					let param = self.param(Value::String(format!("%{}%", escape_like(&s)).into()));
+					Ok(format!("{} LIKE {}{}", column, param, self.like_escape()))
+				}
+				_ => Err(SqlError::Unsupported(
+					"CONTAINS on a text column needs a string literal".to_string(),
+				)),
+			},
+			Operand::Column(column) => Err(SqlError::Unsupported(format!("IN on scalar column '{}'", column.name))),
+			Operand::Literal(Value::String(_)) if contains => {
+				Err(SqlError::Unsupported("CONTAINS on a string literal".to_string()))
+			}
+			Operand::Literal(_) => Err(SqlError::Unsupported(
+				"membership test against a scalar literal".to_string(),
+			)),
+		}
+	}
+
+	/// `text LIKE pattern` with glob wildcards translated to `%` / `_`
+	fn like(&mut self, text: Operand, pattern: Operand) -> Result<String, SqlError> {
+		let Operand::Literal(Value::String(pattern)) = pattern else {
+			return Err(SqlError::Unsupported("LIKE needs a string literal pattern".to_string()));
+		};
+		let text = self.scalar(text)?;
+		let pattern: String = escape_like(&pattern)
+			.chars()
+			.map(|c| match c {
+				'*' => '%',
+				'?' => '_',
+				c => c,
+			})
+			.collect();
+		let param = self.param(Value::String(pattern.into()));
+		Ok(format!("{} LIKE {}{}", text, param, self.like_escape()))
+	}
+
+	/// `ESCAPE` clause naming `\`, which MySQL string literals need doubled
+	fn like_escape(&self) -> &'static str {
+		match self.dialect {
+			SqlDialect::MySql => " ESCAPE '\\\\'",
+			SqlDialect::Postgres | SqlDialect::Sqlite => " ESCAPE '\\'",
+		}
+	}
+
+	fn operand<'n>(&mut self, node: &'n AstNode) -> Result<Operand<'n>, SqlError> {
+		match node {
+			AstNode::Attribute { object, field } => self
+				.mapper
+				.map_column(object, field)
+				.map(Operand::Column)
+				.ok_or_else(|| SqlError::UnmappedAttribute(format!("{}.{}", object, field))),
+			AstNode::String(s) => Ok(Operand::Literal(Value::String(s.clone()))),
+			AstNode::Number(n) => Ok(Operand::Literal(Value::Number(*n as f64))),
+			AstNode::Float(f) => Ok(Operand::Literal(Value::Number(*f))),
+			AstNode::Bool(b) => Ok(Operand::Literal(Value::Bool(*b))),
+			AstNode::ListLiteral(items) => Ok(Operand::List(items)),
+			AstNode::Range { start, end } => Ok(Operand::Range(start, end)),
+			AstNode::And(nodes) | AstNode::Or(nodes) | AstNode::Xor(nodes) if nodes.len() == 1 => {
+				self.operand(&nodes[0])
+			}
+			AstNode::Identifier(_)
+			| AstNode::MapLiteral(_)
+			| AstNode::FunctionCall { .. }
+			| AstNode::Wildcard { .. }
+			| AstNode::Comparison { .. }
+			| AstNode::And(_)
+			| AstNode::Or(_)
+			| AstNode::Xor(_) => Err(unsupported(node)),
+		}
+	}
+
+	/// Render a scalar operand: a quoted column or a placeholder
+	fn scalar(&mut self, operand: Operand) -> Result<String, SqlError> {
+		match operand {
+			Operand::Column(column) if !column.array => Ok(self.quote(&column.name)),
+			Operand::Column(column) => Err(SqlError::Unsupported(format!(
+				"array column '{}' in a scalar comparison",
+				column.name
+			))),
+			Operand::Literal(value) => Ok(self.param(value)),
+			Operand::List(_) => Err(SqlError::Unsupported("list literal in a scalar comparison".to_string())),
+			Operand::Range(..) => Err(SqlError::Unsupported("range in a scalar comparison".to_string())),
+		}
+	}
+
+	fn param(&mut self, value: Value) -> String {
+		self.params.push(value);
+		match self.dialect {
+			SqlDialect::Postgres => format!("${}", self.params.len()),
+			SqlDialect::MySql | SqlDialect::Sqlite => "?".to_string(),
+		}
+	}
+
+	fn quote(&self, name: &str) -> String {
+		let quote = match self.dialect {
+			SqlDialect::MySql => '`',
+			SqlDialect::Postgres | SqlDialect::Sqlite => '"',
+		};
+		name.split('.')
+			.map(|part| {
+				let escaped = part.replace(quote, &format!("{}{}", quote, quote));
+				format!("{}{}{}", quote, escaped, quote)
+			})
+			.collect::<Vec<_>>()
+			.join(".")
+	}
+}
+
+fn unsupported(node: &AstNode) -> SqlError {
+	let construct = match node {
+		AstNode::FunctionCall { namespace, name, .. } => match namespace {
+			Some(ns) => format!("function call {}.{}()", ns, name),
+			None => format!("function call {}()", name),
+		},
+		AstNode::Identifier(name) => format!("identifier '{}'", name),
+		AstNode::MapLiteral(_) => "map literal".to_string(),
+		AstNode::ListLiteral(_) => "list literal in boolean position".to_string(),
+		AstNode::Range { .. } => "range in boolean position".to_string(),
+		AstNode::Wildcard { object, field } => format!("wildcard attribute {}.*.{}", object, field),
+		AstNode::Comparison { .. } | AstNode::And(_) | AstNode::Or(_) | AstNode::Xor(_) => {
+			"boolean expression as an operand".to_string()
+		}
+		AstNode::Bool(_) | AstNode::String(_) | AstNode::Number(_) | AstNode::Float(_) | AstNode::Attribute { .. } => {
+			"literal in boolean position".to_string()
+		}
+	};
+	SqlError::Unsupported(construct)
+}
+
+/// Escape `LIKE` wildcards (`\` is the escape character)
+fn escape_like(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+// endregion: --- to_sql
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse_expression;
+
+	fn mapper(object: &str, field: &str) -> Option<SqlColumn> {
+		match (object, field) {
+			("app", "tags") => Some(SqlColumn::array("tags")),
+			("app", _) => Some(SqlColumn::scalar(field)),
+			_ => None,
+		}
+	}
+
+	fn to_sql(expr: &str, dialect: SqlDialect) -> Result<SqlQuery, SqlError> {
+		parse_expression(expr).expect("parse failed").to_sql(dialect, &mapper)
+	}
+
+	#[test]
+	fn test_to_sql_dialects() {
+		let expr = r#"(app.name CONTAINS "50%_off" OR app.rating >= 4) AND app.vendor != "acme" AND app.verified"#;
+		let query = to_sql(expr, SqlDialect::Postgres).unwrap();
+		assert_eq!(
+			query.clause,
+			r#"(("name" LIKE $1 ESCAPE '\' OR "rating" >= $2) AND ("vendor" <> $3 OR "vendor" IS NULL) AND "verified" = $4)"#
+		);
+		assert_eq!(
+			query.params,
+			vec![
+				Value::String(r"%50\%\_off%".into()),
+				Value::Number(4.0),
+				Value::String("acme".into()),
+				Value::Bool(true),
+			]
+		);
+
+		let query = to_sql(
+			r#"app.name IN ["a", "b"] AND [1, 2] CONTAINS app.level"#,
+			SqlDialect::MySql,
+		)
+		.unwrap();
+		assert_eq!(query.clause, "(`name` IN (?, ?) AND `level` IN (?, ?))");
+
+		let query = to_sql(r#"app.tags CONTAINS "beta""#, SqlDialect::Postgres).unwrap();
+		assert_eq!(query.clause, r#"$1 = ANY("tags")"#);
+
+		let query = to_sql(r#"app.vendor EQI "Acme""#, SqlDialect::Sqlite).unwrap();
+		assert_eq!(query.clause, r#"LOWER("vendor") = LOWER(?)"#);
+
+		let query = to_sql(r#"app.path LIKE "C:\*_v?.exe""#, SqlDialect::Sqlite).unwrap();
+		assert_eq!(query.clause, r#""path" LIKE ? ESCAPE '\'"#);
+		assert_eq!(query.params, vec![Value::String(r"C:\\%\_v_.exe".into())]);
+		let query = to_sql(r#"app.path LIKE "C:\*_v?.exe""#, SqlDialect::MySql).unwrap();
+		assert_eq!(query.clause, r"`path` LIKE ? ESCAPE '\\'");
+		assert_eq!(query.params, vec![Value::String(r"C:\\%\_v_.exe".into())]);
+		let query = to_sql(r#"app.name CONTAINS "50%""#, SqlDialect::MySql).unwrap();
+		assert_eq!(query.clause, r"`name` LIKE ? ESCAPE '\\'");
+
+		// `!=` keeps rows whose column is NULL, like a missing attribute in HEL
+		let query = to_sql("app.level != app.rating", SqlDialect::MySql).unwrap();
+		assert_eq!(query.clause, "(`level` <> `rating` OR `level` IS NULL OR `rating` IS NULL)");
+		let query = to_sql(r#""a" != app.vendor"#, SqlDialect::Sqlite).unwrap();
+		assert_eq!(query.clause, r#"(? <> "vendor" OR "vendor" IS NULL)"#);
+		assert_eq!(to_sql("1 != 2", SqlDialect::Sqlite).unwrap().clause, "? <> ?");
+
+		let query = to_sql("app.level > 1 XOR app.verified", SqlDialect::Postgres).unwrap();
+		assert_eq!(query.clause, r#"(("level" > $1) <> ("verified" = $2))"#);
+		let query = to_sql("app.level > 1 XOR app.verified", SqlDialect::MySql).unwrap();
+		assert_eq!(query.clause, "(`level` > ? XOR `verified` = ?)");
+
+		let query = to_sql("app.level BETWEEN 1 AND app.rating", SqlDialect::Postgres).unwrap();
+		assert_eq!(query.clause, r#""level" BETWEEN $1 AND "rating""#);
+		assert_eq!(
+			to_sql("app.*.level > 1", SqlDialect::Postgres),
+			Err(SqlError::Unsupported("wildcard attribute app.*.level".to_string()))
+		);
+
+		let query = to_sql(r#""x" IN [] OR app.level > 1"#, SqlDialect::Postgres).unwrap();
+		assert_eq!(query.clause, r#"(1 = 0 OR "level" > $1)"#);
+		assert_eq!(query.params, vec![Value::Number(1.0)]);
+	}
+
+	#[test]
+	fn test_to_sql_errors() {
+		assert_eq!(
+			to_sql("other.x == 1", SqlDialect::Sqlite),
+			Err(SqlError::UnmappedAttribute("other.x".to_string()))
+		);
+		assert!(matches!(
+			to_sql(r#"app.tags CONTAINS "beta""#, SqlDialect::Sqlite),
+			Err(SqlError::Unsupported(_))
+		));
+		assert!(matches!(
+			to_sql("core.len(app.tags) > 2", SqlDialect::Postgres),
+			Err(SqlError::Unsupported(_))
+		));
+		assert!(matches!(
+			to_sql("app.level == $limit", SqlDialect::Postgres),
+			Err(SqlError::Unsupported(_))
+		));
+	}
+}
+
+// endregion: --- Tests
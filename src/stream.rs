@@ -0,0 +1,278 @@
+//! Streaming evaluation over record iterators
+//!
+//! Log-scanning workloads evaluate the same rules against millions of records.
+//! `evaluate_stream` takes a pre-parsed `RuleSet` and lazily yields one
+//! `MatchResult` per record, so rule text is parsed once and memory use stays
+//! independent of the number of records.
+//!
+//! Records are evaluated with the options set by `RuleSet::with_options()`.
+//!
+//! ## Determinism
+//! - Records are processed in iterator order
+//! - Matched rule ids are reported in evaluation order (rule-set order, with rules
+//!   referenced by `rule("id")` first); referenced results are memoized per record
+
+use alloc::sync::Arc;
+
+use crate::builtins::BuiltinsRegistry;
+use crate::prelude::*;
+use crate::rules::RuleSet;
+use crate::{EvalError, HelResolver};
+
+// region:    --- MatchResult
+
+/// Result of evaluating a rule set against a single record
+#[derive(Debug, Clone)]
+pub struct MatchResult {
+	/// Zero-based position of the record in the input stream
+	pub index: usize,
+	/// Ids of rules that evaluated to true (in evaluation order)
+	pub matched: Vec<Arc<str>>,
+	/// Rules that failed to evaluate for this record
+	pub errors: Vec<(Arc<str>, EvalError)>,
+	/// True if evaluation stopped early because `max_matches` was reached
+	pub truncated: bool,
+}
+
+impl MatchResult {
+	/// Check if any rule matched this record
+	pub fn is_match(&self) -> bool {
+		!self.matched.is_empty()
+	}
+}
+
+// endregion: --- MatchResult
+
+// region:    --- EvalStream
+
+/// Lazy iterator returned by `evaluate_stream`
+pub struct EvalStream<'a, I> {
+	rules: &'a RuleSet,
+	records: I,
+	builtins: Option<&'a BuiltinsRegistry>,
+	max_matches: Option<usize>,
+	matches_only: bool,
+	index: usize,
+}
+
+impl<'a, I> EvalStream<'a, I> {
+	/// Enable function calls using the given built-ins registry
+	///
+	/// Replaces the registry set with `RuleSet::with_builtins()`, if any.
+	pub fn with_builtins(mut self, builtins: &'a BuiltinsRegistry) -> Self {
+		self.builtins = Some(builtins);
+		self
+	}
+
+	/// Stop evaluating a record after `max` rules have matched
+	///
+	/// Bounds the size of each `MatchResult` for rule sets where many rules may fire.
+	pub fn max_matches(mut self, max: usize) -> Self {
+		self.max_matches = Some(max);
+		self
+	}
+
+	/// Only yield records that matched at least one rule or produced an error
+	pub fn matches_only(mut self) -> Self {
+		self.matches_only = true;
+		self
+	}
+}
+
+impl<I, R> Iterator for EvalStream<'_, I>
+where
+	I: Iterator<Item = R>,
+	R: HelResolver,
+{
+	type Item = MatchResult;
+
+	fn next(&mut self) -> Option<MatchResult> {
+		loop {
+			let record = self.records.next()?;
+			let index = self.index;
+			self.index += 1;
+
+			let mut result = MatchResult {
+				index,
+				matched: Vec::new(),
+				errors: Vec::new(),
+				truncated: false,
+			};
+
+			let max_matches = self.max_matches;
+			result.truncated = self.rules.evaluate_each(
+				|_| true,
+				|| match self.builtins {
+					Some(builtins) => self.rules.context(&record).with_registry(builtins),
+					None => self.rules.context(&record),
+				},
+				|rule, outcome| {
+					match outcome {
+						Ok(true) => result.matched.push(rule.id.clone()),
+						Ok(false) => {}
+						Err(e) => result.errors.push((rule.id.clone(), e)),
+					}
+					max_matches.is_none_or(|max| result.matched.len() < max)
+				},
+			);
+
+			if self.matches_only && result.matched.is_empty() && result.errors.is_empty() {
+				continue;
+			}
+			return Some(result);
+		}
+	}
+}
+
+// endregion: --- EvalStream
+
+/// Evaluate a rule set against a stream of records
+///
+/// Each record is any `HelResolver` (for example a `FactsEvalContext` built from a
+/// log line). Rules are parsed once when the `RuleSet` is built; the returned
+/// iterator is lazy and holds no per-record state between items.
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_stream, FactsEvalContext, RuleSet, Value};
+///
+/// let mut rules = RuleSet::new();
+/// rules.add_rule("is-elf", r#"binary.format == "elf""#).expect("invalid rule");
+///
+/// let records = ["elf", "pe", "elf"].into_iter().map(|format| {
+///     let mut ctx = FactsEvalContext::new();
+///     ctx.add_fact("binary.format", Value::String(format.into()));
+///     ctx
+/// });
+///
+/// let hits: Vec<usize> = evaluate_stream(&rules, records)
+///     .matches_only()
+///     .map(|m| m.index)
+///     .collect();
+/// assert_eq!(hits, vec![0, 2]);
+/// ```
+pub fn evaluate_stream<I, R>(rules: &RuleSet, records: I) -> EvalStream<'_, I::IntoIter>
+where
+	I: IntoIterator<Item = R>,
+	R: HelResolver,
+{
+	EvalStream {
+		rules,
+		records: records.into_iter(),
+		builtins: None,
+		max_matches: None,
+		matches_only: false,
+		index: 0,
+	}
+}
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{CoreBuiltinsProvider, FactsEvalContext, Value};
+
+	fn record(entropy: f64) -> FactsEvalContext {
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("binary.entropy", Value::Number(entropy));
+		ctx
+	}
+
+	fn rules() -> RuleSet {
+		let mut rules = RuleSet::new();
+		rules.add_rule("packed", "binary.entropy > 7.5").expect("add failed");
+		rules
+			.add_rule("very-packed", "binary.entropy > 7.9")
+			.expect("add failed");
+		rules
+	}
+
+	#[test]
+	fn test_stream_yields_one_result_per_record() {
+		let rules = rules();
+		let results: Vec<MatchResult> = evaluate_stream(&rules, vec![record(7.0), record(7.6), record(8.0)]).collect();
+
+		assert_eq!(results.len(), 3);
+		assert!(!results[0].is_match());
+		assert_eq!(results[1].matched, vec![Arc::from("packed")]);
+		assert_eq!(results[2].matched, vec![Arc::from("packed"), Arc::from("very-packed")]);
+	}
+
+	#[test]
+	fn test_stream_max_matches_and_matches_only() {
+		let rules = rules();
+		let results: Vec<MatchResult> = evaluate_stream(&rules, vec![record(7.0), record(8.0)])
+			.max_matches(1)
+			.matches_only()
+			.collect();
+
+		assert_eq!(results.len(), 1);
+		assert_eq!(results[0].index, 1);
+		assert_eq!(results[0].matched.len(), 1);
+		assert!(results[0].truncated);
+	}
+
+	#[test]
+	fn test_stream_with_builtins_and_errors() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("many-perms", "core.len(app.perms) > 1")
+			.expect("add failed");
+
+		let mut ctx = FactsEvalContext::new();
+		ctx.add_fact("app.perms", Value::List(vec!["A".into(), "B".into()]));
+
+		let without: Vec<MatchResult> = evaluate_stream(&rules, vec![&ctx]).collect();
+		assert_eq!(without[0].errors.len(), 1);
+
+		let mut registry = BuiltinsRegistry::new();
+		registry.register(&CoreBuiltinsProvider).expect("register failed");
+		let with: Vec<MatchResult> = evaluate_stream(&rules, vec![&ctx]).with_builtins(&registry).collect();
+		assert!(with[0].is_match());
+		assert!(with[0].errors.is_empty());
+
+		let rules = rules.with_builtins(registry);
+		let owned: Vec<MatchResult> = evaluate_stream(&rules, vec![&ctx]).collect();
+		assert!(owned[0].is_match());
+	}
+
+	#[test]
+	fn test_stream_memoizes_rule_references_per_record() {
+		let mut rules = RuleSet::new();
+		rules
+			.add_rule("packed-not-huge", r#"rule("packed") AND rule("very-packed") == false"#)
+			.expect("add failed");
+		rules.add_rule("packed", "binary.entropy > 7.5").expect("add failed");
+		rules
+			.add_rule("very-packed", "binary.entropy > 7.9")
+			.expect("add failed");
+
+		let results: Vec<MatchResult> = evaluate_stream(&rules, vec![record(7.6), record(8.0)]).collect();
+		assert_eq!(
+			results[0].matched,
+			vec![Arc::from("packed"), Arc::from("packed-not-huge")]
+		);
+		assert_eq!(results[1].matched, vec![Arc::from("packed"), Arc::from("very-packed")]);
+	}
+
+	#[test]
+	fn test_stream_applies_rule_set_options() {
+		let mut rules = RuleSet::new();
+		rules.add_rule("exact", "binary.entropy == 7.5").expect("add failed");
+
+		let tolerant = rules.clone().with_options(crate::EvalOptions::new().with_numeric_tolerance(0.01));
+		let results: Vec<MatchResult> = evaluate_stream(&tolerant, vec![record(7.501)]).collect();
+		assert!(results[0].is_match());
+		let results: Vec<MatchResult> = evaluate_stream(&rules, vec![record(7.501)]).collect();
+		assert!(!results[0].is_match());
+
+		let expired = rules.with_options(crate::EvalOptions::new().with_timeout(std::time::Duration::ZERO));
+		let results: Vec<MatchResult> = evaluate_stream(&expired, vec![record(7.5)]).collect();
+		assert!(!results[0].is_match());
+		assert!(matches!(results[0].errors[..], [(_, EvalError::Timeout)]));
+	}
+}
+
+// endregion: --- Tests
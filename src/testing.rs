@@ -0,0 +1,454 @@
+//! Test support: golden-trace snapshots and fact fixtures
+//!
+//! A rule's trace records every atom it evaluated and every value it resolved, so a
+//! stored trace catches regressions a bare `true`/`false` assertion misses (an atom
+//! that now matches for a different reason, a fact that is no longer read).
+//! `GoldenTrace` evaluates a rule against fixture facts and compares the trace with a
+//! golden file checked in next to the tests.
+//!
+//! ## Snapshot files
+//! - Three sections: the rule source, the `EvalTrace::stable_hash` of the trace and
+//!   the trace's `pretty_print` form, so a regression shows up as a readable diff
+//! - Values pass through the configured `Redactor` before they are stored or hashed,
+//!   so golden files never contain secrets
+//! - Line endings are normalized to `\n` before comparing
+//!
+//! ## Updating
+//! A missing or outdated snapshot fails the check. Set `HEL_UPDATE_SNAPSHOTS=1` (or
+//! call `update_snapshots(true)`) to write the current traces instead, then review the
+//! diff of the golden files.
+//!
+//! ## Fact fixtures
+//! `Facts` builds a `FactsEvalContext` in one expression
+//! (`Facts::new().str("binary.arch", "x86_64").num("binary.entropy", 8.0).build()`)
+//! instead of a mutable context and one `add_fact` call per fact.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::builtins::BuiltinsRegistry;
+use crate::redact::Redactor;
+use crate::trace::trace_ast;
+use crate::{parse_expression, EvalContext, EvalError, FactsEvalContext, HelResolver, Value};
+
+/// Environment variable that turns on snapshot updates in `GoldenTrace::new`
+pub const UPDATE_SNAPSHOTS_ENV: &str = "HEL_UPDATE_SNAPSHOTS";
+
+// region:    --- SnapshotError
+
+/// Failure of a golden-trace check
+#[derive(Debug, Clone)]
+pub enum SnapshotError {
+	/// The rule could not be parsed or evaluated
+	Eval(EvalError),
+	/// The snapshot file could not be read or written
+	Io {
+		/// Snapshot file
+		path: PathBuf,
+		/// I/O error message
+		message: String,
+	},
+	/// No snapshot file exists yet
+	Missing {
+		/// Snapshot file
+		path: PathBuf,
+		/// The snapshot the evaluation produced
+		actual: String,
+	},
+	/// The snapshot file differs from the evaluation
+	Mismatch {
+		/// Snapshot file
+		path: PathBuf,
+		/// Line diff from the stored snapshot (`-`) to the evaluation (`+`)
+		diff: String,
+	},
+}
+
+impl fmt::Display for SnapshotError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SnapshotError::Eval(e) => write!(f, "{}", e),
+			SnapshotError::Io { path, message } => write!(f, "Snapshot {}: {}", path.display(), message),
+			SnapshotError::Missing { path, actual } => write!(
+				f,
+				"Snapshot {} does not exist (set {}=1 to create it):\n{}",
+				path.display(),
+				UPDATE_SNAPSHOTS_ENV,
+				actual
+			),
+			SnapshotError::Mismatch { path, diff } => write!(
+				f,
+				"Snapshot {} does not match (set {}=1 to update it):\n{}",
+				path.display(),
+				UPDATE_SNAPSHOTS_ENV,
+				diff
+			),
+		}
+	}
+}
+
+impl std::error::Error for SnapshotError {}
+
+// endregion: --- SnapshotError
+
+// region:    --- GoldenTrace
+
+/// What a successful golden-trace check did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotStatus {
+	/// The stored snapshot matched
+	Matched,
+	/// Update mode wrote a snapshot that did not exist
+	Created,
+	/// Update mode overwrote a snapshot that differed
+	Updated,
+}
+
+/// Evaluates rules against fixture facts and compares their traces with golden files
+///
+/// # Examples
+///
+/// ```
+/// use hel::testing::{GoldenTrace, SnapshotStatus};
+/// use hel::{FactsEvalContext, RedactionPolicy, Value};
+///
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("user.email", Value::String("ada@example.com".into()));
+/// facts.add_fact("user.failed_logins", Value::Number(7.0));
+///
+/// let policy = RedactionPolicy::new().redact_path("user.email");
+/// let golden = GoldenTrace::new().with_redactor(&policy);
+/// let rule = r#"user.email LIKE "%@example.com" AND user.failed_logins > 5"#;
+///
+/// let snapshot = golden.snapshot(rule, &facts).unwrap();
+/// assert!(snapshot.contains("[REDACTED]") && !snapshot.contains("ada@"));
+///
+/// let dir = std::env::temp_dir().join(format!("hel-golden-{}", std::process::id()));
+/// let path = dir.join("lockout.trace");
+/// let status = golden.update_snapshots(true).check(rule, &facts, &path).unwrap();
+/// assert_eq!(status, SnapshotStatus::Created);
+/// assert_eq!(golden.update_snapshots(false).check(rule, &facts, &path).unwrap(), SnapshotStatus::Matched);
+/// # std::fs::remove_dir_all(dir).unwrap();
+/// ```
+#[derive(Clone, Copy)]
+pub struct GoldenTrace<'a> {
+	builtins: Option<&'a BuiltinsRegistry>,
+	redactor: Option<&'a dyn Redactor>,
+	update: bool,
+}
+
+impl Default for GoldenTrace<'_> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a> GoldenTrace<'a> {
+	/// No built-ins, no redaction; updates snapshots if `HEL_UPDATE_SNAPSHOTS` is set
+	/// to anything but `0` or an empty string
+	pub fn new() -> Self {
+		let update = std::env::var(UPDATE_SNAPSHOTS_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+		Self {
+			builtins: None,
+			redactor: None,
+			update,
+		}
+	}
+
+	/// Built-ins registry the rules are evaluated with
+	pub fn with_builtins(mut self, builtins: &'a BuiltinsRegistry) -> Self {
+		self.builtins = Some(builtins);
+		self
+	}
+
+	/// Redact resolved values before they are stored in (and hashed into) snapshots
+	pub fn with_redactor(mut self, redactor: &'a dyn Redactor) -> Self {
+		self.redactor = Some(redactor);
+		self
+	}
+
+	/// Write current traces to missing or outdated snapshot files instead of failing
+	pub fn update_snapshots(mut self, enabled: bool) -> Self {
+		self.update = enabled;
+		self
+	}
+
+	/// Evaluate `rule` against `resolver` and render its snapshot
+	pub fn snapshot(&self, rule: &str, resolver: &dyn HelResolver) -> Result<String, SnapshotError> {
+		let ast = parse_expression(rule).map_err(|e| SnapshotError::Eval(EvalError::ParseError(e.message)))?;
+		let ctx = match self.builtins {
+			Some(b) => EvalContext::with_builtins(resolver, b),
+			None => EvalContext::new(resolver),
+		};
+		let ctx = match self.redactor {
+			Some(redactor) => ctx.with_redactor(redactor),
+			None => ctx,
+		};
+		let trace = trace_ast(&ast, &ctx).map_err(SnapshotError::Eval)?;
+		Ok(format!(
+			"# rule\n{}\n# hash\n{}\n# trace\n{}",
+			rule.trim_end(),
+			trace.stable_hash(rule),
+			trace.pretty_print()
+		))
+	}
+
+	/// Compare the snapshot of `rule` against `resolver` with the golden file at `path`
+	///
+	/// In update mode, a missing or differing file is (re)written, creating parent
+	/// directories as needed.
+	pub fn check(
+		&self,
+		rule: &str,
+		resolver: &dyn HelResolver,
+		path: impl AsRef<Path>,
+	) -> Result<SnapshotStatus, SnapshotError> {
+		let path = path.as_ref();
+		let actual = self.snapshot(rule, resolver)?;
+		let expected = match fs::read_to_string(path) {
+			Ok(text) => Some(text.replace("\r\n", "\n")),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+			Err(e) => return Err(io_error(path, e)),
+		};
+
+		match expected {
+			Some(expected) if expected == actual => Ok(SnapshotStatus::Matched),
+			expected if self.update => {
+				if let Some(parent) = path.parent() {
+					fs::create_dir_all(parent).map_err(|e| io_error(path, e))?;
+				}
+				fs::write(path, &actual).map_err(|e| io_error(path, e))?;
+				Ok(match expected {
+					Some(_) => SnapshotStatus::Updated,
+					None => SnapshotStatus::Created,
+				})
+			}
+			Some(expected) => Err(SnapshotError::Mismatch {
+				path: path.to_path_buf(),
+				diff: line_diff(&expected, &actual),
+			}),
+			None => Err(SnapshotError::Missing {
+				path: path.to_path_buf(),
+				actual,
+			}),
+		}
+	}
+
+	/// `check`, panicking with the diff on failure (for use in tests)
+	#[track_caller]
+	pub fn assert_matches(&self, rule: &str, resolver: &dyn HelResolver, path: impl AsRef<Path>) {
+		if let Err(e) = self.check(rule, resolver, path) {
+			panic!("{}", e);
+		}
+	}
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> SnapshotError {
+	SnapshotError::Io {
+		path: path.to_path_buf(),
+		message: error.to_string(),
+	}
+}
+
+/// Line diff from `expected` to `actual` (longest common subsequence), unchanged lines
+/// prefixed with two spaces
+fn line_diff(expected: &str, actual: &str) -> String {
+	let old: Vec<&str> = expected.lines().collect();
+	let new: Vec<&str> = actual.lines().collect();
+
+	// common[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+	let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+	for i in (0..old.len()).rev() {
+		for j in (0..new.len()).rev() {
+			common[i][j] = match old[i] == new[j] {
+				true => common[i + 1][j + 1] + 1,
+				false => common[i + 1][j].max(common[i][j + 1]),
+			};
+		}
+	}
+
+	let mut out = String::new();
+	let (mut i, mut j) = (0, 0);
+	while i < old.len() || j < new.len() {
+		if i < old.len() && j < new.len() && old[i] == new[j] {
+			out.push_str(&format!("  {}\n", old[i]));
+			(i, j) = (i + 1, j + 1);
+		} else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+			out.push_str(&format!("- {}\n", old[i]));
+			i += 1;
+		} else {
+			out.push_str(&format!("+ {}\n", new[j]));
+			j += 1;
+		}
+	}
+	out
+}
+
+// endregion: --- GoldenTrace
+
+// region:    --- Facts
+
+/// Fluent builder of `FactsEvalContext` fixtures
+///
+/// Each method adds one fact (replacing an earlier fact with the same path), exactly
+/// like `FactsEvalContext::add_fact`.
+///
+/// # Examples
+///
+/// ```
+/// use hel::testing::Facts;
+/// use hel::evaluate;
+///
+/// let facts = Facts::new()
+///     .str("binary.arch", "x86_64")
+///     .num("binary.entropy", 8.0)
+///     .bool("security.nx", false)
+///     .list("manifest.permissions", ["SEND_SMS", "INTERNET"])
+///     .build();
+///
+/// let rule = r#"binary.entropy > 7.5 AND manifest.permissions CONTAINS "SEND_SMS""#;
+/// assert!(evaluate(rule, &facts).unwrap());
+/// ```
+#[derive(Default)]
+pub struct Facts {
+	context: FactsEvalContext,
+}
+
+impl Facts {
+	/// Builder with no facts
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a string fact
+	pub fn str(self, path: &str, value: &str) -> Self {
+		self.value(path, value)
+	}
+
+	/// Add a number fact
+	pub fn num(self, path: &str, value: f64) -> Self {
+		self.value(path, value)
+	}
+
+	/// Add a boolean fact
+	pub fn bool(self, path: &str, value: bool) -> Self {
+		self.value(path, value)
+	}
+
+	/// Add a fact that is present but null
+	pub fn null(self, path: &str) -> Self {
+		self.value(path, Value::Null)
+	}
+
+	/// Add a list fact (`["a", "b"]`, `[1.0, 2.0]`, or `Value`s for mixed lists)
+	pub fn list<T: Into<Value>>(self, path: &str, items: impl IntoIterator<Item = T>) -> Self {
+		self.value(path, Value::List(items.into_iter().map(Into::into).collect()))
+	}
+
+	/// Add a fact of any value
+	pub fn value(mut self, path: &str, value: impl Into<Value>) -> Self {
+		self.context.add_fact(path, value.into());
+		self
+	}
+
+	/// The finished context
+	pub fn build(self) -> FactsEvalContext {
+		self.context
+	}
+}
+
+impl From<Facts> for FactsEvalContext {
+	fn from(facts: Facts) -> Self {
+		facts.build()
+	}
+}
+
+// endregion: --- Facts
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::RedactionPolicy;
+
+	fn facts(failed_logins: f64) -> FactsEvalContext {
+		Facts::new().str("user.token", "s3cr3t").num("user.failed_logins", failed_logins).build()
+	}
+
+	#[test]
+	fn test_golden_trace_lifecycle() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let path = dir.path().join("nested/lockout.trace");
+		let rule = r#"user.token != "" AND user.failed_logins > 5"#;
+		let policy = RedactionPolicy::new().redact_path("user.token");
+		let golden = GoldenTrace::new().with_redactor(&policy).update_snapshots(false);
+
+		let err = golden.check(rule, &facts(7.0), &path).unwrap_err();
+		assert!(matches!(err, SnapshotError::Missing { .. }));
+		assert!(!path.exists());
+
+		let writer = golden.update_snapshots(true);
+		assert_eq!(writer.check(rule, &facts(7.0), &path).unwrap(), SnapshotStatus::Created);
+		let stored = fs::read_to_string(&path).unwrap();
+		assert!(stored.starts_with("# rule\nuser.token"));
+		assert!(!stored.contains("s3cr3t"));
+		assert_eq!(golden.check(rule, &facts(7.0), &path).unwrap(), SnapshotStatus::Matched);
+
+		// Stored with Windows line endings still matches
+		fs::write(&path, stored.replace('\n', "\r\n")).unwrap();
+		assert_eq!(golden.check(rule, &facts(7.0), &path).unwrap(), SnapshotStatus::Matched);
+
+		// A changed fact shows up as a diff of the hash and the atom line
+		let SnapshotError::Mismatch { diff, .. } = golden.check(rule, &facts(3.0), &path).unwrap_err() else {
+			panic!("expected a mismatch");
+		};
+		assert!(diff.contains("- Result: true\n+ Result: false\n"), "{}", diff);
+		assert!(diff.contains("+   1: user.failed_logins > 5 => left_resolved=Some(\"3\")"), "{}", diff);
+		assert_eq!(diff.lines().filter(|l| l.starts_with('-')).count(), 3, "{}", diff);
+
+		assert_eq!(writer.check(rule, &facts(3.0), &path).unwrap(), SnapshotStatus::Updated);
+		golden.assert_matches(rule, &facts(3.0), &path);
+	}
+
+	#[test]
+	fn test_snapshot_errors() {
+		let golden = GoldenTrace::new();
+		assert!(matches!(golden.snapshot("user.failed_logins >", &facts(1.0)), Err(SnapshotError::Eval(_))));
+
+		// Redaction applies before hashing, so snapshots do not depend on secret values
+		let policy = RedactionPolicy::new().redact_path("user.token");
+		let redacted = golden.with_redactor(&policy);
+		let mut other = facts(7.0);
+		other.add_fact("user.token", Value::String("hunter2".into()));
+		let rule = r#"user.token != """#;
+		assert_eq!(redacted.snapshot(rule, &facts(7.0)).unwrap(), redacted.snapshot(rule, &other).unwrap());
+		assert_ne!(golden.snapshot(rule, &facts(7.0)).unwrap(), golden.snapshot(rule, &other).unwrap());
+	}
+
+	#[test]
+	fn test_facts_builder() {
+		let ctx: FactsEvalContext = Facts::new()
+			.str("binary.arch", "x86_64")
+			.num("binary.entropy", 8.0)
+			.bool("security.nx", true)
+			.null("binary.signer")
+			.list("binary.sections", [".text", ".upx0"])
+			.list("mixed.items", [Value::Number(1.0), Value::from("a")])
+			.num("binary.entropy", 7.5)
+			.into();
+
+		assert_eq!(ctx.resolve_attr("binary", "arch"), Some(Value::from("x86_64")));
+		assert_eq!(ctx.resolve_attr("binary", "entropy"), Some(Value::Number(7.5)));
+		assert_eq!(ctx.resolve_attr("security", "nx"), Some(Value::Bool(true)));
+		assert_eq!(ctx.resolve_attr("binary", "signer"), Some(Value::Null));
+		assert_eq!(
+			ctx.resolve_attr("binary", "sections"),
+			Some(Value::List(vec![Value::from(".text"), Value::from(".upx0")]))
+		);
+		assert_eq!(ctx.resolve_attr("mixed", "items").and_then(|v| v.as_list().map(<[Value]>::len)), Some(2));
+	}
+}
+
+// endregion: --- Tests
@@ -2,8 +2,137 @@
 //!
 //! This module provides evaluation tracing to explain why a rule matched or didn't match.
 //! It captures atom-level comparisons with resolved values for deterministic audit trails.
+//!
+//! Resolved values are stored in full by default. `TraceOptions` keeps traces of
+//! large list and map facts small: values can be truncated to a maximum length
+//! (marked with `TRUNCATION_MARKER`) or dropped entirely, keeping only results.
+//!
+//! With `TraceOptions::with_timings` (and the `std` feature), each atom also
+//! records how long it spent in the resolver and in built-in calls. Timings are
+//! diagnostics only: they never affect `EvalTrace::stable_hash` or trace output.
+
+use alloc::collections::BTreeSet;
+use core::cell::{Cell, RefCell};
+use core::time::Duration;
+
+use crate::prelude::*;
+use crate::redact::Redactor;
+use crate::{
+    AstNode, Comparator, EvalContext, EvalError, EvalOptions, HelResolver, SourceSpan, Value,
+};
+
+/// Appended to resolved values cut short by `TraceVerbosity::Truncated`
+pub const TRUNCATION_MARKER: &str = "…";
+
+/// How much of each resolved value a trace keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceVerbosity {
+    /// Atom results only: no resolved values and no per-fact wildcard matches
+    ResultsOnly,
+    /// Resolved values longer than `TraceOptions::max_value_len` characters are cut
+    /// and end with `TRUNCATION_MARKER`
+    Truncated,
+    /// Resolved values in full
+    #[default]
+    Full,
+}
+
+/// Size guards for captured traces
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_with_trace_options, EvalOptions, FactsEvalContext, TraceOptions, TraceVerbosity, Value};
+///
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("binary.imports", Value::List((0..100).map(|i| Value::Number(i as f64)).collect()));
+///
+/// let options = EvalOptions::new().with_trace_options(
+///     TraceOptions::new()
+///         .with_verbosity(TraceVerbosity::Truncated)
+///         .with_max_value_len(8),
+/// );
+/// let trace = evaluate_with_trace_options("binary.imports CONTAINS 42", &facts, None, &options).unwrap();
+/// assert_eq!(trace.atoms[0].resolved_left_value.as_deref(), Some("[0, 1, 2…"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceOptions {
+    /// Which values are kept
+    pub verbosity: TraceVerbosity,
+    /// Maximum length in characters of a kept value when truncating (before the marker)
+    pub max_value_len: usize,
+    /// Record per-atom wall-clock timings (`AtomTrace::timing`; requires the `std` feature)
+    pub timings: bool,
+}
+
+impl TraceOptions {
+    /// Default maximum value length for `TraceVerbosity::Truncated`
+    pub const DEFAULT_MAX_VALUE_LEN: usize = 256;
+
+    /// Full values (truncation length preset to `DEFAULT_MAX_VALUE_LEN`)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the verbosity level
+    pub fn with_verbosity(mut self, verbosity: TraceVerbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Set the maximum value length used by `TraceVerbosity::Truncated`
+    pub fn with_max_value_len(mut self, max_value_len: usize) -> Self {
+        self.max_value_len = max_value_len;
+        self
+    }
+
+    /// Record per-atom resolver and built-in durations
+    pub fn with_timings(mut self, enabled: bool) -> Self {
+        self.timings = enabled;
+        self
+    }
+
+    /// Apply these options to an atom captured in full
+    fn apply(&self, atom: &mut AtomTrace) {
+        match self.verbosity {
+            TraceVerbosity::Full => {}
+            TraceVerbosity::ResultsOnly => {
+                atom.resolved_left_value = None;
+                atom.resolved_right_value = None;
+                atom.matches.clear();
+            }
+            TraceVerbosity::Truncated => {
+                let values = [
+                    &mut atom.resolved_left_value,
+                    &mut atom.resolved_right_value,
+                ];
+                for value in values.into_iter().flatten() {
+                    self.truncate(value);
+                }
+                for matched in &mut atom.matches {
+                    self.truncate(&mut matched.value);
+                }
+            }
+        }
+    }
 
-use crate::{AstNode, Comparator, EvalContext, EvalError, Value};
+    fn truncate(&self, value: &mut String) {
+        if let Some((cut, _)) = value.char_indices().nth(self.max_value_len) {
+            value.truncate(cut);
+            value.push_str(TRUNCATION_MARKER);
+        }
+    }
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            verbosity: TraceVerbosity::Full,
+            max_value_len: Self::DEFAULT_MAX_VALUE_LEN,
+            timings: false,
+        }
+    }
+}
 
 /// Trace of a single comparison atom in a rule
 #[derive(Debug, Clone)]
@@ -25,6 +154,41 @@ pub struct AtomTrace {
 
     /// Result of this atom evaluation
     pub atom_result: bool,
+
+    /// Per-fact results when an operand is a wildcard (`sections.*.entropy`); empty otherwise
+    pub matches: Vec<WildcardMatch>,
+
+    /// Where the comparison appears in the rule text (`None` for ASTs built in code)
+    pub span: Option<SourceSpan>,
+
+    /// Wall-clock durations, when enabled with `TraceOptions::with_timings`
+    pub timing: Option<AtomTiming>,
+}
+
+/// Time spent evaluating one atom
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AtomTiming {
+    /// Whole atom, including resolution and built-in calls
+    pub total: Duration,
+
+    /// Spent in the resolver (attributes and wildcards)
+    pub resolution: Duration,
+
+    /// Spent in built-in function calls
+    pub builtins: Duration,
+}
+
+/// One fact matched by a wildcard operand
+#[derive(Debug, Clone)]
+pub struct WildcardMatch {
+    /// Concrete fact path (e.g., `sections.text.entropy`)
+    pub path: String,
+
+    /// Resolved value of the fact
+    pub value: String,
+
+    /// Whether the comparison held for this fact
+    pub result: bool,
 }
 
 /// Complete evaluation trace for a rule
@@ -36,8 +200,11 @@ pub struct EvalTrace {
     /// Atom-level traces (in evaluation order)
     pub atoms: Vec<AtomTrace>,
 
-    /// Fact paths that were accessed during evaluation (stored as HashSet internally)
-    facts_used_set: std::collections::HashSet<String>,
+    /// Fact paths the resolver was asked for during evaluation (stored as a BTreeSet internally)
+    facts_used_set: alloc::collections::BTreeSet<String>,
+
+    /// Fact paths the resolver had no value for
+    missing_facts_set: BTreeSet<String>,
 }
 
 impl EvalTrace {
@@ -46,17 +213,13 @@ impl EvalTrace {
         Self {
             result: false,
             atoms: Vec::new(),
-            facts_used_set: std::collections::HashSet::new(),
+            facts_used_set: alloc::collections::BTreeSet::new(),
+            missing_facts_set: BTreeSet::new(),
         }
     }
 
-    /// Add an atom trace
+    /// Add an atom trace (facts used are recorded at resolution time, not from atoms)
     pub fn add_atom(&mut self, atom: AtomTrace) {
-        // Track fact paths from left side (attributes)
-        if atom.left.contains('.') {
-            self.facts_used_set.insert(atom.left.clone());
-        }
-
         self.atoms.push(atom);
     }
 
@@ -66,11 +229,39 @@ impl EvalTrace {
     }
 
     /// Get facts used (sorted for determinism)
+    ///
+    /// Every attribute the resolver was asked for, on either side of a comparison,
+    /// in function arguments, list literals or bare operands, and every fact matched
+    /// by a wildcard. Attributes are recorded whether or not they resolved (see
+    /// `missing_facts()`).
     pub fn facts_used(&self) -> Vec<String> {
         let mut facts: Vec<String> = self.facts_used_set.iter().cloned().collect();
         facts.sort();
         facts
     }
+
+    /// Fact paths that resolved to nothing (sorted)
+    ///
+    /// Attributes the resolver returned `None` for, and wildcards (`object.*.field`)
+    /// that matched no fact. A rule that did not match with missing facts may have
+    /// failed for lack of enrichment data rather than on the data itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hel::{evaluate_with_trace, FactsEvalContext, Value};
+    ///
+    /// let mut facts = FactsEvalContext::new();
+    /// facts.add_fact("binary.format", Value::String("elf".into()));
+    ///
+    /// let trace = evaluate_with_trace(r#"binary.format == "elf" AND intel.score > 50"#, &facts, None).unwrap();
+    /// assert!(!trace.result);
+    /// assert_eq!(trace.facts_used(), vec!["binary.format", "intel.score"]);
+    /// assert_eq!(trace.missing_facts(), vec!["intel.score"]);
+    /// ```
+    pub fn missing_facts(&self) -> Vec<String> {
+        self.missing_facts_set.iter().cloned().collect()
+    }
 }
 
 impl Default for EvalTrace {
@@ -88,26 +279,185 @@ pub fn evaluate_with_trace(
     resolver: &dyn crate::HelResolver,
     builtins: Option<&crate::builtins::BuiltinsRegistry>,
 ) -> Result<EvalTrace, EvalError> {
-    let ast = crate::parse_rule(condition);
+    let ast = crate::parse_expression(condition).map_err(|e| EvalError::ParseError(e.message))?;
     let ctx = if let Some(b) = builtins {
         EvalContext::with_builtins(resolver, b)
     } else {
         EvalContext::new(resolver)
     };
 
+    trace_ast(&ast, &ctx)
+}
+
+/// Evaluate a condition with tracing enabled, redacting sensitive values
+///
+/// Every resolved value is passed through `redactor` before it is stored in the
+/// trace; the evaluation result itself is unaffected.
+pub fn evaluate_with_trace_redacted(
+    condition: &str,
+    resolver: &dyn crate::HelResolver,
+    builtins: Option<&crate::builtins::BuiltinsRegistry>,
+    redactor: &dyn Redactor,
+) -> Result<EvalTrace, EvalError> {
+    evaluate_with_trace_redacted_options(
+        condition,
+        resolver,
+        builtins,
+        redactor,
+        &EvalOptions::default(),
+    )
+}
+
+/// `evaluate_with_trace_redacted()` under explicit evaluation options
+///
+/// The condition is parsed with `options.limits`; values are redacted before
+/// `options.trace` truncates them.
+pub fn evaluate_with_trace_redacted_options(
+    condition: &str,
+    resolver: &dyn crate::HelResolver,
+    builtins: Option<&crate::builtins::BuiltinsRegistry>,
+    redactor: &dyn Redactor,
+    options: &EvalOptions,
+) -> Result<EvalTrace, EvalError> {
+    let ast = crate::parse_expression_with_limits(condition, &options.limits)
+        .map_err(|e| EvalError::ParseError(e.message))?;
+    let ctx = crate::options_context(resolver, builtins, options);
+
+    trace_ast(&ast, &ctx.with_redactor(redactor))
+}
+
+/// Evaluate a condition with tracing enabled under explicit evaluation options
+///
+/// `options.trace` limits how much of each value is kept. Values are redacted (if a
+/// redactor is set on the context) before they are truncated, so redaction always
+/// sees the full value.
+pub fn evaluate_with_trace_options(
+    condition: &str,
+    resolver: &dyn crate::HelResolver,
+    builtins: Option<&crate::builtins::BuiltinsRegistry>,
+    options: &EvalOptions,
+) -> Result<EvalTrace, EvalError> {
+    let ast = crate::parse_expression_with_limits(condition, &options.limits)
+        .map_err(|e| EvalError::ParseError(e.message))?;
+    trace_ast(&ast, &crate::options_context(resolver, builtins, options))
+}
+
+/// Evaluate an already-parsed AST with tracing enabled
+pub(crate) fn trace_ast(ast: &AstNode, ctx: &EvalContext) -> Result<EvalTrace, EvalError> {
+    let timer = Timer::default();
+    let timer = (ctx.options.trace.timings && cfg!(feature = "std")).then_some(&timer);
+    let recorder = FactsRecorder {
+        inner: ctx.resolver,
+        used: RefCell::new(BTreeSet::new()),
+        missing: RefCell::new(BTreeSet::new()),
+        timer,
+    };
+    let mut ctx = ctx.with_resolver(&recorder);
+    ctx.timer = timer;
     let mut trace = EvalTrace::new();
-    let result = evaluate_ast_with_trace(&ast, &ctx, &mut trace)?;
+    let result = evaluate_ast_with_trace(ast, &ctx, &mut trace)?;
     trace.set_result(result);
+    trace.facts_used_set = recorder.used.into_inner();
+    trace.missing_facts_set = recorder.missing.into_inner();
 
     Ok(trace)
 }
 
+/// Resolver wrapper recording the fact paths requested and those that resolved to
+/// nothing (and, if timing, the time spent resolving them)
+struct FactsRecorder<'a> {
+    inner: &'a dyn HelResolver,
+    used: RefCell<BTreeSet<String>>,
+    missing: RefCell<BTreeSet<String>>,
+    timer: Option<&'a Timer>,
+}
+
+impl HelResolver for FactsRecorder<'_> {
+    fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+        let value = Timer::resolution(self.timer, || self.inner.resolve_attr(object, field));
+        let path = format!("{}.{}", object, field);
+        if value.is_none() {
+            self.missing.borrow_mut().insert(path.clone());
+        }
+        self.used.borrow_mut().insert(path);
+        value
+    }
+
+    fn resolve_wildcard(&self, object: &str, field: &str) -> Vec<(String, Value)> {
+        let matches = Timer::resolution(self.timer, || self.inner.resolve_wildcard(object, field));
+        if matches.is_empty() {
+            self.missing
+                .borrow_mut()
+                .insert(format!("{}.*.{}", object, field));
+        }
+        self.used
+            .borrow_mut()
+            .extend(matches.iter().map(|(path, _)| path.clone()));
+        matches
+    }
+}
+
+/// Resolver and built-in time accumulated during a timed trace
+#[derive(Default)]
+pub(crate) struct Timer {
+    resolution: Cell<Duration>,
+    builtins: Cell<Duration>,
+}
+
+impl Timer {
+    /// Run `f`, adding its duration to the resolver total
+    fn resolution<T>(timer: Option<&Timer>, f: impl FnOnce() -> T) -> T {
+        match timer {
+            Some(timer) => measure(&timer.resolution, f),
+            None => f(),
+        }
+    }
+
+    /// Run `f`, adding its duration to the built-in total
+    pub(crate) fn builtin<T>(timer: Option<&Timer>, f: impl FnOnce() -> T) -> T {
+        match timer {
+            Some(timer) => measure(&timer.builtins, f),
+            None => f(),
+        }
+    }
+
+    /// Run `f` (one atom), returning its timing
+    fn atom<T>(&self, f: impl FnOnce() -> T) -> (T, AtomTiming) {
+        let (resolution, builtins) = (self.resolution.get(), self.builtins.get());
+        let total = Cell::new(Duration::ZERO);
+        let out = measure(&total, f);
+        let timing = AtomTiming {
+            total: total.get(),
+            resolution: self.resolution.get() - resolution,
+            builtins: self.builtins.get() - builtins,
+        };
+        (out, timing)
+    }
+}
+
+/// Run `f`, adding its wall-clock duration to `slot` (a no-op without `std`)
+fn measure<T>(slot: &Cell<Duration>, f: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "std")]
+    {
+        let start = std::time::Instant::now();
+        let out = f();
+        slot.set(slot.get() + start.elapsed());
+        out
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = slot;
+        f()
+    }
+}
+
 /// Evaluate AST node with trace capture
 fn evaluate_ast_with_trace(
     ast: &AstNode,
     ctx: &EvalContext,
     trace: &mut EvalTrace,
 ) -> Result<bool, EvalError> {
+    ctx.options.check_interrupt()?;
     match ast {
         AstNode::Bool(b) => Ok(*b),
         AstNode::And(nodes) => {
@@ -126,10 +476,35 @@ fn evaluate_ast_with_trace(
             }
             Ok(false)
         }
-        AstNode::Comparison { left, op, right } => {
-            evaluate_comparison_with_trace(left, *op, right, ctx, trace)
+        // Every operand is evaluated (and traced) to decide the parity
+        AstNode::Xor(nodes) => {
+            let mut result = false;
+            for node in nodes {
+                result ^= evaluate_ast_with_trace(node, ctx, trace)?;
+            }
+            Ok(result)
         }
-        _ => Ok(false),
+        AstNode::Comparison {
+            left,
+            op,
+            right,
+            span,
+        } => match ctx.timer {
+            None => evaluate_comparison_with_trace(left, *op, right, *span, ctx, trace),
+            Some(timer) => {
+                let atoms = trace.atoms.len();
+                let (result, timing) = timer
+                    .atom(|| evaluate_comparison_with_trace(left, *op, right, *span, ctx, trace));
+                if trace.atoms.len() > atoms {
+                    if let Some(atom) = trace.atoms.last_mut() {
+                        atom.timing = Some(timing);
+                    }
+                }
+                result
+            }
+        },
+        // Bare operands (attributes, calls, `rule("id")`) are evaluated without atoms
+        other => crate::evaluate_ast_with_context(other, ctx),
     }
 }
 
@@ -138,31 +513,115 @@ fn evaluate_comparison_with_trace(
     left: &AstNode,
     op: Comparator,
     right: &AstNode,
+    span: SourceSpan,
     ctx: &EvalContext,
     trace: &mut EvalTrace,
 ) -> Result<bool, EvalError> {
-    // Evaluate left and right nodes
-    let left_val = eval_node_to_value_with_context(left, ctx)?;
-    let right_val = eval_node_to_value_with_context(right, ctx)?;
+    if let Some(wildcard) = crate::evaluate_wildcard(left, op, right, ctx)? {
+        return Ok(trace_wildcard(left, op, right, span, wildcard, ctx, trace));
+    }
 
-    // Perform comparison
-    let result = crate::compare_new_values(&left_val, &right_val, op);
+    let (left_val, right_str, result) = match (op, right) {
+        // Range bounds are resolved individually and rendered as `lo..hi`
+        (Comparator::In, AstNode::Range { start, end }) => {
+            let (left_val, low, high, result) = crate::evaluate_range(left, start, end, ctx)?;
+            let range = format!("{}..{}", low, high);
+            (left_val, range, result)
+        }
+        _ => {
+            // Evaluate left and right nodes
+            let left_val = eval_node_to_value_with_context(left, ctx)?;
+            let right_val = eval_node_to_value_with_context(right, ctx)?;
+
+            // Perform comparison
+            let result = crate::compare_values_with_tolerance(
+                &left_val,
+                &right_val,
+                op,
+                ctx.options.numeric_tolerance,
+            );
+            (left_val, right_val.to_string(), result)
+        }
+    };
 
     // Record atom trace
-    let atom = AtomTrace {
+    let mut atom = AtomTrace {
         left: node_to_string(left),
         op,
         right: node_to_string(right),
-        resolved_left_value: Some(value_to_string(&left_val)),
-        resolved_right_value: Some(value_to_string(&right_val)),
+        resolved_left_value: Some(left_val.to_string()),
+        resolved_right_value: Some(right_str),
         atom_result: result,
+        matches: Vec::new(),
+        span: span.is_known().then_some(span),
+        timing: None,
     };
+    if let Some(redactor) = ctx.redactor {
+        atom.redact(redactor);
+    }
+    ctx.options.trace.apply(&mut atom);
 
     trace.add_atom(atom);
 
     Ok(result)
 }
 
+/// Record a wildcard comparison: the wildcard side resolves to the list of matched
+/// values, and each match is listed with its own result
+fn trace_wildcard(
+    left: &AstNode,
+    op: Comparator,
+    right: &AstNode,
+    span: SourceSpan,
+    wildcard: crate::WildcardComparison,
+    ctx: &EvalContext,
+    trace: &mut EvalTrace,
+) -> bool {
+    let values: Vec<Value> = wildcard
+        .matches
+        .iter()
+        .map(|(_, value, _)| value.clone())
+        .collect();
+    let expanded = Value::List(values).to_string();
+    let other = match wildcard.other.as_slice() {
+        [low, high] => format!("{}..{}", low, high),
+        [other] => other.to_string(),
+        _ => String::new(),
+    };
+    let (resolved_left, resolved_right) = match wildcard.wildcard_left {
+        true => (expanded, other),
+        false => (other, expanded),
+    };
+
+    let mut atom = AtomTrace {
+        left: node_to_string(left),
+        op,
+        right: node_to_string(right),
+        resolved_left_value: Some(resolved_left),
+        resolved_right_value: Some(resolved_right),
+        atom_result: wildcard.result,
+        matches: wildcard
+            .matches
+            .into_iter()
+            .map(|(path, value, result)| WildcardMatch {
+                path,
+                value: value.to_string(),
+                result,
+            })
+            .collect(),
+        span: span.is_known().then_some(span),
+        timing: None,
+    };
+    if let Some(redactor) = ctx.redactor {
+        atom.redact(redactor);
+    }
+    ctx.options.trace.apply(&mut atom);
+
+    trace.add_atom(atom);
+
+    wildcard.result
+}
+
 /// Convert an AST node to a string representation
 fn node_to_string(node: &AstNode) -> String {
     match node {
@@ -172,6 +631,7 @@ fn node_to_string(node: &AstNode) -> String {
         AstNode::Float(f) => f.to_string(),
         AstNode::Identifier(s) => s.to_string(),
         AstNode::Attribute { object, field } => format!("{}.{}", object, field),
+        AstNode::Wildcard { object, field } => format!("{}.*.{}", object, field),
         AstNode::ListLiteral(_) => "[...]".to_string(),
         AstNode::MapLiteral(_) => "{...}".to_string(),
         AstNode::FunctionCall {
@@ -183,33 +643,15 @@ fn node_to_string(node: &AstNode) -> String {
                 format!("{}(...)", name)
             }
         }
-        _ => "?".to_string(),
-    }
-}
-
-/// Convert a Value to a string representation
-fn value_to_string(value: &Value) -> String {
-    match value {
-        Value::Null => "null".to_string(),
-        Value::Bool(b) => b.to_string(),
-        Value::String(s) => s.to_string(),
-        Value::Number(n) => n.to_string(),
-        Value::List(items) => {
-            let strs: Vec<String> = items.iter().map(value_to_string).collect();
-            format!("[{}]", strs.join(", "))
-        }
-        Value::Map(m) => {
-            let entries: Vec<String> = m
-                .iter()
-                .map(|(k, v)| format!("{}: {}", k, value_to_string(v)))
-                .collect();
-            format!("{{{}}}", entries.join(", "))
+        AstNode::Range { start, end } => {
+            format!("{}..{}", node_to_string(start), node_to_string(end))
         }
+        _ => "?".to_string(),
     }
 }
 
 /// Helper: return a stable textual operator for a `Comparator`.
-fn comparator_to_str(op: Comparator) -> &'static str {
+pub(crate) fn comparator_to_str(op: Comparator) -> &'static str {
     match op {
         Comparator::Eq => "==",
         Comparator::Ne => "!=",
@@ -219,10 +661,12 @@ fn comparator_to_str(op: Comparator) -> &'static str {
         Comparator::Le => "<=",
         Comparator::Contains => "CONTAINS",
         Comparator::In => "IN",
+        Comparator::EqIgnoreCase => "EQI",
+        Comparator::Like => "LIKE",
     }
 }
 
-use std::fmt;
+use core::fmt;
 
 /// Pretty-print a single atom trace (stable, deterministic)
 impl fmt::Display for AtomTrace {
@@ -250,12 +694,24 @@ impl fmt::Display for EvalTrace {
         // Atoms in order
         for (i, atom) in self.atoms.iter().enumerate() {
             writeln!(f, "  {}: {}", i, atom)?;
+            // Wildcard matches, one per line under their atom
+            for matched in &atom.matches {
+                writeln!(
+                    f,
+                    "     {} = {} => {}",
+                    matched.path, matched.value, matched.result
+                )?;
+            }
         }
         // Facts used summary (sorted)
         let facts = self.facts_used();
         if !facts.is_empty() {
             writeln!(f, "Facts used: {:?}", facts)?;
         }
+        let missing = self.missing_facts();
+        if !missing.is_empty() {
+            writeln!(f, "Missing facts: {:?}", missing)?;
+        }
         Ok(())
     }
 }
@@ -264,7 +720,7 @@ impl fmt::Display for EvalTrace {
 impl EvalTrace {
     /// Return a human-friendly, deterministic multi-line string of the trace.
     pub fn pretty_print(&self) -> String {
-        use std::fmt::Write as FmtWrite;
+        use core::fmt::Write as FmtWrite;
         let mut out = String::new();
         let _ = write!(&mut out, "{}", self); // uses Display impl above
         out
@@ -312,6 +768,18 @@ mod tests {
         assert!(trace.atoms[0].atom_result);
     }
 
+    #[test]
+    fn test_evaluate_with_trace_parse_error() {
+        let deep = format!(
+            "{}binary.format == \"elf\"{}",
+            "(".repeat(40),
+            ")".repeat(40)
+        );
+
+        let err = evaluate_with_trace(&deep, &TestResolver, None).unwrap_err();
+        assert!(matches!(err, EvalError::ParseError(_)));
+    }
+
     #[test]
     fn test_evaluate_with_trace_and() {
         let resolver = TestResolver;
@@ -339,6 +807,29 @@ mod tests {
         assert!(!trace.atoms[0].atom_result);
     }
 
+    #[test]
+    fn test_trace_bare_operands() {
+        let mut builtins = crate::builtins::BuiltinsRegistry::new();
+        builtins
+            .register(&crate::CoreBuiltinsProvider)
+            .expect("register failed");
+
+        // Bare operands used to be traced as false regardless of their value
+        let condition = r#"security.nx_enabled AND binary.format == "elf""#;
+        let trace = evaluate_with_trace(condition, &TestResolver, None).expect("evaluation failed");
+        assert!(trace.result);
+        assert_eq!(trace.atoms.len(), 1);
+
+        let condition = r#"core.contains(binary.format, "el") OR binary.format == "pe""#;
+        let trace = evaluate_with_trace(condition, &TestResolver, Some(&builtins))
+            .expect("evaluation failed");
+        assert!(trace.result);
+        assert_eq!(
+            trace.result,
+            crate::evaluate_with_context(condition, &TestResolver, &builtins).unwrap()
+        );
+    }
+
     #[test]
     fn test_trace_facts_used() {
         let resolver = TestResolver;
@@ -354,6 +845,136 @@ mod tests {
         assert_eq!(facts_used[0], "binary.format");
         assert_eq!(facts_used[1], "security.nx_enabled");
     }
+
+    #[test]
+    fn test_facts_used_on_every_side() {
+        let mut facts = crate::FactsEvalContext::new();
+        facts.add_fact("binary.entropy", Value::Number(7.9));
+        facts.add_fact("policy.max", Value::Number(7.5));
+        facts.add_fact("binary.format", Value::String("elf".into()));
+        facts.add_fact(
+            "policy.formats",
+            Value::List(vec![Value::String("elf".into())]),
+        );
+
+        let condition = r#"7.5 < binary.entropy AND policy.max < binary.entropy
+            AND [policy.default, "pe"] CONTAINS "pe" AND binary.format IN policy.formats"#;
+        let trace = evaluate_with_trace(condition, &facts, None).expect("evaluation failed");
+        assert!(trace.result);
+        assert_eq!(
+            trace.facts_used(),
+            vec![
+                "binary.entropy",
+                "binary.format",
+                "policy.default",
+                "policy.formats",
+                "policy.max"
+            ]
+        );
+        assert_eq!(trace.missing_facts(), vec!["policy.default"]);
+    }
+
+    #[test]
+    fn test_facts_used_in_calls_and_bare_operands() {
+        let mut builtins = crate::builtins::BuiltinsRegistry::new();
+        builtins
+            .register(&crate::CoreBuiltinsProvider)
+            .expect("register failed");
+        let mut facts = crate::FactsEvalContext::new();
+        facts.add_fact("flags.debug", Value::Bool(true));
+        facts.add_fact("app.perms", Value::List(vec!["SMS".into()]));
+
+        // Only left operands containing a dot used to be recorded
+        let condition = "flags.debug AND 1 == core.len(app.perms)";
+        let trace =
+            evaluate_with_trace(condition, &facts, Some(&builtins)).expect("evaluation failed");
+        assert!(trace.result);
+        assert_eq!(trace.facts_used(), vec!["app.perms", "flags.debug"]);
+    }
+
+    #[test]
+    fn test_trace_timings() {
+        let resolver = TestResolver;
+        let condition = r#"binary.format == "elf" AND security.nx_enabled == true"#;
+
+        let untimed = evaluate_with_trace(condition, &resolver, None).expect("evaluation failed");
+        assert!(untimed.atoms.iter().all(|a| a.timing.is_none()));
+
+        let options = EvalOptions::new().with_trace_options(TraceOptions::new().with_timings(true));
+        let timed = evaluate_with_trace_options(condition, &resolver, None, &options)
+            .expect("evaluation failed");
+        for atom in &timed.atoms {
+            let timing = atom.timing.expect("timing");
+            assert!(timing.resolution + timing.builtins <= timing.total);
+        }
+        assert_eq!(timed.to_string(), untimed.to_string());
+    }
+
+    #[test]
+    fn test_trace_missing_facts() {
+        let resolver = TestResolver;
+        let condition =
+            r#"binary.format == "elf" AND (intel.score > 50 OR sections.*.name == ".text")"#;
+
+        let trace = evaluate_with_trace(condition, &resolver, None).expect("evaluation failed");
+        assert!(!trace.result);
+        assert_eq!(
+            trace.missing_facts(),
+            vec!["intel.score", "sections.*.name"]
+        );
+        assert!(trace
+            .to_string()
+            .contains("Missing facts: [\"intel.score\""));
+
+        let complete = evaluate_with_trace(r#"binary.format == "elf""#, &resolver, None)
+            .expect("evaluation failed");
+        assert!(complete.missing_facts().is_empty());
+    }
+
+    #[test]
+    fn test_trace_verbosity() {
+        let mut facts = crate::FactsEvalContext::new();
+        facts.add_fact("binary.format", Value::String("elf".into()));
+        let section = |name: String| {
+            Value::Map([(alloc::sync::Arc::from("name"), Value::String(name.into()))].into())
+        };
+        facts.add_fact("sections.text", section("é".repeat(10)));
+        facts.add_fact("sections.data", section(".data".into()));
+        let condition = r#"binary.format == "elf" AND sections.*.name == ".data""#;
+        let trace = |options: TraceOptions| {
+            let options = EvalOptions::new().with_trace_options(options);
+            evaluate_with_trace_options(condition, &facts, None, &options)
+                .expect("evaluation failed")
+        };
+
+        let full = trace(TraceOptions::new());
+        assert_eq!(full.atoms[1].matches[1].value, "é".repeat(10));
+
+        let truncated = trace(
+            TraceOptions::new()
+                .with_verbosity(TraceVerbosity::Truncated)
+                .with_max_value_len(4),
+        );
+        assert!(truncated.result);
+        assert_eq!(
+            truncated.atoms[0].resolved_left_value.as_deref(),
+            Some("elf")
+        );
+        assert_eq!(truncated.atoms[1].matches[1].value, "éééé…");
+        assert_eq!(
+            truncated.atoms[1].resolved_left_value.as_deref(),
+            Some("[.da…")
+        );
+
+        let results_only = trace(TraceOptions::new().with_verbosity(TraceVerbosity::ResultsOnly));
+        assert!(results_only.atoms.iter().all(|a| a.atom_result));
+        assert!(results_only
+            .atoms
+            .iter()
+            .all(|a| a.resolved_left_value.is_none()));
+        assert!(results_only.atoms[1].matches.is_empty());
+        assert_eq!(results_only.facts_used(), full.facts_used());
+    }
 }
 
 // endregion: --- Tests
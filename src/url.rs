@@ -0,0 +1,283 @@
+//! URL and domain built-ins (`url` namespace)
+//!
+//! Network detections decompose URLs over and over; doing it with string built-ins
+//! is fragile (userinfo, ports, IPv6 literals, trailing dots). `UrlBuiltinsProvider`
+//! does it once, deterministically and without I/O.
+//!
+//! ## Functions
+//! - `url.scheme(s)`: lowercased scheme before `://`, or null
+//! - `url.host(s)`: lowercased host of a URL or bare authority (`user@Host:443/path` gives
+//!   `host`), without userinfo, port or trailing dot; IPv6 literals keep their brackets
+//! - `url.is_ip_host(s)`: whether the host is an IPv4 or IPv6 address, including the
+//!   numeric forms browsers accept (`0x7f.1`, `2130706433`)
+//! - `url.registered_domain(s)`: registrable domain under the Public Suffix List
+//!   (`www.example.co.uk` gives `example.co.uk`); only with the `psl` feature and a list
+//!   supplied through `UrlBuiltinsProvider::with_public_suffix_list`
+//!
+//! Every function accepts either a full URL or a bare host.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::net::Ipv6Addr;
+
+use crate::builtins::{BuiltinArgs, BuiltinFn, BuiltinsProvider};
+use crate::prelude::*;
+use crate::{EvalError, Value};
+
+// region:    --- UrlBuiltinsProvider
+
+/// Provider of the `url` namespace
+///
+/// # Examples
+///
+/// ```
+/// use hel::{evaluate_with_context, BuiltinsRegistry, FactsEvalContext, UrlBuiltinsProvider, Value};
+///
+/// let builtins = BuiltinsRegistry::builder().with(&UrlBuiltinsProvider::new()).build().unwrap();
+/// let mut facts = FactsEvalContext::new();
+/// facts.add_fact("http.url", Value::String("HTTPS://admin@Login.Example.com:8443/reset".into()));
+///
+/// let rule = r#"url.scheme(http.url) == "https" AND url.host(http.url) == "login.example.com""#;
+/// assert!(evaluate_with_context(rule, &facts, &builtins).unwrap());
+/// ```
+#[derive(Clone, Default)]
+pub struct UrlBuiltinsProvider {
+	#[cfg(feature = "psl")]
+	suffixes: Option<Arc<publicsuffix::List>>,
+	#[cfg(feature = "psl")]
+	version: Option<String>,
+}
+
+impl UrlBuiltinsProvider {
+	/// Provider of `url.scheme`, `url.host` and `url.is_ip_host`
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Also provide `url.registered_domain`, using the Public Suffix List in `list`
+	/// (the text of `public_suffix_list.dat`)
+	///
+	/// The list is part of the provider's version (and so of the registry fingerprint),
+	/// so a list update shows up in audit records. Returns an error if the list does not
+	/// parse or has no rules.
+	#[cfg(feature = "psl")]
+	pub fn with_public_suffix_list(mut self, list: &str) -> Result<Self, String> {
+		let suffixes: publicsuffix::List =
+			list.parse().map_err(|e| format!("Invalid public suffix list: {}", e))?;
+		let digest = crate::audit::sha256_hex(list.as_bytes());
+		self.version = Some(format!("{}+psl.{}", env!("CARGO_PKG_VERSION"), &digest[..12]));
+		self.suffixes = Some(Arc::new(suffixes));
+		Ok(self)
+	}
+}
+
+impl BuiltinsProvider for UrlBuiltinsProvider {
+	fn namespace(&self) -> &str {
+		"url"
+	}
+
+	fn version(&self) -> Option<&str> {
+		#[cfg(feature = "psl")]
+		if let Some(version) = &self.version {
+			return Some(version);
+		}
+		Some(env!("CARGO_PKG_VERSION"))
+	}
+
+	fn get_builtins(&self) -> BTreeMap<String, BuiltinFn> {
+		let mut builtins = BTreeMap::new();
+
+		// url.scheme(s) - lowercased scheme, or null
+		builtins.insert(
+			"scheme".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let s = string_arg("url.scheme", args)?;
+				Ok(scheme(s).map_or(Value::Null, |scheme| Value::String(scheme.to_lowercase().into())))
+			}) as BuiltinFn,
+		);
+
+		// url.host(s) - lowercased host, or null
+		builtins.insert(
+			"host".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let s = string_arg("url.host", args)?;
+				Ok(host(s).map_or(Value::Null, |host| Value::String(host.into())))
+			}) as BuiltinFn,
+		);
+
+		// url.is_ip_host(s) - whether the host is an IP address
+		builtins.insert(
+			"is_ip_host".to_string(),
+			Arc::new(|args: &[Value]| -> Result<Value, EvalError> {
+				let s = string_arg("url.is_ip_host", args)?;
+				Ok(Value::Bool(host(s).is_some_and(|host| is_ip(&host))))
+			}) as BuiltinFn,
+		);
+
+		// url.registered_domain(s) - registrable domain, or null (IP hosts, bare suffixes)
+		#[cfg(feature = "psl")]
+		if let Some(suffixes) = &self.suffixes {
+			use publicsuffix::Psl;
+
+			let suffixes = Arc::clone(suffixes);
+			builtins.insert(
+				"registered_domain".to_string(),
+				Arc::new(move |args: &[Value]| -> Result<Value, EvalError> {
+					let s = string_arg("url.registered_domain", args)?;
+					let domain = host(s)
+						.filter(|host| !is_ip(host))
+						.and_then(|host| {
+							let domain = suffixes.domain(host.as_bytes())?;
+							core::str::from_utf8(domain.as_bytes()).ok().map(Arc::from)
+						});
+					Ok(domain.map_or(Value::Null, Value::String))
+				}) as BuiltinFn,
+			);
+		}
+
+		builtins
+	}
+}
+
+// endregion: --- UrlBuiltinsProvider
+
+// region:    --- URL Helpers
+
+/// Check the single string argument of a `url` builtin
+fn string_arg<'a>(name: &'a str, args: &'a [Value]) -> Result<&'a str, EvalError> {
+	let args = BuiltinArgs::new(name, args);
+	args.expect_args(1)?;
+	args.expect_string(0)
+}
+
+/// Scheme before `://`, if it is a valid scheme (a letter, then letters, digits, `+`, `-`, `.`)
+fn scheme(s: &str) -> Option<&str> {
+	let (scheme, _) = s.trim().split_once("://")?;
+	let mut chars = scheme.chars();
+	let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+		&& chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+	valid.then_some(scheme)
+}
+
+/// Lowercased host of a URL or bare authority, without userinfo, port or trailing dot
+fn host(s: &str) -> Option<String> {
+	let s = s.trim();
+	let rest = match scheme(s) {
+		Some(scheme) => &s[scheme.len() + 3..],
+		None => s.strip_prefix("//").unwrap_or(s),
+	};
+	// Browsers treat `\` like `/` in web URLs
+	let authority = rest.split(['/', '\\', '?', '#']).next().unwrap_or_default();
+	let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+	let host = match authority.find(']') {
+		Some(end) if authority.starts_with('[') => &authority[..=end],
+		_ => authority.split(':').next().unwrap_or_default(),
+	};
+	let host = host.trim_end_matches('.');
+	(!host.is_empty()).then(|| host.to_lowercase())
+}
+
+/// Whether `host` is an IPv6 literal (`[::1]`) or an IPv4 address in any form browsers accept
+fn is_ip(host: &str) -> bool {
+	match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+		Some(ipv6) => ipv6.parse::<Ipv6Addr>().is_ok(),
+		None => is_ipv4(host),
+	}
+}
+
+/// IPv4 address as parsed by browsers: one to four dot-separated numbers, each decimal,
+/// octal (leading `0`) or hexadecimal (`0x`); the last fills the remaining bytes
+fn is_ipv4(host: &str) -> bool {
+	let parts: Vec<&str> = host.split('.').collect();
+	if parts.len() > 4 {
+		return false;
+	}
+	let Some(numbers) = parts.iter().map(|part| ipv4_number(part)).collect::<Option<Vec<u64>>>() else {
+		return false;
+	};
+	let (last, leading) = numbers.split_last().expect("split yields at least one part");
+	leading.iter().all(|&n| n <= 255) && *last < 1u64 << (8 * (5 - numbers.len()))
+}
+
+fn ipv4_number(part: &str) -> Option<u64> {
+	let (digits, radix) = match part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+		Some(hex) => (hex, 16),
+		None if part.len() > 1 && part.starts_with('0') => (&part[1..], 8),
+		None => (part, 10),
+	};
+	match digits {
+		"" if radix == 16 => Some(0),
+		"" => None,
+		_ if !digits.chars().all(|c| c.is_digit(radix)) => None,
+		_ => u64::from_str_radix(digits, radix).ok(),
+	}
+}
+
+// endregion: --- URL Helpers
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scheme_and_host() {
+		let builtins = UrlBuiltinsProvider::new().get_builtins();
+		let call = |name: &str, s: &str| builtins[name](&[Value::from(s)]).unwrap();
+
+		assert_eq!(call("scheme", "HTTPS://example.com"), Value::from("https"));
+		assert_eq!(call("scheme", "example.com:8080/x"), Value::Null);
+		assert_eq!(call("scheme", "1http://example.com"), Value::Null);
+
+		let cases = [
+			("https://www.Example.com/login?next=/", "www.example.com"),
+			("http://user:pw@evil.example:8080#frag", "evil.example"),
+			("http://trusted.example@evil.example/", "evil.example"),
+			("https://example.com.", "example.com"),
+			("//cdn.example.net/lib.js", "cdn.example.net"),
+			("https://example.com\\@evil.example", "example.com"),
+			("Example.org:443", "example.org"),
+			("http://[2001:db8::1]:8080/", "[2001:db8::1]"),
+		];
+		for (url, host) in cases {
+			assert_eq!(call("host", url), Value::from(host), "{}", url);
+		}
+		assert_eq!(call("host", "https:///path"), Value::Null);
+		assert!(builtins["host"](&[Value::Number(1.0)]).is_err());
+	}
+
+	#[test]
+	fn test_is_ip_host() {
+		let builtins = UrlBuiltinsProvider::new().get_builtins();
+		let is_ip_host = |s: &str| builtins["is_ip_host"](&[Value::from(s)]).unwrap() == Value::Bool(true);
+
+		for ip in ["http://192.168.0.1/", "10.0.0.1:22", "http://[::1]/", "http://0x7f.1/", "http://2130706433/"] {
+			assert!(is_ip_host(ip), "{}", ip);
+		}
+		for host in ["http://example.com/", "1.2.3.4.5", "256.1.1.1", "http://[example]/", "example.123", "08.1.1.1"] {
+			assert!(!is_ip_host(host), "{}", host);
+		}
+	}
+
+	#[cfg(feature = "psl")]
+	#[test]
+	fn test_registered_domain() {
+		let list = "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n// ===END ICANN DOMAINS===\n";
+		assert!(!UrlBuiltinsProvider::new().get_builtins().contains_key("registered_domain"));
+
+		let provider = UrlBuiltinsProvider::new().with_public_suffix_list(list).unwrap();
+		let builtins = provider.get_builtins();
+		let call = |s: &str| builtins["registered_domain"](&[Value::from(s)]).unwrap();
+
+		assert_eq!(call("https://login.example.co.uk/x"), Value::from("example.co.uk"));
+		assert_eq!(call("www.example.com"), Value::from("example.com"));
+		assert_eq!(call("co.uk"), Value::Null);
+		assert_eq!(call("http://192.168.0.1/"), Value::Null);
+
+		assert!(provider.version().unwrap().contains("+psl."));
+		assert!(UrlBuiltinsProvider::new().with_public_suffix_list("").is_err());
+	}
+}
+
+// endregion: --- Tests
@@ -0,0 +1,347 @@
+//! AST visitor for HEL expressions
+//!
+//! Tools such as linters, analyzers and transpilers need to traverse expressions
+//! without pattern-matching every `AstNode` variant by hand. Implement `Visitor`,
+//! override the hooks you care about, and call `walk_ast`.
+//!
+//! ## Traversal
+//! - Depth-first, children in source order (left before right, arguments in order)
+//! - `enter_node` / `exit_node` fire for every node, around the per-type hooks
+//! - Returning `Walk::SkipChildren` from an enter hook skips that node's children
+//!   (its exit hooks still fire); `Walk::Stop` ends the traversal immediately
+
+use alloc::sync::Arc;
+
+use crate::{AstNode, Comparator};
+
+// region:    --- Walk
+
+/// Traversal control returned by enter hooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Walk {
+	/// Visit the node's children
+	#[default]
+	Continue,
+	/// Do not visit the node's children
+	SkipChildren,
+	/// Stop the traversal altogether
+	Stop,
+}
+
+// endregion: --- Walk
+
+// region:    --- Visitor
+
+/// Hooks invoked by `walk_ast`
+///
+/// Every hook has a no-op default. Leaf nodes get a single `visit_*` hook;
+/// nodes with children get `enter_*` / `exit_*` pairs.
+#[allow(unused_variables)]
+pub trait Visitor {
+	/// Called before any other hook for every node
+	fn enter_node(&mut self, node: &AstNode) -> Walk {
+		Walk::Continue
+	}
+
+	/// Called after all other hooks for every node
+	fn exit_node(&mut self, node: &AstNode) {}
+
+	/// Boolean literal
+	fn visit_bool(&mut self, value: bool) {}
+
+	/// String literal
+	fn visit_string(&mut self, value: &str) {}
+
+	/// Integer literal
+	fn visit_number(&mut self, value: u64) {}
+
+	/// Float literal
+	fn visit_float(&mut self, value: f64) {}
+
+	/// Identifier (variable or symbolic reference)
+	fn visit_identifier(&mut self, name: &str) {}
+
+	/// Attribute access (`object.field`)
+	fn visit_attribute(&mut self, object: &str, field: &str) {}
+
+	/// Wildcard attribute (`object.*.field`)
+	fn visit_wildcard(&mut self, object: &str, field: &str) {}
+
+	/// Before the operands of a comparison
+	fn enter_comparison(&mut self, left: &AstNode, op: Comparator, right: &AstNode) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the operands of a comparison
+	fn exit_comparison(&mut self, left: &AstNode, op: Comparator, right: &AstNode) {}
+
+	/// Before the operands of an AND
+	fn enter_and(&mut self, nodes: &[AstNode]) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the operands of an AND
+	fn exit_and(&mut self, nodes: &[AstNode]) {}
+
+	/// Before the operands of an OR
+	fn enter_or(&mut self, nodes: &[AstNode]) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the operands of an OR
+	fn exit_or(&mut self, nodes: &[AstNode]) {}
+
+	/// Before the operands of an XOR
+	fn enter_xor(&mut self, nodes: &[AstNode]) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the operands of an XOR
+	fn exit_xor(&mut self, nodes: &[AstNode]) {}
+
+	/// Before the items of a list literal
+	fn enter_list(&mut self, items: &[AstNode]) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the items of a list literal
+	fn exit_list(&mut self, items: &[AstNode]) {}
+
+	/// Before the values of a map literal
+	fn enter_map(&mut self, entries: &[(Arc<str>, AstNode)]) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the values of a map literal
+	fn exit_map(&mut self, entries: &[(Arc<str>, AstNode)]) {}
+
+	/// Before the arguments of a function call
+	fn enter_function_call(&mut self, namespace: Option<&str>, name: &str, args: &[AstNode]) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the arguments of a function call
+	fn exit_function_call(&mut self, namespace: Option<&str>, name: &str, args: &[AstNode]) {}
+
+	/// Before the bounds of a range
+	fn enter_range(&mut self, start: &AstNode, end: &AstNode) -> Walk {
+		Walk::Continue
+	}
+
+	/// After the bounds of a range
+	fn exit_range(&mut self, start: &AstNode, end: &AstNode) {}
+}
+
+// endregion: --- Visitor
+
+// region:    --- walk_ast
+
+/// Traverse an expression depth-first, invoking the visitor's hooks
+///
+/// Returns `Walk::Stop` if the visitor stopped the traversal, `Walk::Continue` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use hel::parse_expression;
+/// use hel::visit::{walk_ast, Visitor};
+///
+/// #[derive(Default)]
+/// struct Attributes(Vec<String>);
+///
+/// impl Visitor for Attributes {
+///     fn visit_attribute(&mut self, object: &str, field: &str) {
+///         self.0.push(format!("{}.{}", object, field));
+///     }
+/// }
+///
+/// let ast = parse_expression(r#"binary.arch == "x86_64" AND core.len(app.perms) > 2"#).unwrap();
+/// let mut attrs = Attributes::default();
+/// walk_ast(&ast, &mut attrs);
+/// assert_eq!(attrs.0, vec!["binary.arch", "app.perms"]);
+/// ```
+pub fn walk_ast<V: Visitor + ?Sized>(node: &AstNode, visitor: &mut V) -> Walk {
+	match visitor.enter_node(node) {
+		Walk::Stop => return Walk::Stop,
+		Walk::SkipChildren => {
+			visitor.exit_node(node);
+			return Walk::Continue;
+		}
+		Walk::Continue => {}
+	}
+
+	let walk = match node {
+		AstNode::Bool(b) => {
+			visitor.visit_bool(*b);
+			Walk::Continue
+		}
+		AstNode::String(s) => {
+			visitor.visit_string(s);
+			Walk::Continue
+		}
+		AstNode::Number(n) => {
+			visitor.visit_number(*n);
+			Walk::Continue
+		}
+		AstNode::Float(f) => {
+			visitor.visit_float(*f);
+			Walk::Continue
+		}
+		AstNode::Identifier(name) => {
+			visitor.visit_identifier(name);
+			Walk::Continue
+		}
+		AstNode::Attribute { object, field } => {
+			visitor.visit_attribute(object, field);
+			Walk::Continue
+		}
+		AstNode::Wildcard { object, field } => {
+			visitor.visit_wildcard(object, field);
+			Walk::Continue
+		}
+		AstNode::Comparison { left, op, right, .. } => {
+			let walk = visitor.enter_comparison(left, *op, right);
+			let walk = walk_children(walk, [left.as_ref(), right.as_ref()], visitor);
+			visitor.exit_comparison(left, *op, right);
+			walk
+		}
+		AstNode::And(nodes) => {
+			let walk = visitor.enter_and(nodes);
+			let walk = walk_children(walk, nodes, visitor);
+			visitor.exit_and(nodes);
+			walk
+		}
+		AstNode::Or(nodes) => {
+			let walk = visitor.enter_or(nodes);
+			let walk = walk_children(walk, nodes, visitor);
+			visitor.exit_or(nodes);
+			walk
+		}
+		AstNode::Xor(nodes) => {
+			let walk = visitor.enter_xor(nodes);
+			let walk = walk_children(walk, nodes, visitor);
+			visitor.exit_xor(nodes);
+			walk
+		}
+		AstNode::ListLiteral(items) => {
+			let walk = visitor.enter_list(items);
+			let walk = walk_children(walk, items, visitor);
+			visitor.exit_list(items);
+			walk
+		}
+		AstNode::MapLiteral(entries) => {
+			let walk = visitor.enter_map(entries);
+			let walk = walk_children(walk, entries.iter().map(|(_, v)| v), visitor);
+			visitor.exit_map(entries);
+			walk
+		}
+		AstNode::FunctionCall { namespace, name, args, .. } => {
+			let walk = visitor.enter_function_call(namespace.as_deref(), name, args);
+			let walk = walk_children(walk, args, visitor);
+			visitor.exit_function_call(namespace.as_deref(), name, args);
+			walk
+		}
+		AstNode::Range { start, end } => {
+			let walk = visitor.enter_range(start, end);
+			let walk = walk_children(walk, [start.as_ref(), end.as_ref()], visitor);
+			visitor.exit_range(start, end);
+			walk
+		}
+	};
+
+	if walk == Walk::Stop {
+		return Walk::Stop;
+	}
+	visitor.exit_node(node);
+	Walk::Continue
+}
+
+fn walk_children<'n, V: Visitor + ?Sized>(
+	walk: Walk,
+	children: impl IntoIterator<Item = &'n AstNode>,
+	visitor: &mut V,
+) -> Walk {
+	if walk != Walk::Continue {
+		return walk;
+	}
+	for child in children {
+		if walk_ast(child, visitor) == Walk::Stop {
+			return Walk::Stop;
+		}
+	}
+	Walk::Continue
+}
+
+// endregion: --- walk_ast
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parse_expression;
+
+	/// Records hook calls as short strings
+	#[derive(Default)]
+	struct Log(Vec<String>);
+
+	impl Visitor for Log {
+		fn visit_attribute(&mut self, object: &str, field: &str) {
+			self.0.push(format!("attr {}.{}", object, field));
+		}
+
+		fn visit_number(&mut self, value: u64) {
+			self.0.push(format!("num {}", value));
+		}
+
+		fn enter_comparison(&mut self, _left: &AstNode, op: Comparator, _right: &AstNode) -> Walk {
+			self.0.push(format!("enter {:?}", op));
+			Walk::Continue
+		}
+
+		fn exit_comparison(&mut self, _left: &AstNode, op: Comparator, _right: &AstNode) {
+			self.0.push(format!("exit {:?}", op));
+		}
+
+		fn enter_function_call(&mut self, _namespace: Option<&str>, name: &str, _args: &[AstNode]) -> Walk {
+			self.0.push(format!("call {}", name));
+			Walk::SkipChildren
+		}
+	}
+
+	#[test]
+	fn test_walk_order_and_skip() {
+		let ast = parse_expression("a.b == 1 OR core.len(c.d) > 2").expect("parse failed");
+		let mut log = Log::default();
+		assert_eq!(walk_ast(&ast, &mut log), Walk::Continue);
+
+		assert_eq!(
+			log.0,
+			vec!["enter Eq", "attr a.b", "num 1", "exit Eq", "enter Gt", "call len", "num 2", "exit Gt"]
+		);
+	}
+
+	#[test]
+	fn test_walk_stop() {
+		struct FirstAttribute(Option<String>);
+
+		impl Visitor for FirstAttribute {
+			fn enter_node(&mut self, node: &AstNode) -> Walk {
+				match node {
+					AstNode::Attribute { object, field } => {
+						self.0 = Some(format!("{}.{}", object, field));
+						Walk::Stop
+					}
+					_ => Walk::Continue,
+				}
+			}
+		}
+
+		let ast = parse_expression(r#"x.y == 1 AND [{"k": p.q}] CONTAINS 1"#).expect("parse failed");
+		let mut first = FirstAttribute(None);
+		assert_eq!(walk_ast(&ast, &mut first), Walk::Stop);
+		assert_eq!(first.0.as_deref(), Some("x.y"));
+	}
+}
+
+// endregion: --- Tests
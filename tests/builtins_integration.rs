@@ -2,6 +2,7 @@
 //!
 //! These tests demonstrate using built-in functions in HEL expressions.
 
+use hel::testing::Facts;
 use hel::{evaluate_with_context, BuiltinsRegistry, CoreBuiltinsProvider, BuiltinsProvider, HelResolver, Value};
 use std::collections::BTreeMap;
 use std::sync::Arc;
@@ -45,6 +46,70 @@ fn test_core_contains_function_call() {
 	assert!(result, "core.contains should not find 'd' in list");
 }
 
+#[test]
+fn test_core_set_function_calls() {
+	let resolver = EmptyResolver;
+	let mut registry = BuiltinsRegistry::new();
+	registry.register(&CoreBuiltinsProvider).expect("registration failed");
+
+	let holds = |condition: &str| evaluate_with_context(condition, &resolver, &registry).expect("evaluation failed");
+
+	// required ⊆ granted
+	assert!(holds(
+		r#"core.is_subset(["READ_SMS"], ["INTERNET", "READ_SMS"]) == true"#
+	));
+	assert!(holds(
+		r#"core.is_subset(["READ_SMS", "SEND_SMS"], ["READ_SMS"]) == false"#
+	));
+
+	// Set results are lists, so CONTAINS and core.len apply to them
+	assert!(holds(r#"core.union(["a", "b"], ["b", "c"]) CONTAINS "c""#));
+	assert!(holds(r#"core.len(core.union(["a", "b"], ["b", "c"])) == 3"#));
+	assert!(holds(r#"core.intersect(["a", "b"], ["b", "c"]) CONTAINS "b""#));
+	assert!(holds(r#"core.len(core.difference(["a", "b"], ["b", "c"])) == 1"#));
+}
+
+#[test]
+fn test_core_coalesce_and_default_function_calls() {
+	let facts = Facts::new().str("app.signer", "acme").build();
+	let mut registry = BuiltinsRegistry::new();
+	registry.register(&CoreBuiltinsProvider).expect("registration failed");
+
+	let holds = |condition: &str| evaluate_with_context(condition, &facts, &registry).expect("evaluation failed");
+
+	// Missing facts resolve to null, so the fallback applies
+	assert!(holds(r#"core.default(app.min_sdk, 21) < 23"#));
+	assert!(holds(r#"core.coalesce(app.publisher, app.signer, "unknown") == "acme""#));
+	assert!(holds(r#"coalesce(app.publisher, app.vendor, "unknown") == "unknown""#));
+	assert!(holds(r#"default(app.signer, "unknown") == "acme""#));
+}
+
+#[test]
+fn test_core_statistics_function_calls() {
+	let facts = Facts::new().list("service.latencies", [120.0, 80.0, 95.0, 610.0, 101.0]).build();
+	let mut registry = BuiltinsRegistry::new();
+	registry.register(&CoreBuiltinsProvider).expect("registration failed");
+
+	let holds = |condition: &str| evaluate_with_context(condition, &facts, &registry).expect("evaluation failed");
+
+	assert!(holds("core.percentile(service.latencies, 95) > 500"));
+	assert!(holds("core.median(service.latencies) == 101"));
+	assert!(holds("core.stddev(service.latencies) > 100"));
+}
+
+#[test]
+fn test_core_string_distance_function_calls() {
+	let facts = Facts::new().str("url.domain", "paypa1.com").build();
+	let mut registry = BuiltinsRegistry::new();
+	registry.register(&CoreBuiltinsProvider).expect("registration failed");
+
+	let holds = |condition: &str| evaluate_with_context(condition, &facts, &registry).expect("evaluation failed");
+
+	// Lookalike of a brand domain, but not the brand itself
+	assert!(holds(r#"core.levenshtein(url.domain, "paypal.com") == 1"#));
+	assert!(holds(r#"core.similarity(url.domain, "paypal.com") > 0.9 AND url.domain != "paypal.com""#));
+}
+
 #[test]
 fn test_core_upper_lower_function_calls() {
 	let resolver = EmptyResolver;
@@ -126,6 +191,15 @@ fn test_custom_domain_builtin() {
 	let condition = r#"security.is_dangerous("EXE") == true"#;
 	let result = evaluate_with_context(condition, &resolver, &registry).expect("evaluation failed");
 	assert!(result, "EXE format should be marked as dangerous");
+
+	// Unqualified calls resolve to `core` unless the search order says otherwise
+	let condition = r#"is_dangerous("EXE") AND len(["a"]) == 1"#;
+	let err = evaluate_with_context(condition, &resolver, &registry).unwrap_err();
+	assert!(err.to_string().contains("core.is_dangerous"), "{}", err);
+
+	registry.set_search_order(&["security", "core"]);
+	let result = evaluate_with_context(condition, &resolver, &registry).expect("evaluation failed");
+	assert!(result, "unqualified calls should search security, then core");
 }
 
 #[test]
@@ -2,28 +2,28 @@
 //!
 //! These tests demonstrate end-to-end script evaluation workflows.
 
-use hel::{evaluate_script, parse_script, FactsEvalContext, Value};
+use hel::testing::Facts;
+use hel::{
+    evaluate_parsed_script, evaluate_script, evaluate_script_with_context,
+    evaluate_script_with_resolver, lint_script, parse_script, parse_script_with_recovery,
+    validate_script, BuiltinsRegistry, CoreBuiltinsProvider, Diagnostics, FactsEvalContext,
+    HelResolver, Value, WarningKind,
+};
 
 #[test]
 fn test_android_malware_detection_script() {
     // Simulate Android app analysis
-    let mut ctx = FactsEvalContext::new();
-
-    // Binary characteristics
-    ctx.add_fact("binary.arch", Value::String("arm".into()));
-    ctx.add_fact("binary.entropy", Value::Number(8.2));
-    ctx.add_fact("strings.count", Value::Number(5.0));
-
-    // Manifest permissions
-    ctx.add_fact(
-        "manifest.permissions",
-        Value::List(vec![
-            Value::String("READ_SMS".into()),
-            Value::String("SEND_SMS".into()),
-            Value::String("INTERNET".into()),
-            Value::String("READ_CONTACTS".into()),
-        ]),
-    );
+    let ctx = Facts::new()
+        // Binary characteristics
+        .str("binary.arch", "arm")
+        .num("binary.entropy", 8.2)
+        .num("strings.count", 5.0)
+        // Manifest permissions
+        .list(
+            "manifest.permissions",
+            ["READ_SMS", "SEND_SMS", "INTERNET", "READ_CONTACTS"],
+        )
+        .build();
 
     let script = r#"
         # Check for suspicious SMS permissions
@@ -46,14 +46,14 @@ fn test_android_malware_detection_script() {
 
 #[test]
 fn test_binary_security_analysis_script() {
-    let mut ctx = FactsEvalContext::new();
-
-    ctx.add_fact("binary.format", Value::String("ELF".into()));
-    ctx.add_fact("binary.arch", Value::String("x86_64".into()));
-    ctx.add_fact("security.nx", Value::Bool(true));
-    ctx.add_fact("security.pie", Value::Bool(true));
-    ctx.add_fact("security.relro", Value::Bool(true));
-    ctx.add_fact("security.stack_canary", Value::Bool(true));
+    let ctx = Facts::new()
+        .str("binary.format", "ELF")
+        .str("binary.arch", "x86_64")
+        .bool("security.nx", true)
+        .bool("security.pie", true)
+        .bool("security.relro", true)
+        .bool("security.stack_canary", true)
+        .build();
 
     let script = r#"
         # Modern security features
@@ -75,19 +75,14 @@ fn test_binary_security_analysis_script() {
 
 #[test]
 fn test_network_behavior_analysis() {
-    let mut ctx = FactsEvalContext::new();
-
-    ctx.add_fact(
-        "network.domains",
-        Value::List(vec![
-            Value::String("api.example.com".into()),
-            Value::String("malicious.cc".into()),
-            Value::String("c2server.xyz".into()),
-        ]),
-    );
-
-    ctx.add_fact("network.port_count", Value::Number(15.0));
-    ctx.add_fact("network.unique_ips", Value::Number(23.0));
+    let ctx = Facts::new()
+        .list(
+            "network.domains",
+            ["api.example.com", "malicious.cc", "c2server.xyz"],
+        )
+        .num("network.port_count", 15.0)
+        .num("network.unique_ips", 23.0)
+        .build();
 
     let script = r#"
         # Check for C2 infrastructure indicators
@@ -123,19 +118,13 @@ fn test_script_validation_catches_errors() {
 
 #[test]
 fn test_complex_conditional_logic() {
-    let mut ctx = FactsEvalContext::new();
-
-    ctx.add_fact("file.type", Value::String("PE".into()));
-    ctx.add_fact("file.size", Value::Number(1024000.0));
-    ctx.add_fact(
-        "signatures.matched",
-        Value::List(vec![
-            Value::String("packed".into()),
-            Value::String("encrypted".into()),
-        ]),
-    );
-    ctx.add_fact("behavior.creates_files", Value::Bool(true));
-    ctx.add_fact("behavior.modifies_registry", Value::Bool(true));
+    let ctx = Facts::new()
+        .str("file.type", "PE")
+        .num("file.size", 1024000.0)
+        .list("signatures.matched", ["packed", "encrypted"])
+        .bool("behavior.creates_files", true)
+        .bool("behavior.modifies_registry", true)
+        .build();
 
     let script = r#"
         # Packer detection
@@ -164,10 +153,10 @@ fn test_complex_conditional_logic() {
 
 #[test]
 fn test_chained_let_bindings() {
-    let mut ctx = FactsEvalContext::new();
-
-    ctx.add_fact("data.value", Value::Number(100.0));
-    ctx.add_fact("data.threshold", Value::Number(50.0));
+    let ctx = Facts::new()
+        .num("data.value", 100.0)
+        .num("data.threshold", 50.0)
+        .build();
 
     let script = r#"
         let exceeds_threshold = data.value > data.threshold
@@ -182,10 +171,10 @@ fn test_chained_let_bindings() {
 
 #[test]
 fn test_empty_list_handling() {
-    let mut ctx = FactsEvalContext::new();
-
-    ctx.add_fact("data.items", Value::List(vec![]));
-    ctx.add_fact("data.has_data", Value::Bool(false));
+    let ctx = Facts::new()
+        .value("data.items", Value::List(vec![]))
+        .bool("data.has_data", false)
+        .build();
 
     let script = r#"
         let list_is_empty = data.has_data == false
@@ -198,10 +187,10 @@ fn test_empty_list_handling() {
 
 #[test]
 fn test_numeric_comparisons_in_bindings() {
-    let mut ctx = FactsEvalContext::new();
-
-    ctx.add_fact("score.risk", Value::Number(7.8));
-    ctx.add_fact("score.confidence", Value::Number(0.92));
+    let ctx = Facts::new()
+        .num("score.risk", 7.8)
+        .num("score.confidence", 0.92)
+        .build();
 
     let script = r#"
         let high_risk = score.risk > 7.0
@@ -243,3 +232,273 @@ fn test_script_parsing_validation() {
     "#;
     assert!(parse_script(no_final).is_err());
 }
+
+#[test]
+fn test_script_reports_all_errors() {
+    let script = "let a = x.y ==\nlet b = x.z > 1\nlet c = (x.w\n  AND b\nlet d = true\nd AND ==";
+    let errors = parse_script_with_recovery(script).expect_err("should fail");
+    let lines: Vec<_> = errors.iter().map(|e| e.line).collect();
+    assert_eq!(lines, vec![Some(1), Some(4), Some(6)], "{:?}", errors);
+    assert!(errors.iter().all(|e| e.column.is_some()));
+
+    // parse_script reports the first of them
+    assert_eq!(parse_script(script).unwrap_err().line, Some(1));
+    assert_eq!(validate_script(script).unwrap_err().len(), 3);
+
+    // Missing final expression is reported alongside syntax errors
+    let errors = validate_script("let a = x.y ==\nlet b = true").unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[1].message.contains("final boolean expression"));
+
+    assert!(validate_script("let a = x.y == 1\na").is_ok());
+}
+
+#[test]
+fn test_script_constants() {
+    let ctx = Facts::new()
+        .num("binary.entropy", 7.2)
+        .str("binary.section", ".upx0")
+        .build();
+
+    let script = r#"
+        const MAX_ENTROPY = 7.5
+        const PACKER_SECTIONS = [".upx0", ".aspack"]
+        const MIN_ENTROPY = 6.5
+        const DEFAULT_MAX = MAX_ENTROPY
+        let packed = binary.section IN PACKER_SECTIONS
+        packed AND binary.entropy > MIN_ENTROPY AND binary.entropy < MAX_ENTROPY
+    "#;
+    let mut parsed = parse_script(script).expect("parse failed");
+    let names: Vec<&str> = parsed.constants.iter().map(|(n, _)| n.as_ref()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "MAX_ENTROPY",
+            "PACKER_SECTIONS",
+            "MIN_ENTROPY",
+            "DEFAULT_MAX"
+        ]
+    );
+    assert_eq!(parsed.constants[3].1, Value::Number(7.5));
+    assert!(evaluate_script(script, &ctx).unwrap());
+
+    // The host overrides a threshold at load time
+    parsed
+        .set_constant("MAX_ENTROPY", Value::Number(7.0))
+        .unwrap();
+    assert!(!evaluate_parsed_script(&parsed, &ctx).unwrap());
+    assert!(parsed.set_constant("MAX_SIZE", Value::Number(1.0)).is_err());
+
+    // Constants may not read facts, must come first and cannot be redeclared or rebound
+    let error = parse_script(
+        "const LIMIT = binary.entropy
+binary.entropy > LIMIT",
+    )
+    .unwrap_err();
+    assert!(error.message.contains("`binary.entropy`"), "{}", error);
+    assert_eq!(error.line, Some(1));
+    let error = parse_script(
+        "let a = true
+const B = 1
+a",
+    )
+    .unwrap_err();
+    assert!(error.message.contains("before any `let`"), "{}", error);
+    assert_eq!(error.line, Some(2));
+    assert!(parse_script(
+        "const A = 1
+const A = 2
+true"
+    )
+    .is_err());
+    assert!(parse_script(
+        "const A = 1
+let A = true
+A"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_unused_and_shadowed_bindings() {
+    let script = r#"
+const MAX_ENTROPY = 7.5
+let packed = binary.entropy > MAX_ENTROPY
+let is_signed = binary.signed == true
+let packed = packed AND
+    binary.size > 1024
+let debug = core.len(binary.sections)
+packed OR is_signed
+"#;
+    let diagnostics = Diagnostics::new();
+    lint_script(script, &diagnostics);
+    let warnings = diagnostics.take();
+
+    let found: Vec<(WarningKind, &str)> = warnings
+        .iter()
+        .map(|w| (w.kind, &script[w.span.clone().unwrap()]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (WarningKind::ShadowedBinding, "packed"),
+            (WarningKind::UnusedBinding, "debug"),
+        ]
+    );
+    assert_eq!(warnings[1].message, "`let debug` is never used");
+    assert_eq!(WarningKind::UnusedBinding.code(), "hel::unused_binding");
+
+    let diagnostics = Diagnostics::new();
+    lint_script("let a = true\nlet a = false\na", &diagnostics);
+    let warnings = diagnostics.take();
+    assert_eq!(warnings[0].kind, WarningKind::UnusedBinding);
+    assert_eq!(warnings[0].span, Some(4..5));
+    assert_eq!(warnings[1].kind, WarningKind::ShadowedBinding);
+    assert_eq!(warnings[1].span, Some(17..18));
+    assert_eq!(warnings.len(), 2);
+}
+
+#[test]
+fn test_script_binding_scope() {
+    let ctx = Facts::new()
+        .num("binary.entropy", 7.9)
+        .str("binary.arch", "packed")
+        .build();
+
+    // Earlier bindings are visible across multi-line definitions; a shadowing
+    // binding sees the previous value in its own expression
+    let script = r#"
+let packed = binary.entropy > 7.5
+let suspicious = packed AND
+    binary.entropy < 8
+let packed = packed AND
+    suspicious
+packed
+"#;
+    assert!(evaluate_script(script, &ctx).unwrap());
+
+    // Before its binding, `packed` is a bare string as in any expression
+    let script = "let arch_is_packed = binary.arch == packed\narch_is_packed";
+    assert!(evaluate_script(script, &ctx).unwrap());
+
+    // ...but naming a binding declared later is an error, not a string
+    let err =
+        parse_script("let early = binary.arch == packed\nlet packed = true\nearly").unwrap_err();
+    assert_eq!(err.message, "`packed` is used before its binding on line 2");
+    assert_eq!(err.line, Some(1));
+    let err = parse_script("let count = count\ncount").unwrap_err();
+    assert_eq!(err.message, "`count` is used before its binding on line 1");
+
+    // Binding names must be referencable as plain identifiers
+    let err = parse_script("let binary.packed = true\nbinary.packed").unwrap_err();
+    assert!(
+        err.message.contains("not a valid binding name"),
+        "{}",
+        err.message
+    );
+    let err = parse_script("let contains = true\ncontains").unwrap_err();
+    assert!(err.message.contains("reserved word"), "{}", err.message);
+}
+
+#[test]
+fn test_binding_names_colliding_with_facts() {
+    let ctx = Facts::new()
+        .str("binary.format", "elf")
+        .str("binary.packer", "upx")
+        .build();
+
+    // A binding named like a fact object does not affect `object.field` access,
+    // and bare words that are not bindings stay strings
+    let script = r#"
+let binary = binary.format == "elf"
+let packed = binary.packer == upx
+binary AND packed AND binary.format == elf
+"#;
+    assert!(evaluate_script(script, &ctx).unwrap());
+
+    let script = "let elf = false\nbinary.format == elf";
+    assert!(!evaluate_script(script, &ctx).unwrap());
+}
+
+#[test]
+fn test_non_boolean_bindings() {
+    let ctx = Facts::new()
+        .str("binary.arch", "x86_64")
+        .num("binary.size", 4096.0)
+        .build();
+
+    // Bindings used to be evaluated as conditions, failing with a type mismatch
+    let script = r#"
+let arch = binary.arch
+let size = binary.size
+let archs = ["x86_64", "aarch64"]
+arch IN archs AND size > 1024 AND arch == "x86_64"
+"#;
+    assert!(evaluate_script(script, &ctx).unwrap());
+
+    let script = "let arch = binary.arch\narch == \"aarch64\"";
+    assert!(!evaluate_script(script, &ctx).unwrap());
+}
+
+#[test]
+fn test_script_with_builtins() {
+    let mut registry = BuiltinsRegistry::new();
+    registry.register(&CoreBuiltinsProvider).unwrap();
+
+    let ctx = Facts::new()
+        .list("manifest.permissions", ["READ_SMS", "SEND_SMS", "INTERNET"])
+        .num("binary.entropy", 7.9)
+        .build();
+
+    let script = r#"
+const MAX_PERMISSIONS = 2
+let permission_count = core.len(manifest.permissions)
+let over_privileged = permission_count > MAX_PERMISSIONS
+over_privileged AND
+    core.len(manifest.permissions) == permission_count AND
+    binary.entropy > 7.5
+"#;
+    assert!(evaluate_script_with_context(script, &ctx, &registry).unwrap());
+
+    // Without a registry, the binding's call fails instead of evaluating
+    let err = evaluate_script(script, &ctx).unwrap_err();
+    assert!(err.message.contains("core.len"), "{}", err.message);
+}
+
+#[test]
+fn test_script_with_custom_resolver() {
+    // Host resolver backed by its own storage rather than FactsEvalContext
+    struct ManifestResolver {
+        permissions: Vec<&'static str>,
+    }
+
+    impl HelResolver for ManifestResolver {
+        fn resolve_attr(&self, object: &str, field: &str) -> Option<Value> {
+            match (object, field) {
+                ("manifest", "permissions") => Some(Value::List(
+                    self.permissions.iter().map(|p| Value::from(*p)).collect(),
+                )),
+                _ => None,
+            }
+        }
+    }
+
+    let resolver = ManifestResolver {
+        permissions: vec!["READ_SMS", "SEND_SMS"],
+    };
+    let script = r#"
+let reads_sms = manifest.permissions CONTAINS "READ_SMS"
+let sends_sms = manifest.permissions CONTAINS "SEND_SMS"
+reads_sms AND sends_sms
+"#;
+    assert!(evaluate_script_with_resolver(script, &resolver).unwrap());
+
+    let mut parsed =
+        parse_script("const PERMISSION = \"INTERNET\"\nmanifest.permissions CONTAINS PERMISSION")
+            .unwrap();
+    assert!(!evaluate_parsed_script(&parsed, &resolver).unwrap());
+    parsed
+        .set_constant("PERMISSION", Value::from("SEND_SMS"))
+        .unwrap();
+    assert!(evaluate_parsed_script(&parsed, &resolver).unwrap());
+}